@@ -56,23 +56,25 @@ pub fn run() -> Result<(), Box<dyn Error>> {
     let (application, ui_sender) = builder_app::build_app();
     let global_shut_down = Arc::new(RwLock::new(false));
 
-    let (torrent_handler_1, torrent_handler_2) =
-        torrent_handler::handle_all_torrents(ui_sender, &global_shut_down)?;
+    let torrent_handlers = torrent_handler::handle_all_torrents(ui_sender, &global_shut_down)?;
 
     let empty_vec: Vec<&str> = vec![];
     application.run_with_args(&empty_vec);
 
     set_global_shut_down(global_shut_down)?;
 
-    let torrent_result_1 = torrent_handler_1
-        .join()
-        .map_err(|_| ("[TorrentHandlerError] Join handle error".to_string()));
-    let torrent_result_2 = torrent_handler_2
-        .join()
-        .map_err(|_| ("[TorrentHandlerError] Join handle error".to_string()));
+    let torrent_results: Vec<_> = torrent_handlers
+        .into_iter()
+        .map(|torrent_handler| {
+            torrent_handler
+                .join()
+                .map_err(|_| ("[TorrentHandlerError] Join handle error".to_string()))
+        })
+        .collect();
 
-    torrent_result_1??;
-    torrent_result_2??;
+    for torrent_result in torrent_results {
+        torrent_result??;
+    }
 
     Ok(())
 }
@@ -2,16 +2,24 @@
 //! Este modulo contiene las funciones encargadas de almacenar bloques
 //! recibidos de mensajes de tipo "Piece" en medio de interacciones individuales
 //! con peers.
+//!
+//! Los bloques se escriben directamente en los archivos finales del torrent
+//! (dentro del directorio de descargas), traduciendo cada (pieza, offset) a la
+//! posicion correspondiente dentro de el/los archivo/s destino. De esta forma
+//! la verificacion SHA1 y la lectura de bloques pedidos por otros peers se
+//! realizan sobre los mismos archivos, sin copias intermedias.
+//! Las rutas de los archivos destino se resuelven y validan una unica vez por
+//! torrent (ver [TargetFiles]).
 
 use core::fmt;
 use log::info;
 use sha1::{Digest, Sha1};
-use shared::torrent_file_data::TorrentFileData;
+use shared::torrent_file_data::{TargetFilesData, TorrentFileData};
 use std::{
     error::Error,
     fs::{self, OpenOptions},
-    io::{Read, Write},
     os::unix::prelude::FileExt,
+    path::{Path, PathBuf},
 };
 
 /// Representa un error de manejo de almacenamiento de bloque.
@@ -21,6 +29,8 @@ pub enum BlockHandlerError {
     CheckingSha1Piece(String),
     IncorrectSha1Piece(String),
     GettingBlock(String),
+    SettingUpTargetFiles(String),
+    InvalidPathComponent(String),
 }
 
 impl fmt::Display for BlockHandlerError {
@@ -31,36 +41,309 @@ impl fmt::Display for BlockHandlerError {
 
 impl Error for BlockHandlerError {}
 
+/// Porcion de un archivo destino que corresponde a un rango de bytes del torrent.
+struct FileSegment<'a> {
+    file_path: &'a Path,
+    offset_in_file: u64,
+    amount_of_bytes: usize,
+}
+
+/// Archivo destino de un torrent junto con la posicion en la que comienza dentro del torrent.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct TargetFile {
+    file_path: PathBuf,
+    torrent_offset: u64,
+    file_length: u64,
+}
+
+/// Archivos destino de un torrent, con sus rutas ya resueltas y validadas, en el mismo orden
+/// en el que aparecen en el .torrent, junto con el directorio de descargas que los contiene.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TargetFiles {
+    download_path: String,
+    files: Vec<TargetFile>,
+}
+
+///
+/// Funcion que verifica que un componente de una ruta (nombre de directorio o de archivo)
+/// obtenido del .torrent no permita escribir fuera del directorio de descargas.
+/// Se rechazan los componentes vacios (rutas absolutas o separadores duplicados), `.` y `..`.
+///
+fn check_path_component(component: &str) -> Result<(), BlockHandlerError> {
+    if component.is_empty()
+        || component == "."
+        || component == ".."
+        || component.contains('\\')
+        || Path::new(component).is_absolute()
+    {
+        return Err(BlockHandlerError::InvalidPathComponent(format!(
+            "Componente de ruta invalido: {:?}",
+            component
+        )));
+    }
+    Ok(())
+}
+
+///
+/// Funcion que construye la ruta final de un archivo de un torrent Multiple File a partir
+/// del directorio del torrent y de la ruta del archivo (con el formato `/dir/.../archivo`
+/// generado al parsear el .torrent).
+///
+fn build_target_file_path(
+    torrent_dir_path: &Path,
+    file_path: &str,
+) -> Result<PathBuf, BlockHandlerError> {
+    let relative_path = file_path.strip_prefix('/').unwrap_or(file_path);
+    let mut target_file_path = torrent_dir_path.to_path_buf();
+    for component in relative_path.split('/') {
+        check_path_component(component)?;
+        target_file_path.push(component);
+    }
+    Ok(target_file_path)
+}
+
+///
+/// Funcion que devuelve la lista de archivos destino de un torrent (ruta dentro del directorio
+/// de descargas y tamaño), en el mismo orden en el que aparecen en el .torrent.
+/// Para torrents Multiple File los archivos quedan dentro de un directorio con el nombre del torrent.
+///
+fn get_target_files(
+    torrent_file_data: &TorrentFileData,
+    download_path: &str,
+) -> Result<Vec<(PathBuf, u64)>, BlockHandlerError> {
+    match &torrent_file_data.target_files_data {
+        TargetFilesData::SingleFile {
+            file_name,
+            file_length,
+        } => {
+            check_path_component(file_name)?;
+            Ok(vec![(
+                Path::new(download_path).join(file_name),
+                *file_length,
+            )])
+        }
+        TargetFilesData::MultipleFiles {
+            dir_name,
+            list_of_files_data,
+        } => {
+            check_path_component(dir_name)?;
+            let torrent_dir_path = Path::new(download_path).join(dir_name);
+            let mut target_files = Vec::with_capacity(list_of_files_data.len());
+            for file_data in list_of_files_data {
+                target_files.push((
+                    build_target_file_path(&torrent_dir_path, &file_data.path)?,
+                    file_data.file_length,
+                ));
+            }
+            Ok(target_files)
+        }
+    }
+}
+
+impl TargetFiles {
+    ///
+    /// Funcion que resuelve y valida las rutas de los archivos destino de un torrent dentro del
+    /// directorio de descargas (ver [get_target_files]).
+    ///
+    pub fn new(
+        torrent_file_data: &TorrentFileData,
+        download_path: &str,
+    ) -> Result<Self, BlockHandlerError> {
+        let mut torrent_offset = 0;
+        let files = get_target_files(torrent_file_data, download_path)?
+            .into_iter()
+            .map(|(file_path, file_length)| {
+                let target_file = TargetFile {
+                    file_path,
+                    torrent_offset,
+                    file_length,
+                };
+                torrent_offset += file_length;
+                target_file
+            })
+            .collect();
+        Ok(TargetFiles {
+            download_path: download_path.to_string(),
+            files,
+        })
+    }
+
+    pub fn get_download_path(&self) -> &str {
+        &self.download_path
+    }
+
+    /// Funcion que devuelve la ruta y el tamaño de cada archivo destino
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, u64)> {
+        self.files
+            .iter()
+            .map(|target_file| (target_file.file_path.as_path(), target_file.file_length))
+    }
+}
+
+///
+/// Funcion que crea el arbol de directorios y los archivos destino de un torrent,
+/// reservando para cada archivo su tamaño final.
+/// Los archivos que ya existen no se truncan, solo se ajusta su tamaño.
+/// Devuelve los archivos destino resueltos, para usarlos en el resto de la descarga.
+///
+pub fn set_up_target_files(
+    torrent_file_data: &TorrentFileData,
+    download_path: &str,
+) -> Result<TargetFiles, BlockHandlerError> {
+    info!("Creo los archivos destino en el directorio de descargas.");
+    fs::create_dir_all(download_path)
+        .map_err(|err| BlockHandlerError::SettingUpTargetFiles(format!("{}", err)))?;
+
+    let target_files = TargetFiles::new(torrent_file_data, download_path)?;
+    for (file_path, file_length) in target_files.iter() {
+        if let Some(parent_dir) = file_path.parent() {
+            fs::create_dir_all(parent_dir)
+                .map_err(|err| BlockHandlerError::SettingUpTargetFiles(format!("{}", err)))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(file_path)
+            .map_err(|err| BlockHandlerError::SettingUpTargetFiles(format!("{}", err)))?;
+        file.set_len(file_length)
+            .map_err(|err| BlockHandlerError::SettingUpTargetFiles(format!("{}", err)))?;
+    }
+    Ok(target_files)
+}
+
+///
+/// Funcion que traduce un rango de bytes de una pieza a las porciones de archivos destino
+/// que lo contienen (un mismo rango puede abarcar varios archivos). El primer archivo del
+/// rango se busca por biseccion.
+///
+fn get_file_segments<'a>(
+    torrent_file_data: &TorrentFileData,
+    piece_index: usize,
+    beginning_byte_index: u64,
+    amount_of_bytes: usize,
+    target_files: &'a TargetFiles,
+) -> Result<Vec<FileSegment<'a>>, BlockHandlerError> {
+    let mut torrent_offset =
+        piece_index as u64 * torrent_file_data.get_piece_length() + beginning_byte_index;
+    let mut bytes_left = amount_of_bytes;
+    if torrent_offset + amount_of_bytes as u64 > torrent_file_data.get_total_length() {
+        return Err(BlockHandlerError::GettingBlock(
+            "The requested bytes exceed the total length of the torrent.".to_string(),
+        ));
+    }
+
+    let first_file_index = target_files.files.partition_point(|target_file| {
+        target_file.torrent_offset + target_file.file_length <= torrent_offset
+    });
+    let mut file_segments = vec![];
+    for target_file in &target_files.files[first_file_index..] {
+        if bytes_left == 0 {
+            break;
+        }
+        let file_end = target_file.torrent_offset + target_file.file_length;
+        if torrent_offset < file_end {
+            let amount_in_file = bytes_left.min((file_end - torrent_offset) as usize);
+            file_segments.push(FileSegment {
+                file_path: &target_file.file_path,
+                offset_in_file: torrent_offset - target_file.torrent_offset,
+                amount_of_bytes: amount_in_file,
+            });
+            torrent_offset += amount_in_file as u64;
+            bytes_left -= amount_in_file;
+        }
+    }
+    Ok(file_segments)
+}
+
 ///
 /// Funcion que, dado un bloque descargado de una comunicacion individual con
-/// un peer, escribe en disco (en un path correspondiente a su PIEZA respectiva)
-/// dicho bloque
+/// un peer, escribe en disco dicho bloque en la posicion que le corresponde
+/// dentro de el/los archivo/s destino del torrent.
 ///
-pub fn store_block(block: &[u8], piece_index: usize, path: &str) -> Result<(), BlockHandlerError> {
-    let file_name = format!("temp/{}/piece_{}", path, piece_index);
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(file_name)
-        .map_err(|err| BlockHandlerError::StoringBlock(format!("{}", err)))?;
-    file.write_all(block)
+pub fn store_block(
+    torrent_file_data: &TorrentFileData,
+    piece_index: usize,
+    beginning_byte_index: u32,
+    block: &[u8],
+    target_files: &TargetFiles,
+) -> Result<(), BlockHandlerError> {
+    let file_segments = get_file_segments(
+        torrent_file_data,
+        piece_index,
+        beginning_byte_index.into(),
+        block.len(),
+        target_files,
+    )
+    .map_err(|err| BlockHandlerError::StoringBlock(format!("{}", err)))?;
+
+    let mut block_position = 0;
+    for segment in file_segments {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(segment.file_path)
+            .map_err(|err| BlockHandlerError::StoringBlock(format!("{}", err)))?;
+        file.write_all_at(
+            &block[block_position..block_position + segment.amount_of_bytes],
+            segment.offset_in_file,
+        )
         .map_err(|err| BlockHandlerError::StoringBlock(format!("{}", err)))?;
+        block_position += segment.amount_of_bytes;
+    }
     Ok(())
 }
 
-fn read_a_piece(piece_index: usize, path: &str) -> Result<Vec<u8>, BlockHandlerError> {
-    let file_name = format!("temp/{}/piece_{}", path, piece_index);
-    let mut file = OpenOptions::new()
-        .create(false)
-        .read(true)
-        .open(file_name)
-        .map_err(|err| BlockHandlerError::CheckingSha1Piece(format!("{}", err)))?;
+fn read_bytes(
+    torrent_file_data: &TorrentFileData,
+    piece_index: usize,
+    beginning_byte_index: u64,
+    amount_of_bytes: usize,
+    target_files: &TargetFiles,
+) -> Result<Vec<u8>, BlockHandlerError> {
+    let file_segments = get_file_segments(
+        torrent_file_data,
+        piece_index,
+        beginning_byte_index,
+        amount_of_bytes,
+        target_files,
+    )?;
+
+    let mut bytes = vec![0; amount_of_bytes];
+    let mut bytes_position = 0;
+    for segment in file_segments {
+        let file = OpenOptions::new()
+            .read(true)
+            .open(segment.file_path)
+            .map_err(|err| BlockHandlerError::GettingBlock(format!("{}", err)))?;
+        file.read_exact_at(
+            &mut bytes[bytes_position..bytes_position + segment.amount_of_bytes],
+            segment.offset_in_file,
+        )
+        .map_err(|err| BlockHandlerError::GettingBlock(format!("{}", err)))?;
+        bytes_position += segment.amount_of_bytes;
+    }
+    Ok(bytes)
+}
 
-    let mut piece = Vec::new();
-    file.read_to_end(&mut piece)
+fn read_a_piece(
+    torrent_file_data: &TorrentFileData,
+    piece_index: usize,
+    target_files: &TargetFiles,
+) -> Result<Vec<u8>, BlockHandlerError> {
+    let piece_length = torrent_file_data
+        .calculate_piece_lenght(piece_index)
         .map_err(|err| BlockHandlerError::CheckingSha1Piece(format!("{}", err)))?;
-
-    Ok(piece)
+    read_bytes(
+        torrent_file_data,
+        piece_index,
+        0,
+        piece_length as usize,
+        target_files,
+    )
+    .map_err(|err| BlockHandlerError::CheckingSha1Piece(format!("{}", err)))
 }
 
 fn get_sha1(buffer: &[u8]) -> Vec<u8> {
@@ -74,16 +357,16 @@ fn to_hex(bytes: &[u8]) -> String {
 }
 
 ///
-/// Funcion que busca en el archivo de piezas a la pieza correspondiente
+/// Funcion que lee de los archivos destino a la pieza correspondiente
 /// segun el indice dado, le calcula sha1 y verifica que sea el mismo que estaba
 /// contenido en el archivo .torrent dado.
 ///
 pub fn check_sha1_piece(
     torrent_file_data: &TorrentFileData,
     piece_index: usize,
-    path: &str,
+    target_files: &TargetFiles,
 ) -> Result<(), BlockHandlerError> {
-    let piece = read_a_piece(piece_index, path)?;
+    let piece = read_a_piece(torrent_file_data, piece_index, target_files)?;
     let piece_sha1 = get_sha1(&piece);
 
     let expected_piece_sha1 = torrent_file_data.get_piece_sha1(piece_index);
@@ -98,9 +381,7 @@ pub fn check_sha1_piece(
     );
 
     if piece_sha1 != expected_piece_sha1 {
-        fs::remove_file(format!("temp/{}/piece_{}", path, piece_index))
-            .map_err(|err| BlockHandlerError::CheckingSha1Piece(format!("{}", err)))?;
-        Err(BlockHandlerError::CheckingSha1Piece(
+        Err(BlockHandlerError::IncorrectSha1Piece(
             "The downloaded piece does not pass the sha1 verification.".to_string(),
         ))
     } else {
@@ -110,95 +391,213 @@ pub fn check_sha1_piece(
 
 ///
 /// Funcion encargada de obtener una bloque previamente descargado dado su numero de pieza, el byte de comienzo
-/// y la cantidad de bytes requeridos. Ademas de estos tres datos mencionados anteriormente, se debe suministrar la ruta en donde se
-/// encuentran guardadas las piezas temporalmente.
+/// y la cantidad de bytes requeridos. Ademas de estos tres datos mencionados anteriormente, se deben suministrar los
+/// archivos destino del torrent.
+/// El bloque pedido debe estar contenido completamente dentro de la pieza indicada.
 ///
 pub fn get_block(
+    torrent_file_data: &TorrentFileData,
     piece_index: u32,
     beginning_byte_index: u32,
     amount_of_bytes: u32,
-    path: &str,
+    target_files: &TargetFiles,
 ) -> Result<Vec<u8>, BlockHandlerError> {
-    let amount_of_bytes = amount_of_bytes
+    let piece_index = piece_index
         .try_into()
         .map_err(|err| BlockHandlerError::GettingBlock(format!("{}", err)))?;
-    let file_name = format!("temp/{}/piece_{}", path, piece_index);
-
-    let mut block = vec![0; amount_of_bytes];
-
-    let file = OpenOptions::new()
-        .read(true)
-        .open(file_name)
+    if piece_index >= torrent_file_data.get_total_amount_pieces() {
+        return Err(BlockHandlerError::GettingBlock(
+            "The requested piece does not exist.".to_string(),
+        ));
+    }
+    let piece_length = torrent_file_data
+        .calculate_piece_lenght(piece_index)
         .map_err(|err| BlockHandlerError::GettingBlock(format!("{}", err)))?;
-    file.read_exact_at(&mut block, beginning_byte_index.into())
+    if u64::from(beginning_byte_index) + u64::from(amount_of_bytes) > piece_length {
+        return Err(BlockHandlerError::GettingBlock(
+            "The requested bytes exceed the length of the piece.".to_string(),
+        ));
+    }
+    let amount_of_bytes = amount_of_bytes
+        .try_into()
         .map_err(|err| BlockHandlerError::GettingBlock(format!("{}", err)))?;
-    Ok(block)
+
+    read_bytes(
+        torrent_file_data,
+        piece_index,
+        beginning_byte_index.into(),
+        amount_of_bytes,
+        target_files,
+    )
 }
 
 #[cfg(test)]
 mod test_block_handler {
     use super::*;
-    use std::{
-        error::Error,
-        fs::{self, File},
-        io::Read,
-    };
+    use shared::torrent_file_data::FileData;
+    use std::{error::Error, fs, io::Read};
+
+    fn create_single_file_torrent(file_name: &str, total_length: u64) -> TorrentFileData {
+        TorrentFileData {
+            target_files_data: TargetFilesData::SingleFile {
+                file_name: file_name.to_string(),
+                file_length: total_length,
+            },
+            sha1_pieces: vec![], // No necesario para el test
+            url_tracker_main: "tracker_main.com".to_string(),
+            url_tracker_list: vec![],
+            sha1_info_hash: vec![],
+            piece_length: 48,
+            total_amount_of_pieces: total_length.div_ceil(48) as usize,
+            total_length,
+        }
+    }
 
-    mod test_get_block {
+    fn create_multiple_files_torrent(list_of_files_data: Vec<FileData>) -> TorrentFileData {
+        let total_length: u64 = list_of_files_data
+            .iter()
+            .map(|file_data| file_data.file_length)
+            .sum();
+        TorrentFileData {
+            target_files_data: TargetFilesData::MultipleFiles {
+                dir_name: "multiple_files".to_string(),
+                list_of_files_data,
+            },
+            sha1_pieces: vec![], // No necesario para el test
+            url_tracker_main: "tracker_main.com".to_string(),
+            url_tracker_list: vec![],
+            sha1_info_hash: vec![],
+            piece_length: 4,
+            total_amount_of_pieces: total_length.div_ceil(4) as usize,
+            total_length,
+        }
+    }
+
+    fn read_file(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut file_bytes = vec![];
+        fs::File::open(path)?.read_to_end(&mut file_bytes)?;
+        Ok(file_bytes)
+    }
+
+    mod test_set_up_target_files {
         use super::*;
 
         #[test]
-        fn first_block_can_be_gotten_ok() -> Result<(), Box<dyn Error>> {
-            let expected_block = [1; 16].to_vec();
-            let piece_index = 0;
-            let beginning_byte_index = 0;
-            let amount_of_bytes = 16;
+        fn single_file_is_preallocated_ok() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/set_up_1";
+            let torrent_file_data = create_single_file_torrent("file.test", 100);
 
-            let test_path = "test_block_handler/get_block_1".to_string();
-            fs::create_dir(format!("temp/{}", test_path))?;
+            set_up_target_files(&torrent_file_data, test_path)?;
 
-            store_block(&expected_block, piece_index, &test_path)?;
+            assert_eq!(100, fs::metadata(format!("{}/file.test", test_path))?.len());
 
-            let received_block = get_block(
-                piece_index.try_into()?,
-                beginning_byte_index,
-                amount_of_bytes,
-                &test_path,
-            )?;
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
 
-            assert_eq!(expected_block, received_block);
+        #[test]
+        fn multiple_files_tree_is_created_ok() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/set_up_2";
+            let torrent_file_data = create_multiple_files_torrent(vec![
+                FileData {
+                    path: "/first.txt".to_string(),
+                    file_length: 3,
+                },
+                FileData {
+                    path: "/sub_dir/nested/second.txt".to_string(),
+                    file_length: 7,
+                },
+            ]);
+
+            set_up_target_files(&torrent_file_data, test_path)?;
+
+            let torrent_dir = format!("{}/multiple_files", test_path);
+            assert_eq!(3, fs::metadata(format!("{}/first.txt", torrent_dir))?.len());
+            assert_eq!(
+                7,
+                fs::metadata(format!("{}/sub_dir/nested/second.txt", torrent_dir))?.len()
+            );
+
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
 
-            fs::remove_dir_all(format!("temp/{}", test_path))?;
+        #[test]
+        fn path_with_parent_dir_components_is_rejected_error() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/set_up_3";
+            let torrent_file_data = create_multiple_files_torrent(vec![
+                FileData {
+                    path: "/inside.txt".to_string(),
+                    file_length: 5,
+                },
+                FileData {
+                    path: "/../outside.txt".to_string(),
+                    file_length: 5,
+                },
+            ]);
+
+            assert!(matches!(
+                set_up_target_files(&torrent_file_data, test_path),
+                Err(BlockHandlerError::InvalidPathComponent(_))
+            ));
+            assert!(!Path::new("temp/test_block_handler/set_up_3/outside.txt").exists());
+
+            let _ = fs::remove_dir_all(test_path);
+            Ok(())
+        }
 
+        #[test]
+        fn absolute_path_component_is_rejected_error() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/set_up_4";
+            let torrent_file_data = create_multiple_files_torrent(vec![FileData {
+                path: "//tmp/absolute.txt".to_string(),
+                file_length: 10,
+            }]);
+
+            assert!(matches!(
+                set_up_target_files(&torrent_file_data, test_path),
+                Err(BlockHandlerError::InvalidPathComponent(_))
+            ));
+
+            let _ = fs::remove_dir_all(test_path);
             Ok(())
         }
+    }
+
+    mod test_get_block {
+        use super::*;
 
         #[test]
-        fn last_block_can_be_gotten_ok() -> Result<(), Box<dyn Error>> {
-            let block_0 = [0; 16].to_vec();
-            let block_1 = [1; 16].to_vec();
-            let block_2 = [2; 16].to_vec();
+        fn first_block_can_be_gotten_ok() -> Result<(), Box<dyn Error>> {
+            let expected_block = [1; 16].to_vec();
             let piece_index = 0;
-            let beginning_byte_index = 32;
+            let beginning_byte_index = 0;
             let amount_of_bytes = 16;
 
-            let test_path = "test_block_handler/get_block_2".to_string();
-            fs::create_dir(format!("temp/{}", test_path))?;
+            let test_path = "temp/test_block_handler/get_block_1";
+            let torrent_file_data = create_single_file_torrent("file.test", 48);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
 
-            store_block(&block_0, piece_index, &test_path)?;
-            store_block(&block_1, piece_index, &test_path)?;
-            store_block(&block_2, piece_index, &test_path)?;
+            store_block(
+                &torrent_file_data,
+                piece_index,
+                beginning_byte_index,
+                &expected_block,
+                &target_files,
+            )?;
 
             let received_block = get_block(
+                &torrent_file_data,
                 piece_index.try_into()?,
                 beginning_byte_index,
                 amount_of_bytes,
-                &test_path,
+                &target_files,
             )?;
 
-            assert_eq!(block_2, received_block);
+            assert_eq!(expected_block, received_block);
+
+            fs::remove_dir_all(test_path)?;
 
-            fs::remove_dir_all(format!("temp/{}", test_path))?;
             Ok(())
         }
 
@@ -207,47 +606,111 @@ mod test_block_handler {
             let block_0 = [0; 16].to_vec();
             let block_1 = [1; 16].to_vec();
             let block_2 = [2; 16].to_vec();
-            let piece_index = 0;
-            let beginning_byte_index = 0;
+            let piece_index = 1;
             let amount_of_bytes = 16;
 
-            let test_path = "test_block_handler/get_block_3".to_string();
-            fs::create_dir(format!("temp/{}", test_path))?;
+            let test_path = "temp/test_block_handler/get_block_2";
+            let torrent_file_data = create_single_file_torrent("file.test", 96);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
 
-            store_block(&block_0, piece_index, &test_path)?;
-            store_block(&block_1, piece_index, &test_path)?;
-            store_block(&block_2, piece_index, &test_path)?;
+            // Se guardan fuera de orden
+            store_block(&torrent_file_data, piece_index, 32, &block_2, &target_files)?;
+            store_block(&torrent_file_data, piece_index, 0, &block_0, &target_files)?;
+            store_block(&torrent_file_data, piece_index, 16, &block_1, &target_files)?;
 
             let received_block_0 = get_block(
+                &torrent_file_data,
                 piece_index.try_into()?,
-                beginning_byte_index,
+                0,
                 amount_of_bytes,
-                &test_path,
+                &target_files,
             )?;
-
-            let beginning_byte_index = beginning_byte_index + 16;
-
             let received_block_1 = get_block(
+                &torrent_file_data,
                 piece_index.try_into()?,
-                beginning_byte_index,
+                16,
                 amount_of_bytes,
-                &test_path,
+                &target_files,
             )?;
-
-            let beginning_byte_index = beginning_byte_index + 16;
-
             let received_block_2 = get_block(
+                &torrent_file_data,
                 piece_index.try_into()?,
-                beginning_byte_index,
+                32,
                 amount_of_bytes,
-                &test_path,
+                &target_files,
             )?;
 
             assert_eq!(block_0, received_block_0);
             assert_eq!(block_1, received_block_1);
             assert_eq!(block_2, received_block_2);
 
-            fs::remove_dir_all(format!("temp/{}", test_path))?;
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn block_beyond_the_total_length_cannot_be_gotten_error() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/get_block_3";
+            let torrent_file_data = create_single_file_torrent("file.test", 48);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
+
+            assert!(get_block(&torrent_file_data, 0, 40, 16, &target_files).is_err());
+
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn block_beyond_the_end_of_its_piece_cannot_be_gotten_error() -> Result<(), Box<dyn Error>>
+        {
+            let test_path = "temp/test_block_handler/get_block_5";
+            let torrent_file_data = create_single_file_torrent("file.test", 96);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
+
+            assert_eq!(
+                Err(BlockHandlerError::GettingBlock(
+                    "The requested bytes exceed the length of the piece.".to_string()
+                )),
+                get_block(&torrent_file_data, 0, 40, 16, &target_files)
+            );
+            assert!(get_block(&torrent_file_data, 2, 0, 16, &target_files).is_err());
+
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn block_spanning_multiple_files_can_be_gotten_ok() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/get_block_4";
+            let torrent_file_data = create_multiple_files_torrent(vec![
+                FileData {
+                    path: "/first.txt".to_string(),
+                    file_length: 3,
+                },
+                FileData {
+                    path: "/second.txt".to_string(),
+                    file_length: 2,
+                },
+                FileData {
+                    path: "/third.txt".to_string(),
+                    file_length: 3,
+                },
+            ]);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
+
+            store_block(&torrent_file_data, 0, 0, &[0, 1, 2, 3], &target_files)?;
+            store_block(&torrent_file_data, 1, 0, &[4, 5, 6, 7], &target_files)?;
+
+            assert_eq!(
+                vec![2u8, 3],
+                get_block(&torrent_file_data, 0, 2, 2, &target_files)?
+            );
+            assert_eq!(
+                vec![4u8, 5, 6, 7],
+                get_block(&torrent_file_data, 1, 0, 4, &target_files)?
+            );
+
+            fs::remove_dir_all(test_path)?;
             Ok(())
         }
     }
@@ -260,48 +723,134 @@ mod test_block_handler {
             let block = [1; 16].to_vec();
             let piece_index = 0;
 
-            let test_path = "test_block_handler/store_block_1".to_string();
-            fs::create_dir(format!("temp/{}", test_path))?;
-
-            store_block(&block, piece_index, &test_path)?;
-
-            let mut file = File::open(format!("temp/{}/piece_{}", test_path, piece_index))?;
-            let mut file_block: Vec<u8> = Vec::new();
+            let test_path = "temp/test_block_handler/store_block_1";
+            let torrent_file_data = create_single_file_torrent("file.test", 16);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
 
-            file.read_to_end(&mut file_block)?;
+            store_block(&torrent_file_data, piece_index, 0, &block, &target_files)?;
 
-            assert_eq!(block, file_block);
+            assert_eq!(block, read_file(&format!("{}/file.test", test_path))?);
 
-            fs::remove_dir_all(format!("temp/{}", test_path))?;
+            fs::remove_dir_all(test_path)?;
             Ok(())
         }
 
         #[test]
-        fn multiple_blocks_can_be_stored() -> Result<(), Box<dyn Error>> {
+        fn blocks_are_stored_at_their_position_in_the_target_file() -> Result<(), Box<dyn Error>> {
             let block_0 = [0; 16].to_vec();
             let mut block_1 = [1; 16].to_vec();
             let mut block_2 = [2; 16].to_vec();
-            let piece_index = 0;
 
-            let test_path = "test_block_handler/store_block_2".to_string();
-            fs::create_dir(format!("temp/{}", test_path))?;
+            let test_path = "temp/test_block_handler/store_block_2";
+            let torrent_file_data = create_single_file_torrent("file.test", 96);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
+
+            store_block(&torrent_file_data, 1, 16, &block_1, &target_files)?;
+            store_block(&torrent_file_data, 1, 0, &block_0, &target_files)?;
+            store_block(&torrent_file_data, 1, 32, &block_2, &target_files)?;
+
+            let mut expected_file = vec![0; 48];
+            expected_file.append(&mut block_0.clone());
+            expected_file.append(&mut block_1);
+            expected_file.append(&mut block_2);
+
+            assert_eq!(
+                expected_file,
+                read_file(&format!("{}/file.test", test_path))?
+            );
+
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn pieces_are_split_across_multiple_files() -> Result<(), Box<dyn Error>> {
+            // La pieza 0 abarca los dos primeros archivos y la pieza 1 el segundo y el cuarto.
+            let test_path = "temp/test_block_handler/store_block_3";
+            let torrent_file_data = create_multiple_files_torrent(vec![
+                FileData {
+                    path: "/first.txt".to_string(),
+                    file_length: 3,
+                },
+                FileData {
+                    path: "/sub_dir/second.txt".to_string(),
+                    file_length: 2,
+                },
+                FileData {
+                    path: "/sub_dir/empty.txt".to_string(),
+                    file_length: 0,
+                },
+                FileData {
+                    path: "/sub_dir/nested/third.txt".to_string(),
+                    file_length: 5,
+                },
+            ]);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
+
+            store_block(&torrent_file_data, 0, 0, &[0, 1, 2, 3], &target_files)?;
+            store_block(&torrent_file_data, 1, 0, &[4, 5, 6, 7], &target_files)?;
+            store_block(&torrent_file_data, 2, 0, &[8, 9], &target_files)?;
+
+            let torrent_dir = format!("{}/multiple_files", test_path);
+            assert_eq!(
+                vec![0u8, 1, 2],
+                read_file(&format!("{}/first.txt", torrent_dir))?
+            );
+            assert_eq!(
+                vec![3u8, 4],
+                read_file(&format!("{}/sub_dir/second.txt", torrent_dir))?
+            );
+            assert_eq!(
+                Vec::<u8>::new(),
+                read_file(&format!("{}/sub_dir/empty.txt", torrent_dir))?
+            );
+            assert_eq!(
+                vec![5u8, 6, 7, 8, 9],
+                read_file(&format!("{}/sub_dir/nested/third.txt", torrent_dir))?
+            );
+
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
+    }
+
+    mod test_check_sha1_piece {
+        use super::*;
+
+        #[test]
+        fn piece_is_verified_in_place_ok() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/check_sha1_1";
+            let mut torrent_file_data = create_single_file_torrent("file.test", 96);
+            let piece = [7; 48].to_vec();
+            torrent_file_data.sha1_pieces = [get_sha1(&[0; 48]), get_sha1(&piece)].concat();
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
 
-            store_block(&block_0, piece_index, &test_path)?;
-            store_block(&block_1, piece_index, &test_path)?;
-            store_block(&block_2, piece_index, &test_path)?;
+            store_block(&torrent_file_data, 1, 0, &piece, &target_files)?;
 
-            let mut expected_block = block_0;
-            expected_block.append(&mut block_1);
-            expected_block.append(&mut block_2);
+            assert_eq!(
+                Ok(()),
+                check_sha1_piece(&torrent_file_data, 1, &target_files)
+            );
 
-            let mut file = File::open(format!("temp/{}/piece_{}", test_path, piece_index))?;
-            let mut file_block: Vec<u8> = Vec::new();
+            fs::remove_dir_all(test_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn corrupted_piece_does_not_pass_the_verification_error() -> Result<(), Box<dyn Error>> {
+            let test_path = "temp/test_block_handler/check_sha1_2";
+            let mut torrent_file_data = create_single_file_torrent("file.test", 48);
+            torrent_file_data.sha1_pieces = get_sha1(&[7; 48]);
+            let target_files = set_up_target_files(&torrent_file_data, test_path)?;
 
-            file.read_to_end(&mut file_block)?;
+            store_block(&torrent_file_data, 0, 0, &[6; 48], &target_files)?;
 
-            assert_eq!(expected_block, file_block);
+            assert!(matches!(
+                check_sha1_piece(&torrent_file_data, 0, &target_files),
+                Err(BlockHandlerError::IncorrectSha1Piece(_))
+            ));
 
-            fs::remove_dir_all(format!("temp/{}", test_path))?;
+            fs::remove_dir_all(test_path)?;
             Ok(())
         }
     }
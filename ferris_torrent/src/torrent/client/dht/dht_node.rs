@@ -0,0 +1,959 @@
+//! # Modulo del nodo DHT
+//! Este modulo contiene el nodo local de la DHT de BitTorrent (BEP 5), compartido por todos los torrents.
+//! El nodo atiende por UDP las queries de otros nodos (ping, find_node, get_peers y announce_peer) y
+//! realiza busquedas iterativas sobre la red para encontrar peers de un torrent sin depender de su tracker,
+//! anunciandose ademas en los nodos mas cercanos a su info hash.
+//! Las respuestas a las queries propias llegan al thread que escucha el socket, el cual se las
+//! entrega al thread que realizo cada query segun su transaction id.
+//!
+
+use super::routing_table::{self, RoutingTable, K};
+use crate::torrent::data::tracker_response_data::PeerDataFromTrackerResponse;
+use log::{debug, info, trace};
+use rand::Rng;
+use sha1::{Digest, Sha1};
+use shared::{
+    parsers::krpc::{
+        KrpcBody, KrpcMessage, KrpcQuery, KrpcResponse, NodeId, NodeInfo, ERROR_PROTOCOL,
+    },
+    torrent_file_data::TorrentFileData,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs, UdpSocket},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, MutexGuard, RwLock, RwLockWriteGuard,
+    },
+    time::{Duration, Instant},
+};
+
+/// Tiempo maximo de espera de las respuestas a un grupo de queries enviadas a la vez
+pub const MILLIS_QUERY_TIMEOUT: u64 = 2000;
+/// Cantidad de queries enviadas a la vez en cada ronda de una busqueda iterativa
+pub const ALPHA: usize = 3;
+/// Tiempo entre dos busquedas de peers de un mismo torrent
+pub const SECS_LOOKUP_INTERVAL: u64 = 15 * 60;
+
+const STATE_FILE_NAME: &str = "dht_state";
+const MAX_DATAGRAM_LEN: usize = 65507;
+const MILLIS_SOCKET_TIMEOUT: u64 = 500;
+const MAX_LOOKUP_ROUNDS: usize = 10;
+const MAX_STORED_PEERS: usize = 100;
+const MAX_STORED_INFO_HASHES: usize = 1000;
+const MAX_VALUES_PER_RESPONSE: usize = 50;
+const SECS_STORED_PEER_LIFETIME: u64 = 30 * 60;
+const TOKEN_SECRET_LEN: usize = 16;
+const SECS_TOKEN_SECRET_LIFETIME: u64 = 5 * 60;
+const SECS_MAINTENANCE_INTERVAL: u64 = 60;
+
+///Enumerado que representa los tipos de error que pueden surgir en el manejo del nodo DHT
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum DhtError {
+    Binding(String),
+    Sending(String),
+    Receiving(String),
+    Timeout,
+    ErrorResponse(String),
+    LockingState(String),
+    ReadingShutDownField(String),
+}
+
+impl fmt::Display for DhtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for DhtError {}
+
+type ResultDht<T> = Result<T, DhtError>;
+type PendingQueries = HashMap<Vec<u8>, (SocketAddrV4, Sender<KrpcMessage>)>;
+
+/// Query enviada que todavia no fue respondida
+struct PendingQuery {
+    transaction_id: Vec<u8>,
+    address: SocketAddrV4,
+    receiver: Receiver<KrpcMessage>,
+}
+
+/// Resultado de una busqueda iterativa: los peers obtenidos y los nodos que respondieron
+/// (junto con el token que entregaron, si lo hicieron), ordenados por cercania al objetivo.
+struct LookupResult {
+    peers: Vec<SocketAddrV4>,
+    responded_nodes: Vec<(NodeInfo, Option<Vec<u8>>)>,
+}
+
+/// Secretos con los que se generan los tokens de get_peers. El secreto se rota periodicamente,
+/// y los tokens generados con el secreto anterior se siguen aceptando hasta la proxima rotacion.
+#[derive(Debug)]
+struct TokenSecrets {
+    current: [u8; TOKEN_SECRET_LEN],
+    previous: [u8; TOKEN_SECRET_LEN],
+    rotated_at: Instant,
+}
+
+/// Nodo local de la DHT
+#[derive(Debug)]
+pub struct DhtNode {
+    own_id: NodeId,
+    socket: UdpSocket,
+    bootstrap_nodes: Vec<String>,
+    routing_table: RwLock<RoutingTable>,
+    stored_peers: RwLock<HashMap<NodeId, Vec<(SocketAddrV4, Instant)>>>,
+    pending_queries: Mutex<PendingQueries>,
+    next_transaction_id: AtomicU16,
+    token_secrets: RwLock<TokenSecrets>,
+}
+
+///
+/// Funcion que devuelve la ruta del archivo donde se guarda la tabla de ruteo de la DHT,
+/// dentro del directorio de descargas.
+///
+pub fn get_state_file_path(download_path: &str) -> String {
+    format!("{}/{}", download_path, STATE_FILE_NAME)
+}
+
+///
+/// Funcion que devuelve la direccion en la que escucha el nodo DHT: la IPv4 de bind configurada
+/// o, si no la hay, todas las interfaces.
+///
+pub fn get_bind_address(bind_address: Option<IpAddr>, dht_port: u16) -> SocketAddrV4 {
+    match bind_address {
+        Some(IpAddr::V4(ip)) => SocketAddrV4::new(ip, dht_port),
+        _ => SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, dht_port),
+    }
+}
+
+///
+/// Funcion que recupera la tabla de ruteo guardada en la ejecucion anterior. Si no existe
+/// o no es valida, se comienza con una tabla vacia y un id nuevo.
+///
+pub fn load_routing_table(state_file_path: &str) -> RoutingTable {
+    match RoutingTable::load(state_file_path) {
+        Ok(routing_table) => {
+            info!(
+                "Tabla de ruteo de la DHT recuperada con {} nodos",
+                routing_table.get_amount_of_nodes()
+            );
+            routing_table
+        }
+        Err(error) => {
+            debug!(
+                "No se pudo recuperar la tabla de ruteo de la DHT: {}",
+                error
+            );
+            RoutingTable::new(routing_table::generate_node_id())
+        }
+    }
+}
+
+//Se resuelven las direcciones de los nodos de bootstrap quedandose solo con las IPv4. Los que no
+//se pueden resolver se ignoran.
+fn resolve_bootstrap_nodes(bootstrap_nodes: &[String]) -> Vec<SocketAddrV4> {
+    bootstrap_nodes
+        .iter()
+        .filter_map(|bootstrap_node| match bootstrap_node.to_socket_addrs() {
+            Ok(addresses) => Some(addresses),
+            Err(error) => {
+                debug!("No se pudo resolver el nodo {}: {}", bootstrap_node, error);
+                None
+            }
+        })
+        .flat_map(|addresses| {
+            addresses.filter_map(|address| match address {
+                SocketAddr::V4(address) => Some(address),
+                SocketAddr::V6(_) => None,
+            })
+        })
+        .collect()
+}
+
+///
+/// Funcion que busca en la DHT peers del torrent dado, anunciandonos en el puerto dado si lo hay.
+/// Los peers se devuelven en el mismo formato que los de un tracker, para sumarlos a los candidatos.
+///
+pub fn find_torrent_peers(
+    dht_node: &DhtNode,
+    torrent_file_data: &TorrentFileData,
+    announce_port: Option<u16>,
+) -> ResultDht<Vec<PeerDataFromTrackerResponse>> {
+    let info_hash: NodeId = match torrent_file_data.get_info_hash().try_into() {
+        Ok(info_hash) => info_hash,
+        Err(_) => return Ok(vec![]),
+    };
+    Ok(dht_node
+        .lookup_peers(&info_hash, announce_port)?
+        .into_iter()
+        .map(|peer_address| PeerDataFromTrackerResponse {
+            peer_id: None,
+            peer_address: SocketAddr::V4(peer_address),
+        })
+        .collect())
+}
+
+//El token que se entrega en get_peers depende de la IP del nodo que lo pide, por lo que solo
+//ese nodo puede usarlo luego en announce_peer.
+fn create_token(token_secret: &[u8; TOKEN_SECRET_LEN], ip: &Ipv4Addr) -> Vec<u8> {
+    let mut hasher = Sha1::new();
+    hasher.update(token_secret);
+    hasher.update(ip.octets());
+    hasher.finalize().to_vec()
+}
+
+//Los errores de recepcion transitorios no deben terminar el thread que escucha el socket. Por
+//ejemplo, en Windows un ConnectionReset llega por UDP ante cualquier ICMP port unreachable.
+fn is_transient_receiving_error(error_kind: ErrorKind) -> bool {
+    matches!(
+        error_kind,
+        ErrorKind::WouldBlock
+            | ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionRefused
+    )
+}
+
+fn is_shut_down_set(shut_down: &Arc<RwLock<bool>>) -> ResultDht<bool> {
+    let shut_down = shut_down
+        .read()
+        .map_err(|error| DhtError::ReadingShutDownField(format!("{:?}", error)))?;
+    Ok(*shut_down)
+}
+
+impl DhtNode {
+    ///
+    /// Funcion que crea el nodo escuchando en la direccion dada, a partir de la tabla de ruteo
+    /// dada (de la cual toma su id). Los nodos de bootstrap (en formato host:puerto) se usan cuando la
+    /// tabla tiene pocos nodos.
+    ///
+    pub fn bind(
+        address: SocketAddrV4,
+        routing_table: RoutingTable,
+        bootstrap_nodes: Vec<String>,
+    ) -> ResultDht<Self> {
+        let socket =
+            UdpSocket::bind(address).map_err(|error| DhtError::Binding(format!("{}", error)))?;
+        socket
+            .set_read_timeout(Some(Duration::from_millis(MILLIS_SOCKET_TIMEOUT)))
+            .map_err(|error| DhtError::Binding(format!("{}", error)))?;
+        Ok(DhtNode {
+            own_id: routing_table.get_own_id(),
+            socket,
+            bootstrap_nodes,
+            routing_table: RwLock::new(routing_table),
+            stored_peers: RwLock::new(HashMap::new()),
+            pending_queries: Mutex::new(HashMap::new()),
+            next_transaction_id: AtomicU16::new(rand::thread_rng().gen()),
+            token_secrets: RwLock::new(TokenSecrets {
+                current: rand::thread_rng().gen(),
+                previous: rand::thread_rng().gen(),
+                rotated_at: Instant::now(),
+            }),
+        })
+    }
+
+    ///Own id getter
+    pub fn get_own_id(&self) -> NodeId {
+        self.own_id
+    }
+
+    /// Devuelve la direccion en la que escucha el nodo
+    pub fn get_local_address(&self) -> ResultDht<SocketAddrV4> {
+        match self.socket.local_addr() {
+            Ok(SocketAddr::V4(address)) => Ok(address),
+            Ok(SocketAddr::V6(address)) => Err(DhtError::Binding(format!(
+                "Unexpected IPv6 address {}",
+                address
+            ))),
+            Err(error) => Err(DhtError::Binding(format!("{}", error))),
+        }
+    }
+
+    /// Devuelve una copia de la tabla de ruteo actual
+    pub fn get_routing_table(&self) -> ResultDht<RoutingTable> {
+        let routing_table = self
+            .routing_table
+            .read()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        Ok(routing_table.clone())
+    }
+
+    fn lock_routing_table(&self) -> ResultDht<RwLockWriteGuard<'_, RoutingTable>> {
+        self.routing_table
+            .write()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))
+    }
+
+    fn lock_pending_queries(&self) -> ResultDht<MutexGuard<'_, PendingQueries>> {
+        self.pending_queries
+            .lock()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))
+    }
+
+    ///
+    /// FUNCION PRINCIPAL
+    /// Funcion que atiende los mensajes recibidos por el socket hasta que se activa el shutdown dado:
+    /// responde las queries de otros nodos y entrega las respuestas a las queries propias.
+    /// Los mensajes invalidos se ignoran.
+    ///
+    pub fn listen(&self, shut_down: &Arc<RwLock<bool>>) -> ResultDht<()> {
+        let mut buffer = vec![0; MAX_DATAGRAM_LEN];
+        let mut last_maintenance_at = Instant::now();
+        loop {
+            if is_shut_down_set(shut_down)? {
+                return Ok(());
+            }
+            if last_maintenance_at.elapsed() >= Duration::from_secs(SECS_MAINTENANCE_INTERVAL) {
+                last_maintenance_at = Instant::now();
+                self.rotate_token_secret_if_due_at(last_maintenance_at)?;
+                self.remove_expired_peers_at(last_maintenance_at)?;
+            }
+            let (amount_read, sender) = match self.socket.recv_from(&mut buffer) {
+                Ok((amount_read, SocketAddr::V4(sender))) => (amount_read, sender),
+                Ok((_, SocketAddr::V6(_))) => continue,
+                Err(error) if is_transient_receiving_error(error.kind()) => {
+                    trace!("Error transitorio al recibir en la DHT: {}", error);
+                    continue;
+                }
+                Err(error) => return Err(DhtError::Receiving(format!("{}", error))),
+            };
+            match KrpcMessage::from_bytes(&buffer[..amount_read]) {
+                Ok(message) => self.handle_message(message, sender)?,
+                Err(error) => trace!("Mensaje KRPC invalido de {}: {}", sender, error),
+            }
+        }
+    }
+
+    fn handle_message(&self, message: KrpcMessage, sender: SocketAddrV4) -> ResultDht<()> {
+        match message.body {
+            KrpcBody::Query { id, query } => {
+                self.lock_routing_table()?.add_node(NodeInfo {
+                    id,
+                    address: sender,
+                });
+                let answer = KrpcMessage {
+                    transaction_id: message.transaction_id,
+                    body: self.answer_query(query, sender)?,
+                };
+                if let Err(error) = self.send_message(&answer, sender) {
+                    debug!("No se pudo responder al nodo {}: {}", sender, error);
+                }
+            }
+            _ => {
+                let mut pending_queries = self.lock_pending_queries()?;
+                match pending_queries.get(&message.transaction_id) {
+                    Some((address, _)) if *address == sender => {
+                        if let Some((_, response_sender)) =
+                            pending_queries.remove(&message.transaction_id)
+                        {
+                            //Si la query ya vencio nadie espera la respuesta, por lo que se descarta
+                            response_sender.send(message).ok();
+                        }
+                    }
+                    _ => trace!("Respuesta inesperada del nodo {}", sender),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn create_token(&self, ip: &Ipv4Addr) -> ResultDht<Vec<u8>> {
+        let token_secrets = self
+            .token_secrets
+            .read()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        Ok(create_token(&token_secrets.current, ip))
+    }
+
+    //Un token es valido si se genero para la IP dada con el secreto actual o con el anterior.
+    fn is_valid_token(&self, token: &[u8], ip: &Ipv4Addr) -> ResultDht<bool> {
+        let token_secrets = self
+            .token_secrets
+            .read()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        Ok(token == create_token(&token_secrets.current, ip)
+            || token == create_token(&token_secrets.previous, ip))
+    }
+
+    ///
+    /// Funcion que rota el secreto de los tokens si paso su tiempo de vida, con lo cual los
+    /// tokens generados hace mas de dos rotaciones dejan de ser validos.
+    ///
+    fn rotate_token_secret_if_due_at(&self, now: Instant) -> ResultDht<()> {
+        let mut token_secrets = self
+            .token_secrets
+            .write()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        if now.saturating_duration_since(token_secrets.rotated_at)
+            < Duration::from_secs(SECS_TOKEN_SECRET_LIFETIME)
+        {
+            return Ok(());
+        }
+        token_secrets.previous = token_secrets.current;
+        token_secrets.current = rand::thread_rng().gen();
+        token_secrets.rotated_at = now;
+        Ok(())
+    }
+
+    ///
+    /// Funcion que olvida los peers anunciados cuyo tiempo de vida ya paso, junto con los
+    /// info hashes que se quedan sin peers.
+    ///
+    fn remove_expired_peers_at(&self, now: Instant) -> ResultDht<()> {
+        let mut stored_peers = self
+            .stored_peers
+            .write()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        let lifetime = Duration::from_secs(SECS_STORED_PEER_LIFETIME);
+        stored_peers.retain(|_, peers| {
+            peers.retain(|(_, stored_at)| now.saturating_duration_since(*stored_at) < lifetime);
+            !peers.is_empty()
+        });
+        Ok(())
+    }
+
+    ///
+    /// Funcion que guarda un peer anunciado para el info hash dado. Si ya se guardan peers de
+    /// demasiados info hashes, los anuncios de info hashes nuevos se ignoran.
+    ///
+    fn store_peer(&self, info_hash: NodeId, peer_address: SocketAddrV4) -> ResultDht<()> {
+        let mut stored_peers = self
+            .stored_peers
+            .write()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        if !stored_peers.contains_key(&info_hash) && stored_peers.len() >= MAX_STORED_INFO_HASHES {
+            debug!(
+                "Se ignora el anuncio de {}: se alcanzo el maximo de info hashes",
+                peer_address
+            );
+            return Ok(());
+        }
+        let peers = stored_peers.entry(info_hash).or_default();
+        let lifetime = Duration::from_secs(SECS_STORED_PEER_LIFETIME);
+        peers.retain(|(address, stored_at)| {
+            *address != peer_address && stored_at.elapsed() < lifetime
+        });
+        peers.push((peer_address, Instant::now()));
+        if peers.len() > MAX_STORED_PEERS {
+            peers.remove(0);
+        }
+        Ok(())
+    }
+
+    fn get_stored_peers(&self, info_hash: &NodeId) -> ResultDht<Vec<SocketAddrV4>> {
+        let stored_peers = self
+            .stored_peers
+            .read()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))?;
+        let lifetime = Duration::from_secs(SECS_STORED_PEER_LIFETIME);
+        Ok(stored_peers
+            .get(info_hash)
+            .map(|peers| {
+                peers
+                    .iter()
+                    .rev()
+                    .filter(|(_, stored_at)| stored_at.elapsed() < lifetime)
+                    .map(|(address, _)| *address)
+                    .take(MAX_VALUES_PER_RESPONSE)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn answer_query(&self, query: KrpcQuery, sender: SocketAddrV4) -> ResultDht<KrpcBody> {
+        let mut response = KrpcResponse {
+            id: self.own_id,
+            ..Default::default()
+        };
+        match query {
+            KrpcQuery::Ping => (),
+            KrpcQuery::FindNode { target } => {
+                response.nodes = self.get_routing_table()?.get_closest_nodes(&target, K);
+            }
+            KrpcQuery::GetPeers { info_hash } => {
+                response.nodes = self.get_routing_table()?.get_closest_nodes(&info_hash, K);
+                response.values = self.get_stored_peers(&info_hash)?;
+                response.token = Some(self.create_token(sender.ip())?);
+            }
+            KrpcQuery::AnnouncePeer {
+                info_hash,
+                port,
+                implied_port,
+                token,
+            } => {
+                if !self.is_valid_token(&token, sender.ip())? {
+                    return Ok(KrpcBody::Error {
+                        code: ERROR_PROTOCOL,
+                        message: "Bad token".to_string(),
+                    });
+                }
+                let port = if implied_port { sender.port() } else { port };
+                self.store_peer(info_hash, SocketAddrV4::new(*sender.ip(), port))?;
+            }
+        }
+        Ok(KrpcBody::Response(response))
+    }
+
+    fn send_message(&self, message: &KrpcMessage, address: SocketAddrV4) -> ResultDht<()> {
+        self.socket
+            .send_to(&message.to_bytes(), address)
+            .map_err(|error| DhtError::Sending(format!("{}", error)))?;
+        Ok(())
+    }
+
+    fn send_query(&self, address: SocketAddrV4, query: KrpcQuery) -> ResultDht<PendingQuery> {
+        let transaction_id = self
+            .next_transaction_id
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes()
+            .to_vec();
+        let (response_sender, receiver) = mpsc::channel();
+        self.lock_pending_queries()?
+            .insert(transaction_id.clone(), (address, response_sender));
+
+        let message = KrpcMessage {
+            transaction_id: transaction_id.clone(),
+            body: KrpcBody::Query {
+                id: self.own_id,
+                query,
+            },
+        };
+        if let Err(error) = self.send_message(&message, address) {
+            self.lock_pending_queries()?.remove(&transaction_id);
+            return Err(error);
+        }
+        Ok(PendingQuery {
+            transaction_id,
+            address,
+            receiver,
+        })
+    }
+
+    //Los nodos que responden se agregan a la tabla de ruteo, y los que no se marcan como fallidos.
+    fn wait_for_response(
+        &self,
+        pending_query: PendingQuery,
+        deadline: Instant,
+    ) -> ResultDht<KrpcResponse> {
+        let result = pending_query
+            .receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()));
+        self.lock_pending_queries()?
+            .remove(&pending_query.transaction_id);
+
+        match result.map(|message| message.body) {
+            Ok(KrpcBody::Response(response)) => {
+                self.lock_routing_table()?.add_node(NodeInfo {
+                    id: response.id,
+                    address: pending_query.address,
+                });
+                Ok(response)
+            }
+            Ok(KrpcBody::Error { code, message }) => {
+                Err(DhtError::ErrorResponse(format!("{} {}", code, message)))
+            }
+            Ok(KrpcBody::Query { .. }) => Err(DhtError::ErrorResponse(
+                "Received a query as response".to_string(),
+            )),
+            Err(_) => {
+                self.lock_routing_table()?
+                    .node_failed(&pending_query.address);
+                Err(DhtError::Timeout)
+            }
+        }
+    }
+
+    ///
+    /// Funcion que envia todas las queries dadas a la vez y espera sus respuestas (hasta
+    /// [MILLIS_QUERY_TIMEOUT] en total), devolviendolas en el mismo orden que las queries.
+    ///
+    fn send_queries(
+        &self,
+        queries: Vec<(SocketAddrV4, KrpcQuery)>,
+    ) -> Vec<ResultDht<KrpcResponse>> {
+        let deadline = Instant::now() + Duration::from_millis(MILLIS_QUERY_TIMEOUT);
+        let pending_queries: Vec<ResultDht<PendingQuery>> = queries
+            .into_iter()
+            .map(|(address, query)| self.send_query(address, query))
+            .collect();
+        pending_queries
+            .into_iter()
+            .map(|pending_query| {
+                pending_query
+                    .and_then(|pending_query| self.wait_for_response(pending_query, deadline))
+            })
+            .collect()
+    }
+
+    fn send_single_query(
+        &self,
+        address: SocketAddrV4,
+        query: KrpcQuery,
+    ) -> ResultDht<KrpcResponse> {
+        self.send_queries(vec![(address, query)])
+            .pop()
+            .unwrap_or(Err(DhtError::Timeout))
+    }
+
+    /// Envia un ping al nodo dado y devuelve su id
+    pub fn ping(&self, address: SocketAddrV4) -> ResultDht<NodeId> {
+        Ok(self.send_single_query(address, KrpcQuery::Ping)?.id)
+    }
+
+    /// Le pide al nodo dado los nodos que conoce mas cercanos al id dado
+    pub fn find_node(&self, address: SocketAddrV4, target: NodeId) -> ResultDht<Vec<NodeInfo>> {
+        Ok(self
+            .send_single_query(address, KrpcQuery::FindNode { target })?
+            .nodes)
+    }
+
+    /// Le pide al nodo dado los peers del info hash dado (o los nodos mas cercanos al mismo)
+    pub fn get_peers(&self, address: SocketAddrV4, info_hash: NodeId) -> ResultDht<KrpcResponse> {
+        self.send_single_query(address, KrpcQuery::GetPeers { info_hash })
+    }
+
+    /// Se anuncia en el nodo dado como peer del info hash dado, con el token que entrego el nodo
+    pub fn announce_peer(
+        &self,
+        address: SocketAddrV4,
+        info_hash: NodeId,
+        port: u16,
+        token: Vec<u8>,
+    ) -> ResultDht<()> {
+        self.send_single_query(
+            address,
+            KrpcQuery::AnnouncePeer {
+                info_hash,
+                port,
+                implied_port: false,
+                token,
+            },
+        )?;
+        Ok(())
+    }
+
+    ///
+    /// Funcion que realiza una busqueda iterativa de Kademlia: en cada ronda se le envia la query
+    /// dada a los [ALPHA] nodos mas cercanos al objetivo que todavia no se consultaron, sumando a
+    /// los candidatos los nodos que informan. La busqueda termina cuando ya se consultaron los [K]
+    /// candidatos mas cercanos.
+    ///
+    fn iterative_lookup(&self, target: &NodeId, query: &KrpcQuery) -> ResultDht<LookupResult> {
+        let mut candidates = self.get_routing_table()?.get_closest_nodes(target, K);
+        let mut queried = HashSet::new();
+        let mut peers = HashSet::new();
+        let mut responded_nodes = vec![];
+
+        for _ in 0..MAX_LOOKUP_ROUNDS {
+            candidates.sort_by_key(|node| routing_table::distance(&node.id, target));
+            let nodes_to_query: Vec<NodeInfo> = candidates
+                .iter()
+                .take(K)
+                .filter(|node| !queried.contains(&node.address))
+                .take(ALPHA)
+                .copied()
+                .collect();
+            if nodes_to_query.is_empty() {
+                break;
+            }
+            queried.extend(nodes_to_query.iter().map(|node| node.address));
+
+            let responses = self.send_queries(
+                nodes_to_query
+                    .iter()
+                    .map(|node| (node.address, query.clone()))
+                    .collect(),
+            );
+            for (node, response) in nodes_to_query.into_iter().zip(responses) {
+                let response = match response {
+                    Ok(response) => response,
+                    Err(error) => {
+                        trace!("El nodo {} no respondio: {}", node.address, error);
+                        candidates.retain(|candidate| candidate.address != node.address);
+                        continue;
+                    }
+                };
+                for new_node in response.nodes {
+                    if new_node.id != self.own_id
+                        && !candidates
+                            .iter()
+                            .any(|candidate| candidate.address == new_node.address)
+                    {
+                        candidates.push(new_node);
+                    }
+                }
+                peers.extend(response.values);
+                responded_nodes.push((
+                    NodeInfo {
+                        id: response.id,
+                        address: node.address,
+                    },
+                    response.token,
+                ));
+            }
+        }
+
+        responded_nodes.sort_by_key(|(node, _)| routing_table::distance(&node.id, target));
+        Ok(LookupResult {
+            peers: peers.into_iter().collect(),
+            responded_nodes,
+        })
+    }
+
+    ///
+    /// Funcion que ingresa a la red a traves de los nodos de bootstrap, buscando los nodos mas
+    /// cercanos al id propio para llenar la tabla de ruteo. Devuelve la cantidad de nodos de la tabla.
+    ///
+    pub fn bootstrap(&self) -> ResultDht<usize> {
+        let find_own_id = KrpcQuery::FindNode {
+            target: self.own_id,
+        };
+        self.send_queries(
+            resolve_bootstrap_nodes(&self.bootstrap_nodes)
+                .into_iter()
+                .map(|address| (address, find_own_id.clone()))
+                .collect(),
+        );
+        self.iterative_lookup(&self.own_id, &find_own_id)?;
+        let amount_of_nodes = self.get_routing_table()?.get_amount_of_nodes();
+        debug!("Bootstrap de la DHT con {} nodos", amount_of_nodes);
+        Ok(amount_of_nodes)
+    }
+
+    ///
+    /// Funcion que busca en la red los peers del info hash dado. Si se indica un puerto, ademas se
+    /// anuncia con el mismo en los [K] nodos mas cercanos al info hash que entregaron un token.
+    /// Si la tabla de ruteo tiene pocos nodos, antes se realiza el bootstrap.
+    ///
+    pub fn lookup_peers(
+        &self,
+        info_hash: &NodeId,
+        announce_port: Option<u16>,
+    ) -> ResultDht<Vec<SocketAddrV4>> {
+        if self.get_routing_table()?.get_amount_of_nodes() < K {
+            self.bootstrap()?;
+        }
+        let lookup_result = self.iterative_lookup(
+            info_hash,
+            &KrpcQuery::GetPeers {
+                info_hash: *info_hash,
+            },
+        )?;
+
+        if let Some(port) = announce_port {
+            let announces = lookup_result
+                .responded_nodes
+                .into_iter()
+                .filter_map(|(node, token)| {
+                    token.map(|token| {
+                        (
+                            node.address,
+                            KrpcQuery::AnnouncePeer {
+                                info_hash: *info_hash,
+                                port,
+                                implied_port: false,
+                                token,
+                            },
+                        )
+                    })
+                })
+                .take(K)
+                .collect();
+            let amount_of_announces = self
+                .send_queries(announces)
+                .iter()
+                .filter(|response| response.is_ok())
+                .count();
+            debug!("Anuncio en la DHT a {} nodos", amount_of_announces);
+        }
+        Ok(lookup_result.peers)
+    }
+}
+
+#[cfg(test)]
+mod test_dht_node {
+    use super::*;
+    use std::thread::{self, JoinHandle};
+
+    type NodeHandler = JoinHandle<ResultDht<()>>;
+
+    fn start_node(
+        bootstrap_nodes: Vec<String>,
+        shut_down: &Arc<RwLock<bool>>,
+    ) -> ResultDht<(Arc<DhtNode>, NodeHandler)> {
+        let dht_node = Arc::new(DhtNode::bind(
+            SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0),
+            RoutingTable::new(routing_table::generate_node_id()),
+            bootstrap_nodes,
+        )?);
+        let listening_node = dht_node.clone();
+        let shut_down = shut_down.clone();
+        let handler = thread::spawn(move || listening_node.listen(&shut_down));
+        Ok((dht_node, handler))
+    }
+
+    fn stop_nodes(shut_down: &Arc<RwLock<bool>>, handlers: Vec<NodeHandler>) -> ResultDht<()> {
+        *shut_down
+            .write()
+            .map_err(|error| DhtError::LockingState(format!("{:?}", error)))? = true;
+        for handler in handlers {
+            handler
+                .join()
+                .map_err(|_| DhtError::LockingState("Join error".to_string()))??;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ping_adds_both_nodes_to_the_routing_tables_ok() -> ResultDht<()> {
+        let shut_down = Arc::new(RwLock::new(false));
+        let (first_node, first_handler) = start_node(vec![], &shut_down)?;
+        let (second_node, second_handler) = start_node(vec![], &shut_down)?;
+
+        let pinged_id = first_node.ping(second_node.get_local_address()?);
+
+        stop_nodes(&shut_down, vec![first_handler, second_handler])?;
+        assert_eq!(second_node.get_own_id(), pinged_id?);
+        assert_eq!(
+            vec![second_node.get_own_id()],
+            first_node
+                .get_routing_table()?
+                .get_all_nodes()
+                .iter()
+                .map(|node| node.id)
+                .collect::<Vec<NodeId>>()
+        );
+        assert_eq!(1, second_node.get_routing_table()?.get_amount_of_nodes());
+        Ok(())
+    }
+
+    #[test]
+    fn announce_with_invalid_token_is_rejected_ok() -> ResultDht<()> {
+        let shut_down = Arc::new(RwLock::new(false));
+        let (first_node, first_handler) = start_node(vec![], &shut_down)?;
+        let (second_node, second_handler) = start_node(vec![], &shut_down)?;
+        let second_address = second_node.get_local_address()?;
+        let info_hash = [7; 20];
+
+        let invalid_announce =
+            first_node.announce_peer(second_address, info_hash, 6881, b"invalid".to_vec());
+        let token = first_node.get_peers(second_address, info_hash)?.token;
+        let valid_announce = match token {
+            Some(token) => first_node.announce_peer(second_address, info_hash, 6881, token),
+            None => Err(DhtError::ErrorResponse("Missing token".to_string())),
+        };
+        let peers = first_node.get_peers(second_address, info_hash)?.values;
+
+        stop_nodes(&shut_down, vec![first_handler, second_handler])?;
+        assert!(matches!(invalid_announce, Err(DhtError::ErrorResponse(_))));
+        assert_eq!(Ok(()), valid_announce);
+        assert_eq!(vec![SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6881)], peers);
+        Ok(())
+    }
+
+    #[test]
+    fn tokens_are_valid_until_the_second_rotation_of_the_secret_ok() -> ResultDht<()> {
+        let dht_node = DhtNode::bind(
+            SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0),
+            RoutingTable::new(routing_table::generate_node_id()),
+            vec![],
+        )?;
+        let ip = Ipv4Addr::LOCALHOST;
+        let token = dht_node.create_token(&ip)?;
+        let now = Instant::now();
+        let token_secret_lifetime = Duration::from_secs(SECS_TOKEN_SECRET_LIFETIME);
+
+        dht_node.rotate_token_secret_if_due_at(now)?;
+        assert_eq!(token, dht_node.create_token(&ip)?);
+
+        dht_node.rotate_token_secret_if_due_at(now + token_secret_lifetime)?;
+        assert_ne!(token, dht_node.create_token(&ip)?);
+        assert!(dht_node.is_valid_token(&token, &ip)?);
+        assert!(!dht_node.is_valid_token(&token, &Ipv4Addr::new(10, 0, 0, 1))?);
+
+        dht_node.rotate_token_secret_if_due_at(now + token_secret_lifetime * 2)?;
+        assert!(!dht_node.is_valid_token(&token, &ip)?);
+        Ok(())
+    }
+
+    #[test]
+    fn stored_info_hashes_are_bounded_and_expire_ok() -> ResultDht<()> {
+        let dht_node = DhtNode::bind(
+            SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0),
+            RoutingTable::new(routing_table::generate_node_id()),
+            vec![],
+        )?;
+        let peer_address = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6881);
+        for _ in 0..=MAX_STORED_INFO_HASHES {
+            dht_node.store_peer(routing_table::generate_node_id(), peer_address)?;
+        }
+        let get_amount_of_info_hashes = || {
+            dht_node
+                .stored_peers
+                .read()
+                .map(|stored_peers| stored_peers.len())
+                .map_err(|error| DhtError::LockingState(format!("{:?}", error)))
+        };
+        assert_eq!(MAX_STORED_INFO_HASHES, get_amount_of_info_hashes()?);
+
+        dht_node.remove_expired_peers_at(Instant::now())?;
+        assert_eq!(MAX_STORED_INFO_HASHES, get_amount_of_info_hashes()?);
+
+        dht_node.remove_expired_peers_at(
+            Instant::now() + Duration::from_secs(SECS_STORED_PEER_LIFETIME),
+        )?;
+        assert_eq!(0, get_amount_of_info_hashes()?);
+        Ok(())
+    }
+
+    #[test]
+    fn query_to_a_node_that_does_not_respond_times_out_ok() -> ResultDht<()> {
+        let shut_down = Arc::new(RwLock::new(false));
+        let (dht_node, handler) = start_node(vec![], &shut_down)?;
+        let silent_socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+            .map_err(|error| DhtError::Binding(format!("{}", error)))?;
+        let silent_address = match silent_socket.local_addr() {
+            Ok(SocketAddr::V4(address)) => address,
+            _ => return Err(DhtError::Binding("Invalid address".to_string())),
+        };
+
+        let result = dht_node.ping(silent_address);
+
+        stop_nodes(&shut_down, vec![handler])?;
+        assert_eq!(Err(DhtError::Timeout), result);
+        Ok(())
+    }
+
+    #[test]
+    fn peers_announced_by_a_node_are_found_by_another_ok() -> ResultDht<()> {
+        let shut_down = Arc::new(RwLock::new(false));
+        let (bootstrap_node, bootstrap_handler) = start_node(vec![], &shut_down)?;
+        let bootstrap_address = bootstrap_node.get_local_address()?;
+        let mut nodes = vec![];
+        let mut handlers = vec![bootstrap_handler];
+        for _ in 0..5 {
+            let (dht_node, handler) = start_node(vec![bootstrap_address.to_string()], &shut_down)?;
+            dht_node.bootstrap()?;
+            nodes.push(dht_node);
+            handlers.push(handler);
+        }
+        let info_hash = routing_table::generate_node_id();
+
+        let peers_before_announce = nodes[0].lookup_peers(&info_hash, Some(6881));
+        let peers_after_announce = nodes[4].lookup_peers(&info_hash, None);
+
+        stop_nodes(&shut_down, handlers)?;
+        assert_eq!(Ok(vec![]), peers_before_announce);
+        assert_eq!(
+            vec![SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6881)],
+            peers_after_announce?
+        );
+        assert!(nodes[4].get_routing_table()?.get_amount_of_nodes() > 1);
+        Ok(())
+    }
+}
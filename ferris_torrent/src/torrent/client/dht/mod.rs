@@ -0,0 +1,7 @@
+//! # Modulo de la DHT
+//! Este modulo contiene el nodo de la DHT de BitTorrent (BEP 5) y su tabla de ruteo, usados para
+//! encontrar peers de los torrents sin depender de sus trackers.
+//!
+
+pub mod dht_node;
+pub mod routing_table;
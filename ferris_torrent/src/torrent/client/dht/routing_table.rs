@@ -0,0 +1,340 @@
+//! # Modulo de la tabla de ruteo de la DHT
+//! Este modulo contiene la tabla de ruteo de Kademlia del nodo DHT local: los nodos conocidos se
+//! agrupan en buckets de a lo sumo [K] nodos segun la cantidad de bits iniciales que comparte su id
+//! con el id local, y las busquedas se resuelven con la distancia XOR entre ids.
+//! La tabla puede guardarse en disco (bencodeada) para no tener que volver a armarla en cada ejecucion.
+//!
+
+use core::fmt;
+use log::debug;
+use rand::Rng;
+use shared::parsers::{
+    bencoding::{self, values::ValuesBencoding},
+    krpc::{self, NodeId, NodeInfo, NODE_ID_LEN},
+};
+use std::{collections::HashMap, error::Error, fs, net::SocketAddrV4};
+
+/// Cantidad maxima de nodos por bucket (y de nodos devueltos en cada busqueda)
+pub const K: usize = 8;
+/// Cantidad de queries seguidas sin respuesta a partir de la cual se quita un nodo de la tabla
+pub const MAX_FAILED_QUERIES: u32 = 2;
+
+const AMOUNT_OF_BUCKETS: usize = NODE_ID_LEN * 8;
+
+const ID: &[u8] = b"id";
+const NODES: &[u8] = b"nodes";
+
+/// Representa un error al guardar o recuperar la tabla de ruteo.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum RoutingTableError {
+    WritingStateFile(String),
+    ReadingStateFile(String),
+    InvalidStateFile(String),
+}
+
+impl fmt::Display for RoutingTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for RoutingTableError {}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct RoutingEntry {
+    node: NodeInfo,
+    failed_queries: u32,
+}
+
+/// Tabla de ruteo de Kademlia. Dentro de cada bucket los nodos se ordenan desde el visto hace
+/// mas tiempo hasta el visto mas recientemente.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct RoutingTable {
+    own_id: NodeId,
+    buckets: Vec<Vec<RoutingEntry>>,
+}
+
+///
+/// Funcion que genera un id de nodo aleatorio.
+///
+pub fn generate_node_id() -> NodeId {
+    rand::thread_rng().gen()
+}
+
+///
+/// Funcion que calcula la distancia XOR entre dos ids. Al comparar las distancias obtenidas
+/// (como arrays de bytes big endian) se obtiene el orden de Kademlia.
+///
+pub fn distance(id: &NodeId, other_id: &NodeId) -> NodeId {
+    let mut distance = [0; NODE_ID_LEN];
+    for (byte_index, byte) in distance.iter_mut().enumerate() {
+        *byte = id[byte_index] ^ other_id[byte_index];
+    }
+    distance
+}
+
+impl RoutingTable {
+    pub fn new(own_id: NodeId) -> Self {
+        RoutingTable {
+            own_id,
+            buckets: vec![vec![]; AMOUNT_OF_BUCKETS],
+        }
+    }
+
+    ///Own id getter
+    pub fn get_own_id(&self) -> NodeId {
+        self.own_id
+    }
+
+    /// Devuelve el indice del bucket de un id: la cantidad de bits iniciales que comparte con
+    /// el id local. El id local no pertenece a ningun bucket.
+    fn get_bucket_index(&self, id: &NodeId) -> Option<usize> {
+        let distance = distance(&self.own_id, id);
+        let first_different_byte = distance.iter().position(|byte| *byte != 0)?;
+        Some(first_different_byte * 8 + distance[first_different_byte].leading_zeros() as usize)
+    }
+
+    ///
+    /// Funcion que agrega (o actualiza como visto recientemente) un nodo que respondio o nos
+    /// envio una query. Si su bucket esta lleno el nodo se descarta, ya que Kademlia prefiere a los
+    /// nodos conocidos hace mas tiempo. Devuelve si el nodo quedo en la tabla.
+    ///
+    pub fn add_node(&mut self, node: NodeInfo) -> bool {
+        let bucket_index = match self.get_bucket_index(&node.id) {
+            Some(bucket_index) => bucket_index,
+            None => return false,
+        };
+        let bucket = &mut self.buckets[bucket_index];
+        if let Some(position) = bucket.iter().position(|entry| entry.node.id == node.id) {
+            bucket.remove(position);
+        } else if bucket.len() >= K {
+            return false;
+        }
+        bucket.push(RoutingEntry {
+            node,
+            failed_queries: 0,
+        });
+        true
+    }
+
+    ///
+    /// Funcion que registra que el nodo con la direccion dada no respondio una query. Luego de
+    /// [MAX_FAILED_QUERIES] fallos seguidos el nodo se quita de la tabla, dejando lugar a otro.
+    ///
+    pub fn node_failed(&mut self, address: &SocketAddrV4) {
+        for bucket in self.buckets.iter_mut() {
+            if let Some(position) = bucket
+                .iter()
+                .position(|entry| entry.node.address == *address)
+            {
+                bucket[position].failed_queries += 1;
+                if bucket[position].failed_queries >= MAX_FAILED_QUERIES {
+                    debug!("Se quita de la tabla de ruteo al nodo {}", address);
+                    bucket.remove(position);
+                }
+                return;
+            }
+        }
+    }
+
+    ///
+    /// Funcion que devuelve los nodos de la tabla mas cercanos al id dado, ordenados de menor a
+    /// mayor distancia.
+    ///
+    pub fn get_closest_nodes(&self, target: &NodeId, amount: usize) -> Vec<NodeInfo> {
+        let mut nodes = self.get_all_nodes();
+        nodes.sort_by_key(|node| distance(&node.id, target));
+        nodes.truncate(amount);
+        nodes
+    }
+
+    /// Devuelve todos los nodos de la tabla
+    pub fn get_all_nodes(&self) -> Vec<NodeInfo> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.iter().map(|entry| entry.node))
+            .collect()
+    }
+
+    /// Devuelve la cantidad de nodos de la tabla
+    pub fn get_amount_of_nodes(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.len()).sum()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut dic_state = HashMap::new();
+        dic_state.insert(ID.to_vec(), ValuesBencoding::String(self.own_id.to_vec()));
+        dic_state.insert(
+            NODES.to_vec(),
+            ValuesBencoding::String(krpc::encode_compact_nodes(&self.get_all_nodes())),
+        );
+        bencoding::encoder::from_dic(dic_state)
+    }
+
+    fn from_bytes(state_bytes: Vec<u8>) -> Result<Self, RoutingTableError> {
+        let (dic_state, _) = bencoding::decoder::to_dic(state_bytes)
+            .map_err(|err| RoutingTableError::InvalidStateFile(format!("{}", err)))?;
+        let own_id = match dic_state.get(ID) {
+            Some(ValuesBencoding::String(own_id)) => own_id
+                .as_slice()
+                .try_into()
+                .map_err(|_| RoutingTableError::InvalidStateFile("Invalid node id.".to_string()))?,
+            _ => {
+                return Err(RoutingTableError::InvalidStateFile(
+                    "Missing node id.".to_string(),
+                ))
+            }
+        };
+        let mut routing_table = RoutingTable::new(own_id);
+        if let Some(ValuesBencoding::String(compact_nodes)) = dic_state.get(NODES) {
+            for node in krpc::decode_compact_nodes(compact_nodes) {
+                routing_table.add_node(node);
+            }
+        }
+        Ok(routing_table)
+    }
+
+    ///
+    /// Funcion que guarda la tabla (el id local y los nodos conocidos) en la ruta dada.
+    /// El archivo se escribe primero con otro nombre y luego se renombra, para no dejar nunca
+    /// un archivo a medio escribir.
+    ///
+    pub fn save(&self, state_file_path: &str) -> Result<(), RoutingTableError> {
+        let temp_state_file_path = format!("{}.tmp", state_file_path);
+        fs::write(&temp_state_file_path, self.to_bytes())
+            .map_err(|err| RoutingTableError::WritingStateFile(format!("{}", err)))?;
+        fs::rename(&temp_state_file_path, state_file_path)
+            .map_err(|err| RoutingTableError::WritingStateFile(format!("{}", err)))?;
+        debug!(
+            "Tabla de ruteo de la DHT guardada en {} ({} nodos)",
+            state_file_path,
+            self.get_amount_of_nodes()
+        );
+        Ok(())
+    }
+
+    ///
+    /// Funcion que recupera una tabla guardada previamente con [RoutingTable::save].
+    ///
+    pub fn load(state_file_path: &str) -> Result<Self, RoutingTableError> {
+        let state_bytes = fs::read(state_file_path)
+            .map_err(|err| RoutingTableError::ReadingStateFile(format!("{}", err)))?;
+        RoutingTable::from_bytes(state_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test_routing_table {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn create_node(id: NodeId, port: u16) -> NodeInfo {
+        NodeInfo {
+            id,
+            address: SocketAddrV4::new(Ipv4Addr::LOCALHOST, port),
+        }
+    }
+
+    //Id que solo difiere del id local en el ultimo byte, por lo que todos caen en el mismo bucket
+    fn create_id_in_last_bucket(last_byte: u8) -> NodeId {
+        let mut id = [0; NODE_ID_LEN];
+        id[NODE_ID_LEN - 1] = last_byte;
+        id
+    }
+
+    #[test]
+    fn nodes_are_placed_in_the_bucket_of_their_common_prefix_ok() {
+        let routing_table = RoutingTable::new([0; NODE_ID_LEN]);
+        let mut id = [0; NODE_ID_LEN];
+        id[0] = 0b1000_0000;
+        assert_eq!(Some(0), routing_table.get_bucket_index(&id));
+        id[0] = 0b0010_0000;
+        assert_eq!(Some(2), routing_table.get_bucket_index(&id));
+        assert_eq!(
+            Some(AMOUNT_OF_BUCKETS - 1),
+            routing_table.get_bucket_index(&create_id_in_last_bucket(1))
+        );
+        assert_eq!(None, routing_table.get_bucket_index(&[0; NODE_ID_LEN]));
+    }
+
+    #[test]
+    fn full_bucket_rejects_new_nodes_ok() {
+        let mut routing_table = RoutingTable::new([0; NODE_ID_LEN]);
+        let mut id = [0; NODE_ID_LEN];
+        for last_byte in 0..K as u8 {
+            id[0] = 0x80 | last_byte;
+            assert!(routing_table.add_node(create_node(id, 7000 + u16::from(last_byte))));
+        }
+        id[0] = 0xFF;
+
+        assert!(!routing_table.add_node(create_node(id, 8000)));
+        assert_eq!(K, routing_table.get_amount_of_nodes());
+    }
+
+    #[test]
+    fn failed_nodes_are_removed_ok() {
+        let mut routing_table = RoutingTable::new([0; NODE_ID_LEN]);
+        let node = create_node(create_id_in_last_bucket(1), 7000);
+        routing_table.add_node(node);
+
+        for _ in 0..MAX_FAILED_QUERIES - 1 {
+            routing_table.node_failed(&node.address);
+        }
+        assert_eq!(1, routing_table.get_amount_of_nodes());
+        routing_table.node_failed(&node.address);
+        assert_eq!(0, routing_table.get_amount_of_nodes());
+    }
+
+    #[test]
+    fn closest_nodes_are_sorted_by_xor_distance_ok() {
+        let mut routing_table = RoutingTable::new([0; NODE_ID_LEN]);
+        for last_byte in [0b0001, 0b0110, 0b0100, 0b1000] {
+            routing_table.add_node(create_node(
+                create_id_in_last_bucket(last_byte),
+                u16::from(last_byte),
+            ));
+        }
+
+        let closest_nodes = routing_table.get_closest_nodes(&create_id_in_last_bucket(0b0101), 3);
+
+        let closest_ports: Vec<u16> = closest_nodes
+            .iter()
+            .map(|node| node.address.port())
+            .collect();
+        assert_eq!(vec![0b0100, 0b0110, 0b0001], closest_ports);
+    }
+
+    #[test]
+    fn save_and_load_routing_table_ok() -> Result<(), RoutingTableError> {
+        let mut routing_table = RoutingTable::new(generate_node_id());
+        for port in 7000..7005 {
+            routing_table.add_node(create_node(generate_node_id(), port));
+        }
+        let state_file_path = "test_save_and_load_routing_table_ok.dht";
+
+        routing_table.save(state_file_path)?;
+        let loaded_routing_table = RoutingTable::load(state_file_path);
+        fs::remove_file(state_file_path)
+            .map_err(|err| RoutingTableError::WritingStateFile(format!("{}", err)))?;
+
+        let loaded_routing_table = loaded_routing_table?;
+        assert_eq!(
+            routing_table.get_own_id(),
+            loaded_routing_table.get_own_id()
+        );
+        let mut nodes = routing_table.get_all_nodes();
+        let mut loaded_nodes = loaded_routing_table.get_all_nodes();
+        nodes.sort_by_key(|node| node.address);
+        loaded_nodes.sort_by_key(|node| node.address);
+        assert_eq!(nodes, loaded_nodes);
+        Ok(())
+    }
+
+    #[test]
+    fn load_invalid_routing_table_error() {
+        assert!(matches!(
+            RoutingTable::from_bytes(b"d5:nodes0:e".to_vec()),
+            Err(RoutingTableError::InvalidStateFile(_))
+        ));
+    }
+}
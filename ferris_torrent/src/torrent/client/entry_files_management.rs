@@ -7,6 +7,7 @@ use std::{
 };
 
 use log::error;
+use shared::magnet_link::MagnetLink;
 
 #[derive(Debug)]
 pub enum EntryFilesError {
@@ -41,6 +42,7 @@ fn add_files_from_folder(list: &mut Vec<String>, folder: ReadDir) -> Result<(),
 /// pertenecen a ese directorio o archivo.
 /// En caso de ser un directorio, la lista posee cada una de las rutas a cada archivo . torrent.
 /// En caso de ser un archivo, tambien se lo coloca en una lista y se lo retorna como único elemento de la lista.
+/// Los magnet links se agregan a la lista tal como fueron ingresados.
 ///
 pub fn create_list_files() -> Result<Vec<String>, EntryFilesError> {
     let mut list_files = vec![];
@@ -49,7 +51,7 @@ pub fn create_list_files() -> Result<Vec<String>, EntryFilesError> {
 
     for args in iter_args {
         let path_args = Path::new(&args);
-        if path_args.is_file() {
+        if MagnetLink::is_magnet_link(&args) || path_args.is_file() {
             list_files.push(args)
         } else if path_args.is_dir() {
             match fs::read_dir(args) {
@@ -0,0 +1,237 @@
+//! # Modulo de manejo de magnet links
+//! Este modulo contiene las funciones encargadas de obtener, a partir de un magnet link, el
+//! TorrentFileData del torrent: se anuncia a los trackers del magnet (o, si no los tiene o no
+//! responden, se busca el info hash en la DHT) para obtener peers y se les pide la metadata hasta
+//! que alguno envie un diccionario info con el info hash esperado.
+//!
+
+use crate::torrent::{
+    client::{
+        dht::dht_node::{self, DhtError, DhtNode},
+        peers_communication::{local_peer_communicator::generate_peer_id, metadata_exchange},
+        tracker_communication::{
+            http_handler::{self, ErrorMsgHttp},
+            tracker_tiers::TrackerTiers,
+        },
+    },
+    data::{
+        config_file_torrent::ConfigFileTorrent, torrent_status::TorrentStatus,
+        tracker_response_data::PeerDataFromTrackerResponse,
+    },
+};
+use log::{info, warn};
+use shared::{
+    magnet_link::{MagnetError, MagnetLink},
+    torrent_file_data::TorrentFileData,
+};
+use std::{
+    error::Error,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+//Como todavia no se conoce el tamaño del torrent, se anuncia que falta al menos un byte para
+//que el tracker nos considere leecher y nos devuelva tambien seeders.
+const UNKNOWN_LEFT_BYTES: u64 = 1;
+
+/// Representa un tipo de error al crear un torrent a partir de un magnet link
+#[derive(PartialEq, Eq, Debug)]
+pub enum MagnetHandlerError {
+    ParsingMagnet(MagnetError),
+    NoTrackers,
+    CommunicationWithTracker(ErrorMsgHttp),
+    RunningDht(DhtError),
+    NoPeerSentTheMetadata,
+    ReadingShutDownField(String),
+    ShutDown,
+}
+
+impl fmt::Display for MagnetHandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for MagnetHandlerError {}
+
+fn is_shut_down_set(global_shut_down: &Arc<RwLock<bool>>) -> Result<bool, MagnetHandlerError> {
+    let global_shut_down = global_shut_down
+        .read()
+        .map_err(|error| MagnetHandlerError::ReadingShutDownField(format!("{:?}", error)))?;
+    Ok(*global_shut_down)
+}
+
+fn get_peers_from_dht(
+    dht_node: &DhtNode,
+    placeholder_torrent: &TorrentFileData,
+) -> Result<Vec<PeerDataFromTrackerResponse>, MagnetHandlerError> {
+    let peers = dht_node::find_torrent_peers(dht_node, placeholder_torrent, None)
+        .map_err(MagnetHandlerError::RunningDht)?;
+    info!("Se obtuvieron {} peers de la DHT", peers.len());
+    Ok(peers)
+}
+
+///
+/// Funcion que obtiene los peers a los que pedirles la metadata: primero se anuncia a los
+/// trackers del magnet y, si no tiene ninguno o fallan, se busca el info hash en la DHT.
+///
+fn get_peers_for_metadata(
+    magnet: &MagnetLink,
+    placeholder_torrent: &TorrentFileData,
+    config_data: &ConfigFileTorrent,
+    peer_id: &[u8],
+    dht_node: Option<&DhtNode>,
+) -> Result<Vec<PeerDataFromTrackerResponse>, MagnetHandlerError> {
+    if magnet.trackers.is_empty() {
+        return match dht_node {
+            Some(dht_node) => {
+                info!("El magnet link no tiene trackers, se buscan peers en la DHT");
+                get_peers_from_dht(dht_node, placeholder_torrent)
+            }
+            None => Err(MagnetHandlerError::NoTrackers),
+        };
+    }
+
+    let mut torrent_status = TorrentStatus::new(
+        placeholder_torrent.get_total_length(),
+        placeholder_torrent.get_total_amount_pieces(),
+    );
+    torrent_status.left = UNKNOWN_LEFT_BYTES;
+    match http_handler::communicate_with_tracker(
+        &mut TrackerTiers::new(placeholder_torrent),
+        &torrent_status,
+        placeholder_torrent,
+        config_data,
+        peer_id.to_vec(),
+        None,
+    ) {
+        Ok(tracker_response) => Ok(tracker_response.peers),
+        Err(error) => match dht_node {
+            Some(dht_node) => {
+                info!(
+                    "Fallo la comunicacion con los trackers del magnet, se buscan peers en la DHT: {}",
+                    error
+                );
+                get_peers_from_dht(dht_node, placeholder_torrent)
+            }
+            None => Err(MagnetHandlerError::CommunicationWithTracker(error)),
+        },
+    }
+}
+
+///
+/// Funcion que crea el TorrentFileData correspondiente al magnet link dado, obteniendo el
+/// diccionario info desde los peers que informen sus trackers o, en su defecto, la DHT.
+/// La metadata de cada peer se verifica contra el info hash del magnet link, por lo que si
+/// algun peer envia datos invalidos se prueba con el siguiente.
+///
+pub fn create_torrent_from_magnet(
+    magnet_uri: &str,
+    config_data: &ConfigFileTorrent,
+    dht_node: Option<&DhtNode>,
+    global_shut_down: &Arc<RwLock<bool>>,
+) -> Result<TorrentFileData, MagnetHandlerError> {
+    let magnet = MagnetLink::parse(magnet_uri).map_err(MagnetHandlerError::ParsingMagnet)?;
+    let placeholder_torrent = magnet.create_placeholder_torrent();
+    let peer_id = generate_peer_id();
+
+    info!(
+        "Obteniendo peers para descargar la metadata de {}",
+        magnet.get_name()
+    );
+    let peers = get_peers_for_metadata(
+        &magnet,
+        &placeholder_torrent,
+        config_data,
+        &peer_id,
+        dht_node,
+    )?;
+
+    for peer in peers {
+        if is_shut_down_set(global_shut_down)? {
+            return Err(MagnetHandlerError::ShutDown);
+        }
+        let peer_address = peer.peer_address;
+        let result =
+            metadata_exchange::fetch_metadata_from_peer(peer_address, &magnet.info_hash, &peer_id)
+                .map_err(|error| format!("{}", error))
+                .and_then(|info_bytes| {
+                    magnet
+                        .create_torrent(&info_bytes)
+                        .map_err(|error| format!("{}", error))
+                });
+        match result {
+            Ok(torrent) => {
+                info!("Metadata obtenida del peer {}", peer_address);
+                return Ok(torrent);
+            }
+            Err(error) => warn!(
+                "No se pudo obtener la metadata del peer {}: {}",
+                peer_address, error
+            ),
+        }
+    }
+    Err(MagnetHandlerError::NoPeerSentTheMetadata)
+}
+
+#[cfg(test)]
+mod test_magnet_handler {
+    use super::*;
+    use crate::torrent::client::dht::routing_table::{self, RoutingTable};
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    fn create_config() -> Result<ConfigFileTorrent, Box<dyn Error>> {
+        Ok(ConfigFileTorrent::new("config.txt")?)
+    }
+
+    #[test]
+    fn magnet_without_trackers_error() -> Result<(), Box<dyn Error>> {
+        let magnet_uri = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056";
+
+        assert_eq!(
+            Err(MagnetHandlerError::NoTrackers),
+            create_torrent_from_magnet(
+                magnet_uri,
+                &create_config()?,
+                None,
+                &Arc::new(RwLock::new(false))
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn magnet_without_trackers_looks_for_peers_in_the_dht_ok() -> Result<(), Box<dyn Error>> {
+        let magnet_uri = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056";
+        let dht_node = DhtNode::bind(
+            SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0),
+            RoutingTable::new(routing_table::generate_node_id()),
+            vec![],
+        )?;
+
+        assert_eq!(
+            Err(MagnetHandlerError::NoPeerSentTheMetadata),
+            create_torrent_from_magnet(
+                magnet_uri,
+                &create_config()?,
+                Some(&dht_node),
+                &Arc::new(RwLock::new(false))
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_magnet_error() -> Result<(), Box<dyn Error>> {
+        assert!(matches!(
+            create_torrent_from_magnet(
+                "magnet:?dn=without_hash",
+                &create_config()?,
+                None,
+                &Arc::new(RwLock::new(false))
+            ),
+            Err(MagnetHandlerError::ParsingMagnet(_))
+        ));
+        Ok(())
+    }
+}
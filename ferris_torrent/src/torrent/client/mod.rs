@@ -3,7 +3,9 @@
 //!
 
 pub mod block_handler;
+pub mod dht;
 pub mod entry_files_management;
+pub mod magnet_handler;
 pub mod peers_communication;
-pub mod pieces_assembling_handler;
+pub mod resume_handler;
 pub mod tracker_communication;
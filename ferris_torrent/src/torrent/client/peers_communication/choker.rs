@@ -0,0 +1,247 @@
+//! # Modulo de choking
+//! Este modulo contiene el algoritmo central de choking/unchoking de un torrent.
+//! Cada cierto tiempo se decide a que peers externos se les envian bloques (unchoke)
+//! en base a las tasas de transferencia de cada uno, mas un lugar optimista que rota.
+//!
+
+extern crate rand;
+
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+/// Cada cuanto se recalculan los peers a los cuales se les envian bloques
+pub const SECS_CHOKING_ROUND: u64 = 10;
+/// Cantidad de rondas de choking que dura un unchoke optimista (30 segundos)
+pub const ROUNDS_PER_OPTIMISTIC_UNCHOKE: u64 = 3;
+/// Cantidad de peers unchokeados por tasa de transferencia (sin contar el optimista)
+pub const DEFAULT_UNCHOKE_SLOTS: usize = 4;
+
+/// Datos de transferencia de un peer externo desde la ultima ronda de choking
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct PeerTransferStats {
+    pub downloaded: u64,
+    pub uploaded: u64,
+    pub is_interested: bool,
+    pub is_unchoked: bool,
+}
+
+/// Struct compartido por todos los LocalPeerCommunicator de un torrent, que decide
+/// a que peers externos se les permite pedirnos bloques.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Choker {
+    pub peers: HashMap<Vec<u8>, PeerTransferStats>,
+    pub optimistic_unchoked_peer: Option<Vec<u8>>,
+    pub unchoke_slots: usize,
+    pub amount_of_rounds: u64,
+}
+
+impl Choker {
+    /// Funcion que crea un choker sin peers registrados
+    ///
+    pub fn new(unchoke_slots: usize) -> Self {
+        Choker {
+            peers: HashMap::new(),
+            optimistic_unchoked_peer: None,
+            unchoke_slots,
+            amount_of_rounds: 0,
+        }
+    }
+
+    /// Funcion que suma bytes descargados desde un peer externo
+    ///
+    pub fn add_downloaded_bytes(&mut self, peer_id: &[u8], amount_of_bytes: u64) {
+        self.peers.entry(peer_id.to_vec()).or_default().downloaded += amount_of_bytes;
+    }
+
+    /// Funcion que suma bytes enviados a un peer externo
+    ///
+    pub fn add_uploaded_bytes(&mut self, peer_id: &[u8], amount_of_bytes: u64) {
+        self.peers.entry(peer_id.to_vec()).or_default().uploaded += amount_of_bytes;
+    }
+
+    ///
+    /// Funcion que actualiza si un peer externo esta interesado en nuestras piezas.
+    /// Si se interesa y todavia quedan lugares libres, se lo unchokea sin esperar
+    /// a la siguiente ronda.
+    ///
+    pub fn update_peer_interest(&mut self, peer_id: &[u8], is_interested: bool) {
+        let amount_of_unchoked_peers = self
+            .peers
+            .values()
+            .filter(|peer_stats| peer_stats.is_unchoked)
+            .count();
+        let peer_stats = self.peers.entry(peer_id.to_vec()).or_default();
+        peer_stats.is_interested = is_interested;
+        if is_interested && amount_of_unchoked_peers < self.unchoke_slots {
+            peer_stats.is_unchoked = true;
+        }
+    }
+
+    /// Funcion que indica si a un peer externo se le permite pedirnos bloques
+    ///
+    pub fn is_unchoked(&self, peer_id: &[u8]) -> bool {
+        self.peers
+            .get(peer_id)
+            .is_some_and(|peer_stats| peer_stats.is_unchoked)
+    }
+
+    /// Funcion que olvida a un peer externo al finalizar la interaccion con el mismo
+    ///
+    pub fn remove_peer(&mut self, peer_id: &[u8]) {
+        self.peers.remove(peer_id);
+        if self.optimistic_unchoked_peer.as_deref() == Some(peer_id) {
+            self.optimistic_unchoked_peer = None;
+        }
+    }
+
+    ///
+    /// Funcion que ejecuta una ronda de choking: se unchokea a los peers interesados con mayor
+    /// tasa de descarga (o de subida si ya se tiene el archivo completo) y, cada
+    /// [ROUNDS_PER_OPTIMISTIC_UNCHOKE] rondas, se elige al azar un nuevo peer optimista
+    /// entre los restantes interesados. Al finalizar se reinician los contadores de bytes.
+    ///
+    pub fn run_choking_round(&mut self, is_seeding: bool) {
+        let mut interested_peers: Vec<(Vec<u8>, u64)> = self
+            .peers
+            .iter()
+            .filter(|(_peer_id, peer_stats)| peer_stats.is_interested)
+            .map(|(peer_id, peer_stats)| {
+                let rate = if is_seeding {
+                    peer_stats.uploaded
+                } else {
+                    peer_stats.downloaded
+                };
+                (peer_id.clone(), rate)
+            })
+            .collect();
+        interested_peers.sort_by(|(_, rate_a), (_, rate_b)| rate_b.cmp(rate_a));
+
+        let mut unchoked_peers: Vec<Vec<u8>> = interested_peers
+            .iter()
+            .take(self.unchoke_slots)
+            .map(|(peer_id, _rate)| peer_id.clone())
+            .collect();
+
+        let is_optimistic_peer_still_interested = self
+            .optimistic_unchoked_peer
+            .as_ref()
+            .is_some_and(|peer_id| {
+                interested_peers
+                    .iter()
+                    .any(|(interested_peer_id, _rate)| interested_peer_id == peer_id)
+            });
+        if self
+            .amount_of_rounds
+            .is_multiple_of(ROUNDS_PER_OPTIMISTIC_UNCHOKE)
+            || !is_optimistic_peer_still_interested
+        {
+            let candidates: Vec<&Vec<u8>> = interested_peers
+                .iter()
+                .skip(self.unchoke_slots)
+                .map(|(peer_id, _rate)| peer_id)
+                .collect();
+            self.optimistic_unchoked_peer = candidates
+                .choose(&mut rand::thread_rng())
+                .map(|peer_id| peer_id.to_vec());
+        }
+        if let Some(optimistic_unchoked_peer) = &self.optimistic_unchoked_peer {
+            if !unchoked_peers.contains(optimistic_unchoked_peer) {
+                unchoked_peers.push(optimistic_unchoked_peer.clone());
+            }
+        }
+
+        for (peer_id, peer_stats) in self.peers.iter_mut() {
+            peer_stats.is_unchoked = unchoked_peers.contains(peer_id);
+            peer_stats.downloaded = 0;
+            peer_stats.uploaded = 0;
+        }
+        self.amount_of_rounds += 1;
+    }
+}
+
+#[cfg(test)]
+mod test_choker {
+    use super::*;
+
+    fn create_choker_with_interested_peers(downloaded_bytes: &[u64]) -> Choker {
+        let mut choker = Choker::new(2);
+        for (index, downloaded) in downloaded_bytes.iter().enumerate() {
+            let peer_id = vec![u8::try_from(index).unwrap_or_default()];
+            choker.peers.insert(
+                peer_id,
+                PeerTransferStats {
+                    downloaded: *downloaded,
+                    uploaded: 0,
+                    is_interested: true,
+                    is_unchoked: false,
+                },
+            );
+        }
+        choker
+    }
+
+    mod test_update_peer_interest {
+        use super::*;
+
+        #[test]
+        fn an_interested_peer_is_unchoked_while_there_are_free_slots_ok() {
+            let mut choker = Choker::new(1);
+
+            choker.update_peer_interest(&[0], true);
+            choker.update_peer_interest(&[1], true);
+
+            assert!(choker.is_unchoked(&[0]));
+            assert!(!choker.is_unchoked(&[1]));
+        }
+    }
+
+    mod test_run_choking_round {
+        use super::*;
+
+        #[test]
+        fn the_peers_with_the_highest_download_rate_are_unchoked_ok() {
+            let mut choker = create_choker_with_interested_peers(&[10, 30, 20]);
+
+            choker.run_choking_round(false);
+
+            assert!(choker.is_unchoked(&[1]));
+            assert!(choker.is_unchoked(&[2]));
+            assert_eq!(Some(vec![0]), choker.optimistic_unchoked_peer);
+            assert!(choker.is_unchoked(&[0]));
+        }
+
+        #[test]
+        fn the_upload_rate_is_used_when_seeding_ok() {
+            let mut choker = create_choker_with_interested_peers(&[10, 30, 20, 0]);
+            choker.add_uploaded_bytes(&[3], 100);
+            choker.add_uploaded_bytes(&[0], 50);
+
+            choker.run_choking_round(true);
+
+            assert!(choker.is_unchoked(&[3]));
+            assert!(choker.is_unchoked(&[0]));
+        }
+
+        #[test]
+        fn the_optimistic_unchoke_is_kept_between_rotations_ok() {
+            let mut choker = create_choker_with_interested_peers(&[10, 30, 20, 0]);
+            choker.run_choking_round(false);
+            let optimistic_unchoked_peer = choker.optimistic_unchoked_peer.clone();
+
+            choker.run_choking_round(false);
+
+            assert_eq!(optimistic_unchoked_peer, choker.optimistic_unchoked_peer);
+        }
+
+        #[test]
+        fn not_interested_peers_are_choked_ok() {
+            let mut choker = create_choker_with_interested_peers(&[10, 30]);
+            choker.update_peer_interest(&[1], false);
+
+            choker.run_choking_round(false);
+
+            assert!(!choker.is_unchoked(&[1]));
+            assert!(choker.is_unchoked(&[0]));
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! # Modulo de salud de la conexion
+//! Este modulo contiene el seguimiento del estado de la conexion con un peer externo: cuando
+//! enviarle un keep alive para que no nos desconecte, cuando desconectarlo por no enviarnos
+//! mensajes, y cuando considerarlo snubbed por no entregarnos los bloques que le pedimos.
+//!
+
+use super::local_peer_communicator::BlockRequest;
+use crate::torrent::data::config_file_torrent::{
+    DEFAULT_PEER_IDLE_TIMEOUT, DEFAULT_REQUEST_TIMEOUT,
+};
+use std::time::{Duration, Instant};
+
+/// Tiempo de silencio en la conexion tras el cual se le envia un keep alive al peer externo
+pub const SECS_KEEP_ALIVE_INTERVAL: u64 = 120;
+/// Cantidad maxima de bloques pedidos sin respuesta a un peer snubbed
+pub const SNUBBED_REQUEST_QUEUE_DEPTH: usize = 1;
+
+/// Estado de la salud de la conexion con un peer externo.
+#[derive(Debug, Clone)]
+pub struct ConnectionHealth {
+    peer_idle_timeout: Duration,
+    request_timeout: Duration,
+    last_received_at: Instant,
+    last_keep_alive_at: Instant,
+    waiting_for_block_since: Option<Instant>,
+    snubbed: bool,
+    cancelled_requests: Vec<BlockRequest>,
+}
+
+impl Default for ConnectionHealth {
+    fn default() -> Self {
+        ConnectionHealth::new(
+            Duration::from_secs(DEFAULT_PEER_IDLE_TIMEOUT as u64),
+            Duration::from_secs(DEFAULT_REQUEST_TIMEOUT as u64),
+        )
+    }
+}
+
+impl ConnectionHealth {
+    pub fn new(peer_idle_timeout: Duration, request_timeout: Duration) -> Self {
+        let now = Instant::now();
+        ConnectionHealth {
+            peer_idle_timeout,
+            request_timeout,
+            last_received_at: now,
+            last_keep_alive_at: now,
+            waiting_for_block_since: None,
+            snubbed: false,
+            cancelled_requests: vec![],
+        }
+    }
+
+    /// Funcion que registra la recepcion de un mensaje del peer externo
+    ///
+    pub fn message_received_at(&mut self, now: Instant) {
+        self.last_received_at = now;
+    }
+
+    /// Funcion que indica si el peer externo no nos envia mensajes desde hace mas tiempo
+    /// que el permitido, en cuyo caso se debe cortar la conexion.
+    ///
+    pub fn is_idle_at(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_received_at) >= self.peer_idle_timeout
+    }
+
+    /// Funcion que indica si corresponde enviarle un keep alive al peer externo por llevar la
+    /// conexion en silencio el intervalo de keep alive, registrando el envio en ese caso.
+    ///
+    pub fn poll_keep_alive_at(&mut self, now: Instant) -> bool {
+        let last_activity_at = self.last_received_at.max(self.last_keep_alive_at);
+        if now.saturating_duration_since(last_activity_at)
+            < Duration::from_secs(SECS_KEEP_ALIVE_INTERVAL)
+        {
+            return false;
+        }
+        self.last_keep_alive_at = now;
+        true
+    }
+
+    /// Funcion que registra el envio de un Request. Si no se estaba esperando ningun bloque,
+    /// comienza a contar el tiempo de espera.
+    ///
+    pub fn block_requested_at(&mut self, now: Instant) {
+        self.waiting_for_block_since.get_or_insert(now);
+    }
+
+    /// Funcion que registra la recepcion de un bloque pedido, con lo cual el peer externo deja
+    /// de estar snubbed y, si quedan pedidos pendientes, se reinicia el tiempo de espera.
+    ///
+    pub fn block_received_at(&mut self, now: Instant, has_pending_requests: bool) {
+        self.snubbed = false;
+        self.waiting_for_block_since = has_pending_requests.then_some(now);
+    }
+
+    /// Funcion que indica si el peer externo acaba de quedar snubbed: tiene pedidos pendientes y
+    /// no nos entrego ningun bloque dentro del tiempo de espera permitido.
+    ///
+    pub fn check_snubbed_at(&mut self, now: Instant, has_pending_requests: bool) -> bool {
+        if !has_pending_requests {
+            self.waiting_for_block_since = None;
+            return false;
+        }
+        let is_snubbed = self.waiting_for_block_since.is_some_and(|waiting_since| {
+            now.saturating_duration_since(waiting_since) >= self.request_timeout
+        });
+        if is_snubbed {
+            self.snubbed = true;
+            self.waiting_for_block_since = None;
+        }
+        is_snubbed
+    }
+
+    pub fn is_snubbed(&self) -> bool {
+        self.snubbed
+    }
+
+    /// Funcion que devuelve la cantidad maxima de bloques a pedir sin respuesta al peer
+    /// externo, la cual se reduce mientras este snubbed.
+    ///
+    pub fn get_request_queue_depth(&self, request_queue_depth: usize) -> usize {
+        if self.snubbed {
+            SNUBBED_REQUEST_QUEUE_DEPTH
+        } else {
+            request_queue_depth
+        }
+    }
+
+    /// Funcion que recuerda los pedidos cancelados al quedar snubbed el peer externo, para
+    /// ignorar los bloques que igualmente nos envie despues.
+    ///
+    pub fn set_cancelled_requests(&mut self, cancelled_requests: Vec<BlockRequest>) {
+        self.cancelled_requests = cancelled_requests;
+    }
+
+    /// Funcion que indica si un bloque recibido corresponde a un pedido cancelado,
+    /// olvidando dicho pedido en ese caso.
+    ///
+    pub fn take_cancelled_request(
+        &mut self,
+        piece_index: usize,
+        beginning_byte_index: u32,
+    ) -> bool {
+        let previous_len = self.cancelled_requests.len();
+        self.cancelled_requests.retain(|block_request| {
+            block_request.piece_index != piece_index
+                || block_request.beginning_byte_index != beginning_byte_index
+        });
+        self.cancelled_requests.len() != previous_len
+    }
+}
+
+#[cfg(test)]
+mod test_connection_health {
+    use super::*;
+
+    fn create_connection_health(now: Instant) -> ConnectionHealth {
+        let mut connection_health =
+            ConnectionHealth::new(Duration::from_secs(180), Duration::from_secs(60));
+        connection_health.message_received_at(now);
+        connection_health.last_keep_alive_at = now;
+        connection_health
+    }
+
+    #[test]
+    fn keep_alive_is_sent_after_two_minutes_of_silence_ok() {
+        let now = Instant::now();
+        let mut connection_health = create_connection_health(now);
+        let keep_alive_interval = Duration::from_secs(SECS_KEEP_ALIVE_INTERVAL);
+
+        assert!(!connection_health.poll_keep_alive_at(now + Duration::from_secs(60)));
+        assert!(connection_health.poll_keep_alive_at(now + keep_alive_interval));
+        assert!(!connection_health.poll_keep_alive_at(now + keep_alive_interval));
+
+        connection_health.message_received_at(now + Duration::from_secs(200));
+        assert!(!connection_health.poll_keep_alive_at(now + Duration::from_secs(300)));
+        assert!(connection_health
+            .poll_keep_alive_at(now + Duration::from_secs(200) + keep_alive_interval));
+    }
+
+    #[test]
+    fn peer_is_idle_after_the_idle_timeout_ok() {
+        let now = Instant::now();
+        let mut connection_health = create_connection_health(now);
+
+        assert!(!connection_health.is_idle_at(now + Duration::from_secs(179)));
+        assert!(connection_health.is_idle_at(now + Duration::from_secs(180)));
+
+        connection_health.message_received_at(now + Duration::from_secs(180));
+        assert!(!connection_health.is_idle_at(now + Duration::from_secs(200)));
+    }
+
+    #[test]
+    fn peer_is_snubbed_until_it_delivers_a_block_ok() {
+        let now = Instant::now();
+        let mut connection_health = create_connection_health(now);
+        connection_health.block_requested_at(now);
+        connection_health.block_requested_at(now + Duration::from_secs(30));
+
+        assert!(!connection_health.check_snubbed_at(now + Duration::from_secs(59), true));
+        assert!(connection_health.check_snubbed_at(now + Duration::from_secs(60), true));
+        assert!(connection_health.is_snubbed());
+        assert_eq!(
+            SNUBBED_REQUEST_QUEUE_DEPTH,
+            connection_health.get_request_queue_depth(10)
+        );
+
+        connection_health.block_received_at(now + Duration::from_secs(70), false);
+        assert!(!connection_health.is_snubbed());
+        assert_eq!(10, connection_health.get_request_queue_depth(10));
+        assert!(!connection_health.check_snubbed_at(now + Duration::from_secs(500), false));
+    }
+
+    #[test]
+    fn cancelled_requests_are_taken_only_once_ok() {
+        let mut connection_health = ConnectionHealth::default();
+        connection_health.set_cancelled_requests(vec![BlockRequest {
+            piece_index: 1,
+            beginning_byte_index: 0,
+            amount_of_bytes: 16384,
+        }]);
+
+        assert!(!connection_health.take_cancelled_request(1, 16384));
+        assert!(connection_health.take_cancelled_request(1, 0));
+        assert!(!connection_health.take_cancelled_request(1, 0));
+    }
+}
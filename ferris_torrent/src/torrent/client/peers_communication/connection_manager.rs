@@ -0,0 +1,558 @@
+//! # Modulo de manejo de conexiones
+//! Este modulo contiene al ConnectionManager, encargado de decidir a que peers externos
+//! conectarse (a partir de los peers dados por el tracker), respetando los limites de
+//! conexiones simultaneas y esperando cada vez mas entre reintentos a los peers que fallan.
+//! Ademas de los peers del tracker, al pool se agregan los peers recibidos por peer exchange.
+//! El pool tiene un tamaño maximo y nunca incluye a nuestro propio cliente.
+//!
+
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::torrent::data::tracker_response_data::{
+    PeerDataFromTrackerResponse, TrackerResponseData,
+};
+
+/// Tiempo de espera base antes de volver a intentar conectarse a un peer
+pub const SECS_BASE_BACKOFF: u64 = 30;
+/// Cantidad de fallos consecutivos luego de los cuales se descarta un peer
+pub const MAX_AMOUNT_OF_FAILURES: u32 = 5;
+/// Cantidad maxima de peers en el pool por cada conexion simultanea permitida
+pub const POOL_SIZE_PER_MAX_PEER: usize = 10;
+
+/// Estado de un peer candidato dentro del pool de conexiones
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum CandidateState {
+    Available,
+    Connecting,
+    Connected,
+    Discarded,
+}
+
+/// Peer dado por el tracker al cual se puede intentar conectar
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PeerCandidate {
+    pub tracker_response_peer_index: usize,
+    pub state: CandidateState,
+    pub amount_of_failures: u32,
+    pub retry_at: Option<Instant>,
+}
+
+/// Struct compartido por los threads de un torrent que administra el pool de peers candidatos
+/// y la cantidad de conexiones activas (completas y a medio abrir).
+/// El pool de peers se comparte con los threads de interaccion sin copiarlo, y solo se copia
+/// al agregarle peers mientras alguno de ellos lo sigue usando.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ConnectionManager {
+    pub peer_pool: Arc<TrackerResponseData>,
+    pub candidates: Vec<PeerCandidate>,
+    pub amount_of_incoming_peers: usize,
+    pub max_peers: usize,
+    pub max_half_open: usize,
+    pub own_peer_id: Option<Vec<u8>>,
+    pub own_listen_port: Option<u16>,
+}
+
+///
+/// Funcion que calcula cuanto esperar antes de reintentar una conexion con un peer,
+/// duplicando el tiempo base por cada fallo consecutivo.
+///
+fn calculate_backoff(amount_of_failures: u32) -> Duration {
+    let exponent = amount_of_failures
+        .saturating_sub(1)
+        .min(MAX_AMOUNT_OF_FAILURES);
+    Duration::from_secs(SECS_BASE_BACKOFF * 2_u64.pow(exponent))
+}
+
+///
+/// Funcion que indica si un peer corresponde a nuestro propio cliente: tiene nuestro peer id,
+/// o nuestro puerto de escucha en una direccion local.
+///
+fn is_own_peer(
+    peer: &PeerDataFromTrackerResponse,
+    own_peer_id: Option<&[u8]>,
+    own_listen_port: Option<u16>,
+) -> bool {
+    let has_own_peer_id = own_peer_id.is_some_and(|own_peer_id| {
+        peer.peer_id
+            .as_deref()
+            .is_some_and(|peer_id| peer_id == own_peer_id)
+    });
+    let has_own_address = own_listen_port == Some(peer.peer_address.port())
+        && (peer.peer_address.ip().is_loopback() || peer.peer_address.ip().is_unspecified());
+    has_own_peer_id || has_own_address
+}
+
+fn create_candidate(tracker_response_peer_index: usize) -> PeerCandidate {
+    PeerCandidate {
+        tracker_response_peer_index,
+        state: CandidateState::Available,
+        amount_of_failures: 0,
+        retry_at: None,
+    }
+}
+
+impl ConnectionManager {
+    /// Funcion que crea el pool de candidatos a partir de los peers de una respuesta de tracker
+    ///
+    pub fn new(
+        tracker_response: &TrackerResponseData,
+        max_peers: usize,
+        max_half_open: usize,
+    ) -> Self {
+        let mut peer_pool = tracker_response.clone();
+        peer_pool
+            .peers
+            .truncate(max_peers.saturating_mul(POOL_SIZE_PER_MAX_PEER));
+        let candidates = (0..peer_pool.get_total_amount_peers())
+            .map(create_candidate)
+            .collect();
+        ConnectionManager {
+            peer_pool: Arc::new(peer_pool),
+            candidates,
+            amount_of_incoming_peers: 0,
+            max_peers,
+            max_half_open,
+            own_peer_id: None,
+            own_listen_port: None,
+        }
+    }
+
+    ///
+    /// Funcion que registra el peer id y el puerto de escucha de nuestro cliente, para no
+    /// intentar conectarnos con nosotros mismos. Los peers del pool que nos corresponden
+    /// se descartan.
+    ///
+    pub fn with_own_peer(mut self, own_peer_id: &[u8], own_listen_port: Option<u16>) -> Self {
+        self.own_peer_id = Some(own_peer_id.to_vec());
+        self.own_listen_port = own_listen_port;
+        for (candidate, peer) in self.candidates.iter_mut().zip(&self.peer_pool.peers) {
+            let is_own_peer = is_own_peer(peer, self.own_peer_id.as_deref(), self.own_listen_port);
+            if is_own_peer {
+                candidate.state = CandidateState::Discarded;
+            }
+        }
+        self
+    }
+
+    /// Funcion que devuelve la cantidad maxima de peers que puede tener el pool
+    ///
+    pub fn get_max_pool_size(&self) -> usize {
+        self.max_peers.saturating_mul(POOL_SIZE_PER_MAX_PEER)
+    }
+
+    /// Funcion que devuelve el pool de peers, cuyos indices coinciden con los de los candidatos
+    ///
+    pub fn get_peer_pool(&self) -> Arc<TrackerResponseData> {
+        self.peer_pool.clone()
+    }
+
+    ///
+    /// Funcion que agrega al pool los peers recibidos en un nuevo anuncio al tracker que todavia
+    /// no se conocian (segun su direccion), ignorando a nuestro propio cliente. Una vez que el
+    /// pool alcanza su tamaño maximo, los peers nuevos solo ocupan el lugar de peers descartados.
+    /// Devuelve la cantidad de peers agregados.
+    ///
+    pub fn add_peers(&mut self, peers: &[PeerDataFromTrackerResponse]) -> usize {
+        let max_pool_size = self.get_max_pool_size();
+        let peer_pool = Arc::make_mut(&mut self.peer_pool);
+        let mut amount_of_new_peers = 0;
+        for peer in peers {
+            let is_known = peer_pool
+                .peers
+                .iter()
+                .any(|known_peer| known_peer.peer_address == peer.peer_address);
+            if is_known || is_own_peer(peer, self.own_peer_id.as_deref(), self.own_listen_port) {
+                continue;
+            }
+            if peer_pool.get_total_amount_peers() < max_pool_size {
+                self.candidates
+                    .push(create_candidate(peer_pool.get_total_amount_peers()));
+                peer_pool.peers.push(peer.clone());
+            } else {
+                let discarded_candidate = self
+                    .candidates
+                    .iter_mut()
+                    .find(|candidate| candidate.state == CandidateState::Discarded);
+                let candidate = match discarded_candidate {
+                    Some(candidate) => candidate,
+                    None => break,
+                };
+                match peer_pool
+                    .peers
+                    .get_mut(candidate.tracker_response_peer_index)
+                {
+                    Some(pool_peer) => *pool_peer = peer.clone(),
+                    None => continue,
+                }
+                *candidate = create_candidate(candidate.tracker_response_peer_index);
+            }
+            amount_of_new_peers += 1;
+        }
+        amount_of_new_peers
+    }
+
+    fn count_candidates_in_state(&self, state: &CandidateState) -> usize {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.state == *state)
+            .count()
+    }
+
+    /// Funcion que devuelve la cantidad de conexiones a medio abrir (sin handshake completo)
+    ///
+    pub fn get_amount_of_half_open_connections(&self) -> usize {
+        self.count_candidates_in_state(&CandidateState::Connecting)
+    }
+
+    /// Funcion que devuelve la cantidad total de conexiones activas, salientes y entrantes
+    ///
+    pub fn get_amount_of_active_connections(&self) -> usize {
+        self.count_candidates_in_state(&CandidateState::Connected)
+            + self.get_amount_of_half_open_connections()
+            + self.amount_of_incoming_peers
+    }
+
+    ///
+    /// Funcion que elige el proximo peer al cual conectarse, si los limites de conexiones lo
+    /// permiten y hay algun candidato cuyo tiempo de espera ya se cumplio. El candidato elegido
+    /// pasa a contar como conexion a medio abrir y se devuelve su indice en la respuesta del tracker.
+    ///
+    pub fn take_next_candidate(&mut self, now: Instant) -> Option<usize> {
+        if self.get_amount_of_active_connections() >= self.max_peers
+            || self.get_amount_of_half_open_connections() >= self.max_half_open
+        {
+            return None;
+        }
+        let candidate = self.candidates.iter_mut().find(|candidate| {
+            candidate.state == CandidateState::Available
+                && candidate.retry_at.is_none_or(|retry_at| retry_at <= now)
+        })?;
+        candidate.state = CandidateState::Connecting;
+        Some(candidate.tracker_response_peer_index)
+    }
+
+    fn get_candidate_mut(
+        &mut self,
+        tracker_response_peer_index: usize,
+    ) -> Option<&mut PeerCandidate> {
+        self.candidates
+            .iter_mut()
+            .find(|candidate| candidate.tracker_response_peer_index == tracker_response_peer_index)
+    }
+
+    /// Funcion que registra que se completo el handshake con un peer candidato
+    ///
+    pub fn connection_established(&mut self, tracker_response_peer_index: usize) {
+        if let Some(candidate) = self.get_candidate_mut(tracker_response_peer_index) {
+            candidate.state = CandidateState::Connected;
+            candidate.amount_of_failures = 0;
+        }
+    }
+
+    ///
+    /// Funcion que registra que fallo la conexion (o la interaccion) con un peer candidato.
+    /// Se lo vuelve a intentar luego de un tiempo de espera exponencial, y se lo descarta
+    /// si falla [MAX_AMOUNT_OF_FAILURES] veces seguidas.
+    ///
+    pub fn connection_failed(&mut self, tracker_response_peer_index: usize, now: Instant) {
+        if let Some(candidate) = self.get_candidate_mut(tracker_response_peer_index) {
+            candidate.amount_of_failures += 1;
+            if candidate.amount_of_failures >= MAX_AMOUNT_OF_FAILURES {
+                candidate.state = CandidateState::Discarded;
+                return;
+            }
+            candidate.state = CandidateState::Available;
+            candidate.retry_at = Some(now + calculate_backoff(candidate.amount_of_failures));
+        }
+    }
+
+    ///
+    /// Funcion que registra que finalizo sin errores la interaccion con un peer candidato
+    /// (por ejemplo, porque no tenia mas piezas para darnos). Se lo puede volver a intentar
+    /// luego del tiempo de espera base.
+    ///
+    pub fn connection_closed(&mut self, tracker_response_peer_index: usize, now: Instant) {
+        if let Some(candidate) = self.get_candidate_mut(tracker_response_peer_index) {
+            candidate.state = CandidateState::Available;
+            candidate.retry_at = Some(now + Duration::from_secs(SECS_BASE_BACKOFF));
+        }
+    }
+
+    /// Funcion que registra una conexion entrante si todavia no se alcanzo el limite de peers
+    ///
+    pub fn try_to_add_incoming_peer(&mut self) -> bool {
+        if self.get_amount_of_active_connections() >= self.max_peers {
+            return false;
+        }
+        self.amount_of_incoming_peers += 1;
+        true
+    }
+
+    /// Funcion que registra que finalizo una conexion entrante
+    ///
+    pub fn remove_incoming_peer(&mut self) {
+        self.amount_of_incoming_peers = self.amount_of_incoming_peers.saturating_sub(1);
+    }
+
+    /// Funcion que devuelve las direcciones de los peers salientes con los que se esta interactuando
+    ///
+    pub fn get_connected_peer_addresses(&self) -> Vec<SocketAddr> {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.state == CandidateState::Connected)
+            .filter_map(|candidate| {
+                self.peer_pool
+                    .get_peer_address(candidate.tracker_response_peer_index)
+            })
+            .collect()
+    }
+
+    ///
+    /// Funcion que indica si todavia hay peers salientes con los cuales se esta interactuando
+    /// o a los cuales se puede intentar conectar en el futuro.
+    ///
+    pub fn has_outgoing_peers_left(&self) -> bool {
+        self.candidates
+            .iter()
+            .any(|candidate| candidate.state != CandidateState::Discarded)
+    }
+}
+
+#[cfg(test)]
+mod test_connection_manager {
+    use super::*;
+    use std::{net::SocketAddr, str::FromStr};
+
+    fn create_connection_manager(
+        amount_of_peers: u16,
+        max_peers: usize,
+        max_half_open: usize,
+    ) -> ConnectionManager {
+        let peers = (0..amount_of_peers)
+            .filter_map(|port| {
+                SocketAddr::from_str(&format!("127.0.0.1:{}", 8000 + port))
+                    .ok()
+                    .map(|peer_address| PeerDataFromTrackerResponse {
+                        peer_id: None,
+                        peer_address,
+                    })
+            })
+            .collect();
+        let tracker_response = TrackerResponseData {
+            interval: 0,
+            min_interval: None,
+            tracker_id: None,
+            complete: 0,
+            incomplete: 0,
+            peers,
+        };
+        ConnectionManager::new(&tracker_response, max_peers, max_half_open)
+    }
+
+    mod test_take_next_candidate {
+        use super::*;
+
+        #[test]
+        fn the_amount_of_half_open_connections_is_limited_ok() {
+            let mut connection_manager = create_connection_manager(5, 10, 2);
+            let now = Instant::now();
+
+            assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+            assert_eq!(Some(1), connection_manager.take_next_candidate(now));
+            assert_eq!(None, connection_manager.take_next_candidate(now));
+
+            connection_manager.connection_established(0);
+            assert_eq!(Some(2), connection_manager.take_next_candidate(now));
+        }
+
+        #[test]
+        fn the_amount_of_active_connections_is_limited_ok() {
+            let mut connection_manager = create_connection_manager(5, 2, 2);
+            let now = Instant::now();
+
+            assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+            connection_manager.connection_established(0);
+            assert!(connection_manager.try_to_add_incoming_peer());
+
+            assert_eq!(None, connection_manager.take_next_candidate(now));
+            assert!(!connection_manager.try_to_add_incoming_peer());
+
+            connection_manager.remove_incoming_peer();
+            assert_eq!(Some(1), connection_manager.take_next_candidate(now));
+        }
+    }
+
+    mod test_add_peers {
+        use super::*;
+
+        #[test]
+        fn only_unknown_peers_are_added_to_the_pool_ok() -> Result<(), Box<dyn std::error::Error>> {
+            let mut connection_manager = create_connection_manager(2, 10, 10);
+            let now = Instant::now();
+            let new_peers = vec![
+                PeerDataFromTrackerResponse {
+                    peer_id: None,
+                    peer_address: SocketAddr::from_str("127.0.0.1:8001")?,
+                },
+                PeerDataFromTrackerResponse {
+                    peer_id: None,
+                    peer_address: SocketAddr::from_str("127.0.0.1:9000")?,
+                },
+            ];
+
+            assert_eq!(1, connection_manager.add_peers(&new_peers));
+
+            assert_eq!(
+                3,
+                connection_manager.get_peer_pool().get_total_amount_peers()
+            );
+            assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+            assert_eq!(Some(1), connection_manager.take_next_candidate(now));
+            assert_eq!(Some(2), connection_manager.take_next_candidate(now));
+            assert_eq!(
+                Some(new_peers[1].peer_address),
+                connection_manager.get_peer_pool().get_peer_address(2)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn the_pool_size_is_limited_ok() -> Result<(), Box<dyn std::error::Error>> {
+            let max_peers = 1;
+            let mut connection_manager = create_connection_manager(0, max_peers, 1);
+            let now = Instant::now();
+            let new_peers: Vec<PeerDataFromTrackerResponse> = (0..=POOL_SIZE_PER_MAX_PEER)
+                .filter_map(|port| {
+                    SocketAddr::from_str(&format!("127.0.0.1:{}", 9000 + port))
+                        .ok()
+                        .map(|peer_address| PeerDataFromTrackerResponse {
+                            peer_id: None,
+                            peer_address,
+                        })
+                })
+                .collect();
+
+            assert_eq!(
+                POOL_SIZE_PER_MAX_PEER,
+                connection_manager.add_peers(&new_peers)
+            );
+            assert_eq!(
+                POOL_SIZE_PER_MAX_PEER,
+                connection_manager.get_peer_pool().get_total_amount_peers()
+            );
+
+            for _ in 0..MAX_AMOUNT_OF_FAILURES {
+                connection_manager.candidates[0].retry_at = None;
+                assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+                connection_manager.connection_failed(0, now);
+            }
+            assert_eq!(1, connection_manager.add_peers(&new_peers));
+            assert_eq!(
+                POOL_SIZE_PER_MAX_PEER,
+                connection_manager.get_peer_pool().get_total_amount_peers()
+            );
+            assert_eq!(
+                Some(new_peers[POOL_SIZE_PER_MAX_PEER].peer_address),
+                connection_manager.get_peer_pool().get_peer_address(0)
+            );
+            assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+            Ok(())
+        }
+
+        #[test]
+        fn our_own_peer_is_not_added_to_the_pool_ok() -> Result<(), Box<dyn std::error::Error>> {
+            let own_peer_id = b"-FA0001-000000000000".to_vec();
+            let mut connection_manager =
+                create_connection_manager(2, 10, 10).with_own_peer(&own_peer_id, Some(8000));
+            let now = Instant::now();
+            let new_peers = vec![
+                PeerDataFromTrackerResponse {
+                    peer_id: Some(own_peer_id),
+                    peer_address: SocketAddr::from_str("10.0.0.1:6881")?,
+                },
+                PeerDataFromTrackerResponse {
+                    peer_id: None,
+                    peer_address: SocketAddr::from_str("0.0.0.0:8000")?,
+                },
+                PeerDataFromTrackerResponse {
+                    peer_id: None,
+                    peer_address: SocketAddr::from_str("10.0.0.1:8000")?,
+                },
+            ];
+
+            assert_eq!(1, connection_manager.add_peers(&new_peers));
+            assert_eq!(Some(1), connection_manager.take_next_candidate(now));
+            assert_eq!(Some(2), connection_manager.take_next_candidate(now));
+            assert_eq!(None, connection_manager.take_next_candidate(now));
+            Ok(())
+        }
+
+        #[test]
+        fn only_connected_peers_are_listed_ok() -> Result<(), Box<dyn std::error::Error>> {
+            let mut connection_manager = create_connection_manager(3, 10, 10);
+            let now = Instant::now();
+
+            assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+            assert_eq!(Some(1), connection_manager.take_next_candidate(now));
+            connection_manager.connection_established(1);
+
+            assert_eq!(
+                vec![SocketAddr::from_str("127.0.0.1:8001")?],
+                connection_manager.get_connected_peer_addresses()
+            );
+            Ok(())
+        }
+    }
+
+    mod test_backoff {
+        use super::*;
+
+        #[test]
+        fn a_failed_peer_is_retried_after_the_backoff_ok() {
+            let mut connection_manager = create_connection_manager(1, 10, 2);
+            let now = Instant::now();
+
+            assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+            connection_manager.connection_failed(0, now);
+
+            assert_eq!(None, connection_manager.take_next_candidate(now));
+            let after_backoff = now + Duration::from_secs(SECS_BASE_BACKOFF);
+            assert_eq!(
+                Some(0),
+                connection_manager.take_next_candidate(after_backoff)
+            );
+        }
+
+        #[test]
+        fn the_backoff_doubles_with_each_failure_ok() {
+            assert_eq!(Duration::from_secs(SECS_BASE_BACKOFF), calculate_backoff(1));
+            assert_eq!(
+                Duration::from_secs(SECS_BASE_BACKOFF * 2),
+                calculate_backoff(2)
+            );
+            assert_eq!(
+                Duration::from_secs(SECS_BASE_BACKOFF * 4),
+                calculate_backoff(3)
+            );
+        }
+
+        #[test]
+        fn a_peer_that_keeps_failing_is_discarded_ok() {
+            let mut connection_manager = create_connection_manager(1, 10, 2);
+            let mut now = Instant::now();
+
+            for _ in 0..MAX_AMOUNT_OF_FAILURES {
+                assert_eq!(Some(0), connection_manager.take_next_candidate(now));
+                connection_manager.connection_failed(0, now);
+                now += Duration::from_secs(SECS_BASE_BACKOFF * 2_u64.pow(MAX_AMOUNT_OF_FAILURES));
+            }
+
+            assert_eq!(None, connection_manager.take_next_candidate(now));
+            assert!(!connection_manager.has_outgoing_peers_left());
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! # Modulo de manejo del extension protocol (BEP 10)
+//! Este modulo contiene el registro de las extensiones que soporta el cliente en la comunicacion con
+//! un peer externo. Cada extension implementa el trait ExtensionHandler y se registra en el
+//! ExtensionRegistry del LocalPeerCommunicator, el cual se encarga de negociar los ids de cada una
+//! mediante el handshake extendido y de derivarle los mensajes Extended que le correspondan.
+//!
+
+use log::debug;
+use shared::parsers::p2p::{
+    constants::ID_EXTENDED_HANDSHAKE,
+    extension::{ExtendedHandshake, ExtensionMessageError},
+};
+use std::{error::Error, fmt};
+
+/// Nombre del cliente que se informa en el handshake extendido
+pub const CLIENT_NAME: &str = "FerrisTorrent";
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa un tipo de error en el manejo de los mensajes del extension protocol
+pub enum ExtensionHandlerError {
+    DecodingExtendedHandshake(ExtensionMessageError),
+    HandlingMessage(String),
+    TooManyExtensions,
+}
+
+impl fmt::Display for ExtensionHandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for ExtensionHandlerError {}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa un mensaje Extended a enviar al peer externo, con el id que dicho peer
+/// asocio a la extension en su handshake extendido
+pub struct ExtendedMsgToSend {
+    pub ext_id: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Comportamiento de una extension del protocolo (por ejemplo ut_metadata o ut_pex).
+/// Se crea una instancia por cada conexion con un peer externo.
+pub trait ExtensionHandler: fmt::Debug + Send {
+    /// Nombre con el que se anuncia la extension en el diccionario m del handshake extendido
+    fn get_name(&self) -> &str;
+
+    ///
+    /// Funcion que permite agregar al handshake extendido los campos propios de la extension
+    /// (por ejemplo, el metadata_size de ut_metadata).
+    ///
+    fn extend_handshake(&self, _handshake: &mut ExtendedHandshake) {}
+
+    ///
+    /// Funcion que se llama al recibir el handshake extendido del peer externo, solo si este
+    /// soporta la extension.
+    ///
+    fn on_extended_handshake(
+        &mut self,
+        _handshake: &ExtendedHandshake,
+    ) -> Result<(), ExtensionHandlerError> {
+        Ok(())
+    }
+
+    ///
+    /// Funcion que interpreta el payload de un mensaje recibido de la extension y devuelve los
+    /// payloads a enviar como respuesta.
+    ///
+    fn handle_message(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>, ExtensionHandlerError>;
+
+    ///
+    /// Funcion que devuelve los payloads que la extension quiere enviar por iniciativa propia
+    /// (por ejemplo, de forma periodica). Solo se llama si el peer externo soporta la extension.
+    ///
+    fn poll_messages(&mut self) -> Result<Vec<Vec<u8>>, ExtensionHandlerError> {
+        Ok(vec![])
+    }
+}
+
+#[derive(Debug, Default)]
+/// Registro de las extensiones soportadas en la comunicacion con un peer externo.
+/// Cada extension se recibe con el id igual a su posicion en el registro mas uno, ya que el
+/// id 0 esta reservado para el handshake extendido.
+pub struct ExtensionRegistry {
+    handlers: Vec<Box<dyn ExtensionHandler>>,
+    peer_supports_extensions: bool,
+    peer_handshake: Option<ExtendedHandshake>,
+}
+
+impl ExtensionRegistry {
+    pub fn new() -> Self {
+        ExtensionRegistry::default()
+    }
+
+    ///
+    /// Funcion que agrega una extension al registro. Debe llamarse antes de enviar el
+    /// handshake extendido para que la extension sea anunciada al peer externo.
+    ///
+    pub fn register(
+        &mut self,
+        handler: Box<dyn ExtensionHandler>,
+    ) -> Result<(), ExtensionHandlerError> {
+        if self.handlers.len() >= usize::from(u8::MAX) {
+            return Err(ExtensionHandlerError::TooManyExtensions);
+        }
+        self.handlers.push(handler);
+        Ok(())
+    }
+
+    /// Indica si el peer externo anuncio en su handshake que soporta el extension protocol
+    pub fn set_peer_supports_extensions(&mut self, peer_supports_extensions: bool) {
+        self.peer_supports_extensions = peer_supports_extensions;
+    }
+
+    pub fn peer_supports_extensions(&self) -> bool {
+        self.peer_supports_extensions
+    }
+
+    /// Devuelve el ultimo handshake extendido recibido del peer externo
+    pub fn get_peer_handshake(&self) -> Option<&ExtendedHandshake> {
+        self.peer_handshake.as_ref()
+    }
+
+    ///
+    /// Funcion que arma el handshake extendido a enviar al peer externo, con todas las
+    /// extensiones registradas.
+    ///
+    pub fn create_extended_handshake(&self) -> ExtendedHandshake {
+        let mut handshake = ExtendedHandshake {
+            client: Some(CLIENT_NAME.to_string()),
+            ..ExtendedHandshake::default()
+        };
+        for (local_ext_id, handler) in (1..=u8::MAX).zip(self.handlers.iter()) {
+            handshake
+                .extensions
+                .insert(handler.get_name().to_string(), local_ext_id);
+            handler.extend_handshake(&mut handshake);
+        }
+        handshake
+    }
+
+    fn get_peer_extension_id(&self, extension_name: &str) -> Option<u8> {
+        self.peer_handshake
+            .as_ref()
+            .and_then(|handshake| handshake.get_extension_id(extension_name))
+    }
+
+    fn to_msgs_to_send(
+        &self,
+        extension_name: &str,
+        payloads: Vec<Vec<u8>>,
+    ) -> Vec<ExtendedMsgToSend> {
+        match self.get_peer_extension_id(extension_name) {
+            Some(ext_id) => payloads
+                .into_iter()
+                .map(|payload| ExtendedMsgToSend { ext_id, payload })
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn handle_extended_handshake(&mut self, payload: &[u8]) -> Result<(), ExtensionHandlerError> {
+        let peer_handshake = ExtendedHandshake::from_payload(payload)
+            .map_err(ExtensionHandlerError::DecodingExtendedHandshake)?;
+        for handler in self.handlers.iter_mut() {
+            if peer_handshake
+                .get_extension_id(handler.get_name())
+                .is_some()
+            {
+                handler.on_extended_handshake(&peer_handshake)?;
+            }
+        }
+        self.peer_handshake = Some(peer_handshake);
+        Ok(())
+    }
+
+    ///
+    /// Funcion que deriva un mensaje Extended recibido a la extension correspondiente y
+    /// devuelve los mensajes que se deben enviar como respuesta.
+    /// Los mensajes de extensiones desconocidas se ignoran.
+    ///
+    pub fn handle_extended_msg(
+        &mut self,
+        ext_id: u8,
+        payload: &[u8],
+    ) -> Result<Vec<ExtendedMsgToSend>, ExtensionHandlerError> {
+        if ext_id == ID_EXTENDED_HANDSHAKE {
+            self.handle_extended_handshake(payload)?;
+            return Ok(vec![]);
+        }
+        let handler = match self.handlers.get_mut(usize::from(ext_id) - 1) {
+            Some(handler) => handler,
+            None => {
+                debug!(
+                    "Se ignora un mensaje Extended con id desconocido: {}",
+                    ext_id
+                );
+                return Ok(vec![]);
+            }
+        };
+        let payloads = handler.handle_message(payload)?;
+        let extension_name = handler.get_name().to_string();
+        Ok(self.to_msgs_to_send(&extension_name, payloads))
+    }
+
+    ///
+    /// Funcion que junta los mensajes que las extensiones soportadas por el peer externo
+    /// quieren enviar por iniciativa propia.
+    ///
+    pub fn poll_messages(&mut self) -> Result<Vec<ExtendedMsgToSend>, ExtensionHandlerError> {
+        let mut msgs_to_send = vec![];
+        for index in 0..self.handlers.len() {
+            let extension_name = self.handlers[index].get_name().to_string();
+            if self.get_peer_extension_id(&extension_name).is_none() {
+                continue;
+            }
+            let payloads = self.handlers[index].poll_messages()?;
+            msgs_to_send.append(&mut self.to_msgs_to_send(&extension_name, payloads));
+        }
+        Ok(msgs_to_send)
+    }
+}
+
+#[cfg(test)]
+mod test_extensions {
+    use super::*;
+    use std::collections::HashMap;
+
+    const ECHO: &str = "echo";
+
+    /// Extension de prueba que responde cada mensaje con el mismo payload y que, una vez
+    /// recibido el handshake extendido, envia un saludo.
+    #[derive(Debug, Default)]
+    struct EchoExtension {
+        handshake_received: bool,
+    }
+
+    impl ExtensionHandler for EchoExtension {
+        fn get_name(&self) -> &str {
+            ECHO
+        }
+
+        fn extend_handshake(&self, handshake: &mut ExtendedHandshake) {
+            handshake.metadata_size = Some(10);
+        }
+
+        fn on_extended_handshake(
+            &mut self,
+            _handshake: &ExtendedHandshake,
+        ) -> Result<(), ExtensionHandlerError> {
+            self.handshake_received = true;
+            Ok(())
+        }
+
+        fn handle_message(
+            &mut self,
+            payload: &[u8],
+        ) -> Result<Vec<Vec<u8>>, ExtensionHandlerError> {
+            Ok(vec![payload.to_vec()])
+        }
+
+        fn poll_messages(&mut self) -> Result<Vec<Vec<u8>>, ExtensionHandlerError> {
+            if self.handshake_received {
+                self.handshake_received = false;
+                return Ok(vec![b"hello".to_vec()]);
+            }
+            Ok(vec![])
+        }
+    }
+
+    fn create_registry_with_echo() -> Result<ExtensionRegistry, ExtensionHandlerError> {
+        let mut registry = ExtensionRegistry::new();
+        registry.register(Box::new(EchoExtension::default()))?;
+        Ok(registry)
+    }
+
+    fn peer_handshake_payload(extensions: &[(&str, u8)]) -> Vec<u8> {
+        ExtendedHandshake {
+            extensions: extensions
+                .iter()
+                .map(|(name, ext_id)| (name.to_string(), *ext_id))
+                .collect(),
+            ..ExtendedHandshake::default()
+        }
+        .to_payload()
+    }
+
+    #[test]
+    fn extended_handshake_announces_registered_extensions_ok() -> Result<(), ExtensionHandlerError>
+    {
+        let registry = create_registry_with_echo()?;
+
+        let handshake = registry.create_extended_handshake();
+
+        assert_eq!(HashMap::from([(ECHO.to_string(), 1)]), handshake.extensions);
+        assert_eq!(Some(10), handshake.metadata_size);
+        assert_eq!(Some(CLIENT_NAME.to_string()), handshake.client);
+        Ok(())
+    }
+
+    #[test]
+    fn messages_are_answered_with_the_id_of_the_peer_ok() -> Result<(), ExtensionHandlerError> {
+        let mut registry = create_registry_with_echo()?;
+
+        let peer_handshake = peer_handshake_payload(&[(ECHO, 7)]);
+        assert!(registry
+            .handle_extended_msg(ID_EXTENDED_HANDSHAKE, &peer_handshake)?
+            .is_empty());
+
+        assert_eq!(
+            vec![ExtendedMsgToSend {
+                ext_id: 7,
+                payload: b"ping".to_vec()
+            }],
+            registry.handle_extended_msg(1, b"ping")?
+        );
+        assert_eq!(
+            vec![ExtendedMsgToSend {
+                ext_id: 7,
+                payload: b"hello".to_vec()
+            }],
+            registry.poll_messages()?
+        );
+        assert!(registry.poll_messages()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn nothing_is_sent_to_a_peer_without_the_extension_ok() -> Result<(), ExtensionHandlerError> {
+        let mut registry = create_registry_with_echo()?;
+
+        assert!(registry.handle_extended_msg(1, b"ping")?.is_empty());
+
+        let peer_handshake = peer_handshake_payload(&[("other", 2), (ECHO, 0)]);
+        registry.handle_extended_msg(ID_EXTENDED_HANDSHAKE, &peer_handshake)?;
+
+        assert!(registry.handle_extended_msg(1, b"ping")?.is_empty());
+        assert!(registry.poll_messages()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_extension_ids_are_ignored_ok() -> Result<(), ExtensionHandlerError> {
+        let mut registry = create_registry_with_echo()?;
+
+        assert!(registry.handle_extended_msg(9, b"ping")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_extended_handshake_error() -> Result<(), ExtensionHandlerError> {
+        let mut registry = create_registry_with_echo()?;
+
+        assert!(matches!(
+            registry.handle_extended_msg(ID_EXTENDED_HANDSHAKE, b"not bencoding"),
+            Err(ExtensionHandlerError::DecodingExtendedHandshake(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+//! # Modulo de fast extension
+//! Este modulo contiene el estado de la fast extension (BEP 6) para la comunicacion con un
+//! peer externo: si la negocio en el handshake, que piezas puede pedirnos aunque lo tengamos
+//! chokeado (allowed fast set), que piezas podemos pedirle nosotros estando chokeados y que
+//! piezas nos sugirio descargar.
+//!
+
+use sha1::{Digest, Sha1};
+use std::{collections::HashSet, net::IpAddr};
+
+/// Cantidad de piezas del allowed fast set que se le otorga a cada peer externo
+pub const ALLOWED_FAST_SET_SIZE: usize = 10;
+/// Cantidad maxima de piezas sugeridas por el peer externo que se recuerdan
+pub const MAX_SUGGESTED_PIECES: usize = 10;
+/// Cantidad maxima de piezas del allowed fast set otorgado por el peer externo que se recuerdan
+pub const MAX_ALLOWED_FAST_FROM_PEER: usize = 32;
+
+const IPV4_MASK: u32 = 0xFFFFFF00;
+const SHA1_LEN: usize = 20;
+const BYTES_PER_INDEX: usize = 4;
+
+/// Estado de la fast extension para la comunicacion con un peer externo.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct FastExtension {
+    peer_supports_fast: bool,
+    allowed_fast_for_peer: HashSet<usize>,
+    allowed_fast_from_peer: HashSet<usize>,
+    suggested_pieces: Vec<usize>,
+}
+
+///
+/// Funcion que genera el allowed fast set que le corresponde a un peer externo segun el
+/// algoritmo canonico de la BEP 6, a partir de su ip y del info hash del torrent.
+/// Solo esta definido para direcciones IPv4, por lo que para IPv6 devuelve un set vacio.
+///
+pub fn generate_allowed_fast_set(
+    external_peer_ip: IpAddr,
+    info_hash: &[u8],
+    total_amount_pieces: usize,
+    set_size: usize,
+) -> Vec<usize> {
+    let ip = match external_peer_ip {
+        IpAddr::V4(ip) => u32::from(ip) & IPV4_MASK,
+        IpAddr::V6(_) => return vec![],
+    };
+    let set_size = set_size.min(total_amount_pieces);
+    let mut allowed_fast_set = Vec::with_capacity(set_size);
+
+    let mut hash = ip.to_be_bytes().to_vec();
+    hash.extend_from_slice(info_hash);
+    while allowed_fast_set.len() < set_size {
+        hash = Sha1::digest(&hash).to_vec();
+        for index_bytes in hash[..SHA1_LEN].chunks_exact(BYTES_PER_INDEX) {
+            if allowed_fast_set.len() >= set_size {
+                break;
+            }
+            let mut bytes = [0; BYTES_PER_INDEX];
+            bytes.copy_from_slice(index_bytes);
+            let piece_index = u32::from_be_bytes(bytes) as usize % total_amount_pieces;
+            if !allowed_fast_set.contains(&piece_index) {
+                allowed_fast_set.push(piece_index);
+            }
+        }
+    }
+    allowed_fast_set
+}
+
+impl FastExtension {
+    pub fn new(peer_supports_fast: bool) -> Self {
+        FastExtension {
+            peer_supports_fast,
+            ..Default::default()
+        }
+    }
+
+    /// Funcion que indica si el peer externo anuncio en su handshake que soporta la fast extension
+    ///
+    pub fn peer_supports_fast(&self) -> bool {
+        self.peer_supports_fast
+    }
+
+    /// Funcion que registra una pieza que el peer externo puede pedirnos aunque lo tengamos chokeado
+    ///
+    pub fn add_allowed_fast_for_peer(&mut self, piece_index: usize) {
+        self.allowed_fast_for_peer.insert(piece_index);
+    }
+
+    pub fn is_allowed_fast_for_peer(&self, piece_index: usize) -> bool {
+        self.allowed_fast_for_peer.contains(&piece_index)
+    }
+
+    /// Funcion que registra una pieza que podemos pedirle al peer externo aunque nos tenga chokeados.
+    /// Si ya se recuerdan demasiadas, la pieza se ignora.
+    ///
+    pub fn add_allowed_fast_from_peer(&mut self, piece_index: usize) {
+        if self.allowed_fast_from_peer.len() >= MAX_ALLOWED_FAST_FROM_PEER {
+            return;
+        }
+        self.allowed_fast_from_peer.insert(piece_index);
+    }
+
+    pub fn is_allowed_fast_from_peer(&self, piece_index: usize) -> bool {
+        self.allowed_fast_from_peer.contains(&piece_index)
+    }
+
+    /// Funcion que devuelve, ordenadas, las piezas que podemos pedirle al peer externo
+    /// aunque nos tenga chokeados
+    ///
+    pub fn get_allowed_fast_from_peer(&self) -> Vec<usize> {
+        let mut allowed_fast_from_peer: Vec<usize> =
+            self.allowed_fast_from_peer.iter().copied().collect();
+        allowed_fast_from_peer.sort_unstable();
+        allowed_fast_from_peer
+    }
+
+    /// Funcion que recuerda una pieza sugerida por el peer externo. Si ya se recuerdan
+    /// demasiadas, se olvida la sugerencia mas antigua.
+    ///
+    pub fn add_suggested_piece(&mut self, piece_index: usize) {
+        if self.suggested_pieces.contains(&piece_index) {
+            return;
+        }
+        if self.suggested_pieces.len() >= MAX_SUGGESTED_PIECES {
+            self.suggested_pieces.remove(0);
+        }
+        self.suggested_pieces.push(piece_index);
+    }
+
+    pub fn get_suggested_pieces(&self) -> &[usize] {
+        &self.suggested_pieces
+    }
+}
+
+#[cfg(test)]
+mod test_fast_extension {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    mod test_generate_allowed_fast_set {
+        use super::*;
+
+        #[test]
+        fn allowed_fast_set_matches_the_bep_6_reference_values_ok() {
+            let ip = IpAddr::V4(Ipv4Addr::new(80, 4, 4, 200));
+            let info_hash = [0xaa; 20];
+
+            assert_eq!(
+                vec![1059, 431, 808, 1217, 287, 376, 1188],
+                generate_allowed_fast_set(ip, &info_hash, 1313, 7)
+            );
+            assert_eq!(
+                vec![1059, 431, 808, 1217, 287, 376, 1188, 353, 508],
+                generate_allowed_fast_set(ip, &info_hash, 1313, 9)
+            );
+        }
+
+        #[test]
+        fn allowed_fast_set_ignores_the_last_byte_of_the_ip_ok() {
+            let info_hash = [0xaa; 20];
+            assert_eq!(
+                generate_allowed_fast_set(
+                    IpAddr::V4(Ipv4Addr::new(80, 4, 4, 200)),
+                    &info_hash,
+                    1313,
+                    5
+                ),
+                generate_allowed_fast_set(
+                    IpAddr::V4(Ipv4Addr::new(80, 4, 4, 1)),
+                    &info_hash,
+                    1313,
+                    5
+                )
+            );
+        }
+
+        #[test]
+        fn allowed_fast_set_is_not_bigger_than_the_amount_of_pieces_ok() {
+            let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+            let mut allowed_fast_set = generate_allowed_fast_set(ip, &[1; 20], 3, 10);
+            allowed_fast_set.sort_unstable();
+            assert_eq!(vec![0, 1, 2], allowed_fast_set);
+        }
+
+        #[test]
+        fn allowed_fast_set_for_ipv6_is_empty_ok() {
+            let ip = IpAddr::V6(Ipv6Addr::LOCALHOST);
+            assert!(generate_allowed_fast_set(ip, &[1; 20], 100, 10).is_empty());
+        }
+    }
+
+    #[test]
+    fn suggested_pieces_are_bounded_and_not_repeated_ok() {
+        let mut fast_extension = FastExtension::new(true);
+        fast_extension.add_suggested_piece(3);
+        fast_extension.add_suggested_piece(3);
+        assert_eq!(&[3], fast_extension.get_suggested_pieces());
+
+        for piece_index in 0..MAX_SUGGESTED_PIECES {
+            fast_extension.add_suggested_piece(100 + piece_index);
+        }
+        assert_eq!(
+            MAX_SUGGESTED_PIECES,
+            fast_extension.get_suggested_pieces().len()
+        );
+        assert!(!fast_extension.get_suggested_pieces().contains(&3));
+    }
+
+    #[test]
+    fn allowed_fast_pieces_from_peer_are_bounded_ok() {
+        let mut fast_extension = FastExtension::new(true);
+        for piece_index in 0..=MAX_ALLOWED_FAST_FROM_PEER {
+            fast_extension.add_allowed_fast_from_peer(piece_index);
+        }
+
+        assert_eq!(
+            MAX_ALLOWED_FAST_FROM_PEER,
+            fast_extension.get_allowed_fast_from_peer().len()
+        );
+        assert!(!fast_extension.is_allowed_fast_from_peer(MAX_ALLOWED_FAST_FROM_PEER));
+    }
+}
@@ -4,61 +4,122 @@
 
 use log::{debug, info};
 
-use crate::torrent::client::pieces_assembling_handler;
-use crate::torrent::client::tracker_communication::http_handler;
+use crate::torrent::client::block_handler::{self, TargetFiles};
+use crate::torrent::client::dht::dht_node::{self, DhtNode, SECS_LOOKUP_INTERVAL};
+use crate::torrent::client::resume_handler;
+use crate::torrent::client::tracker_communication::{
+    announce_scheduler::AnnounceScheduler,
+    http_handler::{self, ErrorMsgHttp},
+    tracker_tiers::TrackerTiers,
+};
 use crate::torrent::data::config_file_torrent::ConfigFileTorrent;
 use crate::torrent::data::{
-    torrent_status::TorrentStatus, tracker_response_data::TrackerResponseData,
+    piece_picker,
+    torrent_status::{StateOfDownload, TorrentStatus},
+    tracker_response_data::{TrackerResponseData, TrackerScrapeData},
 };
 use crate::torrent::user_interface::constants::MessageUI;
 use crate::torrent::user_interface::ui_sender_handler;
 use gtk::glib::Sender as UiSender;
 use shared::torrent_file_data::TorrentFileData;
-use std::net::TcpListener;
-use std::sync::mpsc::Sender as LoggerSender;
-use std::sync::{Arc, RwLock};
+use std::sync::mpsc::{RecvTimeoutError, Sender as LoggerSender};
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use std::thread;
 use std::thread::JoinHandle;
-use std::time::Duration;
-use std::{fs, thread};
+use std::time::{Duration, Instant};
 
+use super::choker::{Choker, DEFAULT_UNCHOKE_SLOTS, SECS_CHOKING_ROUND};
+use super::connection_manager::ConnectionManager;
 use super::local_peer_communicator::{
     InteractionHandlerError, InteractionHandlerErrorKind, InteractionHandlerStatus,
-    LocalPeerCommunicator,
+    LocalPeerCommunicator, RequestSettings,
 };
+use super::peer_exchange::PexExtension;
+use super::peer_listener::{IncomingPeer, IncomingPeerReceiver};
+use super::seeding::SeedingLimits;
 
 type ResultInteraction<T> = Result<T, InteractionHandlerError>;
 type JoinHandleInteraction<T> = JoinHandle<ResultInteraction<T>>;
 
 type PeerId = Vec<u8>;
-type ExternalPeerAddres = String;
+type SharedTorrentData = (
+    Arc<RwLock<TorrentStatus>>,
+    Arc<RwLock<Choker>>,
+    Arc<RwLock<ConnectionManager>>,
+    Arc<TargetFiles>,
+);
 
 pub const BLOCK_BYTES: u32 = 16384; //2^14 bytes
 
-pub const PUBLIC_IP: &str = "127.0.0.1:";
-
-fn generate_address(config_data: &ConfigFileTorrent) -> String {
-    let address = PUBLIC_IP.to_string();
-    let port = config_data.get_port().to_string();
-    address + &port
+fn set_up_directory(
+    torrent_file_data: &TorrentFileData,
+    config_data: &ConfigFileTorrent,
+) -> ResultInteraction<TargetFiles> {
+    block_handler::set_up_target_files(torrent_file_data, &config_data.get_download_path())
+        .map_err(|err| InteractionHandlerError::SetUpDirectory(format!("{}", err)))
 }
 
-fn set_up_directory(torrent_file_data: &TorrentFileData) -> ResultInteraction<()> {
-    info!("Creo un directorio para guardar piezas");
-    let torrent_path = torrent_file_data.get_torrent_representative_name();
-    let _ = fs::remove_dir_all(format!("temp/{}", torrent_path));
-    fs::create_dir(format!("temp/{}", torrent_path))
-        .map_err(|err| InteractionHandlerError::SetUpDirectory(format!("{}", err)))?;
+fn save_download_state(
+    torrent_file_data: &TorrentFileData,
+    torrent_status: &Arc<RwLock<TorrentStatus>>,
+    config_data: &ConfigFileTorrent,
+) -> ResultInteraction<()> {
+    let torrent_status = torrent_status
+        .read()
+        .map_err(|error| InteractionHandlerError::LockingTorrentStatus(format!("{:?}", error)))?;
+    if let Err(err) = resume_handler::save_resume_data(
+        torrent_file_data,
+        &torrent_status,
+        &config_data.get_download_path(),
+    ) {
+        info!("No se pudo guardar el estado de la descarga: {}", err);
+    }
     Ok(())
 }
 
-fn remove_all(torrent_file_data: &TorrentFileData) -> ResultInteraction<()> {
-    let torrent_path = torrent_file_data.get_torrent_representative_name();
-    let _ = fs::remove_dir_all(format!("temp/{}", torrent_path)).map_err(|err| {
-        InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::RestartingDownload(
-            format!("{}", err),
+fn create_request_settings(config_data: &ConfigFileTorrent) -> RequestSettings {
+    RequestSettings {
+        request_queue_depth: config_data.get_request_queue_depth(),
+        piece_picker: piece_picker::create_piece_picker(&config_data.get_piece_selection()),
+        peer_idle_timeout: Duration::from_secs(config_data.get_peer_idle_timeout() as u64),
+        request_timeout: Duration::from_secs(config_data.get_request_timeout() as u64),
+    }
+}
+
+///
+/// Funcion que registra en el LocalPeerCommunicator las extensiones del protocolo que soporta
+/// el cliente, las cuales se negocian luego con el peer externo en el handshake extendido.
+///
+fn register_extensions(
+    local_peer: &mut LocalPeerCommunicator,
+    connection_manager: &Arc<RwLock<ConnectionManager>>,
+) -> Result<(), InteractionHandlerErrorKind> {
+    let external_peer_addr = local_peer.stream.peer_addr().map_err(|error| {
+        InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::ConectingWithPeer(
+            format!("{}", error),
         ))
-    });
+    })?;
+    local_peer
+        .extensions
+        .register(Box::new(PexExtension::new(
+            connection_manager.clone(),
+            external_peer_addr,
+        )))
+        .map_err(|error| {
+            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::HandlingExtension(
+                format!("{}", error),
+            ))
+        })
+}
 
+fn remove_peer_from_choker(
+    choker: &Arc<RwLock<Choker>>,
+    local_peer: &LocalPeerCommunicator,
+) -> ResultInteraction<()> {
+    let mut choker = choker
+        .write()
+        .map_err(|error| InteractionHandlerError::UpdatingChoker(format!("{:?}", error)))?;
+    choker.remove_peer(&local_peer.external_peer_data.peer_id);
     Ok(())
 }
 
@@ -69,6 +130,13 @@ fn is_shut_down_set(shut_down: &Arc<RwLock<bool>>) -> Result<bool, InteractionHa
     Ok(*global_shut_down)
 }
 
+fn is_torrent_completed(torrent_status: &Arc<RwLock<TorrentStatus>>) -> ResultInteraction<bool> {
+    let torrent_status = torrent_status
+        .read()
+        .map_err(|error| InteractionHandlerError::LockingTorrentStatus(format!("{:?}", error)))?;
+    Ok(torrent_status.is_torrent_state_set_as_completed())
+}
+
 fn set_shut_down(shut_down: Arc<RwLock<bool>>) -> Result<(), InteractionHandlerError> {
     let mut shut_down = shut_down
         .write()
@@ -78,295 +146,627 @@ fn set_shut_down(shut_down: Arc<RwLock<bool>>) -> Result<(), InteractionHandlerE
 }
 
 ///
-/// Funcion encargada de realizar la interaccion con unico peer dentro de un thread. Esta interaccion se
-/// comienza con el protocolo correspondiente a un server.
+/// Funcion encargada de ejecutar, dentro de un thread, una ronda de choking cada [SECS_CHOKING_ROUND]
+/// segundos sobre todos los peers del torrent. Las decisiones son aplicadas luego por cada
+/// LocalPeerCommunicator. La funcion finaliza cuando se activa el shutdown global o local.
+///
+fn handle_choker(
+    choker: Arc<RwLock<Choker>>,
+    torrent_status: Arc<RwLock<TorrentStatus>>,
+    global_shut_down: Arc<RwLock<bool>>,
+    local_shut_down: Arc<RwLock<bool>>,
+) -> JoinHandleInteraction<()> {
+    thread::spawn(move || {
+        let mut secs_since_last_round = 0;
+        loop {
+            if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_secs(1));
+            secs_since_last_round += 1;
+            if secs_since_last_round < SECS_CHOKING_ROUND {
+                continue;
+            }
+            secs_since_last_round = 0;
+
+            let is_seeding = torrent_status
+                .read()
+                .map_err(|error| {
+                    InteractionHandlerError::LockingTorrentStatus(format!("{:?}", error))
+                })?
+                .is_torrent_state_set_as_completed();
+            choker
+                .write()
+                .map_err(|error| InteractionHandlerError::UpdatingChoker(format!("{:?}", error)))?
+                .run_choking_round(is_seeding);
+        }
+    })
+}
+
+///
+/// Funcion encargada de supervisar, dentro de un thread, el paso del torrent al estado de seeding.
+/// A partir del momento en que se completa la descarga se sigue compartiendo el torrent hasta
+/// alcanzar alguno de los limites de seeding configurados, momento en el cual se activa el shutdown
+/// local. Si el torrent ya estaba completo al comenzar, se comparte directamente.
+/// La funcion finaliza cuando se activa el shutdown global o local.
+///
+fn handle_seeding(
+    read_only_data: (TorrentFileData, ConfigFileTorrent),
+    torrent_status: Arc<RwLock<TorrentStatus>>,
+    global_shut_down: Arc<RwLock<bool>>,
+    local_shut_down: Arc<RwLock<bool>>,
+) -> ResultInteraction<JoinHandleInteraction<()>> {
+    let (torrent_file_data, config_data) = read_only_data;
+    let seeding_limits = SeedingLimits::new(&config_data);
+    let mut seeding_since = is_torrent_completed(&torrent_status)?.then(Instant::now);
+    Ok(thread::spawn(move || loop {
+        if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+
+        let torrent_status = torrent_status.read().map_err(|error| {
+            InteractionHandlerError::LockingTorrentStatus(format!("{:?}", error))
+        })?;
+        if !torrent_status.is_torrent_state_set_as_completed() {
+            continue;
+        }
+        let seeding_start = *seeding_since.get_or_insert_with(|| {
+            info!(
+                "Descarga completa del torrent {}, comenzando a compartirlo.",
+                torrent_file_data.get_torrent_representative_name()
+            );
+            Instant::now()
+        });
+        if seeding_limits.is_reached(
+            torrent_status.get_uploaded_bytes(),
+            torrent_file_data.get_total_length(),
+            seeding_start.elapsed(),
+        ) {
+            info!(
+                "Se alcanzo el limite de seeding del torrent {}.",
+                torrent_file_data.get_torrent_representative_name()
+            );
+            return set_shut_down(local_shut_down);
+        }
+    }))
+}
+
+fn clone_torrent_status(
+    torrent_status: &Arc<RwLock<TorrentStatus>>,
+) -> ResultInteraction<TorrentStatus> {
+    let torrent_status = torrent_status
+        .read()
+        .map_err(|error| InteractionHandlerError::LockingTorrentStatus(format!("{:?}", error)))?;
+    Ok(torrent_status.clone())
+}
+
+fn announce_to_tracker(
+    read_only_data: &(TorrentFileData, ConfigFileTorrent, PeerId),
+    tracker_tiers: &mut TrackerTiers,
+    torrent_status: &Arc<RwLock<TorrentStatus>>,
+    event: Option<StateOfDownload>,
+) -> ResultInteraction<Result<TrackerResponseData, ErrorMsgHttp>> {
+    let (torrent_file_data, config_data, peer_id) = read_only_data;
+    Ok(http_handler::communicate_with_tracker(
+        tracker_tiers,
+        &clone_torrent_status(torrent_status)?,
+        torrent_file_data,
+        config_data,
+        peer_id.clone(),
+        event,
+    ))
+}
+
+fn scrape_tracker(
+    read_only_data: &(TorrentFileData, ConfigFileTorrent, PeerId),
+    tracker_tiers: &mut TrackerTiers,
+    torrent_status: &Arc<RwLock<TorrentStatus>>,
+) -> ResultInteraction<Result<TrackerScrapeData, ErrorMsgHttp>> {
+    let (torrent_file_data, config_data, peer_id) = read_only_data;
+    Ok(http_handler::scrape_tracker(
+        tracker_tiers,
+        &clone_torrent_status(torrent_status)?,
+        torrent_file_data,
+        config_data,
+        peer_id.clone(),
+    ))
+}
+
+fn update_seeders_and_leechers(
+    ui_sender: &UiSender<MessageUI>,
+    torrent_file_data: &TorrentFileData,
+    seeders: u64,
+    leechers: u64,
+) -> ResultInteraction<()> {
+    ui_sender_handler::update_seeders_and_leechers(ui_sender, torrent_file_data, seeders, leechers)
+        .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))
+}
+
+///
+/// Funcion encargada de volver a anunciarse al tracker, dentro de un thread, cada vez que lo indique
+/// el AnnounceScheduler. Los peers nuevos de cada respuesta se agregan al ConnectionManager.
+/// Entre anuncios se le realizan scrapes al tracker para mantener actualizada la cantidad de
+/// seeders y leechers de la interfaz grafica.
+/// Al completarse la descarga se anuncia el evento `completed` y, al activarse el shutdown global o
+/// local, el evento `stopped` (sin esperar a que el tracker lo reciba correctamente).
+///
+fn handle_announces(
+    read_only_data: (TorrentFileData, ConfigFileTorrent, PeerId),
+    tracker_schedule: (TrackerTiers, AnnounceScheduler),
+    torrent_status: Arc<RwLock<TorrentStatus>>,
+    connection_manager: Arc<RwLock<ConnectionManager>>,
+    ui_sender: UiSender<MessageUI>,
+    global_shut_down: Arc<RwLock<bool>>,
+    local_shut_down: Arc<RwLock<bool>>,
+) -> ResultInteraction<JoinHandleInteraction<()>> {
+    let (mut tracker_tiers, mut announce_scheduler) = tracker_schedule;
+    let mut was_completed = is_torrent_completed(&torrent_status)?;
+    let mut is_completion_pending = false;
+    Ok(thread::spawn(move || loop {
+        if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
+            if let Err(err) = announce_to_tracker(
+                &read_only_data,
+                &mut tracker_tiers,
+                &torrent_status,
+                Some(StateOfDownload::Stopped),
+            )? {
+                debug!("No se pudo anunciar el evento stopped al tracker: {}", err);
+            }
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(1));
+
+        if !was_completed && is_torrent_completed(&torrent_status)? {
+            was_completed = true;
+            is_completion_pending = true;
+            announce_scheduler.announce_now(Instant::now());
+        }
+
+        if announce_scheduler.is_announce_due(Instant::now()) {
+            let event = is_completion_pending.then_some(StateOfDownload::Completed);
+            match announce_to_tracker(&read_only_data, &mut tracker_tiers, &torrent_status, event)?
+            {
+                Ok(tracker_response) => {
+                    is_completion_pending = false;
+                    announce_scheduler.announce_succeeded(&tracker_response, Instant::now());
+                    let amount_of_new_peers = lock_connection_manager(&connection_manager)?
+                        .add_peers(&tracker_response.peers);
+                    debug!(
+                        "Anuncio al tracker exitoso: {} peers nuevos",
+                        amount_of_new_peers
+                    );
+                    update_seeders_and_leechers(
+                        &ui_sender,
+                        &read_only_data.0,
+                        tracker_response.get_total_amount_seeders(),
+                        tracker_response.get_total_amount_leechers(),
+                    )?;
+                }
+                Err(err) => {
+                    info!("Fallo el anuncio al tracker: {}", err);
+                    announce_scheduler.announce_failed(Instant::now());
+                }
+            }
+        } else if announce_scheduler.is_scrape_due(Instant::now()) {
+            announce_scheduler.scrape_done(Instant::now());
+            match scrape_tracker(&read_only_data, &mut tracker_tiers, &torrent_status)? {
+                Ok(scrape_data) => update_seeders_and_leechers(
+                    &ui_sender,
+                    &read_only_data.0,
+                    scrape_data.complete,
+                    scrape_data.incomplete,
+                )?,
+                Err(err) => debug!("Fallo el scrape al tracker: {}", err),
+            }
+        }
+    }))
+}
+
+///
+/// Funcion encargada de buscar, dentro de un thread, peers del torrent en la DHT cada
+/// [SECS_LOOKUP_INTERVAL] segundos (comenzando apenas se lanza), anunciandonos en los nodos mas
+/// cercanos a su info hash. Los peers encontrados se agregan al ConnectionManager.
+/// La funcion finaliza cuando se activa el shutdown global o local.
+///
+fn handle_dht_lookups(
+    read_only_data: (TorrentFileData, ConfigFileTorrent),
+    dht_node: Arc<DhtNode>,
+    connection_manager: Arc<RwLock<ConnectionManager>>,
+    global_shut_down: Arc<RwLock<bool>>,
+    local_shut_down: Arc<RwLock<bool>>,
+) -> JoinHandleInteraction<()> {
+    let (torrent_file_data, config_data) = read_only_data;
+    let announce_port = u16::try_from(config_data.get_port()).ok();
+    thread::spawn(move || {
+        let mut secs_since_last_lookup = SECS_LOOKUP_INTERVAL;
+        loop {
+            if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
+                return Ok(());
+            }
+            if secs_since_last_lookup >= SECS_LOOKUP_INTERVAL {
+                secs_since_last_lookup = 0;
+                match dht_node::find_torrent_peers(&dht_node, &torrent_file_data, announce_port) {
+                    Ok(peers) => {
+                        let amount_of_new_peers =
+                            lock_connection_manager(&connection_manager)?.add_peers(&peers);
+                        debug!(
+                            "Busqueda en la DHT exitosa: {} peers nuevos",
+                            amount_of_new_peers
+                        );
+                    }
+                    Err(err) => info!("Fallo la busqueda de peers en la DHT: {}", err),
+                }
+            }
+            thread::sleep(Duration::from_secs(1));
+            secs_since_last_lookup += 1;
+        }
+    })
+}
+
+///
+/// Funcion que libera todo lo que el peer externo aportaba al torrent (pedidos pendientes y
+/// piezas disponibles) y lo quita de la interfaz grafica, al finalizar la interaccion con el mismo.
+///
+fn release_external_peer(
+    torrent_file_data: &TorrentFileData,
+    torrent_status: &Arc<RwLock<TorrentStatus>>,
+    local_peer: &mut LocalPeerCommunicator,
+    ui_sender: &UiSender<MessageUI>,
+) -> ResultInteraction<()> {
+    let mut torrent_status = torrent_status.write().map_err(|error| {
+        InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", error))
+    })?;
+    local_peer.release_external_peer_resources(&mut torrent_status);
+    ui_sender_handler::remove_external_peer(
+        ui_sender,
+        torrent_file_data,
+        &local_peer.external_peer_data,
+    )
+    .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))
+}
+
+fn lock_connection_manager(
+    connection_manager: &Arc<RwLock<ConnectionManager>>,
+) -> ResultInteraction<RwLockWriteGuard<'_, ConnectionManager>> {
+    connection_manager
+        .write()
+        .map_err(|error| InteractionHandlerError::UpdatingConnectionManager(format!("{:?}", error)))
+}
+
+///
+/// Funcion que espera a que finalicen los threads dados y devuelve el primer error
+/// encontrado (si lo hubiera).
+///
+fn join_handlers(handlers: Vec<JoinHandleInteraction<()>>) -> ResultInteraction<()> {
+    let mut result = Ok(());
+    for handler in handlers {
+        let handler_result = handler.join().unwrap_or_else(|_| {
+            Err(InteractionHandlerError::JoinHandle(
+                "[InteractionHandlerError] Join handle error".to_string(),
+            ))
+        });
+        if result.is_ok() {
+            result = handler_result;
+        }
+    }
+    result
+}
+
+///
+/// Funcion que espera a los threads que ya finalizaron y devuelve los que siguen en
+/// ejecucion, junto con el resultado de los finalizados.
+///
+fn join_finished_handlers(
+    handlers: Vec<JoinHandleInteraction<()>>,
+) -> (Vec<JoinHandleInteraction<()>>, ResultInteraction<()>) {
+    let (finished_handlers, running_handlers): (Vec<_>, Vec<_>) = handlers
+        .into_iter()
+        .partition(|handler| handler.is_finished());
+    (running_handlers, join_handlers(finished_handlers))
+}
+
+///
+/// Funcion encargada de realizar la interaccion con un peer externo que se conecto a nosotros,
+/// dentro de un thread. Esta interaccion se comienza con el protocolo correspondiente a un server.
 /// La funcion finaliza cuando se activa el shutdown global, local, cuando se obtienen todas las piezas posibles
 /// a partir del peer con el cual no estamos comunicando o en caso de error.
 /// Es importante remarcar que no todos los tipos de errores detienen la comunicacion con los peers, dado que
 /// existen errores recuperables (los cuales no afectan la continuacion de la descarga de piezas a traves de otros
 /// medios) y los errores irrecuperables (los cuales detienen completamente la descarga del torrent e imprimen un fallo tanto por consola como en el archivo logs)
 ///
-fn handle_interaction_starting_as_server(
-    read_only_data: (
-        TorrentFileData,
-        ConfigFileTorrent,
-        PeerId,
-        ExternalPeerAddres,
-    ),
-    torrent_status: Arc<RwLock<TorrentStatus>>,
+fn handle_interaction_with_an_incoming_peer(
+    read_only_data: (TorrentFileData, ConfigFileTorrent, PeerId),
+    shared_torrent_data: SharedTorrentData,
+    incoming_peer: IncomingPeer,
     logger_sender: LoggerSender<String>,
     ui_sender: UiSender<MessageUI>,
     global_shut_down: Arc<RwLock<bool>>,
     local_shut_down: Arc<RwLock<bool>>,
 ) -> JoinHandleInteraction<()> {
-    let (torrent_file_data, config_data, peer_id, address) = read_only_data;
+    let (torrent_file_data, config_data, peer_id) = read_only_data;
+    let (torrent_status, choker, connection_manager, target_files) = shared_torrent_data;
+    let request_settings = create_request_settings(&config_data);
     thread::spawn(move || {
-        let listener = TcpListener::bind(address)
-            .map_err(|error| InteractionHandlerError::ConectingWithPeer(format!("{}", error)))?;
-        listener
-            .set_nonblocking(true)
-            .map_err(|error| InteractionHandlerError::ConectingWithPeer(format!("{}", error)))?;
+        let mut local_peer = match LocalPeerCommunicator::start_communication_as_server(
+            &torrent_file_data,
+            peer_id,
+            incoming_peer,
+            logger_sender,
+            ui_sender.clone(),
+            request_settings,
+        ) {
+            Ok(local_peer) => local_peer,
+            Err(InteractionHandlerErrorKind::Recoverable(err)) => {
+                debug!("Recoverable error in the peers communication: {:?}", err);
+                lock_connection_manager(&connection_manager)?.remove_incoming_peer();
+                return Ok(());
+            }
+            Err(InteractionHandlerErrorKind::Unrecoverable(err)) => {
+                lock_connection_manager(&connection_manager)?.remove_incoming_peer();
+                set_shut_down(local_shut_down)?;
+                return Err(err);
+            }
+        };
 
-        loop {
-            if let Ok((stream, external_peer_addr)) = listener.accept() {
-                let mut local_peer = match LocalPeerCommunicator::start_communication_as_server(
-                    &torrent_file_data,
-                    peer_id.clone(),
-                    stream,
-                    external_peer_addr,
-                    logger_sender.clone(),
-                    ui_sender.clone(),
-                ) {
-                    Ok(local_peer) => local_peer,
-                    Err(InteractionHandlerErrorKind::Recoverable(err)) => {
-                        debug!("Recoverable error in the peers communication: {:?}", err);
-                        continue;
-                    }
-                    Err(InteractionHandlerErrorKind::Unrecoverable(err)) => {
-                        remove_all(&torrent_file_data)?;
-                        set_shut_down(local_shut_down)?;
-                        return Err(err);
-                    }
-                };
-                let interaction_result = match local_peer.interact_with_peer(
+        let interaction_result = register_extensions(&mut local_peer, &connection_manager)
+            .and_then(|_| {
+                local_peer.interact_with_peer(
                     &torrent_file_data,
                     &torrent_status,
+                    &target_files,
+                    &choker,
                     &global_shut_down,
                     &local_shut_down,
-                ) {
-                    Ok(InteractionHandlerStatus::SecureLocalShutDown) => {
-                        remove_all(&torrent_file_data)?;
-                        Ok(())
-                    }
-                    Ok(InteractionHandlerStatus::SecureGlobalShutDown) => Ok(()),
-                    Ok(InteractionHandlerStatus::FinishInteraction) => {
-                        pieces_assembling_handler::assemble_all_completed_pieces(
-                            config_data.get_download_path(),
-                            &torrent_file_data,
-                        )
-                        .map_err(|err| {
-                            InteractionHandlerError::PiecesHandler(format!("{}", err))
-                        })?;
-                        set_shut_down(local_shut_down)?;
-                        Ok(())
-                    }
-                    Ok(InteractionHandlerStatus::LookForAnotherPeer) => {
-                        ui_sender_handler::remove_external_peer(
-                            &ui_sender,
-                            &torrent_file_data,
-                            &local_peer.external_peer_data,
-                        )
-                        .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))?;
-                        continue;
-                    }
-                    Err(InteractionHandlerErrorKind::Recoverable(err)) => {
-                        debug!("Recoverable error in the peers communication: {:?}", err);
-                        let mut torrent_status = torrent_status.write().map_err(|error| {
-                            InteractionHandlerError::UpdatingWasRequestedField(format!(
-                                "{:?}",
-                                error
-                            ))
-                        })?;
-                        torrent_status.set_all_pieces_as_not_requested();
-                        ui_sender_handler::remove_external_peer(
-                            &ui_sender,
-                            &torrent_file_data,
-                            &local_peer.external_peer_data,
-                        )
-                        .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))?;
-                        continue;
-                    }
-                    Err(InteractionHandlerErrorKind::Unrecoverable(err)) => {
-                        remove_all(&torrent_file_data)?;
-                        set_shut_down(local_shut_down)?;
-                        Err(err)
-                    }
-                };
-
-                ui_sender_handler::remove_external_peer(
-                    &ui_sender,
-                    &torrent_file_data,
-                    &local_peer.external_peer_data,
                 )
-                .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))?;
-                return interaction_result;
-            } else {
-                if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
-                    return Ok(());
-                }
-                thread::sleep(Duration::from_secs(1));
-                continue;
+            });
+        remove_peer_from_choker(&choker, &local_peer)?;
+        lock_connection_manager(&connection_manager)?.remove_incoming_peer();
+        let interaction_result = match interaction_result {
+            Ok(InteractionHandlerStatus::SecureLocalShutDown) => Ok(()),
+            Ok(InteractionHandlerStatus::SecureGlobalShutDown) => Ok(()),
+            Ok(InteractionHandlerStatus::FinishInteraction) => Ok(()),
+            Ok(InteractionHandlerStatus::LookForAnotherPeer) => Ok(()),
+            Err(InteractionHandlerErrorKind::Recoverable(err)) => {
+                debug!("Recoverable error in the peers communication: {:?}", err);
+                Ok(())
             }
-        }
+            Err(InteractionHandlerErrorKind::Unrecoverable(err)) => {
+                set_shut_down(local_shut_down)?;
+                Err(err)
+            }
+        };
+
+        release_external_peer(
+            &torrent_file_data,
+            &torrent_status,
+            &mut local_peer,
+            &ui_sender,
+        )?;
+        interaction_result
     })
 }
 
-fn generate_list_of_connected_peers(
-    tracker_response: &TrackerResponseData,
-) -> (Vec<usize>, Vec<usize>) {
-    let mut list_connected_peers_1: Vec<usize> = vec![];
-    let mut list_connected_peers_2: Vec<usize> = vec![];
-    for i in 0..tracker_response.get_total_amount_peers() {
-        if i % 2 == 0 {
-            list_connected_peers_1.push(i)
-        } else {
-            list_connected_peers_2.push(i)
-        }
-    }
-    (list_connected_peers_1, list_connected_peers_2)
+///
+/// Funcion encargada de atender, dentro de un thread, las conexiones entrantes de peers externos que el
+/// listener compartido deriva a este torrent. Por cada conexion recibida (mientras no se supere el limite
+/// de peers del torrent) se lanza un thread que interactua con el peer. La funcion finaliza cuando se
+/// activa el shutdown global o local.
+///
+fn handle_incoming_connections(
+    read_only_data: (TorrentFileData, ConfigFileTorrent, PeerId),
+    incoming_peers: IncomingPeerReceiver,
+    shared_torrent_data: SharedTorrentData,
+    logger_sender: LoggerSender<String>,
+    ui_sender: UiSender<MessageUI>,
+    global_shut_down: Arc<RwLock<bool>>,
+    local_shut_down: Arc<RwLock<bool>>,
+) -> JoinHandleInteraction<()> {
+    let (torrent_file_data, config_data, peer_id) = read_only_data;
+    thread::spawn(move || {
+        let mut peer_handlers = vec![];
+        let result = loop {
+            if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
+                break Ok(());
+            }
+            let (running_handlers, finished_result) = join_finished_handlers(peer_handlers);
+            peer_handlers = running_handlers;
+            if finished_result.is_err() {
+                break finished_result;
+            }
+
+            let incoming_peer = match incoming_peers.recv_timeout(Duration::from_secs(1)) {
+                Ok(incoming_peer) => incoming_peer,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break Ok(()),
+            };
+            if !lock_connection_manager(&shared_torrent_data.2)?.try_to_add_incoming_peer() {
+                debug!(
+                    "Se rechaza la conexion de {}: se alcanzo el maximo de peers",
+                    incoming_peer.external_peer_addr
+                );
+                continue;
+            }
+            peer_handlers.push(handle_interaction_with_an_incoming_peer(
+                (
+                    torrent_file_data.clone(),
+                    config_data.clone(),
+                    peer_id.clone(),
+                ),
+                shared_torrent_data.clone(),
+                incoming_peer,
+                logger_sender.clone(),
+                ui_sender.clone(),
+                global_shut_down.clone(),
+                local_shut_down.clone(),
+            ));
+        };
+
+        let peers_result = join_handlers(peer_handlers);
+        result.and(peers_result)
+    })
 }
 
 ///
-/// Funcion encargada de realizar la interaccion con unico peer dentro de un thread. Esta interaccion se
-/// comienza con el protocolo correspondiente a un cliente.
-/// La funcion finaliza cuando se activa el shutdown global, local, cuando se obtienen todas las piezas posibles
-/// a partir del peer con el cual no estamos comunicando o en caso de error.
+/// Funcion encargada de realizar la interaccion con un peer externo dado por el tracker, dentro
+/// de un thread. Esta interaccion se comienza con el protocolo correspondiente a un cliente.
+/// Al finalizar se le informa al ConnectionManager como termino la conexion, para que decida
+/// si y cuando volver a intentarla.
 /// Es importante remarcar que no todos los tipos de errores detienen la comunicacion con los peers, dado que
 /// existen errores recuperables (los cuales no afectan la continuacion de la descarga de piezas a traves de otros
 /// medios) y los errores irrecuperables (los cuales detienen completamente la descarga del torrent e imprimen un fallo tanto por consola como en el archivo logs)
 ///
-fn handle_interaction_starting_as_client(
+fn handle_interaction_with_an_outgoing_peer(
     read_only_data: (
         TorrentFileData,
-        TrackerResponseData,
+        Arc<TrackerResponseData>,
         ConfigFileTorrent,
         PeerId,
     ),
-    torrent_status: Arc<RwLock<TorrentStatus>>,
-    mut list_connected_peers: Vec<usize>,
+    shared_torrent_data: SharedTorrentData,
+    tracker_response_peer_index: usize,
     logger_sender: LoggerSender<String>,
     ui_sender: UiSender<MessageUI>,
     global_shut_down: Arc<RwLock<bool>>,
     local_shut_down: Arc<RwLock<bool>>,
 ) -> JoinHandleInteraction<()> {
-    let (torrent_file_data, tracker_response, config_data, peer_id) = read_only_data;
-    thread::spawn(move || loop {
-        if list_connected_peers.is_empty() {
-            //set_shut_down(local_shut_down)?;
-            return Err(InteractionHandlerError::ConectingWithPeer(
-                "No peers left to connect.".to_string(),
-            ));
-        }
-        if is_shut_down_set(&global_shut_down)? {
-            info!(
-                "Shut down seguro del torrent {}.",
-                torrent_file_data.get_torrent_representative_name()
-            );
-            return Ok(());
-        }
-
-        let current_peer_index = list_connected_peers[0];
+    let (torrent_file_data, peer_pool, config_data, peer_id) = read_only_data;
+    let (torrent_status, choker, connection_manager, target_files) = shared_torrent_data;
+    let request_settings = create_request_settings(&config_data);
+    thread::spawn(move || {
         let mut local_peer = match LocalPeerCommunicator::start_communication_as_client(
             &torrent_file_data,
-            &tracker_response,
-            current_peer_index,
+            &peer_pool,
+            tracker_response_peer_index,
             peer_id.clone(),
-            logger_sender.clone(),
+            logger_sender,
             ui_sender.clone(),
+            request_settings,
         ) {
             Ok(local_peer) => local_peer,
             Err(InteractionHandlerErrorKind::Recoverable(err)) => {
-                list_connected_peers.remove(0);
                 debug!("Recoverable error in the peers communication: {:?}", err);
-                continue;
+                lock_connection_manager(&connection_manager)?
+                    .connection_failed(tracker_response_peer_index, Instant::now());
+                return Ok(());
             }
             Err(InteractionHandlerErrorKind::Unrecoverable(err)) => {
-                remove_all(&torrent_file_data)?;
-                let mut local_shut_down = local_shut_down.write().map_err(|error| {
-                    InteractionHandlerError::ReadingShutDownField(format!("{:?}", error))
-                })?;
-                *local_shut_down = true;
+                set_shut_down(local_shut_down)?;
                 return Err(err);
             }
         };
+        lock_connection_manager(&connection_manager)?
+            .connection_established(tracker_response_peer_index);
 
-        let interaction_result = match local_peer.interact_with_peer(
-            &torrent_file_data,
-            &torrent_status,
-            &global_shut_down,
-            &local_shut_down,
-        ) {
-            Ok(InteractionHandlerStatus::SecureLocalShutDown) => {
-                remove_all(&torrent_file_data)?;
-                Ok(())
-            }
-            Ok(InteractionHandlerStatus::SecureGlobalShutDown) => Ok(()),
-            Ok(InteractionHandlerStatus::FinishInteraction) => {
-                pieces_assembling_handler::assemble_all_completed_pieces(
-                    config_data.get_download_path(),
+        let interaction_result = register_extensions(&mut local_peer, &connection_manager)
+            .and_then(|_| {
+                local_peer.interact_with_peer(
                     &torrent_file_data,
-                )
-                .map_err(|err| InteractionHandlerError::PiecesHandler(format!("{}", err)))?;
-
-                let mut local_shut_down = local_shut_down
-                    .write()
-                    .map_err(|err| InteractionHandlerError::UiError(format!("{}", err)))?;
-                *local_shut_down = true;
-
-                let torrent_status = torrent_status.read().map_err(|error| {
-                    InteractionHandlerError::RecommunicatingWithTracker(format!("{:?}", error))
-                })?;
-
-                http_handler::communicate_with_tracker(
                     &torrent_status,
-                    &torrent_file_data,
-                    &config_data,
-                    peer_id.clone(),
+                    &target_files,
+                    &choker,
+                    &global_shut_down,
+                    &local_shut_down,
                 )
-                .map_err(|err| {
-                    InteractionHandlerError::RecommunicatingWithTracker(err.to_string())
-                })?;
-
+            });
+        remove_peer_from_choker(&choker, &local_peer)?;
+        let interaction_result = match interaction_result {
+            Ok(InteractionHandlerStatus::SecureLocalShutDown) => Ok(()),
+            Ok(InteractionHandlerStatus::SecureGlobalShutDown) => Ok(()),
+            Ok(InteractionHandlerStatus::FinishInteraction)
+            | Ok(InteractionHandlerStatus::LookForAnotherPeer) => {
+                lock_connection_manager(&connection_manager)?
+                    .connection_closed(tracker_response_peer_index, Instant::now());
                 Ok(())
             }
-            Ok(InteractionHandlerStatus::LookForAnotherPeer) => {
-                let index = list_connected_peers.remove(0);
-                list_connected_peers.push(index);
-                let mut torrent_status = torrent_status.write().map_err(|error| {
-                    InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", error))
-                })?;
-                torrent_status.set_all_pieces_as_not_requested();
-                ui_sender_handler::remove_external_peer(
-                    &ui_sender,
-                    &torrent_file_data,
-                    &local_peer.external_peer_data,
-                )
-                .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))?;
-                continue;
-            }
             Err(InteractionHandlerErrorKind::Recoverable(err)) => {
-                list_connected_peers.remove(0);
                 debug!("Recoverable error in the peers communication: {:?}", err);
-                let mut torrent_status = torrent_status.write().map_err(|error| {
-                    InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", error))
-                })?;
-                torrent_status.set_all_pieces_as_not_requested();
-                ui_sender_handler::remove_external_peer(
-                    &ui_sender,
-                    &torrent_file_data,
-                    &local_peer.external_peer_data,
-                )
-                .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))?;
-                continue;
+                lock_connection_manager(&connection_manager)?
+                    .connection_failed(tracker_response_peer_index, Instant::now());
+                Ok(())
             }
             Err(InteractionHandlerErrorKind::Unrecoverable(err)) => {
-                remove_all(&torrent_file_data)?;
-                let mut local_shut_down = local_shut_down.write().map_err(|error| {
-                    InteractionHandlerError::ReadingShutDownField(format!("{:?}", error))
-                })?;
-                *local_shut_down = true;
+                set_shut_down(local_shut_down)?;
                 Err(err)
             }
         };
 
-        ui_sender_handler::remove_external_peer(
-            &ui_sender,
+        release_external_peer(
             &torrent_file_data,
-            &local_peer.external_peer_data,
-        )
-        .map_err(|error| InteractionHandlerError::UiError(format!("{}", error)))?;
+            &torrent_status,
+            &mut local_peer,
+            &ui_sender,
+        )?;
+        interaction_result
+    })
+}
+
+///
+/// Funcion encargada de abrir, dentro de un thread, las conexiones salientes con los peers dados por el
+/// tracker. Cada segundo se le piden al ConnectionManager nuevos peers a los cuales conectarse (respetando
+/// los limites de conexiones) y se lanza un thread de interaccion por cada uno.
+/// Una vez completo el torrent no se abren nuevas conexiones salientes: se comparte solo con los peers
+/// que ya estan conectados y con los que se conectan a nosotros.
+/// Aunque no queden peers a los cuales conectarse se sigue esperando, ya que el pool puede recibir
+/// nuevos peers de los anuncios al tracker, de peer exchange o de la DHT.
+/// La funcion finaliza cuando se activa el shutdown global o local, o en caso de error irrecuperable
+/// en alguna interaccion.
+///
+fn handle_outgoing_connections(
+    read_only_data: (TorrentFileData, ConfigFileTorrent, PeerId),
+    shared_torrent_data: SharedTorrentData,
+    logger_sender: LoggerSender<String>,
+    ui_sender: UiSender<MessageUI>,
+    global_shut_down: Arc<RwLock<bool>>,
+    local_shut_down: Arc<RwLock<bool>>,
+) -> JoinHandleInteraction<()> {
+    let (torrent_file_data, config_data, peer_id) = read_only_data;
+    thread::spawn(move || {
+        let mut peer_handlers = vec![];
+        let result = loop {
+            if is_shut_down_set(&global_shut_down)? || is_shut_down_set(&local_shut_down)? {
+                info!(
+                    "Shut down seguro del torrent {}.",
+                    torrent_file_data.get_torrent_representative_name()
+                );
+                break Ok(());
+            }
+            let (running_handlers, finished_result) = join_finished_handlers(peer_handlers);
+            peer_handlers = running_handlers;
+            if finished_result.is_err() {
+                break finished_result;
+            }
+
+            if is_torrent_completed(&shared_torrent_data.0)? {
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+            let mut connection_manager = lock_connection_manager(&shared_torrent_data.2)?;
+            while let Some(tracker_response_peer_index) =
+                connection_manager.take_next_candidate(Instant::now())
+            {
+                peer_handlers.push(handle_interaction_with_an_outgoing_peer(
+                    (
+                        torrent_file_data.clone(),
+                        connection_manager.get_peer_pool(),
+                        config_data.clone(),
+                        peer_id.clone(),
+                    ),
+                    shared_torrent_data.clone(),
+                    tracker_response_peer_index,
+                    logger_sender.clone(),
+                    ui_sender.clone(),
+                    global_shut_down.clone(),
+                    local_shut_down.clone(),
+                ));
+            }
+            drop(connection_manager);
 
-        return interaction_result;
+            thread::sleep(Duration::from_secs(1));
+        };
+
+        let peers_result = join_handlers(peer_handlers);
+        result.and(peers_result)
     })
 }
 
@@ -376,9 +776,13 @@ fn handle_interaction_starting_as_client(
 /// peers que se hayan obtenido a partir de una respuesta de tracker e info
 /// adicional del archivo .torrent correspondiente.
 /// (***Comportandose como Cliente y como Server por la caracteristica hibrida que poseen los LocalPeerCommunicator***)
+/// Las conexiones entrantes llegan a traves del listener compartido por todos los torrents.
 ///
-/// Finaliza la conexion en caso de activarse el shutdown global, en caso de completar todo el archivo
-/// o en caso de error interno.
+/// Durante toda la comunicacion se vuelve a anunciar periodicamente al tracker y, si hay un nodo DHT,
+/// se buscan peers en la DHT, sumando los nuevos peers que se obtengan.
+/// Una vez completo el archivo, se lo sigue compartiendo (seeding) con los peers externos.
+/// Finaliza la conexion en caso de activarse el shutdown global, en caso de alcanzar alguno de los
+/// limites de seeding configurados o en caso de error interno.
 ///
 pub fn handle_general_interaction_with_peers(
     read_only_data: (
@@ -387,83 +791,229 @@ pub fn handle_general_interaction_with_peers(
         &ConfigFileTorrent,
         PeerId,
     ),
+    peer_sources: (TrackerTiers, Option<Arc<DhtNode>>),
     torrent_status: TorrentStatus,
+    incoming_peers: IncomingPeerReceiver,
     global_shut_down: Arc<RwLock<bool>>,
     logger_sender: &LoggerSender<String>,
     ui_sender: &UiSender<MessageUI>,
 ) -> Result<(), InteractionHandlerError> {
     let (torrent_file_data, tracker_response, config_data, peer_id) = read_only_data;
-    set_up_directory(torrent_file_data)?;
+    let (tracker_tiers, dht_node) = peer_sources;
+    let target_files = Arc::new(set_up_directory(torrent_file_data, config_data)?);
     let torrent_status = Arc::new(RwLock::new(torrent_status));
-    let address = generate_address(config_data);
-    let (list_connected_peers_1, list_connected_peers_2) =
-        generate_list_of_connected_peers(tracker_response);
+    let choker = Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS)));
+    let connection_manager = Arc::new(RwLock::new(
+        ConnectionManager::new(
+            tracker_response,
+            config_data.get_max_peers_per_torrent(),
+            config_data.get_max_half_open(),
+        )
+        .with_own_peer(&peer_id, u16::try_from(config_data.get_port()).ok()),
+    ));
+    let shared_torrent_data = (
+        torrent_status.clone(),
+        choker.clone(),
+        connection_manager.clone(),
+        target_files,
+    );
 
     let local_shut_down = Arc::new(RwLock::new(false));
 
-    let handler_local_peer_0 = handle_interaction_starting_as_server(
+    let handler_seeding = handle_seeding(
+        (torrent_file_data.clone(), config_data.clone()),
+        torrent_status.clone(),
+        global_shut_down.clone(),
+        local_shut_down.clone(),
+    )?;
+
+    let handler_announces = handle_announces(
         (
             torrent_file_data.clone(),
             config_data.clone(),
             peer_id.clone(),
-            address,
+        ),
+        (
+            tracker_tiers,
+            AnnounceScheduler::new(tracker_response, Instant::now()),
         ),
         torrent_status.clone(),
-        logger_sender.clone(),
+        connection_manager.clone(),
         ui_sender.clone(),
         global_shut_down.clone(),
         local_shut_down.clone(),
+    )?;
+
+    let handlers_dht_lookups: Vec<JoinHandleInteraction<()>> = dht_node
+        .into_iter()
+        .map(|dht_node| {
+            handle_dht_lookups(
+                (torrent_file_data.clone(), config_data.clone()),
+                dht_node,
+                connection_manager.clone(),
+                global_shut_down.clone(),
+                local_shut_down.clone(),
+            )
+        })
+        .collect();
+
+    let handler_choker = handle_choker(
+        choker,
+        torrent_status.clone(),
+        global_shut_down.clone(),
+        local_shut_down.clone(),
     );
 
-    let handler_local_peer_1 = handle_interaction_starting_as_client(
+    let handler_incoming_connections = handle_incoming_connections(
         (
             torrent_file_data.clone(),
-            tracker_response.clone(),
             config_data.clone(),
             peer_id.clone(),
         ),
-        torrent_status.clone(),
-        list_connected_peers_1,
+        incoming_peers,
+        shared_torrent_data.clone(),
         logger_sender.clone(),
         ui_sender.clone(),
         global_shut_down.clone(),
         local_shut_down.clone(),
     );
 
-    let handler_local_peer_2 = handle_interaction_starting_as_client(
-        (
-            torrent_file_data.clone(),
-            tracker_response.clone(),
-            config_data.clone(),
-            peer_id,
-        ),
-        torrent_status,
-        list_connected_peers_2,
+    let handler_outgoing_connections = handle_outgoing_connections(
+        (torrent_file_data.clone(), config_data.clone(), peer_id),
+        shared_torrent_data,
         logger_sender.clone(),
         ui_sender.clone(),
         global_shut_down,
-        local_shut_down,
+        local_shut_down.clone(),
     );
 
-    let result_local_peer_1 = handler_local_peer_0.join().map_err(|_| {
-        InteractionHandlerError::JoinHandle(
-            "[InteractionHandlerError] Join handle error".to_string(),
-        )
-    });
-    let result_local_peer_2 = handler_local_peer_1.join().map_err(|_| {
-        InteractionHandlerError::JoinHandle(
-            "[InteractionHandlerError] Join handle error".to_string(),
-        )
-    });
-    let result_local_peer_3 = handler_local_peer_2.join().map_err(|_| {
-        InteractionHandlerError::JoinHandle(
-            "[InteractionHandlerError] Join handle error".to_string(),
-        )
-    });
+    let result_outgoing_connections = join_handlers(vec![handler_outgoing_connections]);
+    set_shut_down(local_shut_down)?;
+    let result_incoming_connections = join_handlers(vec![handler_incoming_connections]);
+    let result_choker = join_handlers(vec![handler_choker]);
+    let result_seeding = join_handlers(vec![handler_seeding]);
+    let result_announces = join_handlers(vec![handler_announces]);
+    let result_dht_lookups = join_handlers(handlers_dht_lookups);
+
+    save_download_state(torrent_file_data, &torrent_status, config_data)?;
 
-    result_local_peer_1??;
-    result_local_peer_2??;
-    result_local_peer_3??;
+    result_incoming_connections?;
+    result_outgoing_connections?;
+    result_choker?;
+    result_seeding?;
+    result_announces?;
+    result_dht_lookups?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test_handler_communication {
+    use super::*;
+    use crate::torrent::{
+        client::peers_communication::local_peer_communicator::generate_peer_id,
+        data::tracker_response_data::PeerDataFromTrackerResponse,
+    };
+    use gtk::glib;
+    use shared::{parsers::p2p::message::PieceStatus, torrent_file_data::TargetFilesData};
+    use std::{error::Error, io::ErrorKind, net::TcpListener, sync::mpsc};
+
+    const SECS_WAITING_FOR_CONNECTION: u64 = 5;
+
+    fn create_torrent_file_data() -> TorrentFileData {
+        TorrentFileData {
+            target_files_data: TargetFilesData::SingleFile {
+                file_name: "outgoing_connections.test".to_string(),
+                file_length: 16384,
+            },
+            sha1_pieces: vec![0; 20],
+            url_tracker_main: "tracker_main.com".to_string(),
+            url_tracker_list: vec![],
+            sha1_info_hash: vec![1; 20],
+            piece_length: 16384,
+            total_amount_of_pieces: 1,
+            total_length: 16384,
+        }
+    }
+
+    fn create_empty_tracker_response() -> TrackerResponseData {
+        TrackerResponseData {
+            interval: 0,
+            min_interval: None,
+            tracker_id: None,
+            complete: 0,
+            incomplete: 0,
+            peers: vec![],
+        }
+    }
+
+    #[test]
+    fn outgoing_connections_wait_for_peers_added_later_to_the_pool_ok() -> Result<(), Box<dyn Error>>
+    {
+        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let torrent_file_data = create_torrent_file_data();
+        let torrent_status = TorrentStatus {
+            uploaded: 0,
+            downloaded: 0,
+            left: 16384,
+            event: StateOfDownload::Started,
+            pieces_availability: vec![PieceStatus::MissingPiece {
+                was_requested: false,
+            }],
+            pieces_peer_count: vec![0],
+            pieces_requesters_count: vec![0],
+        };
+        let connection_manager = Arc::new(RwLock::new(ConnectionManager::new(
+            &create_empty_tracker_response(),
+            config_data.get_max_peers_per_torrent(),
+            config_data.get_max_half_open(),
+        )));
+        let (logger_sender, _logger_receiver) = mpsc::channel();
+        let (ui_sender, _ui_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let local_shut_down = Arc::new(RwLock::new(false));
+
+        let handler = handle_outgoing_connections(
+            (torrent_file_data.clone(), config_data, generate_peer_id()),
+            (
+                Arc::new(RwLock::new(torrent_status)),
+                Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS))),
+                connection_manager.clone(),
+                Arc::new(TargetFiles::new(&torrent_file_data, "temp/test_client")?),
+            ),
+            logger_sender,
+            ui_sender,
+            Arc::new(RwLock::new(false)),
+            local_shut_down.clone(),
+        );
+        thread::sleep(Duration::from_millis(1500));
+        assert!(!handler.is_finished());
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        connection_manager
+            .write()
+            .map_err(|err| format!("{:?}", err))?
+            .add_peers(&[PeerDataFromTrackerResponse {
+                peer_id: None,
+                peer_address: listener.local_addr()?,
+            }]);
+
+        let deadline = Instant::now() + Duration::from_secs(SECS_WAITING_FOR_CONNECTION);
+        let is_connected = loop {
+            match listener.accept() {
+                Ok(_) => break true,
+                Err(err) if err.kind() == ErrorKind::WouldBlock && Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(50))
+                }
+                Err(_) => break false,
+            }
+        };
+        assert!(is_connected);
+
+        *local_shut_down
+            .write()
+            .map_err(|err| format!("{:?}", err))? = true;
+        assert_eq!(Ok(()), handler.join().map_err(|err| format!("{:?}", err))?);
+        Ok(())
+    }
+}
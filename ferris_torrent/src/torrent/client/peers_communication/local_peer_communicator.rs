@@ -6,19 +6,29 @@ extern crate rand;
 
 use crate::torrent::{
     client::{
-        block_handler,
-        peers_communication::{msg_receiver, msg_sender},
+        block_handler::{self, BlockHandlerError, TargetFiles},
+        peers_communication::{
+            choker::Choker,
+            connection_health::ConnectionHealth,
+            extensions::{ExtendedMsgToSend, ExtensionRegistry},
+            fast_extension::{self, FastExtension, ALLOWED_FAST_SET_SIZE},
+            msg_receiver, msg_sender,
+            peer_listener::IncomingPeer,
+            upload_queue::{self, UploadQueue},
+        },
+        resume_handler,
     },
     data::{
-        peer_data_for_communication::PeerDataForP2PCommunication, torrent_status::TorrentStatus,
-        tracker_response_data::TrackerResponseData,
+        peer_data_for_communication::PeerDataForP2PCommunication, piece_picker::PiecePicker,
+        torrent_status::TorrentStatus, tracker_response_data::TrackerResponseData,
     },
     user_interface::{constants::MessageUI, ui_sender_handler},
 };
 
 use shared::{
     parsers::p2p::{
-        constants::PSTR_STRING_HANDSHAKE,
+        self,
+        constants::{ID_EXTENDED_HANDSHAKE, PSTR_STRING_HANDSHAKE},
         message::{P2PMessage, PieceStatus},
     },
     torrent_file_data::TorrentFileData,
@@ -32,7 +42,7 @@ use std::{
     fmt,
     net::{SocketAddr, TcpStream},
     sync::{Arc, RwLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use std::{sync::mpsc::Sender as LoggerSender, time::SystemTime};
 
@@ -41,8 +51,12 @@ use std::{sync::mpsc::Sender as LoggerSender, time::SystemTime};
 const SIZE_PEER_ID: usize = 12;
 const INIT_PEER_ID: &str = "-FA0000-";
 
-pub const SECS_READ_TIMEOUT: u64 = 10; //120 debe estar
+/// Tiempo maximo de espera al leer un mensaje ya comenzado. El tiempo que se espera a que el
+/// peer externo envie un mensaje nuevo lo define el peer_idle_timeout de la configuracion.
+pub const SECS_READ_TIMEOUT: u64 = 10;
 pub const SECS_CONNECT_TIMEOUT: u64 = 10;
+pub const SECS_WAITING_FOR_MESSAGE: u64 = 1;
+pub const MILLIS_WAITING_WITH_QUEUED_UPLOADS: u64 = 10;
 
 //========================================================
 
@@ -56,6 +70,31 @@ pub struct LocalPeerCommunicator {
     pub logger_sender: LoggerSender<String>,
     pub ui_sender: UiSender<MessageUI>,
     pub clock: SystemTime,
+    pub pending_requests: Vec<BlockRequest>,
+    pub request_queue_depth: usize,
+    pub piece_picker: Arc<dyn PiecePicker>,
+    pub extensions: ExtensionRegistry,
+    pub fast_extension: FastExtension,
+    pub upload_queue: UploadQueue,
+    pub connection_health: ConnectionHealth,
+}
+
+/// Configuracion de como se piden las piezas a los peers externos y de cuanto tiempo
+/// se espera a que respondan
+#[derive(Debug, Clone)]
+pub struct RequestSettings {
+    pub request_queue_depth: usize,
+    pub piece_picker: Arc<dyn PiecePicker>,
+    pub peer_idle_timeout: Duration,
+    pub request_timeout: Duration,
+}
+
+/// Representa un bloque pedido a un peer externo del cual todavia no se recibio el mensaje Piece
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BlockRequest {
+    pub piece_index: usize,
+    pub beginning_byte_index: u32,
+    pub amount_of_bytes: u32,
 }
 
 ///Rol que puede tomar un local peer communicator dentro de la interaccion con peers externos
@@ -119,6 +158,9 @@ pub enum InteractionHandlerError {
     CalculatingTime(String),
     PiecesHandler(String),
     RecommunicatingWithTracker(String),
+    UpdatingChoker(String),
+    UpdatingConnectionManager(String),
+    HandlingExtension(String),
 }
 
 impl fmt::Display for InteractionHandlerError {
@@ -238,6 +280,7 @@ fn generate_peer_data_from_handshake_torrent_peer(
         protocol_str: server_protocol_str,
         info_hash: server_info_hash,
         peer_id: server_peer_id,
+        ..
     } = message
     {
         check_handshake(torrent_file_data, server_protocol_str, &server_info_hash)?;
@@ -267,6 +310,7 @@ fn generate_peer_data_from_handshake_new_peer(
         protocol_str: server_protocol_str,
         info_hash: server_info_hash,
         peer_id: server_peer_id,
+        ..
     } = message
     {
         check_handshake(torrent_file_data, server_protocol_str, &server_info_hash)?;
@@ -283,6 +327,26 @@ fn generate_peer_data_from_handshake_new_peer(
     }
 }
 
+/// Funcion que indica si el peer externo anuncio en su handshake que soporta el extension protocol
+///
+fn peer_supports_extensions(message: &P2PMessage) -> bool {
+    match message {
+        P2PMessage::Handshake { reserved, .. } => {
+            p2p::decoder::supports_extension_protocol(reserved)
+        }
+        _ => false,
+    }
+}
+
+/// Funcion que indica si el peer externo anuncio en su handshake que soporta la fast extension
+///
+fn peer_supports_fast_extension(message: &P2PMessage) -> bool {
+    match message {
+        P2PMessage::Handshake { reserved, .. } => p2p::decoder::supports_fast_extension(reserved),
+        _ => false,
+    }
+}
+
 fn log_info_msg(msg: &P2PMessage) {
     match &msg {
         P2PMessage::Piece {
@@ -294,6 +358,9 @@ fn log_info_msg(msg: &P2PMessage) {
             piece_index, beginning_byte_index
         ),
         P2PMessage::Bitfield { bitfield: _ } => info!("Mensaje recibido: Bitfield"),
+        P2PMessage::Extended { ext_id, .. } => {
+            info!("Mensaje recibido: Extended[ext_id: {}]", ext_id)
+        }
         _ => info!("Mensaje recibido: {:?}", msg),
     }
 }
@@ -337,6 +404,7 @@ impl LocalPeerCommunicator {
         peer_id: Vec<u8>,
         logger_sender: LoggerSender<String>,
         ui_sender: UiSender<MessageUI>,
+        request_settings: RequestSettings,
     ) -> Result<Self, InteractionHandlerErrorKind> {
         let (mut local_peer_stream, external_peer_addr) =
             open_connection_with_peer(tracker_response, tracker_response_peer_index)?;
@@ -359,6 +427,9 @@ impl LocalPeerCommunicator {
             })?;
         info!("Mensaje recibido: Handshake.");
 
+        let mut extensions = ExtensionRegistry::new();
+        extensions.set_peer_supports_extensions(peer_supports_extensions(&received_handshake));
+        let fast_extension = FastExtension::new(peer_supports_fast_extension(&received_handshake));
         let external_peer_data = generate_peer_data_from_handshake_torrent_peer(
             received_handshake,
             torrent_file_data,
@@ -389,29 +460,43 @@ impl LocalPeerCommunicator {
             logger_sender,
             ui_sender,
             clock: time,
+            pending_requests: vec![],
+            request_queue_depth: request_settings.request_queue_depth,
+            piece_picker: request_settings.piece_picker,
+            extensions,
+            fast_extension,
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::new(
+                request_settings.peer_idle_timeout,
+                request_settings.request_timeout,
+            ),
         })
     }
 
     ///
-    /// A partir de un stream dado, comienza la comunicacion con un peer externo segun protocolo de server.
+    /// A partir de una conexion entrante (de la cual el listener ya leyo el handshake), comienza la
+    /// comunicacion con un peer externo segun protocolo de server.
     /// En caso de cumplir con el correcto envio y recepcion de mensajes segun protocolo, se procede a crear un
     /// LocalPeerCommunicator.
     ///
     pub fn start_communication_as_server(
         torrent_file_data: &TorrentFileData,
         peer_id: Vec<u8>,
-        mut stream: TcpStream,
-        external_peer_addr: SocketAddr,
+        incoming_peer: IncomingPeer,
         logger_sender: LoggerSender<String>,
         ui_sender: UiSender<MessageUI>,
+        request_settings: RequestSettings,
     ) -> Result<Self, InteractionHandlerErrorKind> {
-        let received_handshake = msg_receiver::receive_handshake(&mut stream).map_err(|error| {
-            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::ReceivingHanshake(
-                format!("{}", error),
-            ))
-        })?;
+        let IncomingPeer {
+            mut stream,
+            external_peer_addr,
+            handshake: received_handshake,
+        } = incoming_peer;
         info!("Mensaje recibido: Handshake.");
 
+        let mut extensions = ExtensionRegistry::new();
+        extensions.set_peer_supports_extensions(peer_supports_extensions(&received_handshake));
+        let fast_extension = FastExtension::new(peer_supports_fast_extension(&received_handshake));
         let external_peer_data =
             generate_peer_data_from_handshake_new_peer(received_handshake, torrent_file_data)?;
 
@@ -445,6 +530,16 @@ impl LocalPeerCommunicator {
             logger_sender,
             ui_sender,
             clock: time,
+            pending_requests: vec![],
+            request_queue_depth: request_settings.request_queue_depth,
+            piece_picker: request_settings.piece_picker,
+            extensions,
+            fast_extension,
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::new(
+                request_settings.peer_idle_timeout,
+                request_settings.request_timeout,
+            ),
         })
     }
 
@@ -458,12 +553,47 @@ impl LocalPeerCommunicator {
         &mut self,
         torrent_file_data: &TorrentFileData,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
+        target_files: &TargetFiles,
+        choker: &Arc<RwLock<Choker>>,
         global_shut_down: &Arc<RwLock<bool>>,
         local_shut_down: &Arc<RwLock<bool>>,
     ) -> Result<InteractionHandlerStatus, InteractionHandlerErrorKind> {
         self.send_bitfield_if_necessary(torrent_status)?;
+        self.send_allowed_fast_set_if_supported(torrent_file_data, torrent_status)?;
+        self.send_extended_handshake_if_supported()?;
 
         loop {
+            self.apply_choker_decision(torrent_file_data, choker)?;
+            self.send_extension_msgs()?;
+            self.send_keep_alive_if_necessary()?;
+            self.release_requests_if_snubbed(torrent_status)?;
+            self.cancel_already_downloaded_requests(torrent_status)?;
+            self.send_queued_blocks(torrent_file_data, torrent_status, choker, target_files)?;
+
+            // Si quedan bloques por enviar no se espera a que llegue un mensaje, sino que
+            // solo se revisa si llego alguno (por ejemplo, un Cancel) antes del proximo envio.
+            let waiting_time = if self.upload_queue.is_empty() {
+                Duration::from_secs(SECS_WAITING_FOR_MESSAGE)
+            } else {
+                Duration::from_millis(MILLIS_WAITING_WITH_QUEUED_UPLOADS)
+            };
+            if !self.wait_for_message(waiting_time)? {
+                if self.connection_health.is_idle_at(Instant::now()) {
+                    return Err(InteractionHandlerErrorKind::Recoverable(
+                        InteractionHandlerError::ReceivingMessage(
+                            "The external peer did not send messages in time.".to_string(),
+                        ),
+                    ));
+                }
+                if is_local_shut_down_set(local_shut_down)? {
+                    return Ok(InteractionHandlerStatus::SecureLocalShutDown);
+                } else if is_global_shut_down_set(global_shut_down)? {
+                    return Ok(InteractionHandlerStatus::SecureGlobalShutDown);
+                }
+                continue;
+            }
+            self.connection_health.message_received_at(Instant::now());
+
             let received_msg =
                 msg_receiver::receive_message(&mut self.stream).map_err(|error| {
                     InteractionHandlerErrorKind::Recoverable(
@@ -475,9 +605,12 @@ impl LocalPeerCommunicator {
             self.update_information_according_to_the_received_msg(
                 torrent_file_data,
                 torrent_status,
+                target_files,
                 &received_msg,
             )?;
 
+            self.update_choker_stats(choker, &received_msg)?;
+
             self.react_according_to_the_peer_role(
                 torrent_file_data,
                 torrent_status,
@@ -496,8 +629,12 @@ impl LocalPeerCommunicator {
                     format!("{:?}", error),
                 ))
             })?;
-            if torrent_status.is_torrent_state_set_as_completed() && !self.peer_interested() {
-                return Ok(InteractionHandlerStatus::FinishInteraction);
+            if torrent_status.is_torrent_state_set_as_completed() {
+                // Mientras se comparte el torrent solo se corta la interaccion con peers que
+                // ya tienen todo; al resto se los sigue atendiendo por si nos piden bloques.
+                if !self.peer_interested() && self.external_peer_data.has_all_pieces() {
+                    return Ok(InteractionHandlerStatus::FinishInteraction);
+                }
             } else if !self.am_interested() && !self.peer_interested() {
                 info!("Se busca un nuevo peer al cual pedirle piezas");
                 return Ok(InteractionHandlerStatus::LookForAnotherPeer);
@@ -507,6 +644,113 @@ impl LocalPeerCommunicator {
 
     //FUNCIONES SECUNDARIAS
 
+    fn wait_for_message(
+        &self,
+        waiting_time: Duration,
+    ) -> Result<bool, InteractionHandlerErrorKind> {
+        msg_receiver::wait_for_message(&self.stream, waiting_time).map_err(|error| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::ReceivingMessage(
+                format!("{:?}", error),
+            ))
+        })
+    }
+
+    ///
+    /// Funcion que le envia un keep alive al peer externo si la conexion lleva en silencio
+    /// el intervalo de keep alive, para que no nos desconecte.
+    ///
+    fn send_keep_alive_if_necessary(&mut self) -> Result<(), InteractionHandlerErrorKind> {
+        if !self.connection_health.poll_keep_alive_at(Instant::now()) {
+            return Ok(());
+        }
+        info!("Mensaje enviado: KeepAlive");
+        msg_sender::send_keep_alive(&mut self.stream).map_err(|err| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                format!("{}", err),
+            ))
+        })
+    }
+
+    //CHOKING
+
+    ///
+    /// Funcion que informa al choker los datos del peer externo que se desprenden del
+    /// mensaje recibido: su interes en nuestras piezas y los bytes transferidos.
+    ///
+    fn update_choker_stats(
+        &self,
+        choker: &Arc<RwLock<Choker>>,
+        received_msg: &P2PMessage,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        let mut choker = choker.write().map_err(|error| {
+            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::UpdatingChoker(
+                format!("{:?}", error),
+            ))
+        })?;
+        let peer_id = &self.external_peer_data.peer_id;
+        match received_msg {
+            P2PMessage::Interested => choker.update_peer_interest(peer_id, true),
+            P2PMessage::NotInterested => choker.update_peer_interest(peer_id, false),
+            P2PMessage::Piece { block, .. } => {
+                choker.add_downloaded_bytes(peer_id, block.len() as u64)
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    ///
+    /// Funcion que indica si la ultima decision del choker no coincide con el estado actual,
+    /// es decir, si hay que enviarle un Choke o un Unchoke al peer externo.
+    ///
+    fn is_choker_decision_pending(
+        &self,
+        choker: &Arc<RwLock<Choker>>,
+    ) -> Result<bool, InteractionHandlerErrorKind> {
+        let is_unchoked = choker
+            .read()
+            .map_err(|error| {
+                InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::UpdatingChoker(
+                    format!("{:?}", error),
+                ))
+            })?
+            .is_unchoked(&self.external_peer_data.peer_id);
+        Ok(is_unchoked == self.am_choking())
+    }
+
+    ///
+    /// Funcion que envia un Choke o un Unchoke al peer externo si la ultima
+    /// decision del choker no coincide con el estado actual.
+    ///
+    fn apply_choker_decision(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        choker: &Arc<RwLock<Choker>>,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        if !self.is_choker_decision_pending(choker)? {
+            return Ok(());
+        }
+        // Si la decision esta pendiente, el choker quiere lo contrario del estado actual.
+        let is_unchoked = self.am_choking();
+
+        if is_unchoked {
+            info!("Mensaje enviado: Unchoke");
+            msg_sender::send_unchoke(&mut self.stream)
+        } else {
+            info!("Mensaje enviado: Choke");
+            msg_sender::send_choke(&mut self.stream)
+        }
+        .map_err(|err| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                format!("{}", err),
+            ))
+        })?;
+        if !is_unchoked {
+            self.reject_queued_requests()?;
+        }
+        self.update_am_choking_field(torrent_file_data, !is_unchoked)
+    }
+
     fn send_bitfield_if_necessary(
         &mut self,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
@@ -517,16 +761,153 @@ impl LocalPeerCommunicator {
             ))
         })?;
 
-        if torrent_status.all_pieces_left() {
-            msg_sender::send_bitfield(&mut self.stream, &torrent_status).map_err(|error| {
-                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
-                    format!("{:?}", error),
+        // Con la fast extension siempre se informan las piezas, usando Have All o Have None
+        // en lugar de un Bitfield cuando se tienen todas o ninguna.
+        let send_result = if self.fast_extension.peer_supports_fast() {
+            if torrent_status.all_pieces_completed() {
+                info!("Mensaje enviado: Have All");
+                msg_sender::send_have_all(&mut self.stream)
+            } else if !torrent_status.all_pieces_left() {
+                info!("Mensaje enviado: Have None");
+                msg_sender::send_have_none(&mut self.stream)
+            } else {
+                msg_sender::send_bitfield(&mut self.stream, &torrent_status)
+            }
+        } else if torrent_status.all_pieces_left() {
+            msg_sender::send_bitfield(&mut self.stream, &torrent_status)
+        } else {
+            Ok(())
+        };
+        send_result.map_err(|error| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                format!("{:?}", error),
+            ))
+        })
+    }
+
+    ///
+    /// Funcion que, si el peer externo soporta la fast extension, le envia las piezas de su
+    /// allowed fast set que tenemos. Dichas piezas las puede pedir aunque lo tengamos chokeado,
+    /// lo cual le permite a un peer nuevo empezar a descargar.
+    ///
+    fn send_allowed_fast_set_if_supported(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        if !self.fast_extension.peer_supports_fast() {
+            return Ok(());
+        }
+        let external_peer_addr = self.stream.peer_addr().map_err(|error| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                format!("{}", error),
+            ))
+        })?;
+        let allowed_fast_set = fast_extension::generate_allowed_fast_set(
+            external_peer_addr.ip(),
+            &torrent_file_data.get_info_hash(),
+            torrent_file_data.get_total_amount_pieces(),
+            ALLOWED_FAST_SET_SIZE,
+        );
+        let torrent_status = torrent_status.read().map_err(|error| {
+            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::SendingMessage(
+                format!("{:?}", error),
+            ))
+        })?;
+
+        for piece_index in allowed_fast_set {
+            if !torrent_status.is_a_valid_and_available_piece(piece_index) {
+                continue;
+            }
+            let converted_piece_index = u32::try_from(piece_index).map_err(|error| {
+                InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::SendingMessage(
+                    format!("{}", error),
                 ))
             })?;
+            msg_sender::send_allowed_fast(&mut self.stream, converted_piece_index).map_err(
+                |error| {
+                    InteractionHandlerErrorKind::Recoverable(
+                        InteractionHandlerError::SendingMessage(format!("{}", error)),
+                    )
+                },
+            )?;
+            self.fast_extension.add_allowed_fast_for_peer(piece_index);
+        }
+        Ok(())
+    }
+
+    //EXTENSIONS
+
+    ///
+    /// Funcion que envia el handshake extendido con las extensiones registradas, solo si el
+    /// peer externo soporta el extension protocol.
+    ///
+    fn send_extended_handshake_if_supported(&mut self) -> Result<(), InteractionHandlerErrorKind> {
+        if !self.extensions.peer_supports_extensions() {
+            return Ok(());
+        }
+        let extended_handshake = self.extensions.create_extended_handshake();
+        info!("Mensaje enviado: Extended Handshake");
+        msg_sender::send_extended(
+            &mut self.stream,
+            ID_EXTENDED_HANDSHAKE,
+            extended_handshake.to_payload(),
+        )
+        .map_err(|error| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                format!("{}", error),
+            ))
+        })
+    }
+
+    fn send_extended_msgs(
+        &mut self,
+        msgs_to_send: Vec<ExtendedMsgToSend>,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        for msg in msgs_to_send {
+            msg_sender::send_extended(&mut self.stream, msg.ext_id, msg.payload).map_err(
+                |error| {
+                    InteractionHandlerErrorKind::Recoverable(
+                        InteractionHandlerError::SendingMessage(format!("{}", error)),
+                    )
+                },
+            )?;
         }
         Ok(())
     }
 
+    ///
+    /// Funcion que deriva un mensaje Extended recibido a la extension registrada que
+    /// corresponda y envia sus respuestas.
+    ///
+    fn react_to_received_extended_msg(
+        &mut self,
+        ext_id: u8,
+        payload: &[u8],
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        let msgs_to_send = self
+            .extensions
+            .handle_extended_msg(ext_id, payload)
+            .map_err(|error| {
+                InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::HandlingExtension(format!("{}", error)),
+                )
+            })?;
+        self.send_extended_msgs(msgs_to_send)
+    }
+
+    ///
+    /// Funcion que envia los mensajes que las extensiones quieren enviar por iniciativa propia.
+    ///
+    fn send_extension_msgs(&mut self) -> Result<(), InteractionHandlerErrorKind> {
+        let msgs_to_send = self.extensions.poll_messages().map_err(|error| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::HandlingExtension(
+                format!("{}", error),
+            ))
+        })?;
+        self.send_extended_msgs(msgs_to_send)
+    }
+
     //BITFIELD
 
     ///
@@ -552,6 +933,28 @@ impl LocalPeerCommunicator {
         Ok(())
     }
 
+    ///
+    /// Funcion que reemplaza las piezas del peer externo por las del bitfield recibido (ya sea
+    /// por un mensaje Bitfield, Have All o Have None) y actualiza el conteo de peers por pieza.
+    ///
+    fn react_to_received_bitfield(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+        bitfield: &[PieceStatus],
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        let previous_pieces_availability = self.external_peer_data.pieces_availability.clone();
+        self.update_peer_bitfield(torrent_file_data, bitfield)?;
+        let mut torrent_status = torrent_status.write().map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::UpdatingBitfield(
+                format!("{:?}", err),
+            ))
+        })?;
+        torrent_status.remove_peer_pieces(&previous_pieces_availability);
+        torrent_status.add_peer_pieces(&self.external_peer_data.pieces_availability);
+        Ok(())
+    }
+
     // HAVE
 
     ///
@@ -583,49 +986,38 @@ impl LocalPeerCommunicator {
         beginning_byte_index: u32,
     ) -> Result<InterestOfReceivedPieceMsg, InteractionHandlerErrorKind> {
         match torrent_status.get_piece_status(piece_index) {
-            Some(piece_status) => match *piece_status {
-                PieceStatus::MissingPiece {
-                    was_requested: true,
-                } => Ok(InterestOfReceivedPieceMsg::IsCorrectlyAsRequested),
-                PieceStatus::MissingPiece {
-                    was_requested: false,
-                } => Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::StoringBlock(
-                        "[InteractionHandlerError] The received piece was not requested."
-                            .to_string(),
-                    ),
-                )),
-                PieceStatus::PartiallyDownloaded {
-                    was_requested: false,
-                    ..
-                } => Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::StoringBlock(
-                        "[InteractionHandlerError] The received piece was not requested."
-                            .to_string(),
-                    ),
-                )),
-                PieceStatus::PartiallyDownloaded {
-                    downloaded_bytes,
-                    was_requested: true,
-                } if beginning_byte_index > downloaded_bytes => {
-                    Err(InteractionHandlerErrorKind::Recoverable(
+            Some(PieceStatus::ValidAndAvailablePiece) => {
+                Ok(InterestOfReceivedPieceMsg::AlreadyDownloaded)
+            }
+            Some(_) if torrent_status.is_a_downloaded_block(piece_index, beginning_byte_index) => {
+                Ok(InterestOfReceivedPieceMsg::AlreadyDownloaded)
+            }
+            Some(_) => {
+                let mut requested_blocks_of_the_piece = self
+                    .pending_requests
+                    .iter()
+                    .filter(|block_request| block_request.piece_index == piece_index)
+                    .peekable();
+                if requested_blocks_of_the_piece.peek().is_none() {
+                    return Err(InteractionHandlerErrorKind::Recoverable(
                         InteractionHandlerError::StoringBlock(
-                            "[InteractionHandlerError] The beginning byte index is incorrect."
+                            "[InteractionHandlerError] The received piece was not requested."
                                 .to_string(),
                         ),
-                    ))
-                }
-                PieceStatus::PartiallyDownloaded {
-                    downloaded_bytes,
-                    was_requested: true,
-                } if beginning_byte_index < downloaded_bytes => {
-                    Ok(InterestOfReceivedPieceMsg::AlreadyDownloaded)
+                    ));
                 }
-                PieceStatus::ValidAndAvailablePiece => {
-                    Ok(InterestOfReceivedPieceMsg::AlreadyDownloaded)
+                if !requested_blocks_of_the_piece
+                    .any(|block_request| block_request.beginning_byte_index == beginning_byte_index)
+                {
+                    return Err(InteractionHandlerErrorKind::Recoverable(
+                        InteractionHandlerError::StoringBlock(
+                            "[InteractionHandlerError] The beginning byte index is incorrect."
+                                .to_string(),
+                        ),
+                    ));
                 }
-                _ => Ok(InterestOfReceivedPieceMsg::IsCorrectlyAsRequested),
-            },
+                Ok(InterestOfReceivedPieceMsg::IsCorrectlyAsRequested)
+            }
             None => Err(InteractionHandlerErrorKind::Recoverable(
                 InteractionHandlerError::StoringBlock(
                     "[InteractionHandlerError] The received piece index is invalid.".to_string(),
@@ -636,30 +1028,17 @@ impl LocalPeerCommunicator {
 
     fn check_block_lenght(
         &self,
-        torrent_file_data: &TorrentFileData,
-        torrent_status: &TorrentStatus,
         piece_index: usize,
         beginning_byte_index: u32,
         block: &[u8],
     ) -> Result<InterestOfReceivedPieceMsg, InteractionHandlerErrorKind> {
-        let expected_amount_of_bytes = torrent_status
-            .calculate_amount_of_bytes_of_block(
-                torrent_file_data,
-                piece_index,
-                beginning_byte_index,
-            )
-            .map_err(|err| {
-                InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::StoringBlock(
-                    format!("{}", err),
-                ))
-            })?
-            .try_into()
-            .map_err(|err| {
-                InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::StoringBlock(
-                    format!("{}", err),
-                ))
-            })?;
-        if block.len() != expected_amount_of_bytes {
+        let is_expected_length = self.pending_requests.iter().any(|block_request| {
+            block_request.piece_index == piece_index
+                && block_request.beginning_byte_index == beginning_byte_index
+                && usize::try_from(block_request.amount_of_bytes)
+                    .is_ok_and(|amount_of_bytes| amount_of_bytes == block.len())
+        });
+        if !is_expected_length {
             return Err(InteractionHandlerErrorKind::Recoverable(
                 InteractionHandlerError::StoringBlock(
                     "[InteractionHandlerError] Block length is not as expected".to_string(),
@@ -671,7 +1050,6 @@ impl LocalPeerCommunicator {
 
     fn check_store_block(
         &self,
-        torrent_file_data: &TorrentFileData,
         torrent_status: &TorrentStatus,
         piece_index: usize,
         beginning_byte_index: u32,
@@ -688,33 +1066,101 @@ impl LocalPeerCommunicator {
             }
             Err(error) => return Err(error),
         }
-        self.check_block_lenght(
-            torrent_file_data,
-            torrent_status,
-            piece_index,
-            beginning_byte_index,
-            block,
-        )?;
+        self.check_block_lenght(piece_index, beginning_byte_index, block)?;
         Ok(InterestOfReceivedPieceMsg::IsCorrectlyAsRequested)
     }
 
+    ///
+    /// Funcion que quita de los pedidos pendientes al bloque recibido y, si ya no quedan
+    /// bloques pendientes de su pieza sin que esta se haya completado, libera dicha pieza
+    /// para que pueda volver a pedirse (salvo que otro peer todavia tenga pedidos de la misma).
+    ///
+    fn remove_pending_request(
+        &mut self,
+        torrent_status: &mut TorrentStatus,
+        piece_index: usize,
+        beginning_byte_index: u32,
+    ) {
+        self.pending_requests.retain(|block_request| {
+            block_request.piece_index != piece_index
+                || block_request.beginning_byte_index != beginning_byte_index
+        });
+        if !self.has_pending_requests_of_piece(piece_index) {
+            torrent_status.remove_piece_requester(piece_index);
+        }
+    }
+
+    fn has_pending_requests_of_piece(&self, piece_index: usize) -> bool {
+        self.pending_requests
+            .iter()
+            .any(|block_request| block_request.piece_index == piece_index)
+    }
+
+    ///
+    /// Funcion que descarta todos los pedidos pendientes al peer externo, liberando las piezas
+    /// correspondientes (que ningun otro peer tenga pedidas) para que puedan pedirse nuevamente.
+    /// Se utiliza cuando el peer externo nos chokea o cuando finaliza la interaccion con el mismo.
+    ///
+    pub fn release_pending_requests(&mut self, torrent_status: &mut TorrentStatus) {
+        let mut requested_pieces: Vec<usize> = self
+            .pending_requests
+            .drain(..)
+            .map(|block_request| block_request.piece_index)
+            .collect();
+        requested_pieces.sort_unstable();
+        requested_pieces.dedup();
+        for piece_index in requested_pieces {
+            torrent_status.remove_piece_requester(piece_index);
+        }
+    }
+
+    ///
+    /// Funcion que libera todo lo que el peer externo aporta al estado del torrent
+    /// al terminar la interaccion: sus pedidos pendientes y sus piezas en el conteo
+    /// de peers por pieza.
+    ///
+    pub fn release_external_peer_resources(&mut self, torrent_status: &mut TorrentStatus) {
+        self.release_pending_requests(torrent_status);
+        torrent_status.remove_peer_pieces(&self.external_peer_data.pieces_availability);
+    }
+
     fn check_piece(
         &self,
         torrent_file_data: &TorrentFileData,
         torrent_status: &mut TorrentStatus,
-        path: &str,
+        target_files: &TargetFiles,
         piece_index: usize,
     ) -> Result<(), InteractionHandlerErrorKind> {
         if torrent_status.is_a_valid_and_available_piece(piece_index) {
             info!("Se completó la pieza {}.", piece_index);
             info!("Verifico el hash SHA1 de la pieza descargada.");
-            block_handler::check_sha1_piece(torrent_file_data, piece_index, path).map_err(
-                |err| {
-                    InteractionHandlerErrorKind::Unrecoverable(
+            match block_handler::check_sha1_piece(torrent_file_data, piece_index, target_files) {
+                Ok(()) => (),
+                Err(BlockHandlerError::IncorrectSha1Piece(err)) => {
+                    torrent_status
+                        .set_piece_as_missing(torrent_file_data, piece_index)
+                        .map_err(|err| {
+                            InteractionHandlerErrorKind::Unrecoverable(
+                                InteractionHandlerError::StoringBlock(format!("{}", err)),
+                            )
+                        })?;
+                    return Err(InteractionHandlerErrorKind::Recoverable(
+                        InteractionHandlerError::StoringBlock(err),
+                    ));
+                }
+                Err(err) => {
+                    return Err(InteractionHandlerErrorKind::Unrecoverable(
                         InteractionHandlerError::StoringBlock(format!("{}", err)),
-                    )
-                },
-            )?;
+                    ))
+                }
+            }
+            if let Err(err) = resume_handler::save_resume_data(
+                torrent_file_data,
+                torrent_status,
+                target_files.get_download_path(),
+            ) {
+                info!("No se pudo guardar el estado de la descarga: {}", err);
+            }
             self.logger_sender
                 .send(format!("[OK] Se completó la pieza número {}.", piece_index))
                 .map_err(|err| {
@@ -746,10 +1192,10 @@ impl LocalPeerCommunicator {
     /// a dicha pieza en el .torrent
     ///
     fn store_block(
-        &self,
+        &mut self,
         torrent_file_data: &TorrentFileData,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
-        path: &str,
+        target_files: &TargetFiles,
         piece_index: usize,
         beginning_byte_index: u32,
         block: Vec<u8>,
@@ -759,18 +1205,34 @@ impl LocalPeerCommunicator {
                 format!("{}", err),
             ))
         })?;
-        match self.check_store_block(
-            torrent_file_data,
-            &torrent_status,
-            piece_index,
-            beginning_byte_index,
-            &block,
-        ) {
-            Ok(InterestOfReceivedPieceMsg::AlreadyDownloaded) => return Ok(()),
-            Ok(InterestOfReceivedPieceMsg::IsCorrectlyAsRequested) => (),
+        let is_a_pending_request = self.pending_requests.iter().any(|block_request| {
+            block_request.piece_index == piece_index
+                && block_request.beginning_byte_index == beginning_byte_index
+        });
+        if !is_a_pending_request
+            && self
+                .connection_health
+                .take_cancelled_request(piece_index, beginning_byte_index)
+        {
+            info!("Se ignora un bloque cancelado al quedar snubbed el peer externo");
+            return Ok(());
+        }
+        match self.check_store_block(&torrent_status, piece_index, beginning_byte_index, &block) {
+            Ok(InterestOfReceivedPieceMsg::AlreadyDownloaded) => {
+                self.remove_pending_request(&mut torrent_status, piece_index, beginning_byte_index);
+                return Ok(());
+            }
+            Ok(InterestOfReceivedPieceMsg::IsCorrectlyAsRequested) => (),
             Err(error) => return Err(error),
         };
-        block_handler::store_block(&block, piece_index, path).map_err(|err| {
+        block_handler::store_block(
+            torrent_file_data,
+            piece_index,
+            beginning_byte_index,
+            &block,
+            target_files,
+        )
+        .map_err(|err| {
             InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::StoringBlock(
                 format!("{}", err),
             ))
@@ -793,7 +1255,14 @@ impl LocalPeerCommunicator {
                 ))
             })?;
 
-        self.check_piece(torrent_file_data, &mut torrent_status, path, piece_index)?;
+        self.remove_pending_request(&mut torrent_status, piece_index, beginning_byte_index);
+
+        self.check_piece(
+            torrent_file_data,
+            &mut torrent_status,
+            target_files,
+            piece_index,
+        )?;
 
         let download_duration = self.clock.elapsed().map_err(|err| {
             InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::CalculatingTime(
@@ -843,6 +1312,30 @@ impl LocalPeerCommunicator {
         Ok(())
     }
 
+    ///
+    ///  Funcion que actualiza si mi cliente tiene chokeado a un peer
+    ///
+    fn update_am_choking_field(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+
+        new_value: bool,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        self.external_peer_data.am_choking = new_value;
+        ui_sender_handler::update_peers_state(
+            &self.ui_sender,
+            torrent_file_data,
+            &self.external_peer_data,
+        )
+        .map_err(|error| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::UiError(format!(
+                "{}",
+                error
+            )))
+        })?;
+        Ok(())
+    }
+
     ///
     ///  Funcion que actualiza si un peer me tiene chokeado a mi cliente
     ///
@@ -935,7 +1428,8 @@ impl LocalPeerCommunicator {
         &self,
         position: usize,
     ) -> bool {
-        self.external_peer_data.pieces_availability[position] == PieceStatus::ValidAndAvailablePiece
+        self.external_peer_data.pieces_availability.get(position)
+            == Some(&PieceStatus::ValidAndAvailablePiece)
     }
 
     fn react_to_received_piece_msg(
@@ -945,9 +1439,9 @@ impl LocalPeerCommunicator {
         block: &[u8],
         torrent_file_data: &TorrentFileData,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
+        target_files: &TargetFiles,
     ) -> Result<(), InteractionHandlerErrorKind> {
         let block = block.to_vec();
-        let temp_path_name = torrent_file_data.get_torrent_representative_name();
         let piece_index = piece_index.try_into().map_err(|err| {
             InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::StoringBlock(
                 format!("{}", err),
@@ -956,7 +1450,7 @@ impl LocalPeerCommunicator {
         self.store_block(
             torrent_file_data,
             torrent_status,
-            &temp_path_name,
+            target_files,
             piece_index,
             beginning_byte_index,
             block,
@@ -974,11 +1468,26 @@ impl LocalPeerCommunicator {
         &mut self,
         torrent_file_data: &TorrentFileData,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
+        target_files: &TargetFiles,
         received_msg: &P2PMessage,
     ) -> Result<(), InteractionHandlerErrorKind> {
         match received_msg {
             P2PMessage::KeepAlive => Ok(()),
-            P2PMessage::Choke => self.update_peer_choking_field(torrent_file_data, true),
+            P2PMessage::Choke => {
+                self.update_peer_choking_field(torrent_file_data, true)?;
+                if self.fast_extension.peer_supports_fast() {
+                    // Con la fast extension el Choke no descarta los pedidos pendientes,
+                    // sino que el peer externo rechaza cada uno con un Reject Request.
+                    return Ok(());
+                }
+                let mut torrent_status = torrent_status.write().map_err(|err| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", err)),
+                    )
+                })?;
+                self.release_pending_requests(&mut torrent_status);
+                Ok(())
+            }
             P2PMessage::Unchoke => self.update_peer_choking_field(torrent_file_data, false),
             P2PMessage::Interested => {
                 self.update_peer_interested_field(torrent_file_data, true)?;
@@ -987,18 +1496,114 @@ impl LocalPeerCommunicator {
             P2PMessage::NotInterested => {
                 self.update_peer_interested_field(torrent_file_data, false)
             }
-            P2PMessage::Have { piece_index } => self.update_server_peer_piece_status(
-                usize::try_from(*piece_index).map_err(|err| {
+            P2PMessage::Have { piece_index } => {
+                let piece_index = usize::try_from(*piece_index).map_err(|err| {
                     InteractionHandlerErrorKind::Unrecoverable(
                         InteractionHandlerError::LookingForPieces(format!("{}", err)),
                     )
-                })?,
-                PieceStatus::ValidAndAvailablePiece,
-            ),
+                })?;
+                let is_a_new_piece =
+                    !self.external_peer_has_a_valid_and_available_piece_on_position(piece_index);
+                self.update_server_peer_piece_status(
+                    piece_index,
+                    PieceStatus::ValidAndAvailablePiece,
+                )?;
+                if is_a_new_piece {
+                    let mut torrent_status = torrent_status.write().map_err(|err| {
+                        InteractionHandlerErrorKind::Unrecoverable(
+                            InteractionHandlerError::UpdatingPieceStatus(format!("{:?}", err)),
+                        )
+                    })?;
+                    torrent_status.add_peer_piece(piece_index);
+                }
+                Ok(())
+            }
             P2PMessage::Bitfield { bitfield } => {
-                self.update_peer_bitfield(torrent_file_data, bitfield)
+                self.react_to_received_bitfield(torrent_file_data, torrent_status, bitfield)
+            }
+            P2PMessage::HaveAll => self.react_to_received_bitfield(
+                torrent_file_data,
+                torrent_status,
+                &vec![
+                    PieceStatus::ValidAndAvailablePiece;
+                    torrent_file_data.get_total_amount_pieces()
+                ],
+            ),
+            P2PMessage::HaveNone => self.react_to_received_bitfield(
+                torrent_file_data,
+                torrent_status,
+                &vec![
+                    PieceStatus::MissingPiece {
+                        was_requested: false
+                    };
+                    torrent_file_data.get_total_amount_pieces()
+                ],
+            ),
+            P2PMessage::SuggestPiece { piece_index } => {
+                let piece_index = usize::try_from(*piece_index).map_err(|err| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::LookingForPieces(format!("{}", err)),
+                    )
+                })?;
+                self.fast_extension.add_suggested_piece(piece_index);
+                Ok(())
+            }
+            P2PMessage::AllowedFast { piece_index } => {
+                let piece_index = usize::try_from(*piece_index).map_err(|err| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::LookingForPieces(format!("{}", err)),
+                    )
+                })?;
+                if piece_index >= torrent_file_data.get_total_amount_pieces() {
+                    info!(
+                        "Se ignora un Allowed Fast con un indice de pieza invalido: {}",
+                        piece_index
+                    );
+                    return Ok(());
+                }
+                self.fast_extension.add_allowed_fast_from_peer(piece_index);
+                Ok(())
+            }
+            P2PMessage::RejectRequest {
+                piece_index,
+                beginning_byte_index,
+                ..
+            } => {
+                let piece_index = usize::try_from(*piece_index).map_err(|err| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::UpdatingWasRequestedField(format!("{}", err)),
+                    )
+                })?;
+                let mut torrent_status = torrent_status.write().map_err(|err| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", err)),
+                    )
+                })?;
+                self.remove_pending_request(
+                    &mut torrent_status,
+                    piece_index,
+                    *beginning_byte_index,
+                );
+                Ok(())
             }
             P2PMessage::Request { .. } => self.set_up_peer_roll_as_server(),
+            P2PMessage::Cancel {
+                piece_index,
+                beginning_byte_index,
+                amount_of_bytes,
+            } => {
+                let piece_index = usize::try_from(*piece_index).map_err(|err| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                    )
+                })?;
+                self.upload_queue.cancel(&BlockRequest {
+                    piece_index,
+                    beginning_byte_index: *beginning_byte_index,
+                    amount_of_bytes: *amount_of_bytes,
+                });
+                Ok(())
+            }
             P2PMessage::Piece {
                 piece_index,
                 beginning_byte_index,
@@ -1010,325 +1615,806 @@ impl LocalPeerCommunicator {
                     block,
                     torrent_file_data,
                     torrent_status,
+                    target_files,
                 )?;
+                self.connection_health
+                    .block_received_at(Instant::now(), !self.pending_requests.is_empty());
                 Ok(())
             }
+            P2PMessage::Extended { ext_id, payload } => {
+                self.react_to_received_extended_msg(*ext_id, payload)
+            }
             _ => Ok(()),
         }?;
         Ok(())
     }
 
     //LOOK FOR PIECES AND SEND MESSAGE
-    fn send_msg_according_to_peer_choking_field(
-        &mut self,
-        piece_index: usize,
+
+    ///
+    /// Funcion que busca, dentro de una pieza, el primer bloque que falta descargar
+    /// y que todavia no fue pedido al peer externo.
+    ///
+    fn look_for_a_block_to_request(
+        &self,
         torrent_file_data: &TorrentFileData,
-        torrent_status: &mut TorrentStatus,
-    ) -> Result<(), InteractionHandlerErrorKind> {
-        if self.peer_choking() {
-            info!("Mensaje enviado: Interested");
-            msg_sender::send_interested(&mut self.stream).map_err(|err| {
-                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
-                    format!("{}", err),
-                ))
-            })?;
-        } else {
-            msg_sender::send_request(
-                &mut self.stream,
-                torrent_file_data,
-                torrent_status,
-                piece_index,
-            )
+        torrent_status: &TorrentStatus,
+        piece_index: usize,
+    ) -> Result<Option<BlockRequest>, InteractionHandlerErrorKind> {
+        let missing_blocks = torrent_status
+            .look_for_missing_blocks(torrent_file_data, piece_index)
             .map_err(|err| {
-                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
-                    format!("{}", err),
-                ))
+                InteractionHandlerErrorKind::Unrecoverable(
+                    InteractionHandlerError::LookingForPieces(format!("{}", err)),
+                )
             })?;
-            torrent_status
-                .set_piece_as_requested(piece_index)
+
+        for beginning_byte_index in missing_blocks {
+            if self.pending_requests.iter().any(|block_request| {
+                block_request.piece_index == piece_index
+                    && block_request.beginning_byte_index == beginning_byte_index
+            }) {
+                continue;
+            }
+            let amount_of_bytes = torrent_status
+                .calculate_amount_of_bytes_of_block(
+                    torrent_file_data,
+                    piece_index,
+                    beginning_byte_index,
+                )
                 .map_err(|err| {
                     InteractionHandlerErrorKind::Unrecoverable(
-                        InteractionHandlerError::SendingMessage(format!("{}", err)),
+                        InteractionHandlerError::LookingForPieces(format!("{}", err)),
                     )
                 })?;
+            return Ok(Some(BlockRequest {
+                piece_index,
+                beginning_byte_index,
+                amount_of_bytes,
+            }));
         }
-
-        Ok(())
+        Ok(None)
     }
 
     ///
-    /// Se realiza una busqueda de las piezas faltantes y se las compara con las que posee el peer con el cual
-    /// estamos interactuando. En base a esta coparación se define si quedan piezas por pedir y el mensaje a enviar en caso de que corresponda:
-    /// En el caso de que el LocalPeerCommunicator este Choke -> se le envía un Interested
-    /// En el caso de que el LocalPeerCommunicator este Unchoke -> se le envía una Request
+    /// Funcion que busca el siguiente bloque a pedir. Primero se completan las piezas que ya
+    /// tienen pedidos pendientes y, en caso de no quedar bloques por pedir en ellas, se reserva
+    /// una nueva pieza faltante que posea el peer externo.
     ///
-    fn look_for_pieces(
-        &mut self,
+    fn look_for_the_next_block_to_request(
+        &self,
         torrent_file_data: &TorrentFileData,
-        torrent_status: &Arc<RwLock<TorrentStatus>>,
-    ) -> Result<(), InteractionHandlerErrorKind> {
-        let mut torrent_status = torrent_status.write().map_err(|err| {
-            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::StoringBlock(
-                format!("{}", err),
-            ))
-        })?;
-        let piece_index = match torrent_status.look_for_a_missing_piece_index(&*self) {
+        torrent_status: &mut TorrentStatus,
+    ) -> Result<Option<BlockRequest>, InteractionHandlerErrorKind> {
+        let mut requested_pieces: Vec<usize> = self
+            .pending_requests
+            .iter()
+            .map(|block_request| block_request.piece_index)
+            .filter(|piece_index| self.can_request_blocks_of_piece(*piece_index))
+            .collect();
+        requested_pieces.dedup();
+
+        for piece_index in requested_pieces {
+            if let Some(block_request) =
+                self.look_for_a_block_to_request(torrent_file_data, torrent_status, piece_index)?
+            {
+                return Ok(Some(block_request));
+            }
+        }
+
+        let next_piece_index = match self.look_for_a_preferred_piece_index(torrent_status) {
+            Some(piece_index) => Some(piece_index),
+            None if self.peer_choking() => None,
+            None => torrent_status.look_for_a_missing_piece_index(self),
+        };
+        match next_piece_index {
             Some(piece_index) => {
-                self.update_am_interested_field(torrent_file_data, true)?;
-                piece_index
+                torrent_status
+                    .set_piece_as_requested(piece_index)
+                    .map_err(|err| {
+                        InteractionHandlerErrorKind::Unrecoverable(
+                            InteractionHandlerError::SendingMessage(format!("{}", err)),
+                        )
+                    })?;
+                self.look_for_a_block_to_request(torrent_file_data, torrent_status, piece_index)
             }
-            None => {
-                self.update_am_interested_field(torrent_file_data, false)?;
-                msg_sender::send_not_interested(&mut self.stream).map_err(|err| {
-                    InteractionHandlerErrorKind::Recoverable(
-                        InteractionHandlerError::SendingMessage(format!("{}", err)),
-                    )
-                })?;
-                return Ok(());
+            None if torrent_status.is_in_endgame() => {
+                self.look_for_an_endgame_block_to_request(torrent_file_data, torrent_status)
             }
-        };
-
-        self.send_msg_according_to_peer_choking_field(
-            piece_index,
-            torrent_file_data,
-            &mut torrent_status,
-        )?;
+            None => Ok(None),
+        }
+    }
 
-        Ok(())
+    ///
+    /// Funcion que devuelve las piezas que, en modo endgame, se le pueden pedir al peer externo:
+    /// las que faltan completar, ya fueron pedidas a algun peer y el peer externo posee.
+    ///
+    fn get_endgame_pieces_to_request(&self, torrent_status: &TorrentStatus) -> Vec<usize> {
+        if !torrent_status.is_in_endgame() {
+            return vec![];
+        }
+        torrent_status
+            .get_requested_pieces_left()
+            .into_iter()
+            .filter(|piece_index| {
+                self.external_peer_has_a_valid_and_available_piece_on_position(*piece_index)
+            })
+            .collect()
     }
 
-    //REQUEST
-    fn check_requested_block(
-        &mut self,
+    ///
+    /// Funcion que, en modo endgame, busca un bloque que falte descargar de las piezas ya pedidas
+    /// a otros peers, para pedirselo tambien al peer externo. Cuando el bloque llegue por
+    /// cualquiera de ellos, el resto de los peers lo cancela.
+    ///
+    fn look_for_an_endgame_block_to_request(
+        &self,
         torrent_file_data: &TorrentFileData,
         torrent_status: &TorrentStatus,
-        piece_index: u32,
-        beginning_byte_index: u32,
-        amount_of_bytes: u32,
-    ) -> Result<(), InteractionHandlerErrorKind> {
-        let piece_index = usize::try_from(piece_index).map_err(|err| {
-            InteractionHandlerErrorKind::Unrecoverable(
-                InteractionHandlerError::SendingRequestedBlock(format!("{:?}", err)),
-            )
-        })?;
-
-        if !torrent_status.is_a_valid_and_available_piece(piece_index) {
-            return Err(InteractionHandlerErrorKind::Recoverable(
-                InteractionHandlerError::SendingRequestedBlock(
-                    "[InteractionHandlerError] The local peer does not have the requested block."
-                        .to_string(),
-                ),
-            ));
-        }
-        if self.am_choking() {
-            return Err(InteractionHandlerErrorKind::Recoverable(
-                InteractionHandlerError::SendingRequestedBlock(
-                    "[InteractionHandlerError] The external peer who send the request is choked."
-                        .to_string(),
-                ),
-            ));
+    ) -> Result<Option<BlockRequest>, InteractionHandlerErrorKind> {
+        for piece_index in self.get_endgame_pieces_to_request(torrent_status) {
+            if !self.can_request_blocks_of_piece(piece_index) {
+                continue;
+            }
+            if let Some(block_request) =
+                self.look_for_a_block_to_request(torrent_file_data, torrent_status, piece_index)?
+            {
+                return Ok(Some(block_request));
+            }
         }
-
-        torrent_file_data
-            .check_requested_block(piece_index, beginning_byte_index, amount_of_bytes)
-            .map_err(|err| {
-                InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(format!("{:?}", err)),
-                )
-            })?;
-
-        Ok(())
+        Ok(None)
     }
 
     ///
-    /// Verificacion de mensaje recibido y envio del bloque solicitado.
+    /// Funcion que envia un Cancel por cada bloque pedido al peer externo que ya fue descargado
+    /// (por ejemplo, porque en modo endgame llego antes por otro peer), y lo quita de los
+    /// pedidos pendientes.
     ///
-    fn send_requested_block(
+    fn cancel_already_downloaded_requests(
         &mut self,
-        torrent_file_data: &TorrentFileData,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
-        piece_index: u32,
-        beginning_byte_index: u32,
-        amount_of_bytes: u32,
     ) -> Result<(), InteractionHandlerErrorKind> {
-        let temp_path_name = torrent_file_data.get_torrent_representative_name();
+        if self.pending_requests.is_empty() {
+            return Ok(());
+        }
         let mut torrent_status = torrent_status.write().map_err(|err| {
             InteractionHandlerErrorKind::Unrecoverable(
-                InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", err)),
             )
         })?;
-        self.check_requested_block(
-            torrent_file_data,
-            &torrent_status,
-            piece_index,
-            beginning_byte_index,
-            amount_of_bytes,
-        )?;
+        let downloaded_requests: Vec<BlockRequest> = self
+            .pending_requests
+            .iter()
+            .filter(|block_request| {
+                torrent_status.is_a_downloaded_block(
+                    block_request.piece_index,
+                    block_request.beginning_byte_index,
+                )
+            })
+            .cloned()
+            .collect();
+
+        for block_request in downloaded_requests {
+            self.send_cancel(&block_request)?;
+            self.remove_pending_request(
+                &mut torrent_status,
+                block_request.piece_index,
+                block_request.beginning_byte_index,
+            );
+        }
+        Ok(())
+    }
 
-        let block = block_handler::get_block(
+    fn send_cancel(
+        &mut self,
+        block_request: &BlockRequest,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        let piece_index = u32::try_from(block_request.piece_index).map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::SendingMessage(
+                format!("{}", err),
+            ))
+        })?;
+        info!(
+            "Mensaje enviado: Cancel[piece_index: {}, beginning_byte_index: {}]",
+            piece_index, block_request.beginning_byte_index
+        );
+        msg_sender::send_cancel(
+            &mut self.stream,
             piece_index,
-            beginning_byte_index,
-            amount_of_bytes,
-            &temp_path_name,
+            block_request.beginning_byte_index,
+            block_request.amount_of_bytes,
         )
         .map_err(|err| {
-            InteractionHandlerErrorKind::Unrecoverable(
-                InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
-            )
-        })?;
-
-        msg_sender::send_piece(&mut self.stream, piece_index, beginning_byte_index, block)
-            .map_err(|err| {
-                InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
-                )
-            })?;
-
-        torrent_status.increment_uploaded_counter(amount_of_bytes.into());
-
-        let upload_duration = self.clock.elapsed().map_err(|err| {
-            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::CalculatingTime(
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
                 format!("{}", err),
             ))
-        })?;
-        ui_sender_handler::update_upload_data(
-            &self.ui_sender,
-            torrent_file_data,
-            &self.external_peer_data,
-            torrent_status.get_uploaded_bytes(),
-            upload_duration,
-        )
-        .map_err(|err| {
-            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::UiError(format!(
-                "{}",
-                err
-            )))
-        })?;
+        })
+    }
 
+    ///
+    /// Funcion que, si el peer externo acaba de quedar snubbed por no entregarnos a tiempo los
+    /// bloques pedidos, cancela dichos pedidos y libera sus piezas para que puedan pedirse a
+    /// otros peers. Mientras siga snubbed, solo se le pide un bloque a la vez.
+    ///
+    fn release_requests_if_snubbed(
+        &mut self,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        if !self
+            .connection_health
+            .check_snubbed_at(Instant::now(), !self.pending_requests.is_empty())
+        {
+            return Ok(());
+        }
+        info!("El peer externo quedo snubbed, se liberan sus pedidos pendientes");
+        let cancelled_requests = self.pending_requests.clone();
+        for block_request in cancelled_requests.iter() {
+            self.send_cancel(block_request)?;
+        }
+        let mut torrent_status = torrent_status.write().map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(
+                InteractionHandlerError::UpdatingWasRequestedField(format!("{:?}", err)),
+            )
+        })?;
+        self.release_pending_requests(&mut torrent_status);
+        self.connection_health
+            .set_cancelled_requests(cancelled_requests);
         Ok(())
     }
 
     ///
-    /// Envia un mesaje dependiendo del mensaje que recibio:
-    /// Si recibió un Interested -> Envia un Unchoke
-    /// Si recibió un Request -> Envia el bloque solicitado
+    /// Funcion que indica si se le pueden pedir bloques de una pieza al peer externo: siempre
+    /// que no nos tenga chokeados o, en caso contrario, si la pieza esta en su allowed fast set.
     ///
-    fn send_msg_according_to_the_received_msg(
+    fn can_request_blocks_of_piece(&self, piece_index: usize) -> bool {
+        !self.peer_choking() || self.fast_extension.is_allowed_fast_from_peer(piece_index)
+    }
+
+    ///
+    /// Funcion que busca, entre las piezas que nos indico el peer externo por la fast extension,
+    /// una pieza faltante para pedir. Si nos tiene chokeados solo se consideran las piezas de su
+    /// allowed fast set, y si no, las piezas que nos sugirio.
+    ///
+    fn look_for_a_preferred_piece_index(&self, torrent_status: &TorrentStatus) -> Option<usize> {
+        let preferred_pieces = if self.peer_choking() {
+            self.fast_extension.get_allowed_fast_from_peer()
+        } else {
+            self.fast_extension.get_suggested_pieces().to_vec()
+        };
+        preferred_pieces.into_iter().find(|piece_index| {
+            torrent_status.is_a_piece_to_request(*piece_index)
+                && self.external_peer_has_a_valid_and_available_piece_on_position(*piece_index)
+        })
+    }
+
+    ///
+    /// Funcion que envia mensajes Request hasta completar la cola de pedidos pendientes
+    /// (o hasta que no queden bloques por pedir al peer externo).
+    ///
+    fn fill_requests_queue(
         &mut self,
         torrent_file_data: &TorrentFileData,
-        torrent_status: &Arc<RwLock<TorrentStatus>>,
-        received_msg: &P2PMessage,
+        torrent_status: &mut TorrentStatus,
     ) -> Result<(), InteractionHandlerErrorKind> {
-        match received_msg {
-            P2PMessage::Interested => {
-                msg_sender::send_unchoke(&mut self.stream).map_err(|err| {
-                    InteractionHandlerErrorKind::Recoverable(
-                        InteractionHandlerError::SendingMessage(format!("{}", err)),
-                    )
-                })?;
-                self.external_peer_data.am_choking = false;
-                Ok(())
-            }
-            P2PMessage::Request {
+        let request_queue_depth = self
+            .connection_health
+            .get_request_queue_depth(self.request_queue_depth);
+        while self.pending_requests.len() < request_queue_depth {
+            let block_request =
+                match self.look_for_the_next_block_to_request(torrent_file_data, torrent_status)? {
+                    Some(block_request) => block_request,
+                    None => break,
+                };
+            let piece_index = u32::try_from(block_request.piece_index).map_err(|err| {
+                InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::SendingMessage(
+                    format!("{}", err),
+                ))
+            })?;
+            msg_sender::send_request(
+                &mut self.stream,
                 piece_index,
-                beginning_byte_index,
-                amount_of_bytes,
-            } => self.send_requested_block(
-                torrent_file_data,
-                torrent_status,
-                *piece_index,
-                *beginning_byte_index,
-                *amount_of_bytes,
-            ),
-            P2PMessage::Cancel {
-                piece_index: _,
-                beginning_byte_index: _,
-                amount_of_bytes: _,
-            } => Ok(()),
-            _ => Ok(()),
-        }?;
-        self.set_up_peer_roll_as_client()
+                block_request.beginning_byte_index,
+                block_request.amount_of_bytes,
+            )
+            .map_err(|err| {
+                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                    format!("{}", err),
+                ))
+            })?;
+            if !self.has_pending_requests_of_piece(block_request.piece_index) {
+                torrent_status
+                    .add_piece_requester(block_request.piece_index)
+                    .map_err(|err| {
+                        InteractionHandlerErrorKind::Unrecoverable(
+                            InteractionHandlerError::SendingMessage(format!("{}", err)),
+                        )
+                    })?;
+            }
+            self.pending_requests.push(block_request);
+            self.connection_health.block_requested_at(Instant::now());
+        }
+        Ok(())
+    }
+
+    fn send_msg_according_to_peer_choking_field(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &mut TorrentStatus,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        if self.peer_choking() {
+            info!("Mensaje enviado: Interested");
+            msg_sender::send_interested(&mut self.stream).map_err(|err| {
+                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                    format!("{}", err),
+                ))
+            })?;
+            // Aun chokeados, se pueden pedir las piezas del allowed fast set del peer externo.
+            self.fill_requests_queue(torrent_file_data, torrent_status)?;
+        } else {
+            self.fill_requests_queue(torrent_file_data, torrent_status)?;
+        }
+
+        Ok(())
     }
 
     ///
-    /// Accionar respecto al rol actual:
-    /// Rol cliente -> Se buscan piezas para pedir
-    /// Rol server -> Se responde el mensajes correspondiente al mensaje recibido
+    /// Se realiza una busqueda de las piezas faltantes y se las compara con las que posee el peer con el cual
+    /// estamos interactuando. En base a esta coparación se define si quedan piezas por pedir y el mensaje a enviar en caso de que corresponda:
+    /// En el caso de que el LocalPeerCommunicator este Choke -> se le envía un Interested (y Requests de las piezas
+    /// de su allowed fast set, si soporta la fast extension)
+    /// En el caso de que el LocalPeerCommunicator este Unchoke -> se le envían Requests hasta completar la cola de pedidos pendientes
     ///
-    fn react_according_to_the_peer_role(
+    fn look_for_pieces(
         &mut self,
         torrent_file_data: &TorrentFileData,
         torrent_status: &Arc<RwLock<TorrentStatus>>,
-        received_msg: &P2PMessage,
     ) -> Result<(), InteractionHandlerErrorKind> {
-        match self.role {
-            PeerRole::Client => self.look_for_pieces(torrent_file_data, torrent_status),
-            PeerRole::Server => self.send_msg_according_to_the_received_msg(
-                torrent_file_data,
-                torrent_status,
-                received_msg,
-            ),
+        let mut torrent_status = torrent_status.write().map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(InteractionHandlerError::StoringBlock(
+                format!("{}", err),
+            ))
+        })?;
+        if self.pending_requests.is_empty()
+            && torrent_status
+                .look_for_a_missing_piece_index(&*self)
+                .is_none()
+            && self
+                .get_endgame_pieces_to_request(&torrent_status)
+                .is_empty()
+        {
+            self.update_am_interested_field(torrent_file_data, false)?;
+            msg_sender::send_not_interested(&mut self.stream).map_err(|err| {
+                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                    format!("{}", err),
+                ))
+            })?;
+            return Ok(());
         }
-    }
-}
+        self.update_am_interested_field(torrent_file_data, true)?;
 
-#[cfg(test)]
-mod test_local_peer {
-    use gtk::glib;
+        self.send_msg_according_to_peer_choking_field(torrent_file_data, &mut torrent_status)?;
 
-    use super::*;
-    use std::{
-        error::Error,
-        fmt,
-        net::{SocketAddr, TcpStream},
-        str::FromStr,
-        sync::mpsc,
-        thread,
-    };
+        Ok(())
+    }
 
-    use crate::torrent::data::{
-        torrent_status::{StateOfDownload, TorrentStatus},
-        tracker_response_data::{PeerDataFromTrackerResponse, TrackerResponseData},
-    };
+    //REQUEST
+    fn check_requested_block(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &TorrentStatus,
+        piece_index: u32,
+        beginning_byte_index: u32,
+        amount_of_bytes: u32,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        let piece_index = usize::try_from(piece_index).map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(
+                InteractionHandlerError::SendingRequestedBlock(format!("{:?}", err)),
+            )
+        })?;
 
-    use shared::{
-        parsers::p2p::{
-            constants::PSTR_STRING_HANDSHAKE,
-            message::{P2PMessage, PieceStatus},
-        },
-        port_binder::listener_binder::*,
-        torrent_file_data::TargetFilesData,
-    };
+        if !torrent_status.is_a_valid_and_available_piece(piece_index) {
+            return Err(InteractionHandlerErrorKind::Recoverable(
+                InteractionHandlerError::SendingRequestedBlock(
+                    "[InteractionHandlerError] The local peer does not have the requested block."
+                        .to_string(),
+                ),
+            ));
+        }
+        if self.am_choking() && !self.fast_extension.is_allowed_fast_for_peer(piece_index) {
+            return Err(InteractionHandlerErrorKind::Recoverable(
+                InteractionHandlerError::SendingRequestedBlock(
+                    "[InteractionHandlerError] The external peer who send the request is choked."
+                        .to_string(),
+                ),
+            ));
+        }
 
-    #[derive(PartialEq, Eq, Debug, Clone)]
-    pub enum TestingError {
-        ClientPeerFieldsInvalidAccess(String),
+        torrent_file_data
+            .check_requested_block(piece_index, beginning_byte_index, amount_of_bytes)
+            .map_err(|err| {
+                InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{:?}", err)),
+                )
+            })?;
+
+        Ok(())
     }
 
-    impl fmt::Display for TestingError {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "\n    {:#?}\n", self)
+    ///
+    /// Verificacion de mensaje recibido y encolado del bloque solicitado, el cual se envia
+    /// luego desde la cola de envios. Si la cola esta llena, se rechaza el pedido.
+    ///
+    fn queue_requested_block(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+        piece_index: u32,
+        beginning_byte_index: u32,
+        amount_of_bytes: u32,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        {
+            let torrent_status = torrent_status.read().map_err(|err| {
+                InteractionHandlerErrorKind::Unrecoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                )
+            })?;
+            self.check_requested_block(
+                torrent_file_data,
+                &torrent_status,
+                piece_index,
+                beginning_byte_index,
+                amount_of_bytes,
+            )?;
+        }
+
+        let block_request = BlockRequest {
+            piece_index: usize::try_from(piece_index).map_err(|err| {
+                InteractionHandlerErrorKind::Unrecoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                )
+            })?,
+            beginning_byte_index,
+            amount_of_bytes,
+        };
+        if !self.upload_queue.push(block_request) {
+            info!("La cola de envios del peer externo esta llena");
+            self.reject_request(piece_index, beginning_byte_index, amount_of_bytes)?;
         }
+        Ok(())
     }
 
-    impl Error for TestingError {}
+    ///
+    /// Funcion que indica si hay que interrumpir el envio de un lote: ya sea porque llego un
+    /// mensaje del peer externo (por ejemplo, un Cancel) o porque el choker decidio chokearlo.
+    ///
+    fn should_interrupt_upload(
+        &self,
+        choker: &Arc<RwLock<Choker>>,
+    ) -> Result<bool, InteractionHandlerErrorKind> {
+        let is_message_available =
+            msg_receiver::is_message_available(&self.stream).map_err(|error| {
+                InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::ReceivingMessage(
+                    format!("{:?}", error),
+                ))
+            })?;
+        Ok(is_message_available || self.is_choker_decision_pending(choker)?)
+    }
 
-    pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
-    pub const DEFAULT_CLIENT_PEER_ID: &str = "-FA0001-000000000000";
-    pub const DEFAULT_SERVER_PEER_ID: &str = "-FA0001-000000000001";
-    pub const DEFAULT_INFO_HASH: [u8; 20] = [0; 20];
+    ///
+    /// Funcion que envia el proximo lote de la cola de envios: todos los bloques encolados de
+    /// una misma pieza, los cuales se leen de disco de una sola vez. Los bytes de cada bloque
+    /// enviado se le informan al choker. Entre bloque y bloque se revisa si llego un mensaje o
+    /// si cambio la decision del choker; en ese caso los bloques restantes vuelven a la cola
+    /// para atender antes el mensaje o el Choke. Si no se pueden leer los bloques de disco, se
+    /// rechazan los pedidos del lote y se corta solo la conexion con este peer externo.
+    ///
+    fn send_queued_blocks(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+        choker: &Arc<RwLock<Choker>>,
+        target_files: &TargetFiles,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        let batch = self.upload_queue.take_next_batch();
+        let merged_request = match upload_queue::merge_batch(&batch) {
+            Some(merged_request) => merged_request,
+            None => return Ok(()),
+        };
+        let piece_index = u32::try_from(merged_request.piece_index).map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(
+                InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+            )
+        })?;
 
-    fn create_default_client_peer_with_unused_server_peer(
-        num_of_test: u32,
-    ) -> Result<
-        (
-            TrackerResponseData,
-            Arc<RwLock<TorrentStatus>>,
-            TorrentFileData,
-            LocalPeerCommunicator,
-            mpsc::Receiver<String>,
-            glib::Receiver<MessageUI>,
-        ),
+        let bytes = block_handler::get_block(
+            torrent_file_data,
+            piece_index,
+            merged_request.beginning_byte_index,
+            merged_request.amount_of_bytes,
+            target_files,
+        );
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                for block_request in batch {
+                    self.reject_request(
+                        piece_index,
+                        block_request.beginning_byte_index,
+                        block_request.amount_of_bytes,
+                    )?;
+                }
+                return Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                ));
+            }
+        };
+
+        let mut torrent_status = torrent_status.write().map_err(|err| {
+            InteractionHandlerErrorKind::Unrecoverable(
+                InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+            )
+        })?;
+        let mut unsent_requests = batch.into_iter();
+        while let Some(block_request) = unsent_requests.next() {
+            let block_start = usize::try_from(
+                block_request.beginning_byte_index - merged_request.beginning_byte_index,
+            )
+            .map_err(|err| {
+                InteractionHandlerErrorKind::Unrecoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                )
+            })?;
+            let block_end = usize::try_from(block_request.amount_of_bytes)
+                .ok()
+                .and_then(|amount_of_bytes| block_start.checked_add(amount_of_bytes))
+                .filter(|block_end| *block_end <= bytes.len())
+                .ok_or_else(|| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::SendingRequestedBlock(
+                            "[InteractionHandlerError] The read bytes do not contain the requested block."
+                                .to_string(),
+                        ),
+                    )
+                })?;
+
+            msg_sender::send_piece(
+                &mut self.stream,
+                piece_index,
+                block_request.beginning_byte_index,
+                bytes[block_start..block_end].to_vec(),
+            )
+            .map_err(|err| {
+                InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                )
+            })?;
+            torrent_status.increment_uploaded_counter(block_request.amount_of_bytes.into());
+            choker
+                .write()
+                .map_err(|error| {
+                    InteractionHandlerErrorKind::Unrecoverable(
+                        InteractionHandlerError::UpdatingChoker(format!("{:?}", error)),
+                    )
+                })?
+                .add_uploaded_bytes(
+                    &self.external_peer_data.peer_id,
+                    block_request.amount_of_bytes.into(),
+                );
+
+            if unsent_requests.len() > 0 && self.should_interrupt_upload(choker)? {
+                self.upload_queue.requeue(unsent_requests.collect());
+                break;
+            }
+        }
+
+        let upload_duration = self.clock.elapsed().map_err(|err| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::CalculatingTime(
+                format!("{}", err),
+            ))
+        })?;
+        ui_sender_handler::update_upload_data(
+            &self.ui_sender,
+            torrent_file_data,
+            &self.external_peer_data,
+            torrent_status.get_uploaded_bytes(),
+            upload_duration,
+        )
+        .map_err(|err| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::UiError(format!(
+                "{}",
+                err
+            )))
+        })?;
+
+        Ok(())
+    }
+
+    ///
+    /// Funcion que quita de la cola de envios, al chokear al peer externo, los pedidos que
+    /// quedaron sin enviar y los rechaza. Se mantienen los pedidos de piezas de su allowed
+    /// fast set, ya que esas piezas se le siguen enviando aunque este chokeado.
+    ///
+    fn reject_queued_requests(&mut self) -> Result<(), InteractionHandlerErrorKind> {
+        let fast_extension = &self.fast_extension;
+        let choked_requests = self.upload_queue.remove_where(|block_request| {
+            !fast_extension.is_allowed_fast_for_peer(block_request.piece_index)
+        });
+        for block_request in choked_requests {
+            let piece_index = u32::try_from(block_request.piece_index).map_err(|err| {
+                InteractionHandlerErrorKind::Unrecoverable(
+                    InteractionHandlerError::SendingRequestedBlock(format!("{}", err)),
+                )
+            })?;
+            self.reject_request(
+                piece_index,
+                block_request.beginning_byte_index,
+                block_request.amount_of_bytes,
+            )?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Funcion que indica si se debe rechazar un Request del peer externo por tenerlo chokeado
+    /// (salvo que la pieza pedida pertenezca a su allowed fast set).
+    ///
+    fn is_request_choked(&self, piece_index: u32) -> bool {
+        self.am_choking()
+            && !usize::try_from(piece_index)
+                .is_ok_and(|piece_index| self.fast_extension.is_allowed_fast_for_peer(piece_index))
+    }
+
+    ///
+    /// Funcion que rechaza un Request que no se va a atender. Si el peer externo soporta la fast
+    /// extension se le envia un Reject Request; si no, simplemente se ignora el pedido.
+    ///
+    fn reject_request(
+        &mut self,
+        piece_index: u32,
+        beginning_byte_index: u32,
+        amount_of_bytes: u32,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        if !self.fast_extension.peer_supports_fast() {
+            info!(
+                "Se ignora un Request[piece_index: {}, beginning_byte_index: {}]",
+                piece_index, beginning_byte_index
+            );
+            return Ok(());
+        }
+        info!(
+            "Mensaje enviado: Reject Request[piece_index: {}, beginning_byte_index: {}]",
+            piece_index, beginning_byte_index
+        );
+        msg_sender::send_reject_request(
+            &mut self.stream,
+            piece_index,
+            beginning_byte_index,
+            amount_of_bytes,
+        )
+        .map_err(|err| {
+            InteractionHandlerErrorKind::Recoverable(InteractionHandlerError::SendingMessage(
+                format!("{}", err),
+            ))
+        })
+    }
+
+    ///
+    /// Envia un mesaje dependiendo del mensaje que recibio:
+    /// Si recibió un Request -> Encola el bloque solicitado para enviarlo (salvo que el peer externo
+    /// este chokeado, en cuyo caso se rechaza el pedido)
+    /// El Unchoke ante un Interested queda a cargo del [Choker].
+    ///
+    fn send_msg_according_to_the_received_msg(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+        received_msg: &P2PMessage,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        match received_msg {
+            P2PMessage::Request {
+                piece_index,
+                beginning_byte_index,
+                amount_of_bytes,
+            } if self.is_request_choked(*piece_index) => {
+                self.reject_request(*piece_index, *beginning_byte_index, *amount_of_bytes)
+            }
+            P2PMessage::Request {
+                piece_index,
+                beginning_byte_index,
+                amount_of_bytes,
+            } => self.queue_requested_block(
+                torrent_file_data,
+                torrent_status,
+                *piece_index,
+                *beginning_byte_index,
+                *amount_of_bytes,
+            ),
+            _ => Ok(()),
+        }?;
+        self.set_up_peer_roll_as_client()
+    }
+
+    ///
+    /// Accionar respecto al rol actual:
+    /// Rol cliente -> Se buscan piezas para pedir
+    /// Rol server -> Se responde el mensajes correspondiente al mensaje recibido
+    ///
+    fn react_according_to_the_peer_role(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        torrent_status: &Arc<RwLock<TorrentStatus>>,
+        received_msg: &P2PMessage,
+    ) -> Result<(), InteractionHandlerErrorKind> {
+        match self.role {
+            PeerRole::Client => self.look_for_pieces(torrent_file_data, torrent_status),
+            PeerRole::Server => self.send_msg_according_to_the_received_msg(
+                torrent_file_data,
+                torrent_status,
+                received_msg,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_local_peer {
+    use gtk::glib;
+
+    use super::*;
+    use std::{
+        error::Error,
+        fmt,
+        net::{SocketAddr, TcpStream},
+        str::FromStr,
+        sync::mpsc,
+        thread,
+    };
+
+    use crate::torrent::client::peers_communication::choker::DEFAULT_UNCHOKE_SLOTS;
+    use crate::torrent::data::{
+        piece_picker::SequentialPicker,
+        torrent_status::{StateOfDownload, TorrentStatus},
+        tracker_response_data::{PeerDataFromTrackerResponse, TrackerResponseData},
+    };
+
+    use shared::{
+        parsers::p2p::{
+            constants::PSTR_STRING_HANDSHAKE,
+            message::{P2PMessage, PieceStatus},
+        },
+        port_binder::listener_binder::*,
+        torrent_file_data::TargetFilesData,
+    };
+
+    #[derive(PartialEq, Eq, Debug, Clone)]
+    pub enum TestingError {
+        ClientPeerFieldsInvalidAccess(String),
+    }
+
+    impl fmt::Display for TestingError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "\n    {:#?}\n", self)
+        }
+    }
+
+    impl Error for TestingError {}
+
+    pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+    pub const DEFAULT_CLIENT_PEER_ID: &str = "-FA0001-000000000000";
+    pub const DEFAULT_SERVER_PEER_ID: &str = "-FA0001-000000000001";
+    pub const DEFAULT_INFO_HASH: [u8; 20] = [0; 20];
+
+    fn create_default_client_peer_with_unused_server_peer(
+        num_of_test: u32,
+    ) -> Result<
+        (
+            TrackerResponseData,
+            Arc<RwLock<TorrentStatus>>,
+            TorrentFileData,
+            LocalPeerCommunicator,
+            mpsc::Receiver<String>,
+            glib::Receiver<MessageUI>,
+        ),
         Box<dyn Error>,
     > {
         let (listener, address) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
@@ -1345,6 +2431,8 @@ mod test_local_peer {
 
         let tracker_response = TrackerResponseData {
             interval: 0,
+            min_interval: None,
+            tracker_id: None,
             complete: 1,
             incomplete: 0,
             peers: vec![server_peer],
@@ -1357,6 +2445,8 @@ mod test_local_peer {
             pieces_availability: vec![PieceStatus::MissingPiece {
                 was_requested: true,
             }],
+            pieces_peer_count: vec![0; 1],
+            pieces_requesters_count: vec![0; 1],
         };
         let torrent_file = TorrentFileData {
             target_files_data: TargetFilesData::SingleFile {
@@ -1391,6 +2481,13 @@ mod test_local_peer {
             logger_sender: logger_sender,
             ui_sender: ui_sender,
             clock: SystemTime::now(),
+            pending_requests: vec![],
+            request_queue_depth: 5,
+            piece_picker: Arc::new(SequentialPicker),
+            extensions: ExtensionRegistry::new(),
+            fast_extension: FastExtension::default(),
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::default(),
         };
         Ok((
             tracker_response,
@@ -1428,6 +2525,8 @@ mod test_local_peer {
         };
         let tracker_response = TrackerResponseData {
             interval: 0,
+            min_interval: None,
+            tracker_id: None,
             complete: 1,
             incomplete: 0,
             peers: vec![server_peer],
@@ -1445,6 +2544,8 @@ mod test_local_peer {
                     was_requested: true,
                 },
             ],
+            pieces_peer_count: vec![0; 2],
+            pieces_requesters_count: vec![0; 2],
         };
         let torrent_file = TorrentFileData {
             target_files_data: TargetFilesData::SingleFile {
@@ -1488,6 +2589,13 @@ mod test_local_peer {
             logger_sender: logger_sender,
             ui_sender: ui_sender,
             clock: SystemTime::now(),
+            pending_requests: vec![],
+            request_queue_depth: 5,
+            piece_picker: Arc::new(SequentialPicker),
+            extensions: ExtensionRegistry::new(),
+            fast_extension: FastExtension::default(),
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::default(),
         };
         Ok((
             tracker_response,
@@ -1525,6 +2633,8 @@ mod test_local_peer {
         };
         let tracker_response = TrackerResponseData {
             interval: 0,
+            min_interval: None,
+            tracker_id: None,
             complete: 0,
             incomplete: 1,
             peers: vec![server_peer],
@@ -1542,6 +2652,8 @@ mod test_local_peer {
                     was_requested: true,
                 },
             ],
+            pieces_peer_count: vec![0; 2],
+            pieces_requesters_count: vec![0; 2],
         };
         let torrent_file = TorrentFileData {
             target_files_data: TargetFilesData::SingleFile {
@@ -1583,6 +2695,13 @@ mod test_local_peer {
             logger_sender: logger_sender,
             ui_sender: ui_sender,
             clock: SystemTime::now(),
+            pending_requests: vec![],
+            request_queue_depth: 5,
+            piece_picker: Arc::new(SequentialPicker),
+            extensions: ExtensionRegistry::new(),
+            fast_extension: FastExtension::default(),
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::default(),
         };
         Ok((
             tracker_response,
@@ -1594,1036 +2713,1825 @@ mod test_local_peer {
         ))
     }
 
-    fn create_default_client_with_a_piece_for_requests(
-        address: String,
-        num_of_test: u32,
-    ) -> Result<
-        (
-            TrackerResponseData,
-            Arc<RwLock<TorrentStatus>>,
-            TorrentFileData,
-            LocalPeerCommunicator,
-            mpsc::Receiver<String>,
-            glib::Receiver<MessageUI>,
-        ),
-        Box<dyn Error>,
-    > {
-        let stream = TcpStream::connect(address.clone())?;
+    fn create_default_client_with_a_piece_for_requests(
+        address: String,
+        num_of_test: u32,
+    ) -> Result<
+        (
+            TrackerResponseData,
+            Arc<RwLock<TorrentStatus>>,
+            TorrentFileData,
+            LocalPeerCommunicator,
+            mpsc::Receiver<String>,
+            glib::Receiver<MessageUI>,
+        ),
+        Box<dyn Error>,
+    > {
+        let stream = TcpStream::connect(address.clone())?;
+
+        let server_peer = PeerDataFromTrackerResponse {
+            peer_id: Some(DEFAULT_SERVER_PEER_ID.bytes().collect()),
+            peer_address: SocketAddr::from_str(&address)?,
+        };
+        let tracker_response = TrackerResponseData {
+            interval: 0,
+            min_interval: None,
+            tracker_id: None,
+            complete: 0,
+            incomplete: 0,
+            peers: vec![server_peer],
+        };
+        let torrent_status = TorrentStatus {
+            uploaded: 0,
+            downloaded: 40000,
+            left: 0,
+            event: StateOfDownload::Started,
+            pieces_availability: vec![
+                PieceStatus::ValidAndAvailablePiece,
+                PieceStatus::MissingPiece {
+                    was_requested: true,
+                },
+            ],
+            pieces_peer_count: vec![0; 2],
+            pieces_requesters_count: vec![0; 2],
+        };
+        let torrent_file = TorrentFileData {
+            target_files_data: TargetFilesData::SingleFile {
+                file_name: format!("send_requested_block_{}.test", num_of_test),
+                file_length: 40000,
+                //1º pieza -> 34000 bytes
+                //2º pieza ->  6000 bytes
+            },
+            sha1_pieces: vec![
+                46, 101, 88, 42, 242, 153, 87, 30, 42, 117, 240, 135, 191, 37, 12, 42, 175, 156,
+                136, 214, 95, 100, 198, 139, 237, 56, 161, 225, 113, 168, 52, 228, 26, 36, 103,
+                150, 103, 76, 233, 34,
+            ],
+            url_tracker_main: "tracker_main.com".to_string(),
+            url_tracker_list: vec![],
+            sha1_info_hash: DEFAULT_INFO_HASH.to_vec(),
+            piece_length: 34000,
+            total_amount_of_pieces: 2,
+            total_length: 40000,
+        };
+        let server_peer_data = PeerDataForP2PCommunication {
+            peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+            pieces_availability: vec![
+                PieceStatus::MissingPiece {
+                    was_requested: true,
+                },
+                PieceStatus::MissingPiece {
+                    was_requested: true,
+                },
+            ],
+            am_interested: false,
+            am_choking: true,
+            peer_choking: true,
+            peer_interested: false,
+        };
+
+        let (logger_sender, _logger_receiver) = mpsc::channel();
+        let (ui_sender, _ui_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+        let local_peer = LocalPeerCommunicator {
+            peer_id: DEFAULT_CLIENT_PEER_ID.bytes().collect(),
+            stream,
+            external_peer_data: server_peer_data,
+            role: PeerRole::Client,
+            logger_sender: logger_sender,
+            ui_sender: ui_sender,
+            clock: SystemTime::now(),
+            pending_requests: vec![],
+            request_queue_depth: 5,
+            piece_picker: Arc::new(SequentialPicker),
+            extensions: ExtensionRegistry::new(),
+            fast_extension: FastExtension::default(),
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::default(),
+        };
+        Ok((
+            tracker_response,
+            Arc::new(RwLock::new(torrent_status)),
+            torrent_file,
+            local_peer,
+            _logger_receiver,
+            _ui_receiver,
+        ))
+    }
+
+    mod test_generate_peer_data_from_handshake {
+        use super::*;
+
+        #[test]
+        fn receive_a_message_that_is_not_a_handshake_error() -> Result<(), Box<dyn Error>> {
+            let server_peer_index = 0;
+            let (
+                tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                _,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let message = P2PMessage::KeepAlive;
+
+            assert!(generate_peer_data_from_handshake_torrent_peer(
+                message,
+                &torrent_file_data,
+                &tracker_response,
+                server_peer_index
+            )
+            .is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn receive_a_handshake_with_an_incorrect_protocol_str_error() -> Result<(), Box<dyn Error>>
+        {
+            let server_peer_index = 0;
+            let (
+                tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                _,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let message = P2PMessage::Handshake {
+                protocol_str: "VitTorrent protocol".to_string(),
+                reserved: [0; 8],
+                info_hash: DEFAULT_INFO_HASH.to_vec(),
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+            };
+
+            assert!(generate_peer_data_from_handshake_torrent_peer(
+                message,
+                &torrent_file_data,
+                &tracker_response,
+                server_peer_index
+            )
+            .is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn receive_a_handshake_with_an_incorrect_info_hash_error() -> Result<(), Box<dyn Error>> {
+            let server_peer_index = 0;
+            let (
+                tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                _,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let message = P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
+                info_hash: [1; 20].to_vec(),
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+            };
+
+            assert!(generate_peer_data_from_handshake_torrent_peer(
+                message,
+                &torrent_file_data,
+                &tracker_response,
+                server_peer_index
+            )
+            .is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn receive_a_handshake_with_an_incorrect_peer_id_error() -> Result<(), Box<dyn Error>> {
+            let server_peer_index = 0;
+            let (
+                tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                _,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let message = P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
+                info_hash: DEFAULT_INFO_HASH.to_vec(),
+                peer_id: "-FA0001-000000000002".bytes().collect(),
+            };
+
+            assert!(generate_peer_data_from_handshake_torrent_peer(
+                message,
+                &torrent_file_data,
+                &tracker_response,
+                server_peer_index
+            )
+            .is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn client_that_has_no_peer_ids_to_check_receive_a_valid_handshake_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let server_peer_index = 0;
+
+            let (mut tracker_response, _, torrent_file_data, _, _log_receiver, _ui_receiver) =
+                create_default_client_peer_with_unused_server_peer(1)?;
+
+            //MODIFICO EL CLIENTE PARA QUE NO TENGA LOS PEER_ID DE LOS SERVER PEER
+            tracker_response.peers = vec![PeerDataFromTrackerResponse {
+                peer_id: None,
+                peer_address: SocketAddr::from_str(DEFAULT_ADDR)?,
+            }];
+
+            let message = P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
+                info_hash: DEFAULT_INFO_HASH.to_vec(),
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+            };
+            let expected_peer_data = PeerDataForP2PCommunication {
+                pieces_availability: vec![PieceStatus::MissingPiece {
+                    was_requested: false,
+                }],
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+                am_interested: false,
+                am_choking: true,
+                peer_choking: true,
+                peer_interested: false,
+            };
+
+            let received_peer_data = generate_peer_data_from_handshake_torrent_peer(
+                message,
+                &torrent_file_data,
+                &tracker_response,
+                server_peer_index,
+            )?;
+
+            assert_eq!(expected_peer_data, received_peer_data);
+            Ok(())
+        }
+
+        #[test]
+        fn client_that_has_peer_ids_to_check_receive_a_valid_handshake_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let server_peer_index = 0;
+            let (
+                tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                _,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let message = P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
+                info_hash: DEFAULT_INFO_HASH.to_vec(),
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+            };
+            let expected_peer_data = PeerDataForP2PCommunication {
+                pieces_availability: vec![PieceStatus::MissingPiece {
+                    was_requested: false,
+                }],
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+                am_interested: false,
+                am_choking: true,
+                peer_choking: true,
+                peer_interested: false,
+            };
+
+            let received_peer_data = generate_peer_data_from_handshake_torrent_peer(
+                message,
+                &torrent_file_data,
+                &tracker_response,
+                server_peer_index,
+            )?;
+
+            assert_eq!(expected_peer_data, received_peer_data);
+            Ok(())
+        }
+    }
+
+    mod test_update_peer_bitfield {
+        use super::*;
+
+        #[test]
+        fn update_peer_bitfield_with_less_pieces_error() -> Result<(), Box<dyn Error>> {
+            let (
+                _tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                mut local_peer,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let bitfield = vec![];
+
+            assert!(local_peer
+                .update_peer_bitfield(&torrent_file_data, &bitfield)
+                .is_err());
+
+            assert_eq!(local_peer.external_peer_data.pieces_availability.len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn update_peer_bitfield_with_more_pieces_and_spare_bits_set_error(
+        ) -> Result<(), Box<dyn Error>> {
+            let (
+                _tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                mut local_peer,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let bitfield = vec![
+                PieceStatus::ValidAndAvailablePiece,
+                PieceStatus::MissingPiece {
+                    was_requested: true,
+                },
+                PieceStatus::ValidAndAvailablePiece,
+                PieceStatus::ValidAndAvailablePiece,
+            ];
+
+            assert!(local_peer
+                .update_peer_bitfield(&torrent_file_data, &bitfield)
+                .is_err());
+
+            assert_eq!(local_peer.external_peer_data.pieces_availability.len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn update_peer_bitfield_with_the_correct_amount_of_pieces_ok() -> Result<(), Box<dyn Error>>
+        {
+            let (
+                _tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                mut local_peer,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let bitfield = vec![PieceStatus::ValidAndAvailablePiece];
+
+            let peer_data = PeerDataForP2PCommunication {
+                pieces_availability: vec![PieceStatus::MissingPiece {
+                    was_requested: true,
+                }],
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+                am_interested: false,
+                am_choking: true,
+                peer_choking: true,
+                peer_interested: false,
+            };
+            local_peer.external_peer_data = peer_data;
+
+            local_peer.update_peer_bitfield(&torrent_file_data, &bitfield)?;
 
-        let server_peer = PeerDataFromTrackerResponse {
-            peer_id: Some(DEFAULT_SERVER_PEER_ID.bytes().collect()),
-            peer_address: SocketAddr::from_str(&address)?,
-        };
-        let tracker_response = TrackerResponseData {
-            interval: 0,
-            complete: 0,
-            incomplete: 0,
-            peers: vec![server_peer],
-        };
-        let torrent_status = TorrentStatus {
-            uploaded: 0,
-            downloaded: 40000,
-            left: 0,
-            event: StateOfDownload::Started,
-            pieces_availability: vec![
+            assert_eq!(
+                vec![PieceStatus::ValidAndAvailablePiece],
+                local_peer.external_peer_data.pieces_availability
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn update_peer_bitfield_with_more_pieces_and_spare_bits_not_set_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let (
+                _tracker_response,
+                _torrent_status,
+                torrent_file_data,
+                mut local_peer,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_unused_server_peer(1)?;
+            let bitfield = vec![
                 PieceStatus::ValidAndAvailablePiece,
                 PieceStatus::MissingPiece {
                     was_requested: true,
                 },
-            ],
-        };
-        let torrent_file = TorrentFileData {
-            target_files_data: TargetFilesData::SingleFile {
-                file_name: format!("send_requested_block_{}.test", num_of_test),
-                file_length: 40000,
-                //1º pieza -> 34000 bytes
-                //2º pieza ->  6000 bytes
-            },
-            sha1_pieces: vec![
-                46, 101, 88, 42, 242, 153, 87, 30, 42, 117, 240, 135, 191, 37, 12, 42, 175, 156,
-                136, 214, 95, 100, 198, 139, 237, 56, 161, 225, 113, 168, 52, 228, 26, 36, 103,
-                150, 103, 76, 233, 34,
-            ],
-            url_tracker_main: "tracker_main.com".to_string(),
-            url_tracker_list: vec![],
-            sha1_info_hash: DEFAULT_INFO_HASH.to_vec(),
-            piece_length: 34000,
-            total_amount_of_pieces: 2,
-            total_length: 40000,
-        };
-        let server_peer_data = PeerDataForP2PCommunication {
-            peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-            pieces_availability: vec![
                 PieceStatus::MissingPiece {
                     was_requested: true,
                 },
                 PieceStatus::MissingPiece {
                     was_requested: true,
                 },
-            ],
-            am_interested: false,
-            am_choking: true,
-            peer_choking: true,
-            peer_interested: false,
-        };
+            ];
 
-        let (logger_sender, _logger_receiver) = mpsc::channel();
-        let (ui_sender, _ui_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+            let peer_data = PeerDataForP2PCommunication {
+                pieces_availability: vec![PieceStatus::MissingPiece {
+                    was_requested: true,
+                }],
+                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+                am_interested: false,
+                am_choking: true,
+                peer_choking: true,
+                peer_interested: false,
+            };
+            local_peer.external_peer_data = peer_data;
 
-        let local_peer = LocalPeerCommunicator {
-            peer_id: DEFAULT_CLIENT_PEER_ID.bytes().collect(),
-            stream,
-            external_peer_data: server_peer_data,
-            role: PeerRole::Client,
-            logger_sender: logger_sender,
-            ui_sender: ui_sender,
-            clock: SystemTime::now(),
-        };
-        Ok((
-            tracker_response,
-            Arc::new(RwLock::new(torrent_status)),
-            torrent_file,
-            local_peer,
-            _logger_receiver,
-            _ui_receiver,
-        ))
+            local_peer.update_peer_bitfield(&torrent_file_data, &bitfield)?;
+
+            assert_eq!(
+                vec![PieceStatus::ValidAndAvailablePiece],
+                local_peer.external_peer_data.pieces_availability
+            );
+            Ok(())
+        }
     }
 
-    mod test_generate_peer_data_from_handshake {
+    mod test_update_server_peer_piece_status {
+
         use super::*;
 
         #[test]
-        fn receive_a_message_that_is_not_a_handshake_error() -> Result<(), Box<dyn Error>> {
-            let server_peer_index = 0;
+        fn client_peer_update_piece_status_ok() -> Result<(), Box<dyn Error>> {
+            let piece_index = 1;
+            let (_, _, _torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_peer_with_a_server_peer_that_has_no_valid_pieces(1)?;
+
+            local_peer.update_server_peer_piece_status(
+                piece_index,
+                PieceStatus::ValidAndAvailablePiece,
+            )?;
+
+            assert_eq!(
+                local_peer
+                    .external_peer_data
+                    .pieces_availability
+                    .get(piece_index),
+                Some(&PieceStatus::ValidAndAvailablePiece)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn client_peer_cannot_update_piece_status_with_invalid_index_error(
+        ) -> Result<(), Box<dyn Error>> {
+            let piece_index = 2;
+            let (_, _, _, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_peer_with_a_server_peer_that_has_no_valid_pieces(1)?;
+
+            assert!(local_peer
+                .update_server_peer_piece_status(piece_index, PieceStatus::ValidAndAvailablePiece,)
+                .is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn client_peer_receives_a_have_with_an_invalid_index_error() -> Result<(), Box<dyn Error>> {
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_peer_with_a_server_peer_that_has_no_valid_pieces(1)?;
+
+            let result = local_peer.update_information_according_to_the_received_msg(
+                &torrent_file_data,
+                &torrent_status,
+                &TargetFiles::new(&torrent_file_data, "temp/test_client")?,
+                &P2PMessage::Have { piece_index: 2 },
+            );
+
+            assert!(matches!(
+                result,
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::UpdatingPieceStatus(_)
+                ))
+            ));
+            Ok(())
+        }
+    }
+
+    mod test_store_block {
+        use std::fs;
+
+        use crate::torrent::client::peers_communication::handler_communication::BLOCK_BYTES;
+
+        use super::*;
+
+        fn add_pending_requests(
+            local_peer: &mut LocalPeerCommunicator,
+            requests: &[(usize, u32, u32)],
+        ) {
+            for (piece_index, beginning_byte_index, amount_of_bytes) in requests {
+                local_peer.pending_requests.push(BlockRequest {
+                    piece_index: *piece_index,
+                    beginning_byte_index: *beginning_byte_index,
+                    amount_of_bytes: *amount_of_bytes,
+                });
+            }
+        }
+
+        #[test]
+        fn the_received_block_is_smaller_than_expected_error() -> Result<(), Box<dyn Error>> {
             let (
-                tracker_response,
-                _torrent_status,
+                _tracker_response,
+                torrent_status,
                 torrent_file_data,
-                _,
+                mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let message = P2PMessage::KeepAlive;
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(1)?;
+            add_pending_requests(&mut local_peer, &[(0, 0, BLOCK_BYTES)]);
 
-            assert!(generate_peer_data_from_handshake_torrent_peer(
-                message,
-                &torrent_file_data,
-                &tracker_response,
-                server_peer_index
-            )
-            .is_err());
+            let piece_index = 0;
+            let beginning_byte_index = 0;
+            let block = vec![];
+            let path = "temp/test_client/store_block_1".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+
+            assert_eq!(
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::StoringBlock(
+                        "[InteractionHandlerError] Block length is not as expected".to_string()
+                    )
+                )),
+                local_peer.store_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &target_files,
+                    piece_index,
+                    beginning_byte_index,
+                    block
+                )
+            );
 
             Ok(())
         }
 
         #[test]
-        fn receive_a_handshake_with_an_incorrect_protocol_str_error() -> Result<(), Box<dyn Error>>
-        {
-            let server_peer_index = 0;
+        fn the_received_block_is_bigger_than_expected_error() -> Result<(), Box<dyn Error>> {
             let (
-                tracker_response,
-                _torrent_status,
+                _tracker_response,
+                torrent_status,
                 torrent_file_data,
-                _,
+                mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let message = P2PMessage::Handshake {
-                protocol_str: "VitTorrent protocol".to_string(),
-                info_hash: DEFAULT_INFO_HASH.to_vec(),
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-            };
-
-            assert!(generate_peer_data_from_handshake_torrent_peer(
-                message,
-                &torrent_file_data,
-                &tracker_response,
-                server_peer_index
-            )
-            .is_err());
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(2)?;
+            add_pending_requests(&mut local_peer, &[(0, 0, BLOCK_BYTES)]);
+            let piece_index = 0;
+            let beginning_byte_index = 0;
+            let block = [0; BLOCK_BYTES as usize + 1].to_vec();
+            let path = "temp/test_client/store_block_2".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+
+            assert_eq!(
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::StoringBlock(
+                        "[InteractionHandlerError] Block length is not as expected".to_string()
+                    )
+                )),
+                local_peer.store_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &target_files,
+                    piece_index,
+                    beginning_byte_index,
+                    block
+                )
+            );
 
             Ok(())
         }
 
         #[test]
-        fn receive_a_handshake_with_an_incorrect_info_hash_error() -> Result<(), Box<dyn Error>> {
-            let server_peer_index = 0;
+        fn the_received_piece_index_is_invalid_error() -> Result<(), Box<dyn Error>> {
             let (
-                tracker_response,
-                _torrent_status,
+                _tracker_response,
+                torrent_status,
                 torrent_file_data,
-                _,
+                mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let message = P2PMessage::Handshake {
-                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
-                info_hash: [1; 20].to_vec(),
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-            };
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(3)?;
+            let piece_index = 2;
+            let beginning_byte_index = 0;
+            let block = [0; BLOCK_BYTES as usize].to_vec();
+            let path = "temp/test_client/store_block_3".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
 
-            assert!(generate_peer_data_from_handshake_torrent_peer(
-                message,
-                &torrent_file_data,
-                &tracker_response,
-                server_peer_index
-            )
-            .is_err());
+            assert_eq!(
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::StoringBlock(
+                        "[InteractionHandlerError] The received piece index is invalid."
+                            .to_string(),
+                    )
+                )),
+                local_peer.store_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &target_files,
+                    piece_index,
+                    beginning_byte_index,
+                    block
+                )
+            );
 
             Ok(())
         }
 
         #[test]
-        fn receive_a_handshake_with_an_incorrect_peer_id_error() -> Result<(), Box<dyn Error>> {
-            let server_peer_index = 0;
+        fn the_client_peer_receives_one_block_ok() -> Result<(), Box<dyn Error>> {
             let (
-                tracker_response,
-                _torrent_status,
+                _tracker_response,
+                torrent_status,
                 torrent_file_data,
-                _,
+                mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let message = P2PMessage::Handshake {
-                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
-                info_hash: DEFAULT_INFO_HASH.to_vec(),
-                peer_id: "-FA0001-000000000002".bytes().collect(),
-            };
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(4)?;
+            add_pending_requests(&mut local_peer, &[(0, 0, BLOCK_BYTES)]);
+            let piece_index = 0;
+            let beginning_byte_index = 0;
+            let block = [0; 16384].to_vec();
+            let path = "temp/test_client/store_block_4".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            assert!(generate_peer_data_from_handshake_torrent_peer(
-                message,
+            let storing_result = local_peer.store_block(
                 &torrent_file_data,
-                &tracker_response,
-                server_peer_index
-            )
-            .is_err());
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block,
+            );
 
-            Ok(())
+            assert_eq!(storing_result, Ok(()));
+
+            if let Some(PieceStatus::PartiallyDownloaded {
+                downloaded_blocks, ..
+            }) = torrent_status
+                .read()
+                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                .pieces_availability
+                .get(piece_index as usize)
+            {
+                assert_eq!(vec![true, false, false], *downloaded_blocks);
+                fs::remove_dir_all(&path)?;
+                return Ok(());
+            }
+
+            fs::remove_dir_all(&path)?;
+            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
+                "Couldn`t access to client peer fields.".to_string(),
+            )))
         }
 
         #[test]
-        fn client_that_has_no_peer_ids_to_check_receive_a_valid_handshake_ok(
-        ) -> Result<(), Box<dyn Error>> {
-            let server_peer_index = 0;
-
-            let (mut tracker_response, _, torrent_file_data, _, _log_receiver, _ui_receiver) =
-                create_default_client_peer_with_unused_server_peer(1)?;
+        fn the_client_peer_receives_an_entire_piece_ok() -> Result<(), Box<dyn Error>> {
+            let (
+                _tracker_response,
+                torrent_status,
+                torrent_file_data,
+                mut local_peer,
+                _log_receiver,
+                _ui_receiver,
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(5)?;
+            add_pending_requests(&mut local_peer, &[(1, 0, 6000)]);
+            let piece_index = 1;
+            let beginning_byte_index = 0;
+            let block = [0; 6000].to_vec();
+            let path = "temp/test_client/store_block_5".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            //MODIFICO EL CLIENTE PARA QUE NO TENGA LOS PEER_ID DE LOS SERVER PEER
-            tracker_response.peers = vec![PeerDataFromTrackerResponse {
-                peer_id: None,
-                peer_address: SocketAddr::from_str(DEFAULT_ADDR)?,
-            }];
+            let storing_result = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block,
+            );
 
-            let message = P2PMessage::Handshake {
-                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
-                info_hash: DEFAULT_INFO_HASH.to_vec(),
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-            };
-            let expected_peer_data = PeerDataForP2PCommunication {
-                pieces_availability: vec![PieceStatus::MissingPiece {
-                    was_requested: false,
-                }],
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-                am_interested: false,
-                am_choking: true,
-                peer_choking: true,
-                peer_interested: false,
-            };
+            assert_eq!(storing_result, Ok(()));
 
-            let received_peer_data = generate_peer_data_from_handshake_torrent_peer(
-                message,
-                &torrent_file_data,
-                &tracker_response,
-                server_peer_index,
-            )?;
+            if let Some(piece_status) = torrent_status
+                .read()
+                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                .pieces_availability
+                .get(piece_index as usize)
+            {
+                assert_eq!(PieceStatus::ValidAndAvailablePiece, *piece_status);
+                fs::remove_dir_all(&path)?;
+                return Ok(());
+            }
 
-            assert_eq!(expected_peer_data, received_peer_data);
-            Ok(())
+            fs::remove_dir_all(&path)?;
+            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
+                "Couldn`t access to client peer fields.".to_string(),
+            )))
         }
 
         #[test]
-        fn client_that_has_peer_ids_to_check_receive_a_valid_handshake_ok(
-        ) -> Result<(), Box<dyn Error>> {
-            let server_peer_index = 0;
+        fn the_client_peer_receives_a_piece_that_already_own_ok() -> Result<(), Box<dyn Error>> {
             let (
-                tracker_response,
-                _torrent_status,
+                _tracker_response,
+                torrent_status,
                 torrent_file_data,
-                _,
+                mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let message = P2PMessage::Handshake {
-                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
-                info_hash: DEFAULT_INFO_HASH.to_vec(),
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-            };
-            let expected_peer_data = PeerDataForP2PCommunication {
-                pieces_availability: vec![PieceStatus::MissingPiece {
-                    was_requested: false,
-                }],
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-                am_interested: false,
-                am_choking: true,
-                peer_choking: true,
-                peer_interested: false,
-            };
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(6)?;
+            add_pending_requests(&mut local_peer, &[(1, 0, 6000)]);
+            let piece_index = 1;
+            let mut beginning_byte_index = 0;
+            let block = [0; 6000].to_vec();
+            let path = "temp/test_client/store_block_6".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            let received_peer_data = generate_peer_data_from_handshake_torrent_peer(
-                message,
+            let storing_result = local_peer.store_block(
                 &torrent_file_data,
-                &tracker_response,
-                server_peer_index,
-            )?;
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block.clone(),
+            );
 
-            assert_eq!(expected_peer_data, received_peer_data);
+            assert_eq!(storing_result, Ok(()));
+
+            beginning_byte_index = 6000;
+            assert_eq!(
+                Ok(()),
+                local_peer.store_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &target_files,
+                    piece_index,
+                    beginning_byte_index,
+                    block
+                )
+            );
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
-    }
 
-    mod test_update_peer_bitfield {
-        use super::*;
+        #[test]
+        fn the_client_peer_receives_two_blocks_ok() -> Result<(), Box<dyn Error>> {
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_peer_with_a_server_peer_that_has_the_whole_file(7)?;
+            add_pending_requests(
+                &mut local_peer,
+                &[(0, 0, BLOCK_BYTES), (0, BLOCK_BYTES, BLOCK_BYTES)],
+            );
+            let piece_index = 0;
+            let mut beginning_byte_index = 0;
+            let block_1 = [0; 16384].to_vec();
+            let block_2 = [0; 16384].to_vec();
+            let path = "temp/test_client/store_block_7".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+
+            let storing_result_1 = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_1,
+            );
+            assert_eq!(storing_result_1, Ok(()));
+
+            beginning_byte_index = 16384;
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
+            let storing_result_2 = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_2,
+            );
 
-        #[test]
-        fn update_peer_bitfield_with_less_pieces_error() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                _torrent_status,
-                torrent_file_data,
-                mut local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let bitfield = vec![];
+            assert_eq!(storing_result_2, Ok(()));
 
-            assert!(local_peer
-                .update_peer_bitfield(&torrent_file_data, &bitfield)
-                .is_err());
+            if let Some(PieceStatus::PartiallyDownloaded {
+                downloaded_blocks, ..
+            }) = torrent_status
+                .read()
+                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                .pieces_availability
+                .get(piece_index as usize)
+            {
+                assert_eq!(vec![true, true, false], *downloaded_blocks);
+                fs::remove_dir_all(&path)?;
+                return Ok(());
+            }
 
-            assert_eq!(local_peer.external_peer_data.pieces_availability.len(), 1);
-            Ok(())
+            fs::remove_dir_all(&path)?;
+            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
+                "Couldn`t access to client peer fields.".to_string(),
+            )))
         }
 
         #[test]
-        fn update_peer_bitfield_with_more_pieces_and_spare_bits_set_error(
+        fn the_client_peer_receives_three_blocks_and_completes_a_piece_ok(
         ) -> Result<(), Box<dyn Error>> {
             let (
                 _tracker_response,
-                _torrent_status,
+                torrent_status,
                 torrent_file_data,
                 mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let bitfield = vec![
-                PieceStatus::ValidAndAvailablePiece,
-                PieceStatus::MissingPiece {
-                    was_requested: true,
-                },
-                PieceStatus::ValidAndAvailablePiece,
-                PieceStatus::ValidAndAvailablePiece,
-            ];
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(8)?;
+            add_pending_requests(
+                &mut local_peer,
+                &[
+                    (0, 0, BLOCK_BYTES),
+                    (0, BLOCK_BYTES, BLOCK_BYTES),
+                    (0, 2 * BLOCK_BYTES, 34000 - 2 * BLOCK_BYTES),
+                ],
+            );
+            let piece_index = 0;
+            let mut beginning_byte_index = 0;
+            let block_1 = [0; 16384].to_vec();
+            let block_2 = [0; 16384].to_vec();
+            let block_3 = [0; 34000 - (2 * 16384)].to_vec();
+            let path = "temp/test_client/store_block_8".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            assert!(local_peer
-                .update_peer_bitfield(&torrent_file_data, &bitfield)
-                .is_err());
+            let storing_result_1 = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_1,
+            );
+            assert_eq!(storing_result_1, Ok(()));
 
-            assert_eq!(local_peer.external_peer_data.pieces_availability.len(), 1);
-            Ok(())
+            beginning_byte_index = 16384;
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
+            let storing_result_2 = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_2,
+            );
+            assert_eq!(storing_result_2, Ok(()));
+
+            beginning_byte_index = 16384 * 2;
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
+            let storing_result_3 = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_3,
+            );
+            assert_eq!(storing_result_3, Ok(()));
+
+            if let Some(piece_status) = torrent_status
+                .read()
+                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                .pieces_availability
+                .get(piece_index as usize)
+            {
+                assert_eq!(PieceStatus::ValidAndAvailablePiece, *piece_status);
+                fs::remove_dir_all(&path)?;
+                return Ok(());
+            }
+
+            fs::remove_dir_all(&path)?;
+            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
+                "Couldn`t access to client peer fields.".to_string(),
+            )))
         }
 
         #[test]
-        fn update_peer_bitfield_with_the_correct_amount_of_pieces_ok() -> Result<(), Box<dyn Error>>
-        {
+        fn the_client_peer_receives_two_blocks_with_an_incorrect_beginning_byte_index_error(
+        ) -> Result<(), Box<dyn Error>> {
             let (
                 _tracker_response,
-                _torrent_status,
+                torrent_status,
                 torrent_file_data,
                 mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let bitfield = vec![PieceStatus::ValidAndAvailablePiece];
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(9)?;
+            add_pending_requests(
+                &mut local_peer,
+                &[(0, 0, BLOCK_BYTES), (0, BLOCK_BYTES, BLOCK_BYTES)],
+            );
+            let piece_index = 0;
+            let block_1 = [0; 16384].to_vec();
+            let block_2 = [0; 16384].to_vec();
+            let beginning_byte_index1 = 0;
+            let beginning_byte_index2 = 20000;
+            let path = "temp/test_client/store_block_9".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            let peer_data = PeerDataForP2PCommunication {
-                pieces_availability: vec![PieceStatus::MissingPiece {
-                    was_requested: true,
-                }],
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-                am_interested: false,
-                am_choking: true,
-                peer_choking: true,
-                peer_interested: false,
-            };
-            local_peer.external_peer_data = peer_data;
+            let storing_result = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index1,
+                block_1.clone(),
+            );
+            assert_eq!(storing_result, Ok(()));
 
-            local_peer.update_peer_bitfield(&torrent_file_data, &bitfield)?;
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
+            assert_eq!(
+                Ok(()),
+                local_peer.store_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &target_files,
+                    piece_index,
+                    beginning_byte_index1,
+                    block_1
+                )
+            );
 
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
             assert_eq!(
-                vec![PieceStatus::ValidAndAvailablePiece],
-                local_peer.external_peer_data.pieces_availability
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::StoringBlock(
+                        "[InteractionHandlerError] The beginning byte index is incorrect."
+                            .to_string(),
+                    )
+                )),
+                local_peer.store_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &target_files,
+                    piece_index,
+                    beginning_byte_index2,
+                    block_2
+                )
             );
+
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn update_peer_bitfield_with_more_pieces_and_spare_bits_not_set_ok(
+        fn the_client_peer_receives_three_blocks_and_updates_downloaded_data_ok(
         ) -> Result<(), Box<dyn Error>> {
             let (
                 _tracker_response,
-                _torrent_status,
+                torrent_status,
                 torrent_file_data,
                 mut local_peer,
                 _log_receiver,
                 _ui_receiver,
-            ) = create_default_client_peer_with_unused_server_peer(1)?;
-            let bitfield = vec![
-                PieceStatus::ValidAndAvailablePiece,
-                PieceStatus::MissingPiece {
-                    was_requested: true,
-                },
-                PieceStatus::MissingPiece {
-                    was_requested: true,
-                },
-                PieceStatus::MissingPiece {
-                    was_requested: true,
-                },
-            ];
+            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(10)?;
+            add_pending_requests(
+                &mut local_peer,
+                &[
+                    (0, 0, BLOCK_BYTES),
+                    (0, BLOCK_BYTES, BLOCK_BYTES),
+                    (0, 2 * BLOCK_BYTES, 34000 - 2 * BLOCK_BYTES),
+                ],
+            );
+            let piece_index = 0;
+            let mut beginning_byte_index = 0;
+            let block_1 = [0; 16384].to_vec();
+            let block_2 = [0; 16384].to_vec();
+            let block_3 = [0; 34000 - (2 * 16384)].to_vec();
+            let path = "temp/test_client/store_block_10".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            let peer_data = PeerDataForP2PCommunication {
-                pieces_availability: vec![PieceStatus::MissingPiece {
-                    was_requested: true,
-                }],
-                peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
-                am_interested: false,
-                am_choking: true,
-                peer_choking: true,
-                peer_interested: false,
-            };
-            local_peer.external_peer_data = peer_data;
+            assert_eq!(
+                0,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .downloaded
+            );
+            assert_eq!(
+                40000,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .left
+            );
+
+            let storing_result = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_1,
+            );
+            assert_eq!(storing_result, Ok(()));
+
+            assert_eq!(
+                16384,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .downloaded
+            );
+            assert_eq!(
+                40000 - 16384,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .left
+            );
+
+            beginning_byte_index = 16384;
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
+            let storing_result = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_2,
+            );
+            assert_eq!(storing_result, Ok(()));
 
-            local_peer.update_peer_bitfield(&torrent_file_data, &bitfield)?;
+            assert_eq!(
+                16384 * 2,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .downloaded
+            );
+            assert_eq!(
+                40000 - 16384 * 2,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .left
+            );
+
+            beginning_byte_index = 16384 * 2;
+            torrent_status
+                .write()
+                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
+                .set_piece_as_requested(piece_index)?;
+            let storing_result = local_peer.store_block(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                piece_index,
+                beginning_byte_index,
+                block_3,
+            );
+            assert_eq!(storing_result, Ok(()));
 
             assert_eq!(
-                vec![PieceStatus::ValidAndAvailablePiece],
-                local_peer.external_peer_data.pieces_availability
+                34000,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .downloaded
+            );
+            assert_eq!(
+                40000 - 34000,
+                torrent_status
+                    .read()
+                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
+                    .left
             );
+
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
     }
 
-    mod test_update_server_peer_piece_status {
+    mod test_send_requested_block {
+        use std::fs;
+
+        use crate::torrent::client::peers_communication::handler_communication::BLOCK_BYTES;
 
         use super::*;
 
         #[test]
-        fn client_peer_update_piece_status_ok() -> Result<(), Box<dyn Error>> {
+        fn local_peer_does_not_have_the_requested_block_error() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 1)?;
+            let (_, _) = listener.accept()?;
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
+
             let piece_index = 1;
-            let (_, _, _torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_peer_with_a_server_peer_that_has_no_valid_pieces(1)?;
+            let beginning_byte_index = 32;
+            let amount_of_bytes = 16;
 
-            local_peer.update_server_peer_piece_status(
-                piece_index,
-                PieceStatus::ValidAndAvailablePiece,
+            let path = "temp/test_client/send_requested_block_1".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+
+            // Los bloques guardados corresponden a la pieza que si se posee
+            block_handler::store_block(&torrent_file_data, 0, 0, &block_0, &target_files)?;
+            block_handler::store_block(
+                &torrent_file_data,
+                0,
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                0,
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
             )?;
 
             assert_eq!(
-                local_peer
-                    .external_peer_data
-                    .pieces_availability
-                    .get(piece_index),
-                Some(&PieceStatus::ValidAndAvailablePiece)
+                local_peer.queue_requested_block(
+                    &torrent_file_data,
+                    &torrent_status,
+                    piece_index.try_into()?,
+                    beginning_byte_index,
+                    amount_of_bytes,
+                ),
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(
+                        "[InteractionHandlerError] The local peer does not have the requested block."
+                            .to_string(),
+                    ),
+                ))
             );
-            Ok(())
-        }
-
-        #[test]
-        fn client_peer_cannot_update_piece_status_with_invalid_index_error(
-        ) -> Result<(), Box<dyn Error>> {
-            let piece_index = 2;
-            let (_, _, _, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_peer_with_a_server_peer_that_has_no_valid_pieces(1)?;
-
-            assert!(local_peer
-                .update_server_peer_piece_status(piece_index, PieceStatus::ValidAndAvailablePiece,)
-                .is_err());
 
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
-    }
-
-    mod test_store_block {
-        use std::fs;
-
-        use crate::torrent::client::peers_communication::handler_communication::BLOCK_BYTES;
-
-        use super::*;
 
         #[test]
-        fn the_received_block_is_smaller_than_expected_error() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(1)?;
+        fn peer_who_request_block_is_chocked_error() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 2)?;
+            let (_, _) = listener.accept()?;
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
 
             let piece_index = 0;
             let beginning_byte_index = 0;
-            let block = vec![];
-            let path = "test_client/store_block_1".to_string();
+            let amount_of_bytes = BLOCK_BYTES;
+
+            let path = "temp/test_client/send_requested_block_2".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                0,
+                &block_0,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
+            )?;
 
             assert_eq!(
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::StoringBlock(
-                        "[InteractionHandlerError] Block length is not as expected".to_string()
-                    )
-                )),
-                local_peer.store_block(
+                local_peer.queue_requested_block(
                     &torrent_file_data,
                     &torrent_status,
-                    &path,
-                    piece_index,
+                    piece_index.try_into()?,
                     beginning_byte_index,
-                    block
-                )
+                    amount_of_bytes,
+                ),
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(
+                        "[InteractionHandlerError] The external peer who send the request is choked."
+                            .to_string(),
+                    ),
+                ))
             );
 
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn the_received_block_is_bigger_than_expected_error() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(2)?;
+        fn beginning_byte_index_is_bigger_than_the_piece_length_error() -> Result<(), Box<dyn Error>>
+        {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 3)?;
+            let (_, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
+
             let piece_index = 0;
-            let beginning_byte_index = 0;
-            let block = [0; BLOCK_BYTES as usize + 1].to_vec();
-            let path = "test_client/store_block_2".to_string();
+            let beginning_byte_index = 34000;
+            let amount_of_bytes = 1;
+
+            let path = "temp/test_client/send_requested_block_3".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                0,
+                &block_0,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
+            )?;
 
             assert_eq!(
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::StoringBlock(
-                        "[InteractionHandlerError] Block length is not as expected".to_string()
-                    )
-                )),
-                local_peer.store_block(
+                local_peer.queue_requested_block(
                     &torrent_file_data,
                     &torrent_status,
-                    &path,
-                    piece_index,
+                    piece_index.try_into()?,
                     beginning_byte_index,
-                    block
-                )
+                    amount_of_bytes,
+                ),
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(
+                        "CheckingRequestBlock(\"[TorrentFileDataError] The requested amount of bytes does not match with piece lenght.\")"
+                            .to_string(),
+                    ),
+                ))
             );
 
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn the_received_piece_index_is_invalid_error() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(3)?;
-            let piece_index = 2;
-            let beginning_byte_index = 0;
-            let block = [0; BLOCK_BYTES as usize].to_vec();
-            let path = "test_client/store_block_3".to_string();
+        fn invalid_requested_amount_of_bytes_error() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 4)?;
+            let (_, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
+
+            let piece_index = 0;
+            let beginning_byte_index = BLOCK_BYTES * 2;
+            let amount_of_bytes = BLOCK_BYTES;
+
+            let path = "temp/test_client/send_requested_block_4".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                0,
+                &block_0,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                piece_index,
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
+            )?;
 
             assert_eq!(
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::StoringBlock(
-                        "[InteractionHandlerError] The received piece index is invalid."
-                            .to_string(),
-                    )
-                )),
-                local_peer.store_block(
+                local_peer.queue_requested_block(
                     &torrent_file_data,
                     &torrent_status,
-                    &path,
-                    piece_index,
+                    piece_index.try_into()?,
                     beginning_byte_index,
-                    block
-                )
+                    amount_of_bytes,
+                ),
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(
+                        "CheckingRequestBlock(\"[TorrentFileDataError] The requested amount of bytes does not match with piece lenght.\")"
+                            .to_string(),
+                    ),
+                ))
             );
 
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn the_client_peer_receives_one_block_ok() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(4)?;
+        fn requested_amount_of_bytes_is_bigger_than_16kbytes_error() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 5)?;
+            let (_, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
+
             let piece_index = 0;
             let beginning_byte_index = 0;
-            let block = [0; 16384].to_vec();
-            let path = "test_client/store_block_4".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            let amount_of_bytes = BLOCK_BYTES + 1;
 
-            let storing_result = local_peer.store_block(
+            let path = "temp/test_client/send_requested_block_5".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+
+            block_handler::store_block(
                 &torrent_file_data,
-                &torrent_status,
-                &path,
                 piece_index,
-                beginning_byte_index,
-                block,
-            );
-
-            assert_eq!(storing_result, Ok(()));
-
-            if let Some(PieceStatus::PartiallyDownloaded {
-                downloaded_bytes, ..
-            }) = torrent_status
-                .read()
-                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                .pieces_availability
-                .get(piece_index as usize)
-            {
-                assert_eq!(BLOCK_BYTES, *downloaded_bytes);
-                fs::remove_dir_all(format!("temp/{}", path))?;
-                return Ok(());
-            }
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
-            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
-                "Couldn`t access to client peer fields.".to_string(),
-            )))
-        }
-
-        #[test]
-        fn the_client_peer_receives_an_entire_piece_ok() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(5)?;
-            let piece_index = 1;
-            let beginning_byte_index = 0;
-            let block = [0; 6000].to_vec();
-            let path = "test_client/store_block_5".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
-
-            let storing_result = local_peer.store_block(
+                0,
+                &block_0,
+                &target_files,
+            )?;
+            block_handler::store_block(
                 &torrent_file_data,
-                &torrent_status,
-                &path,
                 piece_index,
-                beginning_byte_index,
-                block,
-            );
-
-            assert_eq!(storing_result, Ok(()));
-
-            if let Some(piece_status) = torrent_status
-                .read()
-                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                .pieces_availability
-                .get(piece_index as usize)
-            {
-                assert_eq!(PieceStatus::ValidAndAvailablePiece, *piece_status);
-                fs::remove_dir_all(format!("temp/{}", path))?;
-                return Ok(());
-            }
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
-            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
-                "Couldn`t access to client peer fields.".to_string(),
-            )))
-        }
-
-        #[test]
-        fn the_client_peer_receives_a_piece_that_already_own_ok() -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(6)?;
-            let piece_index = 1;
-            let mut beginning_byte_index = 0;
-            let block = [0; 6000].to_vec();
-            let path = "test_client/store_block_6".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
-
-            let storing_result = local_peer.store_block(
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
                 &torrent_file_data,
-                &torrent_status,
-                &path,
                 piece_index,
-                beginning_byte_index,
-                block.clone(),
-            );
-
-            assert_eq!(storing_result, Ok(()));
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
+            )?;
 
-            beginning_byte_index = 6000;
             assert_eq!(
-                Ok(()),
-                local_peer.store_block(
+                local_peer.queue_requested_block(
                     &torrent_file_data,
                     &torrent_status,
-                    &path,
-                    piece_index,
+                    piece_index.try_into()?,
                     beginning_byte_index,
-                    block
-                )
+                    amount_of_bytes,
+                ),
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(
+                        "CheckingRequestBlock(\"[TorrentFileDataError] The requested amount of bytes is bigger than 2^14 bytes.\")"
+                            .to_string(),
+                    ),
+                ))
             );
-            fs::remove_dir_all(format!("temp/{}", path))?;
+
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn the_client_peer_receives_two_blocks_ok() -> Result<(), Box<dyn Error>> {
-            let (_, torrent_status, torrent_file_data, local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_peer_with_a_server_peer_that_has_the_whole_file(7)?;
+        fn local_peer_sends_a_request_ok() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 6)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
+
             let piece_index = 0;
-            let mut beginning_byte_index = 0;
-            let block_1 = [0; 16384].to_vec();
-            let block_2 = [0; 16384].to_vec();
-            let path = "test_client/store_block_7".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            let beginning_byte_index = BLOCK_BYTES;
+            let amount_of_bytes = BLOCK_BYTES;
 
-            let storing_result_1 = local_peer.store_block(
-                &torrent_file_data,
-                &torrent_status,
-                &path,
-                piece_index,
-                beginning_byte_index,
-                block_1,
+            let path = format!(
+                "temp/test_client/{}",
+                torrent_file_data.get_torrent_representative_name()
             );
-            assert_eq!(storing_result_1, Ok(()));
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
 
-            beginning_byte_index = 16384;
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
-            let storing_result_2 = local_peer.store_block(
+            block_handler::store_block(
                 &torrent_file_data,
-                &torrent_status,
-                &path,
                 piece_index,
-                beginning_byte_index,
-                block_2,
-            );
-
-            assert_eq!(storing_result_2, Ok(()));
-
-            if let Some(PieceStatus::PartiallyDownloaded {
-                downloaded_bytes, ..
-            }) = torrent_status
-                .read()
-                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                .pieces_availability
-                .get(piece_index as usize)
-            {
-                assert_eq!(BLOCK_BYTES * 2, *downloaded_bytes);
-                fs::remove_dir_all(format!("temp/{}", path))?;
-                return Ok(());
-            }
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
-            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
-                "Couldn`t access to client peer fields.".to_string(),
-            )))
-        }
-
-        #[test]
-        fn the_client_peer_receives_three_blocks_and_completes_a_piece_ok(
-        ) -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(8)?;
-            let piece_index = 0;
-            let mut beginning_byte_index = 0;
-            let block_1 = [0; 16384].to_vec();
-            let block_2 = [0; 16384].to_vec();
-            let block_3 = [0; 34000 - (2 * 16384)].to_vec();
-            let path = "test_client/store_block_8".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
-
-            let storing_result_1 = local_peer.store_block(
+                0,
+                &block_0,
+                &target_files,
+            )?;
+            block_handler::store_block(
                 &torrent_file_data,
-                &torrent_status,
-                &path,
                 piece_index,
-                beginning_byte_index,
-                block_1,
-            );
-            assert_eq!(storing_result_1, Ok(()));
-
-            beginning_byte_index = 16384;
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
-            let storing_result_2 = local_peer.store_block(
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
                 &torrent_file_data,
-                &torrent_status,
-                &path,
                 piece_index,
-                beginning_byte_index,
-                block_2,
-            );
-            assert_eq!(storing_result_2, Ok(()));
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
+            )?;
 
-            beginning_byte_index = 16384 * 2;
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
-            let storing_result_3 = local_peer.store_block(
+            local_peer.queue_requested_block(
                 &torrent_file_data,
                 &torrent_status,
-                &path,
-                piece_index,
+                piece_index.try_into()?,
                 beginning_byte_index,
-                block_3,
-            );
-            assert_eq!(storing_result_3, Ok(()));
+                amount_of_bytes,
+            )?;
+            local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS))),
+                &target_files,
+            )?;
+
+            let received_msg = msg_receiver::receive_message(&mut external_stream)?;
+            let expected_msg = P2PMessage::Piece {
+                piece_index: piece_index.try_into()?,
+                beginning_byte_index,
+                block: block_1,
+            };
 
-            if let Some(piece_status) = torrent_status
-                .read()
-                .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                .pieces_availability
-                .get(piece_index as usize)
-            {
-                assert_eq!(PieceStatus::ValidAndAvailablePiece, *piece_status);
-                fs::remove_dir_all(format!("temp/{}", path))?;
-                return Ok(());
-            }
+            assert_eq!(expected_msg, received_msg);
 
-            fs::remove_dir_all(format!("temp/{}", path))?;
-            Err(Box::new(TestingError::ClientPeerFieldsInvalidAccess(
-                "Couldn`t access to client peer fields.".to_string(),
-            )))
+            fs::remove_dir_all(&path)?;
+            Ok(())
         }
+    }
+    mod test_fast_extension {
+        use std::fs;
+
+        use crate::torrent::client::peers_communication::handler_communication::BLOCK_BYTES;
+
+        use super::*;
 
         #[test]
-        fn the_client_peer_receives_two_blocks_with_an_incorrect_beginning_byte_index_error(
+        fn choked_peer_that_supports_fast_extension_receives_a_reject_request_ok(
         ) -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(9)?;
-            let piece_index = 0;
-            let block_1 = [0; 16384].to_vec();
-            let block_2 = [0; 16384].to_vec();
-            let beginning_byte_index1 = 0;
-            let beginning_byte_index2 = 20000;
-            let path = "test_client/store_block_9".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 7)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.fast_extension = FastExtension::new(true);
 
-            let storing_result = local_peer.store_block(
+            let request = P2PMessage::Request {
+                piece_index: 0,
+                beginning_byte_index: 0,
+                amount_of_bytes: BLOCK_BYTES,
+            };
+            local_peer.send_msg_according_to_the_received_msg(
                 &torrent_file_data,
                 &torrent_status,
-                &path,
-                piece_index,
-                beginning_byte_index1,
-                block_1.clone(),
-            );
-            assert_eq!(storing_result, Ok(()));
+                &request,
+            )?;
 
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
             assert_eq!(
-                Ok(()),
-                local_peer.store_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    &path,
-                    piece_index,
-                    beginning_byte_index1,
-                    block_1
-                )
+                P2PMessage::RejectRequest {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: BLOCK_BYTES,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
+            Ok(())
+        }
+
+        #[test]
+        fn choked_peer_can_request_a_piece_of_its_allowed_fast_set_ok() -> Result<(), Box<dyn Error>>
+        {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 8)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.fast_extension = FastExtension::new(true);
+            local_peer.fast_extension.add_allowed_fast_for_peer(0);
+
+            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
+            let path = "temp/test_client/send_requested_block_8".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+            block_handler::store_block(&torrent_file_data, 0, 0, &block_0, &target_files)?;
+
+            local_peer.queue_requested_block(
+                &torrent_file_data,
+                &torrent_status,
+                0,
+                0,
+                BLOCK_BYTES,
+            )?;
+            local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS))),
+                &target_files,
+            )?;
 
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
             assert_eq!(
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::StoringBlock(
-                        "[InteractionHandlerError] The beginning byte index is incorrect."
-                            .to_string(),
-                    )
-                )),
-                local_peer.store_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    &path,
-                    piece_index,
-                    beginning_byte_index2,
-                    block_2
-                )
+                P2PMessage::Piece {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    block: block_0,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
 
-            fs::remove_dir_all(format!("temp/{}", path))?;
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn the_client_peer_receives_three_blocks_and_updates_downloaded_data_ok(
-        ) -> Result<(), Box<dyn Error>> {
-            let (
-                _tracker_response,
-                torrent_status,
-                torrent_file_data,
-                local_peer,
-                _log_receiver,
-                _ui_receiver,
-            ) = create_default_client_peer_with_a_server_peer_that_has_the_whole_file(10)?;
-            let piece_index = 0;
-            let mut beginning_byte_index = 0;
-            let block_1 = [0; 16384].to_vec();
-            let block_2 = [0; 16384].to_vec();
-            let block_3 = [0; 34000 - (2 * 16384)].to_vec();
-            let path = "test_client/store_block_10".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+        fn requested_blocks_that_cannot_be_read_are_rejected_error() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 16)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
+            local_peer.fast_extension = FastExtension::new(true);
 
-            assert_eq!(
+            local_peer.queue_requested_block(
+                &torrent_file_data,
+                &torrent_status,
                 0,
-                torrent_status
-                    .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .downloaded
+                0,
+                BLOCK_BYTES,
+            )?;
+            let result = local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS))),
+                &TargetFiles::new(
+                    &torrent_file_data,
+                    "temp/test_client/send_requested_block_16",
+                )?,
             );
+
+            assert!(matches!(
+                result,
+                Err(InteractionHandlerErrorKind::Recoverable(
+                    InteractionHandlerError::SendingRequestedBlock(_)
+                ))
+            ));
             assert_eq!(
-                40000,
-                torrent_status
-                    .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .left
+                P2PMessage::RejectRequest {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: BLOCK_BYTES,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
+            assert!(local_peer.upload_queue.is_empty());
+            Ok(())
+        }
 
-            let storing_result = local_peer.store_block(
+        #[test]
+        fn received_reject_request_releases_the_requested_block_ok() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 9)?;
+            let (_, _) = listener.accept()?;
+            local_peer.fast_extension = FastExtension::new(true);
+            local_peer.pending_requests.push(BlockRequest {
+                piece_index: 1,
+                beginning_byte_index: 0,
+                amount_of_bytes: 6000,
+            });
+
+            local_peer.update_information_according_to_the_received_msg(
                 &torrent_file_data,
                 &torrent_status,
-                &path,
-                piece_index,
-                beginning_byte_index,
-                block_1,
-            );
-            assert_eq!(storing_result, Ok(()));
+                &TargetFiles::new(&torrent_file_data, "temp/test_client")?,
+                &P2PMessage::RejectRequest {
+                    piece_index: 1,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: 6000,
+                },
+            )?;
 
+            assert!(local_peer.pending_requests.is_empty());
             assert_eq!(
-                16384,
+                Some(&PieceStatus::MissingPiece {
+                    was_requested: false
+                }),
                 torrent_status
                     .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .downloaded
+                    .map_err(|err| format!("{:?}", err))?
+                    .get_piece_status(1)
             );
+            Ok(())
+        }
+
+        #[test]
+        fn received_allowed_fast_with_an_invalid_index_is_ignored_ok() -> Result<(), Box<dyn Error>>
+        {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 17)?;
+            let (_, _) = listener.accept()?;
+            local_peer.fast_extension = FastExtension::new(true);
+            let invalid_piece_index = torrent_file_data.get_total_amount_pieces();
+
+            for piece_index in [1, invalid_piece_index] {
+                local_peer.update_information_according_to_the_received_msg(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &TargetFiles::new(&torrent_file_data, "temp/test_client")?,
+                    &P2PMessage::AllowedFast {
+                        piece_index: piece_index.try_into()?,
+                    },
+                )?;
+            }
+
             assert_eq!(
-                40000 - 16384,
-                torrent_status
-                    .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .left
+                vec![1],
+                local_peer.fast_extension.get_allowed_fast_from_peer()
             );
+            Ok(())
+        }
 
-            beginning_byte_index = 16384;
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
-            let storing_result = local_peer.store_block(
+        #[test]
+        fn received_have_all_and_have_none_update_the_peer_pieces_ok() -> Result<(), Box<dyn Error>>
+        {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 10)?;
+            let (_, _) = listener.accept()?;
+
+            local_peer.update_information_according_to_the_received_msg(
                 &torrent_file_data,
                 &torrent_status,
-                &path,
-                piece_index,
-                beginning_byte_index,
-                block_2,
-            );
-            assert_eq!(storing_result, Ok(()));
+                &TargetFiles::new(&torrent_file_data, "temp/test_client")?,
+                &P2PMessage::HaveAll,
+            )?;
+            assert!(local_peer.external_peer_data.has_all_pieces());
+
+            local_peer.update_information_according_to_the_received_msg(
+                &torrent_file_data,
+                &torrent_status,
+                &TargetFiles::new(&torrent_file_data, "temp/test_client")?,
+                &P2PMessage::HaveNone,
+            )?;
+            assert!(!local_peer.external_peer_has_a_valid_and_available_piece_on_position(0));
+            assert!(!local_peer.external_peer_has_a_valid_and_available_piece_on_position(1));
+            Ok(())
+        }
+    }
+    mod test_endgame {
+        use super::*;
+
+        #[test]
+        fn in_endgame_a_piece_requested_to_another_peer_is_requested_again_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 11)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.external_peer_data.peer_choking = false;
+            local_peer.external_peer_data.pieces_availability[1] =
+                PieceStatus::ValidAndAvailablePiece;
+            // La pieza 1 ya fue pedida por otro peer
+            torrent_status
+                .write()
+                .map_err(|err| format!("{:?}", err))?
+                .pieces_requesters_count[1] = 1;
+
+            local_peer.look_for_pieces(&torrent_file_data, &torrent_status)?;
 
             assert_eq!(
-                16384 * 2,
-                torrent_status
-                    .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .downloaded
+                P2PMessage::Request {
+                    piece_index: 1,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: 6000,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
+            assert_eq!(1, local_peer.pending_requests.len());
+            assert!(local_peer.am_interested());
             assert_eq!(
-                40000 - 16384 * 2,
+                2,
                 torrent_status
                     .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .left
-            );
-
-            beginning_byte_index = 16384 * 2;
-            torrent_status
-                .write()
-                .map_err(|err| TestingError::ClientPeerFieldsInvalidAccess(format!("{:?}", err)))?
-                .set_piece_as_requested(piece_index)?;
-            let storing_result = local_peer.store_block(
-                &torrent_file_data,
-                &torrent_status,
-                &path,
-                piece_index,
-                beginning_byte_index,
-                block_3,
+                    .map_err(|err| format!("{:?}", err))?
+                    .pieces_requesters_count[1]
             );
-            assert_eq!(storing_result, Ok(()));
 
+            let mut torrent_status = torrent_status.write().map_err(|err| format!("{:?}", err))?;
+            local_peer.release_pending_requests(&mut torrent_status);
             assert_eq!(
-                34000,
-                torrent_status
-                    .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .downloaded
+                Some(&PieceStatus::MissingPiece {
+                    was_requested: true
+                }),
+                torrent_status.get_piece_status(1)
             );
+            assert!(torrent_status.is_in_endgame());
+            Ok(())
+        }
+
+        #[test]
+        fn a_request_of_a_block_downloaded_by_another_peer_is_cancelled_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let (_, torrent_status, _, mut local_peer, _log_receiver, _ui_receiver) =
+                create_default_client_with_a_piece_for_requests(address, 12)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.pending_requests.push(BlockRequest {
+                piece_index: 1,
+                beginning_byte_index: 0,
+                amount_of_bytes: 6000,
+            });
+
+            local_peer.cancel_already_downloaded_requests(&torrent_status)?;
+            assert_eq!(1, local_peer.pending_requests.len());
+
+            torrent_status
+                .write()
+                .map_err(|err| format!("{:?}", err))?
+                .pieces_availability[1] = PieceStatus::ValidAndAvailablePiece;
+            local_peer.cancel_already_downloaded_requests(&torrent_status)?;
+
+            assert!(local_peer.pending_requests.is_empty());
             assert_eq!(
-                40000 - 34000,
-                torrent_status
-                    .read()
-                    .map_err(|err| InteractionHandlerError::LockingTorrentStatus(err.to_string()))?
-                    .left
+                P2PMessage::Cancel {
+                    piece_index: 1,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: 6000,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
             Ok(())
         }
     }
 
-    mod test_send_requested_block {
+    mod test_upload_queue {
         use std::fs;
 
         use crate::torrent::client::peers_communication::handler_communication::BLOCK_BYTES;
@@ -2631,266 +4539,415 @@ mod test_local_peer {
         use super::*;
 
         #[test]
-        fn local_peer_does_not_have_the_requested_block_error() -> Result<(), Box<dyn Error>> {
+        fn queued_blocks_of_a_piece_are_sent_except_the_cancelled_ones_ok(
+        ) -> Result<(), Box<dyn Error>> {
             let (listener, address) =
                 try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
             let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_with_a_piece_for_requests(address, 1)?;
-            let (_, _) = listener.accept()?;
+                create_default_client_with_a_piece_for_requests(address, 13)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
 
             let block_0 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
-
-            let piece_index = 1;
-            let beginning_byte_index = 32;
-            let amount_of_bytes = 16;
+            let block_1 = [11; BLOCK_BYTES as usize].to_vec();
+            let block_2 = [12; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
+
+            let path = "temp/test_client/upload_queue_13".to_string();
+            let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+            fs::create_dir(&path)?;
+            block_handler::store_block(&torrent_file_data, 0, 0, &block_0, &target_files)?;
+            block_handler::store_block(
+                &torrent_file_data,
+                0,
+                BLOCK_BYTES,
+                &block_1,
+                &target_files,
+            )?;
+            block_handler::store_block(
+                &torrent_file_data,
+                0,
+                2 * BLOCK_BYTES,
+                &block_2,
+                &target_files,
+            )?;
 
-            let path = "test_client/send_requested_block_1".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            let choker = Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS)));
+            local_peer.update_choker_stats(&choker, &P2PMessage::Interested)?;
+            for (beginning_byte_index, amount_of_bytes) in [
+                (2 * BLOCK_BYTES, 34000 - 2 * BLOCK_BYTES),
+                (0, BLOCK_BYTES),
+                (BLOCK_BYTES, BLOCK_BYTES),
+            ] {
+                let request = P2PMessage::Request {
+                    piece_index: 0,
+                    beginning_byte_index,
+                    amount_of_bytes,
+                };
+                local_peer.update_choker_stats(&choker, &request)?;
+                local_peer.send_msg_according_to_the_received_msg(
+                    &torrent_file_data,
+                    &torrent_status,
+                    &request,
+                )?;
+            }
+            let get_uploaded_bytes_of_the_peer = |choker: &Arc<RwLock<Choker>>| {
+                choker
+                    .read()
+                    .map(|choker| {
+                        choker
+                            .peers
+                            .get(DEFAULT_SERVER_PEER_ID.as_bytes())
+                            .map_or(0, |peer_stats| peer_stats.uploaded)
+                    })
+                    .map_err(|err| format!("{:?}", err))
+            };
+            assert_eq!(0, get_uploaded_bytes_of_the_peer(&choker)?);
+            local_peer.update_information_according_to_the_received_msg(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                &P2PMessage::Cancel {
+                    piece_index: 0,
+                    beginning_byte_index: BLOCK_BYTES,
+                    amount_of_bytes: BLOCK_BYTES,
+                },
+            )?;
+            assert_eq!(2, local_peer.upload_queue.len());
 
-            block_handler::store_block(&block_0, piece_index, &path)?;
-            block_handler::store_block(&block_1, piece_index, &path)?;
-            block_handler::store_block(&block_2, piece_index, &path)?;
+            local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &choker,
+                &target_files,
+            )?;
 
+            assert!(local_peer.upload_queue.is_empty());
             assert_eq!(
-                local_peer.send_requested_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    piece_index.try_into()?,
-                    beginning_byte_index,
-                    amount_of_bytes,
-                ),
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(
-                        "[InteractionHandlerError] The local peer does not have the requested block."
-                            .to_string(),
-                    ),
-                ))
+                P2PMessage::Piece {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    block: block_0,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert_eq!(
+                P2PMessage::Piece {
+                    piece_index: 0,
+                    beginning_byte_index: 2 * BLOCK_BYTES,
+                    block: block_2,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert_eq!(
+                34000 - BLOCK_BYTES,
+                torrent_status
+                    .read()
+                    .map_err(|err| format!("{:?}", err))?
+                    .get_uploaded_bytes()
+                    .try_into()?
+            );
+            assert_eq!(
+                u64::from(34000 - BLOCK_BYTES),
+                get_uploaded_bytes_of_the_peer(&choker)?
             );
 
-            fs::remove_dir_all(format!("temp/{}", path))?;
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn peer_who_request_block_is_chocked_error() -> Result<(), Box<dyn Error>> {
+        fn choking_the_peer_rejects_its_queued_requests_ok() -> Result<(), Box<dyn Error>> {
             let (listener, address) =
                 try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
             let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_with_a_piece_for_requests(address, 2)?;
-            let (_, _) = listener.accept()?;
-
-            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
-
-            let piece_index = 0;
-            let beginning_byte_index = 0;
-            let amount_of_bytes = BLOCK_BYTES;
-
-            let path = "test_client/send_requested_block_2".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+                create_default_client_with_a_piece_for_requests(address, 14)?;
+            let (mut external_stream, _) = listener.accept()?;
+            local_peer.external_peer_data.am_choking = false;
+            local_peer.fast_extension = FastExtension::new(true);
 
-            block_handler::store_block(&block_0, piece_index, &path)?;
-            block_handler::store_block(&block_1, piece_index, &path)?;
-            block_handler::store_block(&block_2, piece_index, &path)?;
+            local_peer.queue_requested_block(
+                &torrent_file_data,
+                &torrent_status,
+                0,
+                0,
+                BLOCK_BYTES,
+            )?;
+            let choker = Arc::new(RwLock::new(Choker::new(0)));
+            local_peer.apply_choker_decision(&torrent_file_data, &choker)?;
 
+            assert!(local_peer.am_choking());
+            assert!(local_peer.upload_queue.is_empty());
             assert_eq!(
-                local_peer.send_requested_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    piece_index.try_into()?,
-                    beginning_byte_index,
-                    amount_of_bytes,
-                ),
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(
-                        "[InteractionHandlerError] The external peer who send the request is choked."
-                            .to_string(),
-                    ),
-                ))
+                P2PMessage::Choke,
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert_eq!(
+                P2PMessage::RejectRequest {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: BLOCK_BYTES,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
             Ok(())
         }
 
         #[test]
-        fn beginning_byte_index_is_bigger_than_the_piece_length_error() -> Result<(), Box<dyn Error>>
-        {
+        fn choking_the_peer_keeps_its_allowed_fast_requests_ok() -> Result<(), Box<dyn Error>> {
             let (listener, address) =
                 try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
             let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_with_a_piece_for_requests(address, 3)?;
-            let (_, _) = listener.accept()?;
+                create_default_client_with_a_piece_for_requests(address, 18)?;
+            let (mut external_stream, _) = listener.accept()?;
             local_peer.external_peer_data.am_choking = false;
+            local_peer.fast_extension = FastExtension::new(true);
+            local_peer.fast_extension.add_allowed_fast_for_peer(0);
 
-            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
-
-            let piece_index = 0;
-            let beginning_byte_index = 34000;
-            let amount_of_bytes = 1;
+            local_peer.queue_requested_block(
+                &torrent_file_data,
+                &torrent_status,
+                0,
+                0,
+                BLOCK_BYTES,
+            )?;
+            let choker = Arc::new(RwLock::new(Choker::new(0)));
+            local_peer.apply_choker_decision(&torrent_file_data, &choker)?;
 
-            let path = "test_client/send_requested_block_3".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            assert!(local_peer.am_choking());
+            assert_eq!(1, local_peer.upload_queue.len());
+            assert_eq!(
+                P2PMessage::Choke,
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert!(!msg_receiver::wait_for_message(
+                &external_stream,
+                Duration::from_millis(100)
+            )?);
+            Ok(())
+        }
 
-            block_handler::store_block(&block_0, piece_index, &path)?;
-            block_handler::store_block(&block_1, piece_index, &path)?;
-            block_handler::store_block(&block_2, piece_index, &path)?;
+        fn store_piece_for_upload_tests(
+            torrent_file_data: &TorrentFileData,
+            path: &str,
+        ) -> Result<(TargetFiles, Vec<u8>), Box<dyn Error>> {
+            let piece = (0..34000)
+                .map(|byte| (byte % 256) as u8)
+                .collect::<Vec<u8>>();
+            let target_files = TargetFiles::new(torrent_file_data, path)?;
+            fs::create_dir(path)?;
+            block_handler::store_block(torrent_file_data, 0, 0, &piece, &target_files)?;
+            Ok((target_files, piece))
+        }
 
-            assert_eq!(
-                local_peer.send_requested_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    piece_index.try_into()?,
+        fn queue_three_blocks(
+            local_peer: &mut LocalPeerCommunicator,
+            torrent_file_data: &TorrentFileData,
+            torrent_status: &Arc<RwLock<TorrentStatus>>,
+        ) -> Result<(), Box<dyn Error>> {
+            for (beginning_byte_index, amount_of_bytes) in [
+                (0, BLOCK_BYTES),
+                (BLOCK_BYTES, BLOCK_BYTES),
+                (2 * BLOCK_BYTES, 34000 - 2 * BLOCK_BYTES),
+            ] {
+                local_peer.queue_requested_block(
+                    torrent_file_data,
+                    torrent_status,
+                    0,
                     beginning_byte_index,
                     amount_of_bytes,
-                ),
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(
-                        "CheckingRequestBlock(\"[TorrentFileDataError] The requested amount of bytes does not match with piece lenght.\")"
-                            .to_string(),
-                    ),
-                ))
-            );
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
+                )?;
+            }
             Ok(())
         }
 
         #[test]
-        fn invalid_requested_amount_of_bytes_error() -> Result<(), Box<dyn Error>> {
+        fn a_cancel_received_while_sending_a_batch_is_handled_before_the_next_block_ok(
+        ) -> Result<(), Box<dyn Error>> {
             let (listener, address) =
                 try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
             let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_with_a_piece_for_requests(address, 4)?;
-            let (_, _) = listener.accept()?;
+                create_default_client_with_a_piece_for_requests(address, 19)?;
+            let (mut external_stream, _) = listener.accept()?;
             local_peer.external_peer_data.am_choking = false;
+            let path = "temp/test_client/upload_queue_19".to_string();
+            let (target_files, piece) = store_piece_for_upload_tests(&torrent_file_data, &path)?;
+
+            let choker = Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS)));
+            local_peer.update_choker_stats(&choker, &P2PMessage::Interested)?;
+            queue_three_blocks(&mut local_peer, &torrent_file_data, &torrent_status)?;
+            let cancel = P2PMessage::Cancel {
+                piece_index: 0,
+                beginning_byte_index: BLOCK_BYTES,
+                amount_of_bytes: BLOCK_BYTES,
+            };
+            msg_sender::send_cancel(&mut external_stream, 0, BLOCK_BYTES, BLOCK_BYTES)?;
+            assert!(local_peer.wait_for_message(Duration::from_secs(1))?);
 
-            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
-
-            let piece_index = 0;
-            let beginning_byte_index = BLOCK_BYTES * 2;
-            let amount_of_bytes = BLOCK_BYTES;
-
-            let path = "test_client/send_requested_block_4".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &choker,
+                &target_files,
+            )?;
+            assert_eq!(2, local_peer.upload_queue.len());
 
-            block_handler::store_block(&block_0, piece_index, &path)?;
-            block_handler::store_block(&block_1, piece_index, &path)?;
-            block_handler::store_block(&block_2, piece_index, &path)?;
+            assert_eq!(
+                cancel,
+                msg_receiver::receive_message(&mut local_peer.stream)?
+            );
+            local_peer.update_information_according_to_the_received_msg(
+                &torrent_file_data,
+                &torrent_status,
+                &target_files,
+                &cancel,
+            )?;
+            local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &choker,
+                &target_files,
+            )?;
 
+            assert!(local_peer.upload_queue.is_empty());
             assert_eq!(
-                local_peer.send_requested_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    piece_index.try_into()?,
-                    beginning_byte_index,
-                    amount_of_bytes,
-                ),
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(
-                        "CheckingRequestBlock(\"[TorrentFileDataError] The requested amount of bytes does not match with piece lenght.\")"
-                            .to_string(),
-                    ),
-                ))
+                P2PMessage::Piece {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    block: piece[..BLOCK_BYTES as usize].to_vec(),
+                },
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert_eq!(
+                P2PMessage::Piece {
+                    piece_index: 0,
+                    beginning_byte_index: 2 * BLOCK_BYTES,
+                    block: piece[2 * BLOCK_BYTES as usize..].to_vec(),
+                },
+                msg_receiver::receive_message(&mut external_stream)?
             );
 
-            fs::remove_dir_all(format!("temp/{}", path))?;
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
 
         #[test]
-        fn requested_amount_of_bytes_is_bigger_than_16kbytes_error() -> Result<(), Box<dyn Error>> {
+        fn a_choke_decided_while_sending_a_batch_is_applied_before_the_next_block_ok(
+        ) -> Result<(), Box<dyn Error>> {
             let (listener, address) =
                 try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
             let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_with_a_piece_for_requests(address, 5)?;
-            let (_, _) = listener.accept()?;
+                create_default_client_with_a_piece_for_requests(address, 20)?;
+            let (mut external_stream, _) = listener.accept()?;
             local_peer.external_peer_data.am_choking = false;
+            local_peer.fast_extension = FastExtension::new(true);
+            let path = "temp/test_client/upload_queue_20".to_string();
+            let (target_files, piece) = store_piece_for_upload_tests(&torrent_file_data, &path)?;
 
-            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
-
-            let piece_index = 0;
-            let beginning_byte_index = 0;
-            let amount_of_bytes = BLOCK_BYTES + 1;
-
-            let path = "test_client/send_requested_block_5".to_string();
-            fs::create_dir(format!("temp/{}", path))?;
+            let choker = Arc::new(RwLock::new(Choker::new(0)));
+            queue_three_blocks(&mut local_peer, &torrent_file_data, &torrent_status)?;
 
-            block_handler::store_block(&block_0, piece_index, &path)?;
-            block_handler::store_block(&block_1, piece_index, &path)?;
-            block_handler::store_block(&block_2, piece_index, &path)?;
+            local_peer.send_queued_blocks(
+                &torrent_file_data,
+                &torrent_status,
+                &choker,
+                &target_files,
+            )?;
+            assert_eq!(2, local_peer.upload_queue.len());
+            local_peer.apply_choker_decision(&torrent_file_data, &choker)?;
 
+            assert!(local_peer.upload_queue.is_empty());
             assert_eq!(
-                local_peer.send_requested_block(
-                    &torrent_file_data,
-                    &torrent_status,
-                    piece_index.try_into()?,
-                    beginning_byte_index,
-                    amount_of_bytes,
-                ),
-                Err(InteractionHandlerErrorKind::Recoverable(
-                    InteractionHandlerError::SendingRequestedBlock(
-                        "CheckingRequestBlock(\"[TorrentFileDataError] The requested amount of bytes is bigger than 2^14 bytes.\")"
-                            .to_string(),
-                    ),
-                ))
+                P2PMessage::Piece {
+                    piece_index: 0,
+                    beginning_byte_index: 0,
+                    block: piece[..BLOCK_BYTES as usize].to_vec(),
+                },
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert_eq!(
+                P2PMessage::Choke,
+                msg_receiver::receive_message(&mut external_stream)?
             );
+            for beginning_byte_index in [BLOCK_BYTES, 2 * BLOCK_BYTES] {
+                assert!(matches!(
+                    msg_receiver::receive_message(&mut external_stream)?,
+                    P2PMessage::RejectRequest {
+                        piece_index: 0,
+                        beginning_byte_index: rejected_byte_index,
+                        ..
+                    } if rejected_byte_index == beginning_byte_index
+                ));
+            }
 
-            fs::remove_dir_all(format!("temp/{}", path))?;
+            fs::remove_dir_all(&path)?;
             Ok(())
         }
+    }
+
+    mod test_connection_health {
+        use super::*;
 
         #[test]
-        fn local_peer_sends_a_request_ok() -> Result<(), Box<dyn Error>> {
+        fn snubbed_peer_releases_its_requests_and_late_blocks_are_ignored_ok(
+        ) -> Result<(), Box<dyn Error>> {
             let (listener, address) =
                 try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
             let (_, torrent_status, torrent_file_data, mut local_peer, _log_receiver, _ui_receiver) =
-                create_default_client_with_a_piece_for_requests(address, 6)?;
+                create_default_client_with_a_piece_for_requests(address, 15)?;
             let (mut external_stream, _) = listener.accept()?;
-            local_peer.external_peer_data.am_choking = false;
-
-            let block_0 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_1 = [10; BLOCK_BYTES as usize].to_vec();
-            let block_2 = [10; (34000 - 2 * BLOCK_BYTES) as usize].to_vec();
-
-            let piece_index = 0;
-            let beginning_byte_index = BLOCK_BYTES;
-            let amount_of_bytes = BLOCK_BYTES;
-
-            let path = torrent_file_data.get_torrent_representative_name();
-            fs::create_dir(format!("temp/{}", path))?;
-
-            block_handler::store_block(&block_0, piece_index, &path)?;
-            block_handler::store_block(&block_1, piece_index, &path)?;
-            block_handler::store_block(&block_2, piece_index, &path)?;
+            local_peer.connection_health =
+                ConnectionHealth::new(Duration::from_secs(180), Duration::ZERO);
+            local_peer.pending_requests.push(BlockRequest {
+                piece_index: 1,
+                beginning_byte_index: 0,
+                amount_of_bytes: 6000,
+            });
+            local_peer
+                .connection_health
+                .block_requested_at(Instant::now());
+
+            local_peer.release_requests_if_snubbed(&torrent_status)?;
+
+            assert!(local_peer.connection_health.is_snubbed());
+            assert!(local_peer.pending_requests.is_empty());
+            assert_eq!(
+                P2PMessage::Cancel {
+                    piece_index: 1,
+                    beginning_byte_index: 0,
+                    amount_of_bytes: 6000,
+                },
+                msg_receiver::receive_message(&mut external_stream)?
+            );
+            assert_eq!(
+                Some(&PieceStatus::MissingPiece {
+                    was_requested: false
+                }),
+                torrent_status
+                    .read()
+                    .map_err(|err| format!("{:?}", err))?
+                    .get_piece_status(1)
+            );
 
-            local_peer.send_requested_block(
+            local_peer.update_information_according_to_the_received_msg(
                 &torrent_file_data,
                 &torrent_status,
-                piece_index.try_into()?,
-                beginning_byte_index,
-                amount_of_bytes,
+                &TargetFiles::new(&torrent_file_data, "temp/test_client")?,
+                &P2PMessage::Piece {
+                    piece_index: 1,
+                    beginning_byte_index: 0,
+                    block: vec![0; 6000],
+                },
             )?;
-
-            let received_msg = msg_receiver::receive_message(&mut external_stream)?;
-            let expected_msg = P2PMessage::Piece {
-                piece_index: piece_index.try_into()?,
-                beginning_byte_index,
-                block: block_1,
-            };
-
-            assert_eq!(expected_msg, received_msg);
-
-            fs::remove_dir_all(format!("temp/{}", path))?;
+            assert!(!local_peer.connection_health.is_snubbed());
+            assert_eq!(
+                Some(&PieceStatus::MissingPiece {
+                    was_requested: false
+                }),
+                torrent_status
+                    .read()
+                    .map_err(|err| format!("{:?}", err))?
+                    .get_piece_status(1)
+            );
             Ok(())
         }
     }
@@ -0,0 +1,393 @@
+//! # Modulo de intercambio de metadata
+//! Este modulo contiene las funciones encargadas de obtener de un peer el diccionario info de un
+//! torrent del que solo se conoce su info hash (por ejemplo, a partir de un magnet link), usando
+//! el extension protocol (BEP 10) y la extension ut_metadata (BEP 9).
+//!
+
+use super::{extensions::CLIENT_NAME, msg_receiver, msg_sender};
+use log::{debug, info};
+use shared::parsers::p2p::{
+    self,
+    constants::{ID_EXTENDED_HANDSHAKE, NUM_OF_RESERVED_BYTES_HANDSHAKE, PSTR_STRING_HANDSHAKE},
+    extension::{ExtendedHandshake, MetadataMessage, METADATA_PIECE_SIZE, UT_METADATA},
+    message::P2PMessage,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::Write,
+    net::{SocketAddr, TcpStream},
+    time::{Duration, Instant},
+};
+
+/// Id con el que le pedimos a los peers que nos envien los mensajes de ut_metadata
+pub const LOCAL_UT_METADATA_ID: u8 = 1;
+/// Tamaño maximo aceptado para la metadata de un torrent
+pub const MAX_METADATA_SIZE: u64 = 8 * 1024 * 1024;
+
+const SECS_CONNECT_TIMEOUT: u64 = 5;
+const SECS_READ_TIMEOUT: u64 = 10;
+const SECS_EXCHANGE_TIMEOUT: u64 = 60;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa un tipo de error al obtener la metadata de un peer
+pub enum MetadataExchangeError {
+    ConectingWithPeer(String),
+    SendingMessage(String),
+    ReceivingMessage(String),
+    InvalidHandshake(String),
+    ExtensionProtocolNotSupported,
+    MetadataNotSupported,
+    InvalidMetadataSize(u64),
+    InvalidMetadataPiece(String),
+    MetadataRejected(u32),
+    ExchangeTimeout,
+}
+
+impl fmt::Display for MetadataExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for MetadataExchangeError {}
+
+type ResultExchange<T> = Result<T, MetadataExchangeError>;
+
+fn open_connection_with_peer(peer_address: SocketAddr) -> ResultExchange<TcpStream> {
+    let stream =
+        TcpStream::connect_timeout(&peer_address, Duration::from_secs(SECS_CONNECT_TIMEOUT))
+            .map_err(|error| MetadataExchangeError::ConectingWithPeer(format!("{}", error)))?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(SECS_READ_TIMEOUT)))
+        .map_err(|error| MetadataExchangeError::ConectingWithPeer(format!("{}", error)))?;
+    Ok(stream)
+}
+
+//Todavia no se tiene el TorrentFileData, por lo que el handshake se arma a partir del info hash.
+fn send_handshake(stream: &mut TcpStream, info_hash: &[u8], peer_id: &[u8]) -> ResultExchange<()> {
+    let mut reserved = [0; NUM_OF_RESERVED_BYTES_HANDSHAKE];
+    p2p::encoder::set_extension_protocol_bit(&mut reserved);
+    let handshake_bytes = p2p::encoder::to_bytes(P2PMessage::Handshake {
+        protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+        reserved,
+        info_hash: info_hash.to_vec(),
+        peer_id: peer_id.to_vec(),
+    })
+    .map_err(|error| MetadataExchangeError::SendingMessage(format!("{}", error)))?;
+
+    stream
+        .write_all(&handshake_bytes)
+        .map_err(|error| MetadataExchangeError::SendingMessage(format!("{}", error)))
+}
+
+fn receive_handshake(stream: &mut TcpStream, info_hash: &[u8]) -> ResultExchange<()> {
+    let handshake = msg_receiver::receive_handshake(stream)
+        .map_err(|error| MetadataExchangeError::ReceivingMessage(format!("{}", error)))?;
+
+    match handshake {
+        P2PMessage::Handshake {
+            reserved,
+            info_hash: received_info_hash,
+            ..
+        } if received_info_hash == info_hash => {
+            if !p2p::decoder::supports_extension_protocol(&reserved) {
+                return Err(MetadataExchangeError::ExtensionProtocolNotSupported);
+            }
+            Ok(())
+        }
+        _ => Err(MetadataExchangeError::InvalidHandshake(
+            "The peer answered with an invalid handshake or another info hash".to_string(),
+        )),
+    }
+}
+
+fn send_extended_handshake(stream: &mut TcpStream) -> ResultExchange<()> {
+    let extended_handshake = ExtendedHandshake {
+        extensions: HashMap::from([(UT_METADATA.to_string(), LOCAL_UT_METADATA_ID)]),
+        metadata_size: None,
+        client: Some(CLIENT_NAME.to_string()),
+    };
+    msg_sender::send_extended(
+        stream,
+        ID_EXTENDED_HANDSHAKE,
+        extended_handshake.to_payload(),
+    )
+    .map_err(|error| MetadataExchangeError::SendingMessage(format!("{}", error)))
+}
+
+fn send_metadata_message(
+    stream: &mut TcpStream,
+    ext_id: u8,
+    message: MetadataMessage,
+) -> ResultExchange<()> {
+    msg_sender::send_extended(stream, ext_id, message.to_payload())
+        .map_err(|error| MetadataExchangeError::SendingMessage(format!("{}", error)))
+}
+
+//Recibo mensajes hasta obtener uno Extended con el id dado, ignorando el resto (bitfield,
+//have, etc.), siempre que no se haya cumplido el tiempo limite del intercambio.
+fn receive_extended(
+    stream: &mut TcpStream,
+    expected_ext_id: u8,
+    deadline: Instant,
+) -> ResultExchange<Vec<u8>> {
+    while Instant::now() < deadline {
+        match msg_receiver::receive_message(stream)
+            .map_err(|error| MetadataExchangeError::ReceivingMessage(format!("{}", error)))?
+        {
+            P2PMessage::Extended { ext_id, payload } if ext_id == expected_ext_id => {
+                return Ok(payload)
+            }
+            msg => debug!(
+                "Mensaje ignorado durante el intercambio de metadata: {:?}",
+                msg
+            ),
+        }
+    }
+    Err(MetadataExchangeError::ExchangeTimeout)
+}
+
+fn receive_extended_handshake(
+    stream: &mut TcpStream,
+    deadline: Instant,
+) -> ResultExchange<(u8, u64)> {
+    let payload = receive_extended(stream, ID_EXTENDED_HANDSHAKE, deadline)?;
+    let extended_handshake = ExtendedHandshake::from_payload(&payload)
+        .map_err(|error| MetadataExchangeError::ReceivingMessage(format!("{}", error)))?;
+
+    let ut_metadata_id = extended_handshake
+        .get_extension_id(UT_METADATA)
+        .ok_or(MetadataExchangeError::MetadataNotSupported)?;
+    let metadata_size = extended_handshake
+        .metadata_size
+        .ok_or(MetadataExchangeError::MetadataNotSupported)?;
+    if metadata_size == 0 || metadata_size > MAX_METADATA_SIZE {
+        return Err(MetadataExchangeError::InvalidMetadataSize(metadata_size));
+    }
+    Ok((ut_metadata_id, metadata_size))
+}
+
+fn calculate_amount_of_metadata_pieces(metadata_size: u64) -> u32 {
+    metadata_size.div_ceil(METADATA_PIECE_SIZE) as u32
+}
+
+fn calculate_metadata_piece_length(piece: u32, metadata_size: u64) -> u64 {
+    let beginning = u64::from(piece) * METADATA_PIECE_SIZE;
+    METADATA_PIECE_SIZE.min(metadata_size - beginning)
+}
+
+//Valido que la pieza recibida sea una de las pedidas y tenga el tamaño esperado.
+fn check_metadata_piece(
+    piece: u32,
+    total_size: u64,
+    data: &[u8],
+    metadata_size: u64,
+) -> ResultExchange<()> {
+    if total_size != metadata_size
+        || piece >= calculate_amount_of_metadata_pieces(metadata_size)
+        || data.len() as u64 != calculate_metadata_piece_length(piece, metadata_size)
+    {
+        return Err(MetadataExchangeError::InvalidMetadataPiece(format!(
+            "Piece {} with {} bytes of a total of {}",
+            piece,
+            data.len(),
+            total_size
+        )));
+    }
+    Ok(())
+}
+
+fn receive_metadata_pieces(
+    stream: &mut TcpStream,
+    ut_metadata_id: u8,
+    metadata_size: u64,
+    deadline: Instant,
+) -> ResultExchange<Vec<u8>> {
+    let amount_of_pieces = calculate_amount_of_metadata_pieces(metadata_size);
+    let mut pieces: Vec<Option<Vec<u8>>> = vec![None; amount_of_pieces as usize];
+
+    while pieces.iter().any(Option::is_none) {
+        let payload = receive_extended(stream, LOCAL_UT_METADATA_ID, deadline)?;
+        let message = MetadataMessage::from_payload(&payload)
+            .map_err(|error| MetadataExchangeError::ReceivingMessage(format!("{}", error)))?;
+        match message {
+            MetadataMessage::Data {
+                piece,
+                total_size,
+                data,
+            } => {
+                check_metadata_piece(piece, total_size, &data, metadata_size)?;
+                pieces[piece as usize] = Some(data);
+            }
+            MetadataMessage::Reject { piece } => {
+                return Err(MetadataExchangeError::MetadataRejected(piece))
+            }
+            //Todavia no tenemos la metadata, por lo que no podemos compartirla
+            MetadataMessage::Request { piece } => {
+                send_metadata_message(stream, ut_metadata_id, MetadataMessage::Reject { piece })?
+            }
+        }
+    }
+    Ok(pieces.into_iter().flatten().flatten().collect())
+}
+
+///
+/// Funcion que se conecta al peer dado y le pide, pieza por pieza, el diccionario info del
+/// torrent con el info hash dado. Devuelve los bytes del diccionario tal como fueron recibidos
+/// (en bencoding), sin verificar que su hash sea el esperado.
+///
+pub fn fetch_metadata_from_peer(
+    peer_address: SocketAddr,
+    info_hash: &[u8],
+    peer_id: &[u8],
+) -> ResultExchange<Vec<u8>> {
+    let deadline = Instant::now() + Duration::from_secs(SECS_EXCHANGE_TIMEOUT);
+    let mut stream = open_connection_with_peer(peer_address)?;
+    info!("Conectado con {} para obtener la metadata", peer_address);
+
+    send_handshake(&mut stream, info_hash, peer_id)?;
+    receive_handshake(&mut stream, info_hash)?;
+    send_extended_handshake(&mut stream)?;
+    let (ut_metadata_id, metadata_size) = receive_extended_handshake(&mut stream, deadline)?;
+    debug!(
+        "El peer {} ofrece {} bytes de metadata",
+        peer_address, metadata_size
+    );
+
+    for piece in 0..calculate_amount_of_metadata_pieces(metadata_size) {
+        send_metadata_message(
+            &mut stream,
+            ut_metadata_id,
+            MetadataMessage::Request { piece },
+        )?;
+    }
+    receive_metadata_pieces(&mut stream, ut_metadata_id, metadata_size, deadline)
+}
+
+#[cfg(test)]
+mod test_metadata_exchange {
+    use super::*;
+    use std::{
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    const INFO_HASH: [u8; 20] = [7; 20];
+    const REMOTE_UT_METADATA_ID: u8 = 3;
+
+    type FakePeer = (SocketAddr, JoinHandle<Result<(), String>>);
+
+    fn receive_extended_payload(stream: &mut TcpStream) -> Result<(u8, Vec<u8>), String> {
+        loop {
+            match msg_receiver::receive_message(stream).map_err(|err| format!("{}", err))? {
+                P2PMessage::Extended { ext_id, payload } => return Ok((ext_id, payload)),
+                _ => continue,
+            }
+        }
+    }
+
+    ///
+    /// Peer de prueba que soporta ut_metadata y responde los pedidos con la metadata dada.
+    /// Si reject es true, rechaza todos los pedidos.
+    ///
+    fn spawn_fake_peer(metadata: Vec<u8>, reject: bool) -> Result<FakePeer, Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let handler = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().map_err(|err| format!("{}", err))?;
+            let handshake =
+                msg_receiver::receive_handshake(&mut stream).map_err(|err| format!("{}", err))?;
+            match &handshake {
+                P2PMessage::Handshake { reserved, .. }
+                    if p2p::decoder::supports_extension_protocol(reserved) => {}
+                _ => return Err("The client did not set the extension bit".to_string()),
+            }
+            let handshake = p2p::encoder::to_bytes(handshake).map_err(|err| format!("{}", err))?;
+            stream
+                .write_all(&handshake)
+                .map_err(|err| format!("{}", err))?;
+            msg_sender::send_bitfield(
+                &mut stream,
+                &crate::torrent::data::torrent_status::TorrentStatus::new(0, 0),
+            )
+            .map_err(|err| format!("{}", err))?;
+
+            let (_, payload) = receive_extended_payload(&mut stream)?;
+            let client_handshake =
+                ExtendedHandshake::from_payload(&payload).map_err(|err| format!("{}", err))?;
+            let client_ut_metadata_id = client_handshake
+                .get_extension_id(UT_METADATA)
+                .ok_or("The client does not support ut_metadata")?;
+            let handshake = ExtendedHandshake {
+                extensions: HashMap::from([(UT_METADATA.to_string(), REMOTE_UT_METADATA_ID)]),
+                metadata_size: Some(metadata.len() as u64),
+                client: None,
+            };
+            msg_sender::send_extended(&mut stream, ID_EXTENDED_HANDSHAKE, handshake.to_payload())
+                .map_err(|err| format!("{}", err))?;
+
+            for _ in 0..calculate_amount_of_metadata_pieces(metadata.len() as u64) {
+                let (ext_id, payload) = receive_extended_payload(&mut stream)?;
+                assert_eq!(REMOTE_UT_METADATA_ID, ext_id);
+                let piece = match MetadataMessage::from_payload(&payload) {
+                    Ok(MetadataMessage::Request { piece }) => piece,
+                    other => return Err(format!("Unexpected message {:?}", other)),
+                };
+                let response = match reject {
+                    true => MetadataMessage::Reject { piece },
+                    false => {
+                        let beginning = (u64::from(piece) * METADATA_PIECE_SIZE) as usize;
+                        let length =
+                            calculate_metadata_piece_length(piece, metadata.len() as u64) as usize;
+                        MetadataMessage::Data {
+                            piece,
+                            total_size: metadata.len() as u64,
+                            data: metadata[beginning..beginning + length].to_vec(),
+                        }
+                    }
+                };
+                msg_sender::send_extended(
+                    &mut stream,
+                    client_ut_metadata_id,
+                    response.to_payload(),
+                )
+                .map_err(|err| format!("{}", err))?;
+            }
+            Ok(())
+        });
+        Ok((address, handler))
+    }
+
+    #[test]
+    fn fetch_metadata_of_several_pieces_ok() -> Result<(), Box<dyn Error>> {
+        let metadata: Vec<u8> = (0..40000).map(|i| (i % 251) as u8).collect();
+        let (address, handler) = spawn_fake_peer(metadata.clone(), false)?;
+
+        let fetched_metadata = fetch_metadata_from_peer(address, &INFO_HASH, &[1; 20])?;
+
+        assert_eq!(metadata, fetched_metadata);
+        handler.join().map_err(|_| "The fake peer panicked")??;
+        Ok(())
+    }
+
+    #[test]
+    fn rejected_request_error() -> Result<(), Box<dyn Error>> {
+        let (address, _handler) = spawn_fake_peer(vec![1; 100], true)?;
+
+        assert_eq!(
+            Err(MetadataExchangeError::MetadataRejected(0)),
+            fetch_metadata_from_peer(address, &INFO_HASH, &[1; 20])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_metadata_pieces_error() {
+        assert!(check_metadata_piece(0, 100, &[0; 100], 100).is_ok());
+        assert!(check_metadata_piece(1, 100, &[0; 100], 100).is_err());
+        assert!(check_metadata_piece(0, 101, &[0; 100], 100).is_err());
+        assert!(check_metadata_piece(0, 20000, &[0; 100], 20000).is_err());
+    }
+}
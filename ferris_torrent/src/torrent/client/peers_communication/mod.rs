@@ -3,7 +3,17 @@
 //! realizar la comunicación con peers dados previamente por un tracker.
 //!
 
+pub mod choker;
+pub mod connection_health;
+pub mod connection_manager;
+pub mod extensions;
+pub mod fast_extension;
 pub mod handler_communication;
 pub mod local_peer_communicator;
+pub mod metadata_exchange;
 pub mod msg_receiver;
 pub mod msg_sender;
+pub mod peer_exchange;
+pub mod peer_listener;
+pub mod seeding;
+pub mod upload_queue;
@@ -5,7 +5,12 @@
 use shared::parsers::p2p::{self, constants::TOTAL_NUM_OF_BYTES_HANDSHAKE, message::P2PMessage};
 
 use core::fmt;
-use std::{error::Error, io::Read, net::TcpStream};
+use std::{
+    error::Error,
+    io::{ErrorKind, Read},
+    net::TcpStream,
+    time::Duration,
+};
 
 #[derive(PartialEq, Eq, Debug)]
 /// Representa un tipo de error en la recepcion de mensajes P2P
@@ -80,6 +85,62 @@ pub fn receive_message(stream: &mut TcpStream) -> Result<P2PMessage, MsgReceiver
     build_msg(stream, buffer_lenght_prefix, lenght_prefix_value)
 }
 
+///
+/// Funcion que espera como maximo el tiempo dado a que llegue un mensaje, sin consumirlo.
+/// Devuelve true si hay bytes para leer y false si se cumplio el tiempo de espera.
+///
+pub fn wait_for_message(stream: &TcpStream, timeout: Duration) -> Result<bool, MsgReceiverError> {
+    let previous_timeout = stream
+        .read_timeout()
+        .map_err(|error| MsgReceiverError::ReadingFromTcpStream(format!("{}", error)))?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|error| MsgReceiverError::ReadingFromTcpStream(format!("{}", error)))?;
+
+    let mut buffer = [0; 1];
+    let peek_result = stream.peek(&mut buffer);
+
+    stream
+        .set_read_timeout(previous_timeout)
+        .map_err(|error| MsgReceiverError::ReadingFromTcpStream(format!("{}", error)))?;
+
+    match peek_result {
+        Ok(0) => Err(MsgReceiverError::ReadingFromTcpStream(
+            "The connection was closed by the external peer.".to_string(),
+        )),
+        Ok(_) => Ok(true),
+        Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            Ok(false)
+        }
+        Err(error) => Err(MsgReceiverError::ReadingFromTcpStream(format!("{}", error))),
+    }
+}
+
+///
+/// Funcion que indica, sin esperar ni consumirlo, si ya llego un mensaje para leer.
+///
+pub fn is_message_available(stream: &TcpStream) -> Result<bool, MsgReceiverError> {
+    stream
+        .set_nonblocking(true)
+        .map_err(|error| MsgReceiverError::ReadingFromTcpStream(format!("{}", error)))?;
+
+    let mut buffer = [0; 1];
+    let peek_result = stream.peek(&mut buffer);
+
+    stream
+        .set_nonblocking(false)
+        .map_err(|error| MsgReceiverError::ReadingFromTcpStream(format!("{}", error)))?;
+
+    match peek_result {
+        Ok(0) => Err(MsgReceiverError::ReadingFromTcpStream(
+            "The connection was closed by the external peer.".to_string(),
+        )),
+        Ok(_) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(false),
+        Err(error) => Err(MsgReceiverError::ReadingFromTcpStream(format!("{}", error))),
+    }
+}
+
 #[cfg(test)]
 mod test_msg_receiver {
     use super::*;
@@ -106,6 +167,7 @@ mod test_msg_receiver {
 
             let handshake = P2PMessage::Handshake {
                 protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
                 info_hash: [1; 20].to_vec(),
                 peer_id: "-FA0001-000000000000".bytes().collect(),
             };
@@ -126,6 +188,7 @@ mod test_msg_receiver {
 
             let handshake = P2PMessage::Handshake {
                 protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
                 info_hash: [1; 20].to_vec(),
                 peer_id: "-FA0001-000000000000".bytes().collect(),
             };
@@ -148,6 +211,7 @@ mod test_msg_receiver {
 
             let handshake = P2PMessage::Handshake {
                 protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
                 info_hash: [1; 20].to_vec(),
                 peer_id: "-FA0001-000000000000".bytes().collect(),
             };
@@ -262,4 +326,59 @@ mod test_msg_receiver {
             Ok(())
         }
     }
+
+    mod test_wait_for_message {
+        use super::*;
+
+        #[test]
+        fn wait_for_a_message_that_arrives_ok() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let mut sender_stream = TcpStream::connect(address)?;
+            let (mut receptor_stream, _addr) = listener.accept()?;
+
+            let message = P2PMessage::Unchoke;
+            let buffer = p2p::encoder::to_bytes(message.clone())?;
+            sender_stream.write_all(&buffer)?;
+
+            assert!(wait_for_message(&receptor_stream, Duration::from_secs(1))?);
+            assert_eq!(message, receive_message(&mut receptor_stream)?);
+            Ok(())
+        }
+
+        #[test]
+        fn wait_for_a_message_that_does_not_arrive_ok() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let _sender_stream = TcpStream::connect(address)?;
+            let (receptor_stream, _addr) = listener.accept()?;
+
+            assert!(!wait_for_message(
+                &receptor_stream,
+                Duration::from_millis(100)
+            )?);
+            Ok(())
+        }
+    }
+
+    mod test_is_message_available {
+        use super::*;
+
+        #[test]
+        fn a_message_is_available_only_after_it_arrives_ok() -> Result<(), Box<dyn Error>> {
+            let (listener, address) =
+                try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+            let mut sender_stream = TcpStream::connect(address)?;
+            let (mut receptor_stream, _addr) = listener.accept()?;
+            assert!(!is_message_available(&receptor_stream)?);
+
+            let message = P2PMessage::Unchoke;
+            sender_stream.write_all(&p2p::encoder::to_bytes(message.clone())?)?;
+
+            assert!(wait_for_message(&receptor_stream, Duration::from_secs(1))?);
+            assert!(is_message_available(&receptor_stream)?);
+            assert_eq!(message, receive_message(&mut receptor_stream)?);
+            Ok(())
+        }
+    }
 }
@@ -7,7 +7,11 @@ use log::info;
 use crate::torrent::data::torrent_status::TorrentStatus;
 
 use shared::{
-    parsers::{p2p, p2p::constants::PSTR_STRING_HANDSHAKE, p2p::message::P2PMessage},
+    parsers::{
+        p2p,
+        p2p::constants::{NUM_OF_RESERVED_BYTES_HANDSHAKE, PSTR_STRING_HANDSHAKE},
+        p2p::message::P2PMessage,
+    },
     torrent_file_data::TorrentFileData,
 };
 
@@ -36,15 +40,21 @@ impl Error for MsgSenderError {}
 
 const MAX_BLOCK_BYTES: u32 = 131072; //2^17 bytes
 
-/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Handshake
+/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Handshake.
+/// En los bytes reservados se indica que se soporta el extension protocol (BEP 10)
+/// y la fast extension (BEP 6).
 ///
 pub fn send_handshake(
     stream: &mut TcpStream,
     peer_id: &[u8],
     torrent_file_data: &TorrentFileData,
 ) -> Result<(), MsgSenderError> {
+    let mut reserved = [0; NUM_OF_RESERVED_BYTES_HANDSHAKE];
+    p2p::encoder::set_extension_protocol_bit(&mut reserved);
+    p2p::encoder::set_fast_extension_bit(&mut reserved);
     let handshake_bytes = p2p::encoder::to_bytes(P2PMessage::Handshake {
         protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+        reserved,
         info_hash: torrent_file_data.get_info_hash(),
         peer_id: peer_id.to_vec(),
     })
@@ -66,6 +76,17 @@ fn send_msg(stream: &mut TcpStream, msg_variant: P2PMessage) -> Result<(), MsgSe
     Ok(())
 }
 
+/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Extended, con el payload
+/// ya codificado de la extension correspondiente al id dado
+///
+pub fn send_extended(
+    stream: &mut TcpStream,
+    ext_id: u8,
+    payload: Vec<u8>,
+) -> Result<(), MsgSenderError> {
+    send_msg(stream, P2PMessage::Extended { ext_id, payload })
+}
+
 /// Funcion encargada de codificar y enviar un mensaje P2P de tipo Keep Alive
 ///
 pub fn send_keep_alive(stream: &mut TcpStream) -> Result<(), MsgSenderError> {
@@ -117,6 +138,24 @@ pub fn send_bitfield(
     send_msg(stream, bitfield_msg)
 }
 
+/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Have All
+///
+pub fn send_have_all(stream: &mut TcpStream) -> Result<(), MsgSenderError> {
+    send_msg(stream, P2PMessage::HaveAll)
+}
+
+/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Have None
+///
+pub fn send_have_none(stream: &mut TcpStream) -> Result<(), MsgSenderError> {
+    send_msg(stream, P2PMessage::HaveNone)
+}
+
+/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Allowed Fast
+///
+pub fn send_allowed_fast(stream: &mut TcpStream, piece_index: u32) -> Result<(), MsgSenderError> {
+    send_msg(stream, P2PMessage::AllowedFast { piece_index })
+}
+
 fn check_request_or_cancel_fields(amount_of_bytes: u32) -> Result<(), MsgSenderError> {
     if amount_of_bytes == 0 {
         return Err(MsgSenderError::ZeroAmountOfBytes(
@@ -136,21 +175,10 @@ fn check_request_or_cancel_fields(amount_of_bytes: u32) -> Result<(), MsgSenderE
 ///
 pub fn send_request(
     stream: &mut TcpStream,
-    torrent_file_data: &TorrentFileData,
-    torrent_status: &TorrentStatus,
-    piece_index: usize,
+    piece_index: u32,
+    beginning_byte_index: u32,
+    amount_of_bytes: u32,
 ) -> Result<(), MsgSenderError> {
-    let beginning_byte_index = torrent_status
-        .calculate_beginning_byte_index(piece_index)
-        .map_err(|err| MsgSenderError::SendingRequest(format!("{}", err)))?;
-    let amount_of_bytes = torrent_status
-        .calculate_amount_of_bytes_of_block(torrent_file_data, piece_index, beginning_byte_index)
-        .map_err(|err| MsgSenderError::SendingRequest(format!("{}", err)))?;
-    let piece_index = piece_index
-        .try_into()
-        .map_err(|error| MsgSenderError::SendingRequest(format!("{}", error)))?;
-
-    //habria que ver si ese checkeo sigue siendo necesario
     check_request_or_cancel_fields(amount_of_bytes)?;
     let request_msg = P2PMessage::Request {
         piece_index,
@@ -205,21 +233,10 @@ pub fn send_piece(
 ///
 pub fn send_cancel(
     stream: &mut TcpStream,
-    torrent_file_data: &TorrentFileData,
-    torrent_status: &TorrentStatus,
-    piece_index: usize,
+    piece_index: u32,
+    beginning_byte_index: u32,
+    amount_of_bytes: u32,
 ) -> Result<(), MsgSenderError> {
-    let beginning_byte_index = torrent_status
-        .calculate_beginning_byte_index(piece_index)
-        .map_err(|err| MsgSenderError::SendingRequest(format!("{}", err)))?;
-    let amount_of_bytes = torrent_status
-        .calculate_amount_of_bytes_of_block(torrent_file_data, piece_index, beginning_byte_index)
-        .map_err(|err| MsgSenderError::SendingRequest(format!("{}", err)))?;
-    let piece_index = piece_index
-        .try_into()
-        .map_err(|error| MsgSenderError::SendingRequest(format!("{}", error)))?;
-
-    //habria que ver si ese checkeo sigue siendo necesario
     check_request_or_cancel_fields(amount_of_bytes)?;
     let cancel_msg = P2PMessage::Cancel {
         piece_index,
@@ -229,6 +246,22 @@ pub fn send_cancel(
     send_msg(stream, cancel_msg)
 }
 
+/// Funcion encargada de codificar y enviar un mensaje P2P de tipo Reject Request
+///
+pub fn send_reject_request(
+    stream: &mut TcpStream,
+    piece_index: u32,
+    beginning_byte_index: u32,
+    amount_of_bytes: u32,
+) -> Result<(), MsgSenderError> {
+    let reject_request_msg = P2PMessage::RejectRequest {
+        piece_index,
+        beginning_byte_index,
+        amount_of_bytes,
+    };
+    send_msg(stream, reject_request_msg)
+}
+
 #[cfg(test)]
 mod test_msg_sender {
     use gtk::glib;
@@ -236,11 +269,16 @@ mod test_msg_sender {
     use super::*;
     use crate::torrent::{
         client::peers_communication::{
+            connection_health::ConnectionHealth,
+            extensions::ExtensionRegistry,
+            fast_extension::FastExtension,
             local_peer_communicator::{LocalPeerCommunicator, PeerRole},
             msg_receiver,
+            upload_queue::UploadQueue,
         },
         data::{
             peer_data_for_communication::PeerDataForP2PCommunication,
+            piece_picker::SequentialPicker,
             torrent_status::{StateOfDownload, TorrentStatus},
             tracker_response_data::{PeerDataFromTrackerResponse, TrackerResponseData},
         },
@@ -249,7 +287,10 @@ mod test_msg_sender {
     use shared::{
         parsers::p2p::{
             self,
-            constants::{PSTR_STRING_HANDSHAKE, TOTAL_NUM_OF_BYTES_HANDSHAKE},
+            constants::{
+                EXTENSION_PROTOCOL_BIT, FAST_EXTENSION_BIT, PSTR_STRING_HANDSHAKE,
+                TOTAL_NUM_OF_BYTES_HANDSHAKE,
+            },
             message::{P2PMessage, PieceStatus},
         },
         port_binder::listener_binder::*,
@@ -257,7 +298,12 @@ mod test_msg_sender {
     };
 
     use std::{
-        error::Error, io::Read, net::SocketAddr, str::FromStr, sync::mpsc, time::SystemTime,
+        error::Error,
+        io::Read,
+        net::SocketAddr,
+        str::FromStr,
+        sync::{mpsc, Arc},
+        time::SystemTime,
     };
 
     pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
@@ -286,7 +332,8 @@ mod test_msg_sender {
         };
         let tracker_response = TrackerResponseData {
             interval: 0,
-            //tracker_id: DEFAULT_TRACKER_ID.to_string(),
+            min_interval: None,
+            tracker_id: None,
             complete: 1,
             incomplete: 0,
             peers: vec![server_peer],
@@ -299,6 +346,8 @@ mod test_msg_sender {
             pieces_availability: vec![PieceStatus::MissingPiece {
                 was_requested: false,
             }],
+            pieces_peer_count: vec![0; 1],
+            pieces_requesters_count: vec![0; 1],
         };
         let torrent_file = TorrentFileData {
             target_files_data: TargetFilesData::SingleFile {
@@ -330,6 +379,13 @@ mod test_msg_sender {
             logger_sender: mpsc::channel().0,
             ui_sender: ui_sender,
             clock: SystemTime::now(),
+            pending_requests: vec![],
+            request_queue_depth: 5,
+            piece_picker: Arc::new(SequentialPicker),
+            extensions: ExtensionRegistry::new(),
+            fast_extension: FastExtension::default(),
+            upload_queue: UploadQueue::new(),
+            connection_health: ConnectionHealth::default(),
         };
         Ok((tracker_response, torrent_status, torrent_file, local_peer))
     }
@@ -355,6 +411,7 @@ mod test_msg_sender {
 
         let expected_msg = P2PMessage::Handshake {
             protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+            reserved: [0, 0, 0, 0, 0, EXTENSION_PROTOCOL_BIT, 0, FAST_EXTENSION_BIT],
             info_hash: torrent_file_data.sha1_info_hash.clone(),
             peer_id: local_peer.peer_id.clone(),
         };
@@ -571,31 +628,8 @@ mod test_msg_sender {
         let (listener, address) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
         let mut sender_stream = TcpStream::connect(address)?;
         let (mut receptor_stream, _addr) = listener.accept()?;
-        let torrent_file_data = TorrentFileData {
-            url_tracker_main: "tracker_main.com".to_string(),
-            url_tracker_list: vec![],
-            sha1_pieces: vec![],
-            sha1_info_hash: DEFAULT_INFO_HASH.to_vec(),
-            piece_length: 8,
-            total_length: 8,
-            total_amount_of_pieces: 1,
-            target_files_data: TargetFilesData::SingleFile {
-                file_name: "name.txt".to_string(),
-                file_length: 8,
-            },
-        };
-        let torrent_status = TorrentStatus {
-            uploaded: 0,
-            downloaded: 4,
-            left: 4,
-            event: StateOfDownload::Started,
-            pieces_availability: vec![PieceStatus::PartiallyDownloaded {
-                downloaded_bytes: 4,
-                was_requested: false,
-            }],
-        };
 
-        assert!(send_request(&mut sender_stream, &torrent_file_data, &torrent_status, 0).is_ok());
+        assert!(send_request(&mut sender_stream, 0, 4, 4).is_ok());
 
         let received_msg = msg_receiver::receive_message(&mut receptor_stream)?;
         let expected_msg = P2PMessage::Request {
@@ -666,31 +700,8 @@ mod test_msg_sender {
         let (listener, address) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
         let mut sender_stream = TcpStream::connect(address)?;
         let (mut receptor_stream, _addr) = listener.accept()?;
-        let torrent_file_data = TorrentFileData {
-            url_tracker_main: "tracker_main.com".to_string(),
-            url_tracker_list: vec![],
-            sha1_pieces: vec![],
-            sha1_info_hash: DEFAULT_INFO_HASH.to_vec(),
-            piece_length: 8,
-            total_length: 8,
-            total_amount_of_pieces: 1,
-            target_files_data: TargetFilesData::SingleFile {
-                file_name: "name.txt".to_string(),
-                file_length: 8,
-            },
-        };
-        let torrent_status = TorrentStatus {
-            uploaded: 0,
-            downloaded: 4,
-            left: 4,
-            event: StateOfDownload::Started,
-            pieces_availability: vec![PieceStatus::PartiallyDownloaded {
-                downloaded_bytes: 4,
-                was_requested: false,
-            }],
-        };
 
-        assert!(send_cancel(&mut sender_stream, &torrent_file_data, &torrent_status, 0).is_ok());
+        assert!(send_cancel(&mut sender_stream, 0, 4, 4).is_ok());
 
         let received_msg = msg_receiver::receive_message(&mut receptor_stream)?;
         let expected_msg = P2PMessage::Cancel {
@@ -703,4 +714,45 @@ mod test_msg_sender {
 
         Ok(())
     }
+
+    #[test]
+    fn client_peer_send_reject_request_ok() -> Result<(), Box<dyn Error>> {
+        let (listener, address) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+        let mut sender_stream = TcpStream::connect(address)?;
+        let (mut receptor_stream, _addr) = listener.accept()?;
+
+        assert!(send_reject_request(&mut sender_stream, 1, 0, 16384).is_ok());
+
+        let received_msg = msg_receiver::receive_message(&mut receptor_stream)?;
+        let expected_msg = P2PMessage::RejectRequest {
+            piece_index: 1,
+            beginning_byte_index: 0,
+            amount_of_bytes: 16384,
+        };
+
+        assert_eq!(expected_msg, received_msg);
+
+        Ok(())
+    }
+
+    #[test]
+    fn client_peer_send_have_all_and_allowed_fast_ok() -> Result<(), Box<dyn Error>> {
+        let (listener, address) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+        let mut sender_stream = TcpStream::connect(address)?;
+        let (mut receptor_stream, _addr) = listener.accept()?;
+
+        assert!(send_have_all(&mut sender_stream).is_ok());
+        assert!(send_allowed_fast(&mut sender_stream, 3).is_ok());
+
+        assert_eq!(
+            P2PMessage::HaveAll,
+            msg_receiver::receive_message(&mut receptor_stream)?
+        );
+        assert_eq!(
+            P2PMessage::AllowedFast { piece_index: 3 },
+            msg_receiver::receive_message(&mut receptor_stream)?
+        );
+
+        Ok(())
+    }
 }
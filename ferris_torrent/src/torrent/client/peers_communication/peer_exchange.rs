@@ -0,0 +1,262 @@
+//! # Modulo de peer exchange (ut_pex)
+//! Este modulo contiene la extension ut_pex (BEP 11), mediante la cual se le informa
+//! periodicamente a cada peer externo a que peers estamos conectados, y se agregan al pool de
+//! candidatos del ConnectionManager los peers que nos informan ellos. De esta forma se pueden
+//! encontrar nuevos peers aunque el tracker deje de responder.
+//!
+
+use super::{
+    connection_manager::ConnectionManager,
+    extensions::{ExtensionHandler, ExtensionHandlerError},
+};
+use crate::torrent::data::tracker_response_data::PeerDataFromTrackerResponse;
+use log::debug;
+use shared::parsers::p2p::extension::{PexMessage, UT_PEX};
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Tiempo minimo entre dos mensajes de ut_pex enviados a un mismo peer
+pub const SECS_PEX_INTERVAL: u64 = 60;
+/// Cantidad maxima de peers agregados (y de peers quitados) por mensaje
+pub const MAX_PEX_PEERS: usize = 50;
+
+/// Extension ut_pex para la comunicacion con un peer externo. Recuerda que peers ya se le
+/// informaron para enviarle solo las diferencias.
+#[derive(Debug)]
+pub struct PexExtension {
+    connection_manager: Arc<RwLock<ConnectionManager>>,
+    external_peer_addr: SocketAddr,
+    sent_peers: HashSet<SocketAddr>,
+    last_sent_at: Option<Instant>,
+}
+
+impl PexExtension {
+    pub fn new(
+        connection_manager: Arc<RwLock<ConnectionManager>>,
+        external_peer_addr: SocketAddr,
+    ) -> Self {
+        PexExtension {
+            connection_manager,
+            external_peer_addr,
+            sent_peers: HashSet::new(),
+            last_sent_at: None,
+        }
+    }
+
+    fn get_connected_peers(&self) -> Result<HashSet<SocketAddr>, ExtensionHandlerError> {
+        let connection_manager = self
+            .connection_manager
+            .read()
+            .map_err(|error| ExtensionHandlerError::HandlingMessage(format!("{:?}", error)))?;
+        Ok(connection_manager
+            .get_connected_peer_addresses()
+            .into_iter()
+            .filter(|peer_address| *peer_address != self.external_peer_addr)
+            .collect())
+    }
+
+    ///
+    /// Funcion que arma, si ya paso el intervalo desde el ultimo envio, el mensaje con los peers
+    /// a los que nos conectamos y de los que nos desconectamos desde entonces.
+    ///
+    fn poll_messages_at(&mut self, now: Instant) -> Result<Vec<Vec<u8>>, ExtensionHandlerError> {
+        if self
+            .last_sent_at
+            .is_some_and(|last_sent_at| now < last_sent_at + Duration::from_secs(SECS_PEX_INTERVAL))
+        {
+            return Ok(vec![]);
+        }
+        self.last_sent_at = Some(now);
+
+        let connected_peers = self.get_connected_peers()?;
+        let pex = PexMessage {
+            added: connected_peers
+                .difference(&self.sent_peers)
+                .take(MAX_PEX_PEERS)
+                .copied()
+                .collect(),
+            dropped: self
+                .sent_peers
+                .difference(&connected_peers)
+                .take(MAX_PEX_PEERS)
+                .copied()
+                .collect(),
+        };
+        if pex.added.is_empty() && pex.dropped.is_empty() {
+            return Ok(vec![]);
+        }
+        self.sent_peers.extend(pex.added.iter());
+        for peer_address in pex.dropped.iter() {
+            self.sent_peers.remove(peer_address);
+        }
+        Ok(vec![pex.to_payload()])
+    }
+}
+
+impl ExtensionHandler for PexExtension {
+    fn get_name(&self) -> &str {
+        UT_PEX
+    }
+
+    fn handle_message(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>, ExtensionHandlerError> {
+        let pex = PexMessage::from_payload(payload)
+            .map_err(|error| ExtensionHandlerError::HandlingMessage(format!("{}", error)))?;
+        let peers: Vec<PeerDataFromTrackerResponse> = pex
+            .added
+            .into_iter()
+            .take(MAX_PEX_PEERS)
+            .map(|peer_address| PeerDataFromTrackerResponse {
+                peer_id: None,
+                peer_address,
+            })
+            .collect();
+
+        let mut connection_manager = self
+            .connection_manager
+            .write()
+            .map_err(|error| ExtensionHandlerError::HandlingMessage(format!("{:?}", error)))?;
+        let amount_of_new_peers = connection_manager.add_peers(&peers);
+        debug!(
+            "Se agregaron {} peers recibidos por peer exchange",
+            amount_of_new_peers
+        );
+        Ok(vec![])
+    }
+
+    fn poll_messages(&mut self) -> Result<Vec<Vec<u8>>, ExtensionHandlerError> {
+        self.poll_messages_at(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod test_peer_exchange {
+    use super::*;
+    use crate::torrent::data::tracker_response_data::TrackerResponseData;
+    use std::{error::Error, str::FromStr};
+
+    fn create_connection_manager(
+        amount_of_peers: u16,
+    ) -> Result<Arc<RwLock<ConnectionManager>>, Box<dyn Error>> {
+        let mut peers = vec![];
+        for port in 0..amount_of_peers {
+            peers.push(PeerDataFromTrackerResponse {
+                peer_id: None,
+                peer_address: SocketAddr::from_str(&format!("127.0.0.1:{}", 8000 + port))?,
+            });
+        }
+        let tracker_response = TrackerResponseData {
+            interval: 0,
+            min_interval: None,
+            tracker_id: None,
+            complete: 0,
+            incomplete: 0,
+            peers,
+        };
+        Ok(Arc::new(RwLock::new(ConnectionManager::new(
+            &tracker_response,
+            10,
+            10,
+        ))))
+    }
+
+    fn connect_all_peers(
+        connection_manager: &Arc<RwLock<ConnectionManager>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut connection_manager = connection_manager
+            .write()
+            .map_err(|error| format!("{:?}", error))?;
+        while let Some(peer_index) = connection_manager.take_next_candidate(Instant::now()) {
+            connection_manager.connection_established(peer_index);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn connected_peers_are_sent_once_per_interval_ok() -> Result<(), Box<dyn Error>> {
+        let connection_manager = create_connection_manager(3)?;
+        connect_all_peers(&connection_manager)?;
+        let mut pex =
+            PexExtension::new(connection_manager, SocketAddr::from_str("127.0.0.1:8000")?);
+        let now = Instant::now();
+
+        let payloads = pex.poll_messages_at(now)?;
+        assert_eq!(1, payloads.len());
+        let mut sent = PexMessage::from_payload(&payloads[0])?;
+        sent.added.sort();
+        assert_eq!(
+            vec![
+                SocketAddr::from_str("127.0.0.1:8001")?,
+                SocketAddr::from_str("127.0.0.1:8002")?
+            ],
+            sent.added
+        );
+        assert!(sent.dropped.is_empty());
+
+        assert!(pex.poll_messages_at(now)?.is_empty());
+        let after_interval = now + Duration::from_secs(SECS_PEX_INTERVAL);
+        assert!(pex.poll_messages_at(after_interval)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn disconnected_peers_are_sent_as_dropped_ok() -> Result<(), Box<dyn Error>> {
+        let connection_manager = create_connection_manager(2)?;
+        connect_all_peers(&connection_manager)?;
+        let mut pex = PexExtension::new(
+            connection_manager.clone(),
+            SocketAddr::from_str("10.0.0.1:6881")?,
+        );
+        let now = Instant::now();
+        pex.poll_messages_at(now)?;
+
+        connection_manager
+            .write()
+            .map_err(|error| format!("{:?}", error))?
+            .connection_closed(0, now);
+
+        let payloads = pex.poll_messages_at(now + Duration::from_secs(SECS_PEX_INTERVAL))?;
+        assert_eq!(
+            vec![PexMessage {
+                added: vec![],
+                dropped: vec![SocketAddr::from_str("127.0.0.1:8000")?],
+            }
+            .to_payload()],
+            payloads
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn received_peers_are_added_to_the_candidates_ok() -> Result<(), Box<dyn Error>> {
+        let connection_manager = create_connection_manager(1)?;
+        let mut pex = PexExtension::new(
+            connection_manager.clone(),
+            SocketAddr::from_str("10.0.0.1:6881")?,
+        );
+        let payload = PexMessage {
+            added: vec![
+                SocketAddr::from_str("127.0.0.1:8000")?,
+                SocketAddr::from_str("10.0.0.2:6881")?,
+            ],
+            dropped: vec![],
+        }
+        .to_payload();
+
+        assert!(pex.handle_message(&payload)?.is_empty());
+
+        let peer_pool = connection_manager
+            .read()
+            .map_err(|error| format!("{:?}", error))?
+            .get_peer_pool();
+        assert_eq!(2, peer_pool.get_total_amount_peers());
+        assert_eq!(
+            Some(SocketAddr::from_str("10.0.0.2:6881")?),
+            peer_pool.get_peer_address(1)
+        );
+        Ok(())
+    }
+}
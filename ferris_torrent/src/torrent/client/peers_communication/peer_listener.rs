@@ -0,0 +1,392 @@
+//! # Modulo de escucha de conexiones entrantes
+//! Este modulo contiene al listener compartido por todos los torrents. Acepta las conexiones
+//! entrantes en un unico puerto, lee el handshake de cada una y la deriva al torrent
+//! correspondiente segun su info_hash.
+//!
+
+use log::{debug, info};
+use shared::{
+    parsers::p2p::message::P2PMessage,
+    port_binder::listener_binder::{bind_dual_stack, canonical_address},
+    torrent_file_data::TorrentFileData,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{local_peer_communicator::SECS_READ_TIMEOUT, msg_receiver};
+
+/// Tiempo de espera entre consultas al listener cuando no hay conexiones pendientes
+pub const MILLIS_BETWEEN_ACCEPTS: u64 = 100;
+/// Cantidad maxima de conexiones entrantes de las cuales se esta esperando el handshake a la vez
+pub const MAX_PENDING_HANDSHAKES: usize = 50;
+
+/// Conexion entrante de la cual ya se leyo el handshake
+#[derive(Debug)]
+pub struct IncomingPeer {
+    pub stream: TcpStream,
+    pub external_peer_addr: SocketAddr,
+    pub handshake: P2PMessage,
+}
+
+pub type IncomingPeerReceiver = Receiver<IncomingPeer>;
+
+/// Representa un tipo de error en la escucha de conexiones entrantes
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PeerListenerError {
+    Binding(String),
+    Accepting(String),
+    ReadingShutDownField(String),
+    UpdatingTorrentsRegistry(String),
+    RoutingConnection(String),
+}
+
+impl fmt::Display for PeerListenerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for PeerListenerError {}
+
+///
+/// Registro compartido de los torrents que se encuentran activos. Asocia el info_hash de
+/// cada torrent con el canal por el cual se le envian las conexiones entrantes.
+///
+#[derive(Debug, Clone, Default)]
+pub struct TorrentsRegistry {
+    torrents: Arc<RwLock<HashMap<Vec<u8>, Sender<IncomingPeer>>>>,
+}
+
+impl TorrentsRegistry {
+    /// Funcion que crea un registro sin torrents
+    ///
+    pub fn new() -> Self {
+        TorrentsRegistry::default()
+    }
+
+    ///
+    /// Funcion que registra un torrent para empezar a recibir las conexiones entrantes
+    /// dirigidas a su info_hash. Devuelve el extremo del canal por el cual llegan.
+    ///
+    pub fn register_torrent(
+        &self,
+        torrent_file_data: &TorrentFileData,
+    ) -> Result<IncomingPeerReceiver, PeerListenerError> {
+        let (sender, receiver) = channel();
+        self.torrents
+            .write()
+            .map_err(|error| PeerListenerError::UpdatingTorrentsRegistry(format!("{:?}", error)))?
+            .insert(torrent_file_data.get_info_hash(), sender);
+        Ok(receiver)
+    }
+
+    /// Funcion que deja de derivar conexiones entrantes a un torrent
+    ///
+    pub fn unregister_torrent(
+        &self,
+        torrent_file_data: &TorrentFileData,
+    ) -> Result<(), PeerListenerError> {
+        self.torrents
+            .write()
+            .map_err(|error| PeerListenerError::UpdatingTorrentsRegistry(format!("{:?}", error)))?
+            .remove(&torrent_file_data.get_info_hash());
+        Ok(())
+    }
+
+    ///
+    /// Funcion que deriva una conexion entrante al torrent cuyo info_hash coincide con el del
+    /// handshake recibido. Si no hay ningun torrent registrado con ese info_hash, se devuelve error
+    /// (y la conexion se cierra al descartarse).
+    ///
+    pub fn route_incoming_peer(
+        &self,
+        incoming_peer: IncomingPeer,
+    ) -> Result<(), PeerListenerError> {
+        let info_hash = match &incoming_peer.handshake {
+            P2PMessage::Handshake { info_hash, .. } => info_hash.clone(),
+            _ => {
+                return Err(PeerListenerError::RoutingConnection(
+                    "[PeerListenerError] The received message is not a handshake.".to_string(),
+                ))
+            }
+        };
+        let torrents = self
+            .torrents
+            .read()
+            .map_err(|error| PeerListenerError::UpdatingTorrentsRegistry(format!("{:?}", error)))?;
+        let sender = torrents.get(&info_hash).ok_or_else(|| {
+            PeerListenerError::RoutingConnection(
+                "[PeerListenerError] There is no active torrent with the received info_hash."
+                    .to_string(),
+            )
+        })?;
+        sender
+            .send(incoming_peer)
+            .map_err(|error| PeerListenerError::RoutingConnection(format!("{}", error)))
+    }
+}
+
+fn is_shut_down_set(global_shut_down: &Arc<RwLock<bool>>) -> Result<bool, PeerListenerError> {
+    let global_shut_down = global_shut_down
+        .read()
+        .map_err(|error| PeerListenerError::ReadingShutDownField(format!("{:?}", error)))?;
+    Ok(*global_shut_down)
+}
+
+//Solo los errores que indican que el listener dejo de ser valido son fatales; el resto (como un
+//peer que corta la conexion antes de aceptarla o quedarse sin descriptores) son transitorios.
+fn is_fatal_accepting_error(error_kind: ErrorKind) -> bool {
+    matches!(error_kind, ErrorKind::InvalidInput | ErrorKind::Unsupported)
+}
+
+///
+/// Funcion que lee el handshake de una conexion recien aceptada y la deriva al torrent
+/// correspondiente. Se ejecuta en un thread propio para que un peer lento no demore al resto.
+/// Si ya se estan esperando [MAX_PENDING_HANDSHAKES] handshakes, la conexion se cierra.
+///
+fn handle_accepted_connection(
+    mut stream: TcpStream,
+    external_peer_addr: SocketAddr,
+    torrents_registry: TorrentsRegistry,
+    pending_handshakes: &Arc<AtomicUsize>,
+) {
+    let is_below_the_limit = pending_handshakes
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |amount| {
+            (amount < MAX_PENDING_HANDSHAKES).then_some(amount + 1)
+        })
+        .is_ok();
+    if !is_below_the_limit {
+        debug!(
+            "Se descarta la conexion entrante de {}: demasiados handshakes pendientes",
+            external_peer_addr
+        );
+        return;
+    }
+    let pending_handshakes = pending_handshakes.clone();
+    thread::spawn(move || {
+        let result = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(Duration::from_secs(SECS_READ_TIMEOUT))))
+            .map_err(|error| PeerListenerError::Accepting(format!("{}", error)))
+            .and_then(|_| {
+                msg_receiver::receive_handshake(&mut stream)
+                    .map_err(|error| PeerListenerError::RoutingConnection(format!("{}", error)))
+            })
+            .and_then(|handshake| {
+                torrents_registry.route_incoming_peer(IncomingPeer {
+                    stream,
+                    external_peer_addr,
+                    handshake,
+                })
+            });
+        pending_handshakes.fetch_sub(1, Ordering::SeqCst);
+        if let Err(error) = result {
+            debug!(
+                "Se descarta la conexion entrante de {}: {}",
+                external_peer_addr, error
+            );
+        }
+    });
+}
+
+///
+/// Funcion que abre el puerto en el cual se escuchan las conexiones entrantes de todos los torrents.
+/// Si se indica una direccion local solo se escucha en la misma; si no, se escucha en todas las
+/// interfaces, tanto por IPv4 como por IPv6.
+///
+pub fn bind_listener(
+    port: u32,
+    bind_address: Option<IpAddr>,
+) -> Result<TcpListener, PeerListenerError> {
+    let listener = match bind_address {
+        Some(ip) => TcpListener::bind(SocketAddr::new(ip, port as u16)),
+        None => bind_dual_stack(port as u16),
+    }
+    .map_err(|error| PeerListenerError::Binding(format!("{}", error)))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|error| PeerListenerError::Binding(format!("{}", error)))?;
+    match bind_address {
+        Some(ip) => info!(
+            "Escuchando conexiones entrantes en {}",
+            SocketAddr::new(ip, port as u16)
+        ),
+        None => info!("Escuchando conexiones entrantes en el puerto {}", port),
+    }
+    Ok(listener)
+}
+
+///
+/// FUNCION PRINCIPAL
+/// Funcion encargada de escuchar todas las conexiones entrantes del listener dado, derivando cada
+/// una al torrent que corresponda segun el registro compartido. Los errores transitorios al
+/// aceptar una conexion se ignoran.
+/// La funcion finaliza cuando se activa el shutdown global.
+///
+pub fn listen_for_incoming_peers(
+    listener: TcpListener,
+    torrents_registry: TorrentsRegistry,
+    global_shut_down: Arc<RwLock<bool>>,
+) -> Result<(), PeerListenerError> {
+    let pending_handshakes = Arc::new(AtomicUsize::new(0));
+    loop {
+        if is_shut_down_set(&global_shut_down)? {
+            return Ok(());
+        }
+        match listener.accept() {
+            Ok((stream, external_peer_addr)) => handle_accepted_connection(
+                stream,
+                canonical_address(external_peer_addr),
+                torrents_registry.clone(),
+                &pending_handshakes,
+            ),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(MILLIS_BETWEEN_ACCEPTS))
+            }
+            Err(error) if is_fatal_accepting_error(error.kind()) => {
+                return Err(PeerListenerError::Accepting(format!("{}", error)))
+            }
+            Err(error) => {
+                info!("Fallo al aceptar una conexion entrante: {}", error);
+                thread::sleep(Duration::from_millis(MILLIS_BETWEEN_ACCEPTS))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_peer_listener {
+    use super::*;
+    use shared::parsers::p2p::constants::PSTR_STRING_HANDSHAKE;
+    use shared::torrent_file_data::TargetFilesData;
+    use std::net::TcpListener;
+
+    fn create_torrent_file_data(info_hash: Vec<u8>) -> TorrentFileData {
+        TorrentFileData {
+            target_files_data: TargetFilesData::SingleFile {
+                file_name: "resulting_filename.test".to_string(),
+                file_length: 16,
+            },
+            sha1_pieces: vec![],
+            url_tracker_main: "tracker_main.com".to_string(),
+            url_tracker_list: vec![],
+            sha1_info_hash: info_hash,
+            piece_length: 16,
+            total_amount_of_pieces: 1,
+            total_length: 16,
+        }
+    }
+
+    fn create_incoming_peer(info_hash: Vec<u8>) -> Result<IncomingPeer, Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let stream = TcpStream::connect(listener.local_addr()?)?;
+        let external_peer_addr = listener.local_addr()?;
+        Ok(IncomingPeer {
+            stream,
+            external_peer_addr,
+            handshake: P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                reserved: [0; 8],
+                info_hash,
+                peer_id: [1; 20].to_vec(),
+            },
+        })
+    }
+
+    mod test_route_incoming_peer {
+        use super::*;
+
+        #[test]
+        fn connections_are_routed_by_info_hash_ok() -> Result<(), Box<dyn Error>> {
+            let torrents_registry = TorrentsRegistry::new();
+            let receiver_1 =
+                torrents_registry.register_torrent(&create_torrent_file_data(vec![1; 20]))?;
+            let receiver_2 =
+                torrents_registry.register_torrent(&create_torrent_file_data(vec![2; 20]))?;
+
+            torrents_registry.route_incoming_peer(create_incoming_peer(vec![2; 20])?)?;
+
+            assert!(receiver_1.try_recv().is_err());
+            let incoming_peer = receiver_2.try_recv()?;
+            if let P2PMessage::Handshake { info_hash, .. } = incoming_peer.handshake {
+                assert_eq!(vec![2; 20], info_hash);
+                return Ok(());
+            }
+            Err("The routed message is not a handshake".into())
+        }
+
+        #[test]
+        fn connection_for_an_unknown_torrent_is_rejected_error() -> Result<(), Box<dyn Error>> {
+            let torrents_registry = TorrentsRegistry::new();
+            let torrent_file_data = create_torrent_file_data(vec![1; 20]);
+            let _receiver = torrents_registry.register_torrent(&torrent_file_data)?;
+            torrents_registry.unregister_torrent(&torrent_file_data)?;
+
+            assert!(torrents_registry
+                .route_incoming_peer(create_incoming_peer(vec![1; 20])?)
+                .is_err());
+            Ok(())
+        }
+    }
+
+    mod test_handle_accepted_connection {
+        use super::*;
+        use std::io::Read;
+
+        #[test]
+        fn connections_over_the_pending_handshakes_limit_are_closed_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let mut external_stream = TcpStream::connect(listener.local_addr()?)?;
+            let (stream, external_peer_addr) = listener.accept()?;
+            let pending_handshakes = Arc::new(AtomicUsize::new(MAX_PENDING_HANDSHAKES));
+
+            handle_accepted_connection(
+                stream,
+                external_peer_addr,
+                TorrentsRegistry::new(),
+                &pending_handshakes,
+            );
+
+            external_stream.set_read_timeout(Some(Duration::from_secs(SECS_READ_TIMEOUT)))?;
+            assert_eq!(0, external_stream.read(&mut [0; 1])?);
+            assert_eq!(
+                MAX_PENDING_HANDSHAKES,
+                pending_handshakes.load(Ordering::SeqCst)
+            );
+            Ok(())
+        }
+    }
+
+    mod test_bind_listener {
+        use super::*;
+
+        #[test]
+        fn the_listener_is_bound_to_the_given_address_ok() -> Result<(), Box<dyn Error>> {
+            let listener = bind_listener(0, Some(IpAddr::from([127, 0, 0, 1])))?;
+
+            assert_eq!(IpAddr::from([127, 0, 0, 1]), listener.local_addr()?.ip());
+            Ok(())
+        }
+
+        #[test]
+        fn an_address_that_is_not_local_cannot_be_bound_error() {
+            assert!(matches!(
+                bind_listener(0, Some(IpAddr::from([192, 0, 2, 1]))),
+                Err(PeerListenerError::Binding(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! # Modulo de seeding
+//! Este modulo contiene las condiciones bajo las cuales se deja de compartir un torrent
+//! que ya fue descargado por completo (relacion de subida y tiempo compartiendo).
+//!
+
+use std::time::Duration;
+
+use crate::torrent::data::config_file_torrent::ConfigFileTorrent;
+
+const SECS_PER_MINUTE: u64 = 60;
+
+/// Limites a partir de los cuales se deja de compartir un torrent completo.
+/// Un limite en None indica que no se tiene en cuenta.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SeedingLimits {
+    pub ratio_limit: Option<f64>,
+    pub time_limit: Option<Duration>,
+}
+
+impl SeedingLimits {
+    /// Funcion que crea los limites de seeding a partir del archivo de configuracion
+    ///
+    pub fn new(config_data: &ConfigFileTorrent) -> Self {
+        let seed_ratio_limit = config_data.get_seed_ratio_limit();
+        let seed_time_limit = config_data.get_seed_time_limit() as u64;
+        SeedingLimits {
+            ratio_limit: (seed_ratio_limit > 0.0).then_some(seed_ratio_limit),
+            time_limit: (seed_time_limit > 0)
+                .then(|| Duration::from_secs(seed_time_limit * SECS_PER_MINUTE)),
+        }
+    }
+
+    ///
+    /// Funcion que indica si ya se alcanzo alguno de los limites de seeding, a partir de la
+    /// cantidad de bytes subidos, el tamaño total del torrent y el tiempo que se lleva compartiendo.
+    ///
+    pub fn is_reached(&self, uploaded: u64, total_length: u64, seeding_time: Duration) -> bool {
+        let is_ratio_reached = self.ratio_limit.is_some_and(|ratio_limit| {
+            total_length > 0 && (uploaded as f64 / total_length as f64) >= ratio_limit
+        });
+        let is_time_reached = self
+            .time_limit
+            .is_some_and(|time_limit| seeding_time >= time_limit);
+        is_ratio_reached || is_time_reached
+    }
+}
+
+#[cfg(test)]
+mod test_seeding {
+    use super::*;
+
+    mod test_is_reached {
+        use super::*;
+
+        #[test]
+        fn without_limits_the_torrent_is_seeded_forever_ok() {
+            let seeding_limits = SeedingLimits {
+                ratio_limit: None,
+                time_limit: None,
+            };
+
+            assert!(!seeding_limits.is_reached(1000, 10, Duration::from_secs(u64::MAX)));
+        }
+
+        #[test]
+        fn the_ratio_limit_is_reached_ok() {
+            let seeding_limits = SeedingLimits {
+                ratio_limit: Some(1.5),
+                time_limit: None,
+            };
+
+            assert!(!seeding_limits.is_reached(14, 10, Duration::ZERO));
+            assert!(seeding_limits.is_reached(15, 10, Duration::ZERO));
+        }
+
+        #[test]
+        fn the_time_limit_is_reached_ok() {
+            let seeding_limits = SeedingLimits {
+                ratio_limit: None,
+                time_limit: Some(Duration::from_secs(60)),
+            };
+
+            assert!(!seeding_limits.is_reached(0, 10, Duration::from_secs(59)));
+            assert!(seeding_limits.is_reached(0, 10, Duration::from_secs(60)));
+        }
+    }
+}
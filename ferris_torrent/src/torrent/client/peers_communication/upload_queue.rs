@@ -0,0 +1,206 @@
+//! # Modulo de cola de envios
+//! Este modulo contiene la cola de bloques que un peer externo nos pidio y todavia no le
+//! enviamos. Los pedidos se encolan al recibir un Request, se quitan al recibir un Cancel o al
+//! chokear al peer (salvo los de su allowed fast set), y se envian por lotes de una misma pieza
+//! para leerla de disco una sola vez.
+//!
+
+use super::local_peer_communicator::BlockRequest;
+use std::collections::VecDeque;
+
+/// Cantidad maxima de pedidos encolados por peer externo, para que no pueda inundarnos de Requests
+pub const MAX_UPLOAD_QUEUE_LEN: usize = 64;
+
+/// Cola de bloques pedidos por un peer externo que todavia no se le enviaron.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct UploadQueue {
+    requests: VecDeque<BlockRequest>,
+}
+
+///
+/// Funcion que devuelve un pedido que abarca a todos los pedidos de un lote (los cuales deben
+/// ser de una misma pieza), desde el primer byte pedido hasta el ultimo. Permite leer de disco
+/// todos los bloques del lote de una sola vez.
+///
+pub fn merge_batch(batch: &[BlockRequest]) -> Option<BlockRequest> {
+    let piece_index = batch.first()?.piece_index;
+    let beginning_byte_index = batch
+        .iter()
+        .map(|block_request| block_request.beginning_byte_index)
+        .min()?;
+    let end_byte_index = batch
+        .iter()
+        .map(|block_request| block_request.beginning_byte_index + block_request.amount_of_bytes)
+        .max()?;
+    Some(BlockRequest {
+        piece_index,
+        beginning_byte_index,
+        amount_of_bytes: end_byte_index - beginning_byte_index,
+    })
+}
+
+impl UploadQueue {
+    pub fn new() -> Self {
+        UploadQueue::default()
+    }
+
+    /// Funcion que encola un pedido. Devuelve false si la cola esta llena y el pedido
+    /// se descarto (un pedido repetido no se vuelve a encolar).
+    ///
+    pub fn push(&mut self, block_request: BlockRequest) -> bool {
+        if self.requests.contains(&block_request) {
+            return true;
+        }
+        if self.requests.len() >= MAX_UPLOAD_QUEUE_LEN {
+            return false;
+        }
+        self.requests.push_back(block_request);
+        true
+    }
+
+    /// Funcion que quita de la cola un pedido cancelado por el peer externo.
+    /// Devuelve true si el pedido estaba encolado.
+    ///
+    pub fn cancel(&mut self, block_request: &BlockRequest) -> bool {
+        let previous_len = self.requests.len();
+        self.requests
+            .retain(|queued_request| queued_request != block_request);
+        self.requests.len() != previous_len
+    }
+
+    /// Funcion que vacia la cola, devolviendo los pedidos que quedaron sin enviar
+    ///
+    pub fn clear(&mut self) -> Vec<BlockRequest> {
+        self.requests.drain(..).collect()
+    }
+
+    /// Funcion que quita de la cola los pedidos que cumplen la condicion dada, devolviendolos
+    /// en el orden en el que estaban encolados.
+    ///
+    pub fn remove_where(&mut self, condition: impl Fn(&BlockRequest) -> bool) -> Vec<BlockRequest> {
+        let (removed, kept): (Vec<BlockRequest>, Vec<BlockRequest>) = self
+            .requests
+            .drain(..)
+            .partition(|block_request| condition(block_request));
+        self.requests = kept.into();
+        removed
+    }
+
+    /// Funcion que vuelve a poner al principio de la cola, en el mismo orden, los pedidos de un
+    /// lote que no se llegaron a enviar.
+    ///
+    pub fn requeue(&mut self, block_requests: Vec<BlockRequest>) {
+        for block_request in block_requests.into_iter().rev() {
+            self.requests.push_front(block_request);
+        }
+    }
+
+    /// Funcion que saca de la cola el proximo lote a enviar: el primer pedido encolado junto
+    /// con todos los demas pedidos de su misma pieza, ordenados por su byte de comienzo.
+    ///
+    pub fn take_next_batch(&mut self) -> Vec<BlockRequest> {
+        let piece_index = match self.requests.front() {
+            Some(block_request) => block_request.piece_index,
+            None => return vec![],
+        };
+        let (mut batch, rest): (Vec<BlockRequest>, Vec<BlockRequest>) = self
+            .requests
+            .drain(..)
+            .partition(|block_request| block_request.piece_index == piece_index);
+        self.requests = rest.into();
+        batch.sort_by_key(|block_request| block_request.beginning_byte_index);
+        batch
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test_upload_queue {
+    use super::*;
+
+    fn block_request(piece_index: usize, beginning_byte_index: u32) -> BlockRequest {
+        BlockRequest {
+            piece_index,
+            beginning_byte_index,
+            amount_of_bytes: 16384,
+        }
+    }
+
+    #[test]
+    fn the_queue_length_is_bounded_ok() {
+        let mut upload_queue = UploadQueue::new();
+        for block_index in 0..MAX_UPLOAD_QUEUE_LEN {
+            assert!(upload_queue.push(block_request(0, block_index as u32 * 16384)));
+        }
+        assert!(upload_queue.push(block_request(0, 0)));
+        assert!(!upload_queue.push(block_request(1, 0)));
+        assert_eq!(MAX_UPLOAD_QUEUE_LEN, upload_queue.len());
+    }
+
+    #[test]
+    fn cancelled_and_cleared_requests_are_removed_ok() {
+        let mut upload_queue = UploadQueue::new();
+        upload_queue.push(block_request(0, 0));
+        upload_queue.push(block_request(0, 16384));
+        upload_queue.push(block_request(1, 0));
+
+        assert!(upload_queue.cancel(&block_request(0, 16384)));
+        assert!(!upload_queue.cancel(&block_request(2, 0)));
+        assert_eq!(
+            vec![block_request(0, 0), block_request(1, 0)],
+            upload_queue.clear()
+        );
+        assert!(upload_queue.is_empty());
+    }
+
+    #[test]
+    fn removed_requests_keep_their_order_and_requeued_ones_go_first_ok() {
+        let mut upload_queue = UploadQueue::new();
+        upload_queue.push(block_request(0, 0));
+        upload_queue.push(block_request(1, 0));
+        upload_queue.push(block_request(0, 16384));
+
+        assert_eq!(
+            vec![block_request(0, 0), block_request(0, 16384)],
+            upload_queue.remove_where(|block_request| block_request.piece_index == 0)
+        );
+        upload_queue.requeue(vec![block_request(2, 0), block_request(2, 16384)]);
+        assert_eq!(
+            vec![
+                block_request(2, 0),
+                block_request(2, 16384),
+                block_request(1, 0)
+            ],
+            upload_queue.clear()
+        );
+    }
+
+    #[test]
+    fn the_next_batch_has_every_request_of_the_first_piece_ok() {
+        let mut upload_queue = UploadQueue::new();
+        upload_queue.push(block_request(3, 32768));
+        upload_queue.push(block_request(1, 0));
+        upload_queue.push(block_request(3, 0));
+
+        let batch = upload_queue.take_next_batch();
+        assert_eq!(vec![block_request(3, 0), block_request(3, 32768)], batch);
+        assert_eq!(
+            Some(BlockRequest {
+                piece_index: 3,
+                beginning_byte_index: 0,
+                amount_of_bytes: 49152,
+            }),
+            merge_batch(&batch)
+        );
+        assert_eq!(vec![block_request(1, 0)], upload_queue.take_next_batch());
+        assert!(upload_queue.take_next_batch().is_empty());
+        assert_eq!(None, merge_batch(&[]));
+    }
+}
@@ -0,0 +1,587 @@
+//! # Modulo de reanudacion de descargas
+//! Este modulo contiene las funciones encargadas de persistir el estado de la
+//! descarga de un torrent (archivo "fast-resume" bencodeado junto a los datos)
+//! y de reconstruir el TorrentStatus a partir de lo que ya se encuentra en disco.
+//!
+
+use core::fmt;
+use log::{debug, info};
+use shared::{
+    parsers::{
+        bencoding::{self, values::ValuesBencoding},
+        p2p::message::PieceStatus,
+    },
+    torrent_file_data::TorrentFileData,
+};
+use std::{collections::HashMap, error::Error, fs};
+
+use crate::torrent::{
+    client::{
+        block_handler::{self, TargetFiles},
+        peers_communication::handler_communication::BLOCK_BYTES,
+    },
+    data::torrent_status::{StateOfDownload, TorrentStatus},
+};
+
+const RESUME_FILE_EXTENSION: &str = "fastresume";
+
+const INFO_HASH: &[u8] = b"info_hash";
+const PIECES: &[u8] = b"pieces";
+const PARTIAL_PIECES: &[u8] = b"partial_pieces";
+const PIECE_INDEX: &[u8] = b"piece";
+const DOWNLOADED_BLOCKS: &[u8] = b"downloaded_blocks";
+
+const DOWNLOADED: u8 = b'1';
+const NOT_DOWNLOADED: u8 = b'0';
+
+/// Representa un error al guardar o recuperar el estado de una descarga.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ResumeHandlerError {
+    WritingResumeFile(String),
+    ReadingResumeFile(String),
+    InvalidResumeFile(String),
+    CalculatingPieceLength(String),
+    CalculatingBlocks(String),
+}
+
+impl fmt::Display for ResumeHandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for ResumeHandlerError {}
+
+/// Estado de la descarga recuperado de un archivo fast-resume.
+#[derive(PartialEq, Eq, Debug)]
+struct ResumeData {
+    completed_pieces: Vec<bool>,
+    partial_pieces: Vec<(usize, Vec<bool>)>,
+}
+
+///
+/// Funcion que devuelve la ruta del archivo fast-resume de un torrent, el cual se guarda
+/// en el directorio de descargas junto a los datos del mismo.
+///
+pub fn get_resume_file_path(torrent_file_data: &TorrentFileData, download_path: &str) -> String {
+    format!(
+        "{}/{}.{}",
+        download_path,
+        torrent_file_data.get_torrent_representative_name(),
+        RESUME_FILE_EXTENSION
+    )
+}
+
+fn encode_resume_data(
+    torrent_file_data: &TorrentFileData,
+    torrent_status: &TorrentStatus,
+) -> Vec<u8> {
+    let mut pieces = Vec::with_capacity(torrent_status.pieces_availability.len());
+    let mut partial_pieces = vec![];
+
+    for (piece_index, piece_status) in torrent_status.pieces_availability.iter().enumerate() {
+        match piece_status {
+            PieceStatus::ValidAndAvailablePiece => pieces.push(DOWNLOADED),
+            PieceStatus::PartiallyDownloaded {
+                downloaded_blocks, ..
+            } => {
+                pieces.push(NOT_DOWNLOADED);
+                let mut partial_piece = HashMap::new();
+                partial_piece.insert(
+                    PIECE_INDEX.to_vec(),
+                    ValuesBencoding::Integer(piece_index as i64),
+                );
+                partial_piece.insert(
+                    DOWNLOADED_BLOCKS.to_vec(),
+                    ValuesBencoding::String(
+                        downloaded_blocks
+                            .iter()
+                            .map(|is_downloaded| {
+                                if *is_downloaded {
+                                    DOWNLOADED
+                                } else {
+                                    NOT_DOWNLOADED
+                                }
+                            })
+                            .collect(),
+                    ),
+                );
+                partial_pieces.push(ValuesBencoding::Dic(partial_piece));
+            }
+            PieceStatus::MissingPiece { .. } => pieces.push(NOT_DOWNLOADED),
+        }
+    }
+
+    let mut dic_resume = HashMap::new();
+    dic_resume.insert(
+        INFO_HASH.to_vec(),
+        ValuesBencoding::String(torrent_file_data.get_info_hash()),
+    );
+    dic_resume.insert(PIECES.to_vec(), ValuesBencoding::String(pieces));
+    dic_resume.insert(
+        PARTIAL_PIECES.to_vec(),
+        ValuesBencoding::List(partial_pieces),
+    );
+    bencoding::encoder::from_dic(dic_resume)
+}
+
+fn decode_partial_piece(
+    partial_piece: &ValuesBencoding,
+) -> Result<(usize, Vec<bool>), ResumeHandlerError> {
+    if let ValuesBencoding::Dic(partial_piece) = partial_piece {
+        if let (
+            Some(ValuesBencoding::Integer(piece_index)),
+            Some(ValuesBencoding::String(downloaded_blocks)),
+        ) = (
+            partial_piece.get(PIECE_INDEX),
+            partial_piece.get(DOWNLOADED_BLOCKS),
+        ) {
+            let piece_index = usize::try_from(*piece_index)
+                .map_err(|err| ResumeHandlerError::InvalidResumeFile(format!("{}", err)))?;
+            let downloaded_blocks = downloaded_blocks
+                .iter()
+                .map(|block| *block == DOWNLOADED)
+                .collect();
+            return Ok((piece_index, downloaded_blocks));
+        }
+    }
+    Err(ResumeHandlerError::InvalidResumeFile(
+        "Invalid partial piece entry.".to_string(),
+    ))
+}
+
+fn decode_resume_data(
+    torrent_file_data: &TorrentFileData,
+    resume_bytes: Vec<u8>,
+) -> Result<ResumeData, ResumeHandlerError> {
+    let (dic_resume, _) = bencoding::decoder::to_dic(resume_bytes)
+        .map_err(|err| ResumeHandlerError::InvalidResumeFile(format!("{}", err)))?;
+
+    match dic_resume.get(INFO_HASH) {
+        Some(ValuesBencoding::String(info_hash))
+            if torrent_file_data.has_expected_info_hash(info_hash) => {}
+        _ => {
+            return Err(ResumeHandlerError::InvalidResumeFile(
+                "The info hash does not match with the torrent.".to_string(),
+            ))
+        }
+    }
+
+    let completed_pieces: Vec<bool> = match dic_resume.get(PIECES) {
+        Some(ValuesBencoding::String(pieces))
+            if pieces.len() == torrent_file_data.get_total_amount_pieces() =>
+        {
+            pieces.iter().map(|piece| *piece == DOWNLOADED).collect()
+        }
+        _ => {
+            return Err(ResumeHandlerError::InvalidResumeFile(
+                "The amount of pieces does not match with the torrent.".to_string(),
+            ))
+        }
+    };
+
+    let mut partial_pieces = vec![];
+    if let Some(ValuesBencoding::List(list_partial_pieces)) = dic_resume.get(PARTIAL_PIECES) {
+        for partial_piece in list_partial_pieces {
+            partial_pieces.push(decode_partial_piece(partial_piece)?);
+        }
+    }
+
+    Ok(ResumeData {
+        completed_pieces,
+        partial_pieces,
+    })
+}
+
+fn read_resume_data(
+    torrent_file_data: &TorrentFileData,
+    download_path: &str,
+) -> Result<ResumeData, ResumeHandlerError> {
+    let resume_bytes = fs::read(get_resume_file_path(torrent_file_data, download_path))
+        .map_err(|err| ResumeHandlerError::ReadingResumeFile(format!("{}", err)))?;
+    decode_resume_data(torrent_file_data, resume_bytes)
+}
+
+///
+/// Funcion que guarda en el directorio de descargas el estado actual de la descarga de un torrent
+/// (piezas completas y bloques descargados de las piezas incompletas).
+/// El archivo se escribe primero con otro nombre y luego se renombra, para no dejar nunca
+/// un archivo fast-resume a medio escribir.
+///
+pub fn save_resume_data(
+    torrent_file_data: &TorrentFileData,
+    torrent_status: &TorrentStatus,
+    download_path: &str,
+) -> Result<(), ResumeHandlerError> {
+    let resume_file_path = get_resume_file_path(torrent_file_data, download_path);
+    let temp_resume_file_path = format!("{}.tmp", resume_file_path);
+
+    fs::write(
+        &temp_resume_file_path,
+        encode_resume_data(torrent_file_data, torrent_status),
+    )
+    .map_err(|err| ResumeHandlerError::WritingResumeFile(format!("{}", err)))?;
+    fs::rename(&temp_resume_file_path, &resume_file_path)
+        .map_err(|err| ResumeHandlerError::WritingResumeFile(format!("{}", err)))?;
+
+    debug!("Estado de la descarga guardado en {}", resume_file_path);
+    Ok(())
+}
+
+fn target_files_exist(target_files: &TargetFiles) -> bool {
+    target_files.iter().all(|(file_path, file_length)| {
+        matches!(fs::metadata(file_path), Ok(metadata) if metadata.len() == file_length)
+    })
+}
+
+///
+/// Funcion que restaura los bloques descargados de una pieza incompleta y devuelve
+/// la cantidad de bytes que representan. Las entradas que no coinciden con la pieza
+/// (o que corresponden a una pieza ya verificada) se ignoran.
+///
+fn restore_partial_piece(
+    torrent_file_data: &TorrentFileData,
+    torrent_status: &mut TorrentStatus,
+    piece_index: usize,
+    downloaded_blocks: Vec<bool>,
+) -> Result<u64, ResumeHandlerError> {
+    if piece_index >= torrent_status.pieces_availability.len()
+        || torrent_status.is_a_valid_and_available_piece(piece_index)
+        || !downloaded_blocks.iter().any(|is_downloaded| *is_downloaded)
+        || downloaded_blocks.iter().all(|is_downloaded| *is_downloaded)
+    {
+        return Ok(0);
+    }
+    let amount_of_blocks = torrent_status
+        .calculate_amount_of_blocks(torrent_file_data, piece_index)
+        .map_err(|err| ResumeHandlerError::CalculatingBlocks(format!("{}", err)))?;
+    if downloaded_blocks.len() != amount_of_blocks {
+        return Ok(0);
+    }
+
+    let mut downloaded = 0;
+    for (block_index, is_downloaded) in downloaded_blocks.iter().enumerate() {
+        if *is_downloaded {
+            let beginning_byte_index = u32::try_from(block_index)
+                .map_err(|err| ResumeHandlerError::CalculatingBlocks(format!("{}", err)))?
+                * BLOCK_BYTES;
+            downloaded += u64::from(
+                torrent_status
+                    .calculate_amount_of_bytes_of_block(
+                        torrent_file_data,
+                        piece_index,
+                        beginning_byte_index,
+                    )
+                    .map_err(|err| ResumeHandlerError::CalculatingBlocks(format!("{}", err)))?,
+            );
+        }
+    }
+    torrent_status.pieces_availability[piece_index] = PieceStatus::PartiallyDownloaded {
+        downloaded_blocks,
+        was_requested: false,
+    };
+    Ok(downloaded)
+}
+
+///
+/// FUNCION PRINCIPAL
+/// Funcion que reconstruye el estado de la descarga de un torrent a partir de los datos que ya
+/// se encuentran en el directorio de descargas.
+/// Las piezas que figuran como completas en el archivo fast-resume (o todas, si no existe dicho
+/// archivo) se vuelven a verificar contra su SHA1, y solo las que pasan la verificacion se
+/// consideran descargadas. Si no hay datos previos se devuelve un estado inicial.
+///
+pub fn load_torrent_status(
+    torrent_file_data: &TorrentFileData,
+    download_path: &str,
+) -> Result<TorrentStatus, ResumeHandlerError> {
+    let total_amount_of_pieces = torrent_file_data.get_total_amount_pieces();
+    let mut torrent_status =
+        TorrentStatus::new(torrent_file_data.get_total_length(), total_amount_of_pieces);
+
+    let target_files = match TargetFiles::new(torrent_file_data, download_path) {
+        Ok(target_files) if target_files_exist(&target_files) => target_files,
+        _ => return Ok(torrent_status),
+    };
+    info!(
+        "Se encontraron datos previos del torrent {}, verificando piezas.",
+        torrent_file_data.get_torrent_representative_name()
+    );
+
+    let resume_data = match read_resume_data(torrent_file_data, download_path) {
+        Ok(resume_data) => resume_data,
+        Err(err) => {
+            debug!("No se usa el archivo fast-resume: {}", err);
+            ResumeData {
+                completed_pieces: vec![true; total_amount_of_pieces],
+                partial_pieces: vec![],
+            }
+        }
+    };
+
+    let mut downloaded = 0;
+    for (piece_index, is_completed) in resume_data.completed_pieces.iter().enumerate() {
+        if *is_completed
+            && block_handler::check_sha1_piece(torrent_file_data, piece_index, &target_files)
+                .is_ok()
+        {
+            torrent_status.pieces_availability[piece_index] = PieceStatus::ValidAndAvailablePiece;
+            downloaded += torrent_file_data
+                .calculate_piece_lenght(piece_index)
+                .map_err(|err| ResumeHandlerError::CalculatingPieceLength(format!("{}", err)))?;
+        }
+    }
+
+    for (piece_index, downloaded_blocks) in resume_data.partial_pieces {
+        downloaded += restore_partial_piece(
+            torrent_file_data,
+            &mut torrent_status,
+            piece_index,
+            downloaded_blocks,
+        )?;
+    }
+
+    torrent_status.downloaded = downloaded;
+    torrent_status.left = torrent_file_data.get_total_length() - downloaded;
+    if torrent_status.all_pieces_completed() {
+        torrent_status.event = StateOfDownload::Completed;
+    }
+
+    info!(
+        "Piezas verificadas del torrent {}: {} de {}.",
+        torrent_file_data.get_torrent_representative_name(),
+        torrent_status.get_amount_of_downloaded_pieces(),
+        total_amount_of_pieces
+    );
+    Ok(torrent_status)
+}
+
+#[cfg(test)]
+mod tests_resume_handler {
+    use super::*;
+    use sha1::{Digest, Sha1};
+    use shared::torrent_file_data::TargetFilesData;
+
+    fn get_sha1(buffer: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha1::new();
+        hasher.update(buffer);
+        hasher.finalize().as_slice().to_vec()
+    }
+
+    // 3 piezas de 4 bytes: [1, 1, 1, 1], [2, 2, 2, 2] y [3, 3]
+    fn create_torrent_file_data(file_name: &str) -> TorrentFileData {
+        TorrentFileData {
+            target_files_data: TargetFilesData::SingleFile {
+                file_name: file_name.to_string(),
+                file_length: 10,
+            },
+            sha1_pieces: [
+                get_sha1(&[1, 1, 1, 1]),
+                get_sha1(&[2, 2, 2, 2]),
+                get_sha1(&[3, 3]),
+            ]
+            .concat(),
+            url_tracker_main: "tracker_main.com".to_string(),
+            url_tracker_list: vec![],
+            sha1_info_hash: vec![7; 20],
+            piece_length: 4,
+            total_amount_of_pieces: 3,
+            total_length: 10,
+        }
+    }
+
+    mod test_load_torrent_status {
+        use super::*;
+
+        #[test]
+        fn torrent_without_previous_data_starts_from_scratch_ok() -> Result<(), Box<dyn Error>> {
+            let download_path = "temp/test_resume_handler_1";
+            let torrent_file_data = create_torrent_file_data("resume.test");
+
+            let torrent_status = load_torrent_status(&torrent_file_data, download_path)?;
+
+            assert_eq!(TorrentStatus::new(10, 3), torrent_status);
+            Ok(())
+        }
+
+        #[test]
+        fn torrent_without_resume_file_is_fully_verified_ok() -> Result<(), Box<dyn Error>> {
+            let download_path = "temp/test_resume_handler_2";
+            let torrent_file_data = create_torrent_file_data("resume.test");
+            let target_files =
+                block_handler::set_up_target_files(&torrent_file_data, download_path)?;
+            block_handler::store_block(&torrent_file_data, 0, 0, &[1, 1, 1, 1], &target_files)?;
+            block_handler::store_block(&torrent_file_data, 2, 0, &[3, 3], &target_files)?;
+
+            let torrent_status = load_torrent_status(&torrent_file_data, download_path)?;
+
+            assert_eq!(
+                vec![
+                    PieceStatus::ValidAndAvailablePiece,
+                    PieceStatus::MissingPiece {
+                        was_requested: false
+                    },
+                    PieceStatus::ValidAndAvailablePiece,
+                ],
+                torrent_status.pieces_availability
+            );
+            assert_eq!(6, torrent_status.downloaded);
+            assert_eq!(4, torrent_status.left);
+            assert_eq!(StateOfDownload::Started, torrent_status.event);
+
+            fs::remove_dir_all(download_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn saved_status_is_restored_with_partial_pieces_ok() -> Result<(), Box<dyn Error>> {
+            let download_path = "temp/test_resume_handler_3";
+            let block_length = usize::try_from(BLOCK_BYTES)?;
+            // 2 piezas: la primera de 2 bloques y la ultima de 100 bytes
+            let mut torrent_file_data = create_torrent_file_data("resume.test");
+            torrent_file_data.target_files_data = TargetFilesData::SingleFile {
+                file_name: "resume.test".to_string(),
+                file_length: u64::from(2 * BLOCK_BYTES) + 100,
+            };
+            torrent_file_data.sha1_pieces =
+                [get_sha1(&vec![1; 2 * block_length]), get_sha1(&[3; 100])].concat();
+            torrent_file_data.piece_length = u64::from(2 * BLOCK_BYTES);
+            torrent_file_data.total_amount_of_pieces = 2;
+            torrent_file_data.total_length = u64::from(2 * BLOCK_BYTES) + 100;
+            let target_files =
+                block_handler::set_up_target_files(&torrent_file_data, download_path)?;
+            block_handler::store_block(
+                &torrent_file_data,
+                0,
+                BLOCK_BYTES,
+                &vec![1; block_length],
+                &target_files,
+            )?;
+            block_handler::store_block(&torrent_file_data, 1, 0, &[3; 100], &target_files)?;
+
+            let mut saved_status = TorrentStatus::new(torrent_file_data.total_length, 2);
+            saved_status.pieces_availability = vec![
+                PieceStatus::PartiallyDownloaded {
+                    downloaded_blocks: vec![false, true],
+                    was_requested: true,
+                },
+                PieceStatus::ValidAndAvailablePiece,
+            ];
+            save_resume_data(&torrent_file_data, &saved_status, download_path)?;
+
+            let torrent_status = load_torrent_status(&torrent_file_data, download_path)?;
+
+            assert_eq!(
+                vec![
+                    PieceStatus::PartiallyDownloaded {
+                        downloaded_blocks: vec![false, true],
+                        was_requested: false,
+                    },
+                    PieceStatus::ValidAndAvailablePiece,
+                ],
+                torrent_status.pieces_availability
+            );
+            assert_eq!(u64::from(BLOCK_BYTES) + 100, torrent_status.downloaded);
+            assert_eq!(u64::from(BLOCK_BYTES), torrent_status.left);
+
+            fs::remove_dir_all(download_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn corrupted_piece_listed_as_completed_is_downloaded_again_ok() -> Result<(), Box<dyn Error>>
+        {
+            let download_path = "temp/test_resume_handler_4";
+            let torrent_file_data = create_torrent_file_data("resume.test");
+            let target_files =
+                block_handler::set_up_target_files(&torrent_file_data, download_path)?;
+            block_handler::store_block(&torrent_file_data, 0, 0, &[1, 1, 1, 1], &target_files)?;
+            block_handler::store_block(&torrent_file_data, 1, 0, &[9, 9, 9, 9], &target_files)?;
+            block_handler::store_block(&torrent_file_data, 2, 0, &[3, 3], &target_files)?;
+
+            let mut saved_status = TorrentStatus::new(10, 3);
+            saved_status.pieces_availability = vec![PieceStatus::ValidAndAvailablePiece; 3];
+            save_resume_data(&torrent_file_data, &saved_status, download_path)?;
+
+            let torrent_status = load_torrent_status(&torrent_file_data, download_path)?;
+
+            assert_eq!(
+                vec![
+                    PieceStatus::ValidAndAvailablePiece,
+                    PieceStatus::MissingPiece {
+                        was_requested: false
+                    },
+                    PieceStatus::ValidAndAvailablePiece,
+                ],
+                torrent_status.pieces_availability
+            );
+
+            fs::remove_dir_all(download_path)?;
+            Ok(())
+        }
+
+        #[test]
+        fn completely_downloaded_torrent_is_set_as_completed_ok() -> Result<(), Box<dyn Error>> {
+            let download_path = "temp/test_resume_handler_5";
+            let torrent_file_data = create_torrent_file_data("resume.test");
+            let target_files =
+                block_handler::set_up_target_files(&torrent_file_data, download_path)?;
+            block_handler::store_block(&torrent_file_data, 0, 0, &[1, 1, 1, 1], &target_files)?;
+            block_handler::store_block(&torrent_file_data, 1, 0, &[2, 2, 2, 2], &target_files)?;
+            block_handler::store_block(&torrent_file_data, 2, 0, &[3, 3], &target_files)?;
+
+            let torrent_status = load_torrent_status(&torrent_file_data, download_path)?;
+
+            assert!(torrent_status.is_torrent_state_set_as_completed());
+            assert_eq!(10, torrent_status.downloaded);
+            assert_eq!(0, torrent_status.left);
+
+            fs::remove_dir_all(download_path)?;
+            Ok(())
+        }
+    }
+
+    mod test_resume_file {
+        use super::*;
+
+        #[test]
+        fn resume_file_of_another_torrent_is_rejected_error() {
+            let torrent_file_data = create_torrent_file_data("resume.test");
+            let mut other_torrent_file_data = create_torrent_file_data("resume.test");
+            other_torrent_file_data.sha1_info_hash = vec![8; 20];
+
+            let resume_bytes =
+                encode_resume_data(&other_torrent_file_data, &TorrentStatus::new(10, 3));
+
+            assert!(matches!(
+                decode_resume_data(&torrent_file_data, resume_bytes),
+                Err(ResumeHandlerError::InvalidResumeFile(_))
+            ));
+        }
+
+        #[test]
+        fn encoded_resume_data_can_be_decoded_ok() -> Result<(), Box<dyn Error>> {
+            let torrent_file_data = create_torrent_file_data("resume.test");
+            let mut torrent_status = TorrentStatus::new(10, 3);
+            torrent_status.pieces_availability[1] = PieceStatus::ValidAndAvailablePiece;
+            torrent_status.pieces_availability[2] = PieceStatus::PartiallyDownloaded {
+                downloaded_blocks: vec![true, false],
+                was_requested: false,
+            };
+
+            let resume_data = decode_resume_data(
+                &torrent_file_data,
+                encode_resume_data(&torrent_file_data, &torrent_status),
+            )?;
+
+            assert_eq!(
+                ResumeData {
+                    completed_pieces: vec![false, true, false],
+                    partial_pieces: vec![(2, vec![true, false])],
+                },
+                resume_data
+            );
+            Ok(())
+        }
+    }
+}
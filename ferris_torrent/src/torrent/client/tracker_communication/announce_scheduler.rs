@@ -0,0 +1,176 @@
+//! # Modulo de planificacion de anuncios
+//! Este modulo contiene al AnnounceScheduler, encargado de decidir cuando volver a
+//! anunciarse al tracker respetando el `interval` y `min interval` de sus respuestas,
+//! y cuando realizarle un scrape para actualizar la cantidad de seeders y leechers.
+//!
+
+use std::time::{Duration, Instant};
+
+use crate::torrent::data::tracker_response_data::TrackerResponseData;
+
+/// Intervalo usado si el tracker no informa uno valido
+pub const SECS_DEFAULT_ANNOUNCE_INTERVAL: u64 = 1800;
+/// Tiempo de espera antes de reintentar un anuncio fallido
+pub const SECS_RETRY_FAILED_ANNOUNCE: u64 = 60;
+/// Tiempo entre scrapes al tracker
+pub const SECS_SCRAPE_INTERVAL: u64 = 300;
+
+/// Planificacion de los anuncios periodicos de un torrent a su tracker
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AnnounceScheduler {
+    pub interval: Duration,
+    pub next_announce: Instant,
+    pub next_scrape: Instant,
+}
+
+///
+/// Funcion que calcula cada cuanto anunciarse a partir de una respuesta del tracker. Si el
+/// `interval` informado es menor al `min interval`, prevalece este ultimo.
+///
+fn calculate_interval(tracker_response: &TrackerResponseData) -> Duration {
+    let interval = match tracker_response.interval {
+        0 => SECS_DEFAULT_ANNOUNCE_INTERVAL,
+        interval => interval,
+    };
+    let min_interval = tracker_response.min_interval.unwrap_or_default();
+    Duration::from_secs(interval.max(min_interval))
+}
+
+impl AnnounceScheduler {
+    /// Funcion que crea la planificacion a partir de la respuesta al primer anuncio
+    ///
+    pub fn new(tracker_response: &TrackerResponseData, now: Instant) -> Self {
+        let interval = calculate_interval(tracker_response);
+        AnnounceScheduler {
+            interval,
+            next_announce: now + interval,
+            next_scrape: now + Duration::from_secs(SECS_SCRAPE_INTERVAL),
+        }
+    }
+
+    /// Funcion que indica si ya corresponde realizar el proximo anuncio periodico
+    ///
+    pub fn is_announce_due(&self, now: Instant) -> bool {
+        now >= self.next_announce
+    }
+
+    /// Funcion que adelanta el proximo anuncio para realizarlo cuanto antes
+    ///
+    pub fn announce_now(&mut self, now: Instant) {
+        self.next_announce = now;
+    }
+
+    /// Funcion que registra un anuncio exitoso, actualizando el intervalo segun la nueva respuesta.
+    /// Como la respuesta ya trae la cantidad de seeders y leechers, tambien se posterga el scrape.
+    ///
+    pub fn announce_succeeded(&mut self, tracker_response: &TrackerResponseData, now: Instant) {
+        self.interval = calculate_interval(tracker_response);
+        self.next_announce = now + self.interval;
+        self.scrape_done(now);
+    }
+
+    /// Funcion que indica si ya corresponde realizar el proximo scrape
+    ///
+    pub fn is_scrape_due(&self, now: Instant) -> bool {
+        now >= self.next_scrape
+    }
+
+    /// Funcion que registra un scrape (exitoso o no), programando el siguiente
+    ///
+    pub fn scrape_done(&mut self, now: Instant) {
+        self.next_scrape = now + Duration::from_secs(SECS_SCRAPE_INTERVAL);
+    }
+
+    /// Funcion que registra un anuncio fallido, el cual se reintenta antes del intervalo habitual
+    ///
+    pub fn announce_failed(&mut self, now: Instant) {
+        let retry_after = Duration::from_secs(SECS_RETRY_FAILED_ANNOUNCE).min(self.interval);
+        self.next_announce = now + retry_after;
+    }
+}
+
+#[cfg(test)]
+mod test_announce_scheduler {
+    use super::*;
+
+    fn create_tracker_response(interval: u64, min_interval: Option<u64>) -> TrackerResponseData {
+        TrackerResponseData {
+            interval,
+            min_interval,
+            tracker_id: None,
+            complete: 0,
+            incomplete: 0,
+            peers: vec![],
+        }
+    }
+
+    #[test]
+    fn the_next_announce_is_scheduled_after_the_interval_ok() {
+        let now = Instant::now();
+        let scheduler = AnnounceScheduler::new(&create_tracker_response(120, None), now);
+
+        assert!(!scheduler.is_announce_due(now + Duration::from_secs(119)));
+        assert!(scheduler.is_announce_due(now + Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn the_min_interval_is_honoured_ok() {
+        let now = Instant::now();
+        let scheduler = AnnounceScheduler::new(&create_tracker_response(60, Some(300)), now);
+
+        assert_eq!(Duration::from_secs(300), scheduler.interval);
+    }
+
+    #[test]
+    fn an_invalid_interval_is_replaced_by_the_default_one_ok() {
+        let now = Instant::now();
+        let scheduler = AnnounceScheduler::new(&create_tracker_response(0, None), now);
+
+        assert_eq!(
+            Duration::from_secs(SECS_DEFAULT_ANNOUNCE_INTERVAL),
+            scheduler.interval
+        );
+    }
+
+    #[test]
+    fn a_failed_announce_is_retried_earlier_ok() {
+        let now = Instant::now();
+        let mut scheduler = AnnounceScheduler::new(&create_tracker_response(1800, None), now);
+
+        scheduler.announce_failed(now);
+
+        assert!(scheduler.is_announce_due(now + Duration::from_secs(SECS_RETRY_FAILED_ANNOUNCE)));
+    }
+
+    #[test]
+    fn an_announce_can_be_brought_forward_ok() {
+        let now = Instant::now();
+        let mut scheduler = AnnounceScheduler::new(&create_tracker_response(1800, None), now);
+
+        scheduler.announce_now(now);
+
+        assert!(scheduler.is_announce_due(now));
+    }
+
+    #[test]
+    fn the_interval_is_updated_after_a_successful_announce_ok() {
+        let now = Instant::now();
+        let mut scheduler = AnnounceScheduler::new(&create_tracker_response(1800, None), now);
+
+        scheduler.announce_succeeded(&create_tracker_response(600, None), now);
+
+        assert!(scheduler.is_announce_due(now + Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn a_successful_announce_postpones_the_scrape_ok() {
+        let now = Instant::now();
+        let mut scheduler = AnnounceScheduler::new(&create_tracker_response(1800, None), now);
+        let later = now + Duration::from_secs(SECS_SCRAPE_INTERVAL);
+        assert!(scheduler.is_scrape_due(later));
+
+        scheduler.announce_succeeded(&create_tracker_response(1800, None), later);
+
+        assert!(!scheduler.is_scrape_due(later));
+    }
+}
@@ -9,13 +9,20 @@ extern crate sha1;
 use native_tls::TlsConnector;
 
 use super::constants::*;
+use super::tracker_tiers::{TrackerSession, TrackerTiers};
+use super::udp_handler::{self, UdpTrackerError};
 use crate::torrent::data::{
     config_file_torrent::ConfigFileTorrent,
-    torrent_status::TorrentStatus,
-    tracker_response_data::{ResponseError, TrackerResponseData},
+    torrent_status::{StateOfDownload, TorrentStatus},
+    tracker_response_data::{ResponseError, TrackerResponseData, TrackerScrapeData},
 };
 
 use shared::{
+    http_client::{
+        client::{HttpClient, HttpClientError, ReadAndWrite, TlsConnect},
+        http_url::HttpUrl,
+        response::HttpResponse,
+    },
     parsers::{
         bencoding::{self, values::ValuesBencoding},
         urlencoding::{self},
@@ -28,17 +35,13 @@ use std::{
     collections::HashMap,
     error::Error,
     fmt,
-    io::{Read, Write},
-    net::TcpStream,
+    net::{IpAddr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    time::Duration,
 };
 
 type DicValues = HashMap<Vec<u8>, ValuesBencoding>;
 type ResultMsg<T> = Result<T, ErrorMsgHttp>;
 
-pub trait ReadAndWrite: Read + Write {}
-
-impl<T: Read + Write> ReadAndWrite for T {}
-
 ///Enumerado que representa los tipos de error que pueden surgir en comunicación con tracker
 #[derive(Debug, PartialEq, Eq)]
 pub enum ErrorMsgHttp {
@@ -55,6 +58,10 @@ pub enum ErrorMsgHttp {
     SendingGetMessage,
     ReadingResponse,
     SpecificResponseError(ResponseError),
+    UdpTracker(UdpTrackerError),
+    HttpClient(HttpClientError),
+    TrackersFailed(Vec<(String, ErrorMsgHttp)>),
+    ScrapeNotSupported,
 }
 
 impl fmt::Display for ErrorMsgHttp {
@@ -68,73 +75,81 @@ impl Error for ErrorMsgHttp {}
 struct MsgDescriptor {
     info_hash: String,
     peer_id: String,
-    ip: String,
+    ip: Option<String>,
+    ipv6: Option<String>,
+    compact: bool,
+    no_peer_id: bool,
     port: u32,
     uploaded: u64,
     downloaded: u64,
     left: u64,
-    event: String,
-    host: String,
-    get: String,
+    numwant: usize,
+    key: Option<u32>,
+    tracker_id: Option<String>,
+    event: Option<String>,
+    url: HttpUrl,
 }
 
 fn vec_u8_to_string(vec: &[u8]) -> String {
     String::from_utf8_lossy(vec).into_owned()
 }
 
-fn find_index_msg(response: &[u8], size: usize, end_line: &[u8]) -> Option<usize> {
-    response.windows(size).position(|arr| arr == end_line)
-}
-
 fn init_info_hash(vec_sha1: Vec<u8>) -> String {
     let info_hash = urlencoding::encoder::from_string_bytes(vec_sha1);
     vec_u8_to_string(&info_hash)
 }
 
-//Paso url del tracker al formato que necesito de host.
-//Ej: pasaria de http://torrent.ubuntu.com:6969/announce a torrent.ubuntu.com
-fn init_host(tracker: String) -> ResultMsg<String> {
-    let u8_tracker = tracker.as_bytes();
-    //Voy a quitar todo lo que este por detras del "//"
-    match find_index_msg(u8_tracker, THREE, HTTP_END) {
-        Some(pos) => {
-            let first = pos + THREE;
-            //Voy a quitar todo lo que este por delante del ":"
-            match find_index_msg(&u8_tracker[first..], ONE, TWO_POINTS) {
-                Some(pos) => {
-                    let last = pos + first;
-                    Ok(vec_u8_to_string(&u8_tracker[first..last]))
-                }
-                //Si no hay ":" quito todo lo que haya por delante del "/"
-                None => match find_index_msg(&u8_tracker[first..], ONE, LAST_SLASH) {
-                    Some(pos) => {
-                        let last = pos + first;
-                        Ok(vec_u8_to_string(&u8_tracker[first..last]))
-                    }
-                    None => Err(ErrorMsgHttp::NoAnnounce),
-                },
-            }
-        }
-        None => Err(ErrorMsgHttp::NoAnnounce),
+//Paso la url del tracker a sus partes. Si la url no indica un path, se usa "/announce".
+//Ej: de http://torrent.ubuntu.com:6969 obtengo el host torrent.ubuntu.com, el puerto 6969 y el path /announce
+fn init_url(tracker: &str) -> ResultMsg<HttpUrl> {
+    let mut url = HttpUrl::parse(tracker).map_err(|_| ErrorMsgHttp::NoAnnounce)?;
+    if url.path == LAST_SLASH {
+        url.path = ANNOUNCE.to_string();
     }
+    Ok(url)
 }
 
-fn init_get(tracker: String) -> ResultMsg<String> {
-    let u8_tracker = tracker.as_bytes();
-    //Voy a quitar todo lo que este por detras del "//"
-    match find_index_msg(u8_tracker, THREE, HTTP_END) {
-        Some(pos) => {
-            let first = pos + THREE;
-            //Voy a quitar todo lo que este por delante del "/"
-            match find_index_msg(&u8_tracker[first..], ONE, LAST_SLASH) {
-                Some(pos) => {
-                    let getter = pos + first;
-                    Ok(vec_u8_to_string(&u8_tracker[getter..]))
-                }
-                None => Ok(String::from(ANNOUNCE)),
-            }
+//Si el path del tracker ya tiene un querystring (ej: un passkey), los parametros se agregan al mismo.
+fn query_separator(url: &HttpUrl) -> &str {
+    match url.path.contains(QUERY_START) {
+        true => QUERY_SEPARATOR,
+        false => QUERY_START,
+    }
+}
+
+//Paso el getter del announce al del scrape, siguiendo la convencion de los trackers.
+//Ej: pasaria de /path/announce.php a /path/scrape.php
+//Si la ultima parte del getter no comienza con "announce", el tracker no soporta scrape.
+fn init_scrape_get(get: &str) -> ResultMsg<String> {
+    let (path, last_part) = get
+        .rsplit_once('/')
+        .ok_or(ErrorMsgHttp::ScrapeNotSupported)?;
+    match last_part.strip_prefix(ANNOUNCE_WORD) {
+        Some(rest) => Ok(format!("{}/{}{}", path, SCRAPE_WORD, rest)),
+        None => Err(ErrorMsgHttp::ScrapeNotSupported),
+    }
+}
+
+///
+/// Funcion que obtiene la direccion IPv6 por la cual sale el trafico de este equipo hacia el
+/// tracker, para informarsela al mismo (BEP 7). Conectar un socket UDP a la direccion IPv6 del
+/// tracker no envia ningun paquete, solo elige la interfaz de salida. Si el tracker no tiene
+/// direccion IPv6 o el equipo no tiene conectividad IPv6 se devuelve None.
+///
+fn init_ipv6(tracker_url: &HttpUrl) -> Option<Ipv6Addr> {
+    let tracker_ipv6_address = (tracker_url.host.as_str(), tracker_url.port)
+        .to_socket_addrs()
+        .ok()?
+        .find(SocketAddr::is_ipv6)?;
+    let socket = UdpSocket::bind(IPV6_UNSPECIFIED).ok()?;
+    socket.connect(tracker_ipv6_address).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V6(ipv6)
+            if !ipv6.is_loopback() && !ipv6.is_unspecified() && !ipv6.is_unicast_link_local() =>
+        {
+            Some(ipv6)
         }
-        None => Err(ErrorMsgHttp::NoAnnounce),
+        _ => None,
     }
 }
 
@@ -144,36 +159,44 @@ fn add_description_msg(msg: &mut String, type_msg: &str, value: String) {
 }
 
 impl MsgDescriptor {
-    ///Funcion que va a crear un MsgDescriptor, la cual necesita para crearse un TorrentFileData y
-    /// un peer_id, esta estructura va a servir para crear el mensaje de request al tracker
+    ///Funcion que va a crear un MsgDescriptor, la cual necesita para crearse la url del tracker,
+    /// un TorrentFileData y un peer_id, esta estructura va a servir para crear el mensaje de request al tracker.
+    /// Si no se indica ningun evento, el request corresponde a un anuncio periodico.
     ///
     pub fn new(
+        tracker_url: &str,
         torrent_status: &TorrentStatus,
         torrent_file_data: &TorrentFileData,
         peer_id: String,
         config_data: &ConfigFileTorrent,
+        event: Option<StateOfDownload>,
     ) -> ResultMsg<Self> {
         let info_hash = init_info_hash(torrent_file_data.get_info_hash());
-        let ip = String::from(IP_CLIENT);
+        let ip = config_data.get_announce_ip().map(|ip| ip.to_string());
         let port = config_data.get_port();
         let uploaded = torrent_status.get_uploaded_bytes();
         let downloaded = torrent_status.get_downloaded_bytes();
         let left = torrent_status.get_left_bytes();
-        let event = torrent_status.get_event_as_string();
-        let host = init_host(torrent_file_data.get_tracker_main())?;
-        let get = init_get(torrent_file_data.get_tracker_main())?;
+        let numwant = config_data.max_peers_per_torrent;
+        let event = event.map(|event| event.to_string());
+        let url = init_url(tracker_url)?;
 
         Ok(MsgDescriptor {
             info_hash,
             peer_id,
             ip,
+            ipv6: None,
+            compact: true,
+            no_peer_id: true,
             port,
             uploaded,
             downloaded,
             left,
+            numwant,
+            key: None,
+            tracker_id: None,
             event,
-            host,
-            get,
+            url,
         })
     }
     ///Esta funcion devuelve el info_hash [ver [TorrentFileData]] url encodeado
@@ -184,10 +207,19 @@ impl MsgDescriptor {
     pub fn get_peer_id(&self) -> String {
         self.peer_id.clone()
     }
-    ///Esta funcion devuelve la ip
-    pub fn get_ip(&self) -> String {
+    ///Esta funcion devuelve la ip configurada (None si no se informa, en cuyo caso el
+    /// tracker usa la direccion desde la cual se conecta el cliente)
+    pub fn get_ip(&self) -> Option<String> {
         self.ip.clone()
     }
+    ///Esta funcion devuelve la direccion IPv6 (None si no se informa)
+    pub fn get_ipv6(&self) -> Option<String> {
+        self.ipv6.clone()
+    }
+    ///Funcion que indica la direccion IPv6 que se le informara al tracker
+    pub fn set_ipv6(&mut self, ipv6: Option<Ipv6Addr>) {
+        self.ipv6 = ipv6.map(|ipv6| ipv6.to_string());
+    }
     ///Esta funcion devuelve el puerto
     pub fn get_port(&self) -> String {
         self.port.to_string()
@@ -202,24 +234,50 @@ impl MsgDescriptor {
     pub fn get_downloaded(&self) -> String {
         self.downloaded.to_string()
     }
-    //pub fn get_compact(&self) -> String {
-    //    self.compact.to_string()
-    //}
+    ///Esta funcion devuelve si se pide la lista de peers en formato compacto
+    /// en formato String ("1" o "0")
+    pub fn get_compact(&self) -> String {
+        (self.compact as u8).to_string()
+    }
+    ///Esta funcion devuelve si se pide que la lista de peers no incluya sus peer_id
+    /// en formato String ("1" o "0")
+    pub fn get_no_peer_id(&self) -> String {
+        (self.no_peer_id as u8).to_string()
+    }
+    ///Esta funcion devuelve la cantidad de peers que se le piden al tracker
+    pub fn get_numwant(&self) -> String {
+        self.numwant.to_string()
+    }
+    ///Esta funcion devuelve la key de la sesion en hexadecimal (None si no se informa)
+    pub fn get_key(&self) -> Option<String> {
+        self.key.map(|key| format!("{:08X}", key))
+    }
+    ///Esta funcion devuelve el tracker id url encodeado (None si el tracker no devolvio ninguno)
+    pub fn get_tracker_id(&self) -> Option<String> {
+        self.tracker_id.as_ref().map(|tracker_id| {
+            vec_u8_to_string(&urlencoding::encoder::from_string_bytes(
+                tracker_id.as_bytes().to_vec(),
+            ))
+        })
+    }
+    ///Funcion que indica los datos de la sesion con el tracker (key y tracker id)
+    /// que se le enviaran en el anuncio
+    pub fn set_session(&mut self, session: &TrackerSession) {
+        self.key = Some(session.key);
+        self.tracker_id = session.tracker_id.clone();
+    }
     ///Esta funcion devuelve la cantidad que falta descargar del archivo
     /// en bytes en formato String
     pub fn get_left(&self) -> String {
         self.left.to_string()
     }
-    ///Esta funcion devuelve el event
-    pub fn get_event(&self) -> String {
+    ///Esta funcion devuelve el event (None si se trata de un anuncio periodico)
+    pub fn get_event(&self) -> Option<String> {
         self.event.clone()
     }
     ///Esta funcion devuelve el host
     pub fn get_host(&self) -> String {
-        self.host.clone()
-    }
-    pub fn get_getter(&self) -> String {
-        self.get.clone()
+        self.url.host.clone()
     }
 
     ///Funcion que actualiza los valores de downloaded, uploaded y left
@@ -228,77 +286,102 @@ impl MsgDescriptor {
         self.uploaded += more_up;
         self.left -= more_down;
     }
-    ///Funcion que devuelve el mensaje que debera ser enviado al tracker
-    pub fn get_send_msg(&self) -> ResultMsg<String> {
-        let mut result = String::new();
-        add_description_msg(&mut result, INIT_MSG, self.get_getter());
+    ///Funcion que devuelve la url del anuncio que debera ser enviado al tracker
+    pub fn get_announce_url(&self) -> ResultMsg<String> {
+        let mut result = self.url.to_string();
+        add_description_msg(&mut result, query_separator(&self.url), String::new());
         add_description_msg(&mut result, INFO_HASH, self.get_info_hash());
         add_description_msg(&mut result, PEER_ID, self.get_peer_id());
-        add_description_msg(&mut result, IP, self.get_ip());
-        //add_description_msg(&mut result, COMPACT, self.get_compact());
+        if let Some(ip) = self.get_ip() {
+            add_description_msg(&mut result, IP, ip);
+        }
+        if let Some(ipv6) = self.get_ipv6() {
+            add_description_msg(&mut result, IPV6, ipv6);
+        }
+        add_description_msg(&mut result, COMPACT, self.get_compact());
+        add_description_msg(&mut result, NO_PEER_ID, self.get_no_peer_id());
         add_description_msg(&mut result, PORT, self.get_port());
         add_description_msg(&mut result, UPLOADED, self.get_uploaded());
         add_description_msg(&mut result, DOWNLOADED, self.get_downloaded());
         add_description_msg(&mut result, LEFT, self.get_left());
-        add_description_msg(&mut result, EVENT, self.get_event());
-        add_description_msg(&mut result, HTTP, String::new());
-        add_description_msg(&mut result, HOST, self.get_host());
-        add_description_msg(&mut result, MSG_ENDING, String::new());
+        add_description_msg(&mut result, NUMWANT, self.get_numwant());
+        if let Some(key) = self.get_key() {
+            add_description_msg(&mut result, KEY, key);
+        }
+        if let Some(tracker_id) = self.get_tracker_id() {
+            add_description_msg(&mut result, TRACKER_ID, tracker_id);
+        }
+        if let Some(event) = self.get_event() {
+            add_description_msg(&mut result, EVENT, event);
+        }
+        Ok(result)
+    }
+    ///Funcion que devuelve la url del scrape que debera ser enviado al tracker
+    pub fn get_scrape_url(&self) -> ResultMsg<String> {
+        let scrape_url = HttpUrl {
+            path: init_scrape_get(&self.url.path)?,
+            ..self.url.clone()
+        };
+        let mut result = scrape_url.to_string();
+        add_description_msg(&mut result, query_separator(&scrape_url), String::new());
+        add_description_msg(&mut result, INFO_HASH, self.get_info_hash());
         Ok(result)
     }
 }
 
 //=================================================================
 
-// Struct que representa un manejador general de comunicacion Http con un tracker especifico
-pub struct HttpHandler {
-    msg_get: MsgDescriptor,
-    port: String,
-}
-
-fn is_https(protocol: &[u8]) -> bool {
-    let https = "https".as_bytes();
-    https == protocol
+// Conector TLS (native-tls) usado por el cliente HTTP para los trackers https
+struct NativeTlsConnector {
+    connector: TlsConnector,
 }
 
-fn init_port(tracker_main: String) -> String {
-    let u8_tracker = tracker_main.as_bytes();
-    match find_index_msg(u8_tracker, THREE, HTTP_END) {
-        Some(pos) => {
-            if is_https(&u8_tracker[..pos]) {
-                return PORT_HTTPS.to_string();
-            }
-            let first = pos + THREE;
-            match find_index_msg(&u8_tracker[first..], ONE, TWO_POINTS) {
-                Some(pos_port) => match find_index_msg(&u8_tracker[first..], ONE, LAST_SLASH) {
-                    Some(pos_slash) => {
-                        vec_u8_to_string(&u8_tracker[first + pos_port..first + pos_slash])
-                    }
-                    None => vec_u8_to_string(&u8_tracker[first + pos_port..]),
-                },
-                None => PORT_HTTPS.to_string(),
-            }
+impl TlsConnect for NativeTlsConnector {
+    fn connect(&self, domain: &str, stream: TcpStream) -> Result<Box<dyn ReadAndWrite>, String> {
+        match self.connector.connect(domain, stream) {
+            Ok(tls_conected) => Ok(Box::new(tls_conected)),
+            Err(error) => Err(format!("{}", error)),
         }
-        None => PORT_HTTPS.to_string(),
     }
 }
 
+// Struct que representa un manejador general de comunicacion Http con un tracker especifico
+pub struct HttpHandler {
+    msg_get: MsgDescriptor,
+    client: HttpClient,
+}
+
 impl HttpHandler {
     ///Esta funcion creara el HttpHandler el cual es el encargado de comunicarse con el tracker,
     /// ya sea enviandole la request y recibiendo su respuesta y devolviendola en el HashMap correspondiente,
     /// esta estructura contiene una estructura MsgDescriptor que va a ser la que creara el request con el tracker,
-    /// Para crear el HttpHandler necesitamos pasarle el TorrentFileData correspondiente al .torrent y un peer_id
+    /// Para crear el HttpHandler necesitamos pasarle la url del tracker, el TorrentFileData correspondiente al .torrent y un peer_id
     fn new(
+        tracker_url: &str,
         torrent_status: &TorrentStatus,
         torrent_file_data: &TorrentFileData,
         peer_id: String,
         config_data: &ConfigFileTorrent,
+        event: Option<StateOfDownload>,
     ) -> ResultMsg<Self> {
-        let tracker_main = torrent_file_data.get_tracker_main();
-        Ok(HttpHandler {
-            msg_get: MsgDescriptor::new(torrent_status, torrent_file_data, peer_id, config_data)?,
-            port: init_port(tracker_main),
-        })
+        let msg_get = MsgDescriptor::new(
+            tracker_url,
+            torrent_status,
+            torrent_file_data,
+            peer_id,
+            config_data,
+            event,
+        )?;
+        let mut client = HttpClient::new(
+            Duration::from_secs(SECS_TRACKER_CONNECT_TIMEOUT),
+            Duration::from_secs(SECS_TRACKER_READ_TIMEOUT),
+            MAX_TRACKER_REDIRECTS,
+        );
+        if msg_get.url.is_https {
+            let connector = TlsConnector::new().map_err(|_| ErrorMsgHttp::CreateTls)?;
+            client.set_tls_connector(Box::new(NativeTlsConnector { connector }));
+        }
+        Ok(HttpHandler { msg_get, client })
     }
 
     ///Devuelve el host del mensaje al tracker
@@ -306,177 +389,115 @@ impl HttpHandler {
         self.msg_get.get_host()
     }
 
-    ///Devuelve el mensaje de request que debe ser enviado al tracker
-    pub fn get_send_msg(&self) -> ResultMsg<String> {
-        self.msg_get.get_send_msg()
+    ///Devuelve la url del anuncio que debe ser enviado al tracker
+    pub fn get_announce_url(&self) -> ResultMsg<String> {
+        self.msg_get.get_announce_url()
     }
 
-    ///Funcion que actualiza los estados de downloaded, uploaded y left del MsgDescriptor almacenado
-    pub fn update_download_stats(&mut self, more_down: u64, more_up: u64) {
-        self.msg_get.update_download_stats(more_down, more_up)
+    ///Funcion que indica la direccion IPv6 que se le informara al tracker en el anuncio
+    pub fn set_ipv6(&mut self, ipv6: Option<Ipv6Addr>) {
+        self.msg_get.set_ipv6(ipv6)
     }
 
-    fn connect_tcp(&self) -> ResultMsg<Box<dyn ReadAndWrite>> {
-        let mut addr = self.get_host();
-        addr.push_str(&self.port);
-        debug!("Conectando TCP con addr: {}", addr);
-
-        let connection = match TcpStream::connect(addr) {
-            Ok(tcp_conected) => tcp_conected,
-            Err(_) => {
-                error!("Error al comunicarse con Tcp");
-                return Err(ErrorMsgHttp::ConnectTcp);
-            }
-        };
-        Ok(Box::new(connection))
+    ///Funcion que indica los datos de la sesion con el tracker que se le enviaran en el anuncio
+    pub fn set_session(&mut self, session: &TrackerSession) {
+        self.msg_get.set_session(session)
     }
 
-    ///Funcion que sirve para conectarse con el tracker correspondiente, en caso de que alguna de las conexiones
-    /// falle se devolvera el error correspondiente
-    fn connect_tls(&self) -> ResultMsg<Box<dyn ReadAndWrite>> {
-        let connector = match TlsConnector::new() {
-            Ok(conected) => conected,
-            Err(_) => return Err(ErrorMsgHttp::CreateTls),
-        };
-
-        let mut addr = self.get_host();
-        addr.push_str(&self.port);
-        debug!("Conectando TCP con addr: {}", addr);
-
-        let stream = match TcpStream::connect(addr) {
-            Ok(tcp_conected) => tcp_conected,
-            Err(_) => {
-                error!("Error al comunicarse con Tcp");
-                return Err(ErrorMsgHttp::ConnectTcp);
-            }
-        };
-
-        let domain = self.get_host();
-        debug!("Conectando TLS con domain: {}", domain);
-        let connection = match connector.connect(&domain, stream) {
-            Ok(tls_conected) => tls_conected,
-            Err(_) => {
-                error!("Error al comunicarse con Tls");
-                return Err(ErrorMsgHttp::ConnectTls);
-            }
-        };
-        Ok(Box::new(connection))
-    }
-
-    fn check_http_code(&self, response: &[u8]) -> ResultMsg<()> {
-        let string_response = String::from_utf8_lossy(response).to_string();
-        let mut iter_string = string_response.split(' ');
-        match iter_string.next() {
-            Some("HTTP/1.1") => (),
-            _ => return Err(ErrorMsgHttp::FormatResponseError),
-        }
-        let str_code = match iter_string.next() {
-            Some(code) => code.to_owned(),
-            _ => return Err(ErrorMsgHttp::FormatResponseError),
-        };
-
-        match iter_string.next() {
-            Some(msg_error) => {
-                let mut message = str_code.clone();
-                message.push_str(": ");
-                message.push_str(msg_error);
-                for more_msg in iter_string {
-                    message.push(' ');
-                    message.push_str(more_msg);
-                }
-                match str_code.parse::<u32>() {
-                    Ok(200..=299) => Ok(()),
-                    Ok(_) => Err(ErrorMsgHttp::HttpDescription(message)),
-                    Err(_) => Err(ErrorMsgHttp::FormatResponseError),
-                }
-            }
-            _ => Err(ErrorMsgHttp::FormatResponseError),
-        }
+    ///Funcion que actualiza los estados de downloaded, uploaded y left del MsgDescriptor almacenado
+    pub fn update_download_stats(&mut self, more_down: u64, more_up: u64) {
+        self.msg_get.update_download_stats(more_down, more_up)
     }
 
-    fn tracker_response_to_dic(&self, response: Vec<u8>) -> ResultMsg<DicValues> {
-        //Reviso que la primer linea de la respuesta me de una respuesta y codigo valido.
-        match find_index_msg(&response, TWO, END_LINE) {
-            Some(pos) => self.check_http_code(&response[..pos])?,
-            None => return Err(ErrorMsgHttp::FormatResponseError),
-        }
-        //Tomo el diccionario en bencoding de la respuesta y lo paso a HashMap
-        match find_index_msg(&response, FOUR, DOUBLE_END_LINE) {
-            Some(pos) => {
-                let bencode_response = response[(pos + FOUR)..].to_vec();
-                if let Ok(dic_response) = bencoding::decoder::from_torrent_to_dic(bencode_response)
-                {
-                    Ok(dic_response)
-                } else {
-                    Err(ErrorMsgHttp::ToDicError)
-                }
-            }
-            None => Err(ErrorMsgHttp::FormatResponseError),
+    fn check_http_code(&self, response: &HttpResponse) -> ResultMsg<()> {
+        match response.is_success() {
+            true => Ok(()),
+            false => Err(ErrorMsgHttp::HttpDescription(format!(
+                "{}: {}",
+                response.status_code, response.reason
+            ))),
         }
     }
 
-    ///Funcion en la que le pedimos al HttpHandler que se conecte con el tracker, le envie el request
-    /// correspondiente y luego nos devuelva la respuesta en formato de HashMap.
+    ///Funcion en la que le pedimos al HttpHandler que haga el request a la url dada (siguiendo
+    /// las redirecciones del tracker) y luego nos devuelva la respuesta en formato de HashMap.
     ///
     /// Posibles errores que puede devolver:
     ///
     ///
     /// -En caso de que la respuesta nos de un codigo de error se devolvera el mismo junto con su descripcion
     ///
-    /// -En caso de que no pueda conectarse en TCP o TLS se devolvera el error correspondiente
+    /// -En caso de que falle la conexion, el envio del request o la recepcion de la respuesta (incluso
+    ///  por timeout) se devolvera el error del cliente HTTP correspondiente
     ///
-    /// -En caso de que haya un error en el envio del request o recepcion de la respuesta se devolvera el error
-    ///  correspondiente
-    fn tracker_get_response(&self) -> ResultMsg<DicValues> {
-        let https_port = String::from(PORT_HTTPS);
-
-        let mut connector = if self.port == https_port {
-            self.connect_tls()?
-        } else {
-            self.connect_tcp()?
-        };
-
-        let get_msg = self.get_send_msg()?;
+    /// -En caso de que la respuesta no sea un diccionario en bencoding se devolvera ToDicError
+    fn send_request(&self, url: String) -> ResultMsg<DicValues> {
         trace!("Enviando request al tracker");
-        debug!("Request: [{:?}]", get_msg);
-        if connector.write_all(get_msg.as_bytes()).is_err() {
-            error!("Error al escribir request al Tracker");
-            return Err(ErrorMsgHttp::SendingGetMessage);
-        };
+        debug!("Request: [{}]", url);
+        let response = self.client.get(&url).map_err(|error| {
+            error!("Error al comunicarse con el Tracker: {}", error);
+            ErrorMsgHttp::HttpClient(error)
+        })?;
+        debug!("Response: [{:?}]", String::from_utf8_lossy(&response.body));
+        self.check_http_code(&response)?;
+        bencoding::decoder::from_torrent_to_dic(response.body).map_err(|_| ErrorMsgHttp::ToDicError)
+    }
 
-        let mut response_tracker = vec![];
-        trace!("Recibiendo respuesta del tracker");
-        if connector.read_to_end(&mut response_tracker).is_err() {
-            error!("Error al leer la respuesta del Tracker");
-            return Err(ErrorMsgHttp::ReadingResponse);
-        }
-        debug!(
-            "Response: [{:?}]",
-            String::from_utf8_lossy(&response_tracker.clone())
-        );
-        self.tracker_response_to_dic(response_tracker)
+    ///Funcion que envia el anuncio al tracker y devuelve su respuesta en formato de HashMap
+    fn tracker_get_response(&self) -> ResultMsg<DicValues> {
+        self.send_request(self.get_announce_url()?)
+    }
+
+    ///Funcion que envia el scrape al tracker y devuelve su respuesta en formato de HashMap
+    fn tracker_get_scrape_response(&self) -> ResultMsg<DicValues> {
+        self.send_request(self.msg_get.get_scrape_url()?)
     }
 }
 
-/// Funcion que realiza toda la comunicación con el tracker, interpreta su
-/// respuesta y devuelve la info importante de la misma
+/// Funcion que realiza toda la comunicación con el tracker de la url dada, interpreta su
+/// respuesta y devuelve la info importante de la misma.
+/// Si el tracker es UDP, la comunicacion se delega al [udp_handler].
 ///
-pub fn communicate_with_tracker(
+fn communicate_with_single_tracker(
+    tracker_url: &str,
     torrent_status: &TorrentStatus,
     torrent_file_data: &TorrentFileData,
     config_data: &ConfigFileTorrent,
     peer_id: Vec<u8>,
+    event: Option<StateOfDownload>,
+    session: &TrackerSession,
 ) -> Result<TrackerResponseData, ErrorMsgHttp> {
+    if udp_handler::is_udp_tracker(tracker_url) {
+        trace!("Comunicacion con el Tracker mediante UDP");
+        return udp_handler::communicate_with_udp_tracker(
+            tracker_url,
+            torrent_status,
+            torrent_file_data,
+            config_data,
+            peer_id,
+            event,
+            session.key,
+        )
+        .map_err(ErrorMsgHttp::UdpTracker);
+    }
     let str_peer_id = String::from_utf8_lossy(&peer_id).to_string();
     trace!("Creando httpHandler dentro del Client");
-    let http_handler =
-        match HttpHandler::new(torrent_status, torrent_file_data, str_peer_id, config_data) {
-            Ok(http) => http,
-            Err(error) => {
-                error!("Error del cliente al crear HttpHandler");
-                return Err(error);
-            }
-        };
+    let mut http_handler = match HttpHandler::new(
+        tracker_url,
+        torrent_status,
+        torrent_file_data,
+        str_peer_id,
+        config_data,
+        event,
+    ) {
+        Ok(http) => http,
+        Err(error) => {
+            error!("Error del cliente al crear HttpHandler");
+            return Err(error);
+        }
+    };
+    http_handler.set_ipv6(init_ipv6(&http_handler.msg_get.url));
+    http_handler.set_session(session);
     trace!("HttpHandler creado exitosamente");
     trace!("Comunicacion con el Tracker mediante httpHandler");
     let response_tracker = match http_handler.tracker_get_response() {
@@ -498,12 +519,109 @@ pub fn communicate_with_tracker(
     }
 }
 
+/// Funcion que realiza toda la comunicación con los trackers del torrent, probando
+/// nivel por nivel hasta que alguno responda, interpreta su respuesta y devuelve la
+/// info importante de la misma.
+/// El evento indicado (started, completed o stopped) se envia en el anuncio; si no se
+/// indica ninguno, se realiza un anuncio periodico.
+/// Si el tracker que responde devuelve un tracker id, el mismo se guarda para enviarselo
+/// en los proximos anuncios.
+///
+pub fn communicate_with_tracker(
+    tracker_tiers: &mut TrackerTiers,
+    torrent_status: &TorrentStatus,
+    torrent_file_data: &TorrentFileData,
+    config_data: &ConfigFileTorrent,
+    peer_id: Vec<u8>,
+    event: Option<StateOfDownload>,
+) -> Result<TrackerResponseData, ErrorMsgHttp> {
+    let mut new_tracker_id = None;
+    let tracker_response = tracker_tiers.try_each_tracker(|tracker_url, session| {
+        let tracker_response = communicate_with_single_tracker(
+            tracker_url,
+            torrent_status,
+            torrent_file_data,
+            config_data,
+            peer_id.clone(),
+            event.clone(),
+            session,
+        )?;
+        new_tracker_id = tracker_response
+            .tracker_id
+            .clone()
+            .map(|tracker_id| (tracker_url.to_string(), tracker_id));
+        Ok(tracker_response)
+    })?;
+    if let Some((tracker_url, tracker_id)) = new_tracker_id {
+        tracker_tiers.set_tracker_id(&tracker_url, tracker_id);
+    }
+    Ok(tracker_response)
+}
+
+/// Funcion que realiza un scrape al tracker de la url dada y devuelve las estadisticas
+/// del torrent que informe el mismo.
+///
+fn scrape_single_tracker(
+    tracker_url: &str,
+    torrent_status: &TorrentStatus,
+    torrent_file_data: &TorrentFileData,
+    config_data: &ConfigFileTorrent,
+    peer_id: Vec<u8>,
+) -> Result<TrackerScrapeData, ErrorMsgHttp> {
+    if udp_handler::is_udp_tracker(tracker_url) {
+        trace!("Scrape al Tracker mediante UDP");
+        return udp_handler::scrape_udp_tracker(tracker_url, torrent_file_data)
+            .map_err(ErrorMsgHttp::UdpTracker);
+    }
+    let str_peer_id = String::from_utf8_lossy(&peer_id).to_string();
+    let http_handler = HttpHandler::new(
+        tracker_url,
+        torrent_status,
+        torrent_file_data,
+        str_peer_id,
+        config_data,
+        None,
+    )?;
+    trace!("Scrape al Tracker mediante httpHandler");
+    let response_tracker = http_handler.tracker_get_scrape_response()?;
+    TrackerScrapeData::new(response_tracker, &torrent_file_data.get_info_hash())
+        .map_err(ErrorMsgHttp::SpecificResponseError)
+}
+
+/// Funcion que obtiene de los trackers del torrent (probando nivel por nivel hasta que
+/// alguno responda) la cantidad de seeders y leechers, sin necesidad de anunciarse.
+///
+pub fn scrape_tracker(
+    tracker_tiers: &mut TrackerTiers,
+    torrent_status: &TorrentStatus,
+    torrent_file_data: &TorrentFileData,
+    config_data: &ConfigFileTorrent,
+    peer_id: Vec<u8>,
+) -> Result<TrackerScrapeData, ErrorMsgHttp> {
+    tracker_tiers.try_each_tracker(|tracker_url, _| {
+        scrape_single_tracker(
+            tracker_url,
+            torrent_status,
+            torrent_file_data,
+            config_data,
+            peer_id.clone(),
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests_http_handler {
     use shared::medatada_analyzer::read_torrent_file_to_dic;
 
     use super::*;
     use crate::torrent::data::config_file_torrent::ConfigFileTorrent;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    type FakeTracker = (String, JoinHandle<Vec<String>>);
 
     #[test]
     fn test_creation_file1_ok() -> Result<(), Box<dyn Error>> {
@@ -525,30 +643,33 @@ mod tests_http_handler {
             TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
 
         let http_handler = match HttpHandler::new(
+            &torrent.get_tracker_main(),
             &torrent_status,
             &torrent,
             "ABCDEFGHIJKLMNOPQRST".to_string(),
             &config_data,
+            Some(StateOfDownload::Started),
         ) {
             Ok(handler) => handler,
             Err(error) => return Err(Box::new(error)),
         };
         let info_hash = init_info_hash(torrent.get_info_hash());
 
-        let mut msg_get_expected = String::from("GET /announce");
+        let mut msg_get_expected = String::from("https://torrent.ubuntu.com/announce");
         msg_get_expected.push_str("?info_hash=");
         msg_get_expected.push_str(&info_hash);
-        msg_get_expected.push_str("&peer_id=ABCDEFGHIJKLMNOPQRST&ip=127.0.0.1&port=6889");
+        msg_get_expected.push_str("&peer_id=ABCDEFGHIJKLMNOPQRST");
+        msg_get_expected.push_str("&compact=1&no_peer_id=1&port=6889");
         msg_get_expected.push_str("&uploaded=0&downloaded=0&left=");
         msg_get_expected.push_str(&torrent.get_total_length().to_string());
-        msg_get_expected.push_str("&event=started HTTP/1.0\r\nHost:torrent.ubuntu.com\r\n\r\n");
+        msg_get_expected.push_str("&numwant=30&event=started");
 
-        assert_eq!(http_handler.get_send_msg(), Ok(msg_get_expected));
+        assert_eq!(http_handler.get_announce_url(), Ok(msg_get_expected));
         Ok(())
     }
 
     #[test]
-    fn test_creation_file2_ok() -> Result<(), Box<dyn Error>> {
+    fn test_creation_file2_with_udp_tracker_error() -> Result<(), Box<dyn Error>> {
         let dir = "torrents_for_test/big-buck-bunny.torrent";
 
         let dic_torrent = match read_torrent_file_to_dic(dir) {
@@ -566,32 +687,17 @@ mod tests_http_handler {
         let torrent_status =
             TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
 
-        let http_handler = match HttpHandler::new(
+        //El tracker principal de este torrent es UDP, por lo que no se comunica mediante HttpHandler
+        let http_handler = HttpHandler::new(
+            &torrent.get_tracker_main(),
             &torrent_status,
             &torrent,
             "ABCDEFGHIJKLMNOPQRST".to_string(),
             &config_data,
-        ) {
-            Ok(handler) => handler,
-            Err(error) => return Err(Box::new(error)),
-        };
-        let info_hash = init_info_hash(torrent.get_info_hash());
-
-        let mut msg_get_expected = String::from("GET /announce");
-        msg_get_expected.push_str("?info_hash=");
-        msg_get_expected.push_str(&info_hash);
-        msg_get_expected.push_str("&peer_id=ABCDEFGHIJKLMNOPQRST");
-        msg_get_expected.push_str("&ip=");
-        msg_get_expected.push_str(http_handler.msg_get.get_ip().as_str());
-        msg_get_expected.push_str("&port=");
-        msg_get_expected.push_str(http_handler.msg_get.get_port().as_str());
-        msg_get_expected.push_str("&uploaded=0&downloaded=0&left=");
-        msg_get_expected.push_str(&torrent.get_total_length().to_string());
-        msg_get_expected.push_str("&event=started HTTP/1.0\r\nHost:");
-        msg_get_expected.push_str(http_handler.msg_get.get_host().as_str());
-        msg_get_expected.push_str("\r\n\r\n");
+            Some(StateOfDownload::Started),
+        );
 
-        assert_eq!(http_handler.get_send_msg(), Ok(msg_get_expected));
+        assert!(matches!(http_handler, Err(ErrorMsgHttp::NoAnnounce)));
         Ok(())
     }
 
@@ -609,37 +715,144 @@ mod tests_http_handler {
             Err(error) => return Err(Box::new(error)),
         };
 
-        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let mut config_data = ConfigFileTorrent::new("config.txt")?;
+        config_data.announce_ip = Some("10.0.0.5".parse()?);
 
         let torrent_status =
             TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
 
         let http_handler = match HttpHandler::new(
+            &torrent.get_tracker_main(),
             &torrent_status,
             &torrent,
             "ABCDEFGHIJKLMNOPQRST".to_string(),
             &config_data,
+            Some(StateOfDownload::Started),
         ) {
             Ok(handler) => handler,
             Err(error) => return Err(Box::new(error)),
         };
         let info_hash = init_info_hash(torrent.get_info_hash());
 
-        let mut msg_get_expected = String::from("GET /announce");
+        let mut msg_get_expected = torrent.get_tracker_main();
         msg_get_expected.push_str("?info_hash=");
         msg_get_expected.push_str(&info_hash);
         msg_get_expected.push_str("&peer_id=ABCDEFGHIJKLMNOPQRST");
-        msg_get_expected.push_str("&ip=");
-        msg_get_expected.push_str(http_handler.msg_get.get_ip().as_str());
+        msg_get_expected.push_str("&ip=10.0.0.5");
+        msg_get_expected.push_str("&compact=1&no_peer_id=1");
         msg_get_expected.push_str("&port=");
         msg_get_expected.push_str(http_handler.msg_get.get_port().as_str());
         msg_get_expected.push_str("&uploaded=0&downloaded=0&left=");
         msg_get_expected.push_str(&torrent.get_total_length().to_string());
-        msg_get_expected.push_str("&event=started HTTP/1.0\r\nHost:");
-        msg_get_expected.push_str(http_handler.msg_get.get_host().as_str());
-        msg_get_expected.push_str("\r\n\r\n");
+        msg_get_expected.push_str("&numwant=");
+        msg_get_expected.push_str(http_handler.msg_get.get_numwant().as_str());
+        msg_get_expected.push_str("&event=started");
+
+        assert_eq!(http_handler.get_announce_url(), Ok(msg_get_expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_regular_announce_has_no_event_ok() -> Result<(), Box<dyn Error>> {
+        let dir = "torrents_for_test/ubuntu-22.04-desktop-amd64.iso.torrent";
+
+        let dic_torrent = read_torrent_file_to_dic(dir)?;
+        let torrent = TorrentFileData::new(dic_torrent)?;
+        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let mut torrent_status =
+            TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
+        torrent_status.uploaded = 10;
+
+        let http_handler = HttpHandler::new(
+            &torrent.get_tracker_main(),
+            &torrent_status,
+            &torrent,
+            "ABCDEFGHIJKLMNOPQRST".to_string(),
+            &config_data,
+            None,
+        )?;
 
-        assert_eq!(http_handler.get_send_msg(), Ok(msg_get_expected));
+        let msg_get = http_handler.get_announce_url()?;
+        assert!(!msg_get.contains("&event="));
+        assert!(msg_get.contains("&uploaded=10&downloaded=0&left="));
+        Ok(())
+    }
+
+    #[test]
+    fn test_announce_with_ipv6_ok() -> Result<(), Box<dyn Error>> {
+        let dir = "torrents_for_test/ubuntu-22.04-desktop-amd64.iso.torrent";
+
+        let dic_torrent = read_torrent_file_to_dic(dir)?;
+        let torrent = TorrentFileData::new(dic_torrent)?;
+        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let torrent_status =
+            TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
+
+        let mut http_handler = HttpHandler::new(
+            &torrent.get_tracker_main(),
+            &torrent_status,
+            &torrent,
+            "ABCDEFGHIJKLMNOPQRST".to_string(),
+            &config_data,
+            None,
+        )?;
+        assert!(!http_handler.get_announce_url()?.contains("&ipv6="));
+
+        http_handler.set_ipv6(Some("2001:db8::1".parse()?));
+        assert!(http_handler
+            .get_announce_url()?
+            .contains("&ipv6=2001:db8::1&"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_ipv6_is_found_for_trackers_without_ipv6_address_ok() {
+        let tracker_url = HttpUrl {
+            is_https: false,
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            path: "/announce".to_string(),
+        };
+        assert_eq!(None, init_ipv6(&tracker_url));
+
+        let loopback_tracker_url = HttpUrl {
+            host: "::1".to_string(),
+            ..tracker_url
+        };
+        assert_eq!(None, init_ipv6(&loopback_tracker_url));
+    }
+
+    #[test]
+    fn test_announce_with_session_ok() -> Result<(), Box<dyn Error>> {
+        let dir = "torrents_for_test/ubuntu-22.04-desktop-amd64.iso.torrent";
+
+        let dic_torrent = read_torrent_file_to_dic(dir)?;
+        let torrent = TorrentFileData::new(dic_torrent)?;
+        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let torrent_status =
+            TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
+
+        let mut http_handler = HttpHandler::new(
+            &torrent.get_tracker_main(),
+            &torrent_status,
+            &torrent,
+            "ABCDEFGHIJKLMNOPQRST".to_string(),
+            &config_data,
+            None,
+        )?;
+        http_handler.set_session(&TrackerSession {
+            key: 0x1A2B3C,
+            tracker_id: None,
+        });
+        let msg_get = http_handler.get_announce_url()?;
+        assert!(msg_get.ends_with("&numwant=30&key=001A2B3C"));
+
+        http_handler.set_session(&TrackerSession {
+            key: 0x1A2B3C,
+            tracker_id: Some("id 1".to_string()),
+        });
+        let msg_get = http_handler.get_announce_url()?;
+        assert!(msg_get.ends_with("&key=001A2B3C&trackerid=id%201"));
         Ok(())
     }
 
@@ -663,23 +876,153 @@ mod tests_http_handler {
             TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
 
         let http_handler = match HttpHandler::new(
+            &torrent.get_tracker_main(),
             &torrent_status,
             &torrent,
             "ABCDEFGHIJKLMNOPQRST".to_string(),
             &config_data,
+            Some(StateOfDownload::Started),
         ) {
             Ok(handler) => handler,
             Err(error) => return Err(Box::new(error)),
         };
 
-        let response = http_handler.check_http_code("HTTP/1.1 200 OK".as_bytes());
-        assert_eq!(response, Ok(()));
+        let mut response = HttpResponse {
+            status_code: 200,
+            reason: "OK".to_string(),
+            headers: vec![],
+            body: vec![],
+        };
+        assert_eq!(http_handler.check_http_code(&response), Ok(()));
 
-        let response = http_handler.check_http_code("HTTP/1.1 400 NOT FOUND".as_bytes());
+        response.status_code = 400;
+        response.reason = "NOT FOUND".to_string();
+        let response = http_handler.check_http_code(&response);
         assert_eq!(
             response,
             Err(ErrorMsgHttp::HttpDescription("400: NOT FOUND".to_owned()))
         );
         Ok(())
     }
+
+    #[test]
+    fn test_scrape_getter_ok() {
+        assert_eq!(Ok("/scrape".to_string()), init_scrape_get("/announce"));
+        assert_eq!(
+            Ok("/x/scrape.php".to_string()),
+            init_scrape_get("/x/announce.php")
+        );
+        assert_eq!(
+            Err(ErrorMsgHttp::ScrapeNotSupported),
+            init_scrape_get("/x/a_announce")
+        );
+    }
+
+    #[test]
+    fn test_scrape_msg_ok() -> Result<(), Box<dyn Error>> {
+        let dir = "torrents_for_test/ubuntu-22.04-desktop-amd64.iso.torrent";
+
+        let torrent = TorrentFileData::new(read_torrent_file_to_dic(dir)?)?;
+        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let torrent_status =
+            TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
+
+        let http_handler = HttpHandler::new(
+            &torrent.get_tracker_main(),
+            &torrent_status,
+            &torrent,
+            "ABCDEFGHIJKLMNOPQRST".to_string(),
+            &config_data,
+            None,
+        )?;
+
+        let mut msg_scrape_expected = String::from("https://torrent.ubuntu.com/scrape?info_hash=");
+        msg_scrape_expected.push_str(&init_info_hash(torrent.get_info_hash()));
+
+        assert_eq!(
+            Ok(msg_scrape_expected),
+            http_handler.msg_get.get_scrape_url()
+        );
+        Ok(())
+    }
+
+    ///
+    /// Tracker HTTP de prueba: redirige el primer request y responde el segundo con un
+    /// diccionario en bencoding enviado en chunks.
+    ///
+    fn spawn_fake_http_tracker() -> Result<FakeTracker, Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let tracker_url = format!("http://{}/announce", listener.local_addr()?);
+
+        let mut body = b"d8:intervali1800e5:peers6:".to_vec();
+        body.extend([127, 0, 0, 1, 0x1a, 0xe1]);
+        body.extend(b"10:tracker id6:abc123e");
+        let mut chunked_response =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        chunked_response.extend(format!("{:x}\r\n", body.len()).as_bytes());
+        chunked_response.extend(body);
+        chunked_response.extend(b"\r\n0\r\n\r\n");
+        let responses = vec![
+            b"HTTP/1.1 307 Temporary Redirect\r\nLocation: /tracker/announce\r\nContent-Length: 0\r\n\r\n".to_vec(),
+            chunked_response,
+        ];
+
+        let handler = thread::spawn(move || {
+            let mut request_lines = vec![];
+            for response in responses {
+                let mut stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(_) => break,
+                };
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(size) => request.extend_from_slice(&buffer[..size]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).to_string();
+                request_lines.push(request.lines().next().unwrap_or_default().to_string());
+                let _ = stream.write_all(&response);
+            }
+            request_lines
+        });
+        Ok((tracker_url, handler))
+    }
+
+    #[test]
+    fn test_announce_to_fake_tracker_ok() -> Result<(), Box<dyn Error>> {
+        let dir = "torrents_for_test/ubuntu-22.04-desktop-amd64.iso.torrent";
+
+        let torrent = TorrentFileData::new(read_torrent_file_to_dic(dir)?)?;
+        let config_data = ConfigFileTorrent::new("config.txt")?;
+        let torrent_status =
+            TorrentStatus::new(torrent.get_total_length(), torrent.total_amount_of_pieces);
+        let (tracker_url, handler) = spawn_fake_http_tracker()?;
+
+        let response = communicate_with_single_tracker(
+            &tracker_url,
+            &torrent_status,
+            &torrent,
+            &config_data,
+            "ABCDEFGHIJKLMNOPQRST".as_bytes().to_vec(),
+            Some(StateOfDownload::Started),
+            &TrackerSession {
+                key: 1,
+                tracker_id: None,
+            },
+        )?;
+
+        assert_eq!(1800, response.interval);
+        assert_eq!(Some("abc123".to_string()), response.tracker_id);
+        assert_eq!(
+            Some("127.0.0.1:6881".parse()?),
+            response.get_peer_address(0)
+        );
+        let request_lines = handler.join().map_err(|_| "The fake tracker panicked")?;
+        assert!(request_lines[0].starts_with("GET /announce?info_hash="));
+        assert!(request_lines[1].starts_with("GET /tracker/announce HTTP/1.1"));
+        Ok(())
+    }
 }
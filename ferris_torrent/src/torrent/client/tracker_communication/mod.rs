@@ -1,8 +1,11 @@
 //! # Modulo de comunicacion con Tracker
 //! Modulo usado para estructuras, constantes y funciones relacionadas a
-//! comunicacion general con trackers y recepcion e interpretacion de
+//! comunicacion general con trackers (HTTP y UDP) y recepcion e interpretacion de
 //! sus responses
 //!
 
+pub mod announce_scheduler;
 mod constants;
 pub mod http_handler;
+pub mod tracker_tiers;
+pub mod udp_handler;
@@ -0,0 +1,203 @@
+//! # Modulo de niveles de trackers
+//! Este modulo contiene a TrackerTiers, encargado de recorrer los trackers de un
+//! torrent nivel por nivel (announce-list, BEP 12) hasta que alguno responda, y de
+//! recordar los datos de la sesion con los mismos.
+//!
+
+use log::info;
+use rand::Rng;
+use shared::torrent_file_data::TorrentFileData;
+use std::collections::HashMap;
+
+use super::http_handler::ErrorMsgHttp;
+
+/// Datos de la sesion con un tracker que deben enviarse en cada anuncio: la key, que identifica
+/// a este cliente aunque cambie su direccion, y el tracker id que haya devuelto el tracker.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TrackerSession {
+    pub key: u32,
+    pub tracker_id: Option<String>,
+}
+
+/// Trackers de un torrent agrupados por niveles, en el orden en que deben intentarse
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TrackerTiers {
+    pub tiers: Vec<Vec<String>>,
+    key: u32,
+    tracker_ids: HashMap<String, String>,
+}
+
+impl TrackerTiers {
+    /// Funcion que crea los niveles de trackers a partir del .torrent, junto con una key
+    /// aleatoria que se mantiene durante toda la sesion.
+    ///
+    pub fn new(torrent_file_data: &TorrentFileData) -> Self {
+        TrackerTiers::from_tiers(torrent_file_data.get_tracker_tiers())
+    }
+
+    fn from_tiers(tiers: Vec<Vec<String>>) -> Self {
+        TrackerTiers {
+            tiers,
+            key: rand::thread_rng().gen(),
+            tracker_ids: HashMap::new(),
+        }
+    }
+
+    /// Devuelve los datos de la sesion con el tracker de la url dada
+    pub fn get_session(&self, tracker_url: &str) -> TrackerSession {
+        TrackerSession {
+            key: self.key,
+            tracker_id: self.tracker_ids.get(tracker_url).cloned(),
+        }
+    }
+
+    /// Funcion que guarda el tracker id devuelto por el tracker de la url dada, para
+    /// enviarselo en los proximos anuncios.
+    pub fn set_tracker_id(&mut self, tracker_url: &str, tracker_id: String) {
+        self.tracker_ids.insert(tracker_url.to_string(), tracker_id);
+    }
+
+    ///
+    /// Funcion que intenta la comunicacion dada con cada tracker, nivel por nivel, hasta que alguno
+    /// responda. El tracker que responde pasa a ser el primero de su nivel, para ser el primero en
+    /// intentarse la proxima vez.
+    /// A la comunicacion se le pasan tambien los datos de la sesion con cada tracker.
+    /// Si todos los trackers fallan, se devuelve cual fallo y por que.
+    ///
+    pub fn try_each_tracker<T, F>(&mut self, mut communicate: F) -> Result<T, ErrorMsgHttp>
+    where
+        F: FnMut(&str, &TrackerSession) -> Result<T, ErrorMsgHttp>,
+    {
+        let mut failed_trackers = vec![];
+        for tier in self.tiers.iter_mut() {
+            for tracker_index in 0..tier.len() {
+                let session = TrackerSession {
+                    key: self.key,
+                    tracker_id: self.tracker_ids.get(&tier[tracker_index]).cloned(),
+                };
+                match communicate(&tier[tracker_index], &session) {
+                    Ok(response) => {
+                        let tracker_url = tier.remove(tracker_index);
+                        tier.insert(0, tracker_url);
+                        return Ok(response);
+                    }
+                    Err(error) => {
+                        info!(
+                            "Fallo la comunicacion con el tracker {}: {}",
+                            tier[tracker_index], error
+                        );
+                        failed_trackers.push((tier[tracker_index].clone(), error));
+                    }
+                }
+            }
+        }
+        Err(ErrorMsgHttp::TrackersFailed(failed_trackers))
+    }
+}
+
+#[cfg(test)]
+mod test_tracker_tiers {
+    use super::*;
+
+    fn create_tracker_tiers() -> TrackerTiers {
+        TrackerTiers::from_tiers(vec![
+            vec!["tracker_a".to_string(), "tracker_b".to_string()],
+            vec!["tracker_c".to_string()],
+        ])
+    }
+
+    mod test_try_each_tracker {
+        use super::*;
+
+        #[test]
+        fn the_first_tracker_that_answers_is_used_ok() {
+            let mut tracker_tiers = create_tracker_tiers();
+            let mut tried_trackers = vec![];
+
+            let result = tracker_tiers.try_each_tracker(|tracker_url, _| {
+                tried_trackers.push(tracker_url.to_string());
+                Ok(tracker_url.to_string())
+            });
+
+            assert_eq!(Ok("tracker_a".to_string()), result);
+            assert_eq!(vec!["tracker_a".to_string()], tried_trackers);
+        }
+
+        #[test]
+        fn the_tracker_that_answers_is_promoted_within_its_tier_ok() {
+            let mut tracker_tiers = create_tracker_tiers();
+
+            let result = tracker_tiers.try_each_tracker(|tracker_url, _| match tracker_url {
+                "tracker_a" => Err(ErrorMsgHttp::ConnectTcp),
+                _ => Ok(tracker_url.to_string()),
+            });
+
+            assert_eq!(Ok("tracker_b".to_string()), result);
+            assert_eq!(
+                vec!["tracker_b".to_string(), "tracker_a".to_string()],
+                tracker_tiers.tiers[0]
+            );
+        }
+
+        #[test]
+        fn the_next_tier_is_used_when_a_whole_tier_fails_ok() {
+            let mut tracker_tiers = create_tracker_tiers();
+
+            let result = tracker_tiers.try_each_tracker(|tracker_url, _| match tracker_url {
+                "tracker_c" => Ok(tracker_url.to_string()),
+                _ => Err(ErrorMsgHttp::ConnectTcp),
+            });
+
+            assert_eq!(Ok("tracker_c".to_string()), result);
+        }
+
+        #[test]
+        fn every_failed_tracker_is_reported_error() {
+            let mut tracker_tiers = create_tracker_tiers();
+
+            let result: Result<(), ErrorMsgHttp> =
+                tracker_tiers.try_each_tracker(|_, _| Err(ErrorMsgHttp::ConnectTcp));
+
+            assert_eq!(
+                Err(ErrorMsgHttp::TrackersFailed(vec![
+                    ("tracker_a".to_string(), ErrorMsgHttp::ConnectTcp),
+                    ("tracker_b".to_string(), ErrorMsgHttp::ConnectTcp),
+                    ("tracker_c".to_string(), ErrorMsgHttp::ConnectTcp),
+                ])),
+                result
+            );
+        }
+    }
+
+    mod test_sessions {
+        use super::*;
+
+        #[test]
+        fn the_key_is_the_same_for_every_tracker_ok() {
+            let tracker_tiers = create_tracker_tiers();
+
+            assert_eq!(
+                tracker_tiers.get_session("tracker_a").key,
+                tracker_tiers.get_session("tracker_c").key
+            );
+        }
+
+        #[test]
+        fn each_tracker_receives_its_own_tracker_id_ok() {
+            let mut tracker_tiers = create_tracker_tiers();
+            tracker_tiers.set_tracker_id("tracker_b", "id_b".to_string());
+            let mut received_tracker_ids = vec![];
+
+            let result: Result<(), ErrorMsgHttp> = tracker_tiers.try_each_tracker(|_, session| {
+                received_tracker_ids.push(session.tracker_id.clone());
+                Err(ErrorMsgHttp::ConnectTcp)
+            });
+
+            assert!(result.is_err());
+            assert_eq!(
+                vec![None, Some("id_b".to_string()), None],
+                received_tracker_ids
+            );
+        }
+    }
+}
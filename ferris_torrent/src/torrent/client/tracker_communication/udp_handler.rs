@@ -0,0 +1,659 @@
+//! # Modulo de manejo de comunicacion con trackers UDP
+//! Este modulo contiene las funciones encargadas de comunicarse con un tracker
+//! a traves del protocolo UDP (BEP 15): obtencion del connection_id, anuncio y
+//! scrape, con sus correspondientes reintentos y timeouts.
+//!
+
+use log::{debug, error, trace};
+use rand::Rng;
+use std::{
+    error::Error,
+    fmt,
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::torrent::data::{
+    config_file_torrent::ConfigFileTorrent,
+    torrent_status::{StateOfDownload, TorrentStatus},
+    tracker_response_data::{PeerDataFromTrackerResponse, TrackerResponseData, TrackerScrapeData},
+};
+use shared::torrent_file_data::TorrentFileData;
+
+type ResultUdp<T> = Result<T, UdpTrackerError>;
+
+pub const UDP_SCHEME: &str = "udp://";
+const LOCAL_ADDRESS: &str = "0.0.0.0:0";
+const LOCAL_ADDRESS_V6: &str = "[::]:0";
+
+const PROTOCOL_ID: u64 = 0x41727101980;
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_SCRAPE: u32 = 2;
+const ACTION_ERROR: u32 = 3;
+
+const HEADER_LEN: usize = 8;
+const CONNECT_RESPONSE_LEN: usize = 16;
+const ANNOUNCE_RESPONSE_LEN: usize = 20;
+const SCRAPE_ENTRY_LEN: usize = 12;
+const COMPACT_PEER_LEN: usize = 6;
+const COMPACT_PEER6_LEN: usize = 18;
+const MAX_DATAGRAM_LEN: usize = 65507;
+
+/// Tiempo durante el cual un connection_id es valido
+const SECS_CONNECTION_ID_LIFETIME: u64 = 60;
+/// Tiempo de espera de la primera respuesta, que se duplica en cada reintento
+pub const SECS_BASE_TIMEOUT: u64 = 15;
+/// Cantidad de reintentos antes de dar por fallida la comunicacion
+pub const MAX_RETRIES: u32 = 2;
+
+///Enumerado que representa los tipos de error que pueden surgir en comunicación con un tracker UDP
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum UdpTrackerError {
+    InvalidUrl(String),
+    Connecting(String),
+    Sending(String),
+    Receiving(String),
+    Timeout,
+    InvalidResponse(String),
+    TrackerFailure(String),
+}
+
+impl fmt::Display for UdpTrackerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for UdpTrackerError {}
+
+fn event_to_code(event: &Option<StateOfDownload>) -> u32 {
+    match event {
+        None => 0,
+        Some(StateOfDownload::Completed) => 1,
+        Some(StateOfDownload::Started) => 2,
+        Some(StateOfDownload::Stopped) => 3,
+    }
+}
+
+fn read_u32(bytes: &[u8], start: usize) -> u32 {
+    let mut value = [0; 4];
+    value.copy_from_slice(&bytes[start..start + 4]);
+    u32::from_be_bytes(value)
+}
+
+fn read_u64(bytes: &[u8], start: usize) -> u64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[start..start + 8]);
+    u64::from_be_bytes(value)
+}
+
+///
+/// Funcion que indica si la url dada corresponde a un tracker UDP
+///
+pub fn is_udp_tracker(tracker_url: &str) -> bool {
+    tracker_url.starts_with(UDP_SCHEME)
+}
+
+//Paso url del tracker al formato host:puerto.
+//Ej: pasaria de udp://tracker.opentrackr.org:1337/announce a tracker.opentrackr.org:1337
+fn init_address(tracker_url: &str) -> ResultUdp<String> {
+    let address = tracker_url
+        .strip_prefix(UDP_SCHEME)
+        .and_then(|without_scheme| without_scheme.split('/').next())
+        .unwrap_or_default();
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            Ok(address.to_string())
+        }
+        _ => Err(UdpTrackerError::InvalidUrl(tracker_url.to_string())),
+    }
+}
+
+/// Datos de un anuncio a un tracker UDP
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UdpAnnounce {
+    pub info_hash: Vec<u8>,
+    pub peer_id: Vec<u8>,
+    pub downloaded: u64,
+    pub left: u64,
+    pub uploaded: u64,
+    pub event: Option<StateOfDownload>,
+    /// Direccion IPv4 a informar al tracker (None para que use la direccion del datagrama)
+    pub ip: Option<Ipv4Addr>,
+    pub key: u32,
+    pub num_want: i32,
+    pub port: u16,
+}
+
+/// Struct que representa un manejador de comunicacion con un tracker UDP especifico
+#[derive(Debug)]
+pub struct UdpTrackerClient {
+    socket: UdpSocket,
+    base_timeout: Duration,
+    max_retries: u32,
+    connection_id: Option<(u64, Instant)>,
+    compact_peer_len: usize,
+}
+
+impl UdpTrackerClient {
+    ///
+    /// Funcion que crea el cliente UDP para el tracker de la url dada. La primera respuesta se
+    /// espera durante `base_timeout`, y cada uno de los `max_retries` reintentos duplica la espera.
+    /// Si el tracker se encuentra en una direccion IPv6, los peers se reciben en su formato
+    /// compacto de 18 bytes.
+    ///
+    pub fn new(tracker_url: &str, base_timeout: Duration, max_retries: u32) -> ResultUdp<Self> {
+        let address = init_address(tracker_url)?;
+        let address = address
+            .to_socket_addrs()
+            .map_err(|err| UdpTrackerError::Connecting(format!("{}", err)))?
+            .next()
+            .ok_or_else(|| UdpTrackerError::Connecting(format!("Unresolved host {}", address)))?;
+        let (local_address, compact_peer_len) = match address.is_ipv4() {
+            true => (LOCAL_ADDRESS, COMPACT_PEER_LEN),
+            false => (LOCAL_ADDRESS_V6, COMPACT_PEER6_LEN),
+        };
+        let socket = UdpSocket::bind(local_address)
+            .map_err(|err| UdpTrackerError::Connecting(format!("{}", err)))?;
+        debug!("Conectando UDP con addr: {}", address);
+        socket
+            .connect(address)
+            .map_err(|err| UdpTrackerError::Connecting(format!("{}", err)))?;
+        Ok(UdpTrackerClient {
+            socket,
+            base_timeout,
+            max_retries,
+            connection_id: None,
+            compact_peer_len,
+        })
+    }
+
+    ///
+    /// Funcion que espera la respuesta con el transaction_id dado, ignorando las que correspondan
+    /// a otros pedidos. Si el tracker responde con un error, se devuelve su mensaje.
+    ///
+    fn receive_response(&self, transaction_id: u32, expected_action: u32) -> ResultUdp<Vec<u8>> {
+        let mut buffer = vec![0; MAX_DATAGRAM_LEN];
+        loop {
+            let size = match self.socket.recv(&mut buffer) {
+                Ok(size) => size,
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return Err(UdpTrackerError::Timeout)
+                }
+                Err(err) => return Err(UdpTrackerError::Receiving(format!("{}", err))),
+            };
+            if size < HEADER_LEN || read_u32(&buffer, 4) != transaction_id {
+                trace!("Se descarta una respuesta UDP que no corresponde al pedido");
+                continue;
+            }
+            let action = read_u32(&buffer, 0);
+            if action == ACTION_ERROR {
+                let message = String::from_utf8_lossy(&buffer[HEADER_LEN..size]).to_string();
+                return Err(UdpTrackerError::TrackerFailure(message));
+            }
+            if action != expected_action {
+                return Err(UdpTrackerError::InvalidResponse(format!(
+                    "Unexpected action {}",
+                    action
+                )));
+            }
+            return Ok(buffer[..size].to_vec());
+        }
+    }
+
+    ///
+    /// Funcion que envia el pedido dado y espera su respuesta, reintentando el envio cada vez que
+    /// se agota el tiempo de espera (el cual se duplica en cada intento).
+    ///
+    fn send_and_receive(
+        &self,
+        request: &[u8],
+        transaction_id: u32,
+        expected_action: u32,
+    ) -> ResultUdp<Vec<u8>> {
+        for attempt in 0..=self.max_retries {
+            self.socket
+                .set_read_timeout(Some(self.base_timeout * 2_u32.pow(attempt)))
+                .map_err(|err| UdpTrackerError::Receiving(format!("{}", err)))?;
+            self.socket
+                .send(request)
+                .map_err(|err| UdpTrackerError::Sending(format!("{}", err)))?;
+            match self.receive_response(transaction_id, expected_action) {
+                Err(UdpTrackerError::Timeout) => {
+                    debug!("Timeout del tracker UDP en el intento {}", attempt + 1)
+                }
+                result => return result,
+            }
+        }
+        error!("El tracker UDP no respondio");
+        Err(UdpTrackerError::Timeout)
+    }
+
+    ///
+    /// Funcion que devuelve el connection_id a usar en los pedidos, reutilizando el actual
+    /// mientras siga siendo valido.
+    ///
+    fn get_connection_id(&mut self) -> ResultUdp<u64> {
+        if let Some((connection_id, obtained_at)) = self.connection_id {
+            if obtained_at.elapsed() < Duration::from_secs(SECS_CONNECTION_ID_LIFETIME) {
+                return Ok(connection_id);
+            }
+        }
+        let transaction_id = rand::thread_rng().gen();
+        let mut request = Vec::with_capacity(16);
+        request.extend_from_slice(&PROTOCOL_ID.to_be_bytes());
+        request.extend_from_slice(&ACTION_CONNECT.to_be_bytes());
+        request.extend_from_slice(&u32::to_be_bytes(transaction_id));
+
+        trace!("Obteniendo connection_id del tracker UDP");
+        let response = self.send_and_receive(&request, transaction_id, ACTION_CONNECT)?;
+        if response.len() < CONNECT_RESPONSE_LEN {
+            return Err(UdpTrackerError::InvalidResponse(
+                "Connect response too short".to_string(),
+            ));
+        }
+        let connection_id = read_u64(&response, HEADER_LEN);
+        self.connection_id = Some((connection_id, Instant::now()));
+        Ok(connection_id)
+    }
+
+    ///
+    /// Funcion que realiza un anuncio al tracker y devuelve su respuesta.
+    ///
+    pub fn announce(&mut self, announce: &UdpAnnounce) -> ResultUdp<TrackerResponseData> {
+        let connection_id = self.get_connection_id()?;
+        let transaction_id = rand::thread_rng().gen();
+        let mut request = Vec::with_capacity(98);
+        request.extend_from_slice(&connection_id.to_be_bytes());
+        request.extend_from_slice(&ACTION_ANNOUNCE.to_be_bytes());
+        request.extend_from_slice(&u32::to_be_bytes(transaction_id));
+        request.extend_from_slice(&announce.info_hash);
+        request.extend_from_slice(&announce.peer_id);
+        request.extend_from_slice(&announce.downloaded.to_be_bytes());
+        request.extend_from_slice(&announce.left.to_be_bytes());
+        request.extend_from_slice(&announce.uploaded.to_be_bytes());
+        request.extend_from_slice(&event_to_code(&announce.event).to_be_bytes());
+        request.extend_from_slice(&announce.ip.unwrap_or(Ipv4Addr::UNSPECIFIED).octets());
+        request.extend_from_slice(&announce.key.to_be_bytes());
+        request.extend_from_slice(&announce.num_want.to_be_bytes());
+        request.extend_from_slice(&announce.port.to_be_bytes());
+
+        trace!("Enviando anuncio al tracker UDP");
+        let response = self.send_and_receive(&request, transaction_id, ACTION_ANNOUNCE)?;
+        if response.len() < ANNOUNCE_RESPONSE_LEN {
+            return Err(UdpTrackerError::InvalidResponse(
+                "Announce response too short".to_string(),
+            ));
+        }
+        let peers = response[ANNOUNCE_RESPONSE_LEN..]
+            .chunks_exact(self.compact_peer_len)
+            .filter_map(|compact_peer| {
+                PeerDataFromTrackerResponse::new_from_compact(compact_peer.to_vec()).ok()
+            })
+            .collect();
+        Ok(TrackerResponseData {
+            interval: read_u32(&response, 8) as u64,
+            min_interval: None,
+            tracker_id: None,
+            complete: read_u32(&response, 16) as u64,
+            incomplete: read_u32(&response, 12) as u64,
+            peers,
+        })
+    }
+
+    ///
+    /// Funcion que realiza un scrape al tracker para los info_hash dados, devolviendo las
+    /// estadisticas de cada uno en el mismo orden.
+    ///
+    pub fn scrape(&mut self, info_hashes: &[Vec<u8>]) -> ResultUdp<Vec<TrackerScrapeData>> {
+        let connection_id = self.get_connection_id()?;
+        let transaction_id = rand::thread_rng().gen();
+        let mut request = Vec::with_capacity(16 + 20 * info_hashes.len());
+        request.extend_from_slice(&connection_id.to_be_bytes());
+        request.extend_from_slice(&ACTION_SCRAPE.to_be_bytes());
+        request.extend_from_slice(&u32::to_be_bytes(transaction_id));
+        for info_hash in info_hashes {
+            request.extend_from_slice(info_hash);
+        }
+
+        trace!("Enviando scrape al tracker UDP");
+        let response = self.send_and_receive(&request, transaction_id, ACTION_SCRAPE)?;
+        if response.len() < HEADER_LEN + SCRAPE_ENTRY_LEN * info_hashes.len() {
+            return Err(UdpTrackerError::InvalidResponse(
+                "Scrape response too short".to_string(),
+            ));
+        }
+        Ok(response[HEADER_LEN..]
+            .chunks_exact(SCRAPE_ENTRY_LEN)
+            .take(info_hashes.len())
+            .map(|entry| TrackerScrapeData {
+                complete: read_u32(entry, 0) as u64,
+                downloaded: read_u32(entry, 4) as u64,
+                incomplete: read_u32(entry, 8) as u64,
+            })
+            .collect())
+    }
+}
+
+/// Funcion que realiza toda la comunicación con el tracker UDP de la url dada y devuelve la info
+/// importante de su respuesta, del mismo modo que se hace con los trackers HTTP.
+/// La key dada es la de la sesion, por lo que se envia la misma en todos los anuncios.
+///
+pub fn communicate_with_udp_tracker(
+    tracker_url: &str,
+    torrent_status: &TorrentStatus,
+    torrent_file_data: &TorrentFileData,
+    config_data: &ConfigFileTorrent,
+    peer_id: Vec<u8>,
+    event: Option<StateOfDownload>,
+    key: u32,
+) -> ResultUdp<TrackerResponseData> {
+    let mut udp_client = UdpTrackerClient::new(
+        tracker_url,
+        Duration::from_secs(SECS_BASE_TIMEOUT),
+        MAX_RETRIES,
+    )?;
+    udp_client.announce(&UdpAnnounce {
+        info_hash: torrent_file_data.get_info_hash(),
+        peer_id,
+        downloaded: torrent_status.get_downloaded_bytes(),
+        left: torrent_status.get_left_bytes(),
+        uploaded: torrent_status.get_uploaded_bytes(),
+        event,
+        ip: match config_data.get_announce_ip() {
+            Some(IpAddr::V4(ip)) => Some(ip),
+            _ => None,
+        },
+        key,
+        num_want: config_data.max_peers_per_torrent as i32,
+        port: config_data.get_port() as u16,
+    })
+}
+
+/// Funcion que realiza un scrape al tracker UDP de la url dada y devuelve las
+/// estadisticas del torrent.
+///
+pub fn scrape_udp_tracker(
+    tracker_url: &str,
+    torrent_file_data: &TorrentFileData,
+) -> ResultUdp<TrackerScrapeData> {
+    let mut udp_client = UdpTrackerClient::new(
+        tracker_url,
+        Duration::from_secs(SECS_BASE_TIMEOUT),
+        MAX_RETRIES,
+    )?;
+    udp_client
+        .scrape(&[torrent_file_data.get_info_hash()])?
+        .pop()
+        .ok_or_else(|| UdpTrackerError::InvalidResponse("Empty scrape response".to_string()))
+}
+
+#[cfg(test)]
+mod test_udp_handler {
+    use super::*;
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        thread::{self, JoinHandle},
+    };
+
+    const CONNECTION_ID: u64 = 0x1122334455667788;
+
+    type FakeResponse = Box<dyn FnMut(&[u8]) -> Vec<Vec<u8>> + Send>;
+    type FakeTracker = (String, JoinHandle<Vec<Vec<u8>>>);
+
+    ///
+    /// Tracker UDP de prueba: atiende la cantidad de pedidos dada respondiendo con los
+    /// datagramas que devuelva la funcion recibida, y devuelve los pedidos recibidos.
+    ///
+    fn spawn_fake_tracker(
+        amount_of_requests: usize,
+        mut create_responses: FakeResponse,
+    ) -> Result<FakeTracker, Box<dyn Error>> {
+        let socket = UdpSocket::bind("127.0.0.1:0")?;
+        let tracker_url = format!("udp://{}/announce", socket.local_addr()?);
+        let handler = thread::spawn(move || {
+            let mut requests = vec![];
+            let mut buffer = vec![0; MAX_DATAGRAM_LEN];
+            for _ in 0..amount_of_requests {
+                let (size, client_addr) = match socket.recv_from(&mut buffer) {
+                    Ok(received) => received,
+                    Err(_) => break,
+                };
+                let request = buffer[..size].to_vec();
+                for response in create_responses(&request) {
+                    let _ = socket.send_to(&response, client_addr);
+                }
+                requests.push(request);
+            }
+            requests
+        });
+        Ok((tracker_url, handler))
+    }
+
+    fn create_header(action: u32, request: &[u8]) -> Vec<u8> {
+        let mut response = action.to_be_bytes().to_vec();
+        response.extend_from_slice(&request[12..16]);
+        response
+    }
+
+    fn create_connect_response(request: &[u8]) -> Vec<u8> {
+        let mut response = create_header(ACTION_CONNECT, request);
+        response.extend_from_slice(&CONNECTION_ID.to_be_bytes());
+        response
+    }
+
+    fn create_announce_response(request: &[u8]) -> Vec<u8> {
+        let mut response = create_header(ACTION_ANNOUNCE, request);
+        for value in [1800_u32, 3, 5] {
+            response.extend_from_slice(&value.to_be_bytes());
+        }
+        response.extend_from_slice(&[127, 0, 0, 1, 0x1A, 0xE1]);
+        response.extend_from_slice(&[192, 168, 0, 2, 0x1A, 0xE2]);
+        response
+    }
+
+    fn answer_connect_and_announce() -> FakeResponse {
+        Box::new(|request| match read_u32(request, 8) {
+            ACTION_CONNECT => vec![create_connect_response(request)],
+            _ => vec![create_announce_response(request)],
+        })
+    }
+
+    fn create_announce() -> UdpAnnounce {
+        UdpAnnounce {
+            info_hash: vec![7; 20],
+            peer_id: vec![b'A'; 20],
+            downloaded: 10,
+            left: 20,
+            uploaded: 30,
+            event: Some(StateOfDownload::Started),
+            ip: Some(Ipv4Addr::new(10, 0, 0, 5)),
+            key: 0xABCD1234,
+            num_want: 30,
+            port: 6881,
+        }
+    }
+
+    mod test_init_address {
+        use super::*;
+
+        #[test]
+        fn the_address_is_taken_from_the_url_ok() {
+            assert_eq!(
+                Ok("tracker.opentrackr.org:1337".to_string()),
+                init_address("udp://tracker.opentrackr.org:1337/announce")
+            );
+        }
+
+        #[test]
+        fn an_url_without_port_is_rejected_error() {
+            assert!(init_address("udp://tracker.opentrackr.org/announce").is_err());
+            assert!(init_address("http://tracker.opentrackr.org:1337/announce").is_err());
+        }
+    }
+
+    mod test_announce {
+        use super::*;
+
+        #[test]
+        fn the_announce_returns_the_tracker_response_ok() -> Result<(), Box<dyn Error>> {
+            let (tracker_url, handler) = spawn_fake_tracker(2, answer_connect_and_announce())?;
+            let mut udp_client = UdpTrackerClient::new(&tracker_url, Duration::from_secs(1), 0)?;
+
+            let tracker_response = udp_client.announce(&create_announce())?;
+            let requests = handler.join().map_err(|_| "Join error")?;
+
+            assert_eq!(1800, tracker_response.interval);
+            assert_eq!(3, tracker_response.incomplete);
+            assert_eq!(5, tracker_response.complete);
+            assert_eq!(
+                Some(SocketAddr::new(
+                    IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                    6881
+                )),
+                tracker_response.get_peer_address(0)
+            );
+            assert_eq!(2, tracker_response.get_total_amount_peers());
+
+            let announce_request = &requests[1];
+            assert_eq!(98, announce_request.len());
+            assert_eq!(CONNECTION_ID, read_u64(announce_request, 0));
+            assert_eq!(vec![7; 20], announce_request[16..36].to_vec());
+            assert_eq!(30, read_u64(announce_request, 72));
+            assert_eq!(2, read_u32(announce_request, 80));
+            assert_eq!([10, 0, 0, 5], announce_request[84..88]);
+            assert_eq!(0xABCD1234_u32.to_be_bytes(), announce_request[88..92]);
+            assert_eq!(30_i32.to_be_bytes(), announce_request[92..96]);
+            assert_eq!(6881_u16.to_be_bytes(), announce_request[96..98]);
+            Ok(())
+        }
+
+        #[test]
+        fn responses_of_other_transactions_are_ignored_ok() -> Result<(), Box<dyn Error>> {
+            let (tracker_url, handler) = spawn_fake_tracker(
+                2,
+                Box::new(|request| match read_u32(request, 8) {
+                    ACTION_CONNECT => {
+                        let mut stale_response = create_connect_response(request);
+                        stale_response[4] ^= 0xFF;
+                        vec![stale_response, create_connect_response(request)]
+                    }
+                    _ => vec![create_announce_response(request)],
+                }),
+            )?;
+            let mut udp_client = UdpTrackerClient::new(&tracker_url, Duration::from_secs(1), 0)?;
+
+            let tracker_response = udp_client.announce(&create_announce())?;
+            handler.join().map_err(|_| "Join error")?;
+
+            assert_eq!(1800, tracker_response.interval);
+            Ok(())
+        }
+
+        #[test]
+        fn a_lost_request_is_retried_ok() -> Result<(), Box<dyn Error>> {
+            let mut is_first_request = true;
+            let (tracker_url, handler) = spawn_fake_tracker(
+                3,
+                Box::new(move |request| match read_u32(request, 8) {
+                    ACTION_CONNECT if is_first_request => {
+                        is_first_request = false;
+                        vec![]
+                    }
+                    ACTION_CONNECT => vec![create_connect_response(request)],
+                    _ => vec![create_announce_response(request)],
+                }),
+            )?;
+            let mut udp_client =
+                UdpTrackerClient::new(&tracker_url, Duration::from_millis(100), 1)?;
+
+            let tracker_response = udp_client.announce(&create_announce())?;
+            let requests = handler.join().map_err(|_| "Join error")?;
+
+            assert_eq!(1800, tracker_response.interval);
+            assert_eq!(3, requests.len());
+            Ok(())
+        }
+
+        #[test]
+        fn a_tracker_that_does_not_answer_times_out_error() -> Result<(), Box<dyn Error>> {
+            let (tracker_url, handler) = spawn_fake_tracker(2, Box::new(|_| vec![]))?;
+            let mut udp_client = UdpTrackerClient::new(&tracker_url, Duration::from_millis(50), 1)?;
+
+            let result = udp_client.announce(&create_announce());
+            handler.join().map_err(|_| "Join error")?;
+
+            assert_eq!(Err(UdpTrackerError::Timeout), result);
+            Ok(())
+        }
+
+        #[test]
+        fn the_tracker_error_message_is_returned_error() -> Result<(), Box<dyn Error>> {
+            let (tracker_url, handler) = spawn_fake_tracker(
+                1,
+                Box::new(|request| {
+                    let mut response = create_header(ACTION_ERROR, request);
+                    response.extend_from_slice(b"unregistered torrent");
+                    vec![response]
+                }),
+            )?;
+            let mut udp_client = UdpTrackerClient::new(&tracker_url, Duration::from_secs(1), 0)?;
+
+            let result = udp_client.announce(&create_announce());
+            handler.join().map_err(|_| "Join error")?;
+
+            assert_eq!(
+                Err(UdpTrackerError::TrackerFailure(
+                    "unregistered torrent".to_string()
+                )),
+                result
+            );
+            Ok(())
+        }
+    }
+
+    mod test_scrape {
+        use super::*;
+
+        #[test]
+        fn the_scrape_returns_the_stats_of_each_torrent_ok() -> Result<(), Box<dyn Error>> {
+            let (tracker_url, handler) = spawn_fake_tracker(
+                2,
+                Box::new(|request| match read_u32(request, 8) {
+                    ACTION_CONNECT => vec![create_connect_response(request)],
+                    _ => {
+                        let mut response = create_header(ACTION_SCRAPE, request);
+                        for value in [5_u32, 10, 3, 1, 2, 0] {
+                            response.extend_from_slice(&value.to_be_bytes());
+                        }
+                        vec![response]
+                    }
+                }),
+            )?;
+            let mut udp_client = UdpTrackerClient::new(&tracker_url, Duration::from_secs(1), 0)?;
+
+            let scrape_data = udp_client.scrape(&[vec![1; 20], vec![2; 20]])?;
+            let requests = handler.join().map_err(|_| "Join error")?;
+
+            assert_eq!(
+                vec![
+                    TrackerScrapeData {
+                        complete: 5,
+                        downloaded: 10,
+                        incomplete: 3
+                    },
+                    TrackerScrapeData {
+                        complete: 1,
+                        downloaded: 2,
+                        incomplete: 0
+                    }
+                ],
+                scrape_data
+            );
+            assert_eq!(56, requests[1].len());
+            Ok(())
+        }
+    }
+}
@@ -1,23 +1,78 @@
+use super::piece_picker::PieceSelection;
 use std::{
     collections::HashMap,
     error::Error,
     fmt,
     fs::File,
     io::{BufRead, BufReader},
+    net::IpAddr,
+    ops::RangeInclusive,
 };
 
 const PORT: &str = "port";
 const DOWNLOAD: &str = "download";
 const LOGS: &str = "logs";
+const REQUEST_QUEUE_DEPTH: &str = "request_queue_depth";
+const PIECE_SELECTION: &str = "piece_selection";
+const MAX_PEERS_PER_TORRENT: &str = "max_peers_per_torrent";
+const MAX_HALF_OPEN: &str = "max_half_open";
+const SEED_RATIO_LIMIT: &str = "seed_ratio_limit";
+const SEED_TIME_LIMIT: &str = "seed_time_limit";
+const BIND_ADDRESS: &str = "bind_address";
+const ANNOUNCE_IP: &str = "announce_ip";
+const DHT_PORT: &str = "dht_port";
+const DHT_BOOTSTRAP_NODES: &str = "dht_bootstrap_nodes";
+const PEER_IDLE_TIMEOUT: &str = "peer_idle_timeout";
+const REQUEST_TIMEOUT: &str = "request_timeout";
+const RAREST_FIRST: &str = "rarest_first";
+const SEQUENTIAL: &str = "sequential";
 const WHITESPACE: &str = " ";
+const COMMA: &str = ",";
+
+const AMOUNT_OF_REQUIRED_KEYS: usize = 3;
+pub const DEFAULT_REQUEST_QUEUE_DEPTH: usize = 10;
+pub const MIN_REQUEST_QUEUE_DEPTH: usize = 5;
+pub const MAX_REQUEST_QUEUE_DEPTH: usize = 250;
+pub const DEFAULT_MAX_PEERS_PER_TORRENT: usize = 30;
+pub const MAX_MAX_PEERS_PER_TORRENT: usize = 200;
+pub const DEFAULT_MAX_HALF_OPEN: usize = 8;
+pub const MAX_MAX_HALF_OPEN: usize = 50;
+pub const NO_SEED_RATIO_LIMIT: f64 = 0.0;
+pub const NO_SEED_TIME_LIMIT: usize = 0;
+pub const MAX_SEED_TIME_LIMIT: usize = 525600; //Un año en minutos
+pub const DHT_DISABLED: usize = 0;
+pub const MAX_PORT: usize = 65535;
+pub const DEFAULT_PEER_IDLE_TIMEOUT: usize = 180;
+pub const MIN_PEER_IDLE_TIMEOUT: usize = 30;
+pub const MAX_PEER_IDLE_TIMEOUT: usize = 3600;
+pub const DEFAULT_REQUEST_TIMEOUT: usize = 60;
+pub const MIN_REQUEST_TIMEOUT: usize = 5;
+pub const MAX_REQUEST_TIMEOUT: usize = 600;
+pub const DEFAULT_DHT_BOOTSTRAP_NODES: [&str; 3] = [
+    "router.bittorrent.com:6881",
+    "dht.transmissionbt.com:6881",
+    "router.utorrent.com:6881",
+];
 
 type ResultConfig<T> = Result<T, ConfigFiletTorrentError>;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct ConfigFileTorrent {
     pub port: u32,
     pub log_path: String,
     pub download_path: String,
+    pub request_queue_depth: usize,
+    pub piece_selection: PieceSelection,
+    pub max_peers_per_torrent: usize,
+    pub max_half_open: usize,
+    pub seed_ratio_limit: f64,
+    pub seed_time_limit: usize,
+    pub bind_address: Option<IpAddr>,
+    pub announce_ip: Option<IpAddr>,
+    pub dht_port: u32,
+    pub dht_bootstrap_nodes: Vec<String>,
+    pub peer_idle_timeout: usize,
+    pub request_timeout: usize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -29,6 +84,10 @@ pub enum ConfigFiletTorrentError {
     InvalidFormat,
     MissingPort,
     MissingPath(String),
+    NotANumber(String),
+    OutOfRange(String),
+    InvalidPieceSelection,
+    InvalidAddress(String),
 }
 
 impl fmt::Display for ConfigFiletTorrentError {
@@ -51,23 +110,105 @@ impl ConfigFileTorrent {
     /// port: número de puerto en el que se escuharan conexiones
     /// download: path del directorio descarga del torrent
     /// logs: path del del directorio del archivo de logs
+    /// Y admite las claves opcionales:
+    /// request_queue_depth: cantidad maxima de bloques pedidos a un peer sin haber recibido respuesta
+    /// (entre 5 y 250, por defecto 10)
+    /// piece_selection: estrategia de seleccion de piezas, "rarest_first" (por defecto) o "sequential"
+    /// max_peers_per_torrent: cantidad maxima de peers conectados a la vez por torrent (entre 1 y 200, por defecto 30)
+    /// max_half_open: cantidad maxima de conexiones iniciadas sin handshake completo (entre 1 y 50, por defecto 8)
+    /// seed_ratio_limit: relacion entre lo subido y el tamaño del torrent a partir de la cual se deja de compartir
+    /// (por ejemplo 1.5, por defecto 0 que indica sin limite)
+    /// seed_time_limit: minutos durante los cuales se comparte el torrent una vez completo
+    /// (entre 0 y 525600, por defecto 0 que indica sin limite)
+    /// bind_address: direccion IP local en la que se escuchan las conexiones entrantes
+    /// (por defecto todas las interfaces, tanto IPv4 como IPv6)
+    /// announce_ip: direccion IP que se le informa a los trackers en los anuncios
+    /// (por defecto no se informa, y el tracker usa la direccion desde la cual se conecta el cliente)
+    /// dht_port: puerto UDP del nodo DHT usado para encontrar peers sin trackers
+    /// (por defecto el mismo que port, 0 deshabilita la DHT)
+    /// dht_bootstrap_nodes: nodos host:puerto separados por comas a traves de los cuales se ingresa a la DHT
+    /// (por defecto los nodos publicos de BitTorrent, Transmission y uTorrent)
+    /// peer_idle_timeout: segundos sin recibir mensajes de un peer tras los cuales se corta la conexion
+    /// (entre 30 y 3600, por defecto 180)
+    /// request_timeout: segundos sin recibir un bloque pedido tras los cuales el peer se considera snubbed
+    /// y sus pedidos se liberan para otros peers (entre 5 y 600, por defecto 60)
     /// Por ejemplo:
     /// ```txt
     /// port <nro_puerto>
     /// download <path_descargas>
     /// logs <path_logs>
+    /// request_queue_depth <cantidad_de_pedidos>
+    /// piece_selection <estrategia>
+    /// max_peers_per_torrent <cantidad_de_peers>
+    /// max_half_open <cantidad_de_conexiones>
+    /// seed_ratio_limit <ratio>
+    /// seed_time_limit <minutos>
+    /// bind_address <ip_local>
+    /// announce_ip <ip_publica>
+    /// dht_port <nro_puerto>
+    /// dht_bootstrap_nodes <host:puerto>,<host:puerto>
+    /// peer_idle_timeout <segundos>
+    /// request_timeout <segundos>
     /// ```
     ///
     pub fn new(config_file_path: &str) -> Result<ConfigFileTorrent, ConfigFiletTorrentError> {
         let lines = read_config_file(config_file_path)?;
-        if lines.len() != 3 {
+        if lines.len() < AMOUNT_OF_REQUIRED_KEYS {
             return Err(ConfigFiletTorrentError::BadSize);
         }
         let config_map = get_data_from_config_file(lines)?;
+        let port = read_port(&config_map)?;
         Ok(ConfigFileTorrent {
-            port: read_port(&config_map)?,
+            port,
             log_path: read_path(&config_map, LOGS)?,
             download_path: read_path(&config_map, DOWNLOAD)?,
+            request_queue_depth: read_bounded_number(
+                &config_map,
+                REQUEST_QUEUE_DEPTH,
+                DEFAULT_REQUEST_QUEUE_DEPTH,
+                MIN_REQUEST_QUEUE_DEPTH..=MAX_REQUEST_QUEUE_DEPTH,
+            )?,
+            piece_selection: read_piece_selection(&config_map)?,
+            max_peers_per_torrent: read_bounded_number(
+                &config_map,
+                MAX_PEERS_PER_TORRENT,
+                DEFAULT_MAX_PEERS_PER_TORRENT,
+                1..=MAX_MAX_PEERS_PER_TORRENT,
+            )?,
+            max_half_open: read_bounded_number(
+                &config_map,
+                MAX_HALF_OPEN,
+                DEFAULT_MAX_HALF_OPEN,
+                1..=MAX_MAX_HALF_OPEN,
+            )?,
+            seed_ratio_limit: read_seed_ratio_limit(&config_map)?,
+            seed_time_limit: read_bounded_number(
+                &config_map,
+                SEED_TIME_LIMIT,
+                NO_SEED_TIME_LIMIT,
+                NO_SEED_TIME_LIMIT..=MAX_SEED_TIME_LIMIT,
+            )?,
+            bind_address: read_ip_address(&config_map, BIND_ADDRESS)?,
+            announce_ip: read_ip_address(&config_map, ANNOUNCE_IP)?,
+            dht_port: read_bounded_number(
+                &config_map,
+                DHT_PORT,
+                port as usize,
+                DHT_DISABLED..=MAX_PORT,
+            )? as u32,
+            dht_bootstrap_nodes: read_dht_bootstrap_nodes(&config_map),
+            peer_idle_timeout: read_bounded_number(
+                &config_map,
+                PEER_IDLE_TIMEOUT,
+                DEFAULT_PEER_IDLE_TIMEOUT,
+                MIN_PEER_IDLE_TIMEOUT..=MAX_PEER_IDLE_TIMEOUT,
+            )?,
+            request_timeout: read_bounded_number(
+                &config_map,
+                REQUEST_TIMEOUT,
+                DEFAULT_REQUEST_TIMEOUT,
+                MIN_REQUEST_TIMEOUT..=MAX_REQUEST_TIMEOUT,
+            )?,
         })
     }
 
@@ -85,6 +226,66 @@ impl ConfigFileTorrent {
     pub fn get_download_path(&self) -> String {
         self.download_path.clone()
     }
+
+    ///Request queue depth getter
+    pub fn get_request_queue_depth(&self) -> usize {
+        self.request_queue_depth
+    }
+
+    ///Max peers per torrent getter
+    pub fn get_max_peers_per_torrent(&self) -> usize {
+        self.max_peers_per_torrent
+    }
+
+    ///Max half open connections getter
+    pub fn get_max_half_open(&self) -> usize {
+        self.max_half_open
+    }
+
+    ///Seed ratio limit getter (0 indica sin limite)
+    pub fn get_seed_ratio_limit(&self) -> f64 {
+        self.seed_ratio_limit
+    }
+
+    ///Seed time limit getter, en minutos (0 indica sin limite)
+    pub fn get_seed_time_limit(&self) -> usize {
+        self.seed_time_limit
+    }
+
+    ///Piece selection getter
+    pub fn get_piece_selection(&self) -> PieceSelection {
+        self.piece_selection.clone()
+    }
+
+    ///Bind address getter (None indica todas las interfaces)
+    pub fn get_bind_address(&self) -> Option<IpAddr> {
+        self.bind_address
+    }
+
+    ///Announce ip getter (None indica que no se le informa a los trackers)
+    pub fn get_announce_ip(&self) -> Option<IpAddr> {
+        self.announce_ip
+    }
+
+    ///Dht port getter (0 indica que la DHT esta deshabilitada)
+    pub fn get_dht_port(&self) -> u32 {
+        self.dht_port
+    }
+
+    ///Dht bootstrap nodes getter
+    pub fn get_dht_bootstrap_nodes(&self) -> Vec<String> {
+        self.dht_bootstrap_nodes.clone()
+    }
+
+    ///Peer idle timeout getter, en segundos
+    pub fn get_peer_idle_timeout(&self) -> usize {
+        self.peer_idle_timeout
+    }
+
+    ///Request timeout getter, en segundos
+    pub fn get_request_timeout(&self) -> usize {
+        self.request_timeout
+    }
 }
 
 /// Se encarga de extraer directamente la info del archivo de configuración
@@ -121,6 +322,87 @@ fn read_port(config_map: &HashMap<String, String>) -> Result<u32, ConfigFiletTor
     Err(ConfigFiletTorrentError::MissingPort)
 }
 
+/// Lee una clave opcional numerica, devolviendo el valor por defecto si no esta presente
+/// y verificando que el valor leido se encuentre dentro del rango permitido.
+///
+fn read_bounded_number(
+    config_map: &HashMap<String, String>,
+    key: &str,
+    default_value: usize,
+    allowed_range: RangeInclusive<usize>,
+) -> Result<usize, ConfigFiletTorrentError> {
+    let value = match config_map.get(key) {
+        Some(value_read) => value_read
+            .parse::<usize>()
+            .map_err(|_| ConfigFiletTorrentError::NotANumber(key.to_string()))?,
+        None => return Ok(default_value),
+    };
+    if !allowed_range.contains(&value) {
+        return Err(ConfigFiletTorrentError::OutOfRange(key.to_string()));
+    }
+    Ok(value)
+}
+
+/// Lee la relacion de subida a partir de la cual se deja de compartir un torrent,
+/// la cual debe ser un numero no negativo.
+///
+fn read_seed_ratio_limit(config_map: &HashMap<String, String>) -> ResultConfig<f64> {
+    let value = match config_map.get(SEED_RATIO_LIMIT) {
+        Some(value_read) => value_read
+            .parse::<f64>()
+            .map_err(|_| ConfigFiletTorrentError::NotANumber(SEED_RATIO_LIMIT.to_string()))?,
+        None => return Ok(NO_SEED_RATIO_LIMIT),
+    };
+    if !value.is_finite() || value < NO_SEED_RATIO_LIMIT {
+        return Err(ConfigFiletTorrentError::OutOfRange(
+            SEED_RATIO_LIMIT.to_string(),
+        ));
+    }
+    Ok(value)
+}
+
+/// Lee una clave opcional con una direccion IP (v4 o v6), devolviendo None si no esta presente.
+///
+fn read_ip_address(
+    config_map: &HashMap<String, String>,
+    key: &str,
+) -> ResultConfig<Option<IpAddr>> {
+    match config_map.get(key) {
+        Some(value_read) => value_read
+            .parse::<IpAddr>()
+            .map(Some)
+            .map_err(|_| ConfigFiletTorrentError::InvalidAddress(key.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Lee la lista de nodos de bootstrap de la DHT, separados por comas, devolviendo los nodos
+/// publicos conocidos si no esta presente.
+///
+fn read_dht_bootstrap_nodes(config_map: &HashMap<String, String>) -> Vec<String> {
+    match config_map.get(DHT_BOOTSTRAP_NODES) {
+        Some(value_read) => value_read
+            .split(COMMA)
+            .map(|node| node.trim().to_string())
+            .filter(|node| !node.is_empty())
+            .collect(),
+        None => DEFAULT_DHT_BOOTSTRAP_NODES
+            .iter()
+            .map(|node| node.to_string())
+            .collect(),
+    }
+}
+
+fn read_piece_selection(
+    config_map: &HashMap<String, String>,
+) -> Result<PieceSelection, ConfigFiletTorrentError> {
+    match config_map.get(PIECE_SELECTION).map(|value| value.as_str()) {
+        None | Some(RAREST_FIRST) => Ok(PieceSelection::RarestFirst),
+        Some(SEQUENTIAL) => Ok(PieceSelection::Sequential),
+        Some(_) => Err(ConfigFiletTorrentError::InvalidPieceSelection),
+    }
+}
+
 fn read_path(
     config_map: &HashMap<String, String>,
     results_path: &str,
@@ -176,6 +458,180 @@ mod tests_config_file {
         assert_eq!(config.port, 6889);
         assert_eq!(config.download_path, "ferris_torrent/results/download");
         assert_eq!(config.log_path, "ferris_torrent/results/logs");
+        assert_eq!(config.request_queue_depth, DEFAULT_REQUEST_QUEUE_DEPTH);
+        assert_eq!(config.piece_selection, PieceSelection::RarestFirst);
+        assert_eq!(config.max_peers_per_torrent, DEFAULT_MAX_PEERS_PER_TORRENT);
+        assert_eq!(config.max_half_open, DEFAULT_MAX_HALF_OPEN);
+        assert_eq!(config.seed_ratio_limit, NO_SEED_RATIO_LIMIT);
+        assert_eq!(config.seed_time_limit, NO_SEED_TIME_LIMIT);
+        assert_eq!(config.bind_address, None);
+        assert_eq!(config.announce_ip, None);
+        assert_eq!(config.dht_port, 6889);
+        assert_eq!(
+            config.dht_bootstrap_nodes.len(),
+            DEFAULT_DHT_BOOTSTRAP_NODES.len()
+        );
+        assert_eq!(config.peer_idle_timeout, DEFAULT_PEER_IDLE_TIMEOUT);
+        assert_eq!(config.request_timeout, DEFAULT_REQUEST_TIMEOUT);
+        Ok(())
+    }
+
+    #[test]
+    fn read_request_queue_depth_ok() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["request_queue_depth 50".to_string()])?;
+        assert_eq!(
+            50,
+            read_bounded_number(
+                &config_map,
+                REQUEST_QUEUE_DEPTH,
+                DEFAULT_REQUEST_QUEUE_DEPTH,
+                MIN_REQUEST_QUEUE_DEPTH..=MAX_REQUEST_QUEUE_DEPTH
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_request_queue_depth_out_of_range_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["request_queue_depth 251".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::OutOfRange(
+                REQUEST_QUEUE_DEPTH.to_string()
+            )),
+            read_bounded_number(
+                &config_map,
+                REQUEST_QUEUE_DEPTH,
+                DEFAULT_REQUEST_QUEUE_DEPTH,
+                MIN_REQUEST_QUEUE_DEPTH..=MAX_REQUEST_QUEUE_DEPTH
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_max_half_open_that_is_not_a_number_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["max_half_open many".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::NotANumber(
+                MAX_HALF_OPEN.to_string()
+            )),
+            read_bounded_number(
+                &config_map,
+                MAX_HALF_OPEN,
+                DEFAULT_MAX_HALF_OPEN,
+                1..=MAX_MAX_HALF_OPEN
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_seed_ratio_limit_ok() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["seed_ratio_limit 1.5".to_string()])?;
+        assert_eq!(1.5, read_seed_ratio_limit(&config_map)?);
+        Ok(())
+    }
+
+    #[test]
+    fn read_negative_seed_ratio_limit_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["seed_ratio_limit -1".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::OutOfRange(
+                SEED_RATIO_LIMIT.to_string()
+            )),
+            read_seed_ratio_limit(&config_map)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_piece_selection_ok() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["piece_selection sequential".to_string()])?;
+        assert_eq!(
+            PieceSelection::Sequential,
+            read_piece_selection(&config_map)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_invalid_piece_selection_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["piece_selection random".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::InvalidPieceSelection),
+            read_piece_selection(&config_map)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_ip_addresses_ok() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec![
+            "bind_address 192.168.0.10".to_string(),
+            "announce_ip 2001:db8::1".to_string(),
+        ])?;
+        assert_eq!(
+            Some(IpAddr::from([192, 168, 0, 10])),
+            read_ip_address(&config_map, BIND_ADDRESS)?
+        );
+        assert_eq!(
+            Some(IpAddr::from([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1])),
+            read_ip_address(&config_map, ANNOUNCE_IP)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_invalid_ip_address_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["announce_ip my.host".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::InvalidAddress(
+                ANNOUNCE_IP.to_string()
+            )),
+            read_ip_address(&config_map, ANNOUNCE_IP)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_dht_bootstrap_nodes_ok() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec![
+            "dht_bootstrap_nodes 127.0.0.1:6881,dht.example.com:6881".to_string(),
+        ])?;
+        assert_eq!(
+            vec![
+                "127.0.0.1:6881".to_string(),
+                "dht.example.com:6881".to_string()
+            ],
+            read_dht_bootstrap_nodes(&config_map)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_dht_port_out_of_range_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["dht_port 70000".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::OutOfRange(DHT_PORT.to_string())),
+            read_bounded_number(&config_map, DHT_PORT, 6881, DHT_DISABLED..=MAX_PORT)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_peer_idle_timeout_out_of_range_error() -> Result<(), ConfigFiletTorrentError> {
+        let config_map = get_data_from_config_file(vec!["peer_idle_timeout 10".to_string()])?;
+        assert_eq!(
+            Err(ConfigFiletTorrentError::OutOfRange(
+                PEER_IDLE_TIMEOUT.to_string()
+            )),
+            read_bounded_number(
+                &config_map,
+                PEER_IDLE_TIMEOUT,
+                DEFAULT_PEER_IDLE_TIMEOUT,
+                MIN_PEER_IDLE_TIMEOUT..=MAX_PEER_IDLE_TIMEOUT
+            )
+        );
         Ok(())
     }
 }
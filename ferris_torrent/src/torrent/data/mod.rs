@@ -4,5 +4,6 @@
 
 pub mod config_file_torrent;
 pub mod peer_data_for_communication;
+pub mod piece_picker;
 pub mod torrent_status;
 pub mod tracker_response_data;
@@ -76,6 +76,14 @@ impl PeerDataForP2PCommunication {
         self.pieces_availability = new_pieces_availability;
     }
 
+    /// Funcion que indica si el peer posee todas las piezas del torrent
+    ///
+    pub fn has_all_pieces(&self) -> bool {
+        self.pieces_availability
+            .iter()
+            .all(|piece_status| *piece_status == PieceStatus::ValidAndAvailablePiece)
+    }
+
     pub fn get_peer_name(&self) -> Result<String, PeerDataForP2PCommunicationError> {
         let peer_id = self.peer_id.clone();
         let peer_name = peer_id[1..7].to_vec();
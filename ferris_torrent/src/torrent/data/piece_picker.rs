@@ -0,0 +1,169 @@
+//! # Modulo de seleccion de piezas
+//! Este modulo contiene las distintas estrategias para elegir cual es la
+//! proxima pieza a pedir entre las que le faltan al cliente y posee el peer externo.
+//!
+
+extern crate rand;
+
+use rand::seq::SliceRandom;
+use std::{fmt, sync::Arc};
+
+/// Cantidad de piezas que se eligen al azar antes de empezar a elegir por rareza,
+/// para poder tener rapidamente piezas completas que compartir.
+pub const DEFAULT_RANDOM_FIRST_PIECES: u64 = 4;
+
+/// Estrategia de seleccion de piezas configurable desde el archivo de configuracion
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum PieceSelection {
+    RarestFirst,
+    Sequential,
+}
+
+/// Comportamiento de una estrategia de seleccion de piezas.
+pub trait PiecePicker: fmt::Debug + Send + Sync {
+    ///
+    /// Funcion que elige una pieza entre los indices candidatos (piezas faltantes, no pedidas y
+    /// que posee el peer externo). Recibe la cantidad de peers conectados que tienen cada pieza
+    /// y la cantidad de piezas que ya fueron descargadas y verificadas.
+    ///
+    fn pick_piece(
+        &self,
+        candidates: &[usize],
+        pieces_peer_count: &[u32],
+        amount_of_downloaded_pieces: u64,
+    ) -> Option<usize>;
+}
+
+/// Elige primero las piezas que menos peers conectados poseen, desempatando al azar.
+/// Mientras se tengan menos de `random_first_pieces` piezas descargadas, elige al azar.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct RarestFirstPicker {
+    pub random_first_pieces: u64,
+}
+
+/// Elige siempre la pieza de menor indice (util para reproducir mientras se descarga).
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SequentialPicker;
+
+impl PiecePicker for RarestFirstPicker {
+    fn pick_piece(
+        &self,
+        candidates: &[usize],
+        pieces_peer_count: &[u32],
+        amount_of_downloaded_pieces: u64,
+    ) -> Option<usize> {
+        let mut rng = rand::thread_rng();
+        if amount_of_downloaded_pieces < self.random_first_pieces {
+            return candidates.choose(&mut rng).copied();
+        }
+
+        let peer_count_of = |piece_index: &usize| {
+            pieces_peer_count
+                .get(*piece_index)
+                .copied()
+                .unwrap_or_default()
+        };
+        let min_peer_count = candidates.iter().map(peer_count_of).min()?;
+        let rarest_pieces: Vec<usize> = candidates
+            .iter()
+            .filter(|piece_index| peer_count_of(piece_index) == min_peer_count)
+            .copied()
+            .collect();
+        rarest_pieces.choose(&mut rng).copied()
+    }
+}
+
+impl PiecePicker for SequentialPicker {
+    fn pick_piece(
+        &self,
+        candidates: &[usize],
+        _pieces_peer_count: &[u32],
+        _amount_of_downloaded_pieces: u64,
+    ) -> Option<usize> {
+        candidates.iter().min().copied()
+    }
+}
+
+///
+/// Funcion que crea el selector de piezas correspondiente a la estrategia dada,
+/// listo para ser compartido entre los distintos threads de comunicacion con peers.
+///
+pub fn create_piece_picker(piece_selection: &PieceSelection) -> Arc<dyn PiecePicker> {
+    match piece_selection {
+        PieceSelection::RarestFirst => Arc::new(RarestFirstPicker {
+            random_first_pieces: DEFAULT_RANDOM_FIRST_PIECES,
+        }),
+        PieceSelection::Sequential => Arc::new(SequentialPicker),
+    }
+}
+
+#[cfg(test)]
+mod test_piece_picker {
+    use super::*;
+
+    mod test_rarest_first_picker {
+        use super::*;
+
+        #[test]
+        fn the_rarest_candidate_is_picked_ok() {
+            let picker = RarestFirstPicker {
+                random_first_pieces: 0,
+            };
+            let pieces_peer_count = vec![3, 1, 2, 0];
+
+            assert_eq!(
+                Some(1),
+                picker.pick_piece(&[0, 1, 2], &pieces_peer_count, 0)
+            );
+        }
+
+        #[test]
+        fn ties_are_broken_between_the_rarest_candidates_ok() {
+            let picker = RarestFirstPicker {
+                random_first_pieces: 0,
+            };
+            let pieces_peer_count = vec![1, 3, 1, 1];
+
+            for _ in 0..20 {
+                let picked = picker.pick_piece(&[0, 1, 2], &pieces_peer_count, 0);
+                assert!(picked == Some(0) || picked == Some(2));
+            }
+        }
+
+        #[test]
+        fn while_in_random_first_pieces_mode_any_candidate_can_be_picked_ok() {
+            let picker = RarestFirstPicker {
+                random_first_pieces: 4,
+            };
+            let pieces_peer_count = vec![5, 5, 1];
+
+            for _ in 0..20 {
+                let picked = picker.pick_piece(&[0, 1], &pieces_peer_count, 3);
+                assert!(picked == Some(0) || picked == Some(1));
+            }
+        }
+
+        #[test]
+        fn there_are_no_candidates_ok() {
+            let picker = RarestFirstPicker {
+                random_first_pieces: 0,
+            };
+
+            assert_eq!(None, picker.pick_piece(&[], &[1, 2], 10));
+        }
+    }
+
+    mod test_sequential_picker {
+        use super::*;
+
+        #[test]
+        fn the_lowest_candidate_is_picked_ok() {
+            let pieces_peer_count = vec![3, 3, 1, 0];
+
+            assert_eq!(
+                Some(1),
+                SequentialPicker.pick_piece(&[3, 1, 2], &pieces_peer_count, 0)
+            );
+        }
+    }
+}
@@ -17,8 +17,8 @@ use shared::{parsers::p2p::message::PieceStatus, torrent_file_data::TorrentFileD
 /// Representa el estado de la descarga COMPLETA del torrent
 pub enum TorrentStatusError {
     UpdatingPieceStatus(String),
-    CalculatingBeginningByteIndex(String),
     CalculatingAmountOfBytes(String),
+    CalculatingAmountOfBlocks(String),
     CalculatingDownloadedPorcentage(String),
 }
 
@@ -38,6 +38,17 @@ pub enum StateOfDownload {
     Stopped,
 }
 
+impl fmt::Display for StateOfDownload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let event = match self {
+            StateOfDownload::Started => "started",
+            StateOfDownload::Completed => "completed",
+            StateOfDownload::Stopped => "stopped",
+        };
+        write!(f, "{}", event)
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 /// Representa la data actual de como va la descarga de un torrent
 pub struct TorrentStatus {
@@ -46,6 +57,8 @@ pub struct TorrentStatus {
     pub left: u64,
     pub event: StateOfDownload,
     pub pieces_availability: Vec<PieceStatus>,
+    pub pieces_peer_count: Vec<u32>,
+    pub pieces_requesters_count: Vec<u32>,
 }
 
 fn is_valid_piece_to_request(piece_status: &PieceStatus) -> bool {
@@ -79,6 +92,8 @@ impl TorrentStatus {
             left: size_torrent,
             event: StateOfDownload::Started,
             pieces_availability,
+            pieces_peer_count: vec![0; total_amount_pieces],
+            pieces_requesters_count: vec![0; total_amount_pieces],
         }
     }
 
@@ -95,11 +110,7 @@ impl TorrentStatus {
     }
 
     pub fn get_event_as_string(&self) -> String {
-        match self.event {
-            StateOfDownload::Started => String::from("started"),
-            StateOfDownload::Completed => String::from("completed"),
-            StateOfDownload::Stopped => String::from("stopped"),
-        }
+        self.event.to_string()
     }
 
     pub fn get_porcentage_downloaded(&self) -> Result<f64, TorrentStatusError> {
@@ -147,6 +158,14 @@ impl TorrentStatus {
         }
     }
 
+    /// Funcion que indica si una pieza falta descargar y todavia no fue pedida a ningun peer
+    ///
+    pub fn is_a_piece_to_request(&self, piece_index: usize) -> bool {
+        self.pieces_availability
+            .get(piece_index)
+            .is_some_and(is_valid_piece_to_request)
+    }
+
     pub fn get_piece_status(&self, piece_index: usize) -> Option<&PieceStatus> {
         self.pieces_availability.get(piece_index)
     }
@@ -164,6 +183,11 @@ impl TorrentStatus {
         self.uploaded += amount_of_bytes;
     }
 
+    ///
+    /// Funcion que actualiza el estado de una pieza al recibir uno de sus bloques.
+    /// Los bloques pueden recibirse en cualquier orden; la pieza pasa a estar completa
+    /// cuando se descargaron todos sus bloques.
+    ///
     pub fn update_piece_status(
         &mut self,
         torrent_file_data: &TorrentFileData,
@@ -171,54 +195,47 @@ impl TorrentStatus {
         beginning_byte_index: u32,
         amount_of_bytes: u32,
     ) -> Result<(), TorrentStatusError> {
-        //Se la podria modularizar
-        let piece_lenght = torrent_file_data
-            .calculate_piece_lenght(piece_index)
+        let amount_of_blocks = self.calculate_amount_of_blocks(torrent_file_data, piece_index)?;
+        let block_index = usize::try_from(beginning_byte_index / BLOCK_BYTES)
             .map_err(|err| TorrentStatusError::UpdatingPieceStatus(format!("{}", err)))?;
+        if !beginning_byte_index.is_multiple_of(BLOCK_BYTES) || block_index >= amount_of_blocks {
+            return Err(TorrentStatusError::UpdatingPieceStatus(
+                "[TorrentStatusError] The beginning byte index does not match with a block."
+                    .to_string(),
+            ));
+        }
 
         if let Some(piece_status) = self.pieces_availability.get_mut(piece_index) {
-            match piece_status {
-                PieceStatus::MissingPiece {
-                    was_requested: true,
-                } => {
-                    if piece_lenght == u64::from(amount_of_bytes) {
-                        *piece_status = PieceStatus::ValidAndAvailablePiece;
-                    } else {
-                        *piece_status = PieceStatus::PartiallyDownloaded {
-                            downloaded_bytes: amount_of_bytes,
-                            was_requested: false,
-                        };
-                    }
-                    self.increment_downloaded_counter(amount_of_bytes.into())
+            let (mut downloaded_blocks, was_requested) = match piece_status {
+                PieceStatus::MissingPiece { was_requested } => {
+                    (vec![false; amount_of_blocks], *was_requested)
                 }
                 PieceStatus::PartiallyDownloaded {
-                    downloaded_bytes,
-                    was_requested: true,
-                } => {
-                    let remaining_bytes =
-                        piece_lenght - u64::from(*downloaded_bytes + amount_of_bytes);
-                    if remaining_bytes == 0 {
-                        *piece_status = PieceStatus::ValidAndAvailablePiece;
-                    } else {
-                        *piece_status = PieceStatus::PartiallyDownloaded {
-                            downloaded_bytes: beginning_byte_index + amount_of_bytes,
-                            was_requested: false,
-                        };
-                    }
-                    self.increment_downloaded_counter(amount_of_bytes.into())
-                }
+                    downloaded_blocks,
+                    was_requested,
+                } => (downloaded_blocks.clone(), *was_requested),
                 PieceStatus::ValidAndAvailablePiece => {
                     return Err(TorrentStatusError::UpdatingPieceStatus(
                         "[TorrentStatusError] The piece has already been completed.".to_string(),
                     ))
                 }
-                _ => {
-                    return Err(TorrentStatusError::UpdatingPieceStatus(
-                        "[TorrentStatusError] The received piece was not requested before."
-                            .to_string(),
-                    ))
-                }
+            };
+            if downloaded_blocks[block_index] {
+                return Err(TorrentStatusError::UpdatingPieceStatus(
+                    "[TorrentStatusError] The block has already been downloaded.".to_string(),
+                ));
+            }
+            downloaded_blocks[block_index] = true;
+
+            if downloaded_blocks.iter().all(|is_downloaded| *is_downloaded) {
+                *piece_status = PieceStatus::ValidAndAvailablePiece;
+            } else {
+                *piece_status = PieceStatus::PartiallyDownloaded {
+                    downloaded_blocks,
+                    was_requested,
+                };
             }
+            self.increment_downloaded_counter(amount_of_bytes.into())
         };
         debug!(
             "Nuevo estado de la pieza {}: {:?}",
@@ -237,42 +254,144 @@ impl TorrentStatus {
     }
 
     /// Funcion que busca una nueva pieza que quiera pedir posteriormente, y
-    /// devuelve su indice
+    /// devuelve su indice. La eleccion entre las piezas posibles queda a cargo
+    /// del selector de piezas del LocalPeerCommunicator.
     ///
     pub fn look_for_a_missing_piece_index(
         &self,
         local_peer: &LocalPeerCommunicator,
     ) -> Option<usize> {
-        let (piece_index, _piece_status) =
-            self.pieces_availability
+        let candidates: Vec<usize> = self
+            .pieces_availability
+            .iter()
+            .enumerate()
+            .filter(|(piece_index, piece_status)| {
+                is_valid_piece_to_request(piece_status)
+                    && local_peer
+                        .external_peer_has_a_valid_and_available_piece_on_position(*piece_index)
+            })
+            .map(|(piece_index, _piece_status)| piece_index)
+            .collect();
+        local_peer.piece_picker.pick_piece(
+            &candidates,
+            &self.pieces_peer_count,
+            self.get_amount_of_downloaded_pieces(),
+        )
+    }
+
+    /// Funcion que indica si la descarga esta en modo endgame: todavia faltan piezas
+    /// por completar, pero todas ellas ya fueron pedidas a algun peer.
+    ///
+    pub fn is_in_endgame(&self) -> bool {
+        !self.all_pieces_completed()
+            && !self
+                .pieces_availability
                 .iter()
-                .enumerate()
-                .find(|(piece_index, piece_status)| {
-                    is_valid_piece_to_request(piece_status)
-                        && local_peer
-                            .external_peer_has_a_valid_and_available_piece_on_position(*piece_index)
-                })?;
-        Some(piece_index)
+                .any(is_valid_piece_to_request)
+    }
+
+    /// Funcion que devuelve los indices de las piezas que faltan completar y que ya
+    /// fueron pedidas a algun peer. En modo endgame se le piden a todos los peers que las tengan.
+    ///
+    pub fn get_requested_pieces_left(&self) -> Vec<usize> {
+        self.pieces_availability
+            .iter()
+            .enumerate()
+            .filter(|(_piece_index, piece_status)| {
+                matches!(
+                    piece_status,
+                    PieceStatus::MissingPiece {
+                        was_requested: true,
+                    } | PieceStatus::PartiallyDownloaded {
+                        was_requested: true,
+                        ..
+                    }
+                )
+            })
+            .map(|(piece_index, _piece_status)| piece_index)
+            .collect()
     }
 
-    /// Funcion que calcula el byte inicial desde el cual
-    /// se deberia pedir el siguiente bloque de una pieza
+    /// Funcion que suma a la cantidad de peers que tienen cada pieza
+    /// las piezas validas del bitfield de un peer externo.
     ///
-    pub fn calculate_beginning_byte_index(
+    pub fn add_peer_pieces(&mut self, bitfield: &[PieceStatus]) {
+        for (peer_count, piece_status) in self.pieces_peer_count.iter_mut().zip(bitfield) {
+            if *piece_status == PieceStatus::ValidAndAvailablePiece {
+                *peer_count += 1;
+            }
+        }
+    }
+
+    /// Funcion que resta a la cantidad de peers que tienen cada pieza
+    /// las piezas validas del bitfield de un peer externo (por ejemplo, al desconectarse).
+    ///
+    pub fn remove_peer_pieces(&mut self, bitfield: &[PieceStatus]) {
+        for (peer_count, piece_status) in self.pieces_peer_count.iter_mut().zip(bitfield) {
+            if *piece_status == PieceStatus::ValidAndAvailablePiece {
+                *peer_count = peer_count.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Funcion que suma un peer a la cantidad de peers que tienen una pieza
+    /// (al recibir un mensaje Have).
+    ///
+    pub fn add_peer_piece(&mut self, piece_index: usize) {
+        if let Some(peer_count) = self.pieces_peer_count.get_mut(piece_index) {
+            *peer_count += 1;
+        }
+    }
+
+    /// Funcion que calcula en cuantos bloques se divide una pieza
+    ///
+    pub fn calculate_amount_of_blocks(
         &self,
+        torrent_file_data: &TorrentFileData,
         piece_index: usize,
-    ) -> Result<u32, TorrentStatusError> {
-        match self.pieces_availability.get(piece_index)
-        {
-            Some(PieceStatus::PartiallyDownloaded { downloaded_bytes , was_requested: _ }) => Ok(*downloaded_bytes),
-            Some(PieceStatus::MissingPiece{ was_requested: false}) => Ok(0),
-            _ => Err(TorrentStatusError::CalculatingBeginningByteIndex(
-                "[InteractionHandlerError] Invalid piece index given in order to calculate beggining byte index."
-                    .to_string(),
-            )),
+    ) -> Result<usize, TorrentStatusError> {
+        let piece_length = torrent_file_data
+            .calculate_piece_lenght(piece_index)
+            .map_err(|err| TorrentStatusError::CalculatingAmountOfBlocks(format!("{}", err)))?;
+        let amount_of_blocks = piece_length.div_ceil(u64::from(BLOCK_BYTES));
+        usize::try_from(amount_of_blocks)
+            .map_err(|err| TorrentStatusError::CalculatingAmountOfBlocks(format!("{}", err)))
+    }
+
+    /// Funcion que indica si un bloque de una pieza ya fue descargado
+    ///
+    pub fn is_a_downloaded_block(&self, piece_index: usize, beginning_byte_index: u32) -> bool {
+        match self.pieces_availability.get(piece_index) {
+            Some(PieceStatus::ValidAndAvailablePiece) => true,
+            Some(PieceStatus::PartiallyDownloaded {
+                downloaded_blocks, ..
+            }) => usize::try_from(beginning_byte_index / BLOCK_BYTES)
+                .ok()
+                .and_then(|block_index| downloaded_blocks.get(block_index))
+                .is_some_and(|is_downloaded| *is_downloaded),
+            _ => false,
         }
     }
 
+    /// Funcion que devuelve los bytes iniciales de todos los bloques
+    /// que faltan descargar de una pieza
+    ///
+    pub fn look_for_missing_blocks(
+        &self,
+        torrent_file_data: &TorrentFileData,
+        piece_index: usize,
+    ) -> Result<Vec<u32>, TorrentStatusError> {
+        let amount_of_blocks = self.calculate_amount_of_blocks(torrent_file_data, piece_index)?;
+        let amount_of_blocks = u32::try_from(amount_of_blocks)
+            .map_err(|err| TorrentStatusError::CalculatingAmountOfBlocks(format!("{}", err)))?;
+        Ok((0..amount_of_blocks)
+            .map(|block_index| block_index * BLOCK_BYTES)
+            .filter(|beginning_byte_index| {
+                !self.is_a_downloaded_block(piece_index, *beginning_byte_index)
+            })
+            .collect())
+    }
+
     /// Funcion que calcula la cantidad de bytes adecuada a pedir
     /// posteriormente a un peer
     ///
@@ -308,6 +427,34 @@ impl TorrentStatus {
             .all(|piece| *piece == PieceStatus::ValidAndAvailablePiece)
     }
 
+    /// Funcion que vuelve a marcar como faltante a una pieza que no pasó la verificacion
+    /// SHA1, descontando sus bytes de lo descargado.
+    ///
+    pub fn set_piece_as_missing(
+        &mut self,
+        torrent_file_data: &TorrentFileData,
+        piece_index: usize,
+    ) -> Result<(), TorrentStatusError> {
+        let piece_lenght = torrent_file_data
+            .calculate_piece_lenght(piece_index)
+            .map_err(|err| TorrentStatusError::UpdatingPieceStatus(format!("{}", err)))?;
+
+        match self.pieces_availability.get_mut(piece_index) {
+            Some(piece_status) if *piece_status == PieceStatus::ValidAndAvailablePiece => {
+                *piece_status = PieceStatus::MissingPiece {
+                    was_requested: false,
+                };
+                self.downloaded -= piece_lenght;
+                self.left += piece_lenght;
+                self.event = StateOfDownload::Started;
+                Ok(())
+            }
+            _ => Err(TorrentStatusError::UpdatingPieceStatus(
+                "[TorrentStatusError] Only a completed piece can be set as missing.".to_string(),
+            )),
+        }
+    }
+
     pub fn set_piece_as_requested(&mut self, piece_index: usize) -> Result<(), TorrentStatusError> {
         if let Some(piece_status) = self.pieces_availability.get_mut(piece_index) {
             match piece_status {
@@ -329,33 +476,65 @@ impl TorrentStatus {
         }
     }
 
-    pub fn set_all_pieces_as_not_requested(&mut self) {
-        for piece_status in self.pieces_availability.iter_mut() {
-            match piece_status {
-                PieceStatus::MissingPiece { was_requested } => {
-                    *was_requested = false;
-                }
-                PieceStatus::PartiallyDownloaded { was_requested, .. } => {
-                    *was_requested = false;
-                }
-                _ => (),
+    /// Funcion que registra que un peer tiene pedidos pendientes de una pieza,
+    /// la cual queda reservada mientras algun peer los tenga.
+    ///
+    pub fn add_piece_requester(&mut self, piece_index: usize) -> Result<(), TorrentStatusError> {
+        self.set_piece_as_requested(piece_index)?;
+        if let Some(requesters_count) = self.pieces_requesters_count.get_mut(piece_index) {
+            *requesters_count += 1;
+        }
+        Ok(())
+    }
+
+    /// Funcion que registra que un peer ya no tiene pedidos pendientes de una pieza. Si ningun
+    /// otro peer los tiene (por ejemplo, en modo endgame), se libera la pieza.
+    ///
+    pub fn remove_piece_requester(&mut self, piece_index: usize) {
+        if let Some(requesters_count) = self.pieces_requesters_count.get_mut(piece_index) {
+            *requesters_count = requesters_count.saturating_sub(1);
+            if *requesters_count > 0 {
+                return;
             }
         }
+        self.set_piece_as_not_requested(piece_index);
+    }
+
+    /// Funcion que libera una pieza que habia sido reservada al pedir sus bloques,
+    /// para que pueda volver a pedirse (a este u otro peer).
+    ///
+    pub fn set_piece_as_not_requested(&mut self, piece_index: usize) {
+        match self.pieces_availability.get_mut(piece_index) {
+            Some(PieceStatus::MissingPiece { was_requested }) => *was_requested = false,
+            Some(PieceStatus::PartiallyDownloaded { was_requested, .. }) => *was_requested = false,
+            _ => (),
+        }
     }
 }
 
 #[cfg(test)]
 mod test_torrent_status {
     mod test_look_for_a_missing_piece_index {
-        use std::{error::Error, net::TcpStream, sync::mpsc, thread, time::SystemTime};
+        use std::{
+            error::Error,
+            net::TcpStream,
+            sync::{mpsc, Arc},
+            thread,
+            time::SystemTime,
+        };
 
         use gtk::glib;
 
-        use crate::torrent::client::peers_communication::local_peer_communicator::{
-            LocalPeerCommunicator, PeerRole,
+        use crate::torrent::client::peers_communication::{
+            connection_health::ConnectionHealth,
+            extensions::ExtensionRegistry,
+            fast_extension::FastExtension,
+            local_peer_communicator::{LocalPeerCommunicator, PeerRole},
+            upload_queue::UploadQueue,
         };
         use crate::torrent::data::{
             peer_data_for_communication::PeerDataForP2PCommunication,
+            piece_picker::{RarestFirstPicker, SequentialPicker},
             torrent_status::{StateOfDownload, TorrentStatus},
         };
         use shared::{parsers::p2p::message::PieceStatus, port_binder::listener_binder::*};
@@ -388,6 +567,8 @@ mod test_torrent_status {
                         was_requested: false,
                     },
                 ],
+                pieces_peer_count: vec![0; 2],
+                pieces_requesters_count: vec![0; 2],
             };
             let server_peer_data = PeerDataForP2PCommunication {
                 peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
@@ -409,6 +590,13 @@ mod test_torrent_status {
                 logger_sender: mpsc::channel().0,
                 ui_sender: ui_sender,
                 clock: SystemTime::now(),
+                pending_requests: vec![],
+                request_queue_depth: 5,
+                piece_picker: Arc::new(SequentialPicker),
+                extensions: ExtensionRegistry::new(),
+                fast_extension: FastExtension::default(),
+                upload_queue: UploadQueue::new(),
+                connection_health: ConnectionHealth::default(),
             };
             Ok((torrent_status, local_peer))
         }
@@ -436,6 +624,8 @@ mod test_torrent_status {
                         was_requested: false,
                     },
                 ],
+                pieces_peer_count: vec![0; 2],
+                pieces_requesters_count: vec![0; 2],
             };
             let server_peer_data = PeerDataForP2PCommunication {
                 peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
@@ -459,6 +649,13 @@ mod test_torrent_status {
                 logger_sender: mpsc::channel().0,
                 ui_sender: ui_sender,
                 clock: SystemTime::now(),
+                pending_requests: vec![],
+                request_queue_depth: 5,
+                piece_picker: Arc::new(SequentialPicker),
+                extensions: ExtensionRegistry::new(),
+                fast_extension: FastExtension::default(),
+                upload_queue: UploadQueue::new(),
+                connection_health: ConnectionHealth::default(),
             };
             Ok((torrent_status, local_peer))
         }
@@ -486,6 +683,8 @@ mod test_torrent_status {
                         was_requested: false,
                     },
                 ],
+                pieces_peer_count: vec![0; 2],
+                pieces_requesters_count: vec![0; 2],
             };
             let server_peer_data = PeerDataForP2PCommunication {
                 peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
@@ -511,6 +710,13 @@ mod test_torrent_status {
                 logger_sender: mpsc::channel().0,
                 ui_sender: ui_sender,
                 clock: SystemTime::now(),
+                pending_requests: vec![],
+                request_queue_depth: 5,
+                piece_picker: Arc::new(SequentialPicker),
+                extensions: ExtensionRegistry::new(),
+                fast_extension: FastExtension::default(),
+                upload_queue: UploadQueue::new(),
+                connection_health: ConnectionHealth::default(),
             };
 
             Ok((torrent_status, local_peer))
@@ -569,5 +775,281 @@ mod test_torrent_status {
             );
             Ok(())
         }
+
+        #[test]
+        fn the_rarest_piece_is_chosen_with_a_rarest_first_picker_ok() -> Result<(), Box<dyn Error>>
+        {
+            let (mut torrent_status, mut local_peer) =
+                create_default_torrent_status_with_a_server_peer_that_has_the_whole_file()?;
+            local_peer.piece_picker = Arc::new(RarestFirstPicker {
+                random_first_pieces: 0,
+            });
+            torrent_status.pieces_peer_count = vec![3, 1];
+
+            assert_eq!(
+                Some(1),
+                torrent_status.look_for_a_missing_piece_index(&local_peer)
+            );
+            Ok(())
+        }
+    }
+
+    mod test_pieces_peer_count {
+        use crate::torrent::data::torrent_status::TorrentStatus;
+        use shared::parsers::p2p::message::PieceStatus;
+
+        #[test]
+        fn the_pieces_of_a_peer_are_added_and_removed_ok() {
+            let mut torrent_status = TorrentStatus::new(30, 3);
+            let bitfield = vec![
+                PieceStatus::ValidAndAvailablePiece,
+                PieceStatus::MissingPiece {
+                    was_requested: false,
+                },
+                PieceStatus::ValidAndAvailablePiece,
+            ];
+
+            torrent_status.add_peer_pieces(&bitfield);
+            torrent_status.add_peer_piece(1);
+            assert_eq!(vec![1, 1, 1], torrent_status.pieces_peer_count);
+
+            torrent_status.remove_peer_pieces(&bitfield);
+            assert_eq!(vec![0, 1, 0], torrent_status.pieces_peer_count);
+        }
+    }
+
+    mod test_endgame {
+        use std::error::Error;
+
+        use crate::torrent::data::torrent_status::TorrentStatus;
+        use shared::parsers::p2p::message::PieceStatus;
+
+        #[test]
+        fn the_download_is_in_endgame_when_every_piece_left_was_requested_ok() {
+            let mut torrent_status = TorrentStatus::new(30, 3);
+            torrent_status.pieces_availability = vec![
+                PieceStatus::ValidAndAvailablePiece,
+                PieceStatus::MissingPiece {
+                    was_requested: true,
+                },
+                PieceStatus::MissingPiece {
+                    was_requested: false,
+                },
+            ];
+            assert!(!torrent_status.is_in_endgame());
+
+            torrent_status.pieces_availability[2] = PieceStatus::PartiallyDownloaded {
+                downloaded_blocks: vec![true, false],
+                was_requested: true,
+            };
+            assert!(torrent_status.is_in_endgame());
+            assert_eq!(vec![1, 2], torrent_status.get_requested_pieces_left());
+        }
+
+        #[test]
+        fn a_completed_download_is_not_in_endgame_ok() {
+            let mut torrent_status = TorrentStatus::new(20, 2);
+            torrent_status.pieces_availability = vec![PieceStatus::ValidAndAvailablePiece; 2];
+            assert!(!torrent_status.is_in_endgame());
+            assert!(torrent_status.get_requested_pieces_left().is_empty());
+        }
+
+        #[test]
+        fn a_piece_is_released_when_its_last_requester_leaves_ok() -> Result<(), Box<dyn Error>> {
+            let mut torrent_status = TorrentStatus::new(10, 1);
+            torrent_status.add_piece_requester(0)?;
+            torrent_status.add_piece_requester(0)?;
+            assert!(torrent_status.is_in_endgame());
+
+            torrent_status.remove_piece_requester(0);
+            assert!(torrent_status.is_in_endgame());
+
+            torrent_status.remove_piece_requester(0);
+            assert!(!torrent_status.is_in_endgame());
+            assert_eq!(
+                Some(&PieceStatus::MissingPiece {
+                    was_requested: false
+                }),
+                torrent_status.get_piece_status(0)
+            );
+            Ok(())
+        }
+    }
+
+    mod test_set_piece_as_missing {
+        use std::error::Error;
+
+        use crate::torrent::data::torrent_status::{StateOfDownload, TorrentStatus};
+        use shared::{
+            parsers::p2p::message::PieceStatus,
+            torrent_file_data::{TargetFilesData, TorrentFileData},
+        };
+
+        fn create_default_torrent_file_data() -> TorrentFileData {
+            TorrentFileData {
+                target_files_data: TargetFilesData::SingleFile {
+                    file_name: "set_piece_as_missing.test".to_string(),
+                    file_length: 40000,
+                },
+                sha1_pieces: vec![],
+                url_tracker_main: "tracker_main.com".to_string(),
+                url_tracker_list: vec![],
+                sha1_info_hash: vec![],
+                piece_length: 34000,
+                total_amount_of_pieces: 2,
+                total_length: 40000,
+            }
+        }
+
+        #[test]
+        fn a_completed_piece_that_fails_the_verification_is_missing_again_ok(
+        ) -> Result<(), Box<dyn Error>> {
+            let torrent_file_data = create_default_torrent_file_data();
+            let mut torrent_status = TorrentStatus {
+                uploaded: 0,
+                downloaded: 40000,
+                left: 0,
+                event: StateOfDownload::Completed,
+                pieces_availability: vec![
+                    PieceStatus::ValidAndAvailablePiece,
+                    PieceStatus::ValidAndAvailablePiece,
+                ],
+                pieces_peer_count: vec![0; 2],
+                pieces_requesters_count: vec![0; 2],
+            };
+
+            torrent_status.set_piece_as_missing(&torrent_file_data, 1)?;
+
+            assert_eq!(34000, torrent_status.downloaded);
+            assert_eq!(6000, torrent_status.left);
+            assert_eq!(StateOfDownload::Started, torrent_status.event);
+            assert_eq!(
+                PieceStatus::MissingPiece {
+                    was_requested: false
+                },
+                torrent_status.pieces_availability[1]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn a_missing_piece_cannot_be_set_as_missing_error() {
+            let torrent_file_data = create_default_torrent_file_data();
+            let mut torrent_status = TorrentStatus::new(40000, 2);
+
+            assert!(torrent_status
+                .set_piece_as_missing(&torrent_file_data, 0)
+                .is_err());
+        }
+    }
+
+    mod test_update_piece_status {
+        use std::error::Error;
+
+        use crate::torrent::{
+            client::peers_communication::handler_communication::BLOCK_BYTES,
+            data::torrent_status::{StateOfDownload, TorrentStatus},
+        };
+        use shared::{
+            parsers::p2p::message::PieceStatus,
+            torrent_file_data::{TargetFilesData, TorrentFileData},
+        };
+
+        // 1º pieza -> 34000 bytes (3 bloques)
+        // 2º pieza ->  6000 bytes (1 bloque)
+        fn create_default_torrent_file_data() -> TorrentFileData {
+            TorrentFileData {
+                target_files_data: TargetFilesData::SingleFile {
+                    file_name: "update_piece_status.test".to_string(),
+                    file_length: 40000,
+                },
+                sha1_pieces: vec![],
+                url_tracker_main: "tracker_main.com".to_string(),
+                url_tracker_list: vec![],
+                sha1_info_hash: vec![],
+                piece_length: 34000,
+                total_amount_of_pieces: 2,
+                total_length: 40000,
+            }
+        }
+
+        #[test]
+        fn blocks_received_out_of_order_are_tracked_ok() -> Result<(), Box<dyn Error>> {
+            let torrent_file_data = create_default_torrent_file_data();
+            let mut torrent_status = TorrentStatus::new(40000, 2);
+            torrent_status.set_piece_as_requested(0)?;
+
+            torrent_status.update_piece_status(
+                &torrent_file_data,
+                0,
+                2 * BLOCK_BYTES,
+                34000 - 2 * BLOCK_BYTES,
+            )?;
+
+            assert_eq!(
+                PieceStatus::PartiallyDownloaded {
+                    downloaded_blocks: vec![false, false, true],
+                    was_requested: true
+                },
+                torrent_status.pieces_availability[0]
+            );
+            assert_eq!(
+                vec![0, BLOCK_BYTES],
+                torrent_status.look_for_missing_blocks(&torrent_file_data, 0)?
+            );
+            assert_eq!(
+                u64::from(34000 - 2 * BLOCK_BYTES),
+                torrent_status.downloaded
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn a_piece_is_completed_when_all_its_blocks_are_received_ok() -> Result<(), Box<dyn Error>>
+        {
+            let torrent_file_data = create_default_torrent_file_data();
+            let mut torrent_status = TorrentStatus::new(40000, 2);
+            torrent_status.pieces_availability[1] = PieceStatus::ValidAndAvailablePiece;
+
+            torrent_status.update_piece_status(&torrent_file_data, 0, BLOCK_BYTES, BLOCK_BYTES)?;
+            torrent_status.update_piece_status(
+                &torrent_file_data,
+                0,
+                2 * BLOCK_BYTES,
+                34000 - 2 * BLOCK_BYTES,
+            )?;
+            torrent_status.update_piece_status(&torrent_file_data, 0, 0, BLOCK_BYTES)?;
+
+            assert_eq!(
+                PieceStatus::ValidAndAvailablePiece,
+                torrent_status.pieces_availability[0]
+            );
+            assert_eq!(StateOfDownload::Completed, torrent_status.event);
+            Ok(())
+        }
+
+        #[test]
+        fn a_block_cannot_be_downloaded_twice_error() -> Result<(), Box<dyn Error>> {
+            let torrent_file_data = create_default_torrent_file_data();
+            let mut torrent_status = TorrentStatus::new(40000, 2);
+
+            torrent_status.update_piece_status(&torrent_file_data, 0, BLOCK_BYTES, BLOCK_BYTES)?;
+
+            assert!(torrent_status
+                .update_piece_status(&torrent_file_data, 0, BLOCK_BYTES, BLOCK_BYTES)
+                .is_err());
+            assert_eq!(u64::from(BLOCK_BYTES), torrent_status.downloaded);
+            Ok(())
+        }
+
+        #[test]
+        fn a_block_that_does_not_start_at_a_block_boundary_error() {
+            let torrent_file_data = create_default_torrent_file_data();
+            let mut torrent_status = TorrentStatus::new(40000, 2);
+
+            assert!(torrent_status
+                .update_piece_status(&torrent_file_data, 0, 20000, BLOCK_BYTES)
+                .is_err());
+        }
     }
 }
@@ -6,7 +6,7 @@ use shared::parsers::bencoding::values::ValuesBencoding;
 
 use std::{
     collections::HashMap,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     str::FromStr,
 };
 
@@ -15,20 +15,32 @@ type ResultResponse<T> = Result<T, ResponseError>;
 
 const ZERO: u64 = 0;
 const INTERVAL: &str = "interval";
+const MIN_INTERVAL: &str = "min interval";
+const TRACKER_ID: &str = "tracker id";
 const COMPLETE: &str = "complete";
 const INCOMPLETE: &str = "incomplete";
+const DOWNLOADED: &str = "downloaded";
+const FILES: &str = "files";
 const PEERS: &str = "peers";
+const PEERS6: &str = "peers6";
 const PEER_ID: &str = "peer id";
 const IP: &str = "ip";
 const PORT: &str = "port";
+const LONG_COMPACT_PEER: usize = 6;
+const LONG_COMPACT_PEER6: usize = 18;
 
 #[derive(Debug, PartialEq, Eq)]
 ///Enumerado que representa la seccion en la que el error puede surgir
 pub enum Section {
     Interval,
+    MinInterval,
+    TrackerId,
     Complete,
     Incomplete,
+    Downloaded,
+    Files,
     Peers,
+    Peers6,
     PeerId,
     Ip,
     Port,
@@ -57,19 +69,27 @@ fn to_sock_addr(ip: String, port: u16) -> ResultResponse<SocketAddr> {
     Ok(result_addr)
 }
 
+//Un peer compacto son 4 bytes de IPv4 (o 16 de IPv6, BEP 7) seguidos de 2 bytes del puerto,
+//todo en big endian.
 fn decode_compact_peer(compact_peer: Vec<u8>) -> ResultResponse<(IpAddr, u16)> {
-    let multiplier = 256;
-    if compact_peer.len() != 6 {
-        return Err(ResponseError::ConvertIp(Section::Ip));
-    }
-    let ipv4_addr = Ipv4Addr::new(
-        compact_peer[0],
-        compact_peer[1],
-        compact_peer[2],
-        compact_peer[3],
-    );
-    let port = (compact_peer[4] as u16 * multiplier) + compact_peer[5] as u16;
-    Ok((IpAddr::V4(ipv4_addr), port))
+    let ip_addr = match compact_peer.len() {
+        LONG_COMPACT_PEER => {
+            let octets: [u8; 4] = compact_peer[..4]
+                .try_into()
+                .map_err(|_| ResponseError::ConvertIp(Section::Ip))?;
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        LONG_COMPACT_PEER6 => {
+            let octets: [u8; 16] = compact_peer[..16]
+                .try_into()
+                .map_err(|_| ResponseError::ConvertIp(Section::Ip))?;
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return Err(ResponseError::ConvertIp(Section::Ip)),
+    };
+    let port_index = compact_peer.len() - 2;
+    let port = u16::from_be_bytes([compact_peer[port_index], compact_peer[port_index + 1]]);
+    Ok((ip_addr, port))
 }
 
 impl PeerDataFromTrackerResponse {
@@ -97,11 +117,21 @@ impl PeerDataFromTrackerResponse {
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct TrackerResponseData {
     pub interval: u64,
+    pub min_interval: Option<u64>,
+    pub tracker_id: Option<String>,
     pub complete: u64,
     pub incomplete: u64,
     pub peers: Vec<PeerDataFromTrackerResponse>,
 }
 
+/// Estadisticas de un torrent obtenidas a partir de un scrape al tracker
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TrackerScrapeData {
+    pub complete: u64,
+    pub downloaded: u64,
+    pub incomplete: u64,
+}
+
 fn vec_u8_to_string(vec: &[u8]) -> String {
     String::from_utf8_lossy(vec).into_owned()
 }
@@ -148,27 +178,72 @@ fn init_peers(dic_response: &DicValues) -> ResultResponse<Vec<PeerDataFromTracke
             Ok(vector_peers)
         }
         Some(ValuesBencoding::String(peers_compact)) => {
-            let long_compact = 6;
-            let list_peers_compact: Vec<Vec<u8>> = peers_compact
-                .chunks(long_compact)
-                .map(|s| s.into())
-                .collect();
-            for peer_compact in list_peers_compact {
-                if let Ok(peer_struct) = PeerDataFromTrackerResponse::new_from_compact(peer_compact)
-                {
-                    vector_peers.push(peer_struct)
-                };
-            }
-            Ok(vector_peers)
+            Ok(decode_compact_peers(peers_compact, LONG_COMPACT_PEER))
         }
         Some(_) => Err(ResponseError::Format(Section::Peers)),
+        //Un tracker puede responder solo con peers IPv6
+        None if dic_response.contains_key(PEERS6.as_bytes()) => Ok(vector_peers),
         None => Err(ResponseError::NotFound(Section::Peers)),
     }
 }
 
+fn decode_compact_peers(
+    peers_compact: &[u8],
+    long_compact: usize,
+) -> Vec<PeerDataFromTrackerResponse> {
+    peers_compact
+        .chunks(long_compact)
+        .filter_map(|peer_compact| {
+            PeerDataFromTrackerResponse::new_from_compact(peer_compact.to_vec()).ok()
+        })
+        .collect()
+}
+
+fn init_peers6(dic_response: &DicValues) -> ResultResponse<Vec<PeerDataFromTrackerResponse>> {
+    match dic_response.get(PEERS6.as_bytes()) {
+        Some(ValuesBencoding::String(peers_compact)) => {
+            Ok(decode_compact_peers(peers_compact, LONG_COMPACT_PEER6))
+        }
+        Some(_) => Err(ResponseError::Format(Section::Peers6)),
+        None => Ok(vec![]),
+    }
+}
+
+impl TrackerScrapeData {
+    ///Funcion que obtiene, de la respuesta a un scrape, las estadisticas del torrent con el
+    /// info_hash dado (que se encuentran dentro del diccionario `files`).
+    pub fn new(dic_response: DicValues, info_hash: &[u8]) -> Result<Self, ResponseError> {
+        let dic_stats = match dic_response.get(FILES.as_bytes()) {
+            Some(ValuesBencoding::Dic(dic_files)) => match dic_files.get(info_hash) {
+                Some(ValuesBencoding::Dic(dic_stats)) => dic_stats.clone(),
+                Some(_) => return Err(ResponseError::Format(Section::Files)),
+                None => return Err(ResponseError::NotFound(Section::Files)),
+            },
+            Some(_) => return Err(ResponseError::Format(Section::Files)),
+            None => return Err(ResponseError::NotFound(Section::Files)),
+        };
+
+        Ok(TrackerScrapeData {
+            complete: get_dic_u64(&dic_stats, COMPLETE, Section::Complete)?,
+            downloaded: get_dic_u64(&dic_stats, DOWNLOADED, Section::Downloaded)?,
+            incomplete: get_dic_u64(&dic_stats, INCOMPLETE, Section::Incomplete)?,
+        })
+    }
+}
+
 impl TrackerResponseData {
     pub fn new(dic_response: DicValues) -> Result<Self, ResponseError> {
         let interval = get_dic_u64(&dic_response, INTERVAL, Section::Interval)?;
+        let min_interval = match get_dic_u64(&dic_response, MIN_INTERVAL, Section::MinInterval) {
+            Ok(value_min_interval) => Some(value_min_interval),
+            Err(ResponseError::NotFound(_)) => None,
+            Err(error) => return Err(error),
+        };
+        let tracker_id = match get_dic_string(&dic_response, TRACKER_ID, Section::TrackerId) {
+            Ok(value_tracker_id) => Some(vec_u8_to_string(&value_tracker_id)),
+            Err(ResponseError::NotFound(_)) => None,
+            Err(error) => return Err(error),
+        };
 
         let complete = match get_dic_u64(&dic_response, COMPLETE, Section::Complete) {
             Ok(value_complete) => value_complete,
@@ -180,10 +255,13 @@ impl TrackerResponseData {
             Err(ResponseError::NotFound(_)) => ZERO,
             Err(error) => return Err(error),
         };
-        let peers = init_peers(&dic_response)?;
+        let mut peers = init_peers(&dic_response)?;
+        peers.extend(init_peers6(&dic_response)?);
 
         Ok(TrackerResponseData {
             interval,
+            min_interval,
+            tracker_id,
             complete,
             incomplete,
             peers,
@@ -224,6 +302,40 @@ impl TrackerResponseData {
 #[cfg(test)]
 mod tests_tracker_response_data {
     use super::*;
+
+    fn create_scrape_response(info_hash: &[u8]) -> DicValues {
+        let mut dic_stats = HashMap::new();
+        dic_stats.insert(COMPLETE.as_bytes().to_vec(), ValuesBencoding::Integer(5));
+        dic_stats.insert(DOWNLOADED.as_bytes().to_vec(), ValuesBencoding::Integer(50));
+        dic_stats.insert(INCOMPLETE.as_bytes().to_vec(), ValuesBencoding::Integer(10));
+        let mut dic_files = HashMap::new();
+        dic_files.insert(info_hash.to_vec(), ValuesBencoding::Dic(dic_stats));
+        let mut dic_response = HashMap::new();
+        dic_response.insert(FILES.as_bytes().to_vec(), ValuesBencoding::Dic(dic_files));
+        dic_response
+    }
+
+    #[test]
+    fn test_scrape_data_ok() {
+        let scrape_data = TrackerScrapeData::new(create_scrape_response(&[1; 20]), &[1; 20]);
+
+        assert_eq!(
+            Ok(TrackerScrapeData {
+                complete: 5,
+                downloaded: 50,
+                incomplete: 10
+            }),
+            scrape_data
+        );
+    }
+
+    #[test]
+    fn test_scrape_data_of_another_torrent_error() {
+        let scrape_data = TrackerScrapeData::new(create_scrape_response(&[1; 20]), &[2; 20]);
+
+        assert_eq!(Err(ResponseError::NotFound(Section::Files)), scrape_data);
+    }
+
     #[test]
     fn test_get_dic_u64_ok() {
         let mut dic = HashMap::new();
@@ -325,6 +437,83 @@ mod tests_tracker_response_data {
         assert_eq!(port, Err(ResponseError::NotFound(Section::Interval)))
     }
 
+    fn create_announce_response(peers: &[u8], peers6: &[u8]) -> DicValues {
+        let mut dic_response = HashMap::new();
+        dic_response.insert(INTERVAL.as_bytes().to_vec(), ValuesBencoding::Integer(1800));
+        dic_response.insert(
+            PEERS.as_bytes().to_vec(),
+            ValuesBencoding::String(peers.to_vec()),
+        );
+        dic_response.insert(
+            PEERS6.as_bytes().to_vec(),
+            ValuesBencoding::String(peers6.to_vec()),
+        );
+        dic_response
+    }
+
+    #[test]
+    fn test_compact_peers_and_peers6_ok() -> Result<(), Box<dyn std::error::Error>> {
+        let mut peers6 = vec![0x20, 0x01, 0x0d, 0xb8];
+        peers6.extend([0; 11]);
+        peers6.extend([1, 0x1a, 0xe1]);
+        let dic_response = create_announce_response(&[127, 0, 0, 1, 0x1a, 0xe2], &peers6);
+
+        let response_data =
+            TrackerResponseData::new(dic_response).map_err(|err| format!("{:?}", err))?;
+
+        assert_eq!(
+            Some(SocketAddr::from_str("127.0.0.1:6882")?),
+            response_data.get_peer_address(0)
+        );
+        assert_eq!(
+            Some(SocketAddr::from_str("[2001:db8::1]:6881")?),
+            response_data.get_peer_address(1)
+        );
+        assert_eq!(2, response_data.get_total_amount_peers());
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_with_tracker_id_ok() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dic_response = create_announce_response(&[], &[]);
+        assert_eq!(
+            None,
+            TrackerResponseData::new(dic_response.clone())
+                .map_err(|err| format!("{:?}", err))?
+                .tracker_id
+        );
+
+        dic_response.insert(
+            TRACKER_ID.as_bytes().to_vec(),
+            ValuesBencoding::String(b"abc123".to_vec()),
+        );
+        let response_data =
+            TrackerResponseData::new(dic_response).map_err(|err| format!("{:?}", err))?;
+
+        assert_eq!(Some("abc123".to_string()), response_data.tracker_id);
+        Ok(())
+    }
+
+    #[test]
+    fn test_response_with_only_peers6_ok() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dic_response = create_announce_response(&[], &[0; 18]);
+        dic_response.remove(PEERS.as_bytes());
+
+        let response_data =
+            TrackerResponseData::new(dic_response).map_err(|err| format!("{:?}", err))?;
+
+        assert_eq!(1, response_data.get_total_amount_peers());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_compact_peer_with_invalid_length_error() {
+        assert_eq!(
+            Err(ResponseError::ConvertIp(Section::Ip)),
+            decode_compact_peer(vec![0; 10])
+        );
+    }
+
     //    #[test]
     //    fn test_from_str_to_ipaddr_ok() {
     //        let ip = String::from("197.0.12.1");
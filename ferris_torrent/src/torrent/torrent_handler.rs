@@ -4,11 +4,21 @@
 
 use crate::torrent::{
     client::{
+        dht::dht_node::{self, DhtNode},
         entry_files_management,
-        peers_communication::{self, local_peer_communicator::generate_peer_id},
-        tracker_communication::http_handler,
+        magnet_handler::{self, MagnetHandlerError},
+        peers_communication::{
+            self,
+            local_peer_communicator::generate_peer_id,
+            peer_listener::{self, PeerListenerError, TorrentsRegistry},
+        },
+        resume_handler,
+        tracker_communication::{http_handler, tracker_tiers::TrackerTiers},
+    },
+    data::{
+        config_file_torrent::ConfigFileTorrent, torrent_status::StateOfDownload,
+        tracker_response_data::TrackerResponseData,
     },
-    data::{config_file_torrent::ConfigFileTorrent, torrent_status::TorrentStatus},
     logger::{self, Logger},
     user_interface::{constants::MessageUI, ui_sender_handler},
 };
@@ -16,6 +26,7 @@ use core::fmt;
 use gtk::glib::Sender as UiSender;
 use log::{debug, info, trace};
 use shared::{
+    magnet_link::MagnetLink,
     medatada_analyzer::{self, MetadataError},
     torrent_file_data::TorrentFileData,
 };
@@ -27,9 +38,9 @@ use std::{
 
 use super::{
     client::{
+        block_handler::BlockHandlerError, dht::dht_node::DhtError,
         peers_communication::local_peer_communicator::InteractionHandlerError,
-        pieces_assembling_handler::PiecesAssemblerError,
-        tracker_communication::http_handler::ErrorMsgHttp,
+        resume_handler::ResumeHandlerError, tracker_communication::http_handler::ErrorMsgHttp,
     },
     logger::LogError,
     user_interface::ui_sender_handler::UiError,
@@ -39,6 +50,7 @@ use super::{
 #[derive(PartialEq, Eq, Debug)]
 pub enum TorrentHandlerError {
     CreatingTorrent(MetadataError),
+    CreatingTorrentFromMagnet(MagnetHandlerError),
     UserInterface(UiError),
     CreatingLogger(LogError),
     WritingLogger(String),
@@ -49,7 +61,10 @@ pub enum TorrentHandlerError {
     SetGlobalShutDown(String),
     ReadingShutDownField(String),
     AssemblingTarget(String),
-    PiecesHandler(PiecesAssemblerError),
+    BlockHandler(BlockHandlerError),
+    ResumingDownload(ResumeHandlerError),
+    ListeningForPeers(PeerListenerError),
+    RunningDht(DhtError),
 }
 
 impl fmt::Display for TorrentHandlerError {
@@ -65,32 +80,56 @@ type JoinHandleTorrent = JoinHandle<ResultTorrent>;
 
 ///
 /// Funcion principal del manejo de un archivo .torrent. A partir de la informacion sumistrada por el
-/// archivo de configuracion y por el .torrent, se realiza la comunicación con el tracker para posterior
+/// archivo de configuracion y por el .torrent, se realiza la comunicación con el tracker (probando los
+/// distintos niveles de trackers hasta que alguno responda) para posterior
 /// comunicacion con los distintos peers. Esto trae como consecuencia, la descarga y verificacion de cada
-/// una de las piezas con su posterior ensamblado.
+/// una de las piezas, que se escriben directamente en los archivos destino.
+/// Si ningun tracker responde y hay un nodo DHT, los peers iniciales se buscan en la DHT.
+/// Si en el directorio de descargas ya hay datos del torrent, la descarga se reanuda a partir de ellos.
+/// Mientras dure la comunicacion con los peers, el torrent queda registrado en el listener compartido
+/// para recibir las conexiones entrantes dirigidas a su info_hash.
 ///
 fn handle_torrent(
     torrent_file: TorrentFileData,
     config_data: &ConfigFileTorrent,
+    torrents_registry: &TorrentsRegistry,
+    dht_node: &Option<Arc<DhtNode>>,
     logger_sender: &LoggerSender<String>,
     ui_sender: &UiSender<MessageUI>,
     global_shut_down: &Arc<RwLock<bool>>,
 ) -> ResultTorrent {
-    let torrent_size = torrent_file.get_total_length();
-    let torrent_status = TorrentStatus::new(torrent_size, torrent_file.total_amount_of_pieces);
+    let torrent_status =
+        resume_handler::load_torrent_status(&torrent_file, &config_data.get_download_path())
+            .map_err(TorrentHandlerError::ResumingDownload)?;
     trace!("Creado estado inicial del torrent");
 
     let peer_id = generate_peer_id();
 
     info!("Iniciando comunicacion con tracker");
-    let tracker_response = http_handler::communicate_with_tracker(
+    let mut tracker_tiers = TrackerTiers::new(&torrent_file);
+    let tracker_response = match http_handler::communicate_with_tracker(
+        &mut tracker_tiers,
         &torrent_status,
         &torrent_file,
         config_data,
         peer_id.clone(),
-    )
-    .map_err(TorrentHandlerError::CommunicationWithTracker)?;
-    info!("Comunicacion con el tracker exitosa");
+        Some(StateOfDownload::Started),
+    ) {
+        Ok(tracker_response) => {
+            info!("Comunicacion con el tracker exitosa");
+            tracker_response
+        }
+        Err(error) => match dht_node {
+            Some(dht_node) => {
+                info!(
+                    "Fallo la comunicacion con el tracker, se buscan peers en la DHT: {}",
+                    error
+                );
+                get_initial_peers_from_dht(dht_node, &torrent_file)?
+            }
+            None => return Err(TorrentHandlerError::CommunicationWithTracker(error)),
+        },
+    };
 
     ui_sender_handler::update_torrent_information(
         ui_sender,
@@ -101,15 +140,44 @@ fn handle_torrent(
     .map_err(TorrentHandlerError::UserInterface)?;
 
     info!("Inicio de comunicacion con peers.");
-    peers_communication::handler_communication::handle_general_interaction_with_peers(
+    let incoming_peers = torrents_registry
+        .register_torrent(&torrent_file)
+        .map_err(TorrentHandlerError::ListeningForPeers)?;
+    let result = peers_communication::handler_communication::handle_general_interaction_with_peers(
         (&torrent_file, &tracker_response, config_data, peer_id),
+        (tracker_tiers, dht_node.clone()),
         torrent_status,
+        incoming_peers,
         global_shut_down.clone(),
         logger_sender,
         ui_sender,
     )
-    .map_err(TorrentHandlerError::CommunicationWithPeers)?;
-    Ok(())
+    .map_err(TorrentHandlerError::CommunicationWithPeers);
+    torrents_registry
+        .unregister_torrent(&torrent_file)
+        .map_err(TorrentHandlerError::ListeningForPeers)?;
+    result
+}
+
+///
+/// Funcion que obtiene los peers iniciales de un torrent desde la DHT, para cuando ningun tracker
+/// responde. Se arma una respuesta como la de un tracker, sin seeders ni leechers.
+///
+fn get_initial_peers_from_dht(
+    dht_node: &DhtNode,
+    torrent_file: &TorrentFileData,
+) -> Result<TrackerResponseData, TorrentHandlerError> {
+    let peers = dht_node::find_torrent_peers(dht_node, torrent_file, None)
+        .map_err(TorrentHandlerError::RunningDht)?;
+    info!("Se obtuvieron {} peers de la DHT", peers.len());
+    Ok(TrackerResponseData {
+        interval: 0,
+        min_interval: None,
+        tracker_id: None,
+        complete: 0,
+        incomplete: 0,
+        peers,
+    })
 }
 
 fn log_torrent_error(
@@ -166,41 +234,55 @@ fn set_up_logger(
 }
 
 ///
-/// Funcion que se encarga de descargar todos los archivos .torrent que se encuentran en la lista dada.
-/// Retorna un handler siendo que ejecuta un thread dentro, el cual recorre los distintos .torrent a ser descargados.
+/// Funcion que crea el TorrentFileData a partir de la ruta a un archivo .torrent o de un
+/// magnet link, en cuyo caso la metadata se descarga de los peers.
 ///
-fn handle_list_of_torrents(
-    files_list: Vec<String>,
+fn create_torrent_file(
+    file_path: &str,
+    config_data: &ConfigFileTorrent,
+    dht_node: &Option<Arc<DhtNode>>,
+    global_shut_down: &Arc<RwLock<bool>>,
+) -> Result<TorrentFileData, TorrentHandlerError> {
+    if MagnetLink::is_magnet_link(file_path) {
+        return magnet_handler::create_torrent_from_magnet(
+            file_path,
+            config_data,
+            dht_node.as_deref(),
+            global_shut_down,
+        )
+        .map_err(TorrentHandlerError::CreatingTorrentFromMagnet);
+    }
+    medatada_analyzer::create_torrent(file_path).map_err(TorrentHandlerError::CreatingTorrent)
+}
+
+///
+/// Funcion que se encarga de descargar el archivo .torrent (o magnet link) dado.
+/// Retorna un handler siendo que la descarga se ejecuta dentro de un thread propio, lo cual permite
+/// descargar todos los torrents en simultaneo.
+///
+fn handle_torrent_file(
+    file_path: String,
     config_data: ConfigFileTorrent,
+    torrents_registry: TorrentsRegistry,
+    dht_node: Option<Arc<DhtNode>>,
     ui_sender: UiSender<MessageUI>,
     global_shut_down: Arc<RwLock<bool>>,
 ) -> JoinHandleTorrent {
-    let mut current_torrent_index = 0;
-    let max_torrent_index = files_list.len();
-
-    thread::spawn(move || loop {
+    thread::spawn(move || {
         if is_shut_down_set(&global_shut_down)? {
             return Ok(());
         }
-        if current_torrent_index >= max_torrent_index {
-            info!("No hay mas torrents para descargar, por favor cierre la pestaña.");
-            return Ok(());
-        }
-
-        let file_path = files_list[current_torrent_index].clone();
         debug!("Archivo ingresado: {}", file_path);
         info!("Archivo ingresado con exito");
 
-        let torrent_file = match medatada_analyzer::create_torrent(&file_path)
-            .map_err(TorrentHandlerError::CreatingTorrent)
-        {
-            Ok(torrent_file) => torrent_file,
-            Err(error) => {
-                info!("Error al querer crear el torrent {}: {}", file_path, error);
-                current_torrent_index += 1;
-                continue;
-            }
-        };
+        let torrent_file =
+            match create_torrent_file(&file_path, &config_data, &dht_node, &global_shut_down) {
+                Ok(torrent_file) => torrent_file,
+                Err(error) => {
+                    info!("Error al querer crear el torrent {}: {}", file_path, error);
+                    return Ok(());
+                }
+            };
         trace!("Almacenada y parseada información de metadata");
 
         ui_sender_handler::add_torrent(&ui_sender, &torrent_file)
@@ -214,14 +296,15 @@ fn handle_list_of_torrents(
                     "Error al querer crear el logger del torrent {}: {}",
                     torrent_name, error
                 );
-                current_torrent_index += 1;
-                continue;
+                return Ok(());
             }
         };
 
         if let Err(error) = handle_torrent(
             torrent_file,
             &config_data,
+            &torrents_registry,
+            &dht_node,
             &logger_sender,
             &ui_sender,
             &global_shut_down,
@@ -231,60 +314,117 @@ fn handle_list_of_torrents(
         log_finished_torrent(&torrent_name, &logger_sender)?;
 
         logger::close_logger(logger_handler, logger_sender)
-            .map_err(|err| TorrentHandlerError::ClosingLogger(format!("{}", err)))?;
-
-        current_torrent_index += 1;
+            .map_err(|err| TorrentHandlerError::ClosingLogger(format!("{}", err)))
     })
 }
 
-fn generate_file_lists() -> Result<(Vec<String>, Vec<String>), Box<dyn Error>> {
-    let files_list = entry_files_management::create_list_files()?;
-
-    let mut files_list_1: Vec<String> = vec![];
-    let mut files_list_2: Vec<String> = vec![];
+///
+/// Funcion que lanza, dentro de un thread, el listener compartido por todos los torrents, el cual
+/// deriva cada conexion entrante al torrent correspondiente segun el registro dado.
+///
+fn handle_incoming_peers(
+    config_data: &ConfigFileTorrent,
+    torrents_registry: TorrentsRegistry,
+    global_shut_down: Arc<RwLock<bool>>,
+) -> Result<JoinHandleTorrent, TorrentHandlerError> {
+    let listener =
+        peer_listener::bind_listener(config_data.get_port(), config_data.get_bind_address())
+            .map_err(TorrentHandlerError::ListeningForPeers)?;
+    Ok(thread::spawn(move || {
+        peer_listener::listen_for_incoming_peers(listener, torrents_registry, global_shut_down)
+            .map_err(TorrentHandlerError::ListeningForPeers)
+    }))
+}
 
-    for (i, file) in files_list.iter().enumerate() {
-        if i % 2 == 0 {
-            files_list_1.push(file.clone())
-        } else {
-            files_list_2.push(file.clone())
+///
+/// Funcion que lanza, dentro de un thread, el nodo DHT compartido por todos los torrents, el cual atiende
+/// los mensajes de otros nodos hasta el shutdown global y luego guarda su tabla de ruteo en el directorio
+/// de descargas para la proxima ejecucion. Si la DHT esta deshabilitada o no se puede abrir su puerto,
+/// los torrents se descargan sin ella.
+///
+fn handle_dht_node(
+    config_data: &ConfigFileTorrent,
+    global_shut_down: Arc<RwLock<bool>>,
+) -> Option<(Arc<DhtNode>, JoinHandleTorrent)> {
+    let dht_port = u16::try_from(config_data.get_dht_port())
+        .ok()
+        .filter(|dht_port| *dht_port != 0)?;
+    let state_file_path = dht_node::get_state_file_path(&config_data.get_download_path());
+    let dht_node = match DhtNode::bind(
+        dht_node::get_bind_address(config_data.get_bind_address(), dht_port),
+        dht_node::load_routing_table(&state_file_path),
+        config_data.get_dht_bootstrap_nodes(),
+    ) {
+        Ok(dht_node) => Arc::new(dht_node),
+        Err(error) => {
+            info!(
+                "No se pudo iniciar el nodo DHT, se continua sin DHT: {}",
+                error
+            );
+            return None;
         }
-    }
-    Ok((files_list_1, files_list_2))
+    };
+
+    let listening_node = dht_node.clone();
+    let handler = thread::spawn(move || {
+        let result = listening_node
+            .listen(&global_shut_down)
+            .map_err(TorrentHandlerError::RunningDht);
+        let routing_table = listening_node
+            .get_routing_table()
+            .map_err(TorrentHandlerError::RunningDht)?;
+        if let Err(error) = routing_table.save(&state_file_path) {
+            info!("No se pudo guardar la tabla de ruteo de la DHT: {}", error);
+        }
+        result
+    });
+    Some((dht_node, handler))
 }
 
 ///
 /// FUNCION PRINCIPAL
 /// A partir de un emisor de mensajes del tpo MessageUI y un shutdown global, la función se encarga de manejar
-/// la descarga de todos los archivo .torrent con un manejo multithreading.
-/// La funcion devuelve los handler de los dos threads implementados dentro o un error en caso de que el archivo
-/// de configuracion se encuentre dañado o en caso de que no se haya pasado por consola una ruta válida para
-/// obtener los .torrent a ser descargados.
+/// la descarga de todos los archivo .torrent con un manejo multithreading: cada torrent se descarga en su
+/// propio thread y todos comparten un unico puerto de escucha para las conexiones entrantes y un unico
+/// nodo DHT.
+/// La funcion devuelve los handler de todos los threads implementados dentro o un error en caso de que el archivo
+/// de configuracion se encuentre dañado, en caso de que no se pueda abrir el puerto de escucha o en caso de que
+/// no se haya pasado por consola una ruta válida para obtener los .torrent a ser descargados.
 ///
 pub fn handle_all_torrents(
     ui_sender: UiSender<MessageUI>,
     global_shut_down: &Arc<RwLock<bool>>,
-) -> Result<(JoinHandleTorrent, JoinHandleTorrent), Box<dyn Error>> {
-    let mut config_data = ConfigFileTorrent::new("ferris_torrent/config.txt")?;
+) -> Result<Vec<JoinHandleTorrent>, Box<dyn Error>> {
+    let config_data = ConfigFileTorrent::new("ferris_torrent/config.txt")?;
     info!("Archivo de configuración leido y parseado correctamente");
 
-    let (files_list_1, files_list_2) = generate_file_lists()?;
+    let files_list = entry_files_management::create_list_files()?;
     info!("Archivo ingresado con exito");
 
-    let torrent_handler_1 = handle_list_of_torrents(
-        files_list_1,
-        config_data.clone(),
-        ui_sender.clone(),
-        global_shut_down.clone(),
-    );
-
-    config_data.port += 1;
-    let torrent_handler_2 = handle_list_of_torrents(
-        files_list_2,
-        config_data,
-        ui_sender,
+    let torrents_registry = TorrentsRegistry::new();
+    let mut torrent_handlers = vec![handle_incoming_peers(
+        &config_data,
+        torrents_registry.clone(),
         global_shut_down.clone(),
-    );
+    )?];
+    let dht_node = match handle_dht_node(&config_data, global_shut_down.clone()) {
+        Some((dht_node, dht_handler)) => {
+            torrent_handlers.push(dht_handler);
+            Some(dht_node)
+        }
+        None => None,
+    };
+
+    for file_path in files_list {
+        torrent_handlers.push(handle_torrent_file(
+            file_path,
+            config_data.clone(),
+            torrents_registry.clone(),
+            dht_node.clone(),
+            ui_sender.clone(),
+            global_shut_down.clone(),
+        ));
+    }
 
-    Ok((torrent_handler_1, torrent_handler_2))
+    Ok(torrent_handlers)
 }
@@ -70,6 +70,11 @@ fn build_ui(app: &Application, receiver: Receiver<MessageUI>) {
                 builder_main.change_peers_leechers(torrent_name.clone(), peers, leechers);
                 builder_main.change_single_multiple(torrent_name, type_torrent);
             }
+            MessageUI::UpdateSeedersAndLeechers {
+                torrent_name,
+                seeders,
+                leechers,
+            } => builder_main.change_peers_leechers(torrent_name, seeders, leechers),
             MessageUI::UpdatePiecesDownloaded {
                 torrent_name,
                 pieces_downloaded,
@@ -95,6 +95,11 @@ pub enum MessageUI {
         leechers: u64,
         type_torrent: TorrentFileType,
     },
+    UpdateSeedersAndLeechers {
+        torrent_name: String,
+        seeders: u64,
+        leechers: u64,
+    },
     //-- Status
     UpdatePiecesDownloaded {
         torrent_name: String,
@@ -102,6 +102,21 @@ pub fn update_torrent_information(
     Ok(())
 }
 
+pub fn update_seeders_and_leechers(
+    ui_sender: &Sender<MessageUI>,
+    torrent_file: &TorrentFileData,
+    seeders: u64,
+    leechers: u64,
+) -> Result<(), UiError> {
+    ui_sender
+        .send(MessageUI::UpdateSeedersAndLeechers {
+            torrent_name: torrent_file.get_torrent_representative_name(),
+            seeders,
+            leechers,
+        })
+        .map_err(|err| UiError::UpdatingTorrentInformation(format!("{}", err)))
+}
+
 pub fn update_peers_state(
     ui_sender: &Sender<MessageUI>,
     torrent_file: &TorrentFileData,
@@ -1,14 +1,17 @@
 use core::fmt;
 use ferris_torrent::torrent::{
+    client::block_handler::TargetFiles,
     client::peers_communication::{
+        choker::{Choker, DEFAULT_UNCHOKE_SLOTS},
         handler_communication::BLOCK_BYTES,
         local_peer_communicator::{
             generate_peer_id, InteractionHandlerError, InteractionHandlerErrorKind,
-            InteractionHandlerStatus, LocalPeerCommunicator,
+            InteractionHandlerStatus, LocalPeerCommunicator, RequestSettings,
         },
         msg_receiver,
     },
     data::{
+        piece_picker::SequentialPicker,
         torrent_status::{StateOfDownload, TorrentStatus},
         tracker_response_data::{PeerDataFromTrackerResponse, TrackerResponseData},
     },
@@ -17,7 +20,7 @@ use ferris_torrent::torrent::{
 use shared::{
     parsers::{
         p2p,
-        p2p::constants::PSTR_STRING_HANDSHAKE,
+        p2p::constants::{EXTENSION_PROTOCOL_BIT, FAST_EXTENSION_BIT, PSTR_STRING_HANDSHAKE},
         p2p::message::{P2PMessage, PieceStatus},
     },
     port_binder::listener_binder::*,
@@ -33,6 +36,7 @@ use std::{
     str::FromStr,
     sync::{mpsc, Arc, RwLock},
     thread,
+    time::Duration,
 };
 
 pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
@@ -46,6 +50,9 @@ pub const DEFAULT_INFO_HASH: [u8; 20] = [0; 20];
 pub const DEFAULT_PIECE_LENGHT: usize = 34000;
 pub const DEFAULT_AMOUNT_OF_PIECES: usize = 2;
 pub const DEFAULT_LAST_PIECE_LENGHT: usize = 6000;
+pub const DEFAULT_REQUEST_QUEUE_DEPTH: usize = 5;
+pub const SECS_PEER_IDLE_TIMEOUT: u64 = 180;
+pub const SECS_REQUEST_TIMEOUT: u64 = 60;
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum TestingError {
@@ -68,6 +75,15 @@ impl Error for TestingError {}
 //=================================================
 // RELACIONADAS A CREACION DE DATOS POR DEFECTO:
 
+fn create_default_request_settings() -> RequestSettings {
+    RequestSettings {
+        request_queue_depth: DEFAULT_REQUEST_QUEUE_DEPTH,
+        piece_picker: Arc::new(SequentialPicker),
+        peer_idle_timeout: Duration::from_secs(SECS_PEER_IDLE_TIMEOUT),
+        request_timeout: Duration::from_secs(SECS_REQUEST_TIMEOUT),
+    }
+}
+
 fn create_default_torrent_data(
     torrent_name: &str,
     peer_address: SocketAddr,
@@ -87,6 +103,8 @@ fn create_default_torrent_data(
     };
     let tracker_response = TrackerResponseData {
         interval: 0,
+        min_interval: None,
+        tracker_id: None,
         complete: 1,
         incomplete: 0,
         peers: vec![server_peer],
@@ -104,6 +122,8 @@ fn create_default_torrent_data(
                 was_requested: false,
             },
         ],
+        pieces_peer_count: vec![0; 2],
+        pieces_requesters_count: vec![0; 2],
     };
     let torrent_file = TorrentFileData {
         target_files_data: TargetFilesData::SingleFile {
@@ -151,6 +171,8 @@ fn create_default_client_peer_with_a_server_peer_that_has_the_whole_file(
     };
     let tracker_response = TrackerResponseData {
         interval: 0,
+        min_interval: None,
+        tracker_id: None,
         complete: 1,
         incomplete: 0,
         peers: vec![server_peer],
@@ -168,6 +190,8 @@ fn create_default_client_peer_with_a_server_peer_that_has_the_whole_file(
                 was_requested: false,
             },
         ],
+        pieces_peer_count: vec![0; 2],
+        pieces_requesters_count: vec![0; 2],
     };
     let torrent_file = TorrentFileData {
         target_files_data: TargetFilesData::SingleFile {
@@ -211,6 +235,7 @@ fn server_peer_interaction_mock_for_handshake(
     //ENVIO UN HANDSHAKE DE RESPUESTA
     let server_handshake = P2PMessage::Handshake {
         protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+        reserved: [0; 8],
         info_hash: DEFAULT_INFO_HASH.to_vec(),
         peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
     };
@@ -245,6 +270,7 @@ fn server_peer_interaction_mock_for_receiving_one_block(
         protocol_str,
         info_hash,
         peer_id: _,
+        ..
     } = received_message
     {
         assert_eq!(
@@ -256,6 +282,7 @@ fn server_peer_interaction_mock_for_receiving_one_block(
     //SERVER PEER ENVIA UN HANDSHAKE DE RESPUESTA
     let server_handshake = P2PMessage::Handshake {
         protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+        reserved: [0; 8],
         info_hash: DEFAULT_INFO_HASH.to_vec(),
         peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
     };
@@ -292,6 +319,12 @@ fn server_peer_interaction_mock_for_receiving_one_block(
         received_message
     );
 
+    //SERVER PEER RECIBE EL RESTO DE LOS REQUESTS ENCOLADOS
+    for _ in 0..3 {
+        let received_message = msg_receiver::receive_message(&mut server_stream)?;
+        assert!(matches!(received_message, P2PMessage::Request { .. }));
+    }
+
     //SERVER PEER ENVIA UN BLOQUE QUE CORRESPONDE A LA PIEZA ENTERA
     let server_msg = P2PMessage::Piece {
         piece_index: 0,
@@ -329,6 +362,7 @@ fn server_peer_interaction_mock_for_receiving_an_entire_file(
         protocol_str,
         info_hash,
         peer_id: _,
+        ..
     } = received_message
     {
         assert_eq!(
@@ -340,6 +374,7 @@ fn server_peer_interaction_mock_for_receiving_an_entire_file(
     //SERVER PEER ENVIA UN HANDSHAKE DE RESPUESTA
     let server_handshake = P2PMessage::Handshake {
         protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+        reserved: [0; 8],
         info_hash: DEFAULT_INFO_HASH.to_vec(),
         peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
     };
@@ -460,6 +495,98 @@ fn server_peer_interaction_mock_for_receiving_an_entire_file(
     Ok(())
 }
 
+fn server_peer_interaction_mock_for_receiving_blocks_out_of_order(
+    listener: TcpListener,
+) -> Result<(), Box<dyn Error>> {
+    let (mut server_stream, _addr) = listener.accept()?;
+
+    //SERVER PEER RECIBE UN HANDSHAKE
+    msg_receiver::receive_handshake(&mut server_stream)?;
+
+    //SERVER PEER ENVIA UN HANDSHAKE DE RESPUESTA
+    let server_handshake = P2PMessage::Handshake {
+        protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+        reserved: [0; 8],
+        info_hash: DEFAULT_INFO_HASH.to_vec(),
+        peer_id: DEFAULT_SERVER_PEER_ID.bytes().collect(),
+    };
+    let server_handshake_bytes = p2p::encoder::to_bytes(server_handshake)?;
+    server_stream.write_all(&server_handshake_bytes)?;
+
+    //SERVER PEER ENVIA UN BITFIELD
+    let server_msg = P2PMessage::Bitfield {
+        bitfield: vec![
+            PieceStatus::ValidAndAvailablePiece,
+            PieceStatus::ValidAndAvailablePiece,
+        ],
+    };
+    let server_msg_bytes = p2p::encoder::to_bytes(server_msg)?;
+    server_stream.write_all(&server_msg_bytes)?;
+
+    //SERVER PEER RECIVE UN INTERESTED
+    let received_message = msg_receiver::receive_message(&mut server_stream)?;
+    assert_eq!(P2PMessage::Interested, received_message);
+
+    //SERVER PEER ENVIA UN UNCHOKE
+    let server_msg = P2PMessage::Unchoke;
+    let server_msg_bytes = p2p::encoder::to_bytes(server_msg)?;
+    server_stream.write_all(&server_msg_bytes)?;
+
+    //SERVER PEER RECIBE TODOS LOS REQUESTS SIN HABER RESPONDIDO NINGUNO
+    let mut received_requests = vec![];
+    for _ in 0..4 {
+        received_requests.push(msg_receiver::receive_message(&mut server_stream)?);
+    }
+    assert_eq!(
+        vec![
+            P2PMessage::Request {
+                piece_index: 0,
+                beginning_byte_index: 0,
+                amount_of_bytes: BLOCK_BYTES
+            },
+            P2PMessage::Request {
+                piece_index: 0,
+                beginning_byte_index: BLOCK_BYTES,
+                amount_of_bytes: BLOCK_BYTES
+            },
+            P2PMessage::Request {
+                piece_index: 0,
+                beginning_byte_index: BLOCK_BYTES * 2,
+                amount_of_bytes: u32::try_from(DEFAULT_PIECE_LENGHT)? - 2 * BLOCK_BYTES
+            },
+            P2PMessage::Request {
+                piece_index: 1,
+                beginning_byte_index: 0,
+                amount_of_bytes: u32::try_from(DEFAULT_LAST_PIECE_LENGHT)?
+            },
+        ],
+        received_requests
+    );
+
+    //SERVER PEER RESPONDE LOS REQUESTS EN ORDEN INVERSO
+    for request in received_requests.into_iter().rev() {
+        if let P2PMessage::Request {
+            piece_index,
+            beginning_byte_index,
+            amount_of_bytes,
+        } = request
+        {
+            let server_msg = P2PMessage::Piece {
+                piece_index,
+                beginning_byte_index,
+                block: vec![10; usize::try_from(amount_of_bytes)?],
+            };
+            let server_msg_bytes = p2p::encoder::to_bytes(server_msg)?;
+            server_stream.write_all(&server_msg_bytes)?;
+        }
+    }
+
+    //SERVER PEER RECIBE UN NOT INTERESTED
+    let received_message = msg_receiver::receive_message(&mut server_stream)?;
+    assert_eq!(P2PMessage::NotInterested, received_message);
+    Ok(())
+}
+
 // //=============================================================================================
 // //=============================================================================================
 
@@ -502,6 +629,7 @@ fn client_peer_receives_a_handshake_ok() -> Result<(), Box<dyn Error>> {
         peer_id.clone(),
         logger_sender,
         ui_sender,
+        create_default_request_settings(),
     )?;
     // Obtengo el handshake que le habia llegado al server mock
     let handshake_received_by_server_peer = rx.recv()?;
@@ -510,6 +638,7 @@ fn client_peer_receives_a_handshake_ok() -> Result<(), Box<dyn Error>> {
     assert_eq!(
         P2PMessage::Handshake {
             protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+            reserved: [0, 0, 0, 0, 0, EXTENSION_PROTOCOL_BIT, 0, FAST_EXTENSION_BIT],
             info_hash: DEFAULT_INFO_HASH.to_vec(),
             peer_id: local_peer.get_peer_id(),
         },
@@ -545,8 +674,11 @@ fn client_peer_interact_with_a_peer_and_receives_one_block_ok() -> Result<(), Bo
             SocketAddr::from_str(&address)?,
         )?;
 
-    let path = torrent_file_data.get_torrent_representative_name();
-    let _dir_creation = fs::create_dir(format!("temp/{}", path));
+    let path = format!(
+        "temp/{}",
+        torrent_file_data.get_torrent_representative_name()
+    );
+    let _dir_creation = fs::create_dir(&path);
 
     // Channels auxiliares para comunicacion con ui y logger:
     let (logger_sender, _logger_receiver) = mpsc::channel();
@@ -567,11 +699,16 @@ fn client_peer_interact_with_a_peer_and_receives_one_block_ok() -> Result<(), Bo
         peer_id.clone(),
         logger_sender,
         ui_sender,
+        create_default_request_settings(),
     )?;
 
+    let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+    let choker = Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS)));
     let interaction_result = client_peer.interact_with_peer(
         &torrent_file_data,
         &torrent_status,
+        &target_files,
+        &choker,
         &global_shutdown,
         &local_shutdown,
     );
@@ -623,11 +760,11 @@ fn client_peer_interact_with_a_peer_and_receives_one_block_ok() -> Result<(), Bo
     assert_eq!(
         vec![
             PieceStatus::PartiallyDownloaded {
-                downloaded_bytes: BLOCK_BYTES,
+                downloaded_blocks: vec![true, false, false],
                 was_requested: true
             },
             PieceStatus::MissingPiece {
-                was_requested: false
+                was_requested: true
             }
         ],
         torrent_status
@@ -637,7 +774,7 @@ fn client_peer_interact_with_a_peer_and_receives_one_block_ok() -> Result<(), Bo
     );
 
     let _joined = handle.join();
-    let _result_of_removing = fs::remove_dir_all(format!("temp/{}", path));
+    let _result_of_removing = fs::remove_dir_all(&path);
 
     Ok(())
 }
@@ -655,9 +792,12 @@ fn client_peer_interact_with_a_peer_and_completes_the_whole_file_ok() -> Result<
             SocketAddr::from_str(&address)?,
         )?;
 
-    let path = torrent_file_data.get_torrent_representative_name();
-    let _result_of_removing = fs::remove_dir_all(format!("temp/{}", path));
-    fs::create_dir(format!("temp/{}", path))?;
+    let path = format!(
+        "temp/{}",
+        torrent_file_data.get_torrent_representative_name()
+    );
+    let _result_of_removing = fs::remove_dir_all(&path);
+    fs::create_dir(&path)?;
 
     // Channels auxiliares para comunicacion con ui y logger:
     let (logger_sender, _logger_receiver) = mpsc::channel();
@@ -677,11 +817,16 @@ fn client_peer_interact_with_a_peer_and_completes_the_whole_file_ok() -> Result<
         peer_id.clone(),
         logger_sender,
         ui_sender,
+        create_default_request_settings(),
     )?;
 
+    let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+    let choker = Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS)));
     let interaction_result = client_peer.interact_with_peer(
         &torrent_file_data,
         &torrent_status,
+        &target_files,
+        &choker,
         &_global_shutdown,
         &_local_shutdown,
     );
@@ -739,7 +884,91 @@ fn client_peer_interact_with_a_peer_and_completes_the_whole_file_ok() -> Result<
     );
 
     let _joined = handle.join();
-    let _result_of_removing = fs::remove_dir_all(format!("temp/{}", path));
+    let _result_of_removing = fs::remove_dir_all(&path);
+
+    Ok(())
+}
+
+#[test]
+fn client_peer_pipelines_requests_and_receives_blocks_out_of_order_ok() -> Result<(), Box<dyn Error>>
+{
+    //ABRO LA CONEXION
+    let (listener, address) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+
+    // CREO INFO NECESARIA PARA INICIAR COMUNICACION
+    let (tracker_response, torrent_status, torrent_file_data, global_shutdown, local_shutdown) =
+        create_default_client_peer_with_a_server_peer_that_has_the_whole_file(
+            "test_out_of_order_ok.txt",
+            SocketAddr::from_str(&address)?,
+        )?;
+
+    let path = format!(
+        "temp/{}",
+        torrent_file_data.get_torrent_representative_name()
+    );
+    let _result_of_removing = fs::remove_dir_all(&path);
+    fs::create_dir(&path)?;
+
+    // Channels auxiliares para comunicacion con ui y logger:
+    let (logger_sender, _logger_receiver) = mpsc::channel();
+    let (ui_sender, _ui_receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    //THREAD SECUNDARIO PARA EL SERVER
+    let handle = thread::spawn(move || {
+        server_peer_interaction_mock_for_receiving_blocks_out_of_order(listener)
+            .map_err(|err| err.to_string())
+    });
+
+    let mut client_peer = LocalPeerCommunicator::start_communication_as_client(
+        &torrent_file_data,
+        &tracker_response,
+        0,
+        generate_peer_id(),
+        logger_sender,
+        ui_sender,
+        create_default_request_settings(),
+    )?;
+
+    let target_files = TargetFiles::new(&torrent_file_data, &path)?;
+    let choker = Arc::new(RwLock::new(Choker::new(DEFAULT_UNCHOKE_SLOTS)));
+    let interaction_result = client_peer.interact_with_peer(
+        &torrent_file_data,
+        &torrent_status,
+        &target_files,
+        &choker,
+        &global_shutdown,
+        &local_shutdown,
+    );
+
+    //VERIFICACION
+    assert_eq!(
+        Ok(InteractionHandlerStatus::FinishInteraction),
+        interaction_result
+    );
+    assert!(client_peer.pending_requests.is_empty());
+    assert_eq!(
+        vec![
+            PieceStatus::ValidAndAvailablePiece,
+            PieceStatus::ValidAndAvailablePiece
+        ],
+        torrent_status
+            .read()
+            .map_err(|err| TestingError::ReadingTorrentStatus(format!("{:?}", err)))?
+            .pieces_availability
+    );
+    assert_eq!(
+        u64::try_from(DEFAULT_PIECE_LENGHT + DEFAULT_LAST_PIECE_LENGHT)?,
+        torrent_status
+            .read()
+            .map_err(|err| TestingError::ReadingTorrentStatus(format!("{:?}", err)))?
+            .downloaded
+    );
+
+    let server_result = handle
+        .join()
+        .map_err(|_| TestingError::ClientPeerFieldsInvalidAccess("Join error".to_string()))?;
+    assert_eq!(Ok(()), server_result);
+    let _result_of_removing = fs::remove_dir_all(&path);
 
     Ok(())
 }
@@ -38,7 +38,7 @@ use std::{
 };
 
 use log::{debug, error, info};
-use shared::{medatada_analyzer, port_binder::listener_binder::try_bind_listener};
+use shared::{medatada_analyzer, port_binder::listener_binder::try_bind_dual_stack_listener};
 use tracker::data::constants::JSON;
 
 use crate::tracker::{
@@ -204,7 +204,7 @@ pub fn run() -> ResultDyn<()> {
 
     let join_hander = init_handler_for_quit_input(Arc::clone(&global_shutdown));
 
-    let (listener, _) = try_bind_listener(7878, 7900)?;
+    let (listener, _) = try_bind_dual_stack_listener(7878, 7900)?;
     let _ = listener.set_nonblocking(true);
     info!("Listening...");
 
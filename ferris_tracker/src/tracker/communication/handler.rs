@@ -16,6 +16,7 @@ use crate::{
             constants::*,
             json::JsonHandler,
             peer_info::{get_error_response_for_announce, PeerInfo, PeerInfoError},
+            scrape_info,
             torrent_info::StatusPeer,
         },
         thread_pool::ThreadPool,
@@ -74,10 +75,8 @@ fn get_response_details(
             match dic_torrents.write() {
                 Ok(mut unlocked_dic) => match unlocked_dic.get_mut(&info_hash) {
                     Some(torrent) => {
-                        let response = torrent.get_bencoded_response_for_announce(
-                            info_of_announced_peer.get_peer_id(),
-                            info_of_announced_peer.is_compact(),
-                        );
+                        let response =
+                            torrent.get_bencoded_response_for_announce(&info_of_announced_peer);
                         match torrent
                             .add_peer(info_of_announced_peer.get_peer_id(), info_of_announced_peer)
                         {
@@ -110,6 +109,11 @@ fn get_response_details(
                                 };
                             }
                             StatusPeer::NoChanges => (),
+                            StatusPeer::KeyMismatch => {
+                                return get_error_response_for_announce(PeerInfoError::KeyInvalid)
+                                    .as_bytes()
+                                    .to_vec();
+                            }
                         };
                         response
                     }
@@ -126,6 +130,13 @@ fn get_response_details(
     }
 }
 
+fn get_response_for_scrape(buffer: &[u8], dic_torrents: &ArcMutexOfTorrents) -> Vec<u8> {
+    match dic_torrents.read() {
+        Ok(unlocked_dic) => scrape_info::get_bencoded_response_for_scrape(buffer, &unlocked_dic),
+        Err(_) => ERROR_500.as_bytes().to_vec(),
+    }
+}
+
 fn extract_last_contents_of_response(
     buffer: &[u8],
     dic_torrents: &ArcMutexOfTorrents,
@@ -160,6 +171,8 @@ fn extract_last_contents_of_response(
         }
     } else if buffer.starts_with(ANNOUNCE_URL) {
         get_response_details(buffer, dic_torrents, json, *ip_port)
+    } else if buffer.starts_with(SCRAPE_URL) {
+        get_response_for_scrape(buffer, dic_torrents)
     } else {
         status_line = String::from(ERR_URL);
         fs::read(ERROR_HTML).map_err(|err| {
@@ -2,6 +2,7 @@ use std::ops::RangeInclusive;
 
 pub const GET_URL: &[u8; 16] = b"GET / HTTP/1.1\r\n";
 pub const ANNOUNCE_URL: &[u8; 13] = b"GET /announce";
+pub const SCRAPE_URL: &[u8; 11] = b"GET /scrape";
 pub const CODE_URL: &[u8; 12] = b"GET /js/code";
 pub const STATS_URL: &[u8; 15] = b"GET /stats.html";
 pub const DOCS_URL: &[u8; 14] = b"GET /docs.html";
@@ -26,6 +27,12 @@ pub const LEFT: &str = "left=";
 pub const PORT: &str = "port=";
 pub const EVENT: &str = "event=";
 pub const COMPACT: &str = "compact=";
+pub const IP: &str = "ip=";
+pub const IPV6: &str = "ipv6=";
+pub const NUMWANT: &str = "numwant=";
+pub const NO_PEER_ID: &str = "no_peer_id=";
+pub const KEY: &str = "key=";
+pub const TRACKER_ID: &str = "trackerid=";
 
 pub const STARTED: &str = "started";
 pub const COMPLETED: &str = "completed";
@@ -35,14 +42,20 @@ pub const ZERO: u64 = 0;
 pub const FIRST_PORT: u64 = 6881;
 pub const LAST_PORT: u64 = 6889;
 pub const RANGE_PORT: RangeInclusive<u64> = FIRST_PORT..=LAST_PORT;
+pub const DEFAULT_NUMWANT: usize = 50;
+pub const MAX_NUMWANT: usize = 200;
 
 pub const COMPLETE_BYTES: &[u8] = b"complete";
 pub const INCOMPLETE_BYTES: &[u8] = b"incomplete";
 pub const INTERVAL_BYTES: &[u8] = b"interval";
+pub const DOWNLOADED_BYTES: &[u8] = b"downloaded";
+pub const FILES_BYTES: &[u8] = b"files";
 pub const PEERS_BYTES: &[u8] = b"peers";
-pub const PEER_ID_BYTES: &[u8] = b"peer_id";
+pub const PEERS6_BYTES: &[u8] = b"peers6";
+pub const PEER_ID_BYTES: &[u8] = b"peer id";
 pub const IP_BYTES: &[u8] = b"ip";
 pub const PORT_BYTES: &[u8] = b"port";
+pub const TRACKER_ID_BYTES: &[u8] = b"tracker id";
 
 pub const ERROR_INFO_HASH_NOT_FOUND: &str = "you sent me garbage - no info hash";
 pub const ERROR_INFO_HASH_INVALID: &str =
@@ -52,6 +65,8 @@ pub const ERROR_STAT_NOT_FOUND: &str =
     "you sent me garbage - invalid literal for long() with base 10: ''";
 pub const ERROR_STAT_INVALID: &str = "you sent me garbage - invalid amount";
 pub const ERROR_PORT_INVALID: &str = "you sent me garbage - invalid port";
+pub const ERROR_KEY_INVALID: &str =
+    "d14:failure reason51:Announce from another address with a different key.e";
 pub const ERROR_500: &str = "500 ERROR - Poissoned Lock";
 
 pub const TIMES: &str = "times";
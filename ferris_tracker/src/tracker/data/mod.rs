@@ -2,4 +2,5 @@ pub mod config_file_tracker;
 pub mod constants;
 pub mod json;
 pub mod peer_info;
+pub mod scrape_info;
 pub mod torrent_info;
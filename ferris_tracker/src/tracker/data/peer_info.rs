@@ -1,6 +1,13 @@
 use super::constants::*;
-use shared::parsers::urlencoding;
-use std::{error::Error, fmt, net::SocketAddr};
+use shared::{parsers::urlencoding, port_binder::listener_binder::canonical_address};
+use std::{
+    collections::hash_map::DefaultHasher,
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+};
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum Event {
@@ -18,6 +25,7 @@ pub enum PeerInfoError {
     PortInvalid,
     StatNotFound,
     StatInvalid,
+    KeyInvalid,
     PoissonedLock,
 }
 
@@ -43,10 +51,19 @@ pub struct PeerInfo {
     //OPCIONALES DE ANNOUNCE
     compact: Option<Vec<u8>>,
     event: Option<Event>,
+    ipv6_addr: Option<SocketAddr>,
+    numwant: Option<usize>,
+    no_peer_id: bool,
+    key: Option<Vec<u8>>,
+    tracker_id: Option<Vec<u8>>,
 }
 
+//Solo se tienen en cuenta los parametros que comienzan luego de un '?' o un '&', para que por
+//ejemplo "peer_id=" no coincida con el final de "no_peer_id=".
 fn find_index_msg(response: &[u8], size: usize, end_line: &[u8]) -> Option<usize> {
-    let first_pos = response.windows(size).position(|arr| arr == end_line);
+    let first_pos = response.windows(size).enumerate().position(|(pos, arr)| {
+        arr == end_line && pos > 0 && [b'?', b'&'].contains(&response[pos - 1])
+    });
     first_pos.map(|pos| pos + size)
 }
 
@@ -154,6 +171,54 @@ fn obtain_compact_from_querystring(announce: &[u8]) -> Option<Vec<u8>> {
     obtain_value_from_querystring(announce, COMPACT.len(), COMPACT)
 }
 
+//El parametro ip permite que el peer informe una direccion distinta a aquella desde la que se
+//conecta (por ejemplo, su IP publica). Si no es una direccion IP valida se ignora.
+fn obtain_ip_from_querystring(announce: &[u8]) -> Option<IpAddr> {
+    let ip_url = obtain_value_from_querystring(announce, IP.len(), IP)?;
+    let ip = String::from_utf8_lossy(&urlencoding::decoder::from_url(ip_url)).to_string();
+    IpAddr::from_str(&ip).ok().map(|ip| ip.to_canonical())
+}
+
+//El parametro ipv6 puede ser solo la direccion o tener la forma "[direccion]:puerto" (BEP 7).
+//Si no se indica un puerto se usa el del announce; si la direccion es invalida se ignora.
+fn obtain_ipv6_from_querystring(announce: &[u8], port: u64) -> Option<SocketAddr> {
+    let ipv6_url = obtain_value_from_querystring(announce, IPV6.len(), IPV6)?;
+    let ipv6 = String::from_utf8_lossy(&urlencoding::decoder::from_url(ipv6_url)).to_string();
+    match SocketAddr::from_str(&ipv6) {
+        Ok(sock_addr) if sock_addr.is_ipv6() => Some(sock_addr),
+        Ok(_) => None,
+        Err(_) => Ipv6Addr::from_str(&ipv6)
+            .ok()
+            .map(|ip| SocketAddr::new(ip.into(), port as u16)),
+    }
+}
+
+//Si el numwant es invalido se ignora, usando la cantidad por defecto.
+fn obtain_numwant_from_querystring(announce: &[u8]) -> Option<usize> {
+    let numwant = obtain_value_from_querystring(announce, NUMWANT.len(), NUMWANT)?;
+    String::from_utf8_lossy(&numwant).parse::<usize>().ok()
+}
+
+fn obtain_no_peer_id_from_querystring(announce: &[u8]) -> bool {
+    obtain_value_from_querystring(announce, NO_PEER_ID.len(), NO_PEER_ID)
+        .is_some_and(|no_peer_id| no_peer_id == b"1")
+}
+
+fn obtain_url_decoded_from_querystring(announce: &[u8], command: &str) -> Option<Vec<u8>> {
+    obtain_value_from_querystring(announce, command.len(), command)
+        .filter(|value| !value.is_empty())
+        .map(urlencoding::decoder::from_url)
+}
+
+//Si el peer no envia un tracker id, se le asigna uno derivado del torrent y de su peer_id, para
+//que siempre se le asigne el mismo.
+fn generate_tracker_id(info_hash: &[u8], peer_id: &[u8]) -> Vec<u8> {
+    let mut hasher = DefaultHasher::new();
+    info_hash.hash(&mut hasher);
+    peer_id.hash(&mut hasher);
+    format!("{:016x}", hasher.finish()).as_bytes().to_vec()
+}
+
 impl PeerInfo {
     pub fn get_info_hash(&self) -> Vec<u8> {
         self.info_hash.clone()
@@ -171,6 +236,18 @@ impl PeerInfo {
         self.sock_addr
     }
 
+    /// Devuelve todas las direcciones por las que se puede llegar al peer: aquella desde la que
+    /// se conecto y, si la informo en el announce, su direccion IPv6.
+    pub fn get_sock_addrs(&self) -> Vec<SocketAddr> {
+        let mut sock_addrs = vec![self.sock_addr];
+        if let Some(ipv6_addr) = self.ipv6_addr {
+            if ipv6_addr != self.sock_addr {
+                sock_addrs.push(ipv6_addr);
+            }
+        }
+        sock_addrs
+    }
+
     pub fn get_downloaded_uploaded(&self) -> (u64, u64) {
         (self.downloaded, self.uploaded)
     }
@@ -196,8 +273,44 @@ impl PeerInfo {
         }
     }
 
+    /// Devuelve la cantidad maxima de peers que se le deben informar al peer, segun el numwant
+    /// del announce (o la cantidad por defecto si no lo indico).
+    pub fn get_numwant(&self) -> usize {
+        self.numwant.unwrap_or(DEFAULT_NUMWANT).min(MAX_NUMWANT)
+    }
+
+    /// Indica si el peer pidio que no se le envien los peer_id en la respuesta no compacta
+    pub fn wants_no_peer_id(&self) -> bool {
+        self.no_peer_id
+    }
+
+    pub fn get_key(&self) -> Option<Vec<u8>> {
+        self.key.clone()
+    }
+
+    /// Devuelve el tracker id que se le informa al peer en la respuesta: el que envio en el
+    /// announce o, si no envio ninguno, el que le asigna el tracker.
+    pub fn get_tracker_id(&self) -> Vec<u8> {
+        match &self.tracker_id {
+            Some(tracker_id) => tracker_id.clone(),
+            None => generate_tracker_id(&self.info_hash, &self.peer_id),
+        }
+    }
+
+    /// Indica si este announce puede reemplazar al anterior del mismo peer. Si el peer se
+    /// anuncia desde otra direccion debe enviar la misma key que antes (si es que envio una),
+    /// para que nadie pueda hacerse pasar por el.
+    pub fn can_replace(&self, previous: &PeerInfo) -> bool {
+        match &previous.key {
+            Some(previous_key) if self.sock_addr.ip() != previous.sock_addr.ip() => {
+                self.key.as_ref() == Some(previous_key)
+            }
+            _ => true,
+        }
+    }
+
     pub fn new(announce: Vec<u8>, sock_addr: SocketAddr) -> Result<Self, PeerInfoError> {
-        let mut sock_addr = sock_addr;
+        let mut sock_addr = canonical_address(sock_addr);
         //Si uno de los campos obligatorios del Announce no existe devuelvo error
         let info_hash = match obtain_info_hash_from_querystring(&announce) {
             Ok(result) => result,
@@ -225,9 +338,17 @@ impl PeerInfo {
         };
         let compact = obtain_compact_from_querystring(&announce);
         let event = obtain_event_from_querystring(&announce);
+        let ipv6_addr = obtain_ipv6_from_querystring(&announce, port);
+        let numwant = obtain_numwant_from_querystring(&announce);
+        let no_peer_id = obtain_no_peer_id_from_querystring(&announce);
+        let key = obtain_url_decoded_from_querystring(&announce, KEY);
+        let tracker_id = obtain_url_decoded_from_querystring(&announce, TRACKER_ID);
 
-        //Cambio el puerto dado por el que me dieron en el announce
+        //Cambio el puerto dado por el que me dieron en el announce, y la ip si me dieron una
         sock_addr.set_port(port as u16);
+        if let Some(ip) = obtain_ip_from_querystring(&announce) {
+            sock_addr.set_ip(ip);
+        }
 
         Ok(PeerInfo {
             sock_addr,
@@ -239,6 +360,11 @@ impl PeerInfo {
             left,
             compact,
             event,
+            ipv6_addr,
+            numwant,
+            no_peer_id,
+            key,
+            tracker_id,
         })
     }
 }
@@ -252,6 +378,7 @@ pub fn get_error_response_for_announce(error: PeerInfoError) -> String {
         PeerInfoError::StatInvalid => ERROR_STAT_INVALID.to_owned(),
         PeerInfoError::PortNotFound => ERROR_STAT_NOT_FOUND.to_owned(),
         PeerInfoError::PortInvalid => ERROR_PORT_INVALID.to_owned(),
+        PeerInfoError::KeyInvalid => ERROR_KEY_INVALID.to_owned(),
         PeerInfoError::PoissonedLock => ERROR_500.to_owned(),
     }
 }
@@ -411,6 +538,11 @@ mod tests_peer_info {
                 left: expected_left,
                 compact: Some(expected_compact.clone()),
                 event: expected_event,
+                ipv6_addr: None,
+                numwant: None,
+                no_peer_id: false,
+                key: None,
+                tracker_id: None,
             })
         );
 
@@ -432,6 +564,11 @@ mod tests_peer_info {
                 left: expected_left,
                 compact: Some(expected_compact),
                 event: expected_event,
+                ipv6_addr: None,
+                numwant: None,
+                no_peer_id: false,
+                key: None,
+                tracker_id: None,
             })
         );
 
@@ -472,9 +609,135 @@ mod tests_peer_info {
                 left: expected_left,
                 compact: None,
                 event: None,
+                ipv6_addr: None,
+                numwant: None,
+                no_peer_id: false,
+                key: None,
+                tracker_id: None,
             })
         );
 
         Ok(())
     }
+
+    #[test]
+    fn obtaining_peer_info_with_ipv6_param_ok() -> ResultDyn<()> {
+        let initial_addr = SocketAddr::from_str("127.0.0.1:9999")?;
+        let announce_without_port = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&ipv6=2001%3Adb8%3A%3A1&port=6881&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+        let announce_with_port = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&ipv6=[2001:db8::1]:6999&port=6881&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+
+        let peer_info = PeerInfo::new(announce_without_port.as_bytes().to_vec(), initial_addr)?;
+        assert_eq!(
+            vec![
+                SocketAddr::from_str("127.0.0.1:6881")?,
+                SocketAddr::from_str("[2001:db8::1]:6881")?
+            ],
+            peer_info.get_sock_addrs()
+        );
+
+        let peer_info = PeerInfo::new(announce_with_port.as_bytes().to_vec(), initial_addr)?;
+        assert_eq!(
+            Some(SocketAddr::from_str("[2001:db8::1]:6999")?),
+            peer_info.ipv6_addr
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn obtaining_peer_info_with_announce_extensions_ok() -> ResultDyn<()> {
+        let initial_addr = SocketAddr::from_str("127.0.0.1:9999")?;
+        let announce = "GET /announce?info_hash=abcdefghijklmn123456&no_peer_id=1&peer_id=ABCDEFGHIJKLMNOPQRST&port=6881&uploaded=0&downloaded=0&left=128&compact=1&numwant=500&key=1A2B3C4D&trackerid=id%2F1 HTTP/1.0\r\n\r\n";
+
+        let peer_info = PeerInfo::new(announce.as_bytes().to_vec(), initial_addr)?;
+
+        assert_eq!(b"ABCDEFGHIJKLMNOPQRST".to_vec(), peer_info.get_peer_id());
+        assert!(peer_info.wants_no_peer_id());
+        assert_eq!(MAX_NUMWANT, peer_info.get_numwant());
+        assert_eq!(Some(b"1A2B3C4D".to_vec()), peer_info.get_key());
+        assert_eq!(b"id/1".to_vec(), peer_info.get_tracker_id());
+        Ok(())
+    }
+
+    #[test]
+    fn peer_info_without_tracker_id_is_assigned_a_stable_one_ok() -> ResultDyn<()> {
+        let announce = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&port=6881&uploaded=0&downloaded=0&left=128&numwant=x HTTP/1.0\r\n\r\n";
+
+        let peer_info = PeerInfo::new(
+            announce.as_bytes().to_vec(),
+            SocketAddr::from_str("127.0.0.1:9999")?,
+        )?;
+        let same_peer_info = PeerInfo::new(
+            announce.as_bytes().to_vec(),
+            SocketAddr::from_str("127.0.0.2:9999")?,
+        )?;
+
+        assert_eq!(DEFAULT_NUMWANT, peer_info.get_numwant());
+        assert!(!peer_info.wants_no_peer_id());
+        assert!(!peer_info.get_tracker_id().is_empty());
+        assert_eq!(peer_info.get_tracker_id(), same_peer_info.get_tracker_id());
+        Ok(())
+    }
+
+    #[test]
+    fn a_peer_that_changes_its_address_must_send_the_same_key() -> ResultDyn<()> {
+        let announce_with_key = |key: &str| {
+            format!("GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&port=6881&uploaded=0&downloaded=0&left=128&key={} HTTP/1.0\r\n\r\n", key).as_bytes().to_vec()
+        };
+        let previous = PeerInfo::new(
+            announce_with_key("AAAA"),
+            SocketAddr::from_str("127.0.0.1:9999")?,
+        )?;
+
+        let same_address = PeerInfo::new(
+            announce_with_key("BBBB"),
+            SocketAddr::from_str("127.0.0.1:9999")?,
+        )?;
+        let same_key = PeerInfo::new(
+            announce_with_key("AAAA"),
+            SocketAddr::from_str("10.0.0.1:9999")?,
+        )?;
+        let other_key = PeerInfo::new(
+            announce_with_key("BBBB"),
+            SocketAddr::from_str("10.0.0.1:9999")?,
+        )?;
+
+        assert!(same_address.can_replace(&previous));
+        assert!(same_key.can_replace(&previous));
+        assert!(!other_key.can_replace(&previous));
+        Ok(())
+    }
+
+    #[test]
+    fn the_ip_param_replaces_the_address_of_the_connection_ok() -> ResultDyn<()> {
+        let initial_addr = SocketAddr::from_str("127.0.0.1:9999")?;
+        let announce_with_ip = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&ip=203.0.113.7&port=6881&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+        let announce_with_hostname = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&ip=my.host&port=6881&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+
+        let peer_info = PeerInfo::new(announce_with_ip.as_bytes().to_vec(), initial_addr)?;
+        assert_eq!(
+            SocketAddr::from_str("203.0.113.7:6881")?,
+            peer_info.get_sock_addr()
+        );
+
+        let peer_info = PeerInfo::new(announce_with_hostname.as_bytes().to_vec(), initial_addr)?;
+        assert_eq!(
+            SocketAddr::from_str("127.0.0.1:6881")?,
+            peer_info.get_sock_addr()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_are_stored_as_ipv4_ok() -> ResultDyn<()> {
+        let initial_addr = SocketAddr::from_str("[::ffff:10.0.0.1]:9999")?;
+        let announce = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRST&port=6881&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+
+        let peer_info = PeerInfo::new(announce.as_bytes().to_vec(), initial_addr)?;
+
+        assert_eq!(
+            SocketAddr::from_str("10.0.0.1:6881")?,
+            peer_info.get_sock_addr()
+        );
+        Ok(())
+    }
 }
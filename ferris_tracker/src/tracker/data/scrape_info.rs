@@ -0,0 +1,168 @@
+use super::{constants::*, torrent_info::TorrentInfo};
+use shared::parsers::{
+    bencoding::{self, values::ValuesBencoding},
+    urlencoding,
+};
+use std::collections::HashMap;
+
+const INFO_HASH_LENGTH: usize = 20;
+
+//Me quedo solo con la querystring de la primera linea del request.
+//Ej: de "GET /scrape?info_hash=a&info_hash=b HTTP/1.1\r\n..." obtengo "info_hash=a&info_hash=b"
+fn obtain_querystring(scrape: &[u8]) -> &[u8] {
+    let request_line = scrape
+        .split(|&char| char == b' ')
+        .nth(1)
+        .unwrap_or_default();
+    match request_line.iter().position(|&char| char == b'?') {
+        Some(pos) => &request_line[pos + 1..],
+        None => &[],
+    }
+}
+
+/// Obtiene todos los info_hash (ya decodificados) enviados en un request de scrape. Se
+/// descartan los que no tengan el largo correcto.
+pub fn obtain_info_hashes_from_scrape(scrape: &[u8]) -> Vec<Vec<u8>> {
+    obtain_querystring(scrape)
+        .split(|&char| char == b'&')
+        .filter_map(|param| param.strip_prefix(INFO_HASH.as_bytes()))
+        .map(|info_hash_url| urlencoding::decoder::from_url(info_hash_url.to_vec()))
+        .filter(|info_hash| info_hash.len() == INFO_HASH_LENGTH)
+        .collect()
+}
+
+/// Devuelve la respuesta bencodeada a un scrape, con las estadisticas de cada torrent pedido
+/// dentro del diccionario `files`. Si no se pide ningun info_hash se devuelven las de todos los
+/// torrents, y los info_hash que el tracker no conoce se ignoran.
+pub fn get_bencoded_response_for_scrape(
+    scrape: &[u8],
+    torrents: &HashMap<Vec<u8>, TorrentInfo>,
+) -> Vec<u8> {
+    let info_hashes = obtain_info_hashes_from_scrape(scrape);
+    let requested_torrents: Vec<&TorrentInfo> = if info_hashes.is_empty() {
+        torrents.values().collect()
+    } else {
+        info_hashes
+            .iter()
+            .filter_map(|info_hash| torrents.get(info_hash))
+            .collect()
+    };
+
+    let mut dic_files: HashMap<Vec<u8>, ValuesBencoding> = HashMap::new();
+    for torrent in requested_torrents {
+        dic_files.insert(torrent.get_info_hash(), torrent.get_scrape_stats());
+    }
+    let mut dic_to_bencode: HashMap<Vec<u8>, ValuesBencoding> = HashMap::new();
+    dic_to_bencode.insert(FILES_BYTES.to_vec(), ValuesBencoding::Dic(dic_files));
+    bencoding::encoder::from_dic(dic_to_bencode)
+}
+
+#[cfg(test)]
+mod tests_scrape_info {
+    use super::*;
+    use crate::{tracker::data::peer_info::PeerInfo, ResultDyn};
+    use std::{net::SocketAddr, str::FromStr};
+
+    const INFO_HASH_A: &str = "abcdefghijklmn123456";
+    const INFO_HASH_B: &str = "abcdefghijklmn654321";
+
+    fn create_peer_info(info_hash: &str, peer_id: &str, left: &str) -> ResultDyn<PeerInfo> {
+        let initial_addr = SocketAddr::from_str("127.0.0.1:9999")?;
+        let get_announce_msg = format!("GET /announce?info_hash={}&peer_id={}&port=6881&uploaded=0&downloaded=0&left={}&event=started HTTP/1.0\r\nHost:torrent.ubuntu.com\r\n\r\n", info_hash, peer_id, left).as_bytes().to_vec();
+        Ok(PeerInfo::new(get_announce_msg, initial_addr)?)
+    }
+
+    fn create_torrents() -> ResultDyn<HashMap<Vec<u8>, TorrentInfo>> {
+        let mut torrent_a = TorrentInfo::new(INFO_HASH_A.as_bytes().to_vec());
+        let peer_0 = create_peer_info(INFO_HASH_A, "ABCDEFGHIJKLMNOPQRS0", "128")?;
+        torrent_a.add_peer(peer_0.get_peer_id(), peer_0);
+        let peer_1 = create_peer_info(INFO_HASH_A, "ABCDEFGHIJKLMNOPQRS1", "128")?;
+        torrent_a.add_peer(peer_1.get_peer_id(), peer_1);
+        let peer_1 = create_peer_info(INFO_HASH_A, "ABCDEFGHIJKLMNOPQRS1", "0")?;
+        torrent_a.add_peer(peer_1.get_peer_id(), peer_1);
+
+        let torrent_b = TorrentInfo::new(INFO_HASH_B.as_bytes().to_vec());
+
+        let mut torrents = HashMap::new();
+        torrents.insert(torrent_a.get_info_hash(), torrent_a);
+        torrents.insert(torrent_b.get_info_hash(), torrent_b);
+        Ok(torrents)
+    }
+
+    fn get_files_of_response(response: Vec<u8>) -> ResultDyn<HashMap<Vec<u8>, ValuesBencoding>> {
+        let dic_response = bencoding::decoder::to_dic(response)?.0;
+        match dic_response.get(FILES_BYTES) {
+            Some(ValuesBencoding::Dic(dic_files)) => Ok(dic_files.clone()),
+            _ => Err("The response has no files dictionary".into()),
+        }
+    }
+
+    #[test]
+    fn obtaining_multiple_info_hashes_ok() {
+        let scrape = format!(
+            "GET /scrape?info_hash={}&info_hash={}&info_hash=short HTTP/1.1\r\n\r\n",
+            INFO_HASH_A, INFO_HASH_B
+        );
+
+        assert_eq!(
+            vec![
+                INFO_HASH_A.as_bytes().to_vec(),
+                INFO_HASH_B.as_bytes().to_vec()
+            ],
+            obtain_info_hashes_from_scrape(scrape.as_bytes())
+        );
+    }
+
+    #[test]
+    fn scraping_one_torrent_returns_its_stats_ok() -> ResultDyn<()> {
+        let torrents = create_torrents()?;
+        let scrape = format!("GET /scrape?info_hash={} HTTP/1.1\r\n\r\n", INFO_HASH_A);
+
+        let dic_files = get_files_of_response(get_bencoded_response_for_scrape(
+            scrape.as_bytes(),
+            &torrents,
+        ))?;
+
+        let mut expected_stats = HashMap::new();
+        expected_stats.insert(COMPLETE_BYTES.to_vec(), ValuesBencoding::Integer(1));
+        expected_stats.insert(DOWNLOADED_BYTES.to_vec(), ValuesBencoding::Integer(1));
+        expected_stats.insert(INCOMPLETE_BYTES.to_vec(), ValuesBencoding::Integer(1));
+        assert_eq!(1, dic_files.len());
+        assert_eq!(
+            Some(&ValuesBencoding::Dic(expected_stats)),
+            dic_files.get(INFO_HASH_A.as_bytes())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn scraping_without_info_hash_returns_every_torrent_ok() -> ResultDyn<()> {
+        let torrents = create_torrents()?;
+
+        let dic_files = get_files_of_response(get_bencoded_response_for_scrape(
+            b"GET /scrape HTTP/1.1\r\n\r\n",
+            &torrents,
+        ))?;
+
+        assert_eq!(2, dic_files.len());
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_torrents_are_ignored_ok() -> ResultDyn<()> {
+        let torrents = create_torrents()?;
+        let scrape = format!(
+            "GET /scrape?info_hash=zzzzzzzzzzzzzzzzzzzz&info_hash={} HTTP/1.1\r\n\r\n",
+            INFO_HASH_B
+        );
+
+        let dic_files = get_files_of_response(get_bencoded_response_for_scrape(
+            scrape.as_bytes(),
+            &torrents,
+        ))?;
+
+        assert_eq!(1, dic_files.len());
+        assert!(dic_files.contains_key(INFO_HASH_B.as_bytes()));
+        Ok(())
+    }
+}
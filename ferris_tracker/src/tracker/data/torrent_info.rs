@@ -1,16 +1,29 @@
 use super::{constants::*, peer_info::PeerInfo};
 use shared::parsers::bencoding::{self, values::ValuesBencoding};
-use std::collections::HashMap;
+use std::{collections::HashMap, net::SocketAddr};
 
 pub enum StatusPeer {
     NewPeer,
     NoChanges,
     ChangeToCompleted,
+    KeyMismatch,
+}
+
+//Codifica la direccion en formato compacto: 4 bytes de IPv4 (o 16 de IPv6) seguidos de 2 bytes
+//del puerto, todo en big endian.
+fn encode_compact_peer(sock_addr: SocketAddr) -> Vec<u8> {
+    let mut compact_peer = match sock_addr {
+        SocketAddr::V4(addr) => addr.ip().octets().to_vec(),
+        SocketAddr::V6(addr) => addr.ip().octets().to_vec(),
+    };
+    compact_peer.extend(sock_addr.port().to_be_bytes());
+    compact_peer
 }
 
 pub struct TorrentInfo {
     info_hash: Vec<u8>,
     interval: i64,
+    times_completed: i64,
     peers: HashMap<Vec<u8>, PeerInfo>,
 }
 
@@ -22,6 +35,7 @@ impl TorrentInfo {
         TorrentInfo {
             info_hash,
             interval,
+            times_completed: 0,
             peers,
         }
     }
@@ -33,12 +47,18 @@ impl TorrentInfo {
     /// Agrega el peer al torrent y devuelve el StatusPeer, que nos informa si el peer es nuevo
     /// o si el peer es ya lo tenia, si ya lo tenia me informa si tuvo un cambio a completed
     ///  o si no tuvo ningun camnio importante.
+    /// Si el peer se anuncia desde otra direccion con una key distinta a la anterior, no se
+    /// agrega y se devuelve StatusPeer::KeyMismatch.
     pub fn add_peer(&mut self, peer_id: Vec<u8>, peer_info: PeerInfo) -> StatusPeer {
         let mut result = StatusPeer::NewPeer;
         if let Some(peer) = self.peers.get(&peer_id) {
+            if !peer_info.can_replace(peer) {
+                return StatusPeer::KeyMismatch;
+            }
             result = StatusPeer::NoChanges;
             if !peer.is_complete() && peer_info.is_complete() {
                 result = StatusPeer::ChangeToCompleted;
+                self.times_completed += 1;
             };
         }
         self.peers.insert(peer_id, peer_info);
@@ -58,12 +78,12 @@ impl TorrentInfo {
         (complete, incomplete)
     }
 
-    fn get_response_no_compact(&self, peer_id: Vec<u8>) -> Vec<u8> {
+    //Armo el diccionario con los datos comunes a ambos tipos de respuesta, incluyendo el
+    //tracker id que el peer debe enviar en sus proximos announces.
+    fn get_response_header(&self, announced_peer: &PeerInfo) -> HashMap<Vec<u8>, ValuesBencoding> {
         let (complete, incomplete) = self.get_number_of_complete_and_incomplete_peers();
 
         let mut dic_to_bencode: HashMap<Vec<u8>, ValuesBencoding> = HashMap::new();
-        let mut list_peers: Vec<ValuesBencoding> = vec![];
-
         dic_to_bencode.insert(COMPLETE_BYTES.to_vec(), ValuesBencoding::Integer(complete));
         dic_to_bencode.insert(
             INCOMPLETE_BYTES.to_vec(),
@@ -73,22 +93,37 @@ impl TorrentInfo {
             INTERVAL_BYTES.to_vec(),
             ValuesBencoding::Integer(self.interval),
         );
+        dic_to_bencode.insert(
+            TRACKER_ID_BYTES.to_vec(),
+            ValuesBencoding::String(announced_peer.get_tracker_id()),
+        );
+        dic_to_bencode
+    }
 
-        for key in self.peers.keys() {
-            if key.clone() == peer_id {
-                continue;
-            }
-            if let Some(peer_info) = self.peers.get(key) {
-                if peer_info.is_stopped() {
-                    continue;
-                }
-                let sock_addr = peer_info.get_sock_addr();
-                let peer_id = key.clone();
+    //Devuelvo los peers a informar: todos menos el que se anuncia y los que se detuvieron, hasta
+    //un maximo de numwant.
+    fn get_peers_for_response(&self, announced_peer: &PeerInfo) -> Vec<(&Vec<u8>, &PeerInfo)> {
+        let peer_id = announced_peer.get_peer_id();
+        self.peers
+            .iter()
+            .filter(|(key, peer_info)| **key != peer_id && !peer_info.is_stopped())
+            .take(announced_peer.get_numwant())
+            .collect()
+    }
+
+    fn get_response_no_compact(&self, announced_peer: &PeerInfo) -> Vec<u8> {
+        let mut dic_to_bencode = self.get_response_header(announced_peer);
+        let mut list_peers: Vec<ValuesBencoding> = vec![];
+
+        for (key, peer_info) in self.get_peers_for_response(announced_peer) {
+            for sock_addr in peer_info.get_sock_addrs() {
                 let ip = sock_addr.ip().to_string().as_bytes().to_vec();
                 let port = sock_addr.port() as i64;
 
                 let mut dic_peer = HashMap::new();
-                dic_peer.insert(PEER_ID_BYTES.to_vec(), ValuesBencoding::String(peer_id));
+                if !announced_peer.wants_no_peer_id() {
+                    dic_peer.insert(PEER_ID_BYTES.to_vec(), ValuesBencoding::String(key.clone()));
+                }
                 dic_peer.insert(IP_BYTES.to_vec(), ValuesBencoding::String(ip));
                 dic_peer.insert(PORT_BYTES.to_vec(), ValuesBencoding::Integer(port));
 
@@ -99,58 +134,54 @@ impl TorrentInfo {
         bencoding::encoder::from_dic(dic_to_bencode)
     }
 
-    fn get_response_compact(&self, peer_id: Vec<u8>) -> Vec<u8> {
-        let (complete, incomplete) = self.get_number_of_complete_and_incomplete_peers();
-
-        let mut dic_to_bencode: HashMap<Vec<u8>, ValuesBencoding> = HashMap::new();
+    fn get_response_compact(&self, announced_peer: &PeerInfo) -> Vec<u8> {
+        let mut dic_to_bencode = self.get_response_header(announced_peer);
         let mut vec_u8_peers = vec![];
+        let mut vec_u8_peers6 = vec![];
 
-        dic_to_bencode.insert(COMPLETE_BYTES.to_vec(), ValuesBencoding::Integer(complete));
-        dic_to_bencode.insert(
-            INCOMPLETE_BYTES.to_vec(),
-            ValuesBencoding::Integer(incomplete),
-        );
-        dic_to_bencode.insert(
-            INTERVAL_BYTES.to_vec(),
-            ValuesBencoding::Integer(self.interval),
-        );
-
-        for key in self.peers.keys() {
-            if key.clone() == peer_id {
-                continue;
-            }
-            if let Some(peer_info) = self.peers.get(key) {
-                if peer_info.is_stopped() {
-                    continue;
-                }
-                let sock_addr = peer_info.get_sock_addr();
-                for ip_num in sock_addr.ip().to_string().split('.') {
-                    if let Ok(ip_num) = ip_num.parse::<u8>() {
-                        vec_u8_peers.push(ip_num);
-                    };
-                }
-                if let Ok(port_num) = sock_addr.port().to_string().parse::<u16>() {
-                    let first_port = port_num / 256;
-                    let second_port = port_num % 256;
-                    vec_u8_peers.push(first_port as u8);
-                    vec_u8_peers.push(second_port as u8);
+        for (_, peer_info) in self.get_peers_for_response(announced_peer) {
+            for sock_addr in peer_info.get_sock_addrs() {
+                match sock_addr.is_ipv4() {
+                    true => vec_u8_peers.extend(encode_compact_peer(sock_addr)),
+                    false => vec_u8_peers6.extend(encode_compact_peer(sock_addr)),
                 }
             }
         }
         dic_to_bencode.insert(PEERS_BYTES.to_vec(), ValuesBencoding::String(vec_u8_peers));
+        if !vec_u8_peers6.is_empty() {
+            dic_to_bencode.insert(
+                PEERS6_BYTES.to_vec(),
+                ValuesBencoding::String(vec_u8_peers6),
+            );
+        }
         bencoding::encoder::from_dic(dic_to_bencode)
     }
 
-    //Devuelvo la respuesta en formato bencoding, pido la peer_id solicitante para no devolver la misma al
+    /// Devuelve las estadisticas del torrent para responder a un scrape: cantidad de peers
+    /// completos, incompletos y cantidad de veces que un peer completo la descarga.
+    pub fn get_scrape_stats(&self) -> ValuesBencoding {
+        let (complete, incomplete) = self.get_number_of_complete_and_incomplete_peers();
+
+        let mut dic_stats: HashMap<Vec<u8>, ValuesBencoding> = HashMap::new();
+        dic_stats.insert(COMPLETE_BYTES.to_vec(), ValuesBencoding::Integer(complete));
+        dic_stats.insert(
+            DOWNLOADED_BYTES.to_vec(),
+            ValuesBencoding::Integer(self.times_completed),
+        );
+        dic_stats.insert(
+            INCOMPLETE_BYTES.to_vec(),
+            ValuesBencoding::Integer(incomplete),
+        );
+        ValuesBencoding::Dic(dic_stats)
+    }
+
+    //Devuelvo la respuesta en formato bencoding, pido el peer solicitante para no devolverlo al
     //dar la respuesta ya que puede que no sea la primera vez que se comunique y este incluido entre los peers.
-    pub fn get_bencoded_response_for_announce(
-        &self,
-        peer_id: Vec<u8>,
-        is_compact: bool,
-    ) -> Vec<u8> {
-        match is_compact {
-            true => self.get_response_compact(peer_id),
-            false => self.get_response_no_compact(peer_id),
+    //Del mismo tambien se obtiene el formato de la respuesta y la cantidad de peers que quiere recibir.
+    pub fn get_bencoded_response_for_announce(&self, announced_peer: &PeerInfo) -> Vec<u8> {
+        match announced_peer.is_compact() {
+            true => self.get_response_compact(announced_peer),
+            false => self.get_response_no_compact(announced_peer),
         }
     }
 }
@@ -227,14 +258,21 @@ mod tests_torrent_info {
         Ok(torrent_info)
     }
 
+    fn create_announcing_peer(peer_id_str: &str, extra_params: &str) -> ResultDyn<PeerInfo> {
+        let announce = format!("GET /announce?info_hash=abcdefghijklmn123456&peer_id={}&port=6881&uploaded=0&downloaded=0&left=128{} HTTP/1.0\r\n\r\n", peer_id_str, extra_params);
+        Ok(PeerInfo::new(
+            announce.as_bytes().to_vec(),
+            SocketAddr::from_str("127.0.0.1:9999")?,
+        )?)
+    }
+
     #[test]
     fn getting_response_to_peer_that_requires_compact_ok() -> ResultDyn<()> {
         let torrent_info = create_default_torrent_info_with_multiple_peers_info()?;
-        let peer_id = "ABCDEFGHIJKLMNOPQRS1".as_bytes().to_vec();
-        let is_compact = true;
+        let announced_peer = create_announcing_peer("ABCDEFGHIJKLMNOPQRS1", "&compact=1")?;
 
         let decoded_result_dic = bencoding::decoder::to_dic(
-            torrent_info.get_bencoded_response_for_announce(peer_id, is_compact),
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
         )?
         .0;
 
@@ -251,11 +289,10 @@ mod tests_torrent_info {
     #[test]
     fn getting_response_to_peer_that_requires_no_compact_ok() -> ResultDyn<()> {
         let torrent_info = create_default_torrent_info_with_multiple_peers_info()?;
-        let peer_id = "ABCDEFGHIJKLMNOPQRS3".as_bytes().to_vec();
-        let is_compact = false;
+        let announced_peer = create_announcing_peer("ABCDEFGHIJKLMNOPQRS3", "&compact=0")?;
 
         let decoded_result_dic = bencoding::decoder::to_dic(
-            torrent_info.get_bencoded_response_for_announce(peer_id, is_compact),
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
         )?
         .0;
 
@@ -272,11 +309,11 @@ mod tests_torrent_info {
     #[test]
     fn getting_response_to_peer_that_didnt_specify_compact_ok() -> ResultDyn<()> {
         let torrent_info = create_default_torrent_info_with_multiple_peers_info()?;
-        let peer_id = "ABCDEFGHIJKLMNOPQRS3".as_bytes().to_vec();
-        let is_compact = false; // Si bien aca se asume, asi es como se comporta la funcion is_compact de los PeerInfo tmb
+        let announced_peer = create_announcing_peer("ABCDEFGHIJKLMNOPQRS3", "")?;
+        assert!(!announced_peer.is_compact());
 
         let decoded_result_dic = bencoding::decoder::to_dic(
-            torrent_info.get_bencoded_response_for_announce(peer_id, is_compact),
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
         )?
         .0;
 
@@ -289,4 +326,129 @@ mod tests_torrent_info {
 
         Ok(())
     }
+
+    #[test]
+    fn ipv6_peers_are_sent_in_peers6_when_compact_ok() -> ResultDyn<()> {
+        let announce_ipv4 = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRS0&ipv6=2001%3Adb8%3A%3A1&port=6881&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+        let announce_ipv6 = "GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRS1&port=6882&uploaded=0&downloaded=0&left=128 HTTP/1.0\r\n\r\n";
+        let peer_0 = PeerInfo::new(
+            announce_ipv4.as_bytes().to_vec(),
+            SocketAddr::from_str("10.0.0.1:9999")?,
+        )?;
+        let peer_1 = PeerInfo::new(
+            announce_ipv6.as_bytes().to_vec(),
+            SocketAddr::from_str("[2001:db8::2]:9999")?,
+        )?;
+        let mut torrent_info = TorrentInfo::new("abcdefghijklmn123456".as_bytes().to_vec());
+        torrent_info.add_peer(peer_0.get_peer_id(), peer_0);
+        torrent_info.add_peer(peer_1.get_peer_id(), peer_1);
+
+        let announced_peer = create_announcing_peer("ABCDEFGHIJKLMNOPQRS9", "&compact=1")?;
+
+        let decoded_result_dic = bencoding::decoder::to_dic(
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
+        )?
+        .0;
+
+        assert_eq!(
+            Some(&ValuesBencoding::String(vec![10, 0, 0, 1, 0x1a, 0xe1])),
+            decoded_result_dic.get(PEERS_BYTES)
+        );
+        match decoded_result_dic.get(PEERS6_BYTES) {
+            Some(ValuesBencoding::String(peers6)) => {
+                let mut expected_peer_0 = vec![0x20, 0x01, 0x0d, 0xb8];
+                expected_peer_0.extend([0; 11]);
+                expected_peer_0.extend([1, 0x1a, 0xe1]);
+                let mut expected_peer_1 = vec![0x20, 0x01, 0x0d, 0xb8];
+                expected_peer_1.extend([0; 11]);
+                expected_peer_1.extend([2, 0x1a, 0xe2]);
+
+                assert_eq!(36, peers6.len());
+                assert!(peers6.chunks(18).any(|peer| peer == expected_peer_0));
+                assert!(peers6.chunks(18).any(|peer| peer == expected_peer_1));
+                Ok(())
+            }
+            _ => Err("The response has no peers6".into()),
+        }
+    }
+
+    #[test]
+    fn the_amount_of_peers_is_limited_by_numwant_ok() -> ResultDyn<()> {
+        let torrent_info = create_default_torrent_info_with_multiple_peers_info()?;
+        let announced_peer =
+            create_announcing_peer("ABCDEFGHIJKLMNOPQRS9", "&compact=1&numwant=2")?;
+
+        let decoded_result_dic = bencoding::decoder::to_dic(
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
+        )?
+        .0;
+
+        match decoded_result_dic.get(PEERS_BYTES) {
+            Some(ValuesBencoding::String(peers)) => {
+                assert_eq!(2 * 6, peers.len());
+                Ok(())
+            }
+            _ => Err("The response has no compact peers".into()),
+        }
+    }
+
+    #[test]
+    fn peer_ids_are_omitted_when_no_peer_id_is_requested_ok() -> ResultDyn<()> {
+        let torrent_info = create_default_torrent_info_with_multiple_peers_info()?;
+        let announced_peer = create_announcing_peer("ABCDEFGHIJKLMNOPQRS9", "&no_peer_id=1")?;
+
+        let decoded_result_dic = bencoding::decoder::to_dic(
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
+        )?
+        .0;
+
+        match decoded_result_dic.get(PEERS_BYTES) {
+            Some(ValuesBencoding::List(peers)) => {
+                assert_eq!(3, peers.len());
+                for peer in peers {
+                    assert!(
+                        matches!(peer, ValuesBencoding::Dic(dic_peer) if !dic_peer.contains_key(PEER_ID_BYTES))
+                    );
+                }
+                Ok(())
+            }
+            _ => Err("The response has no list of peers".into()),
+        }
+    }
+
+    #[test]
+    fn the_tracker_id_sent_by_the_peer_is_returned_ok() -> ResultDyn<()> {
+        let torrent_info = create_default_torrent_info_with_multiple_peers_info()?;
+        let announced_peer =
+            create_announcing_peer("ABCDEFGHIJKLMNOPQRS9", "&compact=1&trackerid=abc123")?;
+
+        let decoded_result_dic = bencoding::decoder::to_dic(
+            torrent_info.get_bencoded_response_for_announce(&announced_peer),
+        )?
+        .0;
+
+        assert_eq!(
+            Some(&ValuesBencoding::String(b"abc123".to_vec())),
+            decoded_result_dic.get(TRACKER_ID_BYTES)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn an_announce_from_another_address_with_another_key_is_rejected() -> ResultDyn<()> {
+        let announce = |key: &str| {
+            format!("GET /announce?info_hash=abcdefghijklmn123456&peer_id=ABCDEFGHIJKLMNOPQRS0&port=6881&uploaded=0&downloaded=0&left=128&key={} HTTP/1.0\r\n\r\n", key).as_bytes().to_vec()
+        };
+        let mut torrent_info = TorrentInfo::new("abcdefghijklmn123456".as_bytes().to_vec());
+        let peer = PeerInfo::new(announce("AAAA"), SocketAddr::from_str("10.0.0.1:9999")?)?;
+        let spoofed_peer = PeerInfo::new(announce("BBBB"), SocketAddr::from_str("10.0.0.2:9999")?)?;
+
+        torrent_info.add_peer(peer.get_peer_id(), peer);
+
+        assert!(matches!(
+            torrent_info.add_peer(spoofed_peer.get_peer_id(), spoofed_peer),
+            StatusPeer::KeyMismatch
+        ));
+        Ok(())
+    }
 }
@@ -0,0 +1,296 @@
+//! # Modulo de cliente HTTP
+//! Este modulo contiene a HttpClient, encargado de conectarse a un servidor (opcionalmente a
+//! traves de TLS), enviar un request GET y leer su respuesta, siguiendo las redirecciones.
+
+use super::{
+    http_url::HttpUrl,
+    response::{reading_error, HttpResponse, LOCATION},
+};
+use log::debug;
+use std::{
+    error::Error,
+    fmt,
+    io::{BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+const USER_AGENT: &str = "FerrisTorrent";
+
+type ResultHttp<T> = Result<T, HttpClientError>;
+
+pub trait ReadAndWrite: Read + Write {}
+
+impl<T: Read + Write> ReadAndWrite for T {}
+
+/// Conector encargado de establecer una conexion TLS sobre una conexion TCP ya abierta. Es
+/// necesario para realizar requests a urls https.
+pub trait TlsConnect {
+    fn connect(&self, domain: &str, stream: TcpStream) -> Result<Box<dyn ReadAndWrite>, String>;
+}
+
+/// Representa un tipo de error al realizar un request HTTP
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum HttpClientError {
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    Connecting(String),
+    Tls(String),
+    Sending(String),
+    Receiving(String),
+    Timeout,
+    InvalidResponse(String),
+    UnsupportedEncoding(String),
+    ResponseTooLarge,
+    TooManyRedirects,
+}
+
+impl fmt::Display for HttpClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for HttpClientError {}
+
+/// Cliente HTTP/1.1 que realiza un request por conexion
+pub struct HttpClient {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    max_redirects: u32,
+    tls_connector: Option<Box<dyn TlsConnect>>,
+}
+
+impl HttpClient {
+    ///
+    /// Funcion que crea un cliente con los timeouts dados: `connect_timeout` para establecer la
+    /// conexion y `read_timeout` para cada lectura o escritura. Se siguen como maximo
+    /// `max_redirects` redirecciones por request.
+    ///
+    pub fn new(connect_timeout: Duration, read_timeout: Duration, max_redirects: u32) -> Self {
+        HttpClient {
+            connect_timeout,
+            read_timeout,
+            max_redirects,
+            tls_connector: None,
+        }
+    }
+
+    /// Funcion que indica el conector a usar para las urls https
+    ///
+    pub fn set_tls_connector(&mut self, tls_connector: Box<dyn TlsConnect>) {
+        self.tls_connector = Some(tls_connector);
+    }
+
+    //Pruebo conectarme a cada una de las direcciones del host hasta que alguna responda.
+    fn connect_tcp(&self, url: &HttpUrl) -> ResultHttp<TcpStream> {
+        let addresses = url
+            .get_address()
+            .to_socket_addrs()
+            .map_err(|err| HttpClientError::Connecting(format!("{}", err)))?;
+        let mut last_error = HttpClientError::Connecting(format!("Unresolved host {}", url.host));
+        for address in addresses {
+            debug!("Conectando TCP con addr: {}", address);
+            match TcpStream::connect_timeout(&address, self.connect_timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = HttpClientError::Connecting(format!("{}", err)),
+            }
+        }
+        Err(last_error)
+    }
+
+    fn connect(&self, url: &HttpUrl) -> ResultHttp<Box<dyn ReadAndWrite>> {
+        let stream = self.connect_tcp(url)?;
+        stream
+            .set_read_timeout(Some(self.read_timeout))
+            .and_then(|_| stream.set_write_timeout(Some(self.read_timeout)))
+            .map_err(|err| HttpClientError::Connecting(format!("{}", err)))?;
+        if !url.is_https {
+            return Ok(Box::new(stream));
+        }
+        let tls_connector = self
+            .tls_connector
+            .as_ref()
+            .ok_or_else(|| HttpClientError::Tls("There is no TLS connector".to_string()))?;
+        debug!("Conectando TLS con domain: {}", url.host);
+        tls_connector
+            .connect(&url.host, stream)
+            .map_err(HttpClientError::Tls)
+    }
+
+    fn send_get(&self, url: &HttpUrl) -> ResultHttp<HttpResponse> {
+        let mut stream = self.connect(url)?;
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nAccept-Encoding: identity\r\nConnection: close\r\n\r\n",
+            url.path,
+            url.get_host_header(),
+            USER_AGENT
+        );
+        debug!("Request: [{:?}]", request);
+        stream
+            .write_all(request.as_bytes())
+            .and_then(|_| stream.flush())
+            .map_err(|err| match reading_error(err) {
+                HttpClientError::Receiving(description) => HttpClientError::Sending(description),
+                error => error,
+            })?;
+        HttpResponse::read_from(&mut BufReader::new(stream))
+    }
+
+    ///
+    /// Funcion que realiza un request GET a la url dada y devuelve su respuesta, cualquiera sea
+    /// su codigo. Las redirecciones se siguen hasta llegar a una respuesta que no lo sea.
+    ///
+    pub fn get(&self, url: &str) -> ResultHttp<HttpResponse> {
+        let mut url = HttpUrl::parse(url)?;
+        for _ in 0..=self.max_redirects {
+            let response = self.send_get(&url)?;
+            if !response.is_redirect() {
+                return Ok(response);
+            }
+            let location = response.get_header(LOCATION).ok_or_else(|| {
+                HttpClientError::InvalidResponse("Redirect without location".to_string())
+            })?;
+            url = url.resolve(location)?;
+            debug!("Redireccionando a {}", url);
+        }
+        Err(HttpClientError::TooManyRedirects)
+    }
+}
+
+#[cfg(test)]
+mod test_client {
+    use super::*;
+    use std::{
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    const SECS_TEST_TIMEOUT: u64 = 2;
+
+    type FakeServer = (String, JoinHandle<Vec<String>>);
+
+    ///
+    /// Servidor HTTP de prueba: responde cada conexion con la siguiente de las respuestas dadas
+    /// y devuelve la primera linea de cada request recibido.
+    ///
+    fn spawn_fake_server(responses: Vec<&'static str>) -> Result<FakeServer, Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let handler = thread::spawn(move || {
+            let mut request_lines = vec![];
+            for response in responses {
+                let mut stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(_) => break,
+                };
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(size) => request.extend_from_slice(&buffer[..size]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).to_string();
+                request_lines.push(request.lines().next().unwrap_or_default().to_string());
+                let _ = stream.write_all(response.as_bytes());
+            }
+            request_lines
+        });
+        Ok((base_url, handler))
+    }
+
+    fn create_client() -> HttpClient {
+        HttpClient::new(
+            Duration::from_secs(SECS_TEST_TIMEOUT),
+            Duration::from_secs(SECS_TEST_TIMEOUT),
+            2,
+        )
+    }
+
+    #[test]
+    fn get_with_chunked_tracker_response_ok() -> Result<(), Box<dyn Error>> {
+        let (base_url, handler) = spawn_fake_server(vec![
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nd8:int\r\n9\r\nervali1ee\r\n0\r\n\r\n",
+        ])?;
+
+        let response = create_client().get(&format!("{}/announce?info_hash=abc", base_url))?;
+
+        assert_eq!(b"d8:intervali1ee".to_vec(), response.body);
+        assert_eq!(
+            vec!["GET /announce?info_hash=abc HTTP/1.1".to_string()],
+            handler.join().map_err(|_| "The fake server panicked")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn redirects_are_followed_ok() -> Result<(), Box<dyn Error>> {
+        let (base_url, handler) = spawn_fake_server(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /new/announce?a=1\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+        ])?;
+
+        let response = create_client().get(&format!("{}/announce", base_url))?;
+
+        assert_eq!(b"ok".to_vec(), response.body);
+        assert_eq!(
+            vec![
+                "GET /announce HTTP/1.1".to_string(),
+                "GET /new/announce?a=1 HTTP/1.1".to_string()
+            ],
+            handler.join().map_err(|_| "The fake server panicked")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn too_many_redirects_error() -> Result<(), Box<dyn Error>> {
+        let redirect = "HTTP/1.1 301 Moved\r\nLocation: /announce\r\nContent-Length: 0\r\n\r\n";
+        let (base_url, _handler) = spawn_fake_server(vec![redirect, redirect, redirect])?;
+
+        let response = create_client().get(&format!("{}/announce", base_url));
+
+        assert_eq!(Err(HttpClientError::TooManyRedirects), response);
+        Ok(())
+    }
+
+    #[test]
+    fn error_codes_are_returned_as_responses_ok() -> Result<(), Box<dyn Error>> {
+        let (base_url, _handler) =
+            spawn_fake_server(vec!["HTTP/1.0 404 Not Found\r\n\r\nnot found"])?;
+
+        let response = create_client().get(&format!("{}/announce", base_url))?;
+
+        assert_eq!(404, response.status_code);
+        assert_eq!(b"not found".to_vec(), response.body);
+        Ok(())
+    }
+
+    #[test]
+    fn a_server_that_does_not_answer_times_out_error() -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/announce", listener.local_addr()?);
+        let client = HttpClient::new(
+            Duration::from_secs(SECS_TEST_TIMEOUT),
+            Duration::from_millis(200),
+            0,
+        );
+
+        assert_eq!(Err(HttpClientError::Timeout), client.get(&url));
+        Ok(())
+    }
+
+    #[test]
+    fn https_without_tls_connector_error() -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("https://{}/announce", listener.local_addr()?);
+
+        assert!(matches!(
+            create_client().get(&url),
+            Err(HttpClientError::Tls(_))
+        ));
+        Ok(())
+    }
+}
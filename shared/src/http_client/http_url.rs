@@ -0,0 +1,254 @@
+//! # Modulo de URLs HTTP
+//! Este modulo contiene a HttpUrl, que separa una url http o https en las partes necesarias
+//! para conectarse al servidor y armar el request.
+
+use super::client::HttpClientError;
+use std::fmt;
+
+pub const HTTP_SCHEME: &str = "http";
+pub const HTTPS_SCHEME: &str = "https";
+pub const HTTP_DEFAULT_PORT: u16 = 80;
+pub const HTTPS_DEFAULT_PORT: u16 = 443;
+
+const SCHEME_SEPARATOR: &str = "://";
+
+/// Url http o https separada en sus partes
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HttpUrl {
+    pub is_https: bool,
+    pub host: String,
+    pub port: u16,
+    /// Path junto con el querystring (si lo hay), siempre comenzando con "/"
+    pub path: String,
+}
+
+//Separo el host del puerto, teniendo en cuenta que los hosts IPv6 vienen entre corchetes.
+//Ej: de "[::1]:8080" obtengo ("::1", 8080) y de "tracker.com" obtengo ("tracker.com", default_port)
+fn parse_authority(
+    url: &str,
+    authority: &str,
+    default_port: u16,
+) -> Result<(String, u16), HttpClientError> {
+    let invalid_url = || HttpClientError::InvalidUrl(url.to_string());
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest.split_once(']').ok_or_else(invalid_url)?;
+            match rest {
+                "" => (host, None),
+                _ => (host, Some(rest.strip_prefix(':').ok_or_else(invalid_url)?)),
+            }
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| invalid_url())?,
+        None => default_port,
+    };
+    if host.is_empty() {
+        return Err(invalid_url());
+    }
+    Ok((host.to_string(), port))
+}
+
+impl HttpUrl {
+    ///
+    /// Funcion que separa la url dada en sus partes. Si no se indica un puerto se usa el
+    /// correspondiente al esquema, y si no se indica un path se usa "/".
+    ///
+    pub fn parse(url: &str) -> Result<Self, HttpClientError> {
+        let (scheme, rest) = url
+            .split_once(SCHEME_SEPARATOR)
+            .ok_or_else(|| HttpClientError::InvalidUrl(url.to_string()))?;
+        let (is_https, default_port) = match scheme.to_ascii_lowercase().as_str() {
+            HTTP_SCHEME => (false, HTTP_DEFAULT_PORT),
+            HTTPS_SCHEME => (true, HTTPS_DEFAULT_PORT),
+            _ => return Err(HttpClientError::UnsupportedScheme(scheme.to_string())),
+        };
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, ""),
+        };
+        let (host, port) = parse_authority(url, authority, default_port)?;
+        let path = match path.starts_with('/') {
+            true => path.to_string(),
+            false => format!("/{}", path),
+        };
+
+        Ok(HttpUrl {
+            is_https,
+            host,
+            port,
+            path,
+        })
+    }
+
+    ///
+    /// Funcion que obtiene la url a la que apunta una redireccion (header `Location`) recibida
+    /// al pedir esta url. La nueva ubicacion puede ser absoluta o relativa a esta url.
+    ///
+    pub fn resolve(&self, location: &str) -> Result<Self, HttpClientError> {
+        let scheme = match self.is_https {
+            true => HTTPS_SCHEME,
+            false => HTTP_SCHEME,
+        };
+        if location.contains(SCHEME_SEPARATOR) {
+            HttpUrl::parse(location)
+        } else if location.starts_with("//") {
+            HttpUrl::parse(&format!("{}:{}", scheme, location))
+        } else if location.starts_with('/') {
+            HttpUrl::parse(&format!(
+                "{}://{}{}",
+                scheme,
+                self.get_host_header(),
+                location
+            ))
+        } else {
+            let path_without_query = self.path.split('?').next().unwrap_or_default();
+            let directory = match path_without_query.rsplit_once('/') {
+                Some((directory, _)) => directory,
+                None => "",
+            };
+            HttpUrl::parse(&format!(
+                "{}://{}{}/{}",
+                scheme,
+                self.get_host_header(),
+                directory,
+                location
+            ))
+        }
+    }
+
+    fn get_bracketed_host(&self) -> String {
+        match self.host.contains(':') {
+            true => format!("[{}]", self.host),
+            false => self.host.clone(),
+        }
+    }
+
+    /// Devuelve el valor del header `Host` del request: el host, y el puerto solo si no es
+    /// el correspondiente al esquema.
+    pub fn get_host_header(&self) -> String {
+        let default_port = match self.is_https {
+            true => HTTPS_DEFAULT_PORT,
+            false => HTTP_DEFAULT_PORT,
+        };
+        match self.port == default_port {
+            true => self.get_bracketed_host(),
+            false => format!("{}:{}", self.get_bracketed_host(), self.port),
+        }
+    }
+
+    /// Devuelve la direccion (host:puerto) a la cual conectarse
+    pub fn get_address(&self) -> String {
+        format!("{}:{}", self.get_bracketed_host(), self.port)
+    }
+}
+
+impl fmt::Display for HttpUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scheme = match self.is_https {
+            true => HTTPS_SCHEME,
+            false => HTTP_SCHEME,
+        };
+        write!(f, "{}://{}{}", scheme, self.get_host_header(), self.path)
+    }
+}
+
+#[cfg(test)]
+mod test_http_url {
+    use super::*;
+
+    mod test_parse {
+        use super::*;
+
+        #[test]
+        fn url_with_port_and_query_ok() {
+            assert_eq!(
+                Ok(HttpUrl {
+                    is_https: false,
+                    host: "tracker.com".to_string(),
+                    port: 6969,
+                    path: "/announce?passkey=abc".to_string(),
+                }),
+                HttpUrl::parse("http://tracker.com:6969/announce?passkey=abc")
+            );
+        }
+
+        #[test]
+        fn default_port_and_path_are_used_ok() {
+            assert_eq!(
+                Ok(HttpUrl {
+                    is_https: true,
+                    host: "tracker.com".to_string(),
+                    port: 443,
+                    path: "/".to_string(),
+                }),
+                HttpUrl::parse("https://tracker.com")
+            );
+        }
+
+        #[test]
+        fn ipv6_host_ok() {
+            let url = HttpUrl::parse("http://[::1]:8080/announce");
+
+            assert_eq!(
+                Ok(HttpUrl {
+                    is_https: false,
+                    host: "::1".to_string(),
+                    port: 8080,
+                    path: "/announce".to_string(),
+                }),
+                url
+            );
+            assert_eq!(
+                Ok("[::1]:8080".to_string()),
+                url.map(|url| url.get_address())
+            );
+        }
+
+        #[test]
+        fn invalid_urls_are_rejected_error() {
+            assert_eq!(
+                Err(HttpClientError::UnsupportedScheme("udp".to_string())),
+                HttpUrl::parse("udp://tracker.com:80/announce")
+            );
+            assert!(HttpUrl::parse("tracker.com/announce").is_err());
+            assert!(HttpUrl::parse("http://tracker.com:port/announce").is_err());
+            assert!(HttpUrl::parse("http://:80/announce").is_err());
+        }
+    }
+
+    mod test_resolve {
+        use super::*;
+
+        #[test]
+        fn absolute_locations_ok() -> Result<(), HttpClientError> {
+            let url = HttpUrl::parse("http://tracker.com:6969/announce")?;
+
+            assert_eq!(
+                "https://other.com/announce",
+                url.resolve("https://other.com/announce")?.to_string()
+            );
+            assert_eq!(
+                "http://tracker.com:6969/new/announce?a=1",
+                url.resolve("/new/announce?a=1")?.to_string()
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn relative_locations_ok() -> Result<(), HttpClientError> {
+            let url = HttpUrl::parse("http://tracker.com/path/announce?info_hash=x")?;
+
+            assert_eq!(
+                "http://tracker.com/path/announce.php",
+                url.resolve("announce.php")?.to_string()
+            );
+            Ok(())
+        }
+    }
+}
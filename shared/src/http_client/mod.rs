@@ -0,0 +1,8 @@
+//! # Modulo de cliente HTTP
+//! Este modulo contiene un cliente HTTP/1.1 sencillo para realizar requests GET (por ejemplo a un
+//! tracker), con timeouts de conexion y lectura, seguimiento de redirecciones y soporte de
+//! respuestas con `Content-Length` o `Transfer-Encoding: chunked`.
+
+pub mod client;
+pub mod http_url;
+pub mod response;
@@ -0,0 +1,267 @@
+//! # Modulo de respuestas HTTP
+//! Este modulo contiene a HttpResponse y las funciones encargadas de leerla desde una conexion,
+//! ya sea con su largo indicado por `Content-Length`, con el body dividido en chunks
+//! (`Transfer-Encoding: chunked`) o leyendo hasta que se cierre la conexion.
+
+use super::client::HttpClientError;
+use std::io::{self, BufRead, ErrorKind, Read};
+
+/// Tamaño maximo aceptado para el body de una respuesta
+pub const MAX_BODY_LENGTH: u64 = 10 * 1024 * 1024;
+
+pub const CONTENT_LENGTH: &str = "content-length";
+pub const TRANSFER_ENCODING: &str = "transfer-encoding";
+pub const CONTENT_ENCODING: &str = "content-encoding";
+pub const LOCATION: &str = "location";
+
+const HTTP_VERSION_PREFIX: &str = "HTTP/1.";
+const CHUNKED: &str = "chunked";
+const IDENTITY: &str = "identity";
+const REDIRECT_CODES: [u16; 5] = [301, 302, 303, 307, 308];
+
+type ResultHttp<T> = Result<T, HttpClientError>;
+
+/// Respuesta a un request HTTP. Los nombres de los headers se guardan en minuscula.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Convierte un error de lectura de la conexion, distinguiendo los que son por timeout.
+pub fn reading_error(error: io::Error) -> HttpClientError {
+    match error.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => HttpClientError::Timeout,
+        _ => HttpClientError::Receiving(format!("{}", error)),
+    }
+}
+
+fn invalid_response(description: &str) -> HttpClientError {
+    HttpClientError::InvalidResponse(description.to_string())
+}
+
+//Leo una linea terminada en "\r\n" (o solo "\n") y la devuelvo sin el fin de linea.
+fn read_line<R: BufRead>(reader: &mut R) -> ResultHttp<String> {
+    let mut line = vec![];
+    reader
+        .by_ref()
+        .take(MAX_BODY_LENGTH)
+        .read_until(b'\n', &mut line)
+        .map_err(reading_error)?;
+    if line.pop() != Some(b'\n') {
+        return Err(invalid_response("Connection closed before the end of line"));
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(String::from_utf8_lossy(&line).to_string())
+}
+
+//Ej: de "HTTP/1.1 404 Not Found" obtengo (404, "Not Found")
+fn parse_status_line(status_line: &str) -> ResultHttp<(u16, String)> {
+    let mut parts = status_line.splitn(3, ' ');
+    match parts.next() {
+        Some(version) if version.starts_with(HTTP_VERSION_PREFIX) => (),
+        _ => return Err(invalid_response("Invalid status line")),
+    }
+    let status_code = parts
+        .next()
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| invalid_response("Invalid status code"))?;
+    let reason = parts.next().unwrap_or_default().to_string();
+    Ok((status_code, reason))
+}
+
+fn read_headers<R: BufRead>(reader: &mut R) -> ResultHttp<Vec<(String, String)>> {
+    let mut headers = vec![];
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid_response("Invalid header"))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+}
+
+fn read_exact_body<R: BufRead>(reader: &mut R, length: u64) -> ResultHttp<Vec<u8>> {
+    if length > MAX_BODY_LENGTH {
+        return Err(HttpClientError::ResponseTooLarge);
+    }
+    let mut body = vec![0; length as usize];
+    reader.read_exact(&mut body).map_err(reading_error)?;
+    Ok(body)
+}
+
+fn read_body_to_end<R: BufRead>(reader: &mut R) -> ResultHttp<Vec<u8>> {
+    let mut body = vec![];
+    reader
+        .by_ref()
+        .take(MAX_BODY_LENGTH + 1)
+        .read_to_end(&mut body)
+        .map_err(reading_error)?;
+    if body.len() as u64 > MAX_BODY_LENGTH {
+        return Err(HttpClientError::ResponseTooLarge);
+    }
+    Ok(body)
+}
+
+//Cada chunk es su largo en hexadecimal (con posibles extensiones luego de un ";"), un fin de
+//linea, los datos y otro fin de linea. El ultimo chunk tiene largo 0 y puede estar seguido
+//de headers (trailers) que se descartan.
+fn read_chunked_body<R: BufRead>(reader: &mut R) -> ResultHttp<Vec<u8>> {
+    let mut body = vec![];
+    loop {
+        let size_line = read_line(reader)?;
+        let size = size_line.split(';').next().unwrap_or_default().trim();
+        let size =
+            u64::from_str_radix(size, 16).map_err(|_| invalid_response("Invalid chunk size"))?;
+        if size == 0 {
+            read_headers(reader)?;
+            return Ok(body);
+        }
+        if body.len() as u64 + size > MAX_BODY_LENGTH {
+            return Err(HttpClientError::ResponseTooLarge);
+        }
+        body.extend(read_exact_body(reader, size)?);
+        if !read_line(reader)?.is_empty() {
+            return Err(invalid_response("Chunk longer than its size"));
+        }
+    }
+}
+
+impl HttpResponse {
+    ///
+    /// Funcion que lee una respuesta completa desde la conexion dada. Solo se aceptan bodies
+    /// sin codificar, ya que en los requests se pide `Accept-Encoding: identity`.
+    ///
+    pub fn read_from<R: BufRead>(reader: &mut R) -> ResultHttp<Self> {
+        let (status_code, reason) = parse_status_line(&read_line(reader)?)?;
+        let mut response = HttpResponse {
+            status_code,
+            reason,
+            headers: read_headers(reader)?,
+            body: vec![],
+        };
+
+        if let Some(encoding) = response.get_header(CONTENT_ENCODING) {
+            if !encoding.eq_ignore_ascii_case(IDENTITY) {
+                return Err(HttpClientError::UnsupportedEncoding(encoding.to_string()));
+            }
+        }
+        let is_chunked = response
+            .get_header(TRANSFER_ENCODING)
+            .is_some_and(|encoding| encoding.to_ascii_lowercase().contains(CHUNKED));
+        let content_length = match response.get_header(CONTENT_LENGTH) {
+            Some(length) => Some(
+                length
+                    .parse::<u64>()
+                    .map_err(|_| invalid_response("Invalid content length"))?,
+            ),
+            None => None,
+        };
+
+        response.body = if is_chunked {
+            read_chunked_body(reader)?
+        } else if let Some(length) = content_length {
+            read_exact_body(reader, length)?
+        } else if response.has_no_body() {
+            vec![]
+        } else {
+            read_body_to_end(reader)?
+        };
+        Ok(response)
+    }
+
+    /// Devuelve el valor del header con el nombre dado (sin distinguir mayusculas)
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Indica si el codigo de la respuesta es de exito (2xx)
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    /// Indica si la respuesta es una redireccion a otra url
+    pub fn is_redirect(&self) -> bool {
+        REDIRECT_CODES.contains(&self.status_code)
+    }
+
+    fn has_no_body(&self) -> bool {
+        (100..200).contains(&self.status_code) || [204, 304].contains(&self.status_code)
+    }
+}
+
+#[cfg(test)]
+mod test_response {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read_response(raw_response: &[u8]) -> ResultHttp<HttpResponse> {
+        HttpResponse::read_from(&mut Cursor::new(raw_response.to_vec()))
+    }
+
+    #[test]
+    fn response_with_content_length_ok() -> ResultHttp<()> {
+        let response =
+            read_response(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nX-Other: a:b\r\n\r\nhello")?;
+
+        assert_eq!(200, response.status_code);
+        assert_eq!("OK", response.reason);
+        assert_eq!(Some("a:b"), response.get_header("x-other"));
+        assert_eq!(b"hello".to_vec(), response.body);
+        Ok(())
+    }
+
+    #[test]
+    fn chunked_response_ok() -> ResultHttp<()> {
+        let response = read_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4;ext=1\r\nd8:i\r\nB\r\nntervali1ee\r\n0\r\nTrailer: x\r\n\r\n",
+        )?;
+
+        assert_eq!(b"d8:intervali1ee".to_vec(), response.body);
+        Ok(())
+    }
+
+    #[test]
+    fn http_1_0_response_without_length_is_read_to_end_ok() -> ResultHttp<()> {
+        let response = read_response(b"HTTP/1.0 404 Not Found\n\nnot here")?;
+
+        assert_eq!(404, response.status_code);
+        assert!(!response.is_success());
+        assert_eq!(b"not here".to_vec(), response.body);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_responses_error() {
+        assert!(matches!(
+            read_response(b"ICY 200 OK\r\n\r\n"),
+            Err(HttpClientError::InvalidResponse(_))
+        ));
+        assert!(matches!(
+            read_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n"),
+            Err(HttpClientError::InvalidResponse(_))
+        ));
+        assert_eq!(
+            Err(HttpClientError::UnsupportedEncoding("gzip".to_string())),
+            read_response(b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\n\r\n")
+        );
+    }
+
+    #[test]
+    fn truncated_body_error() {
+        assert!(matches!(
+            read_response(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort"),
+            Err(HttpClientError::Receiving(_))
+        ));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod http_client;
+pub mod magnet_link;
 pub mod medatada_analyzer;
 pub mod parsers;
 pub mod port_binder;
@@ -0,0 +1,339 @@
+//! # Modulo de magnet links
+//! Este modulo contiene a MagnetLink, que interpreta una URI `magnet:?xt=urn:btih:...` y permite,
+//! una vez obtenido el diccionario info desde los peers (BEP 9), verificarlo y crear el
+//! [TorrentFileData] correspondiente.
+
+use crate::{
+    parsers::{
+        bencoding::{self, values::ValuesBencoding},
+        urlencoding,
+    },
+    torrent_file_data::{TargetFilesData, TorrentFileData, TorrentFileDataError},
+};
+use sha1::{Digest, Sha1};
+use std::{collections::HashMap, error::Error, fmt};
+
+const MAGNET_PREFIX: &str = "magnet:?";
+const EXACT_TOPIC: &str = "xt";
+const DISPLAY_NAME: &str = "dn";
+const TRACKER: &str = "tr";
+const BTIH_PREFIX: &str = "urn:btih:";
+
+const ANNOUNCE: &str = "announce";
+const ANNOUNCE_LIST: &str = "announce-list";
+const INFO: &str = "info";
+
+const INFO_HASH_LENGTH: usize = 20;
+const HEX_INFO_HASH_LENGTH: usize = 40;
+const BASE32_INFO_HASH_LENGTH: usize = 32;
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const UNNAMED_MAGNET: &str = "unnamed_magnet";
+
+#[derive(PartialEq, Eq, Debug)]
+/// Representa un tipo de error al interpretar un magnet link o su metadata
+pub enum MagnetError {
+    IsNotMagnet(String),
+    MissingInfoHash,
+    InvalidInfoHash(String),
+    InfoHashMismatch,
+    TransferToDic(String),
+    CreatingTorrentFileData(TorrentFileDataError),
+}
+
+impl fmt::Display for MagnetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for MagnetError {}
+
+/// Informacion contenida en un magnet link
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MagnetLink {
+    pub info_hash: Vec<u8>,
+    pub display_name: Option<String>,
+    pub trackers: Vec<String>,
+}
+
+fn decode_hex_info_hash(hash: &str) -> Option<Vec<u8>> {
+    (0..HEX_INFO_HASH_LENGTH)
+        .step_by(2)
+        .map(|pos| u8::from_str_radix(hash.get(pos..pos + 2)?, 16).ok())
+        .collect()
+}
+
+//Cada caracter en base32 representa 5 bits, por lo que 32 caracteres son los 160 bits del hash.
+fn decode_base32_info_hash(hash: &str) -> Option<Vec<u8>> {
+    let mut info_hash = Vec::with_capacity(INFO_HASH_LENGTH);
+    let mut buffer: u64 = 0;
+    let mut amount_of_bits = 0;
+    for char in hash.to_ascii_uppercase().bytes() {
+        let value = BASE32_ALPHABET.iter().position(|byte| *byte == char)?;
+        buffer = (buffer << 5) | value as u64;
+        amount_of_bits += 5;
+        if amount_of_bits >= 8 {
+            amount_of_bits -= 8;
+            info_hash.push((buffer >> amount_of_bits) as u8);
+        }
+    }
+    Some(info_hash)
+}
+
+fn decode_info_hash(exact_topic: &str) -> Result<Vec<u8>, MagnetError> {
+    let invalid_hash = || MagnetError::InvalidInfoHash(exact_topic.to_string());
+    let hash = exact_topic
+        .strip_prefix(BTIH_PREFIX)
+        .ok_or_else(invalid_hash)?;
+    match hash.len() {
+        HEX_INFO_HASH_LENGTH => decode_hex_info_hash(hash),
+        BASE32_INFO_HASH_LENGTH => decode_base32_info_hash(hash),
+        _ => None,
+    }
+    .ok_or_else(invalid_hash)
+}
+
+fn decode_param_value(value: &str) -> String {
+    let value = value.replace('+', " ");
+    String::from_utf8_lossy(&urlencoding::decoder::from_url(value.into_bytes())).to_string()
+}
+
+fn calculate_sha1(bytes: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    hasher.finalize().as_slice().to_vec()
+}
+
+fn to_bencoding_string(value: &str) -> ValuesBencoding {
+    ValuesBencoding::String(value.as_bytes().to_vec())
+}
+
+impl MagnetLink {
+    ///
+    /// Funcion que interpreta un magnet link. Se requiere un parametro xt con el info hash en
+    /// hexadecimal o en base32; los trackers (tr) y el nombre (dn) son opcionales.
+    ///
+    pub fn parse(uri: &str) -> Result<Self, MagnetError> {
+        let params = uri
+            .strip_prefix(MAGNET_PREFIX)
+            .ok_or_else(|| MagnetError::IsNotMagnet(uri.to_string()))?;
+
+        let mut info_hash = None;
+        let mut display_name = None;
+        let mut trackers = vec![];
+        for param in params.split('&') {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            match key {
+                EXACT_TOPIC if info_hash.is_none() && value.starts_with(BTIH_PREFIX) => {
+                    info_hash = Some(decode_info_hash(&decode_param_value(value))?)
+                }
+                DISPLAY_NAME => display_name = Some(decode_param_value(value)),
+                TRACKER => {
+                    let tracker = decode_param_value(value);
+                    if !tracker.is_empty() && !trackers.contains(&tracker) {
+                        trackers.push(tracker);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(MagnetLink {
+            info_hash: info_hash.ok_or(MagnetError::MissingInfoHash)?,
+            display_name,
+            trackers,
+        })
+    }
+
+    /// Indica si el texto dado es un magnet link
+    pub fn is_magnet_link(uri: &str) -> bool {
+        uri.starts_with(MAGNET_PREFIX)
+    }
+
+    /// Devuelve el nombre indicado en el magnet link, o uno generico si no tiene
+    pub fn get_name(&self) -> String {
+        self.display_name
+            .clone()
+            .unwrap_or_else(|| UNNAMED_MAGNET.to_string())
+    }
+
+    ///
+    /// Funcion que crea un TorrentFileData provisorio, con el info hash y los trackers del
+    /// magnet link pero sin piezas ni archivos. Solo sirve para anunciarse a los trackers y
+    /// obtener los peers de los que descargar la metadata.
+    ///
+    pub fn create_placeholder_torrent(&self) -> TorrentFileData {
+        TorrentFileData {
+            url_tracker_main: self.trackers.first().cloned().unwrap_or_default(),
+            url_tracker_list: self
+                .trackers
+                .iter()
+                .map(|tracker| vec![tracker.clone()])
+                .collect(),
+            sha1_pieces: vec![],
+            sha1_info_hash: self.info_hash.clone(),
+            piece_length: 0,
+            total_amount_of_pieces: 0,
+            total_length: 0,
+            target_files_data: TargetFilesData::SingleFile {
+                file_name: self.get_name(),
+                file_length: 0,
+            },
+        }
+    }
+
+    ///
+    /// Funcion que verifica que el diccionario info dado (en bencoding, tal como se lo recibio
+    /// de los peers) corresponda al info hash del magnet link, y a partir de el crea el
+    /// TorrentFileData. Cada tracker del magnet link queda como un nivel del announce-list.
+    ///
+    pub fn create_torrent(&self, info_bytes: &[u8]) -> Result<TorrentFileData, MagnetError> {
+        if calculate_sha1(info_bytes) != self.info_hash {
+            return Err(MagnetError::InfoHashMismatch);
+        }
+        let dic_info = bencoding::decoder::from_torrent_to_dic(info_bytes.to_vec())
+            .map_err(|error| MagnetError::TransferToDic(format!("{}", error)))?;
+
+        let mut dic_torrent = HashMap::new();
+        if let Some(tracker) = self.trackers.first() {
+            dic_torrent.insert(ANNOUNCE.as_bytes().to_vec(), to_bencoding_string(tracker));
+        }
+        let announce_list = self
+            .trackers
+            .iter()
+            .map(|tracker| ValuesBencoding::List(vec![to_bencoding_string(tracker)]))
+            .collect();
+        dic_torrent.insert(
+            ANNOUNCE_LIST.as_bytes().to_vec(),
+            ValuesBencoding::List(announce_list),
+        );
+        dic_torrent.insert(INFO.as_bytes().to_vec(), ValuesBencoding::Dic(dic_info));
+
+        let mut torrent =
+            TorrentFileData::new(dic_torrent).map_err(MagnetError::CreatingTorrentFileData)?;
+        //El hash ya se verifico sobre los bytes originales, que pueden no estar en el orden
+        //canonico con el que se vuelven a codificar
+        torrent.sha1_info_hash = self.info_hash.clone();
+        Ok(torrent)
+    }
+}
+
+#[cfg(test)]
+mod tests_magnet_link {
+    use super::*;
+
+    const INFO_HASH_HEX: &str = "c9e15763f722f23e98a29decdfae341b98d53056";
+
+    fn info_hash_bytes() -> Vec<u8> {
+        vec![
+            0xc9, 0xe1, 0x57, 0x63, 0xf7, 0x22, 0xf2, 0x3e, 0x98, 0xa2, 0x9d, 0xec, 0xdf, 0xae,
+            0x34, 0x1b, 0x98, 0xd5, 0x30, 0x56,
+        ]
+    }
+
+    fn create_info_bytes() -> Vec<u8> {
+        let mut dic_info = HashMap::new();
+        dic_info.insert(b"name".to_vec(), to_bencoding_string("file.txt"));
+        dic_info.insert(b"length".to_vec(), ValuesBencoding::Integer(40000));
+        dic_info.insert(b"piece length".to_vec(), ValuesBencoding::Integer(16384));
+        dic_info.insert(b"pieces".to_vec(), ValuesBencoding::String(vec![7; 60]));
+        bencoding::encoder::from_dic(dic_info)
+    }
+
+    mod tests_parse {
+        use super::*;
+
+        #[test]
+        fn magnet_with_hex_info_hash_and_trackers_ok() -> Result<(), MagnetError> {
+            let uri = format!(
+                "magnet:?xt=urn:btih:{}&dn=Some+file%21&tr=http%3A%2F%2Ftracker.com%2Fannounce&tr=udp%3A%2F%2Fother.org%3A6969",
+                INFO_HASH_HEX
+            );
+
+            let magnet = MagnetLink::parse(&uri)?;
+
+            assert_eq!(info_hash_bytes(), magnet.info_hash);
+            assert_eq!(Some("Some file!".to_string()), magnet.display_name);
+            assert_eq!(
+                vec![
+                    "http://tracker.com/announce".to_string(),
+                    "udp://other.org:6969".to_string()
+                ],
+                magnet.trackers
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn magnet_with_base32_info_hash_ok() -> Result<(), MagnetError> {
+            let magnet = MagnetLink::parse("magnet:?xt=urn:btih:ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW")?;
+
+            assert_eq!(info_hash_bytes(), magnet.info_hash);
+            assert_eq!(None, magnet.display_name);
+            assert!(magnet.trackers.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_magnets_error() {
+            assert!(matches!(
+                MagnetLink::parse("http://tracker.com"),
+                Err(MagnetError::IsNotMagnet(_))
+            ));
+            assert_eq!(
+                Err(MagnetError::MissingInfoHash),
+                MagnetLink::parse("magnet:?dn=name")
+            );
+            assert!(matches!(
+                MagnetLink::parse("magnet:?xt=urn:btih:c9e157"),
+                Err(MagnetError::InvalidInfoHash(_))
+            ));
+        }
+    }
+
+    mod tests_create_torrent {
+        use super::*;
+
+        #[test]
+        fn info_with_the_expected_hash_creates_the_torrent_ok() -> Result<(), MagnetError> {
+            let info_bytes = create_info_bytes();
+            let magnet = MagnetLink {
+                info_hash: calculate_sha1(&info_bytes),
+                display_name: None,
+                trackers: vec![
+                    "http://a.com/announce".to_string(),
+                    "udp://b.org:80".to_string(),
+                ],
+            };
+
+            let torrent = magnet.create_torrent(&info_bytes)?;
+
+            assert_eq!(magnet.info_hash, torrent.get_info_hash());
+            assert_eq!("http://a.com/announce", torrent.get_tracker_main());
+            assert_eq!(
+                vec![
+                    vec!["http://a.com/announce".to_string()],
+                    vec!["udp://b.org:80".to_string()]
+                ],
+                torrent.get_tracker_tiers()
+            );
+            assert_eq!(3, torrent.get_total_amount_pieces());
+            assert_eq!(40000, torrent.get_total_length());
+            Ok(())
+        }
+
+        #[test]
+        fn info_with_another_hash_error() {
+            let magnet = MagnetLink {
+                info_hash: info_hash_bytes(),
+                display_name: None,
+                trackers: vec!["http://a.com/announce".to_string()],
+            };
+
+            assert_eq!(
+                Err(MagnetError::InfoHashMismatch),
+                magnet.create_torrent(&create_info_bytes())
+            );
+        }
+    }
+}
@@ -0,0 +1,590 @@
+//! # Modulo de mensajes KRPC
+//! Este modulo contiene las estructuras y funciones encargadas de codificar y decodificar los
+//! mensajes KRPC de la DHT de BitTorrent (BEP 5): queries, respuestas y errores, que se envian
+//! como diccionarios bencodeados sobre UDP. Los nodos y peers se informan en formato compacto,
+//! solo para IPv4.
+
+use crate::parsers::bencoding::{self, values::ValuesBencoding};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    net::{Ipv4Addr, SocketAddrV4},
+};
+
+type DicValues = HashMap<Vec<u8>, ValuesBencoding>;
+
+pub const NODE_ID_LEN: usize = 20;
+/// Identificador de 160 bits de un nodo de la DHT (tambien usado para los info hash)
+pub type NodeId = [u8; NODE_ID_LEN];
+
+pub const PING: &str = "ping";
+pub const FIND_NODE: &str = "find_node";
+pub const GET_PEERS: &str = "get_peers";
+pub const ANNOUNCE_PEER: &str = "announce_peer";
+
+pub const ERROR_GENERIC: i64 = 201;
+pub const ERROR_SERVER: i64 = 202;
+pub const ERROR_PROTOCOL: i64 = 203;
+pub const ERROR_METHOD_UNKNOWN: i64 = 204;
+
+const TRANSACTION_ID: &str = "t";
+const MSG_TYPE: &str = "y";
+const QUERY_NAME: &str = "q";
+const ARGUMENTS: &str = "a";
+const RESPONSE: &str = "r";
+const ERROR: &str = "e";
+
+const ID: &str = "id";
+const TARGET: &str = "target";
+const INFO_HASH: &str = "info_hash";
+const PORT: &str = "port";
+const IMPLIED_PORT: &str = "implied_port";
+const TOKEN: &str = "token";
+const NODES: &str = "nodes";
+const VALUES: &str = "values";
+
+const MSG_TYPE_QUERY: &[u8] = b"q";
+const MSG_TYPE_RESPONSE: &[u8] = b"r";
+const MSG_TYPE_ERROR: &[u8] = b"e";
+
+const LONG_COMPACT_PEER: usize = 6;
+const LONG_COMPACT_NODE: usize = NODE_ID_LEN + LONG_COMPACT_PEER;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa un tipo de error al interpretar un mensaje KRPC
+pub enum KrpcError {
+    Bencoding(String),
+    MissingField(String),
+    InvalidField(String),
+    UnknownMsgType(Vec<u8>),
+    UnknownQuery(String),
+}
+
+impl fmt::Display for KrpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for KrpcError {}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+/// Informacion de contacto de un nodo de la DHT
+pub struct NodeInfo {
+    pub id: NodeId,
+    pub address: SocketAddrV4,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa una query KRPC junto con sus argumentos (salvo el id del nodo que la envia)
+pub enum KrpcQuery {
+    Ping,
+    FindNode {
+        target: NodeId,
+    },
+    GetPeers {
+        info_hash: NodeId,
+    },
+    AnnouncePeer {
+        info_hash: NodeId,
+        port: u16,
+        implied_port: bool,
+        token: Vec<u8>,
+    },
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+/// Representa la respuesta a cualquier query KRPC. Los campos que no corresponden a la query
+/// respondida quedan vacios.
+pub struct KrpcResponse {
+    pub id: NodeId,
+    pub nodes: Vec<NodeInfo>,
+    pub values: Vec<SocketAddrV4>,
+    pub token: Option<Vec<u8>>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Cuerpo de un mensaje KRPC segun su tipo (`y`)
+pub enum KrpcBody {
+    Query { id: NodeId, query: KrpcQuery },
+    Response(KrpcResponse),
+    Error { code: i64, message: String },
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Mensaje KRPC completo. El transaction id permite asociar cada respuesta con su query.
+pub struct KrpcMessage {
+    pub transaction_id: Vec<u8>,
+    pub body: KrpcBody,
+}
+
+fn string_value(value: &[u8]) -> ValuesBencoding {
+    ValuesBencoding::String(value.to_vec())
+}
+
+fn get_bytes<'a>(dic: &'a DicValues, key: &str) -> Result<Option<&'a Vec<u8>>, KrpcError> {
+    match dic.get(key.as_bytes()) {
+        Some(ValuesBencoding::String(value)) => Ok(Some(value)),
+        Some(_) => Err(KrpcError::InvalidField(key.to_string())),
+        None => Ok(None),
+    }
+}
+
+fn get_required_bytes<'a>(dic: &'a DicValues, key: &str) -> Result<&'a Vec<u8>, KrpcError> {
+    get_bytes(dic, key)?.ok_or_else(|| KrpcError::MissingField(key.to_string()))
+}
+
+fn get_node_id(dic: &DicValues, key: &str) -> Result<NodeId, KrpcError> {
+    get_required_bytes(dic, key)?
+        .as_slice()
+        .try_into()
+        .map_err(|_| KrpcError::InvalidField(key.to_string()))
+}
+
+fn get_integer(dic: &DicValues, key: &str) -> Result<Option<i64>, KrpcError> {
+    match dic.get(key.as_bytes()) {
+        Some(ValuesBencoding::Integer(value)) => Ok(Some(*value)),
+        Some(_) => Err(KrpcError::InvalidField(key.to_string())),
+        None => Ok(None),
+    }
+}
+
+fn get_dic<'a>(dic: &'a DicValues, key: &str) -> Result<&'a DicValues, KrpcError> {
+    match dic.get(key.as_bytes()) {
+        Some(ValuesBencoding::Dic(value)) => Ok(value),
+        Some(_) => Err(KrpcError::InvalidField(key.to_string())),
+        None => Err(KrpcError::MissingField(key.to_string())),
+    }
+}
+
+fn encode_compact_peer(peer: &SocketAddrV4) -> Vec<u8> {
+    let mut compact_peer = peer.ip().octets().to_vec();
+    compact_peer.extend_from_slice(&peer.port().to_be_bytes());
+    compact_peer
+}
+
+fn decode_compact_peer(compact_peer: &[u8]) -> Option<SocketAddrV4> {
+    let octets: [u8; 4] = compact_peer.get(..4)?.try_into().ok()?;
+    let port: [u8; 2] = compact_peer.get(4..LONG_COMPACT_PEER)?.try_into().ok()?;
+    Some(SocketAddrV4::new(
+        Ipv4Addr::from(octets),
+        u16::from_be_bytes(port),
+    ))
+}
+
+///
+/// Funcion que codifica los nodos dados en formato compacto: por cada nodo, los 20 bytes de su
+/// id seguidos de los 4 bytes de la IP y los 2 del puerto, todo en big endian.
+///
+pub fn encode_compact_nodes(nodes: &[NodeInfo]) -> Vec<u8> {
+    let mut compact_nodes = Vec::with_capacity(nodes.len() * LONG_COMPACT_NODE);
+    for node in nodes {
+        compact_nodes.extend_from_slice(&node.id);
+        compact_nodes.append(&mut encode_compact_peer(&node.address));
+    }
+    compact_nodes
+}
+
+///
+/// Funcion que decodifica nodos en formato compacto. Los bytes sobrantes que no llegan a
+/// formar un nodo completo se ignoran.
+///
+pub fn decode_compact_nodes(compact_nodes: &[u8]) -> Vec<NodeInfo> {
+    compact_nodes
+        .chunks_exact(LONG_COMPACT_NODE)
+        .filter_map(|compact_node| {
+            Some(NodeInfo {
+                id: compact_node[..NODE_ID_LEN].try_into().ok()?,
+                address: decode_compact_peer(&compact_node[NODE_ID_LEN..])?,
+            })
+        })
+        .collect()
+}
+
+impl KrpcQuery {
+    /// Devuelve el nombre (`q`) de la query
+    pub fn get_name(&self) -> &str {
+        match self {
+            KrpcQuery::Ping => PING,
+            KrpcQuery::FindNode { .. } => FIND_NODE,
+            KrpcQuery::GetPeers { .. } => GET_PEERS,
+            KrpcQuery::AnnouncePeer { .. } => ANNOUNCE_PEER,
+        }
+    }
+
+    fn encode_arguments(&self, id: &NodeId) -> DicValues {
+        let mut arguments = HashMap::new();
+        arguments.insert(ID.as_bytes().to_vec(), string_value(id));
+        match self {
+            KrpcQuery::Ping => (),
+            KrpcQuery::FindNode { target } => {
+                arguments.insert(TARGET.as_bytes().to_vec(), string_value(target));
+            }
+            KrpcQuery::GetPeers { info_hash } => {
+                arguments.insert(INFO_HASH.as_bytes().to_vec(), string_value(info_hash));
+            }
+            KrpcQuery::AnnouncePeer {
+                info_hash,
+                port,
+                implied_port,
+                token,
+            } => {
+                arguments.insert(INFO_HASH.as_bytes().to_vec(), string_value(info_hash));
+                arguments.insert(
+                    PORT.as_bytes().to_vec(),
+                    ValuesBencoding::Integer(i64::from(*port)),
+                );
+                arguments.insert(
+                    IMPLIED_PORT.as_bytes().to_vec(),
+                    ValuesBencoding::Integer(i64::from(*implied_port)),
+                );
+                arguments.insert(TOKEN.as_bytes().to_vec(), string_value(token));
+            }
+        }
+        arguments
+    }
+
+    fn decode(name: &[u8], arguments: &DicValues) -> Result<Self, KrpcError> {
+        match String::from_utf8_lossy(name).as_ref() {
+            PING => Ok(KrpcQuery::Ping),
+            FIND_NODE => Ok(KrpcQuery::FindNode {
+                target: get_node_id(arguments, TARGET)?,
+            }),
+            GET_PEERS => Ok(KrpcQuery::GetPeers {
+                info_hash: get_node_id(arguments, INFO_HASH)?,
+            }),
+            ANNOUNCE_PEER => {
+                let port = get_integer(arguments, PORT)?
+                    .ok_or_else(|| KrpcError::MissingField(PORT.to_string()))?;
+                Ok(KrpcQuery::AnnouncePeer {
+                    info_hash: get_node_id(arguments, INFO_HASH)?,
+                    port: u16::try_from(port)
+                        .map_err(|_| KrpcError::InvalidField(PORT.to_string()))?,
+                    implied_port: get_integer(arguments, IMPLIED_PORT)?.unwrap_or_default() != 0,
+                    token: get_required_bytes(arguments, TOKEN)?.clone(),
+                })
+            }
+            unknown_query => Err(KrpcError::UnknownQuery(unknown_query.to_string())),
+        }
+    }
+}
+
+impl KrpcResponse {
+    fn encode(&self) -> DicValues {
+        let mut response = HashMap::new();
+        response.insert(ID.as_bytes().to_vec(), string_value(&self.id));
+        if !self.nodes.is_empty() {
+            response.insert(
+                NODES.as_bytes().to_vec(),
+                ValuesBencoding::String(encode_compact_nodes(&self.nodes)),
+            );
+        }
+        if !self.values.is_empty() {
+            response.insert(
+                VALUES.as_bytes().to_vec(),
+                ValuesBencoding::List(
+                    self.values
+                        .iter()
+                        .map(|peer| ValuesBencoding::String(encode_compact_peer(peer)))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(token) = &self.token {
+            response.insert(TOKEN.as_bytes().to_vec(), string_value(token));
+        }
+        response
+    }
+
+    //Los peers de `values` que no tienen el largo esperado se ignoran.
+    fn decode(response: &DicValues) -> Result<Self, KrpcError> {
+        let nodes = get_bytes(response, NODES)?
+            .map(|compact_nodes| decode_compact_nodes(compact_nodes))
+            .unwrap_or_default();
+        let values = match response.get(VALUES.as_bytes()) {
+            Some(ValuesBencoding::List(values)) => values
+                .iter()
+                .filter_map(|value| match value {
+                    ValuesBencoding::String(compact_peer)
+                        if compact_peer.len() == LONG_COMPACT_PEER =>
+                    {
+                        decode_compact_peer(compact_peer)
+                    }
+                    _ => None,
+                })
+                .collect(),
+            Some(_) => return Err(KrpcError::InvalidField(VALUES.to_string())),
+            None => vec![],
+        };
+        Ok(KrpcResponse {
+            id: get_node_id(response, ID)?,
+            nodes,
+            values,
+            token: get_bytes(response, TOKEN)?.cloned(),
+        })
+    }
+}
+
+fn decode_error(dic: &DicValues) -> Result<KrpcBody, KrpcError> {
+    match dic.get(ERROR.as_bytes()) {
+        Some(ValuesBencoding::List(error)) => match &error[..] {
+            [ValuesBencoding::Integer(code), ValuesBencoding::String(message)] => {
+                Ok(KrpcBody::Error {
+                    code: *code,
+                    message: String::from_utf8_lossy(message).to_string(),
+                })
+            }
+            _ => Err(KrpcError::InvalidField(ERROR.to_string())),
+        },
+        Some(_) => Err(KrpcError::InvalidField(ERROR.to_string())),
+        None => Err(KrpcError::MissingField(ERROR.to_string())),
+    }
+}
+
+impl KrpcMessage {
+    ///
+    /// Funcion que codifica el mensaje en bencoding para ser enviado en un datagrama UDP.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut dic = HashMap::new();
+        dic.insert(
+            TRANSACTION_ID.as_bytes().to_vec(),
+            string_value(&self.transaction_id),
+        );
+        match &self.body {
+            KrpcBody::Query { id, query } => {
+                dic.insert(MSG_TYPE.as_bytes().to_vec(), string_value(MSG_TYPE_QUERY));
+                dic.insert(
+                    QUERY_NAME.as_bytes().to_vec(),
+                    string_value(query.get_name().as_bytes()),
+                );
+                dic.insert(
+                    ARGUMENTS.as_bytes().to_vec(),
+                    ValuesBencoding::Dic(query.encode_arguments(id)),
+                );
+            }
+            KrpcBody::Response(response) => {
+                dic.insert(
+                    MSG_TYPE.as_bytes().to_vec(),
+                    string_value(MSG_TYPE_RESPONSE),
+                );
+                dic.insert(
+                    RESPONSE.as_bytes().to_vec(),
+                    ValuesBencoding::Dic(response.encode()),
+                );
+            }
+            KrpcBody::Error { code, message } => {
+                dic.insert(MSG_TYPE.as_bytes().to_vec(), string_value(MSG_TYPE_ERROR));
+                dic.insert(
+                    ERROR.as_bytes().to_vec(),
+                    ValuesBencoding::List(vec![
+                        ValuesBencoding::Integer(*code),
+                        string_value(message.as_bytes()),
+                    ]),
+                );
+            }
+        }
+        bencoding::encoder::from_dic(dic)
+    }
+
+    ///
+    /// Funcion que interpreta un datagrama recibido como mensaje KRPC. Los campos desconocidos
+    /// se ignoran.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, KrpcError> {
+        let (dic, _) = bencoding::decoder::to_dic(bytes.to_vec())
+            .map_err(|error| KrpcError::Bencoding(format!("{}", error)))?;
+        let transaction_id = get_required_bytes(&dic, TRANSACTION_ID)?.clone();
+
+        let body = match get_required_bytes(&dic, MSG_TYPE)?.as_slice() {
+            MSG_TYPE_QUERY => {
+                let arguments = get_dic(&dic, ARGUMENTS)?;
+                KrpcBody::Query {
+                    id: get_node_id(arguments, ID)?,
+                    query: KrpcQuery::decode(get_required_bytes(&dic, QUERY_NAME)?, arguments)?,
+                }
+            }
+            MSG_TYPE_RESPONSE => {
+                KrpcBody::Response(KrpcResponse::decode(get_dic(&dic, RESPONSE)?)?)
+            }
+            MSG_TYPE_ERROR => decode_error(&dic)?,
+            unknown_type => return Err(KrpcError::UnknownMsgType(unknown_type.to_vec())),
+        };
+        Ok(KrpcMessage {
+            transaction_id,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests_krpc {
+    use super::*;
+
+    mod tests_queries {
+        use super::*;
+
+        #[test]
+        fn encode_ping_query_ok() {
+            let ping = KrpcMessage {
+                transaction_id: b"aa".to_vec(),
+                body: KrpcBody::Query {
+                    id: *b"abcdefghij0123456789",
+                    query: KrpcQuery::Ping,
+                },
+            };
+
+            assert_eq!(
+                b"d1:ad2:id20:abcdefghij0123456789e1:q4:ping1:t2:aa1:y1:qe".to_vec(),
+                ping.to_bytes()
+            );
+        }
+
+        #[test]
+        fn decode_find_node_query_ok() -> Result<(), KrpcError> {
+            let bytes = b"d1:ad2:id20:abcdefghij01234567896:target20:mnopqrstuvwxyz123456e1:q9:find_node1:t2:aa1:y1:qe";
+
+            assert_eq!(
+                KrpcMessage {
+                    transaction_id: b"aa".to_vec(),
+                    body: KrpcBody::Query {
+                        id: *b"abcdefghij0123456789",
+                        query: KrpcQuery::FindNode {
+                            target: *b"mnopqrstuvwxyz123456"
+                        },
+                    },
+                },
+                KrpcMessage::from_bytes(bytes)?
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn encode_and_decode_announce_peer_query_ok() -> Result<(), KrpcError> {
+            let announce = KrpcMessage {
+                transaction_id: vec![0, 7],
+                body: KrpcBody::Query {
+                    id: [1; NODE_ID_LEN],
+                    query: KrpcQuery::AnnouncePeer {
+                        info_hash: [2; NODE_ID_LEN],
+                        port: 6881,
+                        implied_port: true,
+                        token: b"aoeusnth".to_vec(),
+                    },
+                },
+            };
+
+            assert_eq!(announce, KrpcMessage::from_bytes(&announce.to_bytes())?);
+            Ok(())
+        }
+
+        #[test]
+        fn decode_unknown_query_error() {
+            let bytes = b"d1:ad2:id20:abcdefghij0123456789e1:q4:vote1:t2:aa1:y1:qe";
+
+            assert_eq!(
+                Err(KrpcError::UnknownQuery("vote".to_string())),
+                KrpcMessage::from_bytes(bytes)
+            );
+        }
+
+        #[test]
+        fn decode_query_with_short_id_error() {
+            let bytes = b"d1:ad2:id3:abce1:q4:ping1:t2:aa1:y1:qe";
+
+            assert_eq!(
+                Err(KrpcError::InvalidField(ID.to_string())),
+                KrpcMessage::from_bytes(bytes)
+            );
+        }
+    }
+
+    mod tests_responses {
+        use super::*;
+
+        #[test]
+        fn encode_and_decode_get_peers_response_ok() -> Result<(), KrpcError> {
+            let response = KrpcMessage {
+                transaction_id: b"aa".to_vec(),
+                body: KrpcBody::Response(KrpcResponse {
+                    id: [3; NODE_ID_LEN],
+                    nodes: vec![NodeInfo {
+                        id: [4; NODE_ID_LEN],
+                        address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 6881),
+                    }],
+                    values: vec![
+                        SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8000),
+                        SocketAddrV4::new(Ipv4Addr::new(192, 168, 0, 2), 51413),
+                    ],
+                    token: Some(b"token".to_vec()),
+                }),
+            };
+
+            assert_eq!(response, KrpcMessage::from_bytes(&response.to_bytes())?);
+            Ok(())
+        }
+
+        #[test]
+        fn decode_error_message_ok() -> Result<(), KrpcError> {
+            let bytes = b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee";
+
+            assert_eq!(
+                KrpcMessage {
+                    transaction_id: b"aa".to_vec(),
+                    body: KrpcBody::Error {
+                        code: ERROR_GENERIC,
+                        message: "A Generic Error Ocurred".to_string(),
+                    },
+                },
+                KrpcMessage::from_bytes(bytes)?
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn decode_message_without_transaction_id_error() {
+            let bytes = b"d1:rd2:id20:abcdefghij0123456789e1:y1:re";
+
+            assert_eq!(
+                Err(KrpcError::MissingField(TRANSACTION_ID.to_string())),
+                KrpcMessage::from_bytes(bytes)
+            );
+        }
+    }
+
+    mod tests_compact_nodes {
+        use super::*;
+
+        #[test]
+        fn encode_and_decode_compact_nodes_ok() {
+            let nodes = vec![
+                NodeInfo {
+                    id: [5; NODE_ID_LEN],
+                    address: SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 0x1AE1),
+                },
+                NodeInfo {
+                    id: [6; NODE_ID_LEN],
+                    address: SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6881),
+                },
+            ];
+
+            let compact_nodes = encode_compact_nodes(&nodes);
+
+            assert_eq!(2 * LONG_COMPACT_NODE, compact_nodes.len());
+            assert_eq!(vec![1, 2, 3, 4, 0x1A, 0xE1], compact_nodes[20..26]);
+            assert_eq!(nodes, decode_compact_nodes(&compact_nodes));
+        }
+
+        #[test]
+        fn incomplete_compact_nodes_are_ignored_ok() {
+            let mut compact_nodes = encode_compact_nodes(&[NodeInfo {
+                id: [5; NODE_ID_LEN],
+                address: SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6881),
+            }]);
+            compact_nodes.extend_from_slice(&[1, 2, 3]);
+
+            assert_eq!(1, decode_compact_nodes(&compact_nodes).len());
+        }
+    }
+}
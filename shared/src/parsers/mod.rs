@@ -1,7 +1,9 @@
 //! # Modulo general de Parsers
 //! Este modulo contiene todas las definiciones de funciones, constantes y estructuras usadas para
-//! realizar Encoding y Decoding de los distintos formatos (Comunicacion P2P, Bencoding y URLencoding)
+//! realizar Encoding y Decoding de los distintos formatos (Comunicacion P2P, Bencoding, mensajes KRPC
+//! de la DHT y URLencoding)
 
 pub mod bencoding;
+pub mod krpc;
 pub mod p2p;
 pub mod urlencoding;
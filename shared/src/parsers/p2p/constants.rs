@@ -22,3 +22,26 @@ pub const ID_REQUEST: u8 = 6;
 pub const ID_PIECE: u8 = 7;
 pub const ID_CANCEL: u8 = 8;
 pub const ID_PORT: u8 = 9;
+pub const ID_SUGGEST_PIECE: u8 = 13;
+pub const ID_HAVE_ALL: u8 = 14;
+pub const ID_HAVE_NONE: u8 = 15;
+pub const ID_REJECT_REQUEST: u8 = 16;
+pub const ID_ALLOWED_FAST: u8 = 17;
+pub const ID_EXTENDED: u8 = 20;
+
+// Bytes reservados del handshake, que comienzan luego del pstrlen y el pstr.
+pub const FIRST_RESERVED_BYTE_HANDSHAKE: usize = 20;
+pub const NUM_OF_RESERVED_BYTES_HANDSHAKE: usize = 8;
+
+// Extension protocol (BEP 10): se indica su soporte con el bit 0x10 del sexto byte reservado.
+pub const EXTENSION_PROTOCOL_RESERVED_BYTE: usize = 5;
+pub const EXTENSION_PROTOCOL_BIT: u8 = 0x10;
+pub const ID_EXTENDED_HANDSHAKE: u8 = 0;
+
+// Fast extension (BEP 6): se indica su soporte con el bit 0x04 del ultimo byte reservado.
+pub const FAST_EXTENSION_RESERVED_BYTE: usize = 7;
+pub const FAST_EXTENSION_BIT: u8 = 0x04;
+
+// Tamaño del payload de los mensajes de la fast extension.
+pub const NUM_OF_BYTES_PIECE_INDEX: usize = 4;
+pub const NUM_OF_BYTES_REJECT_REQUEST: usize = 12;
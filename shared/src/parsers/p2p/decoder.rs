@@ -62,10 +62,13 @@ fn try_decode_handshake_p2p_message(bytes: &[u8]) -> Result<P2PMessage, P2PMessa
     let protocol_str = String::from_utf8(bytes[1..20].to_vec()).map_err(|err| {
         P2PMessageDecodingError::FromBytesToString(format!("[P2PMessageDecodingError] {:?}", err))
     })?;
+    let mut reserved = [0; NUM_OF_RESERVED_BYTES_HANDSHAKE];
+    reserved.copy_from_slice(&bytes[FIRST_RESERVED_BYTE_HANDSHAKE..28]);
     let info_hash = bytes[28..48].to_vec();
     let peer_id = bytes[48..68].to_vec();
     Ok(P2PMessage::Handshake {
         protocol_str,
+        reserved,
         info_hash,
         peer_id,
     })
@@ -204,6 +207,106 @@ fn try_decode_port_p2p_message(bytes: &[u8]) -> Result<P2PMessage, P2PMessageDec
     })
 }
 
+///
+/// A partir de una cadena de bytes (u8) recibidos en un slice con los datos de un extended, intenta generar un mensaje p2p del tipo "extended".
+///
+/// El primer byte es el id de la extension y el resto es su payload, el cual queda sin interpretar.
+///
+fn try_decode_extended_p2p_message(bytes: &[u8]) -> Result<P2PMessage, P2PMessageDecodingError> {
+    match bytes.split_first() {
+        Some((ext_id, payload)) => Ok(P2PMessage::Extended {
+            ext_id: *ext_id,
+            payload: payload.to_vec(),
+        }),
+        None => Err(P2PMessageDecodingError::ByteAmount(
+            "[P2PMessageDecodingError] The extended msg does not have an extension id".to_string(),
+        )),
+    }
+}
+
+///
+/// Verifica que el payload de un mensaje p2p tenga exactamente la cantidad de bytes esperada para su id,
+/// devolviendo un P2PMessageDecodingError en caso contrario.
+///
+fn check_payload_length(
+    bytes: &[u8],
+    expected_amount_of_bytes: usize,
+) -> Result<(), P2PMessageDecodingError> {
+    if bytes.len() != expected_amount_of_bytes {
+        return Err(P2PMessageDecodingError::ByteAmount(format!(
+            "[P2PMessageDecodingError] The payload of the P2P msg has {} bytes ({} are required)",
+            bytes.len(),
+            expected_amount_of_bytes
+        )));
+    }
+    Ok(())
+}
+
+///
+/// A partir de una cadena de bytes (u8) recibidos en un slice con los datos validos de un suggest piece, intenta generar un mensaje p2p del tipo "suggest piece".
+///
+/// Tener en cuenta que el slice de bytes esperado debe estar ordenado a modo big endian.
+///
+fn try_decode_suggest_piece_p2p_message(
+    bytes: &[u8],
+) -> Result<P2PMessage, P2PMessageDecodingError> {
+    check_payload_length(bytes, NUM_OF_BYTES_PIECE_INDEX)?;
+    let piece_index = concatenate_bytes_into_u32(&bytes[0..4])?;
+    Ok(P2PMessage::SuggestPiece { piece_index })
+}
+
+///
+/// A partir de una cadena de bytes (u8) recibidos en un slice con los datos validos de un reject request, intenta generar un mensaje p2p del tipo "reject request".
+///
+/// Tener en cuenta que el slice de bytes esperado debe estar ordenado a modo big endian.
+///
+fn try_decode_reject_request_p2p_message(
+    bytes: &[u8],
+) -> Result<P2PMessage, P2PMessageDecodingError> {
+    check_payload_length(bytes, NUM_OF_BYTES_REJECT_REQUEST)?;
+    let piece_index = concatenate_bytes_into_u32(&bytes[0..4])?;
+    let beginning_byte_index = concatenate_bytes_into_u32(&bytes[4..8])?;
+    let amount_of_bytes = concatenate_bytes_into_u32(&bytes[8..12])?;
+    Ok(P2PMessage::RejectRequest {
+        piece_index,
+        beginning_byte_index,
+        amount_of_bytes,
+    })
+}
+
+///
+/// A partir de una cadena de bytes (u8) recibidos en un slice con los datos validos de un allowed fast, intenta generar un mensaje p2p del tipo "allowed fast".
+///
+/// Tener en cuenta que el slice de bytes esperado debe estar ordenado a modo big endian.
+///
+fn try_decode_allowed_fast_p2p_message(
+    bytes: &[u8],
+) -> Result<P2PMessage, P2PMessageDecodingError> {
+    check_payload_length(bytes, NUM_OF_BYTES_PIECE_INDEX)?;
+    let piece_index = concatenate_bytes_into_u32(&bytes[0..4])?;
+    Ok(P2PMessage::AllowedFast { piece_index })
+}
+
+///
+/// A partir de los bytes reservados de un handshake, devuelve true si el peer que lo envio indica
+/// que soporta el extension protocol (BEP 10).
+///
+pub fn supports_extension_protocol(reserved: &[u8]) -> bool {
+    reserved
+        .get(EXTENSION_PROTOCOL_RESERVED_BYTE)
+        .is_some_and(|byte| byte & EXTENSION_PROTOCOL_BIT != 0)
+}
+
+///
+/// A partir de los bytes reservados de un handshake, devuelve true si el peer que lo envio indica
+/// que soporta la fast extension (BEP 6).
+///
+pub fn supports_fast_extension(reserved: &[u8]) -> bool {
+    reserved
+        .get(FAST_EXTENSION_RESERVED_BYTE)
+        .is_some_and(|byte| byte & FAST_EXTENSION_BIT != 0)
+}
+
 // Matchea la id del mensaje p2p con su representacion correspondiente.
 // Devuelve un Result tal que:
 // - El Ok value es una variante de P2PMessage segun sea adecuado.
@@ -222,6 +325,12 @@ fn match_p2p_msg_according_to_id(bytes: &[u8]) -> Result<P2PMessage, P2PMessageD
         ID_PIECE => try_decode_piece_p2p_message(&bytes[5..]),
         ID_CANCEL => try_decode_cancel_p2p_message(&bytes[5..]),
         ID_PORT => try_decode_port_p2p_message(&bytes[5..]),
+        ID_SUGGEST_PIECE => try_decode_suggest_piece_p2p_message(&bytes[5..]),
+        ID_HAVE_ALL => Ok(P2PMessage::HaveAll),
+        ID_HAVE_NONE => Ok(P2PMessage::HaveNone),
+        ID_REJECT_REQUEST => try_decode_reject_request_p2p_message(&bytes[5..]),
+        ID_ALLOWED_FAST => try_decode_allowed_fast_p2p_message(&bytes[5..]),
+        ID_EXTENDED => try_decode_extended_p2p_message(&bytes[5..]),
         _ => Err(P2PMessageDecodingError::InvalidId(
             "[P2PMessageDecodingError] Tried to decode a message with invalid ID".to_string(),
         )),
@@ -521,6 +630,7 @@ mod tests_p2p_decoder {
             assert_eq!(
                 Ok(P2PMessage::Handshake {
                     protocol_str: PSTR_STRING_HANDSHAKE.to_string(),
+                    reserved: [0; 8],
                     info_hash: [1; 20].to_vec(),
                     peer_id: "-FA0001-012345678901".bytes().collect(),
                 }),
@@ -646,5 +756,122 @@ mod tests_p2p_decoder {
                 from_bytes(&p2p_msg_bytes)
             );
         }
+
+        #[test]
+        fn decode_extended_ok() {
+            let p2p_msg_bytes = [0, 0, 0, 4, ID_EXTENDED, 3, b'd', b'e'];
+            assert_eq!(
+                Ok(P2PMessage::Extended {
+                    ext_id: 3,
+                    payload: b"de".to_vec()
+                }),
+                from_bytes(&p2p_msg_bytes)
+            );
+        }
+
+        #[test]
+        fn decode_fast_extension_messages_ok() {
+            assert_eq!(
+                Ok(P2PMessage::HaveAll),
+                from_bytes(&[0, 0, 0, 1, ID_HAVE_ALL])
+            );
+            assert_eq!(
+                Ok(P2PMessage::HaveNone),
+                from_bytes(&[0, 0, 0, 1, ID_HAVE_NONE])
+            );
+            assert_eq!(
+                Ok(P2PMessage::SuggestPiece { piece_index: 4 }),
+                from_bytes(&[0, 0, 0, 5, ID_SUGGEST_PIECE, 0, 0, 0, 4])
+            );
+            assert_eq!(
+                Ok(P2PMessage::AllowedFast { piece_index: 9 }),
+                from_bytes(&[0, 0, 0, 5, ID_ALLOWED_FAST, 0, 0, 0, 9])
+            );
+        }
+
+        #[test]
+        fn decode_reject_request_ok() {
+            let p2p_msg_bytes = [
+                0,
+                0,
+                0,
+                13,
+                ID_REJECT_REQUEST,
+                0,
+                0,
+                0,
+                1,
+                0,
+                0,
+                0,
+                2,
+                0,
+                0,
+                0,
+                3,
+            ];
+            assert_eq!(
+                Ok(P2PMessage::RejectRequest {
+                    piece_index: 1,
+                    beginning_byte_index: 2,
+                    amount_of_bytes: 3
+                }),
+                from_bytes(&p2p_msg_bytes)
+            );
+        }
+
+        #[test]
+        fn decode_truncated_fast_extension_messages_error() {
+            let expected_error = |amount_of_bytes, expected_amount_of_bytes| {
+                Err(P2PMessageDecodingError::ByteAmount(format!(
+                    "[P2PMessageDecodingError] The payload of the P2P msg has {} bytes ({} are required)",
+                    amount_of_bytes, expected_amount_of_bytes
+                )))
+            };
+            assert_eq!(
+                expected_error(0, 4),
+                from_bytes(&[0, 0, 0, 1, ID_SUGGEST_PIECE])
+            );
+            assert_eq!(
+                expected_error(2, 4),
+                from_bytes(&[0, 0, 0, 3, ID_ALLOWED_FAST, 0, 9])
+            );
+            assert_eq!(
+                expected_error(8, 12),
+                from_bytes(&[0, 0, 0, 9, ID_REJECT_REQUEST, 0, 0, 0, 1, 0, 0, 0, 2])
+            );
+        }
+
+        #[test]
+        fn decode_extended_without_extension_id_error() {
+            let p2p_msg_bytes = [0, 0, 0, 1, ID_EXTENDED];
+            assert!(from_bytes(&p2p_msg_bytes).is_err());
+        }
+    }
+
+    mod tests_supports_extension_protocol {
+        use super::*;
+
+        #[test]
+        fn handshake_with_extension_bit_ok() {
+            let mut reserved = [0; NUM_OF_RESERVED_BYTES_HANDSHAKE];
+            assert!(!supports_extension_protocol(&reserved));
+
+            reserved[5] = EXTENSION_PROTOCOL_BIT;
+            assert!(supports_extension_protocol(&reserved));
+        }
+    }
+    mod tests_supports_fast_extension {
+        use super::*;
+
+        #[test]
+        fn handshake_with_fast_extension_bit_ok() {
+            let mut reserved = [0; NUM_OF_RESERVED_BYTES_HANDSHAKE];
+            reserved[5] = EXTENSION_PROTOCOL_BIT;
+            assert!(!supports_fast_extension(&reserved));
+
+            reserved[7] = FAST_EXTENSION_BIT;
+            assert!(supports_fast_extension(&reserved));
+        }
     }
 }
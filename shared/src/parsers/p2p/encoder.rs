@@ -46,17 +46,23 @@ fn encode_not_interested() -> Vec<u8> {
     vec![0, 0, 0, 1, ID_NOT_INTERESTED]
 }
 
+///
+/// Logica de encodificacion común para los mensajes cuyo unico payload es un indice de pieza.
+fn common_encode_for_piece_index(piece_index: u32, id: u8) -> Vec<u8> {
+    let mut encoded_msg: Vec<u8> = vec![0, 0, 0, 5, id];
+    piece_index
+        .to_be_bytes()
+        .iter()
+        .for_each(|byte| encoded_msg.push(*byte));
+    encoded_msg
+}
+
 ///
 /// Devuelve un vec de bytes de tipo:
 /// <len=5><id=4><piece index>; tal que cada uno mide:
 /// <4bytes><1byte><4bytes>
 fn encode_have(piece_index: u32) -> Vec<u8> {
-    let mut encoded_have: Vec<u8> = vec![0, 0, 0, 5, ID_HAVE];
-    piece_index
-        .to_be_bytes()
-        .iter()
-        .for_each(|byte| encoded_have.push(*byte));
-    encoded_have
+    common_encode_for_piece_index(piece_index, ID_HAVE)
 }
 
 ///
@@ -162,7 +168,7 @@ fn encode_bitfield(
 }
 
 ///
-/// Logica de encodificacion común para ambos mensajes.
+/// Logica de encodificacion común para los mensajes Request, Cancel y RejectRequest.
 fn common_encode_for_request_and_cancel(
     piece_index: u32,
     beginning_byte_index: u32,
@@ -271,12 +277,101 @@ fn encode_port(listen_port: u32) -> Vec<u8> {
     encoded_port
 }
 
+///
+/// Devuelve un vec de bytes de tipo:
+/// <len=5><id=13><piece index>; tal que cada uno mide:
+/// <4bytes><1byte><4bytes>
+fn encode_suggest_piece(piece_index: u32) -> Vec<u8> {
+    common_encode_for_piece_index(piece_index, ID_SUGGEST_PIECE)
+}
+
+///
+/// Devuelve un vec de bytes de tipo:
+/// <len=1><id=14>; tal que cada uno mide:
+/// <4bytes><1byte>
+fn encode_have_all() -> Vec<u8> {
+    vec![0, 0, 0, 1, ID_HAVE_ALL]
+}
+
+///
+/// Devuelve un vec de bytes de tipo:
+/// <len=1><id=15>; tal que cada uno mide:
+/// <4bytes><1byte>
+fn encode_have_none() -> Vec<u8> {
+    vec![0, 0, 0, 1, ID_HAVE_NONE]
+}
+
+///
+/// Devuelve un vec de bytes de tipo:
+/// <len=13><id=16><index><begin><length>; tal que cada uno mide:
+/// <4bytes><1byte><4bytes><4bytes><4bytes>
+fn encode_reject_request(
+    piece_index: u32,
+    beginning_byte_index: u32,
+    amount_of_bytes: u32,
+) -> Vec<u8> {
+    common_encode_for_request_and_cancel(
+        piece_index,
+        beginning_byte_index,
+        amount_of_bytes,
+        ID_REJECT_REQUEST,
+    )
+}
+
+///
+/// Devuelve un vec de bytes de tipo:
+/// <len=5><id=17><piece index>; tal que cada uno mide:
+/// <4bytes><1byte><4bytes>
+fn encode_allowed_fast(piece_index: u32) -> Vec<u8> {
+    common_encode_for_piece_index(piece_index, ID_ALLOWED_FAST)
+}
+
+///
+/// Si no hubo fallas de conversión, el Ok value es un vec de bytes de tipo:
+/// <len=2+X><id=20><ext_id><payload>; tal que cada uno mide:
+/// <4bytes><1byte><1byte><Xbytes>
+fn encode_extended(ext_id: u8, payload: Vec<u8>) -> Result<Vec<u8>, P2PMessageEncodingError> {
+    let length_prefix = u32::try_from(payload.len())
+        .ok()
+        .and_then(|payload_len| payload_len.checked_add(2))
+        .ok_or_else(|| {
+            P2PMessageEncodingError::FromUsizeToU32Error(
+                "[P2PMessageEncodingError] The extended payload is too long".to_string(),
+            )
+        })?;
+
+    let mut encoded_extended = length_prefix.to_be_bytes().to_vec();
+    encoded_extended.push(ID_EXTENDED);
+    encoded_extended.push(ext_id);
+    encoded_extended.extend(payload);
+    Ok(encoded_extended)
+}
+
+///
+/// Marca en los bytes reservados de un handshake que se soporta el extension protocol (BEP 10).
+///
+pub fn set_extension_protocol_bit(reserved: &mut [u8]) {
+    if let Some(byte) = reserved.get_mut(EXTENSION_PROTOCOL_RESERVED_BYTE) {
+        *byte |= EXTENSION_PROTOCOL_BIT;
+    }
+}
+
+///
+/// Marca en los bytes reservados de un handshake que se soporta la fast extension (BEP 6).
+///
+pub fn set_fast_extension_bit(reserved: &mut [u8]) {
+    if let Some(byte) = reserved.get_mut(FAST_EXTENSION_RESERVED_BYTE) {
+        *byte |= FAST_EXTENSION_BIT;
+    }
+}
+
 ///
 /// Si el protocolo usado es BitTorrent protocol, el Ok value es un vec de bytes de tipo:
 /// <pstrlen><pstr><reserved><info_hash><peer_id>;  tal que cada uno mide:
 /// <1byte><19bytes><8bytes><20bytes><20bytes>
 fn encode_handshake(
     protocol_str: String,
+    reserved_bytes: [u8; 8],
     info_hash: Vec<u8>,
     peer_id: Vec<u8>,
 ) -> Result<Vec<u8>, P2PMessageEncodingError> {
@@ -286,7 +381,6 @@ fn encode_handshake(
         ));
     }
     let protocol_str_len = PSTRLEN_VALUE_HANDSHAKE; // Específico de protocolo BitTorrent
-    let reserved_bytes = [0u8; 8];
 
     let mut encoded_handshake = vec![protocol_str_len];
 
@@ -318,7 +412,8 @@ fn encode_handshake(
 ///
 /// ## Notas importantes según mensaje:
 ///
-/// - ***KeepAlive, Choke, Unchoke, Interested, Not interested, Have, Request, Cancel, Port***: En estos casos
+/// - ***KeepAlive, Choke, Unchoke, Interested, Not interested, Have, Request, Cancel, Port, SuggestPiece, HaveAll,
+///   HaveNone, RejectRequest, AllowedFast***: En estos casos
 ///   NUNCA se va a obtener por retorno la variante Err.
 ///
 /// - ***Bitfield***: Antes de realizar su encoding, verificar que la longitud del vector de PieceStatuses que se
@@ -334,6 +429,8 @@ fn encode_handshake(
 ///   del block a enviar. Como la longitud del block es un usize, entonces se requiere una conversion que puede fallar. Por esto puede
 ///   devolver Err.
 ///
+/// - ***Extended*** : Similar a Piece, el length prefix se calcula a partir de la longitud del payload.
+///
 /// - ***Bitfield*** : Similar a Piece, al encodearlo se convierte un usize (del largo del payload en bytes) a u32 para calcular el
 ///   length prefix.
 ///
@@ -386,11 +483,26 @@ pub fn to_bytes(p2p_msg: P2PMessage) -> Result<Vec<u8>, P2PMessageEncodingError>
             amount_of_bytes,
         )),
         P2PMessage::Port { listen_port } => Ok(encode_port(listen_port)),
+        P2PMessage::SuggestPiece { piece_index } => Ok(encode_suggest_piece(piece_index)),
+        P2PMessage::HaveAll => Ok(encode_have_all()),
+        P2PMessage::HaveNone => Ok(encode_have_none()),
+        P2PMessage::RejectRequest {
+            piece_index,
+            beginning_byte_index,
+            amount_of_bytes,
+        } => Ok(encode_reject_request(
+            piece_index,
+            beginning_byte_index,
+            amount_of_bytes,
+        )),
+        P2PMessage::AllowedFast { piece_index } => Ok(encode_allowed_fast(piece_index)),
+        P2PMessage::Extended { ext_id, payload } => encode_extended(ext_id, payload),
         P2PMessage::Handshake {
             protocol_str,
+            reserved,
             info_hash,
             peer_id,
-        } => encode_handshake(protocol_str, info_hash, peer_id),
+        } => encode_handshake(protocol_str, reserved, info_hash, peer_id),
     }
 }
 
@@ -658,6 +770,78 @@ mod tests_p2p_encoder {
 
             assert_eq!(Ok(expected_bytes), to_bytes(msg_to_send));
         }
+
+        #[test]
+        fn encode_extended_results_ok() {
+            let msg_to_send = P2PMessage::Extended {
+                ext_id: 2,
+                payload: b"d8:msg_typei0ee".to_vec(),
+            };
+
+            let mut expected_bytes = vec![0, 0, 0, 17, ID_EXTENDED, 2];
+            expected_bytes.extend_from_slice(b"d8:msg_typei0ee");
+
+            assert_eq!(Ok(expected_bytes), to_bytes(msg_to_send));
+        }
+    }
+
+    mod tests_fast_extension_encodings {
+        use super::*;
+
+        #[test]
+        fn encode_have_all_and_have_none_ok() {
+            assert_eq!(
+                Ok(vec![0, 0, 0, 1, ID_HAVE_ALL]),
+                to_bytes(P2PMessage::HaveAll)
+            );
+            assert_eq!(
+                Ok(vec![0, 0, 0, 1, ID_HAVE_NONE]),
+                to_bytes(P2PMessage::HaveNone)
+            );
+        }
+
+        #[test]
+        fn encode_suggest_piece_and_allowed_fast_ok() {
+            assert_eq!(
+                Ok(vec![0, 0, 0, 5, ID_SUGGEST_PIECE, 0, 0, 1, 2]),
+                to_bytes(P2PMessage::SuggestPiece { piece_index: 258 })
+            );
+            assert_eq!(
+                Ok(vec![0, 0, 0, 5, ID_ALLOWED_FAST, 0, 0, 0, 7]),
+                to_bytes(P2PMessage::AllowedFast { piece_index: 7 })
+            );
+        }
+
+        #[test]
+        fn encode_reject_request_ok() {
+            let msg_to_send = P2PMessage::RejectRequest {
+                piece_index: 1,
+                beginning_byte_index: 16384,
+                amount_of_bytes: 16384,
+            };
+
+            let expected_bytes = vec![
+                0,
+                0,
+                0,
+                13,
+                ID_REJECT_REQUEST,
+                0,
+                0,
+                0,
+                1,
+                0,
+                0,
+                0x40,
+                0,
+                0,
+                0,
+                0x40,
+                0,
+            ];
+
+            assert_eq!(Ok(expected_bytes), to_bytes(msg_to_send));
+        }
     }
 
     mod tests_handshake_encoding {
@@ -669,6 +853,7 @@ mod tests_p2p_encoder {
             let mut peer_id: Vec<u8> = "-FA0001-012345678901".bytes().collect();
             let msg_to_send = P2PMessage::Handshake {
                 protocol_str: PSTR_STRING_HANDSHAKE.to_owned(),
+                reserved: [0; 8],
                 info_hash: sha1d_info_hash.clone(),
                 peer_id: peer_id.clone(),
             };
@@ -683,5 +868,40 @@ mod tests_p2p_encoder {
 
             assert_eq!(Ok(expected_bytes), to_bytes(msg_to_send));
         }
+
+        #[test]
+        fn encode_handshake_with_extension_protocol_bit_ok() -> Result<(), P2PMessageEncodingError>
+        {
+            let mut reserved = [0; 8];
+            set_extension_protocol_bit(&mut reserved);
+
+            let handshake_bytes = to_bytes(P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_owned(),
+                reserved,
+                info_hash: vec![1; 20],
+                peer_id: vec![2; 20],
+            })?;
+
+            assert_eq!([0, 0, 0, 0, 0, 0x10, 0, 0], handshake_bytes[20..28]);
+            assert_eq!(vec![1; 20], handshake_bytes[28..48]);
+            Ok(())
+        }
+
+        #[test]
+        fn encode_handshake_with_fast_extension_bit_ok() -> Result<(), P2PMessageEncodingError> {
+            let mut reserved = [0; 8];
+            set_extension_protocol_bit(&mut reserved);
+            set_fast_extension_bit(&mut reserved);
+
+            let handshake_bytes = to_bytes(P2PMessage::Handshake {
+                protocol_str: PSTR_STRING_HANDSHAKE.to_owned(),
+                reserved,
+                info_hash: vec![1; 20],
+                peer_id: vec![2; 20],
+            })?;
+
+            assert_eq!([0, 0, 0, 0, 0, 0x10, 0, 0x04], handshake_bytes[20..28]);
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,486 @@
+//! # Modulo de mensajes del extension protocol
+//! Este modulo contiene las estructuras y funciones encargadas de codificar y decodificar los payloads
+//! de los mensajes Extended (BEP 10): el handshake extendido y los mensajes de la extension
+//! ut_metadata (BEP 9), usada para intercambiar el diccionario info de un torrent entre peers, y
+//! de la extension ut_pex (BEP 11), usada para intercambiar peers conocidos.
+
+use crate::parsers::bencoding::{self, values::ValuesBencoding};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+type DicValues = HashMap<Vec<u8>, ValuesBencoding>;
+
+pub const UT_METADATA: &str = "ut_metadata";
+pub const UT_PEX: &str = "ut_pex";
+/// Tamaño de cada pieza de la metadata (salvo la ultima, que puede ser menor)
+pub const METADATA_PIECE_SIZE: u64 = 16384; //2^14 bytes
+
+const M: &str = "m";
+const METADATA_SIZE: &str = "metadata_size";
+const CLIENT_VERSION: &str = "v";
+const MSG_TYPE: &str = "msg_type";
+const PIECE: &str = "piece";
+const TOTAL_SIZE: &str = "total_size";
+
+const ADDED: &str = "added";
+const ADDED_FLAGS: &str = "added.f";
+const ADDED6: &str = "added6";
+const ADDED6_FLAGS: &str = "added6.f";
+const DROPPED: &str = "dropped";
+const DROPPED6: &str = "dropped6";
+
+const LONG_COMPACT_PEER: usize = 6;
+const LONG_COMPACT_PEER6: usize = 18;
+
+const MSG_TYPE_REQUEST: i64 = 0;
+const MSG_TYPE_DATA: i64 = 1;
+const MSG_TYPE_REJECT: i64 = 2;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa un tipo de error al interpretar el payload de un mensaje Extended
+pub enum ExtensionMessageError {
+    Bencoding(String),
+    MissingField(String),
+    InvalidField(String),
+    UnknownMetadataMsgType(i64),
+}
+
+impl fmt::Display for ExtensionMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\n    {:#?}\n", self)
+    }
+}
+
+impl Error for ExtensionMessageError {}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+/// Handshake extendido (mensaje Extended de ext_id 0). En extensions se asocia el nombre de cada
+/// extension soportada con el id con el que se la quiere recibir.
+pub struct ExtendedHandshake {
+    pub extensions: HashMap<String, u8>,
+    pub metadata_size: Option<u64>,
+    pub client: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Representa un mensaje de la extension ut_metadata
+pub enum MetadataMessage {
+    Request {
+        piece: u32,
+    },
+    Data {
+        piece: u32,
+        total_size: u64,
+        data: Vec<u8>,
+    },
+    Reject {
+        piece: u32,
+    },
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+/// Representa un mensaje de la extension ut_pex, con los peers a los que el emisor se conecto y
+/// de los que se desconecto desde su ultimo mensaje
+pub struct PexMessage {
+    pub added: Vec<SocketAddr>,
+    pub dropped: Vec<SocketAddr>,
+}
+
+fn get_integer(dic: &DicValues, key: &str) -> Result<Option<i64>, ExtensionMessageError> {
+    match dic.get(key.as_bytes()) {
+        Some(ValuesBencoding::Integer(value)) => Ok(Some(*value)),
+        Some(_) => Err(ExtensionMessageError::InvalidField(key.to_string())),
+        None => Ok(None),
+    }
+}
+
+fn get_unsigned<T: TryFrom<i64>>(dic: &DicValues, key: &str) -> Result<T, ExtensionMessageError> {
+    let value = get_integer(dic, key)?
+        .ok_or_else(|| ExtensionMessageError::MissingField(key.to_string()))?;
+    T::try_from(value).map_err(|_| ExtensionMessageError::InvalidField(key.to_string()))
+}
+
+//Las extensiones con id fuera de rango se ignoran, y las de id 0 quedan registradas como
+//deshabilitadas por el peer.
+fn decode_extensions(dic: &DicValues) -> Result<HashMap<String, u8>, ExtensionMessageError> {
+    let dic_extensions = match dic.get(M.as_bytes()) {
+        Some(ValuesBencoding::Dic(dic_extensions)) => dic_extensions,
+        Some(_) => return Err(ExtensionMessageError::InvalidField(M.to_string())),
+        None => return Ok(HashMap::new()),
+    };
+    let mut extensions = HashMap::new();
+    for (name, value) in dic_extensions {
+        if let ValuesBencoding::Integer(ext_id) = value {
+            if let Ok(ext_id) = u8::try_from(*ext_id) {
+                extensions.insert(String::from_utf8_lossy(name).to_string(), ext_id);
+            }
+        }
+    }
+    Ok(extensions)
+}
+
+impl ExtendedHandshake {
+    ///
+    /// Funcion que codifica el handshake extendido en bencoding para ser enviado como payload
+    /// de un mensaje Extended.
+    ///
+    pub fn to_payload(&self) -> Vec<u8> {
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|(name, ext_id)| {
+                (
+                    name.as_bytes().to_vec(),
+                    ValuesBencoding::Integer(i64::from(*ext_id)),
+                )
+            })
+            .collect();
+
+        let mut dic = HashMap::new();
+        dic.insert(M.as_bytes().to_vec(), ValuesBencoding::Dic(extensions));
+        if let Some(metadata_size) = self.metadata_size {
+            dic.insert(
+                METADATA_SIZE.as_bytes().to_vec(),
+                ValuesBencoding::Integer(metadata_size as i64),
+            );
+        }
+        if let Some(client) = &self.client {
+            dic.insert(
+                CLIENT_VERSION.as_bytes().to_vec(),
+                ValuesBencoding::String(client.as_bytes().to_vec()),
+            );
+        }
+        bencoding::encoder::from_dic(dic)
+    }
+
+    ///
+    /// Funcion que interpreta el payload de un handshake extendido recibido. Los campos
+    /// desconocidos se ignoran.
+    ///
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ExtensionMessageError> {
+        let dic = bencoding::decoder::from_torrent_to_dic(payload.to_vec())
+            .map_err(|error| ExtensionMessageError::Bencoding(format!("{}", error)))?;
+
+        let metadata_size = match get_integer(&dic, METADATA_SIZE)? {
+            Some(size) => Some(
+                u64::try_from(size)
+                    .map_err(|_| ExtensionMessageError::InvalidField(METADATA_SIZE.to_string()))?,
+            ),
+            None => None,
+        };
+        let client = match dic.get(CLIENT_VERSION.as_bytes()) {
+            Some(ValuesBencoding::String(client)) => {
+                Some(String::from_utf8_lossy(client).to_string())
+            }
+            _ => None,
+        };
+
+        Ok(ExtendedHandshake {
+            extensions: decode_extensions(&dic)?,
+            metadata_size,
+            client,
+        })
+    }
+
+    /// Devuelve el id con el que el peer quiere recibir la extension dada, si la soporta
+    pub fn get_extension_id(&self, extension_name: &str) -> Option<u8> {
+        self.extensions
+            .get(extension_name)
+            .copied()
+            .filter(|ext_id| *ext_id != 0)
+    }
+}
+
+//Un peer compacto son 4 bytes de IPv4 (o 16 de IPv6) seguidos de 2 bytes del puerto, todo en
+//big endian. Se devuelven por separado los peers IPv4 y los IPv6.
+fn encode_compact_peers(peers: &[SocketAddr]) -> (Vec<u8>, Vec<u8>) {
+    let mut compact_peers = vec![];
+    let mut compact_peers6 = vec![];
+    for peer in peers {
+        match peer.ip() {
+            IpAddr::V4(ip) => {
+                compact_peers.extend_from_slice(&ip.octets());
+                compact_peers.extend_from_slice(&peer.port().to_be_bytes());
+            }
+            IpAddr::V6(ip) => {
+                compact_peers6.extend_from_slice(&ip.octets());
+                compact_peers6.extend_from_slice(&peer.port().to_be_bytes());
+            }
+        }
+    }
+    (compact_peers, compact_peers6)
+}
+
+//Los bytes sobrantes que no llegan a formar un peer completo se ignoran.
+fn decode_compact_peers(compact_peers: &[u8], long_compact: usize) -> Vec<SocketAddr> {
+    compact_peers
+        .chunks_exact(long_compact)
+        .filter_map(|compact_peer| {
+            let port_index = long_compact - 2;
+            let port = u16::from_be_bytes([compact_peer[port_index], compact_peer[port_index + 1]]);
+            let ip = match long_compact {
+                LONG_COMPACT_PEER => {
+                    let octets: [u8; 4] = compact_peer[..port_index].try_into().ok()?;
+                    IpAddr::V4(Ipv4Addr::from(octets))
+                }
+                _ => {
+                    let octets: [u8; 16] = compact_peer[..port_index].try_into().ok()?;
+                    IpAddr::V6(Ipv6Addr::from(octets))
+                }
+            };
+            Some(SocketAddr::new(ip, port))
+        })
+        .collect()
+}
+
+fn get_compact_peers(
+    dic: &DicValues,
+    key: &str,
+    long_compact: usize,
+) -> Result<Vec<SocketAddr>, ExtensionMessageError> {
+    match dic.get(key.as_bytes()) {
+        Some(ValuesBencoding::String(compact_peers)) => {
+            Ok(decode_compact_peers(compact_peers, long_compact))
+        }
+        Some(_) => Err(ExtensionMessageError::InvalidField(key.to_string())),
+        None => Ok(vec![]),
+    }
+}
+
+impl PexMessage {
+    ///
+    /// Funcion que codifica el mensaje para ser enviado como payload de un mensaje Extended.
+    /// Los flags de los peers agregados se envian en cero ya que no se conocen sus capacidades.
+    ///
+    pub fn to_payload(&self) -> Vec<u8> {
+        let (added, added6) = encode_compact_peers(&self.added);
+        let (dropped, dropped6) = encode_compact_peers(&self.dropped);
+        let added_flags = vec![0; added.len() / LONG_COMPACT_PEER];
+        let added6_flags = vec![0; added6.len() / LONG_COMPACT_PEER6];
+
+        let dic = [
+            (ADDED, added),
+            (ADDED_FLAGS, added_flags),
+            (ADDED6, added6),
+            (ADDED6_FLAGS, added6_flags),
+            (DROPPED, dropped),
+            (DROPPED6, dropped6),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.as_bytes().to_vec(), ValuesBencoding::String(value)))
+        .collect();
+        bencoding::encoder::from_dic(dic)
+    }
+
+    ///
+    /// Funcion que interpreta el payload de un mensaje de ut_pex recibido. Las claves que
+    /// faltan se consideran listas vacias.
+    ///
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ExtensionMessageError> {
+        let dic = bencoding::decoder::from_torrent_to_dic(payload.to_vec())
+            .map_err(|error| ExtensionMessageError::Bencoding(format!("{}", error)))?;
+
+        let mut added = get_compact_peers(&dic, ADDED, LONG_COMPACT_PEER)?;
+        added.append(&mut get_compact_peers(&dic, ADDED6, LONG_COMPACT_PEER6)?);
+        let mut dropped = get_compact_peers(&dic, DROPPED, LONG_COMPACT_PEER)?;
+        dropped.append(&mut get_compact_peers(&dic, DROPPED6, LONG_COMPACT_PEER6)?);
+        Ok(PexMessage { added, dropped })
+    }
+}
+
+impl MetadataMessage {
+    ///
+    /// Funcion que codifica el mensaje para ser enviado como payload de un mensaje Extended.
+    /// En los mensajes Data, los bytes de la pieza se agregan luego del diccionario.
+    ///
+    pub fn to_payload(&self) -> Vec<u8> {
+        let (msg_type, piece) = match self {
+            MetadataMessage::Request { piece } => (MSG_TYPE_REQUEST, piece),
+            MetadataMessage::Data { piece, .. } => (MSG_TYPE_DATA, piece),
+            MetadataMessage::Reject { piece } => (MSG_TYPE_REJECT, piece),
+        };
+        let mut dic = HashMap::new();
+        dic.insert(
+            MSG_TYPE.as_bytes().to_vec(),
+            ValuesBencoding::Integer(msg_type),
+        );
+        dic.insert(
+            PIECE.as_bytes().to_vec(),
+            ValuesBencoding::Integer(i64::from(*piece)),
+        );
+        if let MetadataMessage::Data { total_size, .. } = self {
+            dic.insert(
+                TOTAL_SIZE.as_bytes().to_vec(),
+                ValuesBencoding::Integer(*total_size as i64),
+            );
+        }
+
+        let mut payload = bencoding::encoder::from_dic(dic);
+        if let MetadataMessage::Data { data, .. } = self {
+            payload.extend_from_slice(data);
+        }
+        payload
+    }
+
+    ///
+    /// Funcion que interpreta el payload de un mensaje de ut_metadata recibido.
+    ///
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ExtensionMessageError> {
+        let (dic, data) = bencoding::decoder::to_dic(payload.to_vec())
+            .map_err(|error| ExtensionMessageError::Bencoding(format!("{}", error)))?;
+        let msg_type = get_integer(&dic, MSG_TYPE)?
+            .ok_or_else(|| ExtensionMessageError::MissingField(MSG_TYPE.to_string()))?;
+        let piece = get_unsigned(&dic, PIECE)?;
+
+        match msg_type {
+            MSG_TYPE_REQUEST => Ok(MetadataMessage::Request { piece }),
+            MSG_TYPE_DATA => Ok(MetadataMessage::Data {
+                piece,
+                total_size: get_unsigned(&dic, TOTAL_SIZE)?,
+                data,
+            }),
+            MSG_TYPE_REJECT => Ok(MetadataMessage::Reject { piece }),
+            _ => Err(ExtensionMessageError::UnknownMetadataMsgType(msg_type)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_extension {
+    use super::*;
+
+    mod tests_extended_handshake {
+        use super::*;
+
+        #[test]
+        fn encode_and_decode_extended_handshake_ok() -> Result<(), ExtensionMessageError> {
+            let handshake = ExtendedHandshake {
+                extensions: HashMap::from([(UT_METADATA.to_string(), 3)]),
+                metadata_size: Some(31235),
+                client: Some("FerrisTorrent".to_string()),
+            };
+
+            let payload = handshake.to_payload();
+
+            assert_eq!(
+                b"d1:md11:ut_metadatai3ee13:metadata_sizei31235e1:v13:FerrisTorrente".to_vec(),
+                payload
+            );
+            assert_eq!(handshake, ExtendedHandshake::from_payload(&payload)?);
+            Ok(())
+        }
+
+        #[test]
+        fn disabled_extensions_are_not_supported_ok() -> Result<(), ExtensionMessageError> {
+            let handshake =
+                ExtendedHandshake::from_payload(b"d1:md11:ut_metadatai0e6:ut_pexi1000eee")?;
+
+            assert_eq!(None, handshake.get_extension_id(UT_METADATA));
+            assert_eq!(None, handshake.get_extension_id("ut_pex"));
+            assert_eq!(None, handshake.metadata_size);
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_extended_handshake_error() {
+            assert!(ExtendedHandshake::from_payload(b"d1:mi1ee").is_err());
+            assert!(ExtendedHandshake::from_payload(b"not bencoding").is_err());
+        }
+    }
+
+    mod tests_metadata_message {
+        use super::*;
+
+        #[test]
+        fn encode_and_decode_request_ok() -> Result<(), ExtensionMessageError> {
+            let request = MetadataMessage::Request { piece: 2 };
+
+            let payload = request.to_payload();
+
+            assert_eq!(b"d8:msg_typei0e5:piecei2ee".to_vec(), payload);
+            assert_eq!(request, MetadataMessage::from_payload(&payload)?);
+            Ok(())
+        }
+
+        #[test]
+        fn encode_and_decode_data_with_trailing_bytes_ok() -> Result<(), ExtensionMessageError> {
+            let data = MetadataMessage::Data {
+                piece: 0,
+                total_size: 4,
+                data: b"d1:e".to_vec(),
+            };
+
+            let payload = data.to_payload();
+
+            assert_eq!(
+                b"d8:msg_typei1e5:piecei0e10:total_sizei4eed1:e".to_vec(),
+                payload
+            );
+            assert_eq!(data, MetadataMessage::from_payload(&payload)?);
+            Ok(())
+        }
+
+        #[test]
+        fn unknown_msg_type_error() {
+            assert_eq!(
+                Err(ExtensionMessageError::UnknownMetadataMsgType(7)),
+                MetadataMessage::from_payload(b"d8:msg_typei7e5:piecei0ee")
+            );
+            assert_eq!(
+                Err(ExtensionMessageError::MissingField(PIECE.to_string())),
+                MetadataMessage::from_payload(b"d8:msg_typei2ee")
+            );
+        }
+    }
+
+    mod tests_pex_message {
+        use super::*;
+        use std::str::FromStr;
+
+        #[test]
+        fn encode_and_decode_pex_message_ok() -> Result<(), Box<dyn Error>> {
+            let pex = PexMessage {
+                added: vec![
+                    SocketAddr::from_str("127.0.0.1:6881")?,
+                    SocketAddr::from_str("[::1]:6882")?,
+                ],
+                dropped: vec![SocketAddr::from_str("10.0.0.2:80")?],
+            };
+
+            let payload = pex.to_payload();
+
+            let mut expected_payload = b"d5:added6:\x7f\x00\x00\x01\x1a\xe1".to_vec();
+            expected_payload.extend_from_slice(b"7:added.f1:\x00");
+            expected_payload.extend_from_slice(b"6:added618:");
+            expected_payload.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+            expected_payload.extend_from_slice(b"\x1a\xe2");
+            expected_payload.extend_from_slice(b"8:added6.f1:\x00");
+            expected_payload.extend_from_slice(b"7:dropped6:\x0a\x00\x00\x02\x00\x50");
+            expected_payload.extend_from_slice(b"8:dropped60:e");
+            assert_eq!(expected_payload, payload);
+            assert_eq!(pex, PexMessage::from_payload(&payload)?);
+            Ok(())
+        }
+
+        #[test]
+        fn missing_keys_and_incomplete_peers_are_ignored_ok() -> Result<(), Box<dyn Error>> {
+            let pex = PexMessage::from_payload(b"d5:added8:\x7f\x00\x00\x01\x1a\xe1\x01\x02e")?;
+
+            assert_eq!(vec![SocketAddr::from_str("127.0.0.1:6881")?], pex.added);
+            assert!(pex.dropped.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_pex_message_error() {
+            assert_eq!(
+                Err(ExtensionMessageError::InvalidField(ADDED.to_string())),
+                PexMessage::from_payload(b"d5:addedi1ee")
+            );
+        }
+    }
+}
@@ -5,8 +5,10 @@ use std::{error::Error, fmt};
 /// (Teniendo un vector de PieceStatuses se puede representar el Bitfield de forma comoda)
 pub enum PieceStatus {
     ValidAndAvailablePiece,
+    /// Cada posicion de downloaded_blocks indica si el bloque correspondiente
+    /// de la pieza ya fue descargado (los bloques pueden llegar en cualquier orden)
     PartiallyDownloaded {
-        downloaded_bytes: u32,
+        downloaded_blocks: Vec<bool>,
         was_requested: bool,
     },
     MissingPiece {
@@ -47,8 +49,30 @@ pub enum P2PMessage {
     Port {
         listen_port: u32,
     },
+    /// Mensajes de la fast extension (BEP 6).
+    SuggestPiece {
+        piece_index: u32,
+    },
+    HaveAll,
+    HaveNone,
+    RejectRequest {
+        piece_index: u32,
+        beginning_byte_index: u32,
+        amount_of_bytes: u32,
+    },
+    AllowedFast {
+        piece_index: u32,
+    },
+    /// Mensaje del extension protocol (BEP 10). El ext_id 0 corresponde al handshake extendido,
+    /// y el resto a la extension que el receptor haya asociado a dicho id en su handshake.
+    Extended {
+        ext_id: u8,
+        payload: Vec<u8>,
+    },
+    /// Los bytes reservados indican que extensiones del protocolo soporta el peer.
     Handshake {
         protocol_str: String,
+        reserved: [u8; 8],
         info_hash: Vec<u8>, // Valor del SHA1
         peer_id: Vec<u8>,
     },
@@ -6,4 +6,5 @@
 pub mod constants;
 pub mod decoder;
 pub mod encoder;
+pub mod extension;
 pub mod message;
@@ -1,10 +1,18 @@
 //! Modulo para bind de puertos para listeners, de tal forma que se evita error de bind por puerto ya usado por algun otro proceso.
 //!
 
-use std::{error::Error, fmt, io::ErrorKind, net::TcpListener};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    error::Error,
+    fmt, io,
+    io::ErrorKind,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener},
+};
 
+/// Direccion a la cual conectarse para llegar a un listener bindeado en esta misma maquina
 pub const LOCALHOST: &str = "127.0.0.1";
 pub const MAX_PORT_RANGE_SIZE: u16 = 1000;
+const LISTEN_BACKLOG: i32 = 128;
 
 pub const STARTING_PORT_FOR_TESTS: u16 = 8080; // Puertos genericos para uso en todos los tests con el try_bind
 pub const MAX_PORT_FOR_TESTS: u16 = 9079;
@@ -34,10 +42,61 @@ fn update_port(current_port: u16, max_port: u16) -> Result<u16, PortBindingError
     }
 }
 
+// Bindea un listener en la direccion IPv6 no especificada desactivando IPV6_V6ONLY, para que
+// acepte tambien conexiones IPv4 (como direcciones IPv4-mapped) sin depender del valor por
+// defecto del sistema operativo.
+fn bind_ipv6_accepting_ipv4(port: u16) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_only_v6(false)?;
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port).into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+    Ok(socket.into())
+}
+
+/// Bindea un listener en todas las interfaces del puerto dado. Se intenta primero con la direccion
+/// IPv6 no especificada en modo dual-stack, que acepta tambien conexiones IPv4; si el sistema no
+/// soporta IPv6 (o no permite desactivar IPV6_V6ONLY), se bindea solo sobre IPv4.
+pub fn bind_dual_stack(port: u16) -> io::Result<TcpListener> {
+    match bind_ipv6_accepting_ipv4(port) {
+        Err(bind_err) if bind_err.kind() != ErrorKind::AddrInUse => {
+            TcpListener::bind(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port))
+        }
+        result => result,
+    }
+}
+
+fn bind_localhost(port: u16) -> io::Result<TcpListener> {
+    TcpListener::bind(format!("{}:{}", LOCALHOST, port))
+}
+
+/// Devuelve la direccion dada en su forma canonica: las direcciones IPv4-mapped que se obtienen
+/// de un listener dual-stack se convierten a IPv4.
+pub fn canonical_address(address: SocketAddr) -> SocketAddr {
+    SocketAddr::new(address.ip().to_canonical(), address.port())
+}
+
 /// Busca bindear un listener a un puerto de localhost mientras que el error sea por causa de una direccion que ya está en uso.
 pub fn try_bind_listener(
     first_port: u16,
     max_port: u16,
+) -> Result<(TcpListener, String), Box<dyn Error>> {
+    try_bind_listener_with(first_port, max_port, bind_localhost)
+}
+
+/// Busca bindear un listener dual-stack (ver [bind_dual_stack]) a un puerto mientras que el error sea por causa de una direccion que ya está en uso.
+pub fn try_bind_dual_stack_listener(
+    first_port: u16,
+    max_port: u16,
+) -> Result<(TcpListener, String), Box<dyn Error>> {
+    try_bind_listener_with(first_port, max_port, bind_dual_stack)
+}
+
+fn try_bind_listener_with(
+    first_port: u16,
+    max_port: u16,
+    bind: fn(u16) -> io::Result<TcpListener>,
 ) -> Result<(TcpListener, String), Box<dyn Error>> {
     if first_port >= max_port {
         return Err(Box::new(
@@ -47,7 +106,7 @@ pub fn try_bind_listener(
         return Err(Box::new(PortBindingError::GivenPortRangeIsWayTooLarge));
     }
 
-    let mut listener = TcpListener::bind(format!("{}:{}", LOCALHOST, first_port));
+    let mut listener = bind(first_port);
 
     let mut current_port = first_port;
 
@@ -56,7 +115,7 @@ pub fn try_bind_listener(
             return Err(Box::new(bind_err));
         } else {
             current_port = update_port(current_port, max_port)?;
-            listener = TcpListener::bind(format!("{}:{}", LOCALHOST, current_port));
+            listener = bind(current_port);
         }
     }
     let resulting_listener = listener?; // SI BIEN TIENE ?; ACÁ NUNCA VA A SER UN ERROR
@@ -66,3 +125,41 @@ pub fn try_bind_listener(
         format!("{}:{}", LOCALHOST, current_port),
     ))
 }
+
+#[cfg(test)]
+mod test_listener_binder {
+    use super::*;
+    use std::{net::TcpStream, str::FromStr};
+
+    #[test]
+    fn dual_stack_listener_accepts_ipv4_connections_ok() -> Result<(), Box<dyn Error>> {
+        let listener = bind_dual_stack(0)?;
+        let _stream = TcpStream::connect((LOCALHOST, listener.local_addr()?.port()))?;
+
+        let (_, external_address) = listener.accept()?;
+
+        assert!(canonical_address(external_address).is_ipv4());
+        Ok(())
+    }
+
+    #[test]
+    fn listener_for_tests_is_bound_to_localhost_ok() -> Result<(), Box<dyn Error>> {
+        let (listener, _) = try_bind_listener(STARTING_PORT_FOR_TESTS, MAX_PORT_FOR_TESTS)?;
+
+        assert!(listener.local_addr()?.ip().is_loopback());
+        Ok(())
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_are_converted_to_ipv4_ok() -> Result<(), Box<dyn Error>> {
+        let mapped_address = SocketAddr::from_str("[::ffff:10.0.0.1]:6881")?;
+        let ipv6_address = SocketAddr::from_str("[2001:db8::1]:6881")?;
+
+        assert_eq!(
+            SocketAddr::from_str("10.0.0.1:6881")?,
+            canonical_address(mapped_address)
+        );
+        assert_eq!(ipv6_address, canonical_address(ipv6_address));
+        Ok(())
+    }
+}
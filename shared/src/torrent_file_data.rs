@@ -72,7 +72,7 @@ pub enum TargetFilesData {
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct TorrentFileData {
     pub url_tracker_main: String,
-    pub url_tracker_list: Vec<String>,
+    pub url_tracker_list: Vec<Vec<String>>,
     pub sha1_pieces: Vec<u8>,
     pub sha1_info_hash: Vec<u8>,
     pub piece_length: u64,
@@ -112,7 +112,7 @@ fn init_tracker_main(dic_torrent: &DicValues) -> Result<String, TorrentFileDataE
     }
 }
 
-fn init_tracker_list(dic_torrent: &DicValues) -> Result<Vec<String>, TorrentFileDataError> {
+fn init_tracker_list(dic_torrent: &DicValues) -> Result<Vec<Vec<String>>, TorrentFileDataError> {
     let mut list_tracker_url = vec![];
     match dic_torrent.get(&ANNOUNCE_LIST.as_bytes().to_vec()) {
         Some(ValuesBencoding::List(list_of_lists)) => {
@@ -121,16 +121,18 @@ fn init_tracker_list(dic_torrent: &DicValues) -> Result<Vec<String>, TorrentFile
                     ValuesBencoding::List(list_values) => {
                         let mut list_shuffle = list_values.clone();
                         list_shuffle.shuffle(&mut thread_rng());
+                        let mut tier = vec![];
                         for value in list_shuffle {
                             match value {
-                                ValuesBencoding::String(url) => {
-                                    list_tracker_url.push(vec_u8_to_string(&url))
-                                }
+                                ValuesBencoding::String(url) => tier.push(vec_u8_to_string(&url)),
                                 _ => {
                                     return Err(TorrentFileDataError::Format(Section::TrackerList))
                                 }
                             }
                         }
+                        if !tier.is_empty() {
+                            list_tracker_url.push(tier);
+                        }
                     }
                     _ => return Err(TorrentFileDataError::Format(Section::TrackerList)),
                 }
@@ -344,6 +346,17 @@ impl TorrentFileData {
         self.url_tracker_main.clone()
     }
 
+    ///Funcion que devuelve los trackers del Torrent agrupados por niveles (tiers), en el orden
+    /// en que deben intentarse. Si el .torrent no tiene announce-list, el unico nivel es el
+    /// tracker principal.
+    ///
+    pub fn get_tracker_tiers(&self) -> Vec<Vec<String>> {
+        if self.url_tracker_list.is_empty() {
+            return vec![vec![self.get_tracker_main()]];
+        }
+        self.url_tracker_list.clone()
+    }
+
     ///Funcion que va a devolver el info_hash, que es el campo info del .torrent bencodeado y encriptado mediante
     /// SHA-1
     ///
@@ -554,4 +567,21 @@ mod tests_torrent_file_data {
         assert_eq!(last_piece, torrent.sha1_pieces[pos_last_piece..]);
         Ok(())
     }
+
+    #[test]
+    fn test_tracker_tiers_ok() -> Result<(), Box<dyn Error>> {
+        let dir = "../ferris_torrent/torrents_for_test/ubuntu-14.04.6-server-ppc64el.iso.torrent";
+        let torrent = TorrentFileData::new(medatada_analyzer::read_torrent_file_to_dic(dir)?)?;
+        assert_eq!(
+            torrent.get_tracker_tiers(),
+            vec![vec![torrent.get_tracker_main()]]
+        );
+
+        let dir = "../ferris_torrent/torrents_for_test/big-buck-bunny.torrent";
+        let torrent = TorrentFileData::new(medatada_analyzer::read_torrent_file_to_dic(dir)?)?;
+        let tracker_tiers = torrent.get_tracker_tiers();
+        assert!(tracker_tiers.len() > 1);
+        assert!(tracker_tiers.iter().all(|tier| !tier.is_empty()));
+        Ok(())
+    }
 }